@@ -0,0 +1,66 @@
+//! Key generation, signing and verification on each crypto backend in this build
+//!
+//! `cargo bench --bench crypto` compares the software backend with gcrypt;
+//! add `--features gcrypt` to include the latter. The `cached` group measures
+//! [`CachedSigner`] against signing from a hex key on every call.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use etherlink::{CachedSigner, CryptoAlgorithm, CryptoBackend, CryptoProvider};
+use std::hint::black_box;
+
+const MESSAGE: &[u8] = b"transfer 1000 GCC from ghost1sender to ghost1recipient, nonce 42";
+
+fn backends() -> Vec<(CryptoBackend, CryptoProvider)> {
+    [CryptoBackend::Software, CryptoBackend::Gcrypt]
+        .into_iter()
+        .filter_map(|backend| CryptoProvider::with_backend(backend).ok().map(|provider| (backend, provider)))
+        .collect()
+}
+
+fn bench_algorithm(c: &mut Criterion, algorithm: CryptoAlgorithm) {
+    let mut group = c.benchmark_group(format!("{:?}", algorithm));
+    for (backend, provider) in backends() {
+        let key = provider.generate_keypair(&algorithm).unwrap();
+        let signature = provider.sign_message(MESSAGE, key.private_key.expose_secret(), &algorithm).unwrap();
+
+        group.bench_function(BenchmarkId::new("keygen", format!("{:?}", backend)), |b| {
+            b.iter(|| provider.generate_keypair(black_box(&algorithm)).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("sign", format!("{:?}", backend)), |b| {
+            b.iter(|| provider.sign_message(black_box(MESSAGE), key.private_key.expose_secret(), &algorithm).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("verify", format!("{:?}", backend)), |b| {
+            b.iter(|| provider.verify_signature(black_box(MESSAGE), &signature, &key.public_key, &algorithm).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_cached(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cached");
+    group.throughput(Throughput::Elements(1));
+    let Ok(provider) = CryptoProvider::with_backend(CryptoBackend::Software) else {
+        return;
+    };
+    for algorithm in [CryptoAlgorithm::Ed25519, CryptoAlgorithm::Secp256k1] {
+        let key = provider.generate_keypair(&algorithm).unwrap();
+        let cached = CachedSigner::new(&key).unwrap();
+
+        group.bench_function(BenchmarkId::new("hex_key", format!("{:?}", algorithm)), |b| {
+            b.iter(|| provider.sign_message(black_box(MESSAGE), key.private_key.expose_secret(), &algorithm).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("cached_signer", format!("{:?}", algorithm)), |b| {
+            b.iter(|| cached.sign_hex(black_box(MESSAGE)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn crypto(c: &mut Criterion) {
+    bench_algorithm(c, CryptoAlgorithm::Ed25519);
+    bench_algorithm(c, CryptoAlgorithm::Secp256k1);
+    bench_cached(c);
+}
+
+criterion_group!(benches, crypto);
+criterion_main!(benches);
@@ -14,15 +14,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "proto/cns.proto",
                 "proto/ghostchain.proto",
                 "proto/ghostplane.proto",
+                "proto/walletd.proto",
+                "proto/gid.proto",
+                "proto/gsig.proto",
+                "proto/gledger.proto",
             ],
             &["proto"],
         )?;
 
+    if env::var_os("CARGO_FEATURE_GHOSTPLANE_FFI").is_some() {
+        if let Some(dir) = env::var_os("GHOSTPLANE_LIB_DIR") {
+            println!("cargo:rustc-link-search=native={}", PathBuf::from(dir).display());
+        }
+        println!("cargo:rustc-link-lib=ghostplane");
+    }
+    println!("cargo:rerun-if-env-changed=GHOSTPLANE_LIB_DIR");
+
     // Tell cargo to recompile if any proto files change
     println!("cargo:rerun-if-changed=proto/");
     println!("cargo:rerun-if-changed=proto/cns.proto");
     println!("cargo:rerun-if-changed=proto/ghostchain.proto");
     println!("cargo:rerun-if-changed=proto/ghostplane.proto");
+    println!("cargo:rerun-if-changed=proto/walletd.proto");
+    println!("cargo:rerun-if-changed=proto/gid.proto");
+    println!("cargo:rerun-if-changed=proto/gsig.proto");
+    println!("cargo:rerun-if-changed=proto/gledger.proto");
 
     Ok(())
 }
\ No newline at end of file
@@ -30,6 +30,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         timeout_ms: 10000,
         max_connections: 50,
         keepalive_interval_ms: 30000,
+        ..Default::default()
     };
 
     let transport = HttpTransport::new(transport_config)?;
@@ -64,6 +65,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         nonce: 1,
         data: None,
         signature: None,
+        delegation: None,
+        token_type: TokenType::GCC,
     };
 
     println!("\nCreated sample transaction:");
@@ -74,7 +77,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Example: Authentication credentials
     let auth_credentials = AuthCredentials {
         identity: "did:ghost:example123456789abcdef".to_string(),
-        secret: AuthSecret::PrivateKey("sample_private_key".to_string()),
+        secret: AuthSecret::PrivateKey("sample_private_key".into()),
         permissions: vec![
             Permission::ReadBlockchain,
             Permission::TransferTokens(TokenType::GCC),
@@ -0,0 +1,95 @@
+//! End-to-end domain-gated payment against an in-process GhostChain
+//!
+//! Authenticates with Guardian, resolves a `.ghost` domain to its payee, signs and
+//! submits a transfer, waits for the receipt and checks both balances. Every step is
+//! asserted, so `cargo run --example e2e_payment --features testing` doubles as an
+//! integration test of the whole client stack.
+
+use etherlink::{
+    AuthCredentials, AuthProvider, AuthSecret, CallContext, CryptoAlgorithm, CryptoProvider,
+    GuardianAuthProvider, Permission, ServiceClients, TokenType, clients::ghostd::Transaction,
+    testing::{DevAccounts, MockGhostChain, TRANSFER_GAS},
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+const PAYEE_DOMAIN: &str = "coffee.ghost";
+const AMOUNT: u64 = 250_000;
+const GAS_PRICE: u64 = 2;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    etherlink::init_with_tracing("info")?;
+
+    // A local chain producing a block every 200ms, with funded dev accounts and a payee domain
+    let chain = MockGhostChain::start(Duration::from_millis(200)).await?;
+    let crypto = CryptoProvider::new();
+    let accounts = DevAccounts::new(&CryptoAlgorithm::Ed25519)?;
+    chain.fund_dev_accounts(&accounts);
+    let payer = accounts.by_name("alice").expect("alice is a dev account").keypair.clone();
+    let payee = accounts.by_name("bob").expect("bob is a dev account").keypair.clone();
+    let (payer_address, payee_address) = (payer.address()?, payee.address()?);
+    chain.register_domain(PAYEE_DOMAIN, &payee_address);
+    println!("Mock GhostChain running at {}", chain.endpoint());
+
+    let config = chain.config();
+    let services = ServiceClients::new(&config, Arc::new(reqwest::Client::new()));
+
+    // 1. Authenticate with Guardian
+    let guardian = GuardianAuthProvider::new(Arc::new(services.gid.clone()));
+    let token = guardian
+        .authenticate(&AuthCredentials {
+            identity: format!("did:ghost:{}", payer_address),
+            secret: AuthSecret::PrivateKey(payer.private_key.clone()),
+            permissions: vec![Permission::ReadBlockchain, Permission::SubmitTransaction],
+        })
+        .await?;
+    assert!(!token.is_expired());
+    println!("✅ Guardian token {} for {}", token.token_id, token.identity);
+    let ghostd = services.ghostd.with_context(CallContext::new().auth_token(token.token_id.clone()));
+
+    // 2. Resolve the payee's domain
+    let resolution = services.cns.resolve_domain(PAYEE_DOMAIN).await?;
+    let recipient = resolution.owner;
+    assert_eq!(recipient, payee_address);
+    println!("✅ {} resolves to {}", PAYEE_DOMAIN, recipient);
+
+    let payer_before = ghostd.get_balance(&payer_address).await?;
+    let payee_before = ghostd.get_balance(&recipient).await?;
+
+    // 3. Build, sign and submit the transfer
+    let mut tx = Transaction {
+        from: payer_address.clone(),
+        to: recipient.clone(),
+        amount: AMOUNT,
+        gas_limit: TRANSFER_GAS,
+        gas_price: GAS_PRICE,
+        nonce: 0,
+        data: None,
+        signature: None,
+        delegation: None,
+        token_type: TokenType::GCC,
+    };
+    tx.sign(&payer)?;
+    let verified = crypto.verify_signature(&tx.signing_payload(), tx.signature.as_deref().unwrap_or_default(), &payer.public_key, &payer.algorithm)?;
+    assert!(verified, "signature must verify against the payer's key");
+    let tx_hash = ghostd.submit_transaction(tx).await?;
+    println!("✅ Submitted transfer {}", tx_hash.as_str());
+
+    // 4. Await the receipt
+    let receipt = ghostd.wait_for_receipt(&tx_hash, Duration::from_millis(50), Duration::from_secs(5)).await?;
+    assert!(receipt.is_success(), "transfer failed: {:?}", receipt.error);
+    println!("✅ Included in block {:?}, gas used {}", receipt.block_height, receipt.gas_used);
+
+    // 5. Verify the balance change
+    let fee = receipt.gas_used * GAS_PRICE;
+    let payer_after = ghostd.get_balance(&payer_address).await?;
+    let payee_after = ghostd.get_balance(&recipient).await?;
+    assert_eq!(payer_after, payer_before - AMOUNT - fee);
+    assert_eq!(payee_after, payee_before + AMOUNT);
+    println!("✅ Payer {} -> {} (fee {}), payee {} -> {}", payer_before, payer_after, fee, payee_before, payee_after);
+
+    chain.stop().await;
+    println!("\nEnd-to-end payment flow completed");
+    Ok(())
+}
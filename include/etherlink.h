@@ -0,0 +1,85 @@
+#ifndef ETHERLINK_H
+#define ETHERLINK_H
+
+/* Generated by cbindgen from src/capi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result codes returned by every fallible C API function
+typedef enum EtherlinkStatus {
+  ETHERLINK_STATUS_OK = 0,
+  // A pointer was null or a string was not valid UTF-8 / JSON
+  ETHERLINK_STATUS_INVALID_ARGUMENT = 1,
+  // The service could not be reached
+  ETHERLINK_STATUS_NETWORK = 2,
+  // The service rejected the request
+  ETHERLINK_STATUS_API = 3,
+  ETHERLINK_STATUS_TIMEOUT = 4,
+  // Any other failure, including a panic inside the library
+  ETHERLINK_STATUS_INTERNAL = 5,
+} EtherlinkStatus;
+
+// Opaque client handle
+typedef struct EtherlinkHandle EtherlinkHandle;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create a client handle
+//
+// `config_json` is an `EtherlinkConfig` as JSON, or null for the defaults.
+// Returns null on failure; see [`etherlink_last_error`].
+//
+// # Safety
+// `config_json` must be null or a valid NUL-terminated string.
+struct EtherlinkHandle *etherlink_new(const char *config_json);
+
+// Release a client handle; null is ignored
+//
+// # Safety
+// `handle` must come from [`etherlink_new`] and not be used afterwards.
+void etherlink_free(struct EtherlinkHandle *handle);
+
+// Resolve a CNS domain, writing the resolution as JSON to `out_json`
+//
+// # Safety
+// `handle` must be live, `domain` a valid NUL-terminated string and `out_json` writable.
+enum EtherlinkStatus etherlink_resolve_domain(const struct EtherlinkHandle *handle,
+                                              const char *domain,
+                                              char **out_json);
+
+// Submit a signed transaction given as JSON, writing its hash to `out_hash`
+//
+// # Safety
+// `handle` must be live, `tx_json` a valid NUL-terminated string and `out_hash` writable.
+enum EtherlinkStatus etherlink_submit_tx(const struct EtherlinkHandle *handle,
+                                         const char *tx_json,
+                                         char **out_hash);
+
+// Get the GCC balance of an address
+//
+// # Safety
+// `handle` must be live, `address` a valid NUL-terminated string and `out_balance` writable.
+enum EtherlinkStatus etherlink_get_balance(const struct EtherlinkHandle *handle,
+                                           const char *address,
+                                           uint64_t *out_balance);
+
+// Release a string returned by this library; null is ignored
+//
+// # Safety
+// `value` must come from an `out_*` parameter of this library and not be used afterwards.
+void etherlink_string_free(char *value);
+
+// Message of the last error on this thread, or null if the last call succeeded
+const char *etherlink_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ETHERLINK_H */
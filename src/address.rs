@@ -0,0 +1,155 @@
+//! Address derivation
+//!
+//! Secp256k1 keys map to EVM addresses the way Ethereum does it: the last 20 bytes of
+//! the Keccak-256 hash of the uncompressed public key, written with the EIP-55
+//! mixed-case checksum. Contract addresses follow `CREATE` (RLP of deployer and nonce)
+//! and `CREATE2` (EIP-1014), so they match what Solidity and EVM tooling expect.
+//! Ed25519 and BLS keys get a native address: the bech32m encoding of a 20-byte
+//! payload under the `ghost` human-readable part (`ghost1…`).
+//! Addresses written before bech32m, `ghost1` followed by the payload as 40 hex
+//! digits, are still read; [`upgrade_native`] rewrites them in the current form.
+
+use crate::bech32::{self, Variant};
+use crate::hash::{HashAlgorithm, Hasher};
+use crate::rlp::Item;
+use crate::{Address, Result};
+
+/// Human-readable part of native addresses
+pub const NATIVE_HRP: &str = "ghost";
+
+/// Prefix of the legacy hex form of native addresses
+const LEGACY_NATIVE_PREFIX: &str = "ghost1";
+
+/// Why a string is not a valid address
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AddressError {
+    /// The bech32 or EIP-55 checksum does not match
+    #[error("checksum mismatch")]
+    Checksum,
+    /// The bech32 human-readable part is not the expected one
+    #[error("expected human-readable part {expected:?}, found {found:?}")]
+    Hrp { expected: String, found: String },
+    /// The string or decoded payload has the wrong length
+    #[error("expected length {expected}, found {found}")]
+    Length { expected: usize, found: usize },
+    /// A character outside the encoding's alphabet
+    #[error("invalid character {0:?}")]
+    Character(char),
+    /// Anything else malformed, such as a missing prefix or separator
+    #[error("{0}")]
+    Format(String),
+}
+
+/// `0x`-prefixed, EIP-55 checksummed address for 20 raw bytes
+pub fn evm_address(bytes: &[u8; 20]) -> Address {
+    Address::new(to_checksum(bytes))
+}
+
+/// EIP-55 rendering: a hex letter is upper-cased when the matching nibble of the
+/// Keccak-256 hash of the lowercase hex is 8 or more
+pub fn to_checksum(bytes: &[u8; 20]) -> String {
+    let lower = hex::encode(bytes);
+    let hash = Hasher::digest(HashAlgorithm::Keccak256, lower.as_bytes());
+    let mut out = String::with_capacity(42);
+    out.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+        out.push(if nibble >= 8 { c.to_ascii_uppercase() } else { c });
+    }
+    out
+}
+
+/// Raw bytes of a `0x` address
+///
+/// All-lowercase and all-uppercase hex is accepted as is; mixed case must carry a valid
+/// EIP-55 checksum.
+pub fn evm_bytes(address: &Address) -> Result<[u8; 20]> {
+    let value = address.as_str();
+    let digits = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X"))
+        .ok_or_else(|| AddressError::Format(format!("{} is not a 0x address", value)))?;
+    if digits.len() != 40 {
+        return Err(AddressError::Length { expected: 40, found: digits.len() }.into());
+    }
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(AddressError::Character(c).into());
+    }
+    let bytes: [u8; 20] = hex::decode(digits).expect("40 hex digits").try_into().expect("20 bytes");
+    let mixed_case = digits.chars().any(|c| c.is_ascii_lowercase()) && digits.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case && to_checksum(&bytes)[2..] != *digits {
+        return Err(AddressError::Checksum.into());
+    }
+    Ok(bytes)
+}
+
+/// EVM address of an uncompressed secp256k1 public key (64 bytes, or 65 with the `0x04` tag)
+pub fn from_uncompressed_public_key(public_key: &[u8]) -> Result<Address> {
+    let point = match public_key {
+        [0x04, rest @ ..] if rest.len() == 64 => rest,
+        _ if public_key.len() == 64 => public_key,
+        _ => return Err(AddressError::Length { expected: 65, found: public_key.len() }.into()),
+    };
+    let hash = Hasher::digest(HashAlgorithm::Keccak256, point);
+    Ok(evm_address(hash[12..].try_into().expect("20-byte slice")))
+}
+
+/// Native address of a hex-encoded Ed25519 or BLS public key
+pub fn native_address(public_key: &str) -> Address {
+    let hash = Hasher::digest(HashAlgorithm::Sha256, public_key.as_bytes());
+    from_native_bytes(hash[..20].try_into().expect("20-byte slice"))
+}
+
+/// Native bech32m address for a 20-byte payload
+pub fn from_native_bytes(bytes: &[u8; 20]) -> Address {
+    Address::new(bech32::encode(NATIVE_HRP, bytes, Variant::Bech32m).expect("20-byte payload fits in bech32"))
+}
+
+/// 20-byte payload of a native address
+///
+/// Plain bech32 checksums are accepted alongside bech32m, as is the legacy
+/// `ghost1` + 40 hex digits form.
+pub fn native_bytes(address: &Address) -> Result<[u8; 20]> {
+    if let Some(bytes) = legacy_native_bytes(address.as_str()) {
+        return Ok(bytes);
+    }
+    let (hrp, data, _) = bech32::decode(address.as_str())?;
+    if hrp != NATIVE_HRP {
+        return Err(AddressError::Hrp { expected: NATIVE_HRP.to_string(), found: hrp }.into());
+    }
+    let found = data.len();
+    Ok(data.try_into().map_err(|_| AddressError::Length { expected: 20, found })?)
+}
+
+/// Current bech32m form of a native address, converting the legacy hex form
+pub fn upgrade_native(address: &Address) -> Result<Address> {
+    Ok(from_native_bytes(&native_bytes(address)?))
+}
+
+/// Payload of a legacy `ghost1<40 hex digits>` address
+///
+/// A 20-byte bech32 address has 38 characters after the separator, so the two forms
+/// cannot be confused.
+fn legacy_native_bytes(value: &str) -> Option<[u8; 20]> {
+    let digits = value.strip_prefix(LEGACY_NATIVE_PREFIX)?;
+    if digits.len() != 40 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    hex::decode(digits).ok()?.try_into().ok()
+}
+
+/// Address of a contract deployed with `CREATE`: `keccak256(rlp([deployer, nonce]))[12..]`
+pub fn create_address(deployer: &Address, nonce: u64) -> Result<Address> {
+    let payload = Item::List(vec![Item::address(deployer)?, Item::from(nonce)]).encode();
+    let hash = Hasher::digest(HashAlgorithm::Keccak256, payload);
+    Ok(evm_address(hash[12..].try_into().expect("20-byte slice")))
+}
+
+/// Address of a contract deployed with `CREATE2`:
+/// `keccak256(0xff ++ deployer ++ salt ++ keccak256(init_code))[12..]`
+pub fn create2_address(deployer: &Address, salt: &[u8; 32], init_code: &[u8]) -> Result<Address> {
+    let deployer = evm_bytes(deployer)?;
+    let code_hash = Hasher::digest(HashAlgorithm::Keccak256, init_code);
+    let mut hasher = Hasher::new(HashAlgorithm::Keccak256);
+    hasher.update([0xff]).update(deployer).update(salt).update(code_hash);
+    let hash = hasher.finalize();
+    Ok(evm_address(hash[12..].try_into().expect("20-byte slice")))
+}
@@ -0,0 +1,141 @@
+//! Signer that parses its key once
+//!
+//! [`CryptoProvider::sign_message`] takes a hex private key, so every call decodes
+//! it and rebuilds the signing key. [`CachedSigner`] does that work up front: it
+//! holds the parsed key and its public key and signs on the shared libsecp256k1
+//! context, which is what high-volume signers such as relayers and market makers
+//! want. Signatures are identical to the provider's software backend.
+//!
+//! [`CryptoProvider::sign_message`]: super::crypto::CryptoProvider::sign_message
+
+use super::crypto::{
+    CryptoAlgorithm, KeyPair, Signature, Signer, SigningError, address_from_public_key, secp256k1_context, software_fallback,
+};
+use crate::hash::{HashAlgorithm, Hasher};
+use crate::{Address, EtherlinkError, Result};
+use std::fmt;
+use zeroize::Zeroizing;
+
+enum ParsedKey {
+    Ed25519(ed25519_dalek::SigningKey),
+    Secp256k1(secp256k1::SecretKey),
+}
+
+/// A key parsed once for repeated signing
+pub struct CachedSigner {
+    key: ParsedKey,
+    algorithm: CryptoAlgorithm,
+    public_key: Vec<u8>,
+    address: Address,
+}
+
+impl CachedSigner {
+    /// Parse `key` and precompute its public key and address
+    ///
+    /// Both are derived from the private key; `key.public_key` is not consulted.
+    pub fn new(key: &KeyPair) -> Result<Self> {
+        software_fallback("Cached signing")?;
+        let bytes = Zeroizing::new(hex::decode(key.private_key.expose_secret())
+            .map_err(|e| EtherlinkError::Crypto(format!("Invalid private key: {}", e)))?);
+        let (parsed, public_key) = match key.algorithm {
+            CryptoAlgorithm::Ed25519 => {
+                let seed: Zeroizing<[u8; 32]> = Zeroizing::new(bytes.as_slice().try_into()
+                    .map_err(|_| EtherlinkError::Crypto("Invalid key length".to_string()))?);
+                let signing_key = ed25519_dalek::SigningKey::from_bytes(&seed);
+                let public_key = signing_key.verifying_key().to_bytes().to_vec();
+                (ParsedKey::Ed25519(signing_key), public_key)
+            }
+            CryptoAlgorithm::Secp256k1 => {
+                let secret_key = secp256k1::SecretKey::from_slice(&bytes)
+                    .map_err(|e| EtherlinkError::Crypto(format!("Invalid secret key: {}", e)))?;
+                let public_key = secret_key.public_key(secp256k1_context()).serialize().to_vec();
+                (ParsedKey::Secp256k1(secret_key), public_key)
+            }
+            CryptoAlgorithm::Bls12381 => {
+                return Err(EtherlinkError::Crypto("BLS12-381 not yet implemented".to_string()));
+            }
+        };
+        Ok(Self {
+            address: address_from_public_key(&hex::encode(&public_key), &key.algorithm)?,
+            key: parsed,
+            algorithm: key.algorithm.clone(),
+            public_key,
+        })
+    }
+
+    /// Account the key signs for
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Hex public key, compressed for secp256k1
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(&self.public_key)
+    }
+
+    /// Hex signature, as [`CryptoProvider::sign_message`](super::crypto::CryptoProvider::sign_message) returns
+    pub fn sign_hex(&self, message: &[u8]) -> Result<String> {
+        Ok(hex::encode(self.sign_bytes(message)))
+    }
+
+    /// Recoverable secp256k1 signature over `digest`, as [`super::crypto::sign_recoverable`] returns
+    pub fn sign_recoverable(&self, digest: &[u8; 32]) -> Result<[u8; 65]> {
+        let ParsedKey::Secp256k1(secret_key) = &self.key else {
+            return Err(EtherlinkError::Crypto("Recoverable signatures need a secp256k1 key".to_string()));
+        };
+        let (recovery_id, compact) = secp256k1_context()
+            .sign_ecdsa_recoverable(&secp256k1::Message::from_digest(*digest), secret_key)
+            .serialize_compact();
+        let mut signature = [0u8; 65];
+        signature[..64].copy_from_slice(&compact);
+        signature[64] = recovery_id.to_i32() as u8;
+        Ok(signature)
+    }
+
+    fn sign_bytes(&self, message: &[u8]) -> Vec<u8> {
+        match &self.key {
+            ParsedKey::Ed25519(signing_key) => {
+                ed25519_dalek::Signer::sign(signing_key, message).to_bytes().to_vec()
+            }
+            ParsedKey::Secp256k1(secret_key) => {
+                let digest = Hasher::digest(HashAlgorithm::Sha256, message);
+                secp256k1_context()
+                    .sign_ecdsa(&secp256k1::Message::from_digest(digest), secret_key)
+                    .serialize_compact()
+                    .to_vec()
+            }
+        }
+    }
+}
+
+impl Signer for CachedSigner {
+    fn algorithm(&self) -> CryptoAlgorithm {
+        self.algorithm.clone()
+    }
+
+    fn public_key(&self) -> std::result::Result<Vec<u8>, SigningError> {
+        Ok(self.public_key.clone())
+    }
+
+    fn sign(&self, message: &[u8]) -> std::result::Result<Signature, SigningError> {
+        Ok(Signature::new(self.algorithm.clone(), self.sign_bytes(message)))
+    }
+}
+
+impl Drop for CachedSigner {
+    fn drop(&mut self) {
+        // ed25519-dalek zeroizes its own keys
+        if let ParsedKey::Secp256k1(secret_key) = &mut self.key {
+            secret_key.non_secure_erase();
+        }
+    }
+}
+
+impl fmt::Debug for CachedSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedSigner")
+            .field("algorithm", &self.algorithm)
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,22 +1,77 @@
 //! Cryptographic utilities for authentication
 
 use crate::{Result, EtherlinkError};
+use crate::address;
+use crate::hash::{HashAlgorithm, Hasher};
+use crate::secret::{Secret, SecretString, constant_time_eq};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use serde::{Serialize, Deserialize};
+use zeroize::Zeroizing;
+
+pub use crate::primitives::{CryptoAlgorithm, Signature, Signer, SigningError};
+
+/// Implementation Ed25519 and secp256k1 operations run on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CryptoBackend {
+    /// ed25519-dalek and libsecp256k1 (`fallback-crypto`)
+    Software,
+    /// GhostChain's gcrypt (`gcrypt`)
+    Gcrypt,
+}
+
+impl CryptoBackend {
+    /// gcrypt when it is compiled in, the software backend otherwise
+    pub fn preferred() -> Self {
+        if cfg!(feature = "gcrypt") { CryptoBackend::Gcrypt } else { CryptoBackend::Software }
+    }
+
+    /// Whether this build can use the backend
+    ///
+    /// `strict-crypto` builds rule out the software backend.
+    pub fn is_available(self) -> bool {
+        match self {
+            CryptoBackend::Software => !cfg!(feature = "strict-crypto"),
+            CryptoBackend::Gcrypt => cfg!(feature = "gcrypt"),
+        }
+    }
+}
+
+impl std::str::FromStr for CryptoBackend {
+    type Err = EtherlinkError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "software" => Ok(CryptoBackend::Software),
+            "gcrypt" => Ok(CryptoBackend::Gcrypt),
+            _ => Err(EtherlinkError::Configuration(format!("Unknown crypto backend `{}`; expected software or gcrypt", value))),
+        }
+    }
+}
 
 /// Cryptographic provider for authentication operations
 #[derive(Debug, Clone)]
 pub struct CryptoProvider {
-    #[cfg(feature = "gcrypt")]
-    _gcrypt_enabled: bool,
+    backend: CryptoBackend,
 }
 
 impl CryptoProvider {
-    /// Create a new crypto provider
+    /// Create a new crypto provider on the [preferred](CryptoBackend::preferred) backend
     pub fn new() -> Self {
-        Self {
-            #[cfg(feature = "gcrypt")]
-            _gcrypt_enabled: true,
+        Self { backend: CryptoBackend::preferred() }
+    }
+
+    /// Create a provider on `backend`, failing if this build does not include it
+    pub fn with_backend(backend: CryptoBackend) -> Result<Self> {
+        if !backend.is_available() {
+            return Err(EtherlinkError::Unsupported(format!("{:?} crypto backend is not available in this build", backend)));
         }
+        Ok(Self { backend })
+    }
+
+    pub fn backend(&self) -> CryptoBackend {
+        self.backend
     }
 
     /// Generate a new keypair
@@ -46,17 +101,95 @@ impl CryptoProvider {
         }
     }
 
+    /// Sign `message` behind a [`MessagePrefix`], as wallets do for `personal_sign`
+    ///
+    /// Secp256k1 signs the Keccak-256 of the prefixed message and returns hex
+    /// `r ++ s ++ v` (`v` 27 or 28), so any Ethereum tool can recover the signer.
+    /// Other algorithms sign the prefixed bytes as [`CryptoProvider::sign_message`] does.
+    pub fn sign_message_prefixed(&self, message: &[u8], private_key: &str, algorithm: &CryptoAlgorithm, prefix: MessagePrefix) -> Result<String> {
+        match algorithm {
+            CryptoAlgorithm::Secp256k1 => {
+                let mut signature = sign_recoverable(&prefix.digest(message), private_key)?;
+                signature[64] += 27;
+                Ok(format!("0x{}", hex::encode(signature)))
+            }
+            _ => self.sign_message(&prefix.apply(message), private_key, algorithm),
+        }
+    }
+
+    /// Check a [`CryptoProvider::sign_message_prefixed`] signature against `public_key`
+    pub fn verify_message_prefixed(&self, message: &[u8], signature: &str, public_key: &str, algorithm: &CryptoAlgorithm, prefix: MessagePrefix) -> Result<bool> {
+        match algorithm {
+            CryptoAlgorithm::Secp256k1 => {
+                let signer = address::evm_bytes(&address_from_public_key(public_key, algorithm)?)?;
+                let recovered = address::evm_bytes(&recover_message_signer(message, signature, prefix)?)?;
+                Ok(constant_time_eq(&recovered, &signer))
+            }
+            _ => self.verify_signature(&prefix.apply(message), signature, public_key, algorithm),
+        }
+    }
+
+    /// Encrypt `plaintext` under a 32-byte symmetric key
+    ///
+    /// `aad` is authenticated but not encrypted; the same bytes must be passed to
+    /// [`CryptoProvider::decrypt`].
+    pub fn encrypt(&self, algorithm: SymmetricAlgorithm, key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<EncryptedEnvelope> {
+        let (nonce, ciphertext) = aead_encrypt(algorithm, key, plaintext, aad)?;
+        Ok(EncryptedEnvelope {
+            version: ENVELOPE_VERSION,
+            algorithm,
+            recipients: Vec::new(),
+            nonce: hex::encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    /// Decrypt an envelope produced by [`CryptoProvider::encrypt`]
+    pub fn decrypt(&self, envelope: &EncryptedEnvelope, key: &[u8; 32], aad: &[u8]) -> Result<Vec<u8>> {
+        if envelope.version != ENVELOPE_VERSION {
+            return Err(EtherlinkError::Crypto(format!("Unsupported envelope version {}", envelope.version)));
+        }
+        let nonce: [u8; 12] = decode_hex_array(&envelope.nonce, "envelope nonce")?;
+        let ciphertext = BASE64.decode(&envelope.ciphertext)
+            .map_err(|e| EtherlinkError::Crypto(format!("Invalid ciphertext: {}", e)))?;
+        aead_decrypt(envelope.algorithm, key, &nonce, &ciphertext, aad)
+    }
+
+    /// Encrypt `plaintext` so that any of `recipients` (X25519 public keys) can open it
+    ///
+    /// A fresh content key encrypts the payload and is wrapped once per recipient
+    /// with a key agreed from an ephemeral X25519 key.
+    pub fn seal(&self, algorithm: SymmetricAlgorithm, recipients: &[[u8; 32]], plaintext: &[u8], aad: &[u8]) -> Result<EncryptedEnvelope> {
+        if recipients.is_empty() {
+            return Err(EtherlinkError::Crypto("Envelope needs at least one recipient".to_string()));
+        }
+        let content_key: Zeroizing<[u8; 32]> = Zeroizing::new(rand::random());
+        let mut envelope = self.encrypt(algorithm, &content_key, plaintext, aad)?;
+        for recipient in recipients {
+            envelope.recipients.push(wrap_key(algorithm, &content_key, recipient)?);
+        }
+        Ok(envelope)
+    }
+
+    /// Open an envelope from [`CryptoProvider::seal`] with a recipient's X25519 secret key
+    pub fn open(&self, envelope: &EncryptedEnvelope, recipient_secret: &[u8; 32], aad: &[u8]) -> Result<Vec<u8>> {
+        use x25519_dalek::{PublicKey, StaticSecret};
+
+        let secret = StaticSecret::from(*recipient_secret);
+        let public_key = hex::encode(PublicKey::from(&secret).as_bytes());
+        let wrapped = envelope.recipients.iter()
+            .find(|wrapped| wrapped.recipient_public_key == public_key)
+            .ok_or_else(|| EtherlinkError::Crypto("Envelope is not sealed to this key".to_string()))?;
+        let content_key = Zeroizing::new(unwrap_key(envelope.algorithm, wrapped, &secret)?);
+        self.decrypt(envelope, &content_key, aad)
+    }
+
     // Ed25519 implementations
     fn generate_ed25519_keypair(&self) -> Result<KeyPair> {
-        #[cfg(feature = "gcrypt")]
-        {
-            // Use gcrypt for Ed25519 if available
-            // TODO: Implement gcrypt Ed25519 key generation
-            self.fallback_ed25519_keypair()
-        }
-        #[cfg(not(feature = "gcrypt"))]
-        {
-            self.fallback_ed25519_keypair()
+        match self.backend {
+            #[cfg(feature = "gcrypt")]
+            CryptoBackend::Gcrypt => super::gcrypt_backend::generate_ed25519_keypair(),
+            _ => self.fallback_ed25519_keypair(),
         }
     }
 
@@ -64,50 +197,55 @@ impl CryptoProvider {
         use ed25519_dalek::{SigningKey, VerifyingKey};
         use rand::rngs::OsRng;
 
+        software_fallback("Ed25519 key generation")?;
         let mut rng = OsRng;
-        let secret_bytes: [u8; 32] = rand::random();
+        let secret_bytes: Zeroizing<[u8; 32]> = Zeroizing::new(rand::random());
         let signing_key = SigningKey::from_bytes(&secret_bytes);
         let verifying_key = signing_key.verifying_key();
 
         Ok(KeyPair {
-            private_key: hex::encode(signing_key.to_bytes()),
+            private_key: hex::encode(signing_key.to_bytes()).into(),
             public_key: hex::encode(verifying_key.to_bytes()),
             algorithm: CryptoAlgorithm::Ed25519,
         })
     }
 
     fn sign_ed25519(&self, message: &[u8], private_key: &str) -> Result<String> {
-        #[cfg(feature = "gcrypt")]
-        {
-            // Use gcrypt for Ed25519 if available
-            // TODO: Implement gcrypt Ed25519 signing
-            self.fallback_sign_ed25519(message, private_key)
-        }
-        #[cfg(not(feature = "gcrypt"))]
-        {
-            self.fallback_sign_ed25519(message, private_key)
+        match self.backend {
+            #[cfg(feature = "gcrypt")]
+            CryptoBackend::Gcrypt => super::gcrypt_backend::sign_ed25519(message, private_key),
+            _ => self.fallback_sign_ed25519(message, private_key),
         }
     }
 
     fn fallback_sign_ed25519(&self, message: &[u8], private_key: &str) -> Result<String> {
         use ed25519_dalek::{SigningKey, Signature, Signer};
 
-        let key_bytes = hex::decode(private_key)
-            .map_err(|e| EtherlinkError::Crypto(format!("Invalid private key: {}", e)))?;
+        software_fallback("Ed25519 signing")?;
+        let key_bytes = Zeroizing::new(hex::decode(private_key)
+            .map_err(|e| EtherlinkError::Crypto(format!("Invalid private key: {}", e)))?);
 
-        let signing_key = SigningKey::from_bytes(
-            &key_bytes.try_into()
-                .map_err(|_| EtherlinkError::Crypto("Invalid key length".to_string()))?
-        );
+        let key_bytes: Zeroizing<[u8; 32]> = Zeroizing::new(key_bytes.as_slice().try_into()
+            .map_err(|_| EtherlinkError::Crypto("Invalid key length".to_string()))?);
+        let signing_key = SigningKey::from_bytes(&key_bytes);
 
         let signature: Signature = signing_key.sign(message);
         Ok(hex::encode(signature.to_bytes()))
     }
 
     fn verify_ed25519(&self, message: &[u8], signature: &str, public_key: &str) -> Result<bool> {
+        match self.backend {
+            #[cfg(feature = "gcrypt")]
+            CryptoBackend::Gcrypt => super::gcrypt_backend::verify_ed25519(message, signature, public_key),
+            _ => self.fallback_verify_ed25519(message, signature, public_key),
+        }
+    }
+
+    fn fallback_verify_ed25519(&self, message: &[u8], signature: &str, public_key: &str) -> Result<bool> {
         use ed25519_dalek::{VerifyingKey, Signature, Verifier};
 
-        let sig_bytes = hex::decode(signature)
+        software_fallback("Ed25519 verification")?;
+        let sig_bytes = hex::decode(signature.trim_start_matches("0x"))
             .map_err(|e| EtherlinkError::Crypto(format!("Invalid signature: {}", e)))?;
 
         let pub_key_bytes = hex::decode(public_key)
@@ -128,20 +266,29 @@ impl CryptoProvider {
 
     // Secp256k1 implementations
     fn generate_secp256k1_keypair(&self) -> Result<KeyPair> {
+        match self.backend {
+            #[cfg(feature = "gcrypt")]
+            CryptoBackend::Gcrypt => super::gcrypt_backend::generate_secp256k1_keypair(),
+            _ => self.fallback_secp256k1_keypair(),
+        }
+    }
+
+    fn fallback_secp256k1_keypair(&self) -> Result<KeyPair> {
         #[cfg(feature = "fallback-crypto")]
         {
-            use secp256k1::{Secp256k1, SecretKey, PublicKey};
+            use secp256k1::{SecretKey, PublicKey};
             use rand::{rngs::OsRng, RngCore};
 
-            let secp = Secp256k1::new();
-            let mut secret_bytes = [0u8; 32];
-            OsRng.fill_bytes(&mut secret_bytes);
-            let secret_key = SecretKey::from_slice(&secret_bytes)
+            software_fallback("Secp256k1 key generation")?;
+            let secp = secp256k1_context();
+            let mut secret_bytes = Zeroizing::new([0u8; 32]);
+            OsRng.fill_bytes(secret_bytes.as_mut());
+            let secret_key = SecretKey::from_slice(secret_bytes.as_slice())
                 .map_err(|e| EtherlinkError::Crypto(format!("Failed to create secret key: {}", e)))?;
             let public_key = PublicKey::from_secret_key(&secp, &secret_key);
 
             Ok(KeyPair {
-                private_key: hex::encode(secret_key.secret_bytes()),
+                private_key: hex::encode(secret_key.secret_bytes()).into(),
                 public_key: hex::encode(public_key.serialize()),
                 algorithm: CryptoAlgorithm::Secp256k1,
             })
@@ -153,22 +300,28 @@ impl CryptoProvider {
     }
 
     fn sign_secp256k1(&self, message: &[u8], private_key: &str) -> Result<String> {
+        match self.backend {
+            #[cfg(feature = "gcrypt")]
+            CryptoBackend::Gcrypt => super::gcrypt_backend::sign_secp256k1(message, private_key),
+            _ => self.fallback_sign_secp256k1(message, private_key),
+        }
+    }
+
+    fn fallback_sign_secp256k1(&self, message: &[u8], private_key: &str) -> Result<String> {
         #[cfg(feature = "fallback-crypto")]
         {
-            use secp256k1::{Secp256k1, SecretKey, Message};
-            use sha2::{Sha256, Digest};
+            use secp256k1::{SecretKey, Message};
 
-            let secp = Secp256k1::new();
-            let key_bytes = hex::decode(private_key)
-                .map_err(|e| EtherlinkError::Crypto(format!("Invalid private key: {}", e)))?;
+            software_fallback("Secp256k1 signing")?;
+            let secp = secp256k1_context();
+            let key_bytes = Zeroizing::new(hex::decode(private_key)
+                .map_err(|e| EtherlinkError::Crypto(format!("Invalid private key: {}", e)))?);
 
             let secret_key = SecretKey::from_slice(&key_bytes)
                 .map_err(|e| EtherlinkError::Crypto(format!("Invalid secret key: {}", e)))?;
 
             // Hash the message
-            let mut hasher = Sha256::new();
-            hasher.update(message);
-            let hash = hasher.finalize();
+            let hash = Hasher::digest(HashAlgorithm::Sha256, message);
 
             let message = Message::from_slice(&hash)
                 .map_err(|e| EtherlinkError::Crypto(format!("Invalid message: {}", e)))?;
@@ -183,12 +336,20 @@ impl CryptoProvider {
     }
 
     fn verify_secp256k1(&self, message: &[u8], signature: &str, public_key: &str) -> Result<bool> {
+        match self.backend {
+            #[cfg(feature = "gcrypt")]
+            CryptoBackend::Gcrypt => super::gcrypt_backend::verify_secp256k1(message, signature, public_key),
+            _ => self.fallback_verify_secp256k1(message, signature, public_key),
+        }
+    }
+
+    fn fallback_verify_secp256k1(&self, message: &[u8], signature: &str, public_key: &str) -> Result<bool> {
         #[cfg(feature = "fallback-crypto")]
         {
-            use secp256k1::{Secp256k1, PublicKey, Message, ecdsa::Signature};
-            use sha2::{Sha256, Digest};
+            use secp256k1::{PublicKey, Message, ecdsa::Signature};
 
-            let secp = Secp256k1::new();
+            software_fallback("Secp256k1 verification")?;
+            let secp = secp256k1_context();
 
             let sig_bytes = hex::decode(signature)
                 .map_err(|e| EtherlinkError::Crypto(format!("Invalid signature: {}", e)))?;
@@ -203,9 +364,7 @@ impl CryptoProvider {
                 .map_err(|e| EtherlinkError::Crypto(format!("Invalid signature: {}", e)))?;
 
             // Hash the message
-            let mut hasher = Sha256::new();
-            hasher.update(message);
-            let hash = hasher.finalize();
+            let hash = Hasher::digest(HashAlgorithm::Sha256, message);
 
             let message = Message::from_slice(&hash)
                 .map_err(|e| EtherlinkError::Crypto(format!("Invalid message: {}", e)))?;
@@ -241,30 +400,401 @@ impl Default for CryptoProvider {
     }
 }
 
-/// Cryptographic algorithm types
+/// Process-wide libsecp256k1 context
+///
+/// Building a context precomputes signing and verification tables, which costs
+/// far more than a signature; every software secp256k1 operation shares this one.
+#[cfg(feature = "fallback-crypto")]
+pub(crate) fn secp256k1_context() -> &'static secp256k1::Secp256k1<secp256k1::All> {
+    static CONTEXT: std::sync::OnceLock<secp256k1::Secp256k1<secp256k1::All>> = std::sync::OnceLock::new();
+    CONTEXT.get_or_init(secp256k1::Secp256k1::new)
+}
+
+/// Refuse a software (ed25519-dalek/secp256k1) operation in `strict-crypto` builds
+///
+/// Strict builds only sign and verify through the gcrypt backend; an operation it
+/// does not cover fails instead of silently using the software implementation.
+pub(crate) fn software_fallback(operation: &str) -> Result<()> {
+    if cfg!(feature = "strict-crypto") {
+        return Err(EtherlinkError::Crypto(format!(
+            "{} is not available from the gcrypt backend and strict-crypto forbids the software fallback",
+            operation
+        )));
+    }
+    Ok(())
+}
+
+/// Current [`EncryptedEnvelope`] format version
+pub const ENVELOPE_VERSION: u8 = 1;
+
+const KEY_WRAP_INFO: &[u8] = b"etherlink-envelope-v1 key wrap";
+
+/// AEAD used for envelope payloads and key wrapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymmetricAlgorithm {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+/// Versioned ciphertext with everything needed to decrypt it except the key
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub enum CryptoAlgorithm {
-    Ed25519,
-    Secp256k1,
-    Bls12381,
+pub struct EncryptedEnvelope {
+    pub version: u8,
+    pub algorithm: SymmetricAlgorithm,
+    /// Content key wrapped for each recipient; empty for symmetric envelopes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<WrappedKey>,
+    /// Hex 12-byte nonce
+    pub nonce: String,
+    /// Base64 ciphertext including the authentication tag
+    pub ciphertext: String,
+}
+
+/// Content key encrypted to one recipient's X25519 public key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrappedKey {
+    /// Hex X25519 public key of the recipient
+    pub recipient_public_key: String,
+    /// Hex X25519 public key of the per-recipient ephemeral key
+    pub ephemeral_public_key: String,
+    /// Hex 12-byte nonce
+    pub nonce: String,
+    /// Hex wrapped content key including the authentication tag
+    pub wrapped_key: String,
+}
+
+/// Symmetric key that counts what it encrypts
+///
+/// Each envelope gets a random 96-bit nonce, which stays collision-safe for about
+/// 2^32 messages under one key. Past [`SealingKey::MAX_MESSAGES`] the key refuses
+/// to encrypt, so it is rotated before nonce reuse becomes a real risk.
+pub struct SealingKey {
+    algorithm: SymmetricAlgorithm,
+    key: Secret<[u8; 32]>,
+    sealed: std::sync::atomic::AtomicU64,
+}
+
+impl SealingKey {
+    /// Encryptions allowed under one key
+    pub const MAX_MESSAGES: u64 = 1 << 32;
+
+    pub fn new(algorithm: SymmetricAlgorithm, key: [u8; 32]) -> Self {
+        Self { algorithm, key: Secret::new(key), sealed: std::sync::atomic::AtomicU64::new(0) }
+    }
+
+    /// A fresh random key
+    pub fn generate(algorithm: SymmetricAlgorithm) -> Self {
+        Self::new(algorithm, rand::random())
+    }
+
+    /// Encrypt `plaintext`, failing once the key's message budget is spent
+    pub fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Result<EncryptedEnvelope> {
+        use std::sync::atomic::Ordering;
+
+        let previous = self.sealed.fetch_add(1, Ordering::SeqCst);
+        if previous >= Self::MAX_MESSAGES {
+            self.sealed.store(Self::MAX_MESSAGES, Ordering::SeqCst);
+            return Err(EtherlinkError::Crypto(format!(
+                "Key has encrypted {} messages; rotate it before encrypting more",
+                Self::MAX_MESSAGES
+            )));
+        }
+        CryptoProvider::new().encrypt(self.algorithm, self.key.expose_secret(), plaintext, aad)
+    }
+
+    pub fn decrypt(&self, envelope: &EncryptedEnvelope, aad: &[u8]) -> Result<Vec<u8>> {
+        CryptoProvider::new().decrypt(envelope, self.key.expose_secret(), aad)
+    }
+
+    /// Messages encrypted so far
+    pub fn messages_sealed(&self) -> u64 {
+        self.sealed.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+impl std::fmt::Debug for SealingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SealingKey")
+            .field("algorithm", &self.algorithm)
+            .field("key", &self.key)
+            .field("sealed", &self.messages_sealed())
+            .finish()
+    }
+}
+
+fn wrap_key(algorithm: SymmetricAlgorithm, content_key: &[u8; 32], recipient: &[u8; 32]) -> Result<WrappedKey> {
+    use x25519_dalek::{PublicKey, StaticSecret};
+
+    let recipient = PublicKey::from(*recipient);
+    let ephemeral = StaticSecret::from(rand::random::<[u8; 32]>());
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = Zeroizing::new(ephemeral.diffie_hellman(&recipient).to_bytes());
+    let kek = key_encryption_key(&shared, ephemeral_public.as_bytes(), recipient.as_bytes());
+    let (nonce, wrapped_key) = aead_encrypt(algorithm, &kek, content_key, &[])?;
+    Ok(WrappedKey {
+        recipient_public_key: hex::encode(recipient.as_bytes()),
+        ephemeral_public_key: hex::encode(ephemeral_public.as_bytes()),
+        nonce: hex::encode(nonce),
+        wrapped_key: hex::encode(wrapped_key),
+    })
+}
+
+fn unwrap_key(algorithm: SymmetricAlgorithm, wrapped: &WrappedKey, secret: &x25519_dalek::StaticSecret) -> Result<[u8; 32]> {
+    use x25519_dalek::PublicKey;
+
+    let ephemeral_public = PublicKey::from(decode_hex_array::<32>(&wrapped.ephemeral_public_key, "ephemeral key")?);
+    let nonce: [u8; 12] = decode_hex_array(&wrapped.nonce, "key wrap nonce")?;
+    let wrapped_key = hex::decode(&wrapped.wrapped_key)
+        .map_err(|e| EtherlinkError::Crypto(format!("Invalid wrapped key: {}", e)))?;
+    let shared = Zeroizing::new(secret.diffie_hellman(&ephemeral_public).to_bytes());
+    let kek = key_encryption_key(&shared, ephemeral_public.as_bytes(), PublicKey::from(secret).as_bytes());
+    let content_key = Zeroizing::new(aead_decrypt(algorithm, &kek, &nonce, &wrapped_key, &[])?);
+    content_key
+        .as_slice()
+        .try_into()
+        .map_err(|_| EtherlinkError::Crypto("Wrapped key has the wrong length".to_string()))
+}
+
+/// HKDF-SHA256 over the X25519 shared secret, salted with both public keys
+fn key_encryption_key(shared: &[u8; 32], ephemeral_public: &[u8; 32], recipient_public: &[u8; 32]) -> Zeroizing<[u8; 32]> {
+    let salt = [ephemeral_public.as_slice(), recipient_public.as_slice()].concat();
+    let mut key = Zeroizing::new([0u8; 32]);
+    hkdf::Hkdf::<sha2::Sha256>::new(Some(&salt), shared)
+        .expand(KEY_WRAP_INFO, key.as_mut_slice())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+/// Encrypt under a fresh random nonce, returned with the ciphertext
+///
+/// Callers never pick the nonce, so one cannot be reused by mistake.
+fn aead_encrypt(algorithm: SymmetricAlgorithm, key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<([u8; 12], Vec<u8>)> {
+    use aes_gcm::aead::{Aead, KeyInit, Payload};
+
+    let nonce: [u8; 12] = rand::random();
+    let payload = Payload { msg: plaintext, aad };
+    let result = match algorithm {
+        SymmetricAlgorithm::Aes256Gcm => aes_gcm::Aes256Gcm::new(key.into()).encrypt((&nonce).into(), payload),
+        SymmetricAlgorithm::ChaCha20Poly1305 => chacha20poly1305::ChaCha20Poly1305::new(key.into()).encrypt((&nonce).into(), payload),
+    };
+    result
+        .map(|ciphertext| (nonce, ciphertext))
+        .map_err(|_| EtherlinkError::Crypto("Encryption failed".to_string()))
+}
+
+fn aead_decrypt(algorithm: SymmetricAlgorithm, key: &[u8; 32], nonce: &[u8; 12], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    use aes_gcm::aead::{Aead, KeyInit, Payload};
+
+    let payload = Payload { msg: ciphertext, aad };
+    let result = match algorithm {
+        SymmetricAlgorithm::Aes256Gcm => aes_gcm::Aes256Gcm::new(key.into()).decrypt(nonce.into(), payload),
+        SymmetricAlgorithm::ChaCha20Poly1305 => chacha20poly1305::ChaCha20Poly1305::new(key.into()).decrypt(nonce.into(), payload),
+    };
+    result.map_err(|_| EtherlinkError::Crypto("Decryption failed: wrong key or tampered data".to_string()))
+}
+
+fn decode_hex_array<const N: usize>(value: &str, what: &str) -> Result<[u8; N]> {
+    hex::decode(value).ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| EtherlinkError::Crypto(format!("Invalid {}", what)))
 }
 
 /// Key pair structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyPair {
-    pub private_key: String,
+    pub private_key: SecretString,
     pub public_key: String,
     pub algorithm: CryptoAlgorithm,
 }
 
 impl KeyPair {
-    /// Get the address for this keypair (placeholder implementation)
-    pub fn address(&self) -> crate::Address {
-        // Simple address generation from public key hash
-        use sha2::{Sha256, Digest};
-        let mut hasher = Sha256::new();
-        hasher.update(self.public_key.as_bytes());
-        let hash = hasher.finalize();
-        crate::Address::new(format!("ghost1{}", hex::encode(&hash[..20])))
-    }
-}
\ No newline at end of file
+    /// Keypair for an existing hex-encoded Ed25519 or secp256k1 private key
+    pub fn from_private_key(private_key: &str, algorithm: &CryptoAlgorithm) -> Result<Self> {
+        let key_bytes: Zeroizing<[u8; 32]> = Zeroizing::new(decode_hex_array(private_key, "private key")?);
+        let public_key = match algorithm {
+            #[cfg(feature = "fallback-crypto")]
+            CryptoAlgorithm::Ed25519 => {
+                software_fallback("Ed25519 key import")?;
+                hex::encode(ed25519_dalek::SigningKey::from_bytes(&key_bytes).verifying_key().to_bytes())
+            }
+            #[cfg(feature = "fallback-crypto")]
+            CryptoAlgorithm::Secp256k1 => {
+                software_fallback("Secp256k1 key import")?;
+                let secret_key = secp256k1::SecretKey::from_slice(key_bytes.as_slice())
+                    .map_err(|e| EtherlinkError::Crypto(format!("Invalid secret key: {}", e)))?;
+                hex::encode(secp256k1::PublicKey::from_secret_key(secp256k1_context(), &secret_key).serialize())
+            }
+            _ => return Err(EtherlinkError::Crypto(format!("Importing {:?} private keys is not supported", algorithm))),
+        };
+        Ok(KeyPair {
+            private_key: hex::encode(key_bytes.as_slice()).into(),
+            public_key,
+            algorithm: algorithm.clone(),
+        })
+    }
+
+    /// Address of this key: EVM-style for secp256k1, `ghost1` otherwise
+    ///
+    /// Fails if the public key is malformed, e.g. a secp256k1 key that is not a curve point.
+    pub fn address(&self) -> Result<crate::Address> {
+        address_from_public_key(&self.public_key, &self.algorithm)
+    }
+}
+
+/// Address owned by a hex-encoded public key
+///
+/// Secp256k1 keys, compressed or not, derive the Keccak-256 EVM address; Ed25519 and
+/// BLS keys derive the native `ghost1` address.
+pub fn address_from_public_key(public_key: &str, algorithm: &CryptoAlgorithm) -> Result<crate::Address> {
+    match algorithm {
+        CryptoAlgorithm::Secp256k1 => {
+            let bytes = hex::decode(public_key)
+                .map_err(|e| EtherlinkError::Crypto(format!("Invalid public key: {}", e)))?;
+            address::from_uncompressed_public_key(&uncompressed_secp256k1(&bytes)?)
+        }
+        CryptoAlgorithm::Ed25519 | CryptoAlgorithm::Bls12381 => Ok(address::native_address(public_key)),
+    }
+}
+
+/// 65-byte form of a secp256k1 public key given compressed, uncompressed or as the bare
+/// 64-byte point, checked to lie on the curve
+fn uncompressed_secp256k1(public_key: &[u8]) -> Result<Vec<u8>> {
+    #[cfg(feature = "fallback-crypto")]
+    {
+        let tagged;
+        let public_key = if public_key.len() == 64 {
+            tagged = [&[0x04], public_key].concat();
+            tagged.as_slice()
+        } else {
+            public_key
+        };
+        let key = secp256k1::PublicKey::from_slice(public_key)
+            .map_err(|e| EtherlinkError::Crypto(format!("Invalid secp256k1 public key: {}", e)))?;
+        Ok(key.serialize_uncompressed().to_vec())
+    }
+    #[cfg(not(feature = "fallback-crypto"))]
+    {
+        let _ = public_key;
+        Err(EtherlinkError::Crypto("Secp256k1 not available".to_string()))
+    }
+}
+
+/// Recoverable secp256k1 signature over a 32-byte digest: `r ++ s ++ recovery id`
+///
+/// Unlike [`CryptoProvider::sign_message`] the digest is signed as given, which is
+/// what EVM transactions and typed data expect.
+pub fn sign_recoverable(digest: &[u8; 32], private_key: &str) -> Result<[u8; 65]> {
+    #[cfg(feature = "fallback-crypto")]
+    {
+        use secp256k1::{Message, SecretKey};
+
+        software_fallback("Secp256k1 signing")?;
+        let key_bytes = Zeroizing::new(hex::decode(private_key)
+            .map_err(|e| EtherlinkError::Crypto(format!("Invalid private key: {}", e)))?);
+        let secret_key = SecretKey::from_slice(&key_bytes)
+            .map_err(|e| EtherlinkError::Crypto(format!("Invalid secret key: {}", e)))?;
+        let (recovery_id, compact) = secp256k1_context()
+            .sign_ecdsa_recoverable(&Message::from_digest(*digest), &secret_key)
+            .serialize_compact();
+
+        let mut signature = [0u8; 65];
+        signature[..64].copy_from_slice(&compact);
+        signature[64] = recovery_id.to_i32() as u8;
+        Ok(signature)
+    }
+    #[cfg(not(feature = "fallback-crypto"))]
+    {
+        let _ = (digest, private_key);
+        Err(EtherlinkError::Crypto("Secp256k1 not available".to_string()))
+    }
+}
+
+/// Domain prefix that keeps a signed message from ever being a valid transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessagePrefix {
+    /// `"\x19Ghost Signed Message:\n" ++ len(message)`
+    Ghost,
+    /// `"\x19Ethereum Signed Message:\n" ++ len(message)`, as used by `personal_sign`
+    Ethereum,
+}
+
+impl MessagePrefix {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessagePrefix::Ghost => "\x19Ghost Signed Message:\n",
+            MessagePrefix::Ethereum => "\x19Ethereum Signed Message:\n",
+        }
+    }
+
+    /// Prefix, decimal byte length of `message`, then `message`
+    pub fn apply(&self, message: &[u8]) -> Vec<u8> {
+        let mut out = format!("{}{}", self.as_str(), message.len()).into_bytes();
+        out.extend_from_slice(message);
+        out
+    }
+
+    /// Keccak-256 of the prefixed message, the digest secp256k1 signs
+    pub fn digest(&self, message: &[u8]) -> [u8; 32] {
+        Hasher::digest(HashAlgorithm::Keccak256, self.apply(message))
+    }
+}
+
+/// EVM address behind a secp256k1 [`CryptoProvider::sign_message_prefixed`] signature
+pub fn recover_message_signer(message: &[u8], signature: &str, prefix: MessagePrefix) -> Result<crate::Address> {
+    recover_address(&prefix.digest(message), &parse_recoverable(signature)?)
+}
+
+/// Hex `r ++ s ++ v` with `v` either 0/1 or 27/28, normalised to a recovery id
+pub fn parse_recoverable(signature: &str) -> Result<[u8; 65]> {
+    let bytes = hex::decode(signature.trim_start_matches("0x"))
+        .map_err(|e| EtherlinkError::Crypto(format!("Invalid signature: {}", e)))?;
+    let mut signature: [u8; 65] = bytes.try_into()
+        .map_err(|_| EtherlinkError::Crypto("Recoverable signature must be 65 bytes".to_string()))?;
+    if signature[64] >= 27 {
+        signature[64] -= 27;
+    }
+    Ok(signature)
+}
+
+/// EVM address of the key that produced a [`sign_recoverable`] signature over `digest`
+pub fn recover_address(digest: &[u8; 32], signature: &[u8; 65]) -> Result<crate::Address> {
+    #[cfg(feature = "fallback-crypto")]
+    {
+        use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+        use secp256k1::Message;
+
+        software_fallback("Secp256k1 recovery")?;
+        let recovery_id = RecoveryId::from_i32(signature[64] as i32)
+            .map_err(|e| EtherlinkError::Crypto(format!("Invalid recovery id: {}", e)))?;
+        let signature = RecoverableSignature::from_compact(&signature[..64], recovery_id)
+            .map_err(|e| EtherlinkError::Crypto(format!("Invalid signature: {}", e)))?;
+        let public_key = secp256k1_context()
+            .recover_ecdsa(&Message::from_digest(*digest), &signature)
+            .map_err(|e| EtherlinkError::Crypto(format!("Signature recovery failed: {}", e)))?;
+        address::from_uncompressed_public_key(&public_key.serialize_uncompressed())
+    }
+    #[cfg(not(feature = "fallback-crypto"))]
+    {
+        let _ = (digest, signature);
+        Err(EtherlinkError::Crypto("Secp256k1 not available".to_string()))
+    }
+}
+
+impl Signer for KeyPair {
+    fn algorithm(&self) -> CryptoAlgorithm {
+        self.algorithm.clone()
+    }
+
+    fn public_key(&self) -> std::result::Result<Vec<u8>, SigningError> {
+        hex::decode(&self.public_key).map_err(|e| SigningError::InvalidKey(e.to_string()))
+    }
+
+    fn sign(&self, message: &[u8]) -> std::result::Result<Signature, SigningError> {
+        let signature = CryptoProvider::new()
+            .sign_message(message, self.private_key.expose_secret(), &self.algorithm)
+            .map_err(|e| SigningError::Backend(e.to_string()))?;
+        let bytes = hex::decode(signature).map_err(|e| SigningError::Backend(e.to_string()))?;
+        Ok(Signature::new(self.algorithm.clone(), bytes))
+    }
+}
@@ -0,0 +1,279 @@
+//! EIP-712 typed structured data
+//!
+//! [`TypedData`] mirrors the JSON accepted by `eth_signTypedData_v4`, so payloads from
+//! dApps (ERC-2612 `permit`, off-chain order books) can be hashed and signed as is.
+//! Signatures are 65-byte `r ++ s ++ v` with `v` in {27, 28}, the form wallets produce.
+
+use crate::address::evm_bytes;
+use crate::auth::crypto::{parse_recoverable, recover_address, sign_recoverable, CryptoAlgorithm, KeyPair};
+use crate::hash::{Hash, HashAlgorithm, Hasher};
+use crate::secret::constant_time_eq;
+use crate::{Address, EtherlinkError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Name of the domain struct type
+pub const DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// Domain fields in the order EIP-712 lists them, used when `types` omits the domain type
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+
+/// One member of a struct type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypedField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+impl TypedField {
+    pub fn new(name: &str, kind: &str) -> Self {
+        Self { name: name.to_string(), kind: kind.to_string() }
+    }
+}
+
+/// Typed data as passed to `eth_signTypedData_v4`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<TypedField>>,
+    pub primary_type: String,
+    pub domain: Value,
+    pub message: Value,
+}
+
+impl TypedData {
+    /// `keccak256(0x19 ++ 0x01 ++ domainSeparator ++ hashStruct(message))`
+    pub fn signing_hash(&self) -> Result<Hash> {
+        let mut hasher = Hasher::new(HashAlgorithm::Keccak256);
+        hasher.update([0x19, 0x01]).update(self.domain_separator()?).update(self.hash_struct(&self.primary_type, &self.message)?);
+        Ok(hasher.finalize())
+    }
+
+    /// `hashStruct(domain)`, deriving the domain type from the present fields if `types` lacks it
+    pub fn domain_separator(&self) -> Result<Hash> {
+        if self.types.contains_key(DOMAIN_TYPE) {
+            return self.hash_struct(DOMAIN_TYPE, &self.domain);
+        }
+        let domain = self.domain.as_object().ok_or_else(|| invalid("domain must be an object"))?;
+        let fields = DOMAIN_FIELDS.iter()
+            .filter(|(name, _)| domain.contains_key(*name))
+            .map(|(name, kind)| TypedField::new(name, kind))
+            .collect();
+        let mut types = self.types.clone();
+        types.insert(DOMAIN_TYPE.to_string(), fields);
+        TypedData { types, ..self.clone() }.hash_struct(DOMAIN_TYPE, &self.domain)
+    }
+
+    /// `keccak256(typeHash ++ encodeData(value))`
+    pub fn hash_struct(&self, kind: &str, value: &Value) -> Result<Hash> {
+        let mut hasher = Hasher::new(HashAlgorithm::Keccak256);
+        hasher.update(self.type_hash(kind)?);
+        let object = value.as_object().ok_or_else(|| invalid(&format!("{} value must be an object", kind)))?;
+        for field in self.fields(kind)? {
+            let member = object.get(&field.name).unwrap_or(&Value::Null);
+            hasher.update(self.encode_value(&field.kind, member)?);
+        }
+        Ok(hasher.finalize())
+    }
+
+    /// `keccak256(encodeType(kind))`
+    pub fn type_hash(&self, kind: &str) -> Result<Hash> {
+        Ok(Hasher::digest(HashAlgorithm::Keccak256, self.encode_type(kind)?))
+    }
+
+    /// `Name(type field,…)` followed by referenced struct types in alphabetical order
+    pub fn encode_type(&self, kind: &str) -> Result<String> {
+        let mut referenced = BTreeSet::new();
+        self.collect_references(kind, &mut referenced)?;
+        referenced.remove(kind);
+
+        let mut out = String::new();
+        for name in std::iter::once(kind).chain(referenced.iter().map(String::as_str)) {
+            let members: Vec<String> = self.fields(name)?.iter().map(|f| format!("{} {}", f.kind, f.name)).collect();
+            out.push_str(&format!("{}({})", name, members.join(",")));
+        }
+        Ok(out)
+    }
+
+    fn fields(&self, kind: &str) -> Result<&[TypedField]> {
+        self.types.get(kind).map(Vec::as_slice).ok_or_else(|| invalid(&format!("unknown type {}", kind)))
+    }
+
+    fn collect_references(&self, kind: &str, found: &mut BTreeSet<String>) -> Result<()> {
+        if !found.insert(kind.to_string()) {
+            return Ok(());
+        }
+        for field in self.fields(kind)? {
+            let base = base_type(&field.kind);
+            if self.types.contains_key(base) {
+                self.collect_references(base, found)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 32-byte encoding of one member
+    fn encode_value(&self, kind: &str, value: &Value) -> Result<[u8; 32]> {
+        if let Some(element) = array_element(kind) {
+            let items = value.as_array().ok_or_else(|| invalid(&format!("{} value must be an array", kind)))?;
+            let mut hasher = Hasher::new(HashAlgorithm::Keccak256);
+            for item in items {
+                hasher.update(self.encode_value(element, item)?);
+            }
+            return Ok(hasher.finalize());
+        }
+        if self.types.contains_key(kind) {
+            return self.hash_struct(kind, value);
+        }
+        encode_atomic(kind, value)
+    }
+}
+
+/// Sign typed data with a secp256k1 key, returning hex `r ++ s ++ v`
+pub fn sign_typed_data(data: &TypedData, key: &KeyPair) -> Result<String> {
+    if key.algorithm != CryptoAlgorithm::Secp256k1 {
+        return Err(EtherlinkError::Crypto("EIP-712 signatures require a secp256k1 key".to_string()));
+    }
+    let mut signature = sign_recoverable(&data.signing_hash()?, key.private_key.expose_secret())?;
+    signature[64] += 27;
+    Ok(format!("0x{}", hex::encode(signature)))
+}
+
+/// Address that produced `signature` over `data`
+pub fn recover_typed_data_signer(data: &TypedData, signature: &str) -> Result<Address> {
+    recover_address(&data.signing_hash()?, &parse_recoverable(signature)?)
+}
+
+/// Whether `signature` over `data` was made by `signer`
+pub fn verify_typed_data(data: &TypedData, signature: &str, signer: &Address) -> Result<bool> {
+    let expected = evm_bytes(signer)?;
+    Ok(constant_time_eq(&evm_bytes(&recover_typed_data_signer(data, signature)?)?, &expected))
+}
+
+/// Element type of `T[]` or `T[n]`
+fn array_element(kind: &str) -> Option<&str> {
+    kind.strip_suffix(']').and_then(|rest| rest.rfind('[').map(|open| &kind[..open]))
+}
+
+fn base_type(kind: &str) -> &str {
+    let mut kind = kind;
+    while let Some(element) = array_element(kind) {
+        kind = element;
+    }
+    kind
+}
+
+fn encode_atomic(kind: &str, value: &Value) -> Result<[u8; 32]> {
+    let mut word = [0u8; 32];
+    match kind {
+        "string" => {
+            let text = value.as_str().ok_or_else(|| invalid("string value expected"))?;
+            return Ok(Hasher::digest(HashAlgorithm::Keccak256, text));
+        }
+        "bytes" => return Ok(Hasher::digest(HashAlgorithm::Keccak256, hex_value(value)?)),
+        "bool" => word[31] = value.as_bool().ok_or_else(|| invalid("bool value expected"))? as u8,
+        "address" => {
+            let address = value.as_str().ok_or_else(|| invalid("address value expected"))?;
+            word[12..].copy_from_slice(&evm_bytes(&Address::new(address.to_string()))?);
+        }
+        _ if kind.starts_with("bytes") => {
+            let size = bit_size(kind, "bytes", 1, 32)?;
+            let bytes = hex_value(value)?;
+            if bytes.len() != size {
+                return Err(invalid(&format!("{} value must be {} bytes", kind, size)));
+            }
+            word[..size].copy_from_slice(&bytes);
+        }
+        _ if kind.starts_with("uint") => word = parse_integer(value, bit_size(kind, "uint", 8, 256)?, false)?,
+        _ if kind.starts_with("int") => word = parse_integer(value, bit_size(kind, "int", 8, 256)?, true)?,
+        _ => return Err(invalid(&format!("unknown type {}", kind))),
+    }
+    Ok(word)
+}
+
+/// Width suffix of `uintN`, `intN` or `bytesN` (defaults to the maximum when absent)
+fn bit_size(kind: &str, prefix: &str, step: usize, max: usize) -> Result<usize> {
+    let suffix = &kind[prefix.len()..];
+    if suffix.is_empty() && prefix != "bytes" {
+        return Ok(max);
+    }
+    suffix.parse::<usize>().ok()
+        .filter(|size| *size > 0 && *size <= max && size % step == 0)
+        .ok_or_else(|| invalid(&format!("unknown type {}", kind)))
+}
+
+fn hex_value(value: &Value) -> Result<Vec<u8>> {
+    let text = value.as_str().ok_or_else(|| invalid("hex string expected"))?;
+    hex::decode(text.trim_start_matches("0x")).map_err(|e| invalid(&format!("invalid hex {}: {}", text, e)))
+}
+
+/// Big-endian two's complement word from a JSON number, decimal string or `0x` hex string
+fn parse_integer(value: &Value, bits: usize, signed: bool) -> Result<[u8; 32]> {
+    let text = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.clone(),
+        _ => return Err(invalid("integer value expected")),
+    };
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) if signed => (true, rest),
+        Some(_) => return Err(invalid(&format!("negative value {} for an unsigned type", text))),
+        None => (false, text.as_str()),
+    };
+    let (radix, digits) = match digits.strip_prefix("0x") {
+        Some(hex) => (16u32, hex),
+        None => (10u32, digits),
+    };
+    if digits.is_empty() {
+        return Err(invalid(&format!("invalid integer {}", text)));
+    }
+
+    let mut word = [0u8; 32];
+    for c in digits.chars() {
+        let digit = c.to_digit(radix).ok_or_else(|| invalid(&format!("invalid integer {}", text)))?;
+        let mut carry = digit;
+        for byte in word.iter_mut().rev() {
+            let next = *byte as u32 * radix + carry;
+            *byte = next as u8;
+            carry = next >> 8;
+        }
+        if carry != 0 {
+            return Err(invalid(&format!("integer {} overflows 256 bits", text)));
+        }
+    }
+
+    // Magnitude limit: 2^bits - 1 unsigned, 2^(bits-1) (negative) or 2^(bits-1) - 1 signed
+    let limit_bits = if signed { bits - 1 } else { bits };
+    let leading_zero_bits = word.iter().take_while(|b| **b == 0).count() * 8
+        + word.iter().find(|b| **b != 0).map_or(0, |b| b.leading_zeros() as usize);
+    let magnitude_bits = 256 - leading_zero_bits;
+    let is_min_negative = negative && magnitude_bits == limit_bits + 1 && word_is_power_of_two(&word);
+    if magnitude_bits > limit_bits && !is_min_negative {
+        return Err(invalid(&format!("integer {} does not fit in {} bits", text, bits)));
+    }
+
+    if negative {
+        let mut carry = 1u16;
+        for byte in word.iter_mut().rev() {
+            let next = (!*byte) as u16 + carry;
+            *byte = next as u8;
+            carry = next >> 8;
+        }
+    }
+    Ok(word)
+}
+
+fn word_is_power_of_two(word: &[u8; 32]) -> bool {
+    word.iter().map(|b| b.count_ones()).sum::<u32>() == 1
+}
+
+fn invalid(message: &str) -> EtherlinkError {
+    EtherlinkError::Codec(format!("EIP-712: {}", message))
+}
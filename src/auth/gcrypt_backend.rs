@@ -0,0 +1,88 @@
+//! gcrypt implementations behind [`CryptoBackend::Gcrypt`]
+//!
+//! Keys, signatures and hashing match the software backend byte for byte: Ed25519
+//! keys are 32-byte seeds with 32-byte public keys; secp256k1 keys sign the SHA-256
+//! of the message and publish 33-byte compressed public keys and 64-byte compact
+//! signatures. Either backend can verify what the other signed.
+//!
+//! [`CryptoBackend::Gcrypt`]: super::crypto::CryptoBackend::Gcrypt
+
+use super::crypto::{CryptoAlgorithm, KeyPair};
+use crate::hash::{HashAlgorithm, Hasher};
+use crate::{EtherlinkError, Result};
+use gcrypt::protocols::{ed25519, secp256k1};
+use zeroize::Zeroizing;
+
+fn gcrypt_error(what: &str, e: impl std::fmt::Debug) -> EtherlinkError {
+    EtherlinkError::Crypto(format!("gcrypt: {}: {:?}", what, e))
+}
+
+fn decode_secret(private_key: &str) -> Result<Zeroizing<[u8; 32]>> {
+    let bytes = Zeroizing::new(hex::decode(private_key)
+        .map_err(|e| EtherlinkError::Crypto(format!("Invalid private key: {}", e)))?);
+    let seed: [u8; 32] = bytes.as_slice().try_into()
+        .map_err(|_| EtherlinkError::Crypto("Invalid key length".to_string()))?;
+    Ok(Zeroizing::new(seed))
+}
+
+fn decode_array<const N: usize>(value: &str, what: &str) -> Result<[u8; N]> {
+    hex::decode(value)
+        .map_err(|e| EtherlinkError::Crypto(format!("Invalid {}: {}", what, e)))?
+        .try_into()
+        .map_err(|_| EtherlinkError::Crypto(format!("Invalid {} length", what)))
+}
+
+pub(crate) fn generate_ed25519_keypair() -> Result<KeyPair> {
+    let seed: Zeroizing<[u8; 32]> = Zeroizing::new(rand::random());
+    let secret = ed25519::SecretKey::from_bytes(&seed);
+    Ok(KeyPair {
+        private_key: hex::encode(*seed).into(),
+        public_key: hex::encode(secret.public_key().to_bytes()),
+        algorithm: CryptoAlgorithm::Ed25519,
+    })
+}
+
+pub(crate) fn sign_ed25519(message: &[u8], private_key: &str) -> Result<String> {
+    let secret = ed25519::SecretKey::from_bytes(&decode_secret(private_key)?);
+    Ok(hex::encode(secret.sign(message).to_bytes()))
+}
+
+pub(crate) fn verify_ed25519(message: &[u8], signature: &str, public_key: &str) -> Result<bool> {
+    let signature = ed25519::Signature::from_bytes(&decode_array::<64>(signature.trim_start_matches("0x"), "signature")?);
+    let public_key = ed25519::PublicKey::from_bytes(&decode_array::<32>(public_key, "public key")?)
+        .map_err(|e| gcrypt_error("invalid Ed25519 public key", e))?;
+    Ok(public_key.verify(message, &signature).is_ok())
+}
+
+pub(crate) fn generate_secp256k1_keypair() -> Result<KeyPair> {
+    loop {
+        let bytes: Zeroizing<[u8; 32]> = Zeroizing::new(rand::random());
+        // Rejects zero and values at or above the group order, both vanishingly rare
+        let Ok(secret) = secp256k1::SecretKey::from_bytes(&bytes) else {
+            continue;
+        };
+        return Ok(KeyPair {
+            private_key: hex::encode(*bytes).into(),
+            public_key: hex::encode(secret.public_key().to_compressed_bytes()),
+            algorithm: CryptoAlgorithm::Secp256k1,
+        });
+    }
+}
+
+pub(crate) fn sign_secp256k1(message: &[u8], private_key: &str) -> Result<String> {
+    let secret = secp256k1::SecretKey::from_bytes(&decode_secret(private_key)?)
+        .map_err(|e| gcrypt_error("invalid secp256k1 secret key", e))?;
+    let digest = Hasher::digest(HashAlgorithm::Sha256, message);
+    Ok(hex::encode(secret.sign_prehashed(&digest).to_compact_bytes()))
+}
+
+pub(crate) fn verify_secp256k1(message: &[u8], signature: &str, public_key: &str) -> Result<bool> {
+    let signature = secp256k1::Signature::from_compact_bytes(&decode_array::<64>(signature.trim_start_matches("0x"), "signature")?)
+        .map_err(|e| gcrypt_error("invalid secp256k1 signature", e))?;
+    let public_key = hex::decode(public_key)
+        .map_err(|e| EtherlinkError::Crypto(format!("Invalid public key: {}", e)))?;
+    let public_key = secp256k1::PublicKey::from_sec1_bytes(&public_key)
+        .map_err(|e| gcrypt_error("invalid secp256k1 public key", e))?;
+    let digest = Hasher::digest(HashAlgorithm::Sha256, message);
+    Ok(public_key.verify_prehashed(&digest, &signature).is_ok())
+}
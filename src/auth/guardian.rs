@@ -1,18 +1,26 @@
 //! Guardian authentication provider implementation
 
 use crate::auth::{AuthProvider, AuthCredentials, AuthToken, Permission};
-use crate::clients::gid::{GidClient, GuardianTokenRequest, AccessToken};
+use crate::auth::crypto::{CryptoAlgorithm, CryptoProvider, KeyPair};
+use crate::clock::{self, SharedClock};
+use crate::clients::gid::{GidClient, GuardianTokenRequest, AccessToken, RequestSigningPolicy};
+use crate::hash::{HashAlgorithm, Hasher};
 use crate::{Result, EtherlinkError};
-use async_trait::async_trait;
 use std::collections::HashMap;
-use std::sync::Arc;
-use chrono::Utc;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+use tracing::{debug, warn};
 
 /// Guardian authentication provider for zero-trust access control
 #[derive(Debug, Clone)]
 pub struct GuardianAuthProvider {
     gid_client: Arc<GidClient>,
     current_token: Option<AuthToken>,
+    signing_key: Option<KeyPair>,
+    /// Server clock minus local clock, in seconds, learned from the signing policy
+    clock_offset: Arc<AtomicI64>,
+    clock: SharedClock,
 }
 
 impl GuardianAuthProvider {
@@ -21,9 +29,57 @@ impl GuardianAuthProvider {
         Self {
             gid_client,
             current_token: None,
+            signing_key: None,
+            clock_offset: Arc::new(AtomicI64::new(0)),
+            clock: clock::system(),
         }
     }
 
+    /// Read the time from `clock` for expiry checks and request timestamps
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Current time in unix seconds, by the injected clock
+    fn now(&self) -> u64 {
+        self.clock.now()
+    }
+
+    /// Sign requests with the identity key, see [`GuardianAuthProvider::sign_request`]
+    pub fn with_signing_key(mut self, key: KeyPair) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Fetch the server's signing policy and align request timestamps with its clock
+    pub async fn sync_signing_policy(&self) -> Result<RequestSigningPolicy> {
+        let policy = self.gid_client.get_request_signing_policy().await?;
+        let offset = policy.server_time as i64 - self.now() as i64;
+        self.clock_offset.store(offset, Ordering::Relaxed);
+        Ok(policy)
+    }
+
+    /// Auth headers plus a signature binding them to this one request
+    ///
+    /// The identity key signs [`canonical_request`] over the method, path, body hash,
+    /// timestamp and a fresh nonce, so captured headers cannot be replayed or moved
+    /// to another request.
+    pub fn sign_request(&self, token: &AuthToken, method: &str, path: &str, body: &[u8]) -> Result<HashMap<String, String>> {
+        let key = self.signing_key.as_ref()
+            .ok_or_else(|| EtherlinkError::Authentication("No request signing key configured".to_string()))?;
+        let timestamp = self.now() as i64 + self.clock_offset.load(Ordering::Relaxed);
+        let nonce = hex::encode(rand::random::<[u8; 16]>());
+        let canonical = canonical_request(method, path, body, timestamp, &nonce);
+        let signature = CryptoProvider::new().sign_message(canonical.as_bytes(), key.private_key.expose_secret(), &key.algorithm)?;
+
+        let mut headers = self.get_auth_headers(token)?;
+        headers.insert("X-Guardian-Timestamp".to_string(), timestamp.to_string());
+        headers.insert("X-Guardian-Nonce".to_string(), nonce);
+        headers.insert("X-Guardian-Request-Signature".to_string(), signature);
+        Ok(headers)
+    }
+
     /// Convert Guardian access token to auth token
     fn convert_access_token(&self, access_token: AccessToken) -> AuthToken {
         AuthToken {
@@ -40,7 +96,7 @@ impl GuardianAuthProvider {
     /// Get current token if valid
     pub fn get_current_token(&self) -> Option<&AuthToken> {
         if let Some(token) = &self.current_token {
-            if !token.is_expired() {
+            if !token.is_expired_at(self.now()) {
                 return Some(token);
             }
         }
@@ -49,16 +105,15 @@ impl GuardianAuthProvider {
 
     /// Check if token needs refresh
     pub fn needs_refresh(&self, threshold_seconds: u64) -> bool {
-        if let Some(token) = &self.current_token {
-            let now = Utc::now().timestamp() as u64;
-            let time_until_expiry = token.expires_at.saturating_sub(now);
-            return time_until_expiry <= threshold_seconds;
+        match &self.current_token {
+            Some(token) => token.needs_refresh_at(threshold_seconds, self.now()),
+            None => true,
         }
-        true
     }
 }
 
-#[async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl AuthProvider for GuardianAuthProvider {
     async fn authenticate(&self, credentials: &AuthCredentials) -> Result<AuthToken> {
         // Create Guardian token request
@@ -85,7 +140,7 @@ impl AuthProvider for GuardianAuthProvider {
         // Create new credentials with existing identity and permissions
         let credentials = AuthCredentials {
             identity: token.identity.clone(),
-            secret: crate::auth::AuthSecret::Password("refresh".to_string()), // Placeholder
+            secret: crate::auth::AuthSecret::Password("refresh".into()), // Placeholder
             permissions: token.permissions.clone(),
         };
 
@@ -95,7 +150,7 @@ impl AuthProvider for GuardianAuthProvider {
 
     async fn validate_token(&self, token: &AuthToken) -> Result<bool> {
         // Check expiration
-        if token.is_expired() {
+        if token.is_expired_at(self.now()) {
             return Ok(false);
         }
 
@@ -114,19 +169,112 @@ impl AuthProvider for GuardianAuthProvider {
         headers.insert("X-Guardian-Signature".to_string(), token.signature.clone());
 
         // Add timestamp for request validation
-        let timestamp = Utc::now().timestamp().to_string();
+        let timestamp = self.now().to_string();
         headers.insert("X-Guardian-Timestamp".to_string(), timestamp);
 
         Ok(headers)
     }
 }
 
+/// String signed for a Guardian request: method, path, body SHA-256, timestamp and nonce, one per line
+pub fn canonical_request(method: &str, path: &str, body: &[u8], timestamp: i64, nonce: &str) -> String {
+    let body_hash = Hasher::digest(HashAlgorithm::Sha256, body);
+    format!("{}\n{}\n{}\n{}\n{}", method.to_ascii_uppercase(), path, hex::encode(body_hash), timestamp, nonce)
+}
+
+/// Server-side check that a signed request is fresh and has not been seen before
+///
+/// Nonces are remembered for twice the skew tolerance, after which their timestamp
+/// alone is enough to reject them.
+#[derive(Debug)]
+pub struct ReplayGuard {
+    max_skew: Duration,
+    seen: Mutex<HashMap<String, i64>>,
+    clock: SharedClock,
+}
+
+impl ReplayGuard {
+    pub fn new(max_skew: Duration) -> Self {
+        Self { max_skew, seen: Mutex::new(HashMap::new()), clock: clock::system() }
+    }
+
+    /// Read the current time from `clock` in [`ReplayGuard::check`] and [`ReplayGuard::verify_request`]
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Guard using the tolerance the server advertises
+    pub fn from_policy(policy: &RequestSigningPolicy) -> Self {
+        Self::new(Duration::from_secs(policy.max_skew_seconds))
+    }
+
+    /// Accept a timestamp and nonce once, at the current time by the guard's clock
+    pub fn check(&self, timestamp: i64, nonce: &str) -> Result<()> {
+        self.check_at(timestamp, nonce, self.clock.now() as i64)
+    }
+
+    /// Accept a timestamp and nonce once, at `now` (unix seconds)
+    pub fn check_at(&self, timestamp: i64, nonce: &str, now: i64) -> Result<()> {
+        let skew = self.max_skew.as_secs() as i64;
+        if (now - timestamp).abs() > skew {
+            return Err(EtherlinkError::Authentication(format!("Request timestamp {} is outside the {}s window", timestamp, skew)));
+        }
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, at| now - *at <= 2 * skew);
+        if seen.contains_key(nonce) {
+            return Err(EtherlinkError::Authentication("Request nonce was already used".to_string()));
+        }
+        seen.insert(nonce.to_string(), timestamp);
+        Ok(())
+    }
+
+    /// Check a request signed by [`GuardianAuthProvider::sign_request`] against the identity's public key
+    pub fn verify_request(&self, headers: &HashMap<String, String>, method: &str, path: &str, body: &[u8], public_key: &str, algorithm: &CryptoAlgorithm) -> Result<()> {
+        let header = |name: &str| headers.get(name)
+            .ok_or_else(|| EtherlinkError::Authentication(format!("Missing {} header", name)));
+        let timestamp: i64 = header("X-Guardian-Timestamp")?.parse()
+            .map_err(|_| EtherlinkError::Authentication("Invalid X-Guardian-Timestamp header".to_string()))?;
+        let nonce = header("X-Guardian-Nonce")?;
+        let signature = header("X-Guardian-Request-Signature")?;
+
+        let canonical = canonical_request(method, path, body, timestamp, nonce);
+        if !CryptoProvider::new().verify_signature(canonical.as_bytes(), signature, public_key, algorithm)? {
+            return Err(EtherlinkError::Authentication("Invalid request signature".to_string()));
+        }
+        self.check(timestamp, nonce)
+    }
+}
+
+/// Refresh events buffered per subscriber before the oldest are dropped
+const REFRESH_EVENT_CAPACITY: usize = 16;
+
+/// Longest wait between attempts after refreshes keep failing
+const MAX_REFRESH_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Broadcast to [`GuardianAuthManager::subscribe_refreshes`] subscribers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthRefreshEvent {
+    /// A new token is in place
+    Refreshed { expires_at: u64 },
+    /// The token could not be refreshed; the current one is used until it expires
+    Failed { error: String, expires_at: u64 },
+}
+
 /// Guardian authentication manager with automatic token refresh
+///
+/// Tokens are refreshed lazily by [`GuardianAuthManager::get_auth_headers`], or ahead
+/// of expiry by the task from [`GuardianAuthManager::start_auto_refresh`].
 #[derive(Debug)]
 pub struct GuardianAuthManager {
     provider: GuardianAuthProvider,
     config: crate::auth::AuthConfig,
     current_token: tokio::sync::RwLock<Option<AuthToken>>,
+    /// Wakes the refresh task when the token is replaced
+    token_changed: tokio::sync::Notify,
+    events: tokio::sync::broadcast::Sender<AuthRefreshEvent>,
+    #[cfg(not(target_arch = "wasm32"))]
+    bus: Option<crate::events::EventBus>,
 }
 
 impl GuardianAuthManager {
@@ -136,17 +284,124 @@ impl GuardianAuthManager {
             provider: GuardianAuthProvider::new(gid_client),
             config,
             current_token: tokio::sync::RwLock::new(None),
+            token_changed: tokio::sync::Notify::new(),
+            events: tokio::sync::broadcast::channel(REFRESH_EVENT_CAPACITY).0,
+            #[cfg(not(target_arch = "wasm32"))]
+            bus: None,
         }
     }
 
+    /// Sign each request with the identity key
+    pub fn with_signing_key(mut self, key: KeyPair) -> Self {
+        self.provider = self.provider.with_signing_key(key);
+        self
+    }
+
+    /// Align request timestamps with the Guardian's clock
+    pub async fn sync_signing_policy(&self) -> Result<RequestSigningPolicy> {
+        self.provider.sync_signing_policy().await
+    }
+
     /// Authenticate and store token
     pub async fn authenticate(&self, credentials: &AuthCredentials) -> Result<()> {
         let token = self.provider.authenticate(credentials).await?;
+        self.store(token).await;
+        Ok(())
+    }
 
-        let mut current_token = self.current_token.write().await;
-        *current_token = Some(token);
+    /// Receive an event for every refresh attempt, lazy or in the background
+    pub fn subscribe_refreshes(&self) -> tokio::sync::broadcast::Receiver<AuthRefreshEvent> {
+        self.events.subscribe()
+    }
 
-        Ok(())
+    /// Read the time from `clock` for token expiry and refresh scheduling
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.provider = self.provider.with_clock(clock);
+        self
+    }
+
+    /// Also publish successful refreshes as [`crate::EtherlinkEvent::TokenRefreshed`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_events(mut self, bus: crate::events::EventBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Refresh the current token now, whatever its expiry
+    pub async fn refresh(&self) -> Result<()> {
+        let current = self.current_token.read().await.clone()
+            .ok_or_else(|| EtherlinkError::Authentication("No token to refresh".to_string()))?;
+        match self.provider.refresh_token(&current).await {
+            Ok(token) => {
+                let _ = self.events.send(AuthRefreshEvent::Refreshed { expires_at: token.expires_at });
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(bus) = &self.bus {
+                    bus.publish(crate::EtherlinkEvent::TokenRefreshed { identity: token.identity.clone(), expires_at: token.expires_at });
+                }
+                self.store(token).await;
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Guardian token refresh failed: {}", e);
+                let _ = self.events.send(AuthRefreshEvent::Failed { error: e.to_string(), expires_at: current.expires_at });
+                Err(e)
+            }
+        }
+    }
+
+    /// Refresh tokens in the background, `refresh_threshold_seconds` before they expire
+    /// (at most half way through a token's lifetime)
+    ///
+    /// The task runs under `supervisor` and stops when it shuts down. Failed attempts
+    /// are retried with exponential backoff and reported through
+    /// [`GuardianAuthManager::subscribe_refreshes`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn start_auto_refresh(self: &Arc<Self>, supervisor: &crate::runtime::TaskSupervisor) -> Result<()> {
+        let manager = self.clone();
+        supervisor
+            .spawn("guardian-auth-refresh", crate::runtime::RestartPolicy::default(), move |token| {
+                let manager = manager.clone();
+                async move { manager.run_auto_refresh(token).await }
+            })
+            .await
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn run_auto_refresh(&self, cancel: tokio_util::sync::CancellationToken) {
+        let mut failures = 0u32;
+        loop {
+            let delay = match failures {
+                0 => self.current_token.read().await.as_ref().map(|token| {
+                    let refresh_at = token.refresh_at(self.config.refresh_threshold_seconds);
+                    // At least a second apart, even for tokens that are already due
+                    Duration::from_secs(refresh_at.saturating_sub(self.provider.now()).max(1))
+                }),
+                n => Some(Duration::from_secs(1 << (n - 1).min(6)).min(MAX_REFRESH_BACKOFF)),
+            };
+            let sleep = async {
+                match delay {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = self.token_changed.notified() => failures = 0,
+                _ = sleep => {
+                    failures = match self.refresh().await {
+                        Ok(()) => 0,
+                        Err(_) => failures.saturating_add(1),
+                    };
+                }
+            }
+        }
+        debug!("Guardian token refresh stopped");
+    }
+
+    /// Replace the token and let the refresh task reschedule
+    async fn store(&self, token: AuthToken) {
+        *self.current_token.write().await = Some(token);
+        self.token_changed.notify_one();
     }
 
     /// Get authentication headers, refreshing token if needed
@@ -156,34 +411,22 @@ impl GuardianAuthManager {
             let should_refresh = {
                 let token_guard = self.current_token.read().await;
                 if let Some(token) = token_guard.as_ref() {
-                    let now = Utc::now().timestamp() as u64;
-                    let time_until_expiry = token.expires_at.saturating_sub(now);
-                    time_until_expiry <= self.config.refresh_threshold_seconds
+                    token.needs_refresh_at(self.config.refresh_threshold_seconds, self.provider.now())
                 } else {
                     true
                 }
             };
 
-            if should_refresh {
-                // Try to refresh token
-                let current_token_clone = {
-                    let token_guard = self.current_token.read().await;
-                    token_guard.clone()
-                };
-
-                if let Some(current_token) = current_token_clone {
-                    if let Ok(new_token) = self.provider.refresh_token(&current_token).await {
-                        let mut token_guard = self.current_token.write().await;
-                        *token_guard = Some(new_token);
-                    }
-                }
+            if should_refresh && self.current_token.read().await.is_some() {
+                // A failed refresh is reported to subscribers; the old token may still be valid
+                let _ = self.refresh().await;
             }
         }
 
         // Get current token and generate headers
         let token_guard = self.current_token.read().await;
         if let Some(token) = token_guard.as_ref() {
-            if !token.is_expired() {
+            if !token.is_expired_at(self.provider.now()) {
                 return self.provider.get_auth_headers(token);
             }
         }
@@ -191,11 +434,18 @@ impl GuardianAuthManager {
         Err(EtherlinkError::Authentication("No valid token available".to_string()))
     }
 
+    /// Signed headers for one request, see [`GuardianAuthProvider::sign_request`]
+    pub async fn get_signed_headers(&self, method: &str, path: &str, body: &[u8]) -> Result<HashMap<String, String>> {
+        let token = self.get_current_token().await
+            .ok_or_else(|| EtherlinkError::Authentication("No valid token available".to_string()))?;
+        self.provider.sign_request(&token, method, path, body)
+    }
+
     /// Check if authenticated
     pub async fn is_authenticated(&self) -> bool {
         let token_guard = self.current_token.read().await;
         if let Some(token) = token_guard.as_ref() {
-            !token.is_expired()
+            !token.is_expired_at(self.provider.now())
         } else {
             false
         }
@@ -205,7 +455,7 @@ impl GuardianAuthManager {
     pub async fn get_current_token(&self) -> Option<AuthToken> {
         let token_guard = self.current_token.read().await;
         if let Some(token) = token_guard.as_ref() {
-            if !token.is_expired() {
+            if !token.is_expired_at(self.provider.now()) {
                 return Some(token.clone());
             }
         }
@@ -0,0 +1,67 @@
+//! Signers backed by an externally provided signing callback
+//!
+//! Browser dApps never see the user's private key: signing is delegated to an
+//! injected wallet (an extension or a hardware bridge) that answers asynchronously.
+//! [`InjectedSigner`] adapts such a callback to the crate's [`Signature`] type.
+
+use crate::primitives::{CryptoAlgorithm, Signature, SigningError};
+use futures::future::LocalBoxFuture;
+use std::fmt;
+use std::future::Future;
+use std::rc::Rc;
+
+type SignFn = dyn Fn(Vec<u8>) -> LocalBoxFuture<'static, std::result::Result<Vec<u8>, SigningError>>;
+
+/// Signer that forwards messages to an injected wallet
+///
+/// The callback future does not need to be `Send`, so it can wrap a JavaScript
+/// promise via `wasm_bindgen_futures::JsFuture`.
+#[derive(Clone)]
+pub struct InjectedSigner {
+    algorithm: CryptoAlgorithm,
+    public_key: Vec<u8>,
+    sign: Rc<SignFn>,
+}
+
+impl InjectedSigner {
+    /// Create a signer for a wallet key with the given public key
+    pub fn new<F, Fut>(algorithm: CryptoAlgorithm, public_key: Vec<u8>, sign: F) -> Self
+    where
+        F: Fn(Vec<u8>) -> Fut + 'static,
+        Fut: Future<Output = std::result::Result<Vec<u8>, SigningError>> + 'static,
+    {
+        Self {
+            algorithm,
+            public_key,
+            sign: Rc::new(move |message| Box::pin(sign(message))),
+        }
+    }
+
+    /// Algorithm of the wallet key
+    pub fn algorithm(&self) -> CryptoAlgorithm {
+        self.algorithm.clone()
+    }
+
+    /// Raw public key bytes of the wallet key
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Ask the wallet to sign a message
+    pub async fn sign(&self, message: &[u8]) -> std::result::Result<Signature, SigningError> {
+        let bytes = (self.sign)(message.to_vec()).await?;
+        if bytes.is_empty() {
+            return Err(SigningError::Backend("Injected wallet returned an empty signature".to_string()));
+        }
+        Ok(Signature::new(self.algorithm.clone(), bytes))
+    }
+}
+
+impl fmt::Debug for InjectedSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InjectedSigner")
+            .field("algorithm", &self.algorithm)
+            .field("public_key", &hex::encode(&self.public_key))
+            .finish()
+    }
+}
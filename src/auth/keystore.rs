@@ -0,0 +1,263 @@
+//! Persistent storage for signing keys and session keys
+//!
+//! [`Keystore`] and [`SessionStore`] keep named [`KeyPair`]s and [`SessionKey`]s in a
+//! [`SecretBackend`]. [`EncryptedFileBackend`] writes one AEAD envelope per secret
+//! to a directory; with the `keyring` feature, [`KeyringBackend`] hands secrets to
+//! the OS keychain instead (macOS Keychain, Windows Credential Manager/DPAPI, the
+//! Linux Secret Service), so nothing sensitive is left on disk.
+
+use super::crypto::{CryptoProvider, EncryptedEnvelope, KeyPair, SymmetricAlgorithm};
+use super::session::SessionKey;
+use crate::{EtherlinkError, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+/// Where secrets are kept, by name
+pub trait SecretBackend: Send + Sync + fmt::Debug {
+    /// The secret stored under `name`, if any
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Store `secret` under `name`, replacing any previous one
+    fn set(&self, name: &str, secret: &[u8]) -> Result<()>;
+
+    /// Remove the secret under `name`, returning whether there was one
+    fn delete(&self, name: &str) -> Result<bool>;
+}
+
+/// Names become file names and keychain accounts, so keep them to a safe alphabet
+fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | ':'));
+    if valid {
+        Ok(())
+    } else {
+        Err(EtherlinkError::Configuration(format!(
+            "Invalid secret name `{}`: use letters, digits, `.`, `_`, `-` and `:`",
+            name
+        )))
+    }
+}
+
+/// Secrets encrypted under a 32-byte key, one file per name
+///
+/// The name is bound to each envelope as associated data, so a file renamed on
+/// disk fails to decrypt rather than loading under the wrong name.
+pub struct EncryptedFileBackend {
+    dir: PathBuf,
+    key: Zeroizing<[u8; 32]>,
+    algorithm: SymmetricAlgorithm,
+}
+
+impl EncryptedFileBackend {
+    pub fn new(dir: impl Into<PathBuf>, key: [u8; 32]) -> Self {
+        Self { dir: dir.into(), key: Zeroizing::new(key), algorithm: SymmetricAlgorithm::ChaCha20Poly1305 }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: SymmetricAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name.replace(':', "_")))
+    }
+}
+
+impl fmt::Debug for EncryptedFileBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedFileBackend")
+            .field("dir", &self.dir)
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecretBackend for EncryptedFileBackend {
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        check_name(name)?;
+        let path = self.path(name);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(EtherlinkError::Configuration(format!("Failed to read {}: {}", path.display(), e))),
+        };
+        let envelope: EncryptedEnvelope = serde_json::from_slice(&bytes)?;
+        CryptoProvider::new().decrypt(&envelope, &*self.key, name.as_bytes()).map(Some)
+    }
+
+    fn set(&self, name: &str, secret: &[u8]) -> Result<()> {
+        check_name(name)?;
+        let envelope = CryptoProvider::new().encrypt(self.algorithm, &*self.key, secret, name.as_bytes())?;
+        let write_error = |path: &std::path::Path, e: std::io::Error| {
+            EtherlinkError::Configuration(format!("Failed to write {}: {}", path.display(), e))
+        };
+        std::fs::create_dir_all(&self.dir).map_err(|e| write_error(&self.dir, e))?;
+        let path = self.path(name);
+        let partial = path.with_extension("json.partial");
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&partial).map_err(|e| write_error(&partial, e))?;
+        std::io::Write::write_all(&mut file, &serde_json::to_vec(&envelope)?).map_err(|e| write_error(&partial, e))?;
+        file.sync_all().map_err(|e| write_error(&partial, e))?;
+        std::fs::rename(&partial, &path).map_err(|e| write_error(&path, e))
+    }
+
+    fn delete(&self, name: &str) -> Result<bool> {
+        check_name(name)?;
+        let path = self.path(name);
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(EtherlinkError::Configuration(format!("Failed to remove {}: {}", path.display(), e))),
+        }
+    }
+}
+
+/// Secrets in the OS keychain, as accounts under one service name
+///
+/// Keychains store strings, so secrets are hex-encoded.
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone)]
+pub struct KeyringBackend {
+    service: String,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringBackend {
+    /// Default service name entries are filed under
+    pub const DEFAULT_SERVICE: &'static str = "etherlink";
+
+    pub fn new(service: impl Into<String>) -> Self {
+        Self { service: service.into() }
+    }
+
+    fn entry(&self, name: &str) -> Result<keyring::Entry> {
+        check_name(name)?;
+        keyring::Entry::new(&self.service, name).map_err(keyring_error)
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl Default for KeyringBackend {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SERVICE)
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_error(e: keyring::Error) -> EtherlinkError {
+    EtherlinkError::Configuration(format!("System keychain: {}", e))
+}
+
+#[cfg(feature = "keyring")]
+impl SecretBackend for KeyringBackend {
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        match self.entry(name)?.get_password() {
+            Ok(secret) => hex::decode(secret)
+                .map(Some)
+                .map_err(|e| EtherlinkError::Configuration(format!("Corrupt keychain entry {}: {}", name, e))),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+
+    fn set(&self, name: &str, secret: &[u8]) -> Result<()> {
+        self.entry(name)?.set_password(&hex::encode(secret)).map_err(keyring_error)
+    }
+
+    fn delete(&self, name: &str) -> Result<bool> {
+        match self.entry(name)?.delete_password() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+}
+
+fn load<T: DeserializeOwned>(backend: &dyn SecretBackend, name: &str) -> Result<Option<T>> {
+    match backend.get(name)? {
+        Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+fn save<T: Serialize>(backend: &dyn SecretBackend, name: &str, value: &T) -> Result<()> {
+    backend.set(name, &serde_json::to_vec(value)?)
+}
+
+/// Named signing keys
+#[derive(Debug, Clone)]
+pub struct Keystore {
+    backend: Arc<dyn SecretBackend>,
+}
+
+impl Keystore {
+    pub fn new(backend: Arc<dyn SecretBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Keys in the OS keychain under [`KeyringBackend::DEFAULT_SERVICE`]
+    #[cfg(feature = "keyring")]
+    pub fn system() -> Self {
+        Self::new(Arc::new(KeyringBackend::default()))
+    }
+
+    pub fn save(&self, name: &str, key: &KeyPair) -> Result<()> {
+        save(self.backend.as_ref(), &format!("key:{}", name), key)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Option<KeyPair>> {
+        load(self.backend.as_ref(), &format!("key:{}", name))
+    }
+
+    pub fn delete(&self, name: &str) -> Result<bool> {
+        self.backend.delete(&format!("key:{}", name))
+    }
+}
+
+/// Named session keys with their delegations
+///
+/// Expired sessions are dropped from the store when loaded.
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    backend: Arc<dyn SecretBackend>,
+}
+
+impl SessionStore {
+    pub fn new(backend: Arc<dyn SecretBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Sessions in the OS keychain under [`KeyringBackend::DEFAULT_SERVICE`]
+    #[cfg(feature = "keyring")]
+    pub fn system() -> Self {
+        Self::new(Arc::new(KeyringBackend::default()))
+    }
+
+    pub fn save(&self, name: &str, session: &SessionKey) -> Result<()> {
+        save(self.backend.as_ref(), &format!("session:{}", name), session)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Option<SessionKey>> {
+        let Some(session) = load::<SessionKey>(self.backend.as_ref(), &format!("session:{}", name))? else {
+            return Ok(None);
+        };
+        if session.delegation.policy.is_expired() {
+            self.delete(name)?;
+            return Ok(None);
+        }
+        Ok(Some(session))
+    }
+
+    pub fn delete(&self, name: &str) -> Result<bool> {
+        self.backend.delete(&format!("session:{}", name))
+    }
+}
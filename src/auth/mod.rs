@@ -1,17 +1,39 @@
 //! Authentication and authorization for GhostChain services
 
 pub mod guardian;
+#[cfg(feature = "fallback-crypto")]
+pub mod cached;
 pub mod crypto;
+pub mod eip712;
+#[cfg(feature = "gcrypt")]
+mod gcrypt_backend;
+pub mod injected;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod keystore;
+pub mod ownership;
+pub mod session;
 
 pub use guardian::*;
+#[cfg(feature = "fallback-crypto")]
+pub use cached::CachedSigner;
 pub use crypto::*;
+pub use eip712::TypedData;
+pub use injected::InjectedSigner;
+pub use ownership::{prove_address_ownership, prove_address_ownership_with_clock, verify_address_ownership, OwnershipProof};
+#[cfg(not(target_arch = "wasm32"))]
+pub use keystore::{EncryptedFileBackend, Keystore, SecretBackend, SessionStore};
+#[cfg(feature = "keyring")]
+pub use keystore::KeyringBackend;
+pub use session::{Delegation, LocalSigner, SessionKey, SessionMethod, SessionPolicy};
 
 use crate::{Result, EtherlinkError};
+use crate::secret::SecretString;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 
 /// Authentication provider trait
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 pub trait AuthProvider: Send + Sync {
     /// Authenticate and get access token
     async fn authenticate(&self, credentials: &AuthCredentials) -> Result<AuthToken>;
@@ -37,10 +59,10 @@ pub struct AuthCredentials {
 /// Authentication secret types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AuthSecret {
-    PrivateKey(String),
-    Mnemonic(String),
-    Password(String),
-    Certificate(String),
+    PrivateKey(SecretString),
+    Mnemonic(SecretString),
+    Password(SecretString),
+    Certificate(SecretString),
 }
 
 /// Authentication token
@@ -58,15 +80,41 @@ pub struct AuthToken {
 impl AuthToken {
     /// Check if token is expired
     pub fn is_expired(&self) -> bool {
-        let now = chrono::Utc::now().timestamp() as u64;
+        self.is_expired_at(chrono::Utc::now().timestamp() as u64)
+    }
+
+    /// Check if token is expired at `now` (unix seconds)
+    pub fn is_expired_at(&self, now: u64) -> bool {
         now >= self.expires_at
     }
 
+    /// `threshold_seconds` capped at half the token's lifetime, so a token issued for
+    /// less than the configured threshold is not refreshed the moment it arrives
+    pub fn refresh_threshold(&self, threshold_seconds: u64) -> u64 {
+        threshold_seconds.min(self.expires_at.saturating_sub(self.issued_at) / 2)
+    }
+
+    /// Unix time (seconds) at which the token should be refreshed
+    pub fn refresh_at(&self, threshold_seconds: u64) -> u64 {
+        self.expires_at.saturating_sub(self.refresh_threshold(threshold_seconds))
+    }
+
+    /// Whether the token is within its (clamped) refresh threshold at `now`
+    pub fn needs_refresh_at(&self, threshold_seconds: u64, now: u64) -> bool {
+        now >= self.refresh_at(threshold_seconds)
+    }
+
     /// Check if token has specific permission
     pub fn has_permission(&self, permission: &Permission) -> bool {
         self.permissions.contains(permission)
     }
 
+    /// Bytes covered by `signature`: the canonical encoding with `signature` unset
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let unsigned = AuthToken { signature: String::new(), ..self.clone() };
+        crate::canonical::to_bytes(&unsigned).unwrap_or_default()
+    }
+
     /// Get token as bearer string
     pub fn as_bearer(&self) -> String {
         format!("Bearer {}", self.token_id)
@@ -107,6 +155,18 @@ pub enum Permission {
     Verify,
     ThresholdSign,
 
+    // Storage permissions
+    ReadStorage,
+    WriteStorage,
+
+    // Staking permissions
+    ReadValidators,
+    ManageValidator,
+
+    // Orderbook permissions
+    ReadOrders,
+    PlaceOrders,
+
     // Administrative permissions
     Admin,
     SystemRead,
@@ -114,7 +174,7 @@ pub enum Permission {
 }
 
 /// Authentication configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AuthConfig {
     pub provider: AuthProviderType,
     pub token_duration_seconds: u64,
@@ -122,7 +182,7 @@ pub struct AuthConfig {
     pub refresh_threshold_seconds: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AuthProviderType {
     Guardian,
     BasicAuth,
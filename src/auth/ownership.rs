@@ -0,0 +1,174 @@
+//! Proofs that a user controls an address
+//!
+//! Exchanges and compliance tools whitelisting withdrawal addresses send the user a
+//! challenge and expect a signed statement back. [`prove_address_ownership`] signs a
+//! fixed-format text statement naming the verifier's domain, the address and the
+//! challenge, the way wallets sign messages: secp256k1 keys with the Ethereum
+//! `personal_sign` prefix (EIP-191), so the proof verifies with any Ethereum tooling,
+//! and other keys with the Ghost prefix. [`verify_address_ownership`] checks a proof
+//! against the domain and challenge of the verifier that issued it.
+//!
+//! As in Sign-In with Ethereum (EIP-4361), the statement opens with the domain asking
+//! for it, so a proof phished for one verifier is rejected by every other.
+
+use crate::address::evm_bytes;
+use crate::auth::crypto::{
+    address_from_public_key, recover_message_signer, CryptoAlgorithm, CryptoProvider, KeyPair, MessagePrefix,
+};
+use crate::secret::constant_time_eq;
+use crate::clock::{self, Clock};
+use crate::{Address, EtherlinkError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Version of the statement format, part of the signed text
+pub const OWNERSHIP_PROOF_VERSION: u32 = 2;
+
+/// A signed statement that the holder of a key controls an address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnershipProof {
+    pub version: u32,
+    pub address: Address,
+    /// Domain of the verifier the statement is addressed to, e.g. `exchange.example`
+    pub domain: String,
+    /// Challenge issued by the verifier, echoed in the statement
+    pub challenge: String,
+    /// Unix time the statement was signed
+    pub issued_at: u64,
+    /// The exact text that was signed, see [`ownership_statement`]
+    pub message: String,
+    pub algorithm: CryptoAlgorithm,
+    /// Hex public key; a secp256k1 signer is recovered from the signature instead
+    pub public_key: String,
+    /// For secp256k1, hex `r ++ s ++ v` over the EIP-191 prefixed message
+    pub signature: String,
+}
+
+/// Text signed for `domain` to prove `address` with `challenge` at `issued_at`
+pub fn ownership_statement(domain: &str, address: &Address, challenge: &str, issued_at: u64) -> String {
+    let issued_at = chrono::DateTime::from_timestamp(issued_at as i64, 0)
+        .map(|at| at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_else(|| issued_at.to_string());
+    format!(
+        "{} wants you to confirm that you control the address:\n{}\n\nDomain: {}\nChallenge: {}\nIssued At: {}\nVersion: {}",
+        domain, address, domain, challenge, issued_at, OWNERSHIP_PROOF_VERSION
+    )
+}
+
+fn prefix(algorithm: &CryptoAlgorithm) -> MessagePrefix {
+    match algorithm {
+        CryptoAlgorithm::Secp256k1 => MessagePrefix::Ethereum,
+        _ => MessagePrefix::Ghost,
+    }
+}
+
+/// Whether `public_key` is the key behind `address`, ignoring EVM checksum casing
+fn controls(address: &Address, public_key: &str, algorithm: &CryptoAlgorithm) -> Result<bool> {
+    let owner = address_from_public_key(public_key, algorithm)?;
+    match algorithm {
+        CryptoAlgorithm::Secp256k1 => Ok(constant_time_eq(&evm_bytes(&owner)?, &evm_bytes(address)?)),
+        _ => Ok(owner == *address),
+    }
+}
+
+fn check_challenge(challenge: &str) -> Result<()> {
+    if challenge.is_empty() || challenge.len() > 256 || challenge.chars().any(char::is_control) {
+        return Err(EtherlinkError::Crypto(
+            "Ownership challenge must be 1 to 256 characters without control characters".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn check_domain(domain: &str) -> Result<()> {
+    if domain.is_empty() || domain.len() > 253 || domain.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(EtherlinkError::Crypto(
+            "Ownership proof domain must be 1 to 253 characters without whitespace".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Prove to the verifier at `domain` that `key` controls `address` by signing a
+/// statement of `challenge`
+///
+/// Fails unless `key`'s address is `address`.
+pub fn prove_address_ownership(address: &Address, domain: &str, challenge: &str, key: &KeyPair) -> Result<OwnershipProof> {
+    prove_address_ownership_with_clock(address, domain, challenge, key, clock::system().as_ref())
+}
+
+/// [`prove_address_ownership`], dating the statement by `clock` instead of the system clock
+pub fn prove_address_ownership_with_clock(
+    address: &Address,
+    domain: &str,
+    challenge: &str,
+    key: &KeyPair,
+    clock: &dyn Clock,
+) -> Result<OwnershipProof> {
+    check_domain(domain)?;
+    check_challenge(challenge)?;
+    if !controls(address, &key.public_key, &key.algorithm)? {
+        return Err(EtherlinkError::Crypto(format!("Key {} does not control address {}", key.public_key, address)));
+    }
+
+    let issued_at = clock.now();
+    let message = ownership_statement(domain, address, challenge, issued_at);
+    let signature = CryptoProvider::new().sign_message_prefixed(
+        message.as_bytes(),
+        key.private_key.expose_secret(),
+        &key.algorithm,
+        prefix(&key.algorithm),
+    )?;
+    Ok(OwnershipProof {
+        version: OWNERSHIP_PROOF_VERSION,
+        address: address.clone(),
+        domain: domain.to_string(),
+        challenge: challenge.to_string(),
+        issued_at,
+        message,
+        algorithm: key.algorithm.clone(),
+        public_key: key.public_key.clone(),
+        signature,
+    })
+}
+
+/// Check that `proof` was made for the verifier at `domain`, answers `challenge` and
+/// was signed by the key controlling its address
+///
+/// Freshness is up to the verifier: issue single-use challenges, or compare
+/// [`OwnershipProof::issued_at`] with the time the challenge was issued.
+pub fn verify_address_ownership(proof: &OwnershipProof, domain: &str, challenge: &str) -> Result<()> {
+    if proof.version != OWNERSHIP_PROOF_VERSION {
+        return Err(EtherlinkError::Crypto(format!("Unsupported ownership proof version {}", proof.version)));
+    }
+    if !proof.domain.eq_ignore_ascii_case(domain) {
+        return Err(EtherlinkError::Crypto(format!("Ownership proof was made for {}, not {}", proof.domain, domain)));
+    }
+    if !constant_time_eq(proof.challenge.as_bytes(), challenge.as_bytes()) {
+        return Err(EtherlinkError::Crypto("Ownership proof answers a different challenge".to_string()));
+    }
+    if proof.message != ownership_statement(&proof.domain, &proof.address, &proof.challenge, proof.issued_at) {
+        return Err(EtherlinkError::Crypto("Ownership proof message does not match its fields".to_string()));
+    }
+
+    let message = proof.message.as_bytes();
+    let verified = match proof.algorithm {
+        CryptoAlgorithm::Secp256k1 => {
+            let signer = recover_message_signer(message, &proof.signature, MessagePrefix::Ethereum)?;
+            constant_time_eq(&evm_bytes(&signer)?, &evm_bytes(&proof.address)?)
+        }
+        _ => {
+            controls(&proof.address, &proof.public_key, &proof.algorithm)?
+                && CryptoProvider::new().verify_message_prefixed(
+                    message,
+                    &proof.signature,
+                    &proof.public_key,
+                    &proof.algorithm,
+                    MessagePrefix::Ghost,
+                )?
+        }
+    };
+    if !verified {
+        return Err(EtherlinkError::Crypto(format!("Ownership proof was not signed by the key controlling {}", proof.address)));
+    }
+    Ok(())
+}
@@ -0,0 +1,270 @@
+//! Delegated session keys with an attached spending policy
+//!
+//! An owner key signs a [`Delegation`] that hands a short-lived session key a
+//! bounded [`SessionPolicy`]. A [`LocalSigner`] holding the session key refuses to
+//! sign anything outside the policy and attaches the delegation to every
+//! transaction, so services can check it with [`Delegation::authorizes`].
+
+use crate::clients::ghostd::Transaction;
+use crate::{Address, EtherlinkError, Result};
+use super::crypto::{address_from_public_key, CryptoAlgorithm, CryptoProvider, KeyPair, Signature, Signer, SigningError};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Kind of call a session key may make
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+#[serde(rename_all = "snake_case")]
+pub enum SessionMethod {
+    /// Plain value transfer (no call data)
+    Transfer,
+    /// Contract call (non-empty call data)
+    ContractCall,
+}
+
+impl SessionMethod {
+    /// Classify a transaction the same way the proto conversion does
+    pub fn of(tx: &Transaction) -> Self {
+        if tx.data.as_ref().is_some_and(|data| !data.is_empty()) {
+            SessionMethod::ContractCall
+        } else {
+            SessionMethod::Transfer
+        }
+    }
+}
+
+/// Limits a session key is bound by
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+pub struct SessionPolicy {
+    /// Total value (amount plus maximum gas cost) the session may spend
+    pub max_spend: u64,
+    /// Unix timestamp (seconds) after which the session is void
+    pub expires_at: u64,
+    /// Calls the session may make
+    pub allowed_methods: Vec<SessionMethod>,
+}
+
+impl SessionPolicy {
+    /// Transfers only, up to `max_spend`, valid for `ttl` from now
+    pub fn new(max_spend: u64, ttl: Duration) -> Self {
+        Self {
+            max_spend,
+            expires_at: now().saturating_add(ttl.as_secs()),
+            allowed_methods: vec![SessionMethod::Transfer],
+        }
+    }
+
+    /// Also allow `method`
+    pub fn allow(mut self, method: SessionMethod) -> Self {
+        if !self.allowed_methods.contains(&method) {
+            self.allowed_methods.push(method);
+        }
+        self
+    }
+
+    /// Whether the policy has lapsed at `now` (unix seconds)
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Whether the policy has lapsed
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(now())
+    }
+
+    /// Check one transaction against the policy, given what was already spent
+    ///
+    /// Returns the new cumulative spend on success.
+    pub fn check(&self, tx: &Transaction, spent: u64, now: u64) -> Result<u64> {
+        if self.is_expired_at(now) {
+            return Err(EtherlinkError::SessionPolicy(format!("session expired at {}", self.expires_at)));
+        }
+        let method = SessionMethod::of(tx);
+        if !self.allowed_methods.contains(&method) {
+            return Err(EtherlinkError::SessionPolicy(format!("method {:?} is not allowed", method)));
+        }
+        let total = spent.checked_add(spend_of(tx)?)
+            .ok_or_else(|| EtherlinkError::SessionPolicy("spend overflows u64".to_string()))?;
+        if total > self.max_spend {
+            return Err(EtherlinkError::SessionPolicy(format!(
+                "spend limit exceeded: {} of {} already spent, transaction needs {}",
+                spent, self.max_spend, total - spent
+            )));
+        }
+        Ok(total)
+    }
+}
+
+/// Owner-signed proof that a session key acts under a policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+pub struct Delegation {
+    /// Account the session spends from
+    pub issuer: Address,
+    /// Hex public key of the issuer; must derive to `issuer`
+    pub issuer_public_key: String,
+    /// Hex public key of the session key
+    pub session_public_key: String,
+    pub algorithm: CryptoAlgorithm,
+    pub policy: SessionPolicy,
+    /// Hex signature by the issuer over [`Delegation::signing_payload`]
+    pub signature: String,
+}
+
+impl Delegation {
+    /// Bytes covered by the issuer signature: the canonical encoding with `signature` unset
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let unsigned = Delegation { signature: String::new(), ..self.clone() };
+        crate::canonical::to_bytes(&unsigned).unwrap_or_default()
+    }
+
+    /// Check the issuer signature and that the issuer key owns `issuer`
+    pub fn verify(&self) -> Result<()> {
+        if address_from_public_key(&self.issuer_public_key, &self.algorithm)? != self.issuer {
+            return Err(EtherlinkError::SessionPolicy("issuer key does not match issuer address".to_string()));
+        }
+        let valid = CryptoProvider::new()
+            .verify_signature(&self.signing_payload(), &self.signature, &self.issuer_public_key, &self.algorithm)?;
+        if !valid {
+            return Err(EtherlinkError::SessionPolicy("invalid delegation signature".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Validate a delegated transaction as a service would
+    ///
+    /// `spent` is what the service has already seen this session spend.
+    /// Returns the new cumulative spend.
+    pub fn authorizes(&self, tx: &Transaction, spent: u64, now: u64) -> Result<u64> {
+        self.verify()?;
+        if tx.from != self.issuer {
+            return Err(EtherlinkError::SessionPolicy(format!("delegation is for {}, not {}", self.issuer, tx.from)));
+        }
+        let total = self.policy.check(tx, spent, now)?;
+        let signature = tx.signature.as_deref()
+            .ok_or_else(|| EtherlinkError::SessionPolicy("transaction is not signed".to_string()))?;
+        let valid = CryptoProvider::new()
+            .verify_signature(&tx.signing_payload(), signature, &self.session_public_key, &self.algorithm)?;
+        if !valid {
+            return Err(EtherlinkError::SessionPolicy("transaction not signed by the session key".to_string()));
+        }
+        Ok(total)
+    }
+}
+
+/// Freshly generated key plus the delegation that empowers it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionKey {
+    pub key: KeyPair,
+    pub delegation: Delegation,
+}
+
+impl SessionKey {
+    /// Generate a session key and have `owner` sign its delegation
+    pub fn derive(owner: &KeyPair, policy: SessionPolicy) -> Result<Self> {
+        let provider = CryptoProvider::new();
+        let key = provider.generate_keypair(&owner.algorithm)?;
+        let mut delegation = Delegation {
+            issuer: owner.address()?,
+            issuer_public_key: owner.public_key.clone(),
+            session_public_key: key.public_key.clone(),
+            algorithm: owner.algorithm.clone(),
+            policy,
+            signature: String::new(),
+        };
+        delegation.signature = provider.sign_message(&delegation.signing_payload(), owner.private_key.expose_secret(), &owner.algorithm)?;
+        Ok(Self { key, delegation })
+    }
+}
+
+/// Signer backed by a key held in memory, optionally bound by a session policy
+#[derive(Debug)]
+pub struct LocalSigner {
+    key: KeyPair,
+    delegation: Option<Delegation>,
+    spent: Mutex<u64>,
+}
+
+impl LocalSigner {
+    /// Unrestricted signer for an owner key
+    pub fn new(key: KeyPair) -> Self {
+        Self { key, delegation: None, spent: Mutex::new(0) }
+    }
+
+    /// Signer that enforces the session's policy
+    pub fn from_session(session: SessionKey) -> Self {
+        Self { key: session.key, delegation: Some(session.delegation), spent: Mutex::new(0) }
+    }
+
+    /// Account transactions are sent from: the issuer for a session key
+    pub fn address(&self) -> Result<Address> {
+        match &self.delegation {
+            Some(delegation) => Ok(delegation.issuer.clone()),
+            None => self.key.address(),
+        }
+    }
+
+    pub fn delegation(&self) -> Option<&Delegation> {
+        self.delegation.as_ref()
+    }
+
+    /// Value spent so far under the session
+    pub fn spent(&self) -> u64 {
+        *self.spent.lock().unwrap()
+    }
+
+    /// Value the session may still spend, `None` when unrestricted
+    pub fn remaining(&self) -> Option<u64> {
+        self.delegation.as_ref().map(|d| d.policy.max_spend.saturating_sub(self.spent()))
+    }
+
+    /// Sign `tx`, enforcing the session policy and attaching the delegation
+    ///
+    /// The spend is only recorded once signing succeeds.
+    pub fn sign_transaction(&self, tx: &mut Transaction) -> Result<()> {
+        let Some(delegation) = &self.delegation else {
+            return tx.sign(&self.key);
+        };
+        if tx.from != delegation.issuer {
+            return Err(EtherlinkError::SessionPolicy(format!("session key cannot spend from {}", tx.from)));
+        }
+        let mut spent = self.spent.lock().unwrap();
+        let total = delegation.policy.check(tx, *spent, now())?;
+        tx.delegation = Some(delegation.clone());
+        tx.sign(&self.key)?;
+        *spent = total;
+        Ok(())
+    }
+}
+
+impl Signer for LocalSigner {
+    fn algorithm(&self) -> CryptoAlgorithm {
+        self.key.algorithm.clone()
+    }
+
+    fn public_key(&self) -> std::result::Result<Vec<u8>, SigningError> {
+        Signer::public_key(&self.key)
+    }
+
+    /// Raw messages cannot be checked against a session policy, so a session key
+    /// refuses them; use [`LocalSigner::sign_transaction`] instead
+    fn sign(&self, message: &[u8]) -> std::result::Result<Signature, SigningError> {
+        if self.delegation.is_some() {
+            return Err(SigningError::Backend("session keys only sign transactions through their policy".to_string()));
+        }
+        self.key.sign(message)
+    }
+}
+
+/// Amount plus the most the transaction can pay in gas
+fn spend_of(tx: &Transaction) -> Result<u64> {
+    tx.gas_limit.checked_mul(tx.gas_price)
+        .and_then(|gas| gas.checked_add(tx.amount))
+        .ok_or_else(|| EtherlinkError::SessionPolicy("transaction cost overflows u64".to_string()))
+}
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
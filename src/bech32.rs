@@ -0,0 +1,128 @@
+//! Bech32 (BIP-173) and bech32m (BIP-350) encoding
+//!
+//! Native addresses are bech32m strings with the `ghost` human-readable part, e.g.
+//! `ghost1…`; see [`crate::address::native_address`]. Both variants decode so that
+//! strings produced by older tooling remain readable.
+
+use crate::address::AddressError;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+/// Longest string BIP-173 allows
+const MAX_LENGTH: usize = 90;
+
+/// Checksum constant in use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc830a3,
+        }
+    }
+}
+
+/// Encode `data` (8-bit bytes) under `hrp`
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> Result<String, AddressError> {
+    validate_hrp(hrp)?;
+    let hrp = hrp.to_ascii_lowercase();
+    let values = convert_bits(data, 8, 5, true)?;
+    let length = hrp.len() + 1 + values.len() + 6;
+    if length > MAX_LENGTH {
+        return Err(AddressError::Length { expected: MAX_LENGTH, found: length });
+    }
+
+    let checksum = checksum(&hrp, &values, variant);
+    let mut out = String::with_capacity(length);
+    out.push_str(&hrp);
+    out.push('1');
+    out.extend(values.iter().chain(&checksum).map(|v| CHARSET[*v as usize] as char));
+    Ok(out)
+}
+
+/// Decode a bech32 or bech32m string into its lowercase HRP, 8-bit data and variant
+pub fn decode(value: &str) -> Result<(String, Vec<u8>, Variant), AddressError> {
+    if value.len() > MAX_LENGTH {
+        return Err(AddressError::Length { expected: MAX_LENGTH, found: value.len() });
+    }
+    if value.chars().any(|c| c.is_ascii_lowercase()) && value.chars().any(|c| c.is_ascii_uppercase()) {
+        return Err(AddressError::Format("mixed-case bech32 string".to_string()));
+    }
+    let value = value.to_ascii_lowercase();
+    let separator = value.rfind('1').ok_or_else(|| AddressError::Format("missing bech32 separator".to_string()))?;
+    let (hrp, rest) = (&value[..separator], &value[separator + 1..]);
+    validate_hrp(hrp)?;
+    if rest.len() < 6 {
+        return Err(AddressError::Length { expected: 6, found: rest.len() });
+    }
+
+    let values = rest.chars()
+        .map(|c| CHARSET.iter().position(|x| *x as char == c).map(|v| v as u8).ok_or(AddressError::Character(c)))
+        .collect::<Result<Vec<u8>, _>>()?;
+    let residue = polymod(&[expand_hrp(hrp), values.clone()].concat());
+    let variant = [Variant::Bech32, Variant::Bech32m].into_iter()
+        .find(|variant| variant.constant() == residue)
+        .ok_or(AddressError::Checksum)?;
+    let data = convert_bits(&values[..values.len() - 6], 5, 8, false)?;
+    Ok((hrp.to_string(), data, variant))
+}
+
+/// Regroup `data` from `from`-bit to `to`-bit values
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>, AddressError> {
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    let max = (1u32 << to) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for value in data {
+        acc = (acc << from) | *value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & max) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & max != 0 {
+        return Err(AddressError::Format("invalid bech32 padding".to_string()));
+    }
+    Ok(out)
+}
+
+fn validate_hrp(hrp: &str) -> Result<(), AddressError> {
+    if hrp.is_empty() || hrp.len() > 83 || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return Err(AddressError::Format(format!("invalid human-readable part {:?}", hrp)));
+    }
+    Ok(())
+}
+
+fn expand_hrp(hrp: &str) -> Vec<u8> {
+    let bytes = hrp.as_bytes();
+    bytes.iter().map(|b| b >> 5).chain([0]).chain(bytes.iter().map(|b| b & 0x1f)).collect()
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ *value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn checksum(hrp: &str, values: &[u8], variant: Variant) -> [u8; 6] {
+    let residue = polymod(&[expand_hrp(hrp), values.to_vec(), vec![0; 6]].concat()) ^ variant.constant();
+    std::array::from_fn(|i| ((residue >> (5 * (5 - i))) & 0x1f) as u8)
+}
@@ -0,0 +1,108 @@
+//! Short-lived read-through caches for hot read paths
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+// `std::time::Instant` panics in the browser
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Bounded map whose entries expire after a fixed TTL
+#[derive(Debug, Clone)]
+pub struct TtlCache<K, V> {
+    entries: HashMap<K, (V, Instant)>,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    /// Create a new cache
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            max_entries,
+        }
+    }
+
+    /// Get a live entry
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.entries
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(value, _)| value.clone())
+    }
+
+    /// Insert an entry, evicting the entry closest to expiry when full
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.entries.len() >= self.max_entries && !self.entries.contains_key(&key) {
+            self.purge_expired();
+            if self.entries.len() >= self.max_entries {
+                let oldest = self.entries
+                    .iter()
+                    .min_by_key(|(_, (_, expires_at))| *expires_at)
+                    .map(|(k, _)| k.clone());
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+
+        self.entries.insert(key, (value, Instant::now() + self.ttl));
+    }
+
+    /// Remove an entry
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.entries.remove(key).map(|(value, _)| value)
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drop expired entries
+    pub fn purge_expired(&mut self) {
+        let now = Instant::now();
+        self.entries.retain(|_, (_, expires_at)| *expires_at > now);
+    }
+
+    /// Number of stored entries (including not yet purged expired ones)
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Configuration for client read caches
+#[derive(Debug, Clone)]
+pub struct ReadCacheConfig {
+    pub balance_ttl_ms: u64,
+    pub height_ttl_ms: u64,
+    pub header_ttl_ms: u64,
+    pub max_balances: usize,
+    pub max_headers: usize,
+}
+
+impl Default for ReadCacheConfig {
+    fn default() -> Self {
+        Self {
+            balance_ttl_ms: 2000,
+            height_ttl_ms: 1000,
+            header_ttl_ms: 60000,
+            max_balances: 10000,
+            max_headers: 256,
+        }
+    }
+}
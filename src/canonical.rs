@@ -0,0 +1,359 @@
+//! Deterministic byte encoding of signable structures
+//!
+//! Signatures are made over [`to_bytes`] rather than JSON, so the Rust and Zig sides
+//! produce identical bytes regardless of map iteration order or number formatting.
+//! The encoding is driven by `serde` and is not self-describing:
+//!
+//! | value                          | bytes                                              |
+//! |--------------------------------|----------------------------------------------------|
+//! | `bool`                         | `0x00` or `0x01`                                   |
+//! | `u8`..`u128`, `i8`..`i128`     | big-endian, fixed width (two's complement)         |
+//! | string, bytes, `char`          | `u32` length, then UTF-8 / raw bytes               |
+//! | `None` / `Some(v)`             | `0x00` / `0x01` followed by `v`                    |
+//! | `()`, unit struct              | nothing                                            |
+//! | sequence, tuple, `Vec<u8>`     | `u32` count, then each element                     |
+//! | map, struct                    | `u32` count, then key/value pairs sorted by the encoded key |
+//! | unit enum variant              | the variant name as a string                       |
+//! | other enum variants            | a one-entry map from the variant name to its content |
+//!
+//! Struct fields are keyed by name, so fields skipped by `skip_serializing_if` are
+//! simply absent and adding an optional field does not change existing encodings.
+//! Floats are rejected.
+
+use crate::{EtherlinkError, Result};
+use serde::ser::{self, Serialize};
+use std::fmt;
+
+/// Canonical encoding of `value`
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut encoder = Encoder::default();
+    value.serialize(&mut encoder).map_err(|e| EtherlinkError::Codec(format!("Canonical encoding failed: {}", e.0)))?;
+    Ok(encoder.out)
+}
+
+#[derive(Debug)]
+struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+type EncodeResult = std::result::Result<(), Error>;
+
+#[derive(Default)]
+struct Encoder {
+    out: Vec<u8>,
+}
+
+impl Encoder {
+    fn length(&mut self, len: usize) -> EncodeResult {
+        let len = u32::try_from(len).map_err(|_| Error(format!("length {} exceeds u32", len)))?;
+        self.out.extend_from_slice(&len.to_be_bytes());
+        Ok(())
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> EncodeResult {
+        self.length(bytes.len())?;
+        self.out.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn encode<T: Serialize + ?Sized>(value: &T) -> std::result::Result<Vec<u8>, Error> {
+        let mut encoder = Encoder::default();
+        value.serialize(&mut encoder)?;
+        Ok(encoder.out)
+    }
+
+    /// Write sorted key/value pairs
+    fn entries(&mut self, mut entries: Vec<(Vec<u8>, Vec<u8>)>) -> EncodeResult {
+        entries.sort();
+        if entries.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(Error("duplicate map key".to_string()));
+        }
+        self.length(entries.len())?;
+        for (key, value) in entries {
+            self.out.extend_from_slice(&key);
+            self.out.extend_from_slice(&value);
+        }
+        Ok(())
+    }
+
+    /// Write a one-entry map from `variant` to already encoded content
+    fn variant(&mut self, variant: &str, content: Vec<u8>) -> EncodeResult {
+        self.entries(vec![(Encoder::encode(variant)?, content)])
+    }
+}
+
+macro_rules! fixed_width {
+    ($($method:ident: $ty:ty),*) => {
+        $(fn $method(self, v: $ty) -> EncodeResult {
+            self.out.extend_from_slice(&v.to_be_bytes());
+            Ok(())
+        })*
+    };
+}
+
+impl<'a> ser::Serializer for &'a mut Encoder {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = SeqEncoder<'a>;
+    type SerializeTuple = SeqEncoder<'a>;
+    type SerializeTupleStruct = SeqEncoder<'a>;
+    type SerializeTupleVariant = SeqEncoder<'a>;
+    type SerializeMap = MapEncoder<'a>;
+    type SerializeStruct = MapEncoder<'a>;
+    type SerializeStructVariant = MapEncoder<'a>;
+
+    fixed_width!(serialize_u8: u8, serialize_u16: u16, serialize_u32: u32, serialize_u64: u64, serialize_u128: u128,
+        serialize_i8: i8, serialize_i16: i16, serialize_i32: i32, serialize_i64: i64, serialize_i128: i128);
+
+    fn serialize_bool(self, v: bool) -> EncodeResult {
+        self.out.push(v as u8);
+        Ok(())
+    }
+
+    fn serialize_f32(self, _v: f32) -> EncodeResult {
+        Err(Error("floats have no canonical encoding".to_string()))
+    }
+
+    fn serialize_f64(self, _v: f64) -> EncodeResult {
+        Err(Error("floats have no canonical encoding".to_string()))
+    }
+
+    fn serialize_char(self, v: char) -> EncodeResult {
+        self.bytes(v.encode_utf8(&mut [0; 4]).as_bytes())
+    }
+
+    fn serialize_str(self, v: &str) -> EncodeResult {
+        self.bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> EncodeResult {
+        self.bytes(v)
+    }
+
+    fn serialize_none(self) -> EncodeResult {
+        self.out.push(0);
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> EncodeResult {
+        self.out.push(1);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> EncodeResult {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> EncodeResult {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> EncodeResult {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> EncodeResult {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> EncodeResult {
+        let content = Encoder::encode(value)?;
+        self.variant(variant, content)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> std::result::Result<SeqEncoder<'a>, Error> {
+        Ok(SeqEncoder { parent: self, items: Encoder::default(), count: 0, variant: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> std::result::Result<SeqEncoder<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> std::result::Result<SeqEncoder<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> std::result::Result<SeqEncoder<'a>, Error> {
+        Ok(SeqEncoder { parent: self, items: Encoder::default(), count: 0, variant: Some(variant) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> std::result::Result<MapEncoder<'a>, Error> {
+        Ok(MapEncoder { parent: self, entries: Vec::new(), key: None, variant: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> std::result::Result<MapEncoder<'a>, Error> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> std::result::Result<MapEncoder<'a>, Error> {
+        Ok(MapEncoder { parent: self, entries: Vec::new(), key: None, variant: Some(variant) })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Buffers elements so the count can be written first
+struct SeqEncoder<'a> {
+    parent: &'a mut Encoder,
+    items: Encoder,
+    count: usize,
+    variant: Option<&'static str>,
+}
+
+impl SeqEncoder<'_> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> EncodeResult {
+        self.count += 1;
+        value.serialize(&mut self.items)
+    }
+
+    fn finish(self) -> EncodeResult {
+        let mut content = Encoder::default();
+        content.length(self.count)?;
+        content.out.extend_from_slice(&self.items.out);
+        match self.variant {
+            Some(variant) => self.parent.variant(variant, content.out),
+            None => {
+                self.parent.out.extend_from_slice(&content.out);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ser::SerializeSeq for SeqEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> EncodeResult {
+        self.element(value)
+    }
+
+    fn end(self) -> EncodeResult {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> EncodeResult {
+        self.element(value)
+    }
+
+    fn end(self) -> EncodeResult {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> EncodeResult {
+        self.element(value)
+    }
+
+    fn end(self) -> EncodeResult {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> EncodeResult {
+        self.element(value)
+    }
+
+    fn end(self) -> EncodeResult {
+        self.finish()
+    }
+}
+
+/// Collects encoded entries so they can be sorted before writing
+struct MapEncoder<'a> {
+    parent: &'a mut Encoder,
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    key: Option<Vec<u8>>,
+    variant: Option<&'static str>,
+}
+
+impl MapEncoder<'_> {
+    fn field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> EncodeResult {
+        self.entries.push((Encoder::encode(key)?, Encoder::encode(value)?));
+        Ok(())
+    }
+
+    fn finish(self) -> EncodeResult {
+        match self.variant {
+            Some(variant) => {
+                let mut content = Encoder::default();
+                content.entries(self.entries)?;
+                self.parent.variant(variant, content.out)
+            }
+            None => self.parent.entries(self.entries),
+        }
+    }
+}
+
+impl ser::SerializeMap for MapEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> EncodeResult {
+        self.key = Some(Encoder::encode(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> EncodeResult {
+        let key = self.key.take().ok_or_else(|| Error("map value without a key".to_string()))?;
+        self.entries.push((key, Encoder::encode(value)?));
+        Ok(())
+    }
+
+    fn end(self) -> EncodeResult {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> EncodeResult {
+        self.field(key, value)
+    }
+
+    fn end(self) -> EncodeResult {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> EncodeResult {
+        self.field(key, value)
+    }
+
+    fn end(self) -> EncodeResult {
+        self.finish()
+    }
+}
@@ -0,0 +1,212 @@
+//! C ABI exported for Zig (GhostPlane) and other native callers
+//!
+//! [`crate::ffi`] covers calls from Rust into Zig; this module is the other direction.
+//! The header is generated with `cbindgen --config cbindgen.toml --output include/etherlink.h`.
+//!
+//! Ownership contract:
+//! - [`etherlink_new`] returns a handle that must be released with [`etherlink_free`].
+//! - Strings returned through `out_*` parameters are owned by the caller and must be
+//!   released with [`etherlink_string_free`]; never with the caller's own `free`.
+//! - Input strings are borrowed for the duration of the call only.
+//! - [`etherlink_last_error`] returns a pointer owned by the library, valid until the
+//!   next call on the same thread.
+//!
+//! Calls block the calling thread and must not be made from inside a Tokio runtime.
+
+use crate::clients::ServiceClients;
+use crate::clients::ghostd::Transaction;
+use crate::{Address, EtherlinkConfig, EtherlinkError, Result};
+use libc::c_char;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+use tokio::runtime::Runtime;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Result codes returned by every fallible C API function
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EtherlinkStatus {
+    Ok = 0,
+    /// A pointer was null or a string was not valid UTF-8 / JSON
+    InvalidArgument = 1,
+    /// The service could not be reached
+    Network = 2,
+    /// The service rejected the request
+    Api = 3,
+    Timeout = 4,
+    /// Any other failure, including a panic inside the library
+    Internal = 5,
+}
+
+impl From<&EtherlinkError> for EtherlinkStatus {
+    fn from(error: &EtherlinkError) -> Self {
+        match error {
+            EtherlinkError::Network(_) | EtherlinkError::Transport(_) => EtherlinkStatus::Network,
+            EtherlinkError::Api(_) | EtherlinkError::NotFound(_) | EtherlinkError::Status(_) | EtherlinkError::Unsupported(_) => EtherlinkStatus::Api,
+            EtherlinkError::Timeout(_) => EtherlinkStatus::Timeout,
+            EtherlinkError::Serialization(_) | EtherlinkError::Codec(_) | EtherlinkError::Ffi(_) | EtherlinkError::InvalidConfig(_) => EtherlinkStatus::InvalidArgument,
+            _ => EtherlinkStatus::Internal,
+        }
+    }
+}
+
+/// Opaque client handle
+pub struct EtherlinkHandle {
+    runtime: Runtime,
+    clients: ServiceClients,
+}
+
+fn set_last_error(message: impl Into<Vec<u8>>) {
+    let message = CString::new(message).unwrap_or_else(|_| c"error message contained a NUL byte".to_owned());
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+/// Run an API call, recording its error and converting panics into `Internal`
+fn guarded(call: impl FnOnce() -> Result<()>) -> EtherlinkStatus {
+    clear_last_error();
+    match catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(())) => EtherlinkStatus::Ok,
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            EtherlinkStatus::from(&e)
+        }
+        Err(_) => {
+            set_last_error("panic inside etherlink");
+            EtherlinkStatus::Internal
+        }
+    }
+}
+
+unsafe fn borrow_str<'a>(value: *const c_char, name: &str) -> Result<&'a str> {
+    if value.is_null() {
+        return Err(EtherlinkError::Ffi(format!("{} is null", name)));
+    }
+    unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .map_err(|e| EtherlinkError::Ffi(format!("{} is not valid UTF-8: {}", name, e)))
+}
+
+unsafe fn handle_ref<'a>(handle: *const EtherlinkHandle) -> Result<&'a EtherlinkHandle> {
+    unsafe { handle.as_ref() }.ok_or_else(|| EtherlinkError::Ffi("handle is null".to_string()))
+}
+
+unsafe fn write_string(out: *mut *mut c_char, value: String) -> Result<()> {
+    if out.is_null() {
+        return Err(EtherlinkError::Ffi("output pointer is null".to_string()));
+    }
+    let value = CString::new(value).map_err(|e| EtherlinkError::Ffi(format!("Invalid C string: {}", e)))?;
+    unsafe { *out = value.into_raw() };
+    Ok(())
+}
+
+/// Create a client handle
+///
+/// `config_json` is an `EtherlinkConfig` as JSON, or null for the defaults.
+/// Returns null on failure; see [`etherlink_last_error`].
+///
+/// # Safety
+/// `config_json` must be null or a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn etherlink_new(config_json: *const c_char) -> *mut EtherlinkHandle {
+    let mut handle = ptr::null_mut();
+    guarded(|| {
+        let config: EtherlinkConfig = if config_json.is_null() {
+            EtherlinkConfig::default()
+        } else {
+            serde_json::from_str(unsafe { borrow_str(config_json, "config_json") }?)?
+        };
+
+        config.validate()?;
+        let runtime = Runtime::new().map_err(|e| EtherlinkError::Configuration(format!("Failed to start runtime: {}", e)))?;
+        let clients = ServiceClients::from_config(&config)?;
+        handle = Box::into_raw(Box::new(EtherlinkHandle { runtime, clients }));
+        Ok(())
+    });
+    handle
+}
+
+/// Release a client handle; null is ignored
+///
+/// # Safety
+/// `handle` must come from [`etherlink_new`] and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn etherlink_free(handle: *mut EtherlinkHandle) {
+    if !handle.is_null() {
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Resolve a CNS domain, writing the resolution as JSON to `out_json`
+///
+/// # Safety
+/// `handle` must be live, `domain` a valid NUL-terminated string and `out_json` writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn etherlink_resolve_domain(handle: *const EtherlinkHandle, domain: *const c_char, out_json: *mut *mut c_char) -> EtherlinkStatus {
+    guarded(|| {
+        let handle = unsafe { handle_ref(handle) }?;
+        let domain = unsafe { borrow_str(domain, "domain") }?;
+
+        let resolution = handle.runtime.block_on(handle.clients.cns.resolve_domain(domain))?;
+        unsafe { write_string(out_json, serde_json::to_string(&resolution)?) }
+    })
+}
+
+/// Submit a signed transaction given as JSON, writing its hash to `out_hash`
+///
+/// # Safety
+/// `handle` must be live, `tx_json` a valid NUL-terminated string and `out_hash` writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn etherlink_submit_tx(handle: *const EtherlinkHandle, tx_json: *const c_char, out_hash: *mut *mut c_char) -> EtherlinkStatus {
+    guarded(|| {
+        let handle = unsafe { handle_ref(handle) }?;
+        let tx: Transaction = serde_json::from_str(unsafe { borrow_str(tx_json, "tx_json") }?)?;
+
+        let tx_hash = handle.runtime.block_on(handle.clients.ghostd.submit_transaction(tx))?;
+        unsafe { write_string(out_hash, tx_hash.0) }
+    })
+}
+
+/// Get the GCC balance of an address
+///
+/// # Safety
+/// `handle` must be live, `address` a valid NUL-terminated string and `out_balance` writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn etherlink_get_balance(handle: *const EtherlinkHandle, address: *const c_char, out_balance: *mut u64) -> EtherlinkStatus {
+    guarded(|| {
+        let handle = unsafe { handle_ref(handle) }?;
+        let address = Address::new(unsafe { borrow_str(address, "address") }?.to_string());
+        if out_balance.is_null() {
+            return Err(EtherlinkError::Ffi("out_balance is null".to_string()));
+        }
+
+        let balance = handle.runtime.block_on(handle.clients.ghostd.get_balance(&address))?;
+        unsafe { *out_balance = balance };
+        Ok(())
+    })
+}
+
+/// Release a string returned by this library; null is ignored
+///
+/// # Safety
+/// `value` must come from an `out_*` parameter of this library and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn etherlink_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(unsafe { CString::from_raw(value) });
+    }
+}
+
+/// Message of the last error on this thread, or null if the last call succeeded
+#[unsafe(no_mangle)]
+pub extern "C" fn etherlink_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
@@ -0,0 +1,369 @@
+//! Record and replay of REST and Zig bridge interactions
+//!
+//! A [`Cassette`] in record mode captures every REST request routed through its
+//! [`CassetteServer`] and every Zig bridge call, together with their responses;
+//! [`Cassette::save`] writes them to a JSON file. In replay mode the file serves the
+//! responses back with no network or Zig library involved, so tests can run offline
+//! and a user's failing session can be reproduced from the cassette they send in.
+//! gRPC channels are not captured.
+//!
+//! Requests match on their target and body. Identical requests receive their
+//! recorded responses in the order they were recorded.
+
+use crate::clients::build_http_client;
+use crate::{EtherlinkConfig, EtherlinkError, Result};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+const CASSETTE_VERSION: u32 = 1;
+
+/// Whether a cassette captures interactions or serves them back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    Record,
+    Replay,
+}
+
+/// Request or response body, kept readable when it is UTF-8
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Payload {
+    Text(String),
+    Hex(String),
+}
+
+impl Payload {
+    pub fn new(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Payload::Text(text.to_string()),
+            Err(_) => Payload::Hex(hex::encode(bytes)),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            Payload::Text(text) => Ok(text.as_bytes().to_vec()),
+            Payload::Hex(encoded) => hex::decode(encoded).map_err(|e| EtherlinkError::Codec(format!("Invalid hex payload in cassette: {}", e))),
+        }
+    }
+}
+
+/// One recorded request and its response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Interaction {
+    Http {
+        service: String,
+        method: String,
+        /// Path and query below the service endpoint
+        path: String,
+        request: Payload,
+        status: u16,
+        content_type: Option<String>,
+        response: Payload,
+    },
+    Ffi {
+        function: String,
+        request: Payload,
+        /// The call's result, or its error message
+        response: std::result::Result<Payload, String>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct CassetteFile {
+    version: u32,
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Default)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    /// Which interactions have been replayed
+    played: Vec<bool>,
+}
+
+/// Recorded interactions backed by a file
+#[derive(Debug)]
+pub struct Cassette {
+    mode: CassetteMode,
+    path: PathBuf,
+    tape: Mutex<Tape>,
+}
+
+impl Cassette {
+    /// Start an empty cassette that will be saved to `path`
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self { mode: CassetteMode::Record, path: path.into(), tape: Mutex::new(Tape::default()) }
+    }
+
+    /// Load a cassette to serve back
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let contents = std::fs::read(&path)
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to read cassette {}: {}", path.display(), e)))?;
+        let file: CassetteFile = serde_json::from_slice(&contents)?;
+        if file.version != CASSETTE_VERSION {
+            return Err(EtherlinkError::Unsupported(format!("Cassette version {}", file.version)));
+        }
+
+        let played = vec![false; file.interactions.len()];
+        Ok(Self {
+            mode: CassetteMode::Replay,
+            path,
+            tape: Mutex::new(Tape { interactions: file.interactions, played }),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Interactions recorded or loaded so far
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.tape.lock().unwrap().interactions.clone()
+    }
+
+    /// Number of loaded interactions not yet replayed
+    pub fn unplayed(&self) -> usize {
+        self.tape.lock().unwrap().played.iter().filter(|played| !**played).count()
+    }
+
+    /// Write the recorded interactions to the cassette file
+    pub fn save(&self) -> Result<()> {
+        let file = CassetteFile { version: CASSETTE_VERSION, interactions: self.interactions() };
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&file)?)
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to write cassette {}: {}", self.path.display(), e)))
+    }
+
+    fn push(&self, interaction: Interaction) {
+        let mut tape = self.tape.lock().unwrap();
+        tape.interactions.push(interaction);
+        tape.played.push(false);
+    }
+
+    /// First unplayed interaction accepted by `matches`, marked as played
+    fn take(&self, matches: impl Fn(&Interaction) -> bool) -> Option<Interaction> {
+        let mut tape = self.tape.lock().unwrap();
+        let Tape { interactions, played } = &mut *tape;
+        let index = interactions.iter().zip(played.iter()).position(|(interaction, played)| !played && matches(interaction))?;
+        played[index] = true;
+        Some(interactions[index].clone())
+    }
+
+    /// Run a bridge call through the cassette
+    ///
+    /// Recording performs `call` and keeps its result; replaying returns the recorded
+    /// result without performing it.
+    pub(crate) async fn ffi<Fut>(&self, function: &str, request: &[u8], call: Fut) -> Result<Vec<u8>>
+    where
+        Fut: Future<Output = Result<Vec<u8>>>,
+    {
+        let request = Payload::new(request);
+        match self.mode {
+            CassetteMode::Record => {
+                let result = call.await;
+                self.push(Interaction::Ffi {
+                    function: function.to_string(),
+                    request,
+                    response: result.as_ref().map(|bytes| Payload::new(bytes)).map_err(|e| e.to_string()),
+                });
+                result
+            }
+            CassetteMode::Replay => {
+                let recorded = self.take(|interaction| {
+                    matches!(interaction, Interaction::Ffi { function: f, request: r, .. } if f == function && *r == request)
+                });
+                match recorded {
+                    Some(Interaction::Ffi { response: Ok(payload), .. }) => payload.to_bytes(),
+                    Some(Interaction::Ffi { response: Err(message), .. }) => Err(EtherlinkError::Ffi(message)),
+                    _ => Err(EtherlinkError::Ffi(format!("No recorded response for bridge call {}", function))),
+                }
+            }
+        }
+    }
+
+    /// Route the REST endpoints in `config` through a local cassette server
+    ///
+    /// Endpoints are rewritten to point at the server, which forwards to the original
+    /// endpoints while recording, or answers from the cassette while replaying.
+    pub async fn serve(self: &Arc<Self>, config: &mut EtherlinkConfig) -> Result<CassetteServer> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| EtherlinkError::Network(format!("Failed to bind cassette server: {}", e)))?;
+        let addr = listener.local_addr().map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        let mut upstreams = HashMap::new();
+        upstreams.insert("ghostd".to_string(), config.ghostd_endpoint.trim_end_matches('/').to_string());
+        config.ghostd_endpoint = format!("http://{}/ghostd", addr);
+        if let Some(endpoint) = &config.cns_endpoint {
+            upstreams.insert("cns".to_string(), endpoint.trim_end_matches('/').to_string());
+            config.cns_endpoint = Some(format!("http://{}/cns", addr));
+        }
+
+        let proxy = Arc::new(CassetteProxy {
+            cassette: self.clone(),
+            upstreams,
+            http_client: build_http_client(config)?,
+        });
+        let make_service = make_service_fn(move |_| {
+            let proxy = proxy.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| proxy.clone().handle(request))) }
+        });
+
+        let incoming = hyper::server::conn::AddrIncoming::from_listener(listener)
+            .map_err(|e| EtherlinkError::Network(format!("Failed to start cassette server: {}", e)))?;
+        let (shutdown, stopped) = oneshot::channel();
+        let server = hyper::Server::builder(incoming)
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            });
+        let task = tokio::spawn(async move {
+            if let Err(e) = server.await {
+                warn!("Cassette server failed: {}", e);
+            }
+        });
+
+        debug!("Cassette server for {} listening on {}", self.path.display(), addr);
+        Ok(CassetteServer { addr, shutdown: Some(shutdown), task })
+    }
+}
+
+/// Local HTTP server recording or replaying REST traffic; stops when dropped
+#[derive(Debug)]
+pub struct CassetteServer {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl CassetteServer {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop accepting requests and wait for in-flight ones to finish
+    pub async fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for CassetteServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+struct CassetteProxy {
+    cassette: Arc<Cassette>,
+    /// Original endpoint per service path prefix
+    upstreams: HashMap<String, String>,
+    http_client: reqwest::Client,
+}
+
+impl CassetteProxy {
+    async fn handle(self: Arc<Self>, request: Request<Body>) -> std::result::Result<Response<Body>, Infallible> {
+        let response = match self.exchange(request).await {
+            Ok(response) => response,
+            Err(e) => error_response(StatusCode::BAD_GATEWAY, &e.to_string()),
+        };
+        Ok(response)
+    }
+
+    async fn exchange(&self, request: Request<Body>) -> Result<Response<Body>> {
+        let path_and_query = request.uri().path_and_query().map_or("/", |p| p.as_str()).to_string();
+        let (service, path) = match path_and_query.trim_start_matches('/').split_once('/') {
+            Some((service, rest)) => (service.to_string(), format!("/{}", rest)),
+            None => (path_and_query.trim_start_matches('/').to_string(), "/".to_string()),
+        };
+        let method = request.method().clone();
+        let headers = request.headers().clone();
+        let body = hyper::body::to_bytes(request.into_body())
+            .await
+            .map_err(|e| EtherlinkError::Network(format!("Failed to read request body: {}", e)))?;
+        let payload = Payload::new(&body);
+
+        if self.cassette.mode == CassetteMode::Replay {
+            let recorded = self.cassette.take(|interaction| {
+                matches!(interaction, Interaction::Http { service: s, method: m, path: p, request: r, .. }
+                    if *s == service && *m == method.as_str() && *p == path && *r == payload)
+            });
+            return match recorded {
+                Some(Interaction::Http { status, content_type, response, .. }) => build_response(status, content_type.as_deref(), response.to_bytes()?),
+                _ => Ok(error_response(
+                    StatusCode::NOT_IMPLEMENTED,
+                    &format!("No recorded response for {} /{}{}", method, service, path),
+                )),
+            };
+        }
+
+        let upstream = self
+            .upstreams
+            .get(&service)
+            .ok_or_else(|| EtherlinkError::Configuration(format!("Unknown service in cassette request: {}", service)))?;
+        let mut forward = self.http_client.request(method.clone(), format!("{}{}", upstream, path)).body(body);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != hyper::header::HOST) {
+            forward = forward.header(name, value);
+        }
+        let response = forward.send().await.map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let bytes = response.bytes().await.map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        self.cassette.push(Interaction::Http {
+            service,
+            method: method.to_string(),
+            path,
+            request: payload,
+            status,
+            content_type: content_type.clone(),
+            response: Payload::new(&bytes),
+        });
+        build_response(status, content_type.as_deref(), bytes.to_vec())
+    }
+}
+
+fn build_response(status: u16, content_type: Option<&str>, body: Vec<u8>) -> Result<Response<Body>> {
+    let mut builder = Response::builder().status(status);
+    if let Some(content_type) = content_type {
+        builder = builder.header(hyper::header::CONTENT_TYPE, content_type);
+    }
+    builder
+        .body(Body::from(body))
+        .map_err(|e| EtherlinkError::Network(format!("Invalid recorded response: {}", e)))
+}
+
+/// Error in the services' `ApiResponse` shape, so clients surface the message
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let body = serde_json::json!({ "success": false, "data": null, "error": message });
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static("application/json"));
+    response
+}
@@ -1,7 +1,8 @@
 use crate::{EtherlinkConfig, EtherlinkError, Result, ConnectionStatus, HealthStatus};
+use crate::transport::{ChannelConfig, ChannelManager};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tonic::transport::{Channel, Endpoint};
+use tonic::transport::Channel;
 use tracing::{info, warn, error};
 
 /// Main Etherlink client for communicating with GhostChain services
@@ -9,15 +10,23 @@ use tracing::{info, warn, error};
 pub struct EtherlinkClient {
     config: EtherlinkConfig,
     channel: Option<Channel>,
+    channels: ChannelManager,
     status: Arc<RwLock<ConnectionStatus>>,
 }
 
 impl EtherlinkClient {
     /// Create a new Etherlink client with the given configuration
     pub fn new(config: EtherlinkConfig) -> Self {
+        let channels = ChannelManager::new(ChannelConfig::from(&config));
+        Self::with_channel_manager(config, channels)
+    }
+
+    /// Create a new Etherlink client that shares channels from an existing manager
+    pub fn with_channel_manager(config: EtherlinkConfig, channels: ChannelManager) -> Self {
         Self {
             config,
             channel: None,
+            channels,
             status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
         }
     }
@@ -36,18 +45,7 @@ impl EtherlinkClient {
             *status = ConnectionStatus::Connecting;
         }
 
-        let endpoint = if self.config.enable_tls {
-            Endpoint::from_shared(self.config.ghostd_endpoint.clone())?
-                .tls_config(tonic::transport::ClientTlsConfig::new())?
-        } else {
-            Endpoint::from_shared(self.config.ghostd_endpoint.clone())?
-        };
-
-        let endpoint = endpoint
-            .timeout(std::time::Duration::from_millis(self.config.timeout_ms))
-            .tcp_keepalive(Some(std::time::Duration::from_secs(30)));
-
-        match endpoint.connect().await {
+        match self.channels.connect(&self.config.ghostd_endpoint).await {
             Ok(channel) => {
                 self.channel = Some(channel);
                 let mut status = self.status.write().await;
@@ -59,7 +57,7 @@ impl EtherlinkClient {
                 let mut status = self.status.write().await;
                 *status = ConnectionStatus::Error(e.to_string());
                 error!("Failed to connect to GhostChain: {}", e);
-                Err(EtherlinkError::Transport(e))
+                Err(e)
             }
         }
     }
@@ -82,6 +80,11 @@ impl EtherlinkClient {
         matches!(*self.status.read().await, ConnectionStatus::Connected)
     }
 
+    /// Get the channel manager shared with other gRPC clients
+    pub fn channel_manager(&self) -> &ChannelManager {
+        &self.channels
+    }
+
     /// Get the gRPC channel (internal use)
     pub(crate) fn channel(&self) -> Result<Channel> {
         self.channel
@@ -180,6 +183,51 @@ impl EtherlinkClientBuilder {
         self
     }
 
+    pub fn proxy(mut self, proxy: crate::transport::ProxyConfig) -> Self {
+        self.config.proxy = Some(proxy);
+        self
+    }
+
+    pub fn confirmations(mut self, confirmations: crate::confirmation::ConfirmationPolicies) -> Self {
+        self.config.confirmations = confirmations;
+        self
+    }
+
+    /// Route private submissions through this relay
+    pub fn private_relay(mut self, relay: crate::clients::ghostd::PrivateRelayConfig) -> Self {
+        self.config.private_relay = Some(relay);
+        self
+    }
+
+    /// Simulate transactions with GHOSTD before sending them, rejecting predicted reverts
+    pub fn simulate_before_send(mut self, enabled: bool) -> Self {
+        self.config.simulate_before_send = enabled;
+        self
+    }
+
+    /// How rejected submissions are resubmitted; [`crate::ResubmitPolicy::disabled`] turns it off
+    pub fn resubmit(mut self, policy: crate::resubmit::ResubmitPolicy) -> Self {
+        self.config.resubmit = policy;
+        self
+    }
+
+    /// gRPC endpoint for services set to [`crate::ServiceTransport::Grpc`]
+    pub fn grpc_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.config.grpc_endpoint = Some(endpoint.into());
+        self
+    }
+
+    pub fn service_transports(mut self, transports: crate::clients::ServiceTransports) -> Self {
+        self.config.service_transports = transports;
+        self
+    }
+
+    /// Pinning service for IPFS content referenced by domains
+    pub fn pinning(mut self, pinning: crate::clients::PinningConfig) -> Self {
+        self.config.pinning = Some(pinning);
+        self
+    }
+
     pub fn build(self) -> EtherlinkClient {
         EtherlinkClient::new(self.config)
     }
@@ -1,7 +1,11 @@
 //! CNS (Crypto Name Server) client implementation
 
-use crate::{Result, EtherlinkConfig, EtherlinkError, Address, TxHash};
+use crate::{Result, EtherlinkConfig, EtherlinkError, Address, TxHash, BlockHeight};
 use crate::clients::{ServiceClient, ApiResponse};
+use crate::clients::context::{CallContext, WithContext};
+use crate::auth::Permission;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::pagination::{PageConfig, PageStream};
 use reqwest::Client as HttpClient;
 use serde::{Serialize, Deserialize};
 use std::sync::Arc;
@@ -12,6 +16,7 @@ use std::collections::HashMap;
 pub struct CnsClient {
     base_url: String,
     http_client: Arc<HttpClient>,
+    context: CallContext,
 }
 
 impl CnsClient {
@@ -25,15 +30,34 @@ impl CnsClient {
         Self {
             base_url,
             http_client,
+            context: CallContext::default(),
         }
     }
 
+    /// Client for calls with a different endpoint, auth token or timeout
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            base_url: context.base_url().unwrap_or_else(|| self.base_url.clone()),
+            http_client: self.http_client.clone(),
+            context,
+        }
+    }
+
+    /// Client reading domain resolution and ownership as of block `height`
+    ///
+    /// Only honored by CNS deployments that keep historical ownership.
+    pub fn at_block(&self, height: BlockHeight) -> Self {
+        self.with_context(self.context.clone().at_block(height))
+    }
+
     /// Resolve a domain to get its information
     pub async fn resolve_domain(&self, domain: &str) -> Result<DomainResolution> {
+        self.context.require(&[Permission::ReadDomains])?;
         let url = format!("{}/domains/resolve/{}", self.base_url, domain);
         let response: ApiResponse<DomainResolution> = self.http_client
             .get(&url)
-            .send()
+            .with_block(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -45,11 +69,13 @@ impl CnsClient {
 
     /// Register a new domain
     pub async fn register_domain(&self, registration: DomainRegistration) -> Result<TxHash> {
+        self.context.require(&[Permission::RegisterDomain])?;
         let url = format!("{}/domains/register", self.base_url);
         let response: ApiResponse<RegistrationResponse> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&registration)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -62,11 +88,13 @@ impl CnsClient {
 
     /// Update domain records
     pub async fn update_domain_records(&self, domain: &str, records: DomainRecords) -> Result<TxHash> {
+        self.context.require(&[Permission::UpdateDomain])?;
         let url = format!("{}/domains/{}/records", self.base_url, domain);
         let response: ApiResponse<RegistrationResponse> = self.http_client
             .put(&url)
+            .with_context(&self.context)
             .json(&records)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -79,10 +107,12 @@ impl CnsClient {
 
     /// Get domain ownership information
     pub async fn get_domain_info(&self, domain: &str) -> Result<DomainInfo> {
+        self.context.require(&[Permission::ReadDomains])?;
         let url = format!("{}/domains/{}", self.base_url, domain);
         let response: ApiResponse<DomainInfo> = self.http_client
             .get(&url)
-            .send()
+            .with_block(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -94,10 +124,12 @@ impl CnsClient {
 
     /// Get domains owned by an address
     pub async fn get_domains_by_owner(&self, address: &Address) -> Result<Vec<String>> {
+        self.context.require(&[Permission::ReadDomains])?;
         let url = format!("{}/domains/owner/{}", self.base_url, address.as_str());
         let response: ApiResponse<DomainsResponse> = self.http_client
             .get(&url)
-            .send()
+            .with_block(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -108,12 +140,60 @@ impl CnsClient {
         Ok(domains_response.domains)
     }
 
+    /// Get domains whose records point to an address, which need not own them
+    pub async fn reverse_resolve(&self, address: &Address) -> Result<Vec<String>> {
+        self.context.require(&[Permission::ReadDomains])?;
+        let url = format!("{}/domains/reverse/{}", self.base_url, address.as_str());
+        let response: ApiResponse<ReverseResolveResponse> = self.http_client
+            .get(&url)
+            .with_block(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        Ok(response.into_result()?.domains)
+    }
+
+    /// Stream every domain owned by an address, fetching pages as they are consumed
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn stream_domains_by_owner(&self, address: &Address, config: PageConfig) -> PageStream<String> {
+        let client = self.clone();
+        let address = address.clone();
+        PageStream::new(0, config, move |offset, limit| {
+            let client = client.clone();
+            let address = address.clone();
+            async move { client.get_domains_by_owner_page(&address, offset, limit).await }
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn get_domains_by_owner_page(&self, address: &Address, offset: u64, limit: u32) -> Result<Vec<String>> {
+        self.context.require(&[Permission::ReadDomains])?;
+        let url = format!("{}/domains/owner/{}?offset={}&limit={}", self.base_url, address.as_str(), offset, limit);
+        let response: ApiResponse<DomainsResponse> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        Ok(response.into_result()?.domains)
+    }
+
     /// Check if a domain is available for registration
     pub async fn check_domain_availability(&self, domain: &str) -> Result<bool> {
+        self.context.require(&[Permission::ReadDomains])?;
         let url = format!("{}/domains/available/{}", self.base_url, domain);
         let response: ApiResponse<AvailabilityResponse> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -126,10 +206,12 @@ impl CnsClient {
 
     /// Get supported TLDs and their pricing
     pub async fn get_supported_tlds(&self) -> Result<Vec<TldInfo>> {
+        self.context.require(&[Permission::ReadDomains])?;
         let url = format!("{}/domains/tlds", self.base_url);
         let response: ApiResponse<Vec<TldInfo>> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -141,10 +223,99 @@ impl CnsClient {
 
     /// Bridge resolution (ENS, Unstoppable, etc.)
     pub async fn bridge_resolve(&self, domain: &str, bridge_type: BridgeType) -> Result<DomainResolution> {
+        self.context.require(&[Permission::ReadDomains])?;
         let url = format!("{}/bridge/{:?}/resolve/{}", self.base_url, bridge_type, domain);
         let response: ApiResponse<DomainResolution> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Put a domain up for sale at a fixed price
+    ///
+    /// Buyers can still make lower offers; see [`crate::clients::DomainMarketplace`]
+    /// for offers backed by escrowed funds.
+    pub async fn list_domain_for_sale(&self, listing: NewListing) -> Result<DomainListing> {
+        self.context.require(&[Permission::UpdateDomain])?;
+        if listing.price == 0 {
+            return Err(EtherlinkError::Configuration("Listing price must be greater than zero".to_string()));
+        }
+        let url = format!("{}/marketplace/listings", self.base_url);
+        self.marketplace_post(&url, &listing).await
+    }
+
+    /// Take a domain off the market; open offers are rejected and their escrows refunded
+    pub async fn cancel_listing(&self, listing_id: &str) -> Result<DomainListing> {
+        self.context.require(&[Permission::UpdateDomain])?;
+        let url = format!("{}/marketplace/listings/{}/cancel", self.base_url, listing_id);
+        self.marketplace_post(&url, &()).await
+    }
+
+    /// Browse active listings matching `filter`
+    pub async fn get_listings(&self, filter: &ListingFilter) -> Result<Vec<DomainListing>> {
+        self.context.require(&[Permission::ReadDomains])?;
+        let url = format!("{}/marketplace/listings", self.base_url);
+        let response: ApiResponse<Vec<DomainListing>> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .query(filter)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Submit an offer whose funds are already held in a GLEDGER escrow
+    pub async fn make_offer(&self, offer: NewOffer) -> Result<DomainOffer> {
+        self.context.require(&[Permission::SubmitTransaction])?;
+        let url = format!("{}/marketplace/offers", self.base_url);
+        self.marketplace_post(&url, &offer).await
+    }
+
+    /// Get every offer made on a domain, newest first
+    pub async fn get_offers(&self, domain: &str) -> Result<Vec<DomainOffer>> {
+        self.context.require(&[Permission::ReadDomains])?;
+        let url = format!("{}/marketplace/domains/{}/offers", self.base_url, domain);
+        let response: ApiResponse<Vec<DomainOffer>> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Accept an offer as the domain owner
+    ///
+    /// CNS transfers the domain to the buyer and releases the offer's escrow to the
+    /// seller in one step, so neither side can walk away half-way.
+    pub async fn accept_offer(&self, offer_id: &str) -> Result<DomainSale> {
+        self.context.require(&[Permission::UpdateDomain])?;
+        let url = format!("{}/marketplace/offers/{}/accept", self.base_url, offer_id);
+        self.marketplace_post(&url, &()).await
+    }
+
+    async fn marketplace_post<B: Serialize, T: serde::de::DeserializeOwned>(&self, url: &str, body: &B) -> Result<T> {
+        let response: ApiResponse<T> = self.http_client
+            .post(url)
+            .with_context(&self.context)
+            .json(body)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -155,7 +326,8 @@ impl CnsClient {
     }
 }
 
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl ServiceClient for CnsClient {
     fn service_name(&self) -> &'static str {
         "cns"
@@ -169,7 +341,8 @@ impl ServiceClient for CnsClient {
         let url = format!("{}/health", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -183,7 +356,8 @@ impl ServiceClient for CnsClient {
         let url = format!("{}/status", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -216,6 +390,13 @@ pub struct DomainRecords {
     pub description: Option<String>,
 }
 
+impl DomainRecords {
+    /// Bytes the owner signs to update `domain` to these records
+    pub fn signing_payload(&self, domain: &str) -> Vec<u8> {
+        crate::canonical::to_bytes(&(domain, self)).unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DomainResolution {
     pub domain: String,
@@ -252,6 +433,12 @@ pub struct DomainsResponse {
     pub total_count: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReverseResolveResponse {
+    pub address: String,
+    pub domains: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AvailabilityResponse {
     pub domain: String,
@@ -277,4 +464,126 @@ pub enum BridgeType {
     Unstoppable, // .crypto, .nft, .x domains
     Web5,        // did: identifiers
     Handshake,   // .hns domains
-}
\ No newline at end of file
+}
+/// A domain put up for sale
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewListing {
+    pub domain: String,
+    pub seller: Address,
+    pub price: u64,
+    pub token: crate::TokenType,
+    /// Unix seconds after which the listing lapses; `None` lists until cancelled
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainListing {
+    pub listing_id: String,
+    pub domain: String,
+    pub seller: Address,
+    pub price: u64,
+    pub token: crate::TokenType,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+}
+
+/// Which listings [`CnsClient::get_listings`] returns; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListingFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tld: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seller: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<crate::TokenType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_price: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_price: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+impl ListingFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tld(mut self, tld: impl Into<String>) -> Self {
+        self.tld = Some(tld.into());
+        self
+    }
+
+    pub fn seller(mut self, seller: Address) -> Self {
+        self.seller = Some(seller);
+        self
+    }
+
+    pub fn token(mut self, token: crate::TokenType) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Only listings priced within `min..=max`
+    pub fn price_range(mut self, min: u64, max: u64) -> Self {
+        self.min_price = Some(min);
+        self.max_price = Some(max);
+        self
+    }
+
+    pub fn page(mut self, offset: u32, limit: u32) -> Self {
+        self.offset = Some(offset);
+        self.limit = Some(limit);
+        self
+    }
+}
+
+/// An offer backed by funds locked with [`crate::clients::GledgerClient::lock_escrow`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewOffer {
+    pub domain: String,
+    pub buyer: Address,
+    pub amount: u64,
+    pub token: crate::TokenType,
+    pub escrow_id: String,
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferStatus {
+    Open,
+    Accepted,
+    Rejected,
+    Withdrawn,
+    Expired,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainOffer {
+    pub offer_id: String,
+    pub domain: String,
+    pub buyer: Address,
+    pub amount: u64,
+    pub token: crate::TokenType,
+    pub escrow_id: String,
+    pub status: OfferStatus,
+    pub expires_at: Option<u64>,
+}
+
+/// A completed domain sale
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainSale {
+    pub domain: String,
+    pub seller: Address,
+    pub buyer: Address,
+    pub price: u64,
+    pub token: crate::TokenType,
+    pub offer_id: String,
+    /// Transfer of the domain to the buyer
+    pub transfer_tx_hash: TxHash,
+    /// Release of the escrowed payment to the seller
+    pub payment_tx_hash: TxHash,
+}
@@ -0,0 +1,264 @@
+//! Per-call overrides for service clients
+//!
+//! Multi-tenant gateways route each request to a different backend or on behalf of a
+//! different caller. Instead of building a client per tenant, derive one for the call:
+//!
+//! ```no_run
+//! # async fn example(clients: &etherlink::ServiceClients) -> etherlink::Result<()> {
+//! use etherlink::clients::CallContext;
+//! use std::time::Duration;
+//!
+//! let ctx = CallContext::new()
+//!     .endpoint("https://tenant-a.ghostchain.org:8545")
+//!     .auth_token("tenant-a-token")
+//!     .timeout(Duration::from_secs(2));
+//! let height = clients.ghostd.with_context(ctx).get_blockchain_height().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{BlockHeight, EtherlinkError, Result};
+use crate::auth::{AuthToken, Permission};
+use crate::correlation::{self, CORRELATION_HEADER, CorrelationId};
+use crate::diagnostics::{self, HTTP_LOG_TARGET, RequestRecord};
+use crate::timesync;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::accounting;
+use reqwest::{RequestBuilder, Response};
+use std::fmt;
+use std::time::Duration;
+
+/// Endpoint, credentials and timeout overriding a client's defaults
+#[derive(Clone, Default)]
+pub struct CallContext {
+    /// Service endpoint, e.g. `https://ghostd.example:8545`; `/api/v1` is appended
+    pub endpoint: Option<String>,
+    /// Sent as `Authorization: Bearer <token>`
+    pub auth_token: Option<String>,
+    /// Total request timeout (ignored in the browser)
+    pub timeout: Option<Duration>,
+    /// Read state as of this block; honored by reads that support history, see `at_block`
+    pub block: Option<BlockHeight>,
+    /// Permissions the token holds; calls needing others fail before any request is sent.
+    /// `None` (unknown) leaves enforcement to the service.
+    pub permissions: Option<Vec<Permission>>,
+    /// Sent as the `x-correlation-id` header; defaults to the ID of the operation
+    /// the call runs in, see [`correlation::scope`]
+    pub correlation_id: Option<CorrelationId>,
+}
+
+impl CallContext {
+    /// Create an empty context that changes nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send the call to another endpoint
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Authenticate the call with a bearer token
+    pub fn auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(token.into());
+        self
+    }
+
+    /// Authenticate with a Guardian token and check its permissions before each call
+    pub fn token(mut self, token: &AuthToken) -> Self {
+        self.auth_token = Some(token.token_id.clone());
+        self.permissions = Some(token.permissions.clone());
+        self
+    }
+
+    /// Declare the permissions held by the bearer token
+    pub fn permissions(mut self, permissions: Vec<Permission>) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Bound the call by a different timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Read historical state as of `height`
+    pub fn at_block(mut self, height: BlockHeight) -> Self {
+        self.block = Some(height);
+        self
+    }
+
+    /// Trace the call as part of operation `id`
+    pub fn correlation_id(mut self, id: CorrelationId) -> Self {
+        self.correlation_id = Some(id);
+        self
+    }
+
+    /// Fail fast with [`EtherlinkError::PermissionDenied`] unless the held permissions cover `required`
+    ///
+    /// [`Permission::Admin`] covers everything.
+    pub(crate) fn require(&self, required: &[Permission]) -> Result<()> {
+        let Some(held) = &self.permissions else {
+            return Ok(());
+        };
+        if held.contains(&Permission::Admin) {
+            return Ok(());
+        }
+        let missing: Vec<Permission> = required.iter().filter(|p| !held.contains(p)).cloned().collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(EtherlinkError::PermissionDenied { required: missing })
+        }
+    }
+
+    /// Correlation ID the call is sent with: the context's, else the current operation's
+    pub(crate) fn resolved_correlation_id(&self) -> Option<CorrelationId> {
+        self.correlation_id.clone().or_else(correlation::current)
+    }
+
+    /// API base URL for the overridden endpoint, if any
+    pub(crate) fn base_url(&self) -> Option<String> {
+        self.endpoint.as_ref().map(|endpoint| format!("{}/api/v1", endpoint.trim_end_matches('/')))
+    }
+}
+
+impl fmt::Debug for CallContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallContext")
+            .field("endpoint", &self.endpoint)
+            .field("auth_token", &self.auth_token.as_ref().map(|_| "<redacted>"))
+            .field("timeout", &self.timeout)
+            .field("block", &self.block)
+            .field("permissions", &self.permissions)
+            .field("correlation_id", &self.correlation_id)
+            .finish()
+    }
+}
+
+/// Applies a [`CallContext`] to an outgoing request
+pub(crate) trait WithContext {
+    fn with_context(self, context: &CallContext) -> Self;
+
+    /// Also pin a read to the context's block, as `?block=<height>`
+    fn with_block(self, context: &CallContext) -> Self;
+
+    /// Send the request, logging its status and latency, recording it for any active
+    /// [`diagnostics::DiagnosticCapture`], sampling its `Date` header for any
+    /// [`timesync::SkewEstimator`] watching the service and, outside the browser,
+    /// accounting it in any [`crate::transport::BandwidthAccounting`] routing the service
+    fn send_logged(self) -> SendFuture;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) type SendFuture = futures::future::BoxFuture<'static, reqwest::Result<Response>>;
+#[cfg(target_arch = "wasm32")]
+pub(crate) type SendFuture = futures::future::LocalBoxFuture<'static, reqwest::Result<Response>>;
+
+impl WithContext for RequestBuilder {
+    fn with_context(self, context: &CallContext) -> Self {
+        let mut request = self;
+        if let Some(token) = &context.auth_token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(id) = context.resolved_correlation_id() {
+            request = request.header(CORRELATION_HEADER, id.as_str());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = context.timeout {
+            request = request.timeout(timeout);
+        }
+        request
+    }
+
+    fn with_block(self, context: &CallContext) -> Self {
+        match context.block {
+            Some(height) => self.with_context(context).query(&[("block", height)]),
+            None => self.with_context(context),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn send_logged(self) -> SendFuture {
+        let (client, request) = self.build_split();
+        Box::pin(async move {
+            let request = request?;
+            let (method, url, correlation_id) = describe(&request);
+            let ledgers = accounting::acquire_routed(request.url().as_str()).await;
+            let sent = request.body().and_then(|body| body.as_bytes()).map_or(0, |body| body.len() as u64);
+            let started_at_ms = diagnostics::now_millis();
+            let result = client.execute(request).await;
+            let latency_ms = diagnostics::now_millis().saturating_sub(started_at_ms);
+
+            for (accounting, service) in ledgers {
+                match &result {
+                    // The body is still unread, so count what the response declares
+                    Ok(response) => accounting.record(
+                        &service,
+                        sent,
+                        response.content_length().unwrap_or(0),
+                        response.status().is_success(),
+                    ),
+                    Err(_) => accounting.record(&service, sent, 0, false),
+                }
+            }
+
+            observe(&result, method, url, correlation_id, started_at_ms, latency_ms);
+            result
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn send_logged(self) -> SendFuture {
+        // The browser builder cannot be split from its client, so describe a copy
+        let described = self.try_clone().and_then(|copy| copy.build().ok());
+        let (method, url, correlation_id) = described.as_ref().map(describe).unwrap_or_default();
+        Box::pin(async move {
+            let started_at_ms = diagnostics::now_millis();
+            let result = self.send().await;
+            let latency_ms = diagnostics::now_millis().saturating_sub(started_at_ms);
+            observe(&result, method, url, correlation_id, started_at_ms, latency_ms);
+            result
+        })
+    }
+}
+
+/// Method, redacted URL and correlation ID of an outgoing request, for logging
+fn describe(request: &reqwest::Request) -> (String, String, String) {
+    let correlation_id = request
+        .headers()
+        .get(CORRELATION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    (request.method().to_string(), diagnostics::redact_url(request.url().as_str()), correlation_id)
+}
+
+/// Log a finished request, sample its `Date` header and record it for any capture
+fn observe(result: &reqwest::Result<Response>, method: String, url: String, correlation_id: String, started_at_ms: u64, latency_ms: u64) {
+    if let Ok(response) = result
+        && timesync::is_sampling()
+        && let Some(date) = response.headers().get(reqwest::header::DATE).and_then(|value| value.to_str().ok())
+    {
+        timesync::observe_date_header(response.url().as_str(), date, started_at_ms, started_at_ms + latency_ms);
+    }
+
+    match result {
+        Ok(response) => {
+            tracing::debug!(target: HTTP_LOG_TARGET, %method, %url, %correlation_id, status = response.status().as_u16(), latency_ms, "request completed")
+        }
+        Err(e) => tracing::debug!(target: HTTP_LOG_TARGET, %method, %url, %correlation_id, latency_ms, error = %diagnostics::redact_text(&e.to_string()), "request failed"),
+    }
+    if diagnostics::is_capturing() {
+        diagnostics::record(RequestRecord {
+            started_at_ms,
+            method,
+            url,
+            status: result.as_ref().ok().map(|response| response.status().as_u16()),
+            latency_ms,
+            error: result.as_ref().err().map(|e| diagnostics::redact_text(&e.to_string())),
+        });
+    }
+}
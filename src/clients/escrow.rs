@@ -0,0 +1,350 @@
+//! Two-of-three escrow payments over GLEDGER and GSIG
+//!
+//! The buyer's funds are locked in a GLEDGER escrow whose release policy names the
+//! buyer, the seller and an arbiter. Paying out either way, released to the seller
+//! or refunded to the buyer, takes approvals from any two of them. Each approval signs
+//! [`EscrowAgreement::approval_payload`] for the outcome, GSIG combines two into a
+//! threshold signature, and GLEDGER settles the escrow only against it. Approvals are
+//! checked locally first, so a bad one fails before anything is sent.
+//!
+//! An [`EscrowAgreement`] records where the escrow stands and is updated by every
+//! step; persist it between them. Past `expires_at` the buyer can also take the
+//! funds back alone with [`Escrow::refund_expired`]; an escrow without one can only
+//! be settled by approval.
+//!
+//! Keys are compared and sent without a `0x` prefix and in lowercase, and addresses
+//! compared case-insensitively, so one account cannot fill two roles by spelling.
+
+use crate::{Result, EtherlinkError, Address, CryptoAlgorithm, CryptoProvider, KeyPair, TokenType, TxHash};
+use crate::clients::gledger::{EscrowLock, EscrowOutcome, EscrowPolicy, EscrowSettlement, GledgerClient};
+use crate::clients::gsig::{GsigClient, ThresholdParticipant, ThresholdSignatureRequest};
+use crate::clients::walletd;
+use crate::clock::{self, SharedClock};
+use crate::validation::{ConfigErrors, Validator};
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+
+/// Approvals needed to settle an escrow
+pub const ESCROW_THRESHOLD: u32 = 2;
+
+/// Buyer, seller and arbiter of an escrow, and what is held
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowTerms {
+    pub buyer: EscrowParty,
+    pub seller: EscrowParty,
+    pub arbiter: EscrowParty,
+    /// Algorithm of all three approval keys
+    pub algorithm: CryptoAlgorithm,
+    pub token_type: TokenType,
+    pub amount: u64,
+    /// What the escrow pays for, shown on the GLEDGER lock
+    pub reference: String,
+    /// Unix seconds after which the buyer may refund without approvals
+    pub expires_at: Option<u64>,
+}
+
+impl EscrowTerms {
+    /// Check every field, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.amount > 0, "amount", "must be greater than zero");
+        v.check(!self.reference.trim().is_empty(), "reference", "must not be empty");
+        for (role, party) in self.parties() {
+            let key = hex::decode(party.key_hex());
+            v.check(key.is_ok_and(|key| !key.is_empty()), &format!("{}.public_key", role), "must be a hex-encoded public key");
+        }
+        let addresses: HashSet<String> = self.parties().iter().map(|(_, party)| party.address.as_str().to_ascii_lowercase()).collect();
+        v.check(addresses.len() == 3, "arbiter.address", "buyer, seller and arbiter must be different accounts");
+        let keys: HashSet<String> = self.parties().iter().map(|(_, party)| party.key_hex()).collect();
+        v.check(keys.len() == 3, "arbiter.public_key", "buyer, seller and arbiter must use different keys");
+        v.finish()
+    }
+
+    pub fn party(&self, role: EscrowRole) -> &EscrowParty {
+        match role {
+            EscrowRole::Buyer => &self.buyer,
+            EscrowRole::Seller => &self.seller,
+            EscrowRole::Arbiter => &self.arbiter,
+        }
+    }
+
+    fn parties(&self) -> [(EscrowRole, &EscrowParty); 3] {
+        [(EscrowRole::Buyer, &self.buyer), (EscrowRole::Seller, &self.seller), (EscrowRole::Arbiter, &self.arbiter)]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowParty {
+    pub address: Address,
+    /// Hex key the party approves outcomes with
+    pub public_key: String,
+}
+
+impl EscrowParty {
+    /// `public_key` in lowercase without a `0x` prefix, as it is compared and sent
+    pub fn key_hex(&self) -> String {
+        self.public_key.trim_start_matches("0x").to_ascii_lowercase()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowRole {
+    Buyer,
+    Seller,
+    Arbiter,
+}
+
+impl std::fmt::Display for EscrowRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EscrowRole::Buyer => "buyer",
+            EscrowRole::Seller => "seller",
+            EscrowRole::Arbiter => "arbiter",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowState {
+    /// Agreed, not funded yet
+    Created,
+    /// Funds are locked on GLEDGER
+    Funded,
+    /// Paid to the seller
+    Released,
+    /// Returned to the buyer
+    Refunded,
+}
+
+/// An escrow and how far it has got
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowAgreement {
+    pub id: String,
+    pub terms: EscrowTerms,
+    pub state: EscrowState,
+    /// GLEDGER escrow holding the funds, once funded
+    pub escrow_id: Option<String>,
+    pub funding_tx: Option<TxHash>,
+    pub settlement_tx: Option<TxHash>,
+}
+
+impl EscrowAgreement {
+    /// Account paid by `outcome`
+    pub fn recipient(&self, outcome: EscrowOutcome) -> &Address {
+        match outcome {
+            EscrowOutcome::Release => &self.terms.seller.address,
+            EscrowOutcome::Refund => &self.terms.buyer.address,
+        }
+    }
+
+    /// Bytes each approver signs to approve `outcome`
+    ///
+    /// They commit to the agreement, the funded GLEDGER escrow and the recipient, so
+    /// an approval cannot be replayed against another escrow.
+    pub fn approval_payload(&self, outcome: EscrowOutcome) -> Vec<u8> {
+        let escrow_id = self.escrow_id.as_deref().unwrap_or_default();
+        crate::canonical::to_bytes(&("escrow", &self.id, escrow_id, outcome, self.recipient(outcome))).unwrap_or_default()
+    }
+
+    fn expect_state(&self, expected: EscrowState) -> Result<()> {
+        if self.state != expected {
+            return Err(EtherlinkError::Configuration(format!("Escrow {} is {:?}, expected {:?}", self.id, self.state, expected)));
+        }
+        if expected == EscrowState::Funded {
+            self.funded_escrow_id()?;
+        }
+        Ok(())
+    }
+
+    fn funded_escrow_id(&self) -> Result<String> {
+        self.escrow_id
+            .clone()
+            .ok_or_else(|| EtherlinkError::Configuration(format!("Escrow {} is marked funded without a GLEDGER escrow", self.id)))
+    }
+}
+
+/// One party's signature approving an outcome
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowApproval {
+    pub role: EscrowRole,
+    pub outcome: EscrowOutcome,
+    /// Hex signature over [`EscrowAgreement::approval_payload`]
+    pub signature: String,
+}
+
+impl EscrowApproval {
+    /// Approve `outcome` as `role`, signing with that party's key
+    ///
+    /// Only a funded escrow can be approved, as the approval commits to its GLEDGER id.
+    pub fn sign(agreement: &EscrowAgreement, role: EscrowRole, outcome: EscrowOutcome, key: &KeyPair) -> Result<Self> {
+        agreement.expect_state(EscrowState::Funded)?;
+        let signature = CryptoProvider::new().sign_message(
+            &agreement.approval_payload(outcome),
+            key.private_key.expose_secret(),
+            &agreement.terms.algorithm,
+        )?;
+        Ok(Self { role, outcome, signature })
+    }
+}
+
+/// Creates, funds and settles two-of-three escrows
+#[derive(Debug, Clone)]
+pub struct Escrow {
+    gledger: GledgerClient,
+    gsig: GsigClient,
+    clock: SharedClock,
+}
+
+impl Escrow {
+    pub fn new(gledger: GledgerClient, gsig: GsigClient) -> Self {
+        Self { gledger, gsig, clock: clock::system() }
+    }
+
+    /// Read escrow expiry against `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Agree on `terms`; nothing is locked until [`Escrow::fund`]
+    pub fn create(&self, terms: EscrowTerms) -> Result<EscrowAgreement> {
+        terms.validate()?;
+        Ok(EscrowAgreement {
+            id: uuid::Uuid::new_v4().to_string(),
+            terms,
+            state: EscrowState::Created,
+            escrow_id: None,
+            funding_tx: None,
+            settlement_tx: None,
+        })
+    }
+
+    /// Lock the buyer's funds under the two-of-three release policy
+    pub async fn fund(&self, agreement: &mut EscrowAgreement) -> Result<TxHash> {
+        agreement.expect_state(EscrowState::Created)?;
+        let terms = &agreement.terms;
+        let receipt = self
+            .gledger
+            .lock_escrow(EscrowLock {
+                from: terms.buyer.address.clone(),
+                token_type: terms.token_type.clone(),
+                amount: terms.amount,
+                reference: format!("escrow:{}:{}", agreement.id, terms.reference),
+                expires_at: terms.expires_at,
+                release_policy: Some(EscrowPolicy {
+                    threshold: ESCROW_THRESHOLD,
+                    public_keys: terms.parties().iter().map(|(_, party)| party.key_hex()).collect(),
+                    algorithm: terms.algorithm.clone(),
+                }),
+            })
+            .await?;
+
+        let tx_hash = TxHash::new(receipt.tx_hash);
+        agreement.escrow_id = Some(receipt.escrow_id);
+        agreement.funding_tx = Some(tx_hash.clone());
+        agreement.state = EscrowState::Funded;
+        Ok(tx_hash)
+    }
+
+    /// Pay the seller, with approvals from two of the three parties
+    pub async fn release(&self, agreement: &mut EscrowAgreement, approvals: &[EscrowApproval]) -> Result<TxHash> {
+        self.settle(agreement, EscrowOutcome::Release, approvals).await
+    }
+
+    /// Return the funds to the buyer, with approvals from two of the three parties
+    pub async fn refund(&self, agreement: &mut EscrowAgreement, approvals: &[EscrowApproval]) -> Result<TxHash> {
+        self.settle(agreement, EscrowOutcome::Refund, approvals).await
+    }
+
+    /// Return the funds to the buyer after `expires_at`, without approvals
+    pub async fn refund_expired(&self, agreement: &mut EscrowAgreement) -> Result<TxHash> {
+        agreement.expect_state(EscrowState::Funded)?;
+        let Some(expires_at) = agreement.terms.expires_at else {
+            return Err(EtherlinkError::Configuration(format!(
+                "Escrow {} has no expiry, so it can only be refunded with approvals",
+                agreement.id
+            )));
+        };
+        let now = self.clock.now();
+        if now <= expires_at {
+            return Err(EtherlinkError::Configuration(format!(
+                "Escrow {} expires at {}, {} seconds from now",
+                agreement.id, expires_at, expires_at - now
+            )));
+        }
+        let escrow_id = agreement.funded_escrow_id()?;
+        let tx_hash = self.gledger.refund_escrow(&escrow_id).await?;
+        agreement.settlement_tx = Some(tx_hash.clone());
+        agreement.state = EscrowState::Refunded;
+        Ok(tx_hash)
+    }
+
+    async fn settle(&self, agreement: &mut EscrowAgreement, outcome: EscrowOutcome, approvals: &[EscrowApproval]) -> Result<TxHash> {
+        agreement.expect_state(EscrowState::Funded)?;
+        let message = agreement.approval_payload(outcome);
+        let provider = CryptoProvider::new();
+        let mut approved = HashSet::new();
+        for approval in approvals.iter().filter(|approval| approval.outcome == outcome) {
+            let party = agreement.terms.party(approval.role);
+            if !provider.verify_signature(&message, &approval.signature, &party.key_hex(), &agreement.terms.algorithm)? {
+                return Err(EtherlinkError::Crypto(format!("{} approval of {:?} does not verify", approval.role, outcome)));
+            }
+            approved.insert(approval.role);
+        }
+        if (approved.len() as u32) < ESCROW_THRESHOLD {
+            return Err(EtherlinkError::Configuration(format!(
+                "{:?} of escrow {} needs {} approvals, got {}",
+                outcome, agreement.id, ESCROW_THRESHOLD, approved.len()
+            )));
+        }
+
+        let participants = agreement
+            .terms
+            .parties()
+            .iter()
+            .map(|(role, party)| ThresholdParticipant {
+                id: role.to_string(),
+                public_key: party.key_hex(),
+                partial_signature: approvals
+                    .iter()
+                    .find(|approval| approval.role == *role && approval.outcome == outcome)
+                    .map(|approval| approval.signature.clone()),
+            })
+            .collect();
+        let threshold = self
+            .gsig
+            .create_threshold_signature(ThresholdSignatureRequest {
+                message: message.clone(),
+                threshold: ESCROW_THRESHOLD,
+                participants,
+                algorithm: gsig_algorithm(&agreement.terms.algorithm),
+            })
+            .await?;
+
+        let settlement = EscrowSettlement {
+            outcome,
+            to: agreement.recipient(outcome).clone(),
+            message,
+            signature: threshold.signature,
+            signature_id: threshold.signature_id,
+        };
+        let escrow_id = agreement.funded_escrow_id()?;
+        let tx_hash = self.gledger.settle_escrow(&escrow_id, &settlement).await?;
+        agreement.settlement_tx = Some(tx_hash.clone());
+        agreement.state = match outcome {
+            EscrowOutcome::Release => EscrowState::Released,
+            EscrowOutcome::Refund => EscrowState::Refunded,
+        };
+        Ok(tx_hash)
+    }
+}
+
+fn gsig_algorithm(algorithm: &CryptoAlgorithm) -> walletd::CryptoAlgorithm {
+    match algorithm {
+        CryptoAlgorithm::Ed25519 => walletd::CryptoAlgorithm::Ed25519,
+        CryptoAlgorithm::Secp256k1 => walletd::CryptoAlgorithm::Secp256k1,
+        CryptoAlgorithm::Bls12381 => walletd::CryptoAlgorithm::Bls12381,
+    }
+}
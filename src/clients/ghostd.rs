@@ -1,36 +1,215 @@
 //! GHOSTD (Blockchain Daemon) client implementation
 
-use crate::{Result, EtherlinkConfig, EtherlinkError, Address, TxHash, BlockHeight, Gas};
-use crate::clients::{ServiceClient, ApiResponse};
+use crate::{Result, EtherlinkConfig, EtherlinkError, Address, TxHash, BlockHeight, Gas, TokenType, SecretString};
+use crate::clients::{ServiceClient, ApiResponse, reject_not_found};
+use crate::clients::context::{CallContext, WithContext};
+use crate::clients::simulation::{SimulationResult, TransactionSimulator};
+use crate::resubmit::{self, ResubmitPolicy, SubmissionAttempt};
+use crate::auth::Permission;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::pagination::{PageConfig, PageStream};
+use crate::cache::{ReadCacheConfig, TtlCache};
+use crate::coalesce::{CoalesceSnapshot, CoalesceStats, SingleFlight};
+use crate::confirmation::{ConfirmationPolicies, OperationClass};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::lifecycle::{OperationTracker, TrackedTransaction, Transition, TxState};
+use crate::version::{Feature, VersionRegistry};
+use crate::primitives::Signer;
+use crate::auth::session::{Delegation, LocalSigner};
+use crate::validation::{ConfigErrors, Validator};
+use crate::timesync::SkewEstimator;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::hash::{to_hex, HashAlgorithm, HashDomain, Hasher};
 use reqwest::Client as HttpClient;
 use serde::{Serialize, Deserialize};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::ops::RangeInclusive;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::events::{EtherlinkEvent, EventBus};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::sync::broadcast;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio_util::sync::{CancellationToken, DropGuard};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use tracing::warn;
 
 /// Client for GHOSTD blockchain daemon service
 #[derive(Debug, Clone)]
 pub struct GhostdClient {
     base_url: String,
     http_client: Arc<HttpClient>,
+    context: CallContext,
+    coalesce_stats: Arc<CoalesceStats>,
+    block_flights: SingleFlight<BlockHeight, Block>,
+    height_flight: SingleFlight<(), BlockHeight>,
+    balance_flights: SingleFlight<Address, (u64, Option<BlockHeight>)>,
+    header_flights: SingleFlight<BlockHeight, BlockHeader>,
+    read_cache: Option<Arc<ReadCache>>,
+    versions: VersionRegistry,
+    confirmations: ConfirmationPolicies,
+    private_relay: Option<PrivateRelayConfig>,
+    simulation: Option<Simulation>,
+    resubmit: ResubmitPolicy,
+}
+
+/// How transactions are checked before they are sent
+#[derive(Debug, Clone)]
+enum Simulation {
+    /// GHOSTD's `/transactions/simulate` endpoint
+    Ghostd,
+    Custom(Arc<dyn TransactionSimulator>),
+}
+
+/// Short-TTL caches for the hottest GHOSTD reads
+///
+/// Balances are tagged with the head block known when their fetch started and only
+/// served while that is still the head, so a fetch in flight across a new block
+/// cannot refill the cache with the old state.
+#[derive(Debug)]
+struct ReadCache {
+    /// Highest block passed to [`GhostdClient::on_new_block`]
+    head: AtomicU64,
+    balances: Mutex<TtlCache<Address, (u64, BlockHeight)>>,
+    height: Mutex<TtlCache<(), BlockHeight>>,
+    headers: Mutex<TtlCache<BlockHeight, BlockHeader>>,
+}
+
+impl ReadCache {
+    fn new(config: &ReadCacheConfig) -> Self {
+        Self {
+            head: AtomicU64::new(0),
+            balances: Mutex::new(TtlCache::new(Duration::from_millis(config.balance_ttl_ms), config.max_balances)),
+            height: Mutex::new(TtlCache::new(Duration::from_millis(config.height_ttl_ms), 1)),
+            headers: Mutex::new(TtlCache::new(Duration::from_millis(config.header_ttl_ms), config.max_headers)),
+        }
+    }
+
+    fn head(&self) -> BlockHeight {
+        self.head.load(Ordering::Acquire)
+    }
 }
 
 impl GhostdClient {
     /// Create a new GHOSTD client
     pub fn new(config: &EtherlinkConfig, http_client: Arc<HttpClient>) -> Self {
         let base_url = format!("{}/api/v1", config.ghostd_endpoint.trim_end_matches('/'));
+        let coalesce_stats = Arc::new(CoalesceStats::default());
         Self {
             base_url,
             http_client,
+            context: CallContext::default(),
+            block_flights: SingleFlight::with_stats(coalesce_stats.clone()),
+            height_flight: SingleFlight::with_stats(coalesce_stats.clone()),
+            balance_flights: SingleFlight::with_stats(coalesce_stats.clone()),
+            header_flights: SingleFlight::with_stats(coalesce_stats.clone()),
+            coalesce_stats,
+            read_cache: None,
+            versions: VersionRegistry::new(),
+            confirmations: config.confirmations.clone(),
+            private_relay: config.private_relay.clone(),
+            simulation: config.simulate_before_send.then_some(Simulation::Ghostd),
+            resubmit: config.resubmit.clone(),
         }
     }
 
+    /// Client for calls with a different endpoint, auth token or timeout
+    ///
+    /// The derived client has no read cache and does not coalesce with this one,
+    /// since another endpoint or caller may see different data.
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            base_url: context.base_url().unwrap_or_else(|| self.base_url.clone()),
+            block_flights: SingleFlight::with_stats(self.coalesce_stats.clone()),
+            height_flight: SingleFlight::with_stats(self.coalesce_stats.clone()),
+            balance_flights: SingleFlight::with_stats(self.coalesce_stats.clone()),
+            header_flights: SingleFlight::with_stats(self.coalesce_stats.clone()),
+            read_cache: None,
+            context,
+            ..self.clone()
+        }
+    }
+
+    /// Client reading balances as of block `height`, for audits of past state
+    ///
+    /// Requires a GHOSTD with historical state support; other reads and writes are
+    /// unaffected.
+    pub fn at_block(&self, height: BlockHeight) -> Self {
+        self.with_context(self.context.clone().at_block(height))
+    }
+
+    /// Share a version registry used to gate newer endpoints
+    pub fn with_versions(mut self, versions: VersionRegistry) -> Self {
+        self.versions = versions;
+        self
+    }
+
+    /// Enable read-through caching of balances, the chain height and block headers
+    ///
+    /// Cached values may be up to one TTL stale; [`GhostdClient::follow_blocks`] (or
+    /// calling [`GhostdClient::on_new_block`] yourself) invalidates them as soon as
+    /// the chain advances.
+    pub fn with_read_cache(mut self, config: ReadCacheConfig) -> Self {
+        self.read_cache = Some(Arc::new(ReadCache::new(&config)));
+        self
+    }
+
+    /// Simulate every transaction with `simulator` before sending it
+    ///
+    /// Replaces GHOSTD's simulate endpoint if `simulate_before_send` is configured.
+    pub fn with_simulator(mut self, simulator: Arc<dyn TransactionSimulator>) -> Self {
+        self.simulation = Some(Simulation::Custom(simulator));
+        self
+    }
+
+    /// Predict the outcome of `tx` without submitting it
+    pub async fn simulate_transaction(&self, tx: &Transaction) -> Result<SimulationResult> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        self.versions.require(self.service_name(), Feature::TransactionSimulation)?;
+        let url = format!("{}/transactions/simulate", self.base_url);
+        let response: ApiResponse<SimulationResult> = self.http_client
+            .post(&url)
+            .with_context(&self.context)
+            .json(tx)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Reject `tx` with [`EtherlinkError::SimulatedRevert`] if the configured simulation predicts a revert
+    async fn simulate_before_send(&self, tx: &Transaction) -> Result<()> {
+        let result = match &self.simulation {
+            None => return Ok(()),
+            Some(Simulation::Ghostd) => self.simulate_transaction(tx).await?,
+            Some(Simulation::Custom(simulator)) => simulator.simulate(tx).await?,
+        };
+        result.into_result().map(|_| ())
+    }
+
     /// Submit a transaction to the blockchain
+    ///
+    /// With simulation enabled, a transaction predicted to revert is never sent.
     pub async fn submit_transaction(&self, tx: Transaction) -> Result<TxHash> {
+        self.context.require(&[Permission::SubmitTransaction])?;
+        self.simulate_before_send(&tx).await?;
+        self.send_transaction(tx).await
+    }
+
+    async fn send_transaction(&self, tx: Transaction) -> Result<TxHash> {
         let url = format!("{}/transactions", self.base_url);
         let response: ApiResponse<TransactionResponse> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&tx)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -41,12 +220,288 @@ impl GhostdClient {
         Ok(TxHash::new(tx_response.tx_hash))
     }
 
+    /// Get the receipt of a submitted transaction
+    ///
+    /// The receipt's status is `pending` until the transaction is included in a block.
+    pub async fn get_transaction_receipt(&self, tx_hash: &TxHash) -> Result<TransactionReceipt> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        let url = format!("{}/transactions/{}/receipt", self.base_url, tx_hash.as_str());
+        let response = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        let response: ApiResponse<TransactionReceipt> = reject_not_found(response, &format!("transaction {}", tx_hash.as_str()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Poll for a transaction's receipt until it is included or `timeout` elapses
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait_for_receipt(&self, tx_hash: &TxHash, poll_interval: Duration, timeout: Duration) -> Result<TransactionReceipt> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let receipt = self.get_transaction_receipt(tx_hash).await?;
+            if !receipt.is_pending() {
+                return Ok(receipt);
+            }
+            if tokio::time::Instant::now() + poll_interval > deadline {
+                return Err(EtherlinkError::Timeout(format!("Transaction {} still pending after {:?}", tx_hash.as_str(), timeout)));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Wait until a transaction meets the configured confirmation policy for `class`
+    ///
+    /// Returns the receipt once the transaction is included and enough blocks are built
+    /// on top of it; a failed transaction is returned as soon as its receipt is known.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait_for_confirmation(&self, tx_hash: &TxHash, class: OperationClass) -> Result<TransactionReceipt> {
+        let policy = self.confirmations.policy(class);
+        let (poll_interval, timeout) = (self.confirmations.poll_interval(), self.confirmations.timeout());
+        let started = tokio::time::Instant::now();
+        let receipt = self.wait_for_receipt(tx_hash, poll_interval, timeout).await?;
+        let Some(included_at) = receipt.block_height.filter(|_| receipt.is_success() && policy.l1_confirmations > 0) else {
+            return Ok(receipt);
+        };
+
+        let target = included_at + policy.l1_confirmations;
+        loop {
+            if self.get_blockchain_height().await? >= target {
+                return Ok(receipt);
+            }
+            if started.elapsed() + poll_interval > timeout {
+                return Err(EtherlinkError::Timeout(format!(
+                    "Transaction {} not {} blocks deep after {:?}",
+                    tx_hash.as_str(), policy.l1_confirmations, timeout
+                )));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Follow tracked operation `id` from broadcast until it meets the policy for `class`
+    ///
+    /// Records [`TxState::Pending`] while the receipt is pending, [`TxState::Confirmed`]
+    /// once included and [`TxState::Finalized`] once deep enough, or [`TxState::Failed`]
+    /// if it fails. Without a receipt, the account's nonce decides: past the operation's
+    /// nonce, another transaction took it and the operation is [`TxState::Replaced`];
+    /// otherwise a [`TxState::Unknown`] operation never reached GHOSTD and has failed.
+    /// On a timeout the operation keeps its last state, so it can be followed again,
+    /// e.g. after a restart.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn follow_operation(&self, tracker: &OperationTracker, id: &str, class: OperationClass) -> Result<TrackedTransaction> {
+        let operation = tracker.get(id).ok_or_else(|| EtherlinkError::Lifecycle(format!("Unknown operation {}", id)))?;
+        if operation.state.is_terminal() {
+            return Ok(operation);
+        }
+        let tx_hash = operation
+            .tx_hash
+            .clone()
+            .ok_or_else(|| EtherlinkError::Lifecycle(format!("Operation {} has not been broadcast", id)))?;
+
+        let mut receipt = match self.get_transaction_receipt(&tx_hash).await {
+            Ok(receipt) => receipt,
+            Err(EtherlinkError::NotFound(what)) => {
+                if self.get_nonce(&operation.from).await? > operation.nonce {
+                    let detail = format!("Nonce {} was used by another transaction", operation.nonce);
+                    return tracker.transition(id, Transition::to(TxState::Replaced).detail(detail)).await;
+                }
+                if operation.state == TxState::Unknown {
+                    return tracker.transition(id, Transition::to(TxState::Failed).detail("GHOSTD never received the transaction")).await;
+                }
+                return Err(EtherlinkError::NotFound(what));
+            }
+            Err(e) => return Err(e),
+        };
+        if receipt.is_pending() {
+            if matches!(operation.state, TxState::Broadcast | TxState::Unknown) {
+                tracker.transition(id, Transition::to(TxState::Pending)).await?;
+            }
+            receipt = self.wait_for_receipt(&tx_hash, self.confirmations.poll_interval(), self.confirmations.timeout()).await?;
+        }
+        if !receipt.is_success() {
+            let error = receipt.error.clone().unwrap_or_else(|| format!("Transaction {}", receipt.status));
+            return tracker.transition(id, Transition::to(TxState::Failed).detail(error)).await;
+        }
+
+        if tracker.get(id).is_some_and(|current| current.state != TxState::Confirmed) {
+            let mut confirmed = Transition::to(TxState::Confirmed);
+            if let Some(height) = receipt.block_height {
+                confirmed = confirmed.block_height(height);
+            }
+            tracker.transition(id, confirmed).await?;
+        }
+        self.wait_for_confirmation(&tx_hash, class).await?;
+        tracker.transition(id, Transition::to(TxState::Finalized)).await
+    }
+
+    /// Submit through the configured private relay, keeping the transaction out of the public mempool
+    ///
+    /// If the relay refuses or drops the transaction, or has not included it within
+    /// [`PrivateRelayConfig::fallback_after_ms`], the same signed transaction is sent to
+    /// GHOSTD instead; its nonce keeps it from being included twice. The returned
+    /// [`Submission`] records which route it finally took. With simulation enabled, a
+    /// transaction predicted to revert goes to neither.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn submit_private(&self, tx: Transaction) -> Result<Submission> {
+        self.context.require(&[Permission::SubmitTransaction])?;
+        self.simulate_before_send(&tx).await?;
+        let relay = self.private_relay.clone().ok_or_else(|| {
+            EtherlinkError::Configuration("Private submission requested but no private_relay is configured".to_string())
+        })?;
+
+        let tx_hash = match self.relay_submit(&relay, &tx).await {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                warn!("Private relay {} refused transaction, submitting publicly: {}", relay.endpoint, e);
+                return self.submit_public_fallback(tx, None).await;
+            }
+        };
+
+        let poll_interval = Duration::from_millis(relay.poll_interval_ms);
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(relay.fallback_after_ms);
+        let mut last = RelayState::Pending;
+        loop {
+            match self.get_relay_status(&tx_hash).await {
+                Ok(status) if status.state == RelayState::Included => {
+                    return Ok(Submission {
+                        tx_hash,
+                        route: SubmissionRoute::PrivateRelay,
+                        relay_state: Some(RelayState::Included),
+                        block_height: status.block_height,
+                        attempts: Vec::new(),
+                        operation_id: None,
+                    });
+                }
+                Ok(status) if status.state == RelayState::Dropped => {
+                    last = RelayState::Dropped;
+                    break;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to query private relay for {}: {}", tx_hash.as_str(), e),
+            }
+            if tokio::time::Instant::now() + poll_interval > deadline {
+                break;
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+
+        warn!("Private relay did not include {} ({:?}), submitting publicly", tx_hash.as_str(), last);
+        self.submit_public_fallback(tx, Some(last)).await
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn submit_public_fallback(&self, tx: Transaction, relay_state: Option<RelayState>) -> Result<Submission> {
+        let tx_hash = self.send_transaction(tx).await?;
+        Ok(Submission { tx_hash, route: SubmissionRoute::Public, relay_state, block_height: None, attempts: Vec::new(), operation_id: None })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn relay_submit(&self, relay: &PrivateRelayConfig, tx: &Transaction) -> Result<TxHash> {
+        let url = format!("{}/relay/transactions", relay.endpoint.trim_end_matches('/'));
+        let response: ApiResponse<TransactionResponse> = relay
+            .authorize(self.http_client.post(&url))
+            .json(tx)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        Ok(TxHash::new(response.into_result()?.tx_hash))
+    }
+
+    /// Inclusion status of a transaction sent to the private relay
+    pub async fn get_relay_status(&self, tx_hash: &TxHash) -> Result<RelayStatus> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        let relay = self.private_relay.as_ref().ok_or_else(|| {
+            EtherlinkError::Configuration("No private_relay is configured".to_string())
+        })?;
+        let url = format!("{}/relay/transactions/{}", relay.endpoint.trim_end_matches('/'), tx_hash.as_str());
+        let response: ApiResponse<RelayStatus> = relay
+            .authorize(self.http_client.get(&url))
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
     /// Get a block by height
+    ///
+    /// Concurrent requests for the same height share a single in-flight call.
     pub async fn get_block(&self, height: BlockHeight) -> Result<Block> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        self.block_flights.run(height, || self.fetch_block(height)).await
+    }
+
+    async fn fetch_block(&self, height: BlockHeight) -> Result<Block> {
         let url = format!("{}/blockchain/block/{}", self.base_url, height);
-        let response: ApiResponse<Block> = self.http_client
+        let response = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        let response: ApiResponse<Block> = reject_not_found(response, &format!("block {}", height))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Stream the blocks of an inclusive height range, fetching a page of blocks at a time
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn stream_blocks(&self, heights: RangeInclusive<BlockHeight>, config: PageConfig) -> PageStream<Block> {
+        let client = self.clone();
+        let last = *heights.end();
+        PageStream::new(*heights.start(), config, move |start, count| {
+            let client = client.clone();
+            async move {
+                if start > last {
+                    return Ok(Vec::new());
+                }
+                let end = last.min(start.saturating_add(count as u64 - 1));
+                futures::future::try_join_all((start..=end).map(|height| client.get_block(height))).await
+            }
+        })
+    }
+
+    /// Get a block header by height
+    pub async fn get_block_header(&self, height: BlockHeight) -> Result<BlockHeader> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        self.versions.require(self.service_name(), Feature::BlockHeaders)?;
+
+        if let Some(cache) = &self.read_cache
+            && let Some(header) = cache.headers.lock().unwrap().get(&height)
+        {
+            return Ok(header);
+        }
+
+        let header = self.header_flights.run(height, || self.fetch_block_header(height)).await?;
+        if let Some(cache) = &self.read_cache {
+            cache.headers.lock().unwrap().insert(height, header.clone());
+        }
+        Ok(header)
+    }
+
+    async fn fetch_block_header(&self, height: BlockHeight) -> Result<BlockHeader> {
+        let url = format!("{}/blockchain/block/{}/header", self.base_url, height);
+        let response: ApiResponse<BlockHeader> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -58,10 +513,31 @@ impl GhostdClient {
 
     /// Get current blockchain height
     pub async fn get_blockchain_height(&self) -> Result<BlockHeight> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        if let Some(cache) = &self.read_cache
+            && let Some(height) = cache.height.lock().unwrap().get(&())
+            && height >= cache.head()
+        {
+            return Ok(height);
+        }
+
+        let height = self.height_flight.run((), || self.fetch_blockchain_height()).await?;
+        if let Some(cache) = &self.read_cache {
+            // A slow fetch must not move the cached height back behind a newer block
+            let mut cached = cache.height.lock().unwrap();
+            if height >= cache.head() {
+                cached.insert((), height);
+            }
+        }
+        Ok(height)
+    }
+
+    async fn fetch_blockchain_height(&self) -> Result<BlockHeight> {
         let url = format!("{}/blockchain/height", self.base_url);
         let response: ApiResponse<HeightResponse> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -72,12 +548,85 @@ impl GhostdClient {
         Ok(height_response.height)
     }
 
+    /// GHOSTD's current time in unix milliseconds
+    pub async fn get_server_time(&self) -> Result<u64> {
+        self.versions.require(self.service_name(), Feature::ServerTime)?;
+        let url = format!("{}/time", self.base_url);
+        let response: ApiResponse<ServerTimeResponse> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        Ok(response.into_result()?.unix_ms)
+    }
+
+    /// Measure the offset to GHOSTD's clock into `estimator`, returning the new estimate in milliseconds
+    ///
+    /// The round trip is timed by the estimator's local clock, see [`SkewEstimator::with_clock`].
+    pub async fn sync_clock(&self, estimator: &SkewEstimator) -> Result<i64> {
+        let sent_ms = estimator.local_millis();
+        let server_ms = self.get_server_time().await?;
+        estimator.observe(server_ms, sent_ms, estimator.local_millis());
+        Ok(estimator.offset_ms())
+    }
+
     /// Get account balance
     pub async fn get_balance(&self, address: &Address) -> Result<u64> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        if let Some(cache) = &self.read_cache
+            && let Some((balance, height)) = cache.balances.lock().unwrap().get(address)
+            && height == cache.head()
+        {
+            return Ok(balance);
+        }
+
+        // The head is read by the flight's leader, so a caller joining a fetch that
+        // started before a new block cannot tag its result with the newer head
+        let (balance, read_at) = self
+            .balance_flights
+            .run(address.clone(), || async {
+                let read_at = self.read_cache.as_ref().map(|cache| cache.head());
+                Ok((self.fetch_balance(address).await?, read_at))
+            })
+            .await?;
+        if let (Some(cache), Some(read_at)) = (&self.read_cache, read_at) {
+            cache.balances.lock().unwrap().insert(address.clone(), (balance, read_at));
+        }
+        Ok(balance)
+    }
+
+    /// Next nonce of `address`: the number of its transactions GHOSTD has accepted
+    pub async fn get_nonce(&self, address: &Address) -> Result<u64> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        let url = format!("{}/accounts/{}/nonce", self.base_url, address.as_str());
+        let response: ApiResponse<NonceResponse> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        Ok(response.into_result()?.nonce)
+    }
+
+    async fn fetch_balance(&self, address: &Address) -> Result<u64> {
+        if self.context.block.is_some() {
+            self.versions.require(self.service_name(), Feature::HistoricalState)?;
+        }
+
         let url = format!("{}/accounts/{}/balance", self.base_url, address.as_str());
         let response: ApiResponse<BalanceResponse> = self.http_client
             .get(&url)
-            .send()
+            .with_block(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -88,12 +637,223 @@ impl GhostdClient {
         Ok(balance_response.balance)
     }
 
+    /// Get base fees, gas usage and priority fee percentiles for the last `block_count` blocks
+    ///
+    /// `percentiles` must be ascending values in `0.0..=100.0`; each block's rewards
+    /// hold one priority fee per requested percentile.
+    pub async fn get_fee_history(&self, block_count: u64, percentiles: &[f64]) -> Result<FeeHistory> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        if !(1..=MAX_FEE_HISTORY_BLOCKS).contains(&block_count) {
+            return Err(EtherlinkError::Configuration(format!(
+                "Fee history block count must be between 1 and {}, got {}",
+                MAX_FEE_HISTORY_BLOCKS, block_count
+            )));
+        }
+        if percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) || percentiles.windows(2).any(|w| w[0] > w[1]) {
+            return Err(EtherlinkError::Configuration(format!(
+                "Fee history percentiles must be ascending values between 0 and 100, got {:?}",
+                percentiles
+            )));
+        }
+        self.versions.require(self.service_name(), Feature::FeeHistory)?;
+
+        let percentile_list = percentiles.iter().map(f64::to_string).collect::<Vec<_>>().join(",");
+        let url = format!("{}/blockchain/fee-history", self.base_url);
+        let response: ApiResponse<FeeHistory> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .query(&[("block_count", block_count.to_string()), ("percentiles", percentile_list)])
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        let mut history = response.into_result()?;
+        history.percentiles = percentiles.to_vec();
+        Ok(history)
+    }
+
+    /// Get recent throughput, block time and gas utilization
+    pub async fn get_chain_stats(&self) -> Result<ChainStats> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        self.versions.require(self.service_name(), Feature::ChainStats)?;
+        let url = format!("{}/blockchain/stats", self.base_url);
+        let response: ApiResponse<ChainStats> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
     /// Get daemon performance metrics
     pub async fn get_metrics(&self) -> Result<DaemonMetrics> {
+        self.context.require(&[Permission::SystemRead])?;
         let url = format!("{}/performance/metrics", self.base_url);
         let response: ApiResponse<DaemonMetrics> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Invalidate cached reads after a new block has been observed
+    ///
+    /// Balances are dropped since any of them may have changed; the height is
+    /// primed with the new value. Headers are immutable and stay cached. Heights at
+    /// or below one already seen are ignored.
+    pub fn on_new_block(&self, height: BlockHeight) {
+        if let Some(cache) = &self.read_cache {
+            let mut balances = cache.balances.lock().unwrap();
+            if cache.head.fetch_max(height, Ordering::AcqRel) >= height {
+                return;
+            }
+            balances.clear();
+            cache.height.lock().unwrap().insert((), height);
+        }
+    }
+
+    /// Keep the read cache current from [`EtherlinkEvent::NewBlock`] events until `token` is cancelled
+    ///
+    /// A [`EtherlinkEvent::ReorgDetected`], or missing events because the receiver
+    /// lagged, drops the whole cache.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn follow_blocks(&self, mut events: broadcast::Receiver<EtherlinkEvent>, token: CancellationToken) {
+        loop {
+            let event = tokio::select! {
+                _ = token.cancelled() => return,
+                event = events.recv() => event,
+            };
+            match event {
+                Ok(EtherlinkEvent::NewBlock { height, .. }) => self.on_new_block(height),
+                Ok(EtherlinkEvent::ReorgDetected { .. }) | Err(broadcast::error::RecvError::Lagged(_)) => self.clear_read_cache(),
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Run [`GhostdClient::follow_blocks`] on `events` in the background until the guard is dropped
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn_block_follower(&self, events: &EventBus) -> DropGuard {
+        let token = CancellationToken::new();
+        let (client, receiver, child) = (self.clone(), events.subscribe(), token.clone());
+        tokio::spawn(async move { client.follow_blocks(receiver, child).await });
+        token.drop_guard()
+    }
+
+    /// Drop all cached reads
+    pub fn clear_read_cache(&self) {
+        if let Some(cache) = &self.read_cache {
+            cache.balances.lock().unwrap().clear();
+            cache.height.lock().unwrap().clear();
+            cache.headers.lock().unwrap().clear();
+        }
+    }
+
+    /// Get request coalescing statistics for this client's read paths
+    pub fn coalesce_stats(&self) -> CoalesceSnapshot {
+        self.coalesce_stats.snapshot()
+    }
+}
+
+/// Node operator endpoints under `/admin`
+///
+/// Every call needs [`Permission::Admin`] and ghostd 1.4 or later. None of them are
+/// cached or coalesced, since each one changes the node.
+impl GhostdClient {
+    /// Start producing blocks as a proof-of-work miner
+    pub async fn start_mining(&self) -> Result<ProductionStatus> {
+        self.set_production(ProductionMode::Mining, true).await
+    }
+
+    /// Stop mining after the block in progress
+    pub async fn stop_mining(&self) -> Result<ProductionStatus> {
+        self.set_production(ProductionMode::Mining, false).await
+    }
+
+    /// Start ordering transactions into blocks as the sequencer
+    pub async fn start_sequencing(&self) -> Result<ProductionStatus> {
+        self.set_production(ProductionMode::Sequencing, true).await
+    }
+
+    /// Stop sequencing; pending transactions stay in the mempool
+    pub async fn stop_sequencing(&self) -> Result<ProductionStatus> {
+        self.set_production(ProductionMode::Sequencing, false).await
+    }
+
+    /// Get whether the node is currently mining or sequencing
+    pub async fn get_production_status(&self) -> Result<ProductionStatus> {
+        self.admin_request(reqwest::Method::GET, "production", None::<&()>).await
+    }
+
+    async fn set_production(&self, mode: ProductionMode, enabled: bool) -> Result<ProductionStatus> {
+        let action = if enabled { "start" } else { "stop" };
+        self.admin_request(reqwest::Method::POST, &format!("{}/{}", mode.path(), action), None::<&()>).await
+    }
+
+    /// Replace the node's log filter, e.g. `info,consensus=debug`
+    pub async fn set_log_level(&self, filter: &str) -> Result<LogLevelChange> {
+        if filter.trim().is_empty() {
+            return Err(EtherlinkError::Configuration("Log filter must not be empty".to_string()));
+        }
+        self.admin_request(reqwest::Method::PUT, "log-level", Some(&serde_json::json!({ "filter": filter }))).await
+    }
+
+    /// Write a state snapshot at the current height
+    ///
+    /// Returns once the node has accepted the request; large states finish
+    /// writing in the background, see [`SnapshotInfo::status`].
+    pub async fn trigger_snapshot(&self) -> Result<SnapshotInfo> {
+        self.admin_request(reqwest::Method::POST, "snapshots", None::<&()>).await
+    }
+
+    /// Delete historical state, keeping the most recent `keep_recent_blocks` blocks
+    pub async fn prune_state(&self, keep_recent_blocks: u64) -> Result<PruneResult> {
+        if keep_recent_blocks == 0 {
+            return Err(EtherlinkError::Configuration("Pruning must keep at least one block".to_string()));
+        }
+        self.admin_request(
+            reqwest::Method::POST,
+            "prune",
+            Some(&serde_json::json!({ "keep_recent_blocks": keep_recent_blocks })),
+        )
+        .await
+    }
+
+    /// Replace one of the node's keys; the old key stays valid until `effective_at_height`
+    pub async fn rotate_key(&self, key: NodeKey) -> Result<KeyRotation> {
+        self.admin_request(reqwest::Method::POST, "keys/rotate", Some(&serde_json::json!({ "key": key }))).await
+    }
+
+    async fn admin_request<B: Serialize, T: serde::de::DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> Result<T> {
+        self.context.require(&[Permission::Admin])?;
+        self.versions.require(self.service_name(), Feature::AdminOperations)?;
+        let url = format!("{}/admin/{}", self.base_url, path);
+        let mut request = self.http_client.request(method, &url).with_context(&self.context);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response: ApiResponse<T> = request
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -104,7 +864,149 @@ impl GhostdClient {
     }
 }
 
-#[async_trait::async_trait]
+/// Chunked state snapshot download for bootstrapping nodes
+#[cfg(not(target_arch = "wasm32"))]
+impl GhostdClient {
+    /// Get the manifest of the snapshot taken at `height`
+    pub async fn get_snapshot_manifest(&self, height: BlockHeight) -> Result<SnapshotManifest> {
+        self.fetch_snapshot_manifest(&height.to_string()).await
+    }
+
+    /// Get the manifest of the most recent complete snapshot
+    pub async fn latest_snapshot(&self) -> Result<SnapshotManifest> {
+        self.fetch_snapshot_manifest("latest").await
+    }
+
+    async fn fetch_snapshot_manifest(&self, height: &str) -> Result<SnapshotManifest> {
+        self.context.require(&[Permission::ReadBlockchain])?;
+        self.versions.require(self.service_name(), Feature::StateSnapshots)?;
+        let url = format!("{}/snapshots/{}", self.base_url, height);
+        let response: ApiResponse<SnapshotManifest> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        let manifest = response.into_result()?;
+        manifest.check()?;
+        Ok(manifest)
+    }
+
+    /// Download the snapshot at `height` into `file`, one verified chunk at a time
+    ///
+    /// `resume_from` is the number of bytes `file` already holds from an earlier attempt
+    /// and must fall on a chunk boundary (see [`SnapshotManifest::resume_offset`]). That
+    /// prefix is read back and checked chunk by chunk; the download resumes at the first
+    /// chunk that fails, overwriting it. Each fetched chunk is checked against its
+    /// SHA-256 before it is written and retried a few times on failure, and the whole
+    /// snapshot is checked at the end. `on_progress` is called after every chunk.
+    pub async fn download_snapshot<F>(
+        &self,
+        height: BlockHeight,
+        file: &mut F,
+        resume_from: u64,
+        mut on_progress: impl FnMut(SnapshotProgress),
+    ) -> Result<SnapshotManifest>
+    where
+        F: tokio::io::AsyncRead + tokio::io::AsyncWrite + tokio::io::AsyncSeek + Unpin,
+    {
+        use std::io::SeekFrom;
+        use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+        let manifest = self.get_snapshot_manifest(height).await?;
+        if resume_from > manifest.size_bytes || (resume_from % manifest.chunk_size != 0 && resume_from != manifest.size_bytes) {
+            return Err(EtherlinkError::Configuration(format!(
+                "Cannot resume snapshot at byte {}: not a chunk boundary of a {} byte snapshot",
+                resume_from, manifest.size_bytes
+            )));
+        }
+
+        let io_error = |action: &str, at: u64, e: std::io::Error| {
+            EtherlinkError::Network(format!("Failed to {} snapshot at byte {}: {}", action, at, e))
+        };
+        let mut whole = Hasher::new(HashAlgorithm::Sha256);
+        let mut downloaded = 0;
+        let mut next = 0;
+        file.seek(SeekFrom::Start(0)).await.map_err(|e| io_error("read", 0, e))?;
+        let mut existing = Vec::new();
+        while downloaded < resume_from {
+            let (start, end) = manifest.chunk_range(next);
+            existing.resize((end - start) as usize, 0);
+            file.read_exact(&mut existing).await.map_err(|e| io_error("read", start, e))?;
+            if to_hex(&Hasher::digest(HashAlgorithm::Sha256, &existing)) != manifest.chunks[next] {
+                warn!("Snapshot chunk {} on disk failed its checksum; fetching it again", next);
+                break;
+            }
+            whole.update(&existing);
+            downloaded = end;
+            next += 1;
+        }
+        file.seek(SeekFrom::Start(downloaded)).await.map_err(|e| io_error("write", downloaded, e))?;
+
+        for index in next..manifest.chunks.len() {
+            let chunk = self.fetch_snapshot_chunk(&manifest, index).await?;
+            file.write_all(&chunk).await.map_err(|e| io_error("write", downloaded, e))?;
+            whole.update(&chunk);
+            downloaded += chunk.len() as u64;
+            on_progress(SnapshotProgress { height, downloaded_bytes: downloaded, total_bytes: manifest.size_bytes });
+        }
+        file.flush().await.map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        if to_hex(&whole.finalize()) != manifest.sha256 {
+            return Err(EtherlinkError::Crypto(format!("Snapshot at height {} does not match its checksum", height)));
+        }
+        Ok(manifest)
+    }
+
+    async fn fetch_snapshot_chunk(&self, manifest: &SnapshotManifest, index: usize) -> Result<Vec<u8>> {
+        let (start, end) = manifest.chunk_range(index);
+        let url = format!("{}/snapshots/{}/data", self.base_url, manifest.height);
+        let mut last_error = None;
+        for attempt in 0..SNAPSHOT_CHUNK_ATTEMPTS {
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_millis(200 * attempt as u64)).await;
+            }
+            let result = async {
+                let response = self.http_client
+                    .get(&url)
+                    .with_context(&self.context)
+                    .header(reqwest::header::RANGE, format!("bytes={}-{}", start, end - 1))
+                    .send_logged()
+                    .await
+                    .map_err(|e| EtherlinkError::Network(e.to_string()))?
+                    .error_for_status()
+                    .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+                let chunk = response.bytes().await.map_err(|e| EtherlinkError::Network(e.to_string()))?;
+                if chunk.len() as u64 != end - start {
+                    return Err(EtherlinkError::Network(format!(
+                        "Snapshot chunk {} has {} bytes, expected {}",
+                        index, chunk.len(), end - start
+                    )));
+                }
+                if to_hex(&Hasher::digest(HashAlgorithm::Sha256, &chunk)) != manifest.chunks[index] {
+                    return Err(EtherlinkError::Crypto(format!("Snapshot chunk {} failed its checksum", index)));
+                }
+                Ok(chunk.to_vec())
+            }
+            .await;
+            match result {
+                Ok(chunk) => return Ok(chunk),
+                Err(e) => {
+                    warn!("Snapshot chunk {} (bytes {}..{}) failed: {}", index, start, end, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.expect("at least one attempt was made"))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl ServiceClient for GhostdClient {
     fn service_name(&self) -> &'static str {
         "ghostd"
@@ -118,7 +1020,8 @@ impl ServiceClient for GhostdClient {
         let url = format!("{}/health", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -132,7 +1035,8 @@ impl ServiceClient for GhostdClient {
         let url = format!("{}/status", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -146,6 +1050,7 @@ impl ServiceClient for GhostdClient {
 // Data structures for GHOSTD API
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub struct Transaction {
     pub from: Address,
     pub to: Address,
@@ -155,6 +1060,324 @@ pub struct Transaction {
     pub nonce: u64,
     pub data: Option<Vec<u8>>,
     pub signature: Option<String>,
+    /// Session-key delegation the signature is made under, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegation: Option<Delegation>,
+    /// Token `amount` is in; omitted when GCC so existing signatures stay valid
+    #[serde(default, skip_serializing_if = "is_gcc")]
+    pub token_type: TokenType,
+}
+
+fn is_gcc(token: &TokenType) -> bool {
+    *token == TokenType::GCC
+}
+
+impl Transaction {
+    /// Bytes covered by the signature: the canonical encoding with `signature` unset
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let unsigned = Transaction { signature: None, ..self.clone() };
+        crate::canonical::to_bytes(&unsigned).unwrap_or_default()
+    }
+
+    /// Hash GHOSTD identifies the transaction by, covering everything but the signature
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn hash(&self) -> TxHash {
+        TxHash::new(hex::encode(Hasher::domain_digest(HashAlgorithm::Sha256, HashDomain::Tx, self.signing_payload())))
+    }
+
+    /// Sign the transaction, storing the hex-encoded signature
+    pub fn sign<S: Signer + ?Sized>(&mut self, signer: &S) -> Result<()> {
+        let signature = signer.sign(&self.signing_payload())?;
+        self.signature = Some(hex::encode(signature.bytes));
+        Ok(())
+    }
+}
+
+/// Builds a [`Transaction`] and chooses how it is submitted
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    tx: Transaction,
+    private_relay: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    tracker: Option<OperationTracker>,
+}
+
+impl TransactionBuilder {
+    /// A plain transfer with the standard 21,000 gas limit and a gas price of 1
+    pub fn new(from: Address, to: Address) -> Self {
+        Self {
+            tx: Transaction { from, to, amount: 0, gas_limit: 21_000, gas_price: 1, nonce: 0, data: None, signature: None, delegation: None, token_type: TokenType::GCC },
+            private_relay: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            tracker: None,
+        }
+    }
+
+    pub fn amount(mut self, amount: u64) -> Self {
+        self.tx.amount = amount;
+        self
+    }
+
+    pub fn gas_limit(mut self, gas_limit: Gas) -> Self {
+        self.tx.gas_limit = gas_limit;
+        self
+    }
+
+    pub fn gas_price(mut self, gas_price: u64) -> Self {
+        self.tx.gas_price = gas_price;
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.tx.nonce = nonce;
+        self
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.tx.data = Some(data);
+        self
+    }
+
+    pub fn token_type(mut self, token_type: TokenType) -> Self {
+        self.tx.token_type = token_type;
+        self
+    }
+
+    /// Submit through the client's private relay (MEV protection) instead of the public mempool
+    pub fn private_relay(mut self, enabled: bool) -> Self {
+        self.private_relay = enabled;
+        self
+    }
+
+    /// Record the transaction's lifecycle in `tracker`, up to [`TxState::Broadcast`]
+    ///
+    /// The [`Submission`] carries the operation id; hand it to
+    /// [`GhostdClient::follow_operation`] to track the rest. A submission that ends in
+    /// a network error or timeout leaves the operation [`TxState::Unknown`] rather than
+    /// failed, since the node may have accepted it; following it settles which.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tracked(mut self, tracker: OperationTracker) -> Self {
+        self.tracker = Some(tracker);
+        self
+    }
+
+    pub fn build(self) -> Transaction {
+        self.tx
+    }
+
+    /// Sign with `signer` and submit by the chosen route
+    ///
+    /// A stale nonce or too low a gas price is corrected and the transaction re-signed
+    /// and resubmitted within the client's [`ResubmitPolicy`]. Before re-signing after a
+    /// stale nonce, the receipts of the earlier attempts are checked, and one already on
+    /// chain is returned instead of sending the payment twice.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn submit<S: Signer + ?Sized>(self, client: &GhostdClient, signer: &S) -> Result<Submission> {
+        self.resubmitting(client, |tx| tx.sign(signer)).await
+    }
+
+    /// Sign with a [`LocalSigner`], enforcing its session policy and attaching the delegation, then submit
+    ///
+    /// Resubmits like [`TransactionBuilder::submit`]; every signature counts against
+    /// the session's spend limit, even for replaced attempts.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn submit_local(self, client: &GhostdClient, signer: &LocalSigner) -> Result<Submission> {
+        self.resubmitting(client, |tx| signer.sign_transaction(tx)).await
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn resubmitting<F>(mut self, client: &GhostdClient, sign: F) -> Result<Submission>
+    where
+        F: Fn(&mut Transaction) -> Result<()>,
+    {
+        let private = self.private_relay;
+        let operation = match self.tracker.take() {
+            Some(tracker) => {
+                let id = tracker.create(&self.tx.from, self.tx.nonce).await?.id;
+                Some((tracker, id))
+            }
+            None => None,
+        };
+        let tx = self.build();
+        let signed: Mutex<HashMap<(u64, u64), TxHash>> = Mutex::new(HashMap::new());
+        let (tx, sign, operation, signed) = (&tx, &sign, &operation, &signed);
+        let (result, attempts) = resubmit::submit_with_resubmission(
+            &client.resubmit,
+            tx.nonce,
+            tx.gas_price,
+            move |nonce, gas_price| async move {
+                let mut tx = Transaction { nonce, gas_price, signature: None, ..tx.clone() };
+                sign(&mut tx)?;
+                signed.lock().unwrap().insert((nonce, gas_price), tx.hash());
+                record(operation, Transition::to(TxState::Signed)).await;
+                Self::dispatch(client, tx, private).await
+            },
+            move |nonce, gas_price| async move {
+                let Some(tx_hash) = signed.lock().unwrap().get(&(nonce, gas_price)).cloned() else {
+                    return Ok(None);
+                };
+                match client.get_transaction_receipt(&tx_hash).await {
+                    Ok(receipt) => {
+                        let route = if private { SubmissionRoute::PrivateRelay } else { SubmissionRoute::Public };
+                        Ok(Some(Submission { tx_hash, route, relay_state: None, block_height: receipt.block_height, attempts: Vec::new(), operation_id: None }))
+                    }
+                    Err(EtherlinkError::NotFound(_)) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            },
+            move || client.get_nonce(&tx.from),
+        )
+        .await;
+
+        let last = attempts.last().map(|attempt| (attempt.nonce, attempt.gas_price, attempt.rejection));
+        let mut submission = match result {
+            Ok(submission) => submission,
+            Err(e) => {
+                // Without a clear answer the node may still have the transaction; following it tells
+                let unanswered = last
+                    .filter(|(_, _, rejection)| rejection.is_none() && e.is_retriable())
+                    .and_then(|(nonce, gas_price, _)| Some((nonce, signed.lock().unwrap().get(&(nonce, gas_price)).cloned()?)));
+                let transition = match unanswered {
+                    Some((nonce, tx_hash)) => Transition::to(TxState::Unknown).tx_hash(tx_hash).nonce(nonce),
+                    None => Transition::to(TxState::Failed),
+                };
+                record(operation, transition.detail(e.to_string())).await;
+                return Err(e);
+            }
+        };
+        let mut broadcast = Transition::to(TxState::Broadcast).tx_hash(submission.tx_hash.clone());
+        if let Some((nonce, _, _)) = last {
+            broadcast = broadcast.nonce(nonce);
+        }
+        record(operation, broadcast).await;
+        submission.attempts = attempts;
+        submission.operation_id = operation.as_ref().map(|(_, id)| id.clone());
+        Ok(submission)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn dispatch(client: &GhostdClient, tx: Transaction, private: bool) -> Result<Submission> {
+        if private {
+            client.submit_private(tx).await
+        } else {
+            let tx_hash = client.submit_transaction(tx).await?;
+            Ok(Submission { tx_hash, route: SubmissionRoute::Public, relay_state: None, block_height: None, attempts: Vec::new(), operation_id: None })
+        }
+    }
+}
+
+/// Record a lifecycle transition of a tracked transaction
+///
+/// Re-signing for a resubmission leaves it [`TxState::Signed`]. A transition that
+/// cannot be recorded is logged rather than failing a submission the node accepted.
+#[cfg(not(target_arch = "wasm32"))]
+async fn record(operation: &Option<(OperationTracker, String)>, transition: Transition) {
+    let Some((tracker, id)) = operation else {
+        return;
+    };
+    if tracker.get(id).is_some_and(|current| current.state == transition.to) {
+        return;
+    }
+    if let Err(e) = tracker.transition(id, transition).await {
+        warn!("Failed to record lifecycle of operation {}: {}", id, e);
+    }
+}
+
+/// Private relay used instead of the public mempool by [`GhostdClient::submit_private`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivateRelayConfig {
+    pub endpoint: String,
+    /// Bearer token for the relay; the caller's Guardian token is never sent to it
+    pub access_token: Option<SecretString>,
+    /// Submit publicly if the relay has not included the transaction by then
+    pub fallback_after_ms: u64,
+    /// Delay between relay status polls
+    pub poll_interval_ms: u64,
+}
+
+impl Default for PrivateRelayConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            access_token: None,
+            fallback_after_ms: 30_000,
+            poll_interval_ms: 1000,
+        }
+    }
+}
+
+impl PrivateRelayConfig {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), ..Self::default() }
+    }
+
+    pub fn with_access_token(mut self, token: impl Into<SecretString>) -> Self {
+        self.access_token = Some(token.into());
+        self
+    }
+
+    /// Check the token and timings; the endpoint is checked by [`EtherlinkConfig::validate`]
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(
+            self.access_token.as_ref().is_none_or(|token| !token.expose_secret().is_empty()),
+            "access_token",
+            "must not be empty when set",
+        );
+        v.timeout("fallback_after_ms", self.fallback_after_ms);
+        v.check(self.poll_interval_ms > 0, "poll_interval_ms", "must be greater than zero");
+        v.check(self.poll_interval_ms <= self.fallback_after_ms, "poll_interval_ms", "must not exceed fallback_after_ms");
+        v.finish()
+    }
+
+    /// Attach only the relay's own credentials, never the caller's context
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.access_token {
+            Some(token) => request.bearer_auth(token.expose_secret()),
+            None => request,
+        }
+    }
+}
+
+/// Where a transaction was finally submitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionRoute {
+    Public,
+    PrivateRelay,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelayState {
+    Pending,
+    Included,
+    /// The relay gave up on the transaction, e.g. after its builders declined it
+    Dropped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayStatus {
+    pub state: RelayState,
+    pub block_height: Option<BlockHeight>,
+}
+
+/// Outcome of [`TransactionBuilder::submit`] or [`GhostdClient::submit_private`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Submission {
+    pub tx_hash: TxHash,
+    pub route: SubmissionRoute,
+    /// Last state reported by the private relay, if it was tried
+    pub relay_state: Option<RelayState>,
+    /// Block the relay reported the transaction in
+    pub block_height: Option<BlockHeight>,
+    /// Every submission made, including those rejected and resubmitted
+    #[serde(default)]
+    pub attempts: Vec<SubmissionAttempt>,
+    /// Id of the lifecycle recorded by [`TransactionBuilder::tracked`]
+    #[serde(default)]
+    pub operation_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -163,6 +1386,26 @@ pub struct TransactionResponse {
     pub status: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionReceipt {
+    pub tx_hash: String,
+    /// `pending`, `success` or `failed`
+    pub status: String,
+    pub block_height: Option<BlockHeight>,
+    pub gas_used: Gas,
+    pub error: Option<String>,
+}
+
+impl TransactionReceipt {
+    pub fn is_pending(&self) -> bool {
+        self.status == "pending"
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == "success"
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
     pub height: BlockHeight,
@@ -175,11 +1418,34 @@ pub struct Block {
     pub gas_limit: Gas,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub height: BlockHeight,
+    pub hash: String,
+    pub previous_hash: String,
+    pub timestamp: u64,
+    pub merkle_root: String,
+    pub gas_used: Gas,
+    pub gas_limit: Gas,
+    pub transaction_count: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HeightResponse {
     pub height: BlockHeight,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTimeResponse {
+    /// Unix milliseconds by GHOSTD's clock
+    pub unix_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NonceResponse {
+    pub nonce: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BalanceResponse {
     pub balance: u64,
@@ -198,4 +1464,200 @@ pub struct DaemonMetrics {
     pub cpu_usage_percent: f64,
     pub network_in_bytes: u64,
     pub network_out_bytes: u64,
-}
\ No newline at end of file
+}
+/// How a node produces blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductionMode {
+    Mining,
+    Sequencing,
+}
+
+impl ProductionMode {
+    fn path(self) -> &'static str {
+        match self {
+            ProductionMode::Mining => "mining",
+            ProductionMode::Sequencing => "sequencer",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductionStatus {
+    /// Active mode, or `None` if the node is only following the chain
+    pub mode: Option<ProductionMode>,
+    pub active: bool,
+    /// Unix seconds when the current mode started
+    pub since: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogLevelChange {
+    pub previous: String,
+    pub current: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotStatus {
+    Pending,
+    Complete,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub snapshot_id: String,
+    pub height: BlockHeight,
+    pub state_root: String,
+    pub status: SnapshotStatus,
+    /// Size on disk, known once the snapshot is complete
+    pub size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneResult {
+    pub pruned_blocks: u64,
+    pub reclaimed_bytes: u64,
+    /// Oldest height whose state is still available
+    pub oldest_retained: BlockHeight,
+}
+
+/// Key held by a node that can be rotated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKey {
+    /// Signs produced blocks and consensus votes
+    Validator,
+    /// Identifies the node to its peers
+    P2p,
+    /// Serves the RPC endpoints over TLS
+    RpcTls,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRotation {
+    pub key: NodeKey,
+    pub previous_public_key: String,
+    pub new_public_key: String,
+    /// First height signed with the new key
+    pub effective_at_height: BlockHeight,
+}
+
+/// Attempts per snapshot chunk before a download gives up
+#[cfg(not(target_arch = "wasm32"))]
+const SNAPSHOT_CHUNK_ATTEMPTS: u32 = 3;
+
+/// Layout and checksums of a state snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub height: BlockHeight,
+    pub state_root: String,
+    pub size_bytes: u64,
+    pub chunk_size: u64,
+    /// Hex SHA-256 of each chunk, in order
+    pub chunks: Vec<String>,
+    /// Hex SHA-256 of the whole snapshot
+    pub sha256: String,
+}
+
+impl SnapshotManifest {
+    /// Byte range `[start, end)` of chunk `index`
+    pub fn chunk_range(&self, index: usize) -> (u64, u64) {
+        let start = index as u64 * self.chunk_size;
+        (start, (start + self.chunk_size).min(self.size_bytes))
+    }
+
+    /// Largest chunk boundary at or below `existing_bytes`, where a partial download can resume
+    ///
+    /// The bytes before it are not trusted either: [`GhostdClient::download_snapshot`]
+    /// checks them again before resuming.
+    pub fn resume_offset(&self, existing_bytes: u64) -> u64 {
+        let existing = existing_bytes.min(self.size_bytes);
+        existing - existing % self.chunk_size
+    }
+
+    fn check(&self) -> Result<()> {
+        let expected = if self.chunk_size == 0 { 0 } else { self.size_bytes.div_ceil(self.chunk_size) };
+        if self.chunk_size == 0 || self.chunks.len() as u64 != expected {
+            return Err(EtherlinkError::Api(format!(
+                "Snapshot manifest for height {} lists {} chunks of {} bytes for {} bytes",
+                self.height, self.chunks.len(), self.chunk_size, self.size_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Reported after each snapshot chunk is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotProgress {
+    pub height: BlockHeight,
+    pub downloaded_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Most blocks a single fee history request may cover
+pub const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// Fees over a range of recent blocks, oldest first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeHistory {
+    pub oldest_block: BlockHeight,
+    /// Base fee per block, plus one trailing entry for the next block
+    pub base_fees: Vec<u64>,
+    /// Fraction of each block's gas limit that was used, between 0 and 1
+    pub gas_used_ratio: Vec<f64>,
+    /// Priority fees per block at each requested percentile
+    #[serde(default)]
+    pub rewards: Vec<Vec<u64>>,
+    /// Percentiles the rewards were computed at, as requested
+    #[serde(default)]
+    pub percentiles: Vec<f64>,
+}
+
+impl FeeHistory {
+    /// Base fee of the block after the newest one in the range
+    pub fn next_base_fee(&self) -> Option<u64> {
+        self.base_fees.last().copied()
+    }
+
+    /// Median across blocks of the priority fee paid at `percentile`
+    ///
+    /// `None` if `percentile` was not requested or no block carried rewards.
+    pub fn priority_fee(&self, percentile: f64) -> Option<u64> {
+        let index = self.percentiles.iter().position(|p| *p == percentile)?;
+        let mut fees: Vec<u64> = self.rewards.iter().filter_map(|block| block.get(index).copied()).collect();
+        if fees.is_empty() {
+            return None;
+        }
+        fees.sort_unstable();
+        Some(fees[fees.len() / 2])
+    }
+
+    /// Gas price likely to be included promptly: next base fee plus the priority fee at `percentile`
+    pub fn suggest_gas_price(&self, percentile: f64) -> Option<u64> {
+        Some(self.next_base_fee()?.saturating_add(self.priority_fee(percentile)?))
+    }
+
+    /// Mean gas utilization across the range
+    pub fn average_gas_used_ratio(&self) -> f64 {
+        if self.gas_used_ratio.is_empty() {
+            return 0.0;
+        }
+        self.gas_used_ratio.iter().sum::<f64>() / self.gas_used_ratio.len() as f64
+    }
+}
+
+/// Chain activity over a recent window of blocks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainStats {
+    pub height: BlockHeight,
+    /// Blocks the averages below cover
+    pub window_blocks: u64,
+    pub transactions_per_second: f64,
+    pub average_block_time_ms: u64,
+    /// Fraction of the gas limit used, between 0 and 1
+    pub gas_utilization: f64,
+    pub total_transactions: u64,
+}
@@ -2,16 +2,37 @@
 
 use crate::{Result, EtherlinkConfig, EtherlinkError, Address};
 use crate::clients::{ServiceClient, ApiResponse};
+use crate::clients::context::{CallContext, WithContext};
+use crate::cache::TtlCache;
+use crate::coalesce::SingleFlight;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::clients::grpc::{CallKind, GrpcRoute};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::proto::gid::v1::{self as gid_pb, identity_service_client::IdentityServiceClient};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::ChannelManager;
 use reqwest::Client as HttpClient;
 use serde::{Serialize, Deserialize};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Client for GID identity management service
 #[derive(Debug, Clone)]
 pub struct GidClient {
     base_url: String,
     http_client: Arc<HttpClient>,
+    context: CallContext,
+    policy_cache: Option<Arc<PolicyCache>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    grpc: Option<GrpcRoute>,
+}
+
+/// Compiled Guardian policy bundle held for local evaluation
+#[derive(Debug)]
+struct PolicyCache {
+    bundle: Mutex<TtlCache<(), PolicyBundle>>,
+    bundle_flight: SingleFlight<(), PolicyBundle>,
 }
 
 impl GidClient {
@@ -21,16 +42,74 @@ impl GidClient {
         Self {
             base_url,
             http_client,
+            context: CallContext::default(),
+            policy_cache: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            grpc: None,
+        }
+    }
+
+    /// Call GID over gRPC at `endpoint` first, see [`crate::clients::grpc`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_grpc(mut self, channels: ChannelManager, endpoint: impl Into<String>) -> Self {
+        self.grpc = Some(GrpcRoute::new(channels, endpoint.into()));
+        self
+    }
+
+    /// Client for calls with a different endpoint, auth token or timeout
+    ///
+    /// The derived client has no policy cache, since another endpoint or caller
+    /// may be subject to a different bundle.
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            base_url: context.base_url().unwrap_or_else(|| self.base_url.clone()),
+            http_client: self.http_client.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            grpc: self.grpc.as_ref().and_then(|grpc| grpc.with_context(&context)),
+            context,
+            policy_cache: None,
+        }
+    }
+
+    /// Evaluate Guardian policies locally against a cached bundle
+    ///
+    /// The bundle is refetched once `ttl` has passed. Requests the bundle cannot
+    /// decide (no matching rule without a default, or a condition on an attribute
+    /// missing from the request context) are still sent to the server.
+    pub fn with_policy_cache(mut self, ttl: Duration) -> Self {
+        self.policy_cache = Some(Arc::new(PolicyCache {
+            bundle: Mutex::new(TtlCache::new(ttl, 1)),
+            bundle_flight: SingleFlight::new(),
+        }));
+        self
+    }
+
+    /// Drop the cached bundle, e.g. after a policy change notification
+    pub fn invalidate_policy_cache(&self) {
+        if let Some(cache) = &self.policy_cache {
+            cache.bundle.lock().unwrap().clear();
         }
     }
 
     /// Create a new identity
     pub async fn create_identity(&self, request: CreateIdentityRequest) -> Result<Identity> {
+        self.context.require(&[Permission::CreateIdentity])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gid_pb::CreateIdentityRequest::from(request.clone());
+            let identity = grpc.call(&self.context, CallKind::Write, message, |channel, request| async move {
+                IdentityServiceClient::new(channel).create_identity(request).await
+            }).await?;
+            if let Some(identity) = identity {
+                return identity.try_into();
+            }
+        }
         let url = format!("{}/identities", self.base_url);
         let response: ApiResponse<Identity> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&request)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -42,10 +121,22 @@ impl GidClient {
 
     /// Resolve an identity by DID
     pub async fn resolve_identity(&self, did: &str) -> Result<IdentityDocument> {
+        self.context.require(&[Permission::ReadIdentity])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gid_pb::ResolveIdentityRequest { did: did.to_string() };
+            let document = grpc.call(&self.context, CallKind::Read, message, |channel, request| async move {
+                IdentityServiceClient::new(channel).resolve_identity(request).await
+            }).await?;
+            if let Some(document) = document {
+                return document.try_into();
+            }
+        }
         let url = format!("{}/identities/resolve/{}", self.base_url, did);
         let response: ApiResponse<IdentityDocument> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -60,8 +151,9 @@ impl GidClient {
         let url = format!("{}/guardian/tokens", self.base_url);
         let response: ApiResponse<AccessToken> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&request)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -71,13 +163,79 @@ impl GidClient {
         response.into_result()
     }
 
-    /// Evaluate Guardian policy
+    /// Evaluate Guardian policy, locally when a policy cache is enabled and the bundle decides
     pub async fn evaluate_policy(&self, request: PolicyRequest) -> Result<PolicyDecision> {
+        if let Some(cache) = &self.policy_cache {
+            // An unreachable bundle endpoint should not block decisions the server can still make
+            let cached = cache.bundle.lock().unwrap().get(&());
+            let bundle = match cached {
+                Some(bundle) => Some(bundle),
+                None => match cache.bundle_flight.run((), || self.fetch_policy_bundle()).await {
+                    Ok(bundle) => {
+                        cache.bundle.lock().unwrap().insert((), bundle.clone());
+                        Some(bundle)
+                    }
+                    Err(_) => None,
+                },
+            };
+            if let Some(decision) = bundle.and_then(|bundle| bundle.evaluate(&request)) {
+                return Ok(decision);
+            }
+        }
+
+        self.evaluate_policy_remote(request).await
+    }
+
+    /// Evaluate Guardian policy on the server, bypassing any policy cache
+    pub async fn evaluate_policy_remote(&self, request: PolicyRequest) -> Result<PolicyDecision> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gid_pb::PolicyRequest::from(request.clone());
+            let decision = grpc.call(&self.context, CallKind::Read, message, |channel, request| async move {
+                IdentityServiceClient::new(channel).evaluate_policy(request).await
+            }).await?;
+            if let Some(decision) = decision {
+                return Ok(decision.into());
+            }
+        }
         let url = format!("{}/guardian/evaluate", self.base_url);
         let response: ApiResponse<PolicyDecision> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&request)
-            .send()
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Fetch the server's request signing policy: allowed clock skew and current time
+    pub async fn get_request_signing_policy(&self) -> Result<RequestSigningPolicy> {
+        let url = format!("{}/guardian/signing-policy", self.base_url);
+        let response: ApiResponse<RequestSigningPolicy> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Fetch the compiled Guardian policy bundle
+    pub async fn fetch_policy_bundle(&self) -> Result<PolicyBundle> {
+        let url = format!("{}/guardian/policies/bundle", self.base_url);
+        let response: ApiResponse<PolicyBundle> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -89,11 +247,13 @@ impl GidClient {
 
     /// Update identity document
     pub async fn update_identity(&self, did: &str, update: IdentityUpdate) -> Result<IdentityDocument> {
+        self.context.require(&[Permission::WriteIdentity])?;
         let url = format!("{}/identities/{}", self.base_url, did);
         let response: ApiResponse<IdentityDocument> = self.http_client
             .put(&url)
+            .with_context(&self.context)
             .json(&update)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -105,10 +265,22 @@ impl GidClient {
 
     /// Get identities by address
     pub async fn get_identities_by_address(&self, address: &Address) -> Result<Vec<Identity>> {
+        self.context.require(&[Permission::ReadIdentity])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gid_pb::GetIdentitiesByAddressRequest { address: address.as_str().to_string() };
+            let response = grpc.call(&self.context, CallKind::Read, message, |channel, request| async move {
+                IdentityServiceClient::new(channel).get_identities_by_address(request).await
+            }).await?;
+            if let Some(response) = response {
+                return response.identities.into_iter().map(Identity::try_from).collect();
+            }
+        }
         let url = format!("{}/identities/address/{}", self.base_url, address.as_str());
         let response: ApiResponse<Vec<Identity>> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -119,7 +291,8 @@ impl GidClient {
     }
 }
 
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl ServiceClient for GidClient {
     fn service_name(&self) -> &'static str {
         "gid"
@@ -133,7 +306,8 @@ impl ServiceClient for GidClient {
         let url = format!("{}/health", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -147,7 +321,8 @@ impl ServiceClient for GidClient {
         let url = format!("{}/status", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -242,6 +417,148 @@ pub struct PolicyDecision {
     pub expires_at: Option<u64>,
 }
 
+/// How the Guardian checks signed requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestSigningPolicy {
+    /// Largest accepted difference between the request timestamp and server time
+    pub max_skew_seconds: u64,
+    /// Server clock (unix seconds) when the policy was served
+    pub server_time: u64,
+}
+
+/// Guardian policies compiled for client-side evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyBundle {
+    pub version: String,
+    pub rules: Vec<PolicyRule>,
+    /// Effect when no rule matches; `None` defers such requests to the server
+    #[serde(default)]
+    pub default_effect: Option<PolicyEffect>,
+}
+
+impl PolicyBundle {
+    /// Decide `request` locally, or `None` if only the server can
+    ///
+    /// A matching deny rule always wins. Otherwise a rule whose conditions name an
+    /// attribute absent from the request context makes the outcome unknown.
+    pub fn evaluate(&self, request: &PolicyRequest) -> Option<PolicyDecision> {
+        let mut allowed_by = None;
+        let mut undecided = false;
+        for rule in self.rules.iter().filter(|rule| rule.applies_to(request)) {
+            match rule.conditions_hold(&request.context) {
+                Some(true) if rule.effect == PolicyEffect::Deny => return Some(self.decision(false, format!("denied by rule {}", rule.id))),
+                Some(true) => allowed_by = allowed_by.or(Some(rule)),
+                Some(false) => {}
+                None => undecided = true,
+            }
+        }
+        if undecided {
+            return None;
+        }
+        match (allowed_by, &self.default_effect) {
+            (Some(rule), _) => Some(self.decision(true, format!("allowed by rule {}", rule.id))),
+            (None, Some(effect)) => Some(self.decision(*effect == PolicyEffect::Allow, "no matching rule".to_string())),
+            (None, None) => None,
+        }
+    }
+
+    fn decision(&self, allowed: bool, reason: String) -> PolicyDecision {
+        PolicyDecision {
+            allowed,
+            reason: format!("{} (bundle {})", reason, self.version),
+            conditions: Vec::new(),
+            expires_at: None,
+        }
+    }
+}
+
+/// One rule of a [`PolicyBundle`]
+///
+/// Identity, action and resource patterns are exact strings, `*`, or a prefix
+/// ending in `*`; an empty list matches anything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub id: String,
+    pub effect: PolicyEffect,
+    #[serde(default)]
+    pub identities: Vec<String>,
+    #[serde(default)]
+    pub actions: Vec<String>,
+    #[serde(default)]
+    pub resources: Vec<String>,
+    /// All must hold for the rule to take effect
+    #[serde(default)]
+    pub conditions: Vec<AttributeCondition>,
+}
+
+impl PolicyRule {
+    fn applies_to(&self, request: &PolicyRequest) -> bool {
+        let matches = |patterns: &[String], value: &str| {
+            patterns.is_empty() || patterns.iter().any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => value.starts_with(prefix),
+                None => pattern == value,
+            })
+        };
+        matches(&self.identities, &request.identity)
+            && matches(&self.actions, &request.action)
+            && matches(&self.resources, &request.resource)
+    }
+
+    /// `None` if a condition names an attribute the context lacks
+    fn conditions_hold(&self, context: &HashMap<String, serde_json::Value>) -> Option<bool> {
+        let mut holds = true;
+        for condition in &self.conditions {
+            holds &= condition.holds(context.get(&condition.attribute)?);
+        }
+        Some(holds)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyEffect {
+    Allow,
+    Deny,
+}
+
+/// Test of one request context attribute
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributeCondition {
+    pub attribute: String,
+    pub op: ConditionOp,
+    pub value: serde_json::Value,
+}
+
+impl AttributeCondition {
+    fn holds(&self, actual: &serde_json::Value) -> bool {
+        let ordering = || match (actual.as_f64(), self.value.as_f64()) {
+            (Some(actual), Some(expected)) => actual.partial_cmp(&expected),
+            _ => None,
+        };
+        match self.op {
+            ConditionOp::Eq => *actual == self.value,
+            ConditionOp::Ne => *actual != self.value,
+            ConditionOp::Lt => ordering().is_some_and(|o| o.is_lt()),
+            ConditionOp::Le => ordering().is_some_and(|o| o.is_le()),
+            ConditionOp::Gt => ordering().is_some_and(|o| o.is_gt()),
+            ConditionOp::Ge => ordering().is_some_and(|o| o.is_ge()),
+            ConditionOp::In => self.value.as_array().is_some_and(|values| values.contains(actual)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConditionOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IdentityUpdate {
     pub verification_method: Option<Vec<VerificationMethod>>,
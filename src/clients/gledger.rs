@@ -1,7 +1,18 @@
 //! GLEDGER (Token Ledger) client implementation
 
-use crate::{Result, EtherlinkConfig, EtherlinkError, Address, TxHash, TokenType};
+use crate::{Result, EtherlinkConfig, EtherlinkError, Address, TxHash, TokenType, BlockHeight};
 use crate::clients::{ServiceClient, ApiResponse};
+use crate::clients::context::{CallContext, WithContext};
+use crate::auth::Permission;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::pagination::{PageConfig, PageStream};
+use crate::coalesce::{CoalesceSnapshot, CoalesceStats, SingleFlight};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::clients::grpc::{CallKind, GrpcRoute};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::proto::gledger::v1::{self as gledger_pb, ledger_service_client::LedgerServiceClient};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::ChannelManager;
 use reqwest::Client as HttpClient;
 use serde::{Serialize, Deserialize};
 use std::sync::Arc;
@@ -12,25 +23,78 @@ use std::collections::HashMap;
 pub struct GledgerClient {
     base_url: String,
     http_client: Arc<HttpClient>,
+    context: CallContext,
+    coalesce_stats: Arc<CoalesceStats>,
+    balance_flights: SingleFlight<(Address, TokenType), u64>,
+    all_balance_flights: SingleFlight<Address, TokenBalances>,
+    #[cfg(not(target_arch = "wasm32"))]
+    grpc: Option<GrpcRoute>,
 }
 
 impl GledgerClient {
     /// Create a new GLEDGER client
     pub fn new(config: &EtherlinkConfig, http_client: Arc<HttpClient>) -> Self {
         let base_url = format!("{}/api/v1", config.ghostd_endpoint.trim_end_matches('/'));
+        let coalesce_stats = Arc::new(CoalesceStats::default());
         Self {
             base_url,
             http_client,
+            context: CallContext::default(),
+            balance_flights: SingleFlight::with_stats(coalesce_stats.clone()),
+            all_balance_flights: SingleFlight::with_stats(coalesce_stats.clone()),
+            coalesce_stats,
+            #[cfg(not(target_arch = "wasm32"))]
+            grpc: None,
         }
     }
 
+    /// Call GLEDGER over gRPC at `endpoint` first, see [`crate::clients::grpc`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_grpc(mut self, channels: ChannelManager, endpoint: impl Into<String>) -> Self {
+        self.grpc = Some(GrpcRoute::new(channels, endpoint.into()));
+        self
+    }
+
+    /// Client for calls with a different endpoint, auth token or timeout
+    ///
+    /// The derived client does not coalesce with this one, since another endpoint
+    /// or caller may see different balances.
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            base_url: context.base_url().unwrap_or_else(|| self.base_url.clone()),
+            balance_flights: SingleFlight::with_stats(self.coalesce_stats.clone()),
+            all_balance_flights: SingleFlight::with_stats(self.coalesce_stats.clone()),
+            #[cfg(not(target_arch = "wasm32"))]
+            grpc: self.grpc.as_ref().and_then(|grpc| grpc.with_context(&context)),
+            context,
+            ..self.clone()
+        }
+    }
+
+    /// Client reading balances as of block `height`, for audits of past state
+    pub fn at_block(&self, height: BlockHeight) -> Self {
+        self.with_context(self.context.clone().at_block(height))
+    }
+
     /// Transfer tokens between accounts
     pub async fn transfer_tokens(&self, transfer: TokenTransfer) -> Result<TxHash> {
+        self.context.require(&[Permission::TransferTokens(transfer.token_type.clone())])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gledger_pb::TransferTokensRequest::from(transfer.clone());
+            let receipt = grpc.call(&self.context, CallKind::Write, message, |channel, request| async move {
+                LedgerServiceClient::new(channel).transfer_tokens(request).await
+            }).await?;
+            if let Some(receipt) = receipt {
+                return Ok(TxHash::new(receipt.tx_hash));
+            }
+        }
         let url = format!("{}/tokens/transfer", self.base_url);
         let response: ApiResponse<TransferResponse> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&transfer)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -41,12 +105,149 @@ impl GledgerClient {
         Ok(TxHash::new(transfer_response.tx_hash))
     }
 
+    /// Lock funds in escrow until a counterparty releases them or they are refunded
+    pub async fn lock_escrow(&self, lock: EscrowLock) -> Result<EscrowReceipt> {
+        self.context.require(&[Permission::TransferTokens(lock.token_type.clone())])?;
+        if lock.amount == 0 {
+            return Err(EtherlinkError::Configuration("Escrow amount must be greater than zero".to_string()));
+        }
+        let url = format!("{}/escrow", self.base_url);
+        let response: ApiResponse<EscrowReceipt> = self.http_client
+            .post(&url)
+            .with_context(&self.context)
+            .json(&lock)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Return escrowed funds to the account that locked them
+    pub async fn refund_escrow(&self, escrow_id: &str) -> Result<TxHash> {
+        self.context.require(&[Permission::SubmitTransaction])?;
+        let url = format!("{}/escrow/{}/refund", self.base_url, escrow_id);
+        let response: ApiResponse<TransferResponse> = self.http_client
+            .post(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        Ok(TxHash::new(response.into_result()?.tx_hash))
+    }
+
+    /// Pay out an escrow locked with a release policy, against the policy's threshold signature
+    pub async fn settle_escrow(&self, escrow_id: &str, settlement: &EscrowSettlement) -> Result<TxHash> {
+        self.context.require(&[Permission::SubmitTransaction])?;
+        let url = format!("{}/escrow/{}/settle", self.base_url, escrow_id);
+        let response: ApiResponse<TransferResponse> = self.http_client
+            .post(&url)
+            .with_context(&self.context)
+            .json(settlement)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        Ok(TxHash::new(response.into_result()?.tx_hash))
+    }
+
+    /// Lock funds for `lock.to`, claimable with the preimage of `lock.hash_lock` until `lock.expires_at`
+    ///
+    /// After expiry the funds go back to the locker through [`GledgerClient::refund_escrow`].
+    pub async fn lock_htlc(&self, lock: &HashLock) -> Result<EscrowReceipt> {
+        self.context.require(&[Permission::TransferTokens(lock.token_type.clone())])?;
+        if lock.amount == 0 {
+            return Err(EtherlinkError::Configuration("Escrow amount must be greater than zero".to_string()));
+        }
+        let url = format!("{}/escrow/htlc", self.base_url);
+        let response: ApiResponse<EscrowReceipt> = self.http_client
+            .post(&url)
+            .with_context(&self.context)
+            .json(lock)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Claim a hash-locked escrow with the hex preimage of its hash lock
+    pub async fn claim_htlc(&self, escrow_id: &str, preimage: &str) -> Result<TxHash> {
+        self.context.require(&[Permission::SubmitTransaction])?;
+        let url = format!("{}/escrow/{}/claim", self.base_url, escrow_id);
+        let response: ApiResponse<TransferResponse> = self.http_client
+            .post(&url)
+            .with_context(&self.context)
+            .json(&serde_json::json!({ "preimage": preimage }))
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        Ok(TxHash::new(response.into_result()?.tx_hash))
+    }
+
+    /// Every hash-locked escrow under `hash_lock`, in any state
+    pub async fn get_htlcs(&self, hash_lock: &str) -> Result<Vec<HtlcInfo>> {
+        self.context.require(&[Permission::ReadTokens])?;
+        let url = format!("{}/escrow/htlc/{}", self.base_url, hash_lock);
+        let response: ApiResponse<Vec<HtlcInfo>> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
     /// Get token balance for a specific token type
+    ///
+    /// Concurrent identical queries share a single in-flight call.
     pub async fn get_balance(&self, address: &Address, token_type: TokenType) -> Result<u64> {
+        self.context.require(&[Permission::ReadTokens])?;
+        let key = (address.clone(), token_type.clone());
+        self.balance_flights.run(key, || self.fetch_balance(address, token_type)).await
+    }
+
+    async fn fetch_balance(&self, address: &Address, token_type: TokenType) -> Result<u64> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gledger_pb::GetBalanceRequest {
+                address: address.as_str().to_string(),
+                token_type: crate::proto::ghostchain::v1::TokenType::from(token_type.clone()) as i32,
+                block_height: self.context.block.unwrap_or_default(),
+            };
+            let response = grpc.call(&self.context, CallKind::Read, message, |channel, request| async move {
+                LedgerServiceClient::new(channel).get_balance(request).await
+            }).await?;
+            if let Some(response) = response {
+                return Ok(response.balance);
+            }
+        }
         let url = format!("{}/tokens/balance/{}/{:?}", self.base_url, address.as_str(), token_type);
         let response: ApiResponse<BalanceResponse> = self.http_client
             .get(&url)
-            .send()
+            .with_block(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -59,10 +260,29 @@ impl GledgerClient {
 
     /// Get all token balances for an address
     pub async fn get_all_balances(&self, address: &Address) -> Result<TokenBalances> {
+        self.context.require(&[Permission::ReadTokens])?;
+        self.all_balance_flights.run(address.clone(), || self.fetch_all_balances(address)).await
+    }
+
+    async fn fetch_all_balances(&self, address: &Address) -> Result<TokenBalances> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gledger_pb::GetAllBalancesRequest {
+                address: address.as_str().to_string(),
+                block_height: self.context.block.unwrap_or_default(),
+            };
+            let balances = grpc.call(&self.context, CallKind::Read, message, |channel, request| async move {
+                LedgerServiceClient::new(channel).get_all_balances(request).await
+            }).await?;
+            if let Some(balances) = balances {
+                return Ok(balances.into());
+            }
+        }
         let url = format!("{}/tokens/balances/{}", self.base_url, address.as_str());
         let response: ApiResponse<TokenBalances> = self.http_client
             .get(&url)
-            .send()
+            .with_block(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -74,11 +294,23 @@ impl GledgerClient {
 
     /// Mint tokens (requires appropriate permissions)
     pub async fn mint_tokens(&self, mint: TokenMint) -> Result<TxHash> {
+        self.context.require(&[Permission::MintTokens(mint.token_type.clone())])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gledger_pb::MintTokensRequest::from(mint.clone());
+            let receipt = grpc.call(&self.context, CallKind::Write, message, |channel, request| async move {
+                LedgerServiceClient::new(channel).mint_tokens(request).await
+            }).await?;
+            if let Some(receipt) = receipt {
+                return Ok(TxHash::new(receipt.tx_hash));
+            }
+        }
         let url = format!("{}/tokens/mint", self.base_url);
         let response: ApiResponse<TransferResponse> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&mint)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -91,11 +323,23 @@ impl GledgerClient {
 
     /// Burn tokens
     pub async fn burn_tokens(&self, burn: TokenBurn) -> Result<TxHash> {
+        self.context.require(&[Permission::BurnTokens(burn.token_type.clone())])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gledger_pb::BurnTokensRequest::from(burn.clone());
+            let receipt = grpc.call(&self.context, CallKind::Write, message, |channel, request| async move {
+                LedgerServiceClient::new(channel).burn_tokens(request).await
+            }).await?;
+            if let Some(receipt) = receipt {
+                return Ok(TxHash::new(receipt.tx_hash));
+            }
+        }
         let url = format!("{}/tokens/burn", self.base_url);
         let response: ApiResponse<TransferResponse> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&burn)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -108,10 +352,12 @@ impl GledgerClient {
 
     /// Get token economics information
     pub async fn get_token_economics(&self) -> Result<TokenEconomics> {
+        self.context.require(&[Permission::ReadTokens])?;
         let url = format!("{}/tokens/economics", self.base_url);
         let response: ApiResponse<TokenEconomics> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -123,6 +369,11 @@ impl GledgerClient {
 
     /// Get transaction history for an address
     pub async fn get_transaction_history(&self, address: &Address, limit: Option<u32>) -> Result<Vec<TokenTransaction>> {
+        self.context.require(&[Permission::ReadTokens])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(history) = self.grpc_history(address, 0, limit.unwrap_or_default()).await? {
+            return Ok(history);
+        }
         let mut url = format!("{}/tokens/history/{}", self.base_url, address.as_str());
         if let Some(limit) = limit {
             url.push_str(&format!("?limit={}", limit));
@@ -130,7 +381,40 @@ impl GledgerClient {
 
         let response: ApiResponse<Vec<TokenTransaction>> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    /// Stream the full transaction history of an address, fetching pages as they are consumed
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn stream_transaction_history(&self, address: &Address, config: PageConfig) -> PageStream<TokenTransaction> {
+        let client = self.clone();
+        let address = address.clone();
+        PageStream::new(0, config, move |offset, limit| {
+            let client = client.clone();
+            let address = address.clone();
+            async move { client.get_transaction_history_page(&address, offset, limit).await }
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn get_transaction_history_page(&self, address: &Address, offset: u64, limit: u32) -> Result<Vec<TokenTransaction>> {
+        self.context.require(&[Permission::ReadTokens])?;
+        if let Some(history) = self.grpc_history(address, offset, limit).await? {
+            return Ok(history);
+        }
+        let url = format!("{}/tokens/history/{}?offset={}&limit={}", self.base_url, address.as_str(), offset, limit);
+        let response: ApiResponse<Vec<TokenTransaction>> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -139,9 +423,30 @@ impl GledgerClient {
 
         response.into_result()
     }
+
+    /// History page over gRPC; `None` when REST should serve it
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn grpc_history(&self, address: &Address, offset: u64, limit: u32) -> Result<Option<Vec<TokenTransaction>>> {
+        let Some(grpc) = &self.grpc else {
+            return Ok(None);
+        };
+        let message = gledger_pb::GetTransactionHistoryRequest { address: address.as_str().to_string(), limit, offset };
+        let response = grpc.call(&self.context, CallKind::Read, message, |channel, request| async move {
+            LedgerServiceClient::new(channel).get_transaction_history(request).await
+        }).await?;
+        response
+            .map(|response| response.transactions.into_iter().map(TokenTransaction::try_from).collect())
+            .transpose()
+    }
+
+    /// Get request coalescing statistics for this client's read paths
+    pub fn coalesce_stats(&self) -> CoalesceSnapshot {
+        self.coalesce_stats.snapshot()
+    }
 }
 
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl ServiceClient for GledgerClient {
     fn service_name(&self) -> &'static str {
         "gledger"
@@ -155,7 +460,8 @@ impl ServiceClient for GledgerClient {
         let url = format!("{}/health", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -169,7 +475,8 @@ impl ServiceClient for GledgerClient {
         let url = format!("{}/status", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -207,6 +514,90 @@ pub struct TokenBurn {
     pub reason: String,
 }
 
+/// Funds to hold until a trade settles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowLock {
+    pub from: Address,
+    pub token_type: TokenType,
+    pub amount: u64,
+    /// What the escrow backs, e.g. `cns-offer:alice.ghost`
+    pub reference: String,
+    /// Unix seconds after which the funds may be refunded without the counterparty
+    pub expires_at: Option<u64>,
+    /// Keys that must jointly approve paying the escrow out; unset lets the counterparty release it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_policy: Option<EscrowPolicy>,
+}
+
+/// `threshold` of `public_keys` must sign to settle an escrow
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowPolicy {
+    pub threshold: u32,
+    pub public_keys: Vec<String>,
+    pub algorithm: crate::CryptoAlgorithm,
+}
+
+/// Which way an escrow is paid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowOutcome {
+    /// To the counterparty
+    Release,
+    /// Back to the account that locked the funds
+    Refund,
+}
+
+/// Funds claimable by `to` with the preimage of `hash_lock`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashLock {
+    pub from: Address,
+    pub to: Address,
+    pub token_type: TokenType,
+    pub amount: u64,
+    /// Hex SHA-256 of the secret preimage
+    pub hash_lock: String,
+    /// Unix seconds after which only a refund to `from` is possible
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HtlcState {
+    Locked,
+    Claimed,
+    Refunded,
+}
+
+/// A hash-locked escrow as GLEDGER holds it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HtlcInfo {
+    pub escrow_id: String,
+    #[serde(flatten)]
+    pub lock: HashLock,
+    pub state: HtlcState,
+    /// Hex preimage, revealed once the escrow is claimed
+    pub preimage: Option<String>,
+}
+
+/// Threshold-signed instruction to pay out an escrow
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowSettlement {
+    pub outcome: EscrowOutcome,
+    pub to: Address,
+    /// Message the threshold signature covers
+    pub message: Vec<u8>,
+    pub signature: String,
+    pub signature_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowReceipt {
+    pub escrow_id: String,
+    pub tx_hash: String,
+    pub token_type: TokenType,
+    pub amount: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransferResponse {
     pub tx_hash: String,
@@ -256,4 +647,7 @@ pub struct TokenTransaction {
     pub timestamp: u64,
     pub block_height: u64,
     pub memo: Option<String>,
+    /// Fee paid by the sender, in units of `token_type`
+    #[serde(default)]
+    pub fee: u64,
 }
\ No newline at end of file
@@ -0,0 +1,145 @@
+//! Transport selection for the WALLETD, GID, GSIG and GLEDGER clients
+//!
+//! Each of these services is reachable over REST and, where deployed, over gRPC.
+//! [`ServiceTransports`] picks one per service. With [`ServiceTransport::Grpc`] a
+//! client makes each call over gRPC first and repeats it over REST only when that
+//! cannot submit a write twice: when no channel to the gRPC service could be opened,
+//! when the service lacks the method (`UNIMPLEMENTED`), or, for reads only, when the
+//! service is `UNAVAILABLE`. An `UNAVAILABLE` write may already have been applied,
+//! so it is returned as an error instead. Calls without a gRPC method always use
+//! REST, as do clients derived with a [`CallContext`] pointing at another endpoint,
+//! whose gRPC endpoint is unknown.
+//!
+//! The public client API is the same either way.
+
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::clients::context::CallContext;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::ChannelManager;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::Result;
+#[cfg(not(target_arch = "wasm32"))]
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use tonic::transport::Channel;
+
+/// How a service client reaches its service
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceTransport {
+    /// REST over the service's `/api/v1` endpoint
+    #[default]
+    Http,
+    /// gRPC, falling back to REST per call; REST only in the browser
+    Grpc,
+}
+
+/// Transport for each service with a gRPC API
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceTransports {
+    pub walletd: ServiceTransport,
+    pub gid: ServiceTransport,
+    pub gsig: ServiceTransport,
+    pub gledger: ServiceTransport,
+}
+
+impl ServiceTransports {
+    /// gRPC for every service
+    pub fn grpc() -> Self {
+        Self {
+            walletd: ServiceTransport::Grpc,
+            gid: ServiceTransport::Grpc,
+            gsig: ServiceTransport::Grpc,
+            gledger: ServiceTransport::Grpc,
+        }
+    }
+
+    /// Whether any service uses gRPC
+    pub fn any_grpc(&self) -> bool {
+        [self.walletd, self.gid, self.gsig, self.gledger].contains(&ServiceTransport::Grpc)
+    }
+}
+
+/// Whether a call changes state on the service, which decides when it may be retried over REST
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CallKind {
+    Read,
+    Write,
+}
+
+/// gRPC endpoint a client calls first, over a shared channel
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub(crate) struct GrpcRoute {
+    channels: ChannelManager,
+    endpoint: String,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GrpcRoute {
+    pub(crate) fn new(channels: ChannelManager, endpoint: String) -> Self {
+        Self { channels, endpoint }
+    }
+
+    /// Route for a client derived with `context`
+    ///
+    /// `None` when the context sends calls to another REST endpoint: the gRPC
+    /// endpoint serving that one is unknown, so those calls use REST only.
+    pub(crate) fn with_context(&self, context: &CallContext) -> Option<Self> {
+        context.endpoint.is_none().then(|| self.clone())
+    }
+
+    /// Make one call, carrying the context's bearer token, timeout and correlation ID
+    ///
+    /// `Ok(None)` means the call certainly did not reach the service and should be
+    /// made over REST.
+    pub(crate) async fn call<Req, Resp, F, Fut>(&self, context: &CallContext, kind: CallKind, message: Req, call: F) -> Result<Option<Resp>>
+    where
+        F: FnOnce(Channel, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<Resp>, tonic::Status>>,
+    {
+        let channel = match self.channels.get_channel(&self.endpoint).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                tracing::debug!("No gRPC channel to {}, using REST: {}", self.endpoint, e);
+                return Ok(None);
+            }
+        };
+
+        let mut request = tonic::Request::new(message);
+        if let Some(token) = &context.auth_token {
+            let value = format!("Bearer {}", token)
+                .parse()
+                .map_err(|_| crate::EtherlinkError::Configuration("Auth token is not a valid header value".to_string()))?;
+            request.metadata_mut().insert("authorization", value);
+        }
+        if let Some(timeout) = context.timeout {
+            request.set_timeout(timeout);
+        }
+        crate::correlation::tag_grpc_request(&mut request, context.correlation_id.clone());
+
+        match call(channel, request).await {
+            Ok(response) => {
+                self.channels.report_success(&self.endpoint).await;
+                Ok(Some(response.into_inner()))
+            }
+            Err(status) if status.code() == tonic::Code::Unimplemented => {
+                tracing::debug!("gRPC endpoint {} lacks the method, using REST", self.endpoint);
+                Ok(None)
+            }
+            Err(status) if status.code() == tonic::Code::Unavailable => {
+                self.channels.report_failure(&self.endpoint).await;
+                if kind == CallKind::Write {
+                    // The service may have applied it before the connection went away
+                    return Err(status.into());
+                }
+                tracing::debug!("gRPC endpoint {} unavailable, using REST: {}", self.endpoint, status.message());
+                Ok(None)
+            }
+            Err(status) => Err(status.into()),
+        }
+    }
+}
@@ -1,8 +1,17 @@
 //! GSIG (Ghost Signature) client implementation
 
-use crate::{Result, EtherlinkConfig, EtherlinkError, Address};
+use crate::{Result, EtherlinkConfig, EtherlinkError, Address, SecretString};
 use crate::clients::{ServiceClient, ApiResponse};
+use crate::clients::context::{CallContext, WithContext};
+use crate::auth::Permission;
+use crate::auth::eip712::{self, TypedData};
 use crate::clients::walletd::CryptoAlgorithm;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::clients::grpc::{CallKind, GrpcRoute};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::proto::gsig::v1::{self as gsig_pb, signature_service_client::SignatureServiceClient};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::ChannelManager;
 use reqwest::Client as HttpClient;
 use serde::{Serialize, Deserialize};
 use std::sync::Arc;
@@ -12,6 +21,9 @@ use std::sync::Arc;
 pub struct GsigClient {
     base_url: String,
     http_client: Arc<HttpClient>,
+    context: CallContext,
+    #[cfg(not(target_arch = "wasm32"))]
+    grpc: Option<GrpcRoute>,
 }
 
 impl GsigClient {
@@ -21,16 +33,49 @@ impl GsigClient {
         Self {
             base_url,
             http_client,
+            context: CallContext::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            grpc: None,
+        }
+    }
+
+    /// Call GSIG over gRPC at `endpoint` first, see [`crate::clients::grpc`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_grpc(mut self, channels: ChannelManager, endpoint: impl Into<String>) -> Self {
+        self.grpc = Some(GrpcRoute::new(channels, endpoint.into()));
+        self
+    }
+
+    /// Client for calls with a different endpoint, auth token or timeout
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            base_url: context.base_url().unwrap_or_else(|| self.base_url.clone()),
+            http_client: self.http_client.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            grpc: self.grpc.as_ref().and_then(|grpc| grpc.with_context(&context)),
+            context,
         }
     }
 
     /// Sign a message
     pub async fn sign(&self, request: SignRequest) -> Result<SignatureResponse> {
+        self.context.require(&[Permission::Sign])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gsig_pb::SignRequest::from(request.clone());
+            let signature = grpc.call(&self.context, CallKind::Write, message, |channel, request| async move {
+                SignatureServiceClient::new(channel).sign(request).await
+            }).await?;
+            if let Some(signature) = signature {
+                return signature.try_into();
+            }
+        }
         let url = format!("{}/signatures/sign", self.base_url);
         let response: ApiResponse<SignatureResponse> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&request)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -40,13 +85,51 @@ impl GsigClient {
         response.into_result()
     }
 
+    /// Have the key held for `signer` sign EIP-712 typed data
+    ///
+    /// The returned `r ++ s ++ v` signature is checked to recover to `signer` before it
+    /// is handed back.
+    pub async fn sign_typed_data(&self, data: &TypedData, signer: &Address) -> Result<SignatureResponse> {
+        self.context.require(&[Permission::Sign])?;
+        let url = format!("{}/signatures/sign-typed-data", self.base_url);
+        let request = SignTypedDataRequest { typed_data: data.clone(), address: signer.clone() };
+        let response: ApiResponse<SignatureResponse> = self.http_client
+            .post(&url)
+            .with_context(&self.context)
+            .json(&request)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        let signature = response.into_result()?;
+        if !eip712::verify_typed_data(data, &signature.signature, signer)? {
+            return Err(EtherlinkError::Crypto(format!("Typed data signature was not made by {}", signer)));
+        }
+        Ok(signature)
+    }
+
     /// Verify a signature
     pub async fn verify(&self, request: VerifyRequest) -> Result<VerificationResult> {
+        self.context.require(&[Permission::Verify])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gsig_pb::VerifyRequest::from(request.clone());
+            let result = grpc.call(&self.context, CallKind::Read, message, |channel, request| async move {
+                SignatureServiceClient::new(channel).verify(request).await
+            }).await?;
+            if let Some(result) = result {
+                return result.try_into();
+            }
+        }
         let url = format!("{}/signatures/verify", self.base_url);
         let response: ApiResponse<VerificationResult> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&request)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -58,11 +141,25 @@ impl GsigClient {
 
     /// Batch verify multiple signatures
     pub async fn batch_verify(&self, requests: Vec<VerifyRequest>) -> Result<Vec<VerificationResult>> {
+        self.context.require(&[Permission::Verify])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = gsig_pb::BatchVerifyRequest {
+                requests: requests.iter().cloned().map(gsig_pb::VerifyRequest::from).collect(),
+            };
+            let response = grpc.call(&self.context, CallKind::Read, message, |channel, request| async move {
+                SignatureServiceClient::new(channel).batch_verify(request).await
+            }).await?;
+            if let Some(response) = response {
+                return response.results.into_iter().map(VerificationResult::try_from).collect();
+            }
+        }
         let url = format!("{}/signatures/batch/verify", self.base_url);
         let response: ApiResponse<Vec<VerificationResult>> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&requests)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -74,11 +171,13 @@ impl GsigClient {
 
     /// Create a threshold signature scheme
     pub async fn create_threshold_signature(&self, request: ThresholdSignatureRequest) -> Result<ThresholdSignatureResponse> {
+        self.context.require(&[Permission::ThresholdSign])?;
         let url = format!("{}/signatures/threshold", self.base_url);
         let response: ApiResponse<ThresholdSignatureResponse> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&request)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -90,10 +189,20 @@ impl GsigClient {
 
     /// Get supported signature algorithms
     pub async fn get_supported_algorithms(&self) -> Result<Vec<AlgorithmInfo>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let response = grpc.call(&self.context, CallKind::Read, (), |channel, request| async move {
+                SignatureServiceClient::new(channel).get_supported_algorithms(request).await
+            }).await?;
+            if let Some(response) = response {
+                return response.algorithms.into_iter().map(AlgorithmInfo::try_from).collect();
+            }
+        }
         let url = format!("{}/signatures/algorithms", self.base_url);
         let response: ApiResponse<Vec<AlgorithmInfo>> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -108,7 +217,8 @@ impl GsigClient {
         let url = format!("{}/signatures/metrics", self.base_url);
         let response: ApiResponse<SignatureMetrics> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -119,7 +229,8 @@ impl GsigClient {
     }
 }
 
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl ServiceClient for GsigClient {
     fn service_name(&self) -> &'static str {
         "gsig"
@@ -133,7 +244,8 @@ impl ServiceClient for GsigClient {
         let url = format!("{}/health", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -147,7 +259,8 @@ impl ServiceClient for GsigClient {
         let url = format!("{}/status", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -164,11 +277,17 @@ impl ServiceClient for GsigClient {
 pub struct SignRequest {
     pub message: Vec<u8>,
     pub algorithm: CryptoAlgorithm,
-    pub private_key: Option<String>, // For client-side signing
+    pub private_key: Option<SecretString>, // For client-side signing
     pub key_id: Option<String>,      // For server-side signing
     pub address: Option<Address>,    // For wallet-based signing
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignTypedDataRequest {
+    pub typed_data: TypedData,
+    pub address: Address,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignatureResponse {
     pub signature: String,
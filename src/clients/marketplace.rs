@@ -0,0 +1,143 @@
+//! Secondary market for CNS domains
+//!
+//! Listings and offers live on CNS; an offer's funds are locked in a GLEDGER escrow
+//! before CNS sees it, and accepting the offer settles the domain transfer and the
+//! payment together. [`DomainMarketplace`] ties the two clients together.
+
+use crate::{Result, Address, EtherlinkError, TokenType};
+use crate::clients::cns::{CnsClient, DomainListing, DomainOffer, DomainSale, ListingFilter, NewListing, NewOffer};
+use crate::clients::gledger::{EscrowLock, GledgerClient};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::events::{EtherlinkEvent, EventBus};
+use tracing::warn;
+
+/// Domain listings and escrowed offers across CNS and GLEDGER
+#[derive(Debug, Clone)]
+pub struct DomainMarketplace {
+    cns: CnsClient,
+    gledger: GledgerClient,
+    #[cfg(not(target_arch = "wasm32"))]
+    events: Option<EventBus>,
+}
+
+impl DomainMarketplace {
+    pub fn new(cns: CnsClient, gledger: GledgerClient) -> Self {
+        Self {
+            cns,
+            gledger,
+            #[cfg(not(target_arch = "wasm32"))]
+            events: None,
+        }
+    }
+
+    /// Publish [`EtherlinkEvent::DomainSold`] to `events` when an offer is accepted
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub async fn list_domain(&self, listing: NewListing) -> Result<DomainListing> {
+        self.cns.list_domain_for_sale(listing).await
+    }
+
+    pub async fn cancel_listing(&self, listing_id: &str) -> Result<DomainListing> {
+        self.cns.cancel_listing(listing_id).await
+    }
+
+    pub async fn browse(&self, filter: &ListingFilter) -> Result<Vec<DomainListing>> {
+        self.cns.get_listings(filter).await
+    }
+
+    pub async fn offers(&self, domain: &str) -> Result<Vec<DomainOffer>> {
+        self.cns.get_offers(domain).await
+    }
+
+    /// Lock `amount` in escrow and offer it for `domain`
+    ///
+    /// If CNS refuses the offer the escrow is refunded before the error is returned.
+    /// When the outcome is unknown (the request or its answer was lost) the domain's
+    /// offers are checked: an offer holding the escrow is returned, and only if there
+    /// is none is the escrow refunded. If even that check fails the escrow stays
+    /// locked, since CNS may still settle against it.
+    pub async fn make_offer(
+        &self,
+        domain: &str,
+        buyer: Address,
+        amount: u64,
+        token: TokenType,
+        expires_at: Option<u64>,
+    ) -> Result<DomainOffer> {
+        let escrow = self
+            .gledger
+            .lock_escrow(EscrowLock {
+                from: buyer.clone(),
+                token_type: token.clone(),
+                amount,
+                reference: format!("cns-offer:{}", domain),
+                expires_at,
+                release_policy: None,
+            })
+            .await?;
+
+        let offer = NewOffer {
+            domain: domain.to_string(),
+            buyer,
+            amount,
+            token,
+            escrow_id: escrow.escrow_id.clone(),
+            expires_at,
+        };
+        let error = match self.cns.make_offer(offer).await {
+            Ok(offer) => return Ok(offer),
+            Err(e) if is_rejection(&e) => e,
+            Err(e) => match self.cns.get_offers(domain).await {
+                Ok(offers) => match offers.into_iter().find(|offer| offer.escrow_id == escrow.escrow_id) {
+                    Some(offer) => return Ok(offer),
+                    None => e,
+                },
+                Err(lookup) => {
+                    warn!(
+                        "Escrow {} stays locked: offer on {} failed with {} and could not be checked: {}",
+                        escrow.escrow_id, domain, e, lookup
+                    );
+                    return Err(e);
+                }
+            },
+        };
+        if let Err(refund) = self.gledger.refund_escrow(&escrow.escrow_id).await {
+            warn!("Failed to refund escrow {} after rejected offer on {}: {}", escrow.escrow_id, domain, refund);
+        }
+        Err(error)
+    }
+
+    /// Accept an offer as the domain owner, transferring the domain and releasing the escrow
+    pub async fn accept_offer(&self, offer_id: &str) -> Result<DomainSale> {
+        let sale = self.cns.accept_offer(offer_id).await?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(events) = &self.events {
+            events.publish(EtherlinkEvent::DomainSold {
+                domain: sale.domain.clone(),
+                seller: sale.seller.clone(),
+                buyer: sale.buyer.clone(),
+                price: sale.price,
+                token: sale.token.clone(),
+                tx_hash: sale.transfer_tx_hash.as_str().to_string(),
+                correlation_id: crate::correlation::current(),
+            });
+        }
+        Ok(sale)
+    }
+}
+
+/// Whether CNS answered and refused, so nothing can reference the escrow
+fn is_rejection(error: &EtherlinkError) -> bool {
+    matches!(
+        error,
+        EtherlinkError::Api(_)
+            | EtherlinkError::NotFound(_)
+            | EtherlinkError::PermissionDenied { .. }
+            | EtherlinkError::Authentication(_)
+            | EtherlinkError::InvalidConfig(_)
+    )
+}
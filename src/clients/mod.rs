@@ -8,6 +8,19 @@ pub mod gid;
 pub mod cns;
 pub mod gsig;
 pub mod gledger;
+pub mod marketplace;
+pub mod escrow;
+pub mod orderbook;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod swap;
+pub mod simulation;
+pub mod validator;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod storage;
+pub mod context;
+pub mod pinning;
+pub mod grpc;
+pub mod overview;
 
 pub use ghostd::GhostdClient;
 pub use walletd::WalletdClient;
@@ -15,10 +28,62 @@ pub use gid::GidClient;
 pub use cns::CnsClient;
 pub use gsig::GsigClient;
 pub use gledger::GledgerClient;
+pub use marketplace::DomainMarketplace;
+pub use escrow::Escrow;
+pub use orderbook::OrderRelayClient;
+#[cfg(not(target_arch = "wasm32"))]
+pub use swap::AtomicSwap;
+pub use simulation::{RevmFork, SimulationResult, TransactionSimulator};
+pub use validator::ValidatorClient;
+pub use pinning::{PinningClient, PinningConfig};
+#[cfg(not(target_arch = "wasm32"))]
+pub use storage::StorageClient;
+pub use context::CallContext;
+pub use grpc::{ServiceTransport, ServiceTransports};
+pub use overview::AccountOverview;
 
-use crate::{Result, EtherlinkConfig};
+use crate::{Result, EtherlinkConfig, EtherlinkError, Address, BlockHeight};
+use crate::version::{ApiVersion, VersionRegistry};
+use std::collections::HashMap;
 use reqwest::Client as HttpClient;
 use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::{ChannelConfig, ChannelManager, HostOverrides};
+
+/// Build the shared HTTP client for REST services, honoring the configured timeout and proxy
+#[cfg(not(target_arch = "wasm32"))]
+pub fn build_http_client(config: &EtherlinkConfig) -> Result<HttpClient> {
+    http_client_builder(config)?.build().map_err(|e| EtherlinkError::Network(e.to_string()))
+}
+
+/// Like [`build_http_client`], dialing hosts found in `overrides` at their registered addresses
+#[cfg(not(target_arch = "wasm32"))]
+pub fn build_http_client_with_overrides(config: &EtherlinkConfig, overrides: &HostOverrides) -> Result<HttpClient> {
+    http_client_builder(config)?
+        .dns_resolver(Arc::new(overrides.clone()))
+        .build()
+        .map_err(|e| EtherlinkError::Network(e.to_string()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn http_client_builder(config: &EtherlinkConfig) -> Result<reqwest::ClientBuilder> {
+    let mut builder = HttpClient::builder().timeout(Duration::from_millis(config.timeout_ms));
+
+    if let Some(proxy) = &config.proxy {
+        builder = proxy.apply(builder)?;
+    }
+    Ok(builder)
+}
+
+/// Build the shared HTTP client for REST services on top of the browser's `fetch`
+///
+/// The browser owns timeouts and proxying, so `timeout_ms` and `proxy` are ignored.
+#[cfg(target_arch = "wasm32")]
+pub fn build_http_client(_config: &EtherlinkConfig) -> Result<HttpClient> {
+    HttpClient::builder().build().map_err(|e| EtherlinkError::Network(e.to_string()))
+}
 
 /// Collection of all GhostChain service clients
 #[derive(Debug, Clone)]
@@ -29,24 +94,158 @@ pub struct ServiceClients {
     pub cns: CnsClient,
     pub gsig: GsigClient,
     pub gledger: GledgerClient,
+    /// Validator operations, served by GHOSTD
+    pub validator: ValidatorClient,
+    /// Chunked blob storage, served by GHOSTD
+    #[cfg(not(target_arch = "wasm32"))]
+    pub storage: StorageClient,
+    /// Pinning service for IPFS domain content, when one is configured
+    pub pinning: Option<PinningClient>,
+    /// API versions negotiated with each service
+    pub versions: VersionRegistry,
+    http_client: Arc<HttpClient>,
 }
 
 impl ServiceClients {
     /// Create new service clients with the given configuration
+    ///
+    /// Services set to [`ServiceTransport::Grpc`] in `service_transports` get their
+    /// own gRPC channels; use [`ServiceClients::with_channel_manager`] to share them.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(config: &EtherlinkConfig, http_client: Arc<HttpClient>) -> Self {
+        Self::with_channel_manager(config, http_client, ChannelManager::new(ChannelConfig::from(config)))
+    }
+
+    /// Create new service clients with the given configuration
+    ///
+    /// The browser build always uses REST, whatever `service_transports` says.
+    #[cfg(target_arch = "wasm32")]
     pub fn new(config: &EtherlinkConfig, http_client: Arc<HttpClient>) -> Self {
+        Self::rest(config, http_client)
+    }
+
+    /// Create service clients whose gRPC calls share channels from `channels`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_channel_manager(config: &EtherlinkConfig, http_client: Arc<HttpClient>, channels: ChannelManager) -> Self {
+        let mut clients = Self::rest(config, http_client);
+        let transports = &config.service_transports;
+        let endpoint = config.grpc_endpoint.clone().unwrap_or_else(|| config.ghostd_endpoint.clone());
+        if transports.walletd == ServiceTransport::Grpc {
+            clients.walletd = clients.walletd.with_grpc(channels.clone(), endpoint.clone());
+        }
+        if transports.gid == ServiceTransport::Grpc {
+            clients.gid = clients.gid.with_grpc(channels.clone(), endpoint.clone());
+        }
+        if transports.gsig == ServiceTransport::Grpc {
+            clients.gsig = clients.gsig.with_grpc(channels.clone(), endpoint.clone());
+        }
+        if transports.gledger == ServiceTransport::Grpc {
+            clients.gledger = clients.gledger.with_grpc(channels, endpoint);
+        }
+        clients
+    }
+
+    fn rest(config: &EtherlinkConfig, http_client: Arc<HttpClient>) -> Self {
+        let versions = VersionRegistry::new();
         Self {
-            ghostd: GhostdClient::new(config, http_client.clone()),
+            ghostd: GhostdClient::new(config, http_client.clone()).with_versions(versions.clone()),
             walletd: WalletdClient::new(config, http_client.clone()),
             gid: GidClient::new(config, http_client.clone()),
             cns: CnsClient::new(config, http_client.clone()),
             gsig: GsigClient::new(config, http_client.clone()),
-            gledger: GledgerClient::new(config, http_client),
+            gledger: GledgerClient::new(config, http_client.clone()),
+            validator: ValidatorClient::new(config, http_client.clone()),
+            #[cfg(not(target_arch = "wasm32"))]
+            storage: StorageClient::new(config, http_client.clone()),
+            pinning: config.pinning.as_ref().map(|pinning| PinningClient::new(pinning, http_client.clone())),
+            versions,
+            http_client,
+        }
+    }
+
+    /// Clients for calls with a different endpoint, auth token or timeout
+    ///
+    /// The context applies to every service, so an endpoint override only makes
+    /// sense for a gateway serving all of them.
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            ghostd: self.ghostd.with_context(context.clone()),
+            walletd: self.walletd.with_context(context.clone()),
+            gid: self.gid.with_context(context.clone()),
+            cns: self.cns.with_context(context.clone()),
+            gsig: self.gsig.with_context(context.clone()),
+            gledger: self.gledger.with_context(context.clone()),
+            #[cfg(not(target_arch = "wasm32"))]
+            storage: self.storage.with_context(context.clone()),
+            validator: self.validator.with_context(context),
+            pinning: self.pinning.clone(),
+            versions: self.versions.clone(),
+            http_client: self.http_client.clone(),
+        }
+    }
+
+    /// Clients reading balances and domain ownership as of block `height`
+    pub fn at_block(&self, height: BlockHeight) -> Self {
+        Self {
+            ghostd: self.ghostd.at_block(height),
+            cns: self.cns.at_block(height),
+            gledger: self.gledger.at_block(height),
+            ..self.clone()
         }
     }
+
+    /// Domain listings and escrowed offers over the CNS and GLEDGER clients
+    pub fn marketplace(&self) -> DomainMarketplace {
+        DomainMarketplace::new(self.cns.clone(), self.gledger.clone())
+    }
+
+    /// Atomic swaps of `account`'s tokens over the GLEDGER client
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn atomic_swap(&self, account: Address) -> AtomicSwap {
+        AtomicSwap::new(self.gledger.clone(), account)
+    }
+
+    /// Client for the orderbook relay at `relay_endpoint`, sharing the HTTP client
+    pub fn order_relay(&self, relay_endpoint: &str) -> OrderRelayClient {
+        OrderRelayClient::new(relay_endpoint, self.http_client.clone())
+    }
+
+    /// Valuation and PnL of GLEDGER history, priced by `oracle`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn portfolio(&self, oracle: Arc<dyn crate::portfolio::PriceOracle>) -> crate::portfolio::Portfolio {
+        crate::portfolio::Portfolio::new(self.gledger.clone(), oracle)
+    }
+
+    /// Two-of-three escrows over the GLEDGER and GSIG clients
+    pub fn escrow(&self) -> Escrow {
+        Escrow::new(self.gledger.clone(), self.gsig.clone())
+    }
+
+    /// Discover the API version of every service
+    ///
+    /// Unreachable services are skipped and treated as supporting all features.
+    pub async fn negotiate_versions(&self) -> HashMap<String, ApiVersion> {
+        let services = [
+            ("ghostd", self.ghostd.base_url()),
+            ("walletd", self.walletd.base_url()),
+            ("gid", self.gid.base_url()),
+            ("cns", self.cns.base_url()),
+            ("gsig", self.gsig.base_url()),
+            ("gledger", self.gledger.base_url()),
+        ];
+
+        self.versions.discover_all(&self.http_client, &services).await
+    }
+
+    /// Create service clients with an HTTP client built from the configuration
+    pub fn from_config(config: &EtherlinkConfig) -> Result<Self> {
+        Ok(Self::new(config, Arc::new(build_http_client(config)?)))
+    }
 }
 
 /// Base trait for all service clients
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 pub trait ServiceClient {
     /// Get the service name
     fn service_name(&self) -> &'static str;
@@ -79,4 +278,14 @@ impl<T> ApiResponse<T> {
             ))
         }
     }
+}
+
+/// Fail a `404 Not Found` response with [`EtherlinkError::NotFound`] naming `what`
+///
+/// Any other response is passed on to be parsed as an [`ApiResponse`].
+pub(crate) fn reject_not_found(response: reqwest::Response, what: &str) -> Result<reqwest::Response> {
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(EtherlinkError::NotFound(what.to_string()));
+    }
+    Ok(response)
 }
\ No newline at end of file
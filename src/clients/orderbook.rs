@@ -0,0 +1,377 @@
+//! Signed limit orders for off-chain orderbooks
+//!
+//! A maker signs a [`LimitOrder`] with any [`Signer`]; the signature covers the
+//! [`HashDomain::Order`] hash of the order's canonical encoding, so it can never be
+//! replayed as a transaction signature. [`OrderRelayClient`] submits signed orders to
+//! a relay, cancels them with a maker-signed [`OrderCancellation`], and follows fills
+//! as a stream. Matching and settlement are the relay's business.
+
+use crate::{Result, EtherlinkError, Address, TokenType, TxHash};
+use crate::auth::Permission;
+use crate::auth::crypto::{CryptoAlgorithm, CryptoProvider, Signer, address_from_public_key};
+use crate::clients::ApiResponse;
+use crate::clients::context::{CallContext, WithContext};
+use crate::clock::{self, SharedClock};
+use crate::hash::{HashAlgorithm, HashDomain, Hasher};
+use crate::queue::QueueConfig;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::queue::{self, QueueReceiver};
+use crate::validation::{ConfigErrors, Validator};
+use reqwest::Client as HttpClient;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use tracing::debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderSide {
+    /// Pay `quote` for `base`
+    Buy,
+    /// Sell `base` for `quote`
+    Sell,
+}
+
+/// Limit order as signed by its maker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LimitOrder {
+    pub maker: Address,
+    /// Only this address may fill the order; anyone may when `None`
+    pub taker: Option<Address>,
+    pub side: OrderSide,
+    pub base: TokenType,
+    pub quote: TokenType,
+    /// Amount of `base` to buy or sell
+    pub amount: u64,
+    /// Units of `quote` per unit of `base`
+    pub price: u64,
+    /// Unix seconds after which the order can no longer be filled
+    pub expiry: u64,
+    /// Distinguishes otherwise identical orders from the same maker
+    pub nonce: u64,
+}
+
+impl LimitOrder {
+    /// Check amounts, tokens and taker
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.amount > 0, "amount", "must be greater than zero");
+        v.check(self.price > 0, "price", "must be greater than zero");
+        v.check(self.amount.checked_mul(self.price).is_some(), "price", "amount times price overflows");
+        v.check(self.base != self.quote, "quote", "must differ from the base token");
+        v.check(self.taker.as_ref() != Some(&self.maker), "taker", "must differ from the maker");
+        v.finish()
+    }
+
+    /// Amount of `quote` exchanged when the whole order is filled
+    pub fn quote_amount(&self) -> u64 {
+        self.amount.saturating_mul(self.price)
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expiry
+    }
+
+    /// Hex [`HashDomain::Order`] SHA-256 hash of the canonical encoding; the order's ID
+    pub fn order_hash(&self) -> String {
+        hex::encode(self.signing_payload())
+    }
+
+    /// Bytes covered by the maker signature
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let bytes = crate::canonical::to_bytes(self).unwrap_or_default();
+        Hasher::domain_digest(HashAlgorithm::Sha256, HashDomain::Order, bytes).to_vec()
+    }
+
+    /// Sign as the maker; fails unless `signer`'s key owns `maker`
+    pub fn sign<S: Signer + ?Sized>(self, signer: &S) -> Result<SignedOrder> {
+        self.validate()?;
+        let public_key = hex::encode(signer.public_key()?);
+        let algorithm = signer.algorithm();
+        expect_owner(&self.maker, &public_key, &algorithm)?;
+        let signature = signer.sign(&self.signing_payload())?;
+        Ok(SignedOrder { order: self, public_key, algorithm, signature: hex::encode(signature.bytes) })
+    }
+}
+
+/// Limit order with its maker's signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedOrder {
+    pub order: LimitOrder,
+    /// Hex public key of the maker
+    pub public_key: String,
+    pub algorithm: CryptoAlgorithm,
+    /// Hex signature over [`LimitOrder::signing_payload`]
+    pub signature: String,
+}
+
+impl SignedOrder {
+    pub fn order_hash(&self) -> String {
+        self.order.order_hash()
+    }
+
+    /// Check the order, that the public key owns `maker`, and the signature
+    pub fn verify(&self) -> Result<()> {
+        self.order.validate()?;
+        expect_owner(&self.order.maker, &self.public_key, &self.algorithm)?;
+        verify(&self.order.signing_payload(), &self.signature, &self.public_key, &self.algorithm)
+    }
+}
+
+/// Maker-signed request to withdraw an order from the relay
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderCancellation {
+    pub order_hash: String,
+    pub maker: Address,
+    pub public_key: String,
+    pub algorithm: CryptoAlgorithm,
+    /// Hex signature over [`OrderCancellation::payload`]
+    pub signature: String,
+}
+
+impl OrderCancellation {
+    /// Cancel `order`, signing with the same key that signed it
+    pub fn sign<S: Signer + ?Sized>(order: &SignedOrder, signer: &S) -> Result<Self> {
+        let public_key = hex::encode(signer.public_key()?);
+        let algorithm = signer.algorithm();
+        expect_owner(&order.order.maker, &public_key, &algorithm)?;
+        let order_hash = order.order_hash();
+        let signature = signer.sign(&Self::payload(&order_hash))?;
+        Ok(Self { order_hash, maker: order.order.maker.clone(), public_key, algorithm, signature: hex::encode(signature.bytes) })
+    }
+
+    /// Bytes covered by the cancellation signature, distinct from any order's
+    pub fn payload(order_hash: &str) -> Vec<u8> {
+        let mut hasher = Hasher::with_domain(HashAlgorithm::Sha256, HashDomain::Order);
+        hasher.update(b"cancel").update(order_hash.as_bytes());
+        hasher.finalize().to_vec()
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        expect_owner(&self.maker, &self.public_key, &self.algorithm)?;
+        verify(&Self::payload(&self.order_hash), &self.signature, &self.public_key, &self.algorithm)
+    }
+}
+
+fn expect_owner(maker: &Address, public_key: &str, algorithm: &CryptoAlgorithm) -> Result<()> {
+    if address_from_public_key(public_key, algorithm)? != *maker {
+        return Err(EtherlinkError::Crypto(format!("Key {} does not own maker address {}", public_key, maker)));
+    }
+    Ok(())
+}
+
+fn verify(message: &[u8], signature: &str, public_key: &str, algorithm: &CryptoAlgorithm) -> Result<()> {
+    if !CryptoProvider::new().verify_signature(message, signature, public_key, algorithm)? {
+        return Err(EtherlinkError::Crypto("Order signature does not verify".to_string()));
+    }
+    Ok(())
+}
+
+/// API base URL of the relay at `endpoint`
+fn relay_base_url(endpoint: &str) -> String {
+    endpoint.trim_end_matches('/').to_string()
+}
+
+/// Client for an orderbook relay
+///
+/// Relays serve their API at the root of their endpoint, with no `/api/v1` prefix,
+/// both for the endpoint given to [`OrderRelayClient::new`] and for a
+/// [`CallContext`] endpoint override.
+#[derive(Debug, Clone)]
+pub struct OrderRelayClient {
+    base_url: String,
+    http_client: Arc<HttpClient>,
+    context: CallContext,
+    clock: SharedClock,
+    /// Queue each fill subscription is read into
+    subscription_queue: QueueConfig,
+}
+
+impl OrderRelayClient {
+    /// Client for the relay at `relay_endpoint`
+    pub fn new(relay_endpoint: &str, http_client: Arc<HttpClient>) -> Self {
+        Self {
+            base_url: relay_base_url(relay_endpoint),
+            http_client,
+            context: CallContext::default(),
+            clock: clock::system(),
+            subscription_queue: QueueConfig::default(),
+        }
+    }
+
+    /// Client for calls with a different endpoint, auth token or timeout
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            base_url: context.endpoint.as_deref().map(relay_base_url).unwrap_or_else(|| self.base_url.clone()),
+            http_client: self.http_client.clone(),
+            context,
+            clock: self.clock.clone(),
+            subscription_queue: self.subscription_queue.clone(),
+        }
+    }
+
+    /// Buffer fill subscriptions per `queue` instead of the default queue
+    pub fn with_subscription_queue(mut self, queue: QueueConfig) -> Self {
+        self.subscription_queue = queue;
+        self
+    }
+
+    /// Check order expiry against `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Submit a signed order, verifying it first so a bad one never leaves the client
+    pub async fn submit(&self, order: &SignedOrder) -> Result<OrderStatus> {
+        self.context.require(&[Permission::PlaceOrders])?;
+        order.verify()?;
+        if order.order.is_expired(self.clock.now()) {
+            return Err(EtherlinkError::Timeout(format!("Order {} has expired", order.order_hash())));
+        }
+        let url = format!("{}/orders", self.base_url);
+        self.post(&url, order).await
+    }
+
+    /// Withdraw the order named by `cancellation`
+    pub async fn cancel(&self, cancellation: &OrderCancellation) -> Result<OrderStatus> {
+        self.context.require(&[Permission::PlaceOrders])?;
+        cancellation.verify()?;
+        let url = format!("{}/orders/{}/cancel", self.base_url, cancellation.order_hash);
+        self.post(&url, cancellation).await
+    }
+
+    pub async fn order_status(&self, order_hash: &str) -> Result<OrderStatus> {
+        self.context.require(&[Permission::ReadOrders])?;
+        let url = format!("{}/orders/{}", self.base_url, order_hash);
+        self.get(&url).await
+    }
+
+    /// Open orders of `maker`
+    pub async fn open_orders(&self, maker: &Address) -> Result<Vec<OrderStatus>> {
+        self.context.require(&[Permission::ReadOrders])?;
+        let url = format!("{}/orders?maker={}&state=open", self.base_url, maker);
+        self.get(&url).await
+    }
+
+    /// Fills of `maker`'s orders with a sequence number above `after`, oldest first
+    pub async fn fills(&self, maker: &Address, after: u64) -> Result<Vec<Fill>> {
+        self.context.require(&[Permission::ReadOrders])?;
+        let url = format!("{}/fills?maker={}&after={}", self.base_url, maker, after);
+        self.get(&url).await
+    }
+
+    /// Follow fills of `maker`'s orders with a sequence number above `after`
+    ///
+    /// The relay is polled every `poll_interval`. A failed poll is delivered as an
+    /// error and polling carries on from the last fill delivered.
+    ///
+    /// A background task reads the fills into a queue configured by
+    /// [`OrderRelayClient::with_subscription_queue`], so a slow subscriber is handled by
+    /// its overflow policy, as with CNS domain change subscriptions. Dropping the
+    /// receiver ends the subscription.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn subscribe_fills(&self, maker: Address, after: u64, poll_interval: Duration) -> QueueReceiver<Result<Fill>> {
+        let (sender, receiver) = queue::bounded(self.subscription_queue.clone());
+        let client = self.clone();
+        tokio::spawn(async move {
+            let mut cursor = after;
+            let mut first = true;
+            loop {
+                let polled = tokio::select! {
+                    polled = async {
+                        if !first {
+                            tokio::time::sleep(poll_interval).await;
+                        }
+                        client.fills(&maker, cursor).await
+                    } => polled,
+                    _ = sender.closed() => break,
+                };
+                first = false;
+                let batch = match polled {
+                    Ok(fills) => fills.into_iter().filter(|fill| fill.sequence > cursor).map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                for item in batch {
+                    if let Ok(fill) = &item {
+                        cursor = cursor.max(fill.sequence);
+                    }
+                    if sender.send(item).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            debug!("Fill subscription for {} ended", maker);
+        });
+        receiver
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response: ApiResponse<T> = self.http_client
+            .get(url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    async fn post<B: Serialize, T: serde::de::DeserializeOwned>(&self, url: &str, body: &B) -> Result<T> {
+        let response: ApiResponse<T> = self.http_client
+            .post(url)
+            .with_context(&self.context)
+            .json(body)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+}
+
+// Data structures for the relay API
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderState {
+    Open,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Expired,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderStatus {
+    pub order_hash: String,
+    pub state: OrderState,
+    /// Amount of `base` filled so far
+    pub filled: u64,
+}
+
+/// A match against one of the maker's orders
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fill {
+    /// Relay-assigned, increasing per maker
+    pub sequence: u64,
+    pub order_hash: String,
+    pub maker: Address,
+    pub taker: Address,
+    /// Amount of `base` exchanged
+    pub base_amount: u64,
+    /// Amount of `quote` exchanged
+    pub quote_amount: u64,
+    /// Settlement transaction, once submitted
+    pub tx_hash: Option<TxHash>,
+    /// Unix seconds
+    pub filled_at: u64,
+}
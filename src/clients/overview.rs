@@ -0,0 +1,82 @@
+//! Everything the services know about one account, fetched in one call
+//!
+//! Wallet and explorer front pages show an account's balances, nonce, domains and
+//! identities together. [`crate::ServiceClients::get_account_overview`] queries
+//! GLEDGER, GHOSTD, CNS and GID concurrently and merges the answers into an
+//! [`AccountOverview`]; a service that fails leaves its field empty and its error in
+//! [`AccountOverview::errors`] instead of failing the whole overview.
+
+use crate::clients::gid::Identity;
+use crate::clients::gledger::TokenBalances;
+use crate::clients::ServiceClients;
+use crate::{Address, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Balances, nonce, domains and identities of one address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountOverview {
+    pub address: Address,
+    /// From GLEDGER
+    pub balances: Option<TokenBalances>,
+    /// Next nonce, from GHOSTD
+    pub nonce: Option<u64>,
+    /// Domains the address owns, from CNS
+    pub domains: Option<Vec<String>>,
+    /// Identities bound to the address, from GID
+    pub identities: Option<Vec<Identity>>,
+    /// Why each missing field could not be fetched, by field name
+    pub errors: BTreeMap<String, String>,
+}
+
+impl AccountOverview {
+    /// Whether every field was fetched
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Record `result` for `field`, keeping the error if it failed
+    fn take<T>(&mut self, field: &str, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.errors.insert(field.to_string(), e.to_string());
+                None
+            }
+        }
+    }
+}
+
+impl ServiceClients {
+    /// Balances, nonce, owned domains and identities of `address`, queried concurrently
+    ///
+    /// Fails only when every service fails, with GLEDGER's error; otherwise the
+    /// fields that could not be fetched are `None` and explained in
+    /// [`AccountOverview::errors`].
+    pub async fn get_account_overview(&self, address: &Address) -> Result<AccountOverview> {
+        let (balances, nonce, domains, identities) = futures::join!(
+            self.gledger.get_all_balances(address),
+            self.ghostd.get_nonce(address),
+            self.cns.get_domains_by_owner(address),
+            self.gid.get_identities_by_address(address),
+        );
+        let balances = match balances {
+            Err(e) if nonce.is_err() && domains.is_err() && identities.is_err() => return Err(e),
+            balances => balances,
+        };
+
+        let mut overview = AccountOverview {
+            address: address.clone(),
+            balances: None,
+            nonce: None,
+            domains: None,
+            identities: None,
+            errors: BTreeMap::new(),
+        };
+        overview.balances = overview.take("balances", balances);
+        overview.nonce = overview.take("nonce", nonce);
+        overview.domains = overview.take("domains", domains);
+        overview.identities = overview.take("identities", identities);
+        Ok(overview)
+    }
+}
@@ -0,0 +1,195 @@
+//! Pinning client for IPFS content behind domain records
+//!
+//! Talks to any service implementing the IPFS Pinning Service API (`/pins`), with
+//! its own bearer token rather than a Guardian token, and never the correlation ID of
+//! the operation it runs in. [`PinningClient::domain_pins`]
+//! reports the pin state of every `ipfs://` content hash on domains an address
+//! owns, and [`crate::watcher::Watcher::with_pinning`] warns when one is unpinned.
+
+use crate::{Result, EtherlinkError, Address, SecretString};
+use crate::clients::cns::CnsClient;
+use crate::clients::context::WithContext;
+use crate::validation::{ConfigErrors, Validator};
+use reqwest::Client as HttpClient;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+
+/// Pinning service used for domain content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinningConfig {
+    /// Base URL of the Pinning Service API, without the `/pins` suffix
+    pub endpoint: String,
+    pub access_token: Option<SecretString>,
+}
+
+impl PinningConfig {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), access_token: None }
+    }
+
+    pub fn with_access_token(mut self, token: impl Into<SecretString>) -> Self {
+        self.access_token = Some(token.into());
+        self
+    }
+
+    /// Check the token; the endpoint is checked by [`crate::EtherlinkConfig::validate`]
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(
+            self.access_token.as_ref().is_none_or(|token| !token.expose_secret().is_empty()),
+            "access_token",
+            "must not be empty when set",
+        );
+        v.finish()
+    }
+}
+
+/// Client for pinning IPFS content on a pinning service
+#[derive(Debug, Clone)]
+pub struct PinningClient {
+    endpoint: String,
+    access_token: Option<SecretString>,
+    http_client: Arc<HttpClient>,
+}
+
+impl PinningClient {
+    pub fn new(config: &PinningConfig, http_client: Arc<HttpClient>) -> Self {
+        Self {
+            endpoint: config.endpoint.trim_end_matches('/').to_string(),
+            access_token: config.access_token.clone(),
+            http_client,
+        }
+    }
+
+    /// Ask the service to pin `cid`, returning the pin request
+    pub async fn pin(&self, cid: &str, name: Option<&str>) -> Result<PinStatus> {
+        let url = format!("{}/pins", self.endpoint);
+        let response = self
+            .authorize(self.http_client.post(&url))
+            .json(&Pin { cid: cid.to_string(), name: name.map(str::to_string) })
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        parse(response).await
+    }
+
+    /// Remove every pin request for `cid`, returning how many were removed
+    pub async fn unpin(&self, cid: &str) -> Result<usize> {
+        let pins = self.pins(cid).await?;
+        for pin in &pins {
+            let url = format!("{}/pins/{}", self.endpoint, pin.requestid);
+            let response = self
+                .authorize(self.http_client.delete(&url))
+                .send_logged()
+                .await
+                .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+            if !response.status().is_success() {
+                return Err(EtherlinkError::Api(format!("Pinning service refused to unpin {}: {}", cid, response.status())));
+            }
+        }
+        Ok(pins.len())
+    }
+
+    /// Pin requests for `cid` in any state
+    pub async fn pins(&self, cid: &str) -> Result<Vec<PinStatus>> {
+        let url = format!("{}/pins", self.endpoint);
+        let response = self
+            .authorize(self.http_client.get(&url).query(&[("cid", cid), ("status", "queued,pinning,pinned,failed")]))
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        let results: PinResults = parse(response).await?;
+        Ok(results.results)
+    }
+
+    /// Most advanced state of any pin request for `cid`; `None` when it has none
+    pub async fn pin_state(&self, cid: &str) -> Result<Option<PinState>> {
+        Ok(self.pins(cid).await?.into_iter().map(|pin| pin.status).max())
+    }
+
+    /// Pin state of the `ipfs://` content of every domain `owner` holds
+    ///
+    /// Domains without IPFS content are left out.
+    pub async fn domain_pins(&self, cns: &CnsClient, owner: &Address) -> Result<Vec<DomainPin>> {
+        let mut pins = Vec::new();
+        for domain in cns.get_domains_by_owner(owner).await? {
+            let resolution = cns.resolve_domain(&domain).await?;
+            let Some(cid) = resolution.records.content_hash.as_deref().and_then(ipfs_cid) else {
+                continue;
+            };
+            let state = self.pin_state(&cid).await?;
+            pins.push(DomainPin { domain, cid, state });
+        }
+        Ok(pins)
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.access_token {
+            Some(token) => request.bearer_auth(token.expose_secret()),
+            None => request,
+        }
+    }
+}
+
+async fn parse<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(EtherlinkError::Api(format!("Pinning service answered {}: {}", status, body)));
+    }
+    response.json().await.map_err(|e| EtherlinkError::Network(e.to_string()))
+}
+
+/// CID of an `ipfs://` content hash, without any path; `None` for other schemes
+pub fn ipfs_cid(content_hash: &str) -> Option<String> {
+    let cid = content_hash.strip_prefix("ipfs://")?.split('/').next()?;
+    (!cid.is_empty()).then(|| cid.to_string())
+}
+
+// Data structures for the Pinning Service API
+
+/// Progress of a pin request, from least to most advanced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PinState {
+    Failed,
+    Queued,
+    Pinning,
+    Pinned,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pin {
+    pub cid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinStatus {
+    pub requestid: String,
+    pub status: PinState,
+    /// RFC 3339 time the request was made
+    pub created: String,
+    pub pin: Pin,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PinResults {
+    results: Vec<PinStatus>,
+}
+
+/// Pin state of the content a domain points at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainPin {
+    pub domain: String,
+    pub cid: String,
+    /// `None` when the service has no pin request for the CID
+    pub state: Option<PinState>,
+}
+
+impl DomainPin {
+    pub fn is_pinned(&self) -> bool {
+        self.state == Some(PinState::Pinned)
+    }
+}
@@ -0,0 +1,102 @@
+//! Simulation of transactions before they are sent
+//!
+//! With a simulator set, every GHOSTD submission path first runs the transaction
+//! through it and fails with [`EtherlinkError::SimulatedRevert`] when it is predicted
+//! to revert, so nothing is paid for a doomed transaction. GHOSTD's own
+//! `/transactions/simulate` endpoint is used when `simulate_before_send` is
+//! configured; [`RevmFork`] simulates against a local rEVM instead.
+
+use crate::{Result, EtherlinkError, Gas};
+use crate::clients::ghostd::Transaction;
+use crate::revm::{EvmSignature, EvmTransaction, REVMClient, decode_revert_reason};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Predicted outcome of a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulationResult {
+    pub success: bool,
+    pub gas_used: Gas,
+    /// Hex return data, or revert data when `success` is false
+    #[serde(default)]
+    pub output: String,
+    /// Reason given by the simulator, if it decoded one itself
+    #[serde(default)]
+    pub revert_reason: Option<String>,
+}
+
+impl SimulationResult {
+    /// Why the transaction reverts: the simulator's reason, else one decoded from `output`
+    pub fn reason(&self) -> Option<String> {
+        self.revert_reason.clone().or_else(|| {
+            let output = hex::decode(self.output.trim_start_matches("0x")).ok()?;
+            decode_revert_reason(&output)
+        })
+    }
+
+    /// `Err(SimulatedRevert)` with the decoded reason unless the transaction succeeds
+    pub fn into_result(self) -> Result<SimulationResult> {
+        if self.success {
+            return Ok(self);
+        }
+        Err(EtherlinkError::SimulatedRevert { reason: self.reason(), gas_used: self.gas_used })
+    }
+}
+
+/// Predicts whether a transaction would revert
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait TransactionSimulator: fmt::Debug + Send + Sync {
+    async fn simulate(&self, tx: &Transaction) -> Result<SimulationResult>;
+}
+
+/// Simulates against a fork of a local rEVM's current state
+///
+/// A transaction the fork refuses outright, e.g. for a bad nonce or an unaffordable
+/// gas limit, is reported as a revert with that reason.
+#[derive(Debug, Clone)]
+pub struct RevmFork {
+    revm: Arc<RwLock<REVMClient>>,
+}
+
+impl RevmFork {
+    pub fn new(revm: Arc<RwLock<REVMClient>>) -> Self {
+        Self { revm }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl TransactionSimulator for RevmFork {
+    async fn simulate(&self, tx: &Transaction) -> Result<SimulationResult> {
+        let revm = self.revm.read().await;
+        let evm_tx = EvmTransaction {
+            from: tx.from.clone(),
+            to: Some(tx.to.clone()),
+            value: tx.amount,
+            data: tx.data.clone().unwrap_or_default(),
+            gas_limit: tx.gas_limit,
+            gas_price: tx.gas_price,
+            nonce: tx.nonce,
+            chain_id: revm.config().chain_id,
+            signature: EvmSignature { v: 0, r: Vec::new(), s: Vec::new() },
+        };
+        match revm.simulate(evm_tx).await {
+            Ok(result) => Ok(SimulationResult {
+                success: result.success,
+                gas_used: result.gas_used,
+                output: hex::encode(&result.output),
+                revert_reason: result.revert_reason,
+            }),
+            Err(EtherlinkError::ContractExecution(reason)) => Ok(SimulationResult {
+                success: false,
+                gas_used: 0,
+                output: String::new(),
+                revert_reason: Some(reason),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+}
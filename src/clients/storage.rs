@@ -0,0 +1,450 @@
+//! Storage client for blobs behind `Storage` domains
+//!
+//! Blobs are uploaded and downloaded in fixed-size chunks. Each chunk is addressed
+//! by its BLAKE3 hash, and the blob by the [`HashDomain::Blob`] hash of its
+//! [`BlobManifest`], so every byte read back is checked against the id it was
+//! fetched by. An upload is a server-side session: chunks the service already holds
+//! are skipped, so an interrupted upload resumes by replaying the same data into
+//! [`StorageClient::resume_upload`]. A finished blob is published by pointing a
+//! domain's content hash at its `gstore://` URI.
+
+use crate::{Result, EtherlinkConfig, EtherlinkError, TxHash};
+use crate::clients::{ServiceClient, ApiResponse};
+use crate::clients::cns::CnsClient;
+use crate::clients::context::{CallContext, WithContext};
+use crate::auth::Permission;
+use crate::hash::{HashAlgorithm, HashDomain, Hasher};
+use reqwest::Client as HttpClient;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Chunk size used by [`StorageClient::upload`], 1 MiB
+pub const DEFAULT_CHUNK_SIZE: u32 = 1 << 20;
+
+/// Largest chunk the storage service accepts, 16 MiB
+pub const MAX_CHUNK_SIZE: u32 = 16 << 20;
+
+/// URI scheme of blob content hashes in domain records
+pub const CONTENT_SCHEME: &str = "gstore://";
+
+/// Client for chunked, content-addressed blob storage
+#[derive(Debug, Clone)]
+pub struct StorageClient {
+    base_url: String,
+    http_client: Arc<HttpClient>,
+    context: CallContext,
+}
+
+impl StorageClient {
+    /// Create a new storage client against GHOSTD
+    pub fn new(config: &EtherlinkConfig, http_client: Arc<HttpClient>) -> Self {
+        let base_url = format!("{}/api/v1", config.ghostd_endpoint.trim_end_matches('/'));
+        Self {
+            base_url,
+            http_client,
+            context: CallContext::default(),
+        }
+    }
+
+    /// Client for calls with a different endpoint, auth token or timeout
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            base_url: context.base_url().unwrap_or_else(|| self.base_url.clone()),
+            http_client: self.http_client.clone(),
+            context,
+        }
+    }
+
+    /// Upload everything `reader` yields as one blob, in [`DEFAULT_CHUNK_SIZE`] chunks
+    pub async fn upload<R: AsyncRead + Unpin>(&self, reader: R) -> Result<BlobManifest> {
+        let session = self.begin_upload(DEFAULT_CHUNK_SIZE).await?;
+        self.resume_upload(&session, reader).await
+    }
+
+    /// Open an upload session; keep it to resume the upload after a failure
+    pub async fn begin_upload(&self, chunk_size: u32) -> Result<UploadSession> {
+        self.context.require(&[Permission::WriteStorage])?;
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(EtherlinkError::Configuration(format!(
+                "chunk_size must be between 1 and {}, got {}",
+                MAX_CHUNK_SIZE, chunk_size
+            )));
+        }
+        let url = format!("{}/storage/uploads", self.base_url);
+        self.post(&url, &serde_json::json!({ "chunk_size": chunk_size })).await
+    }
+
+    /// Chunks the service has received so far for `upload_id`
+    pub async fn upload_status(&self, upload_id: &str) -> Result<UploadStatus> {
+        self.context.require(&[Permission::WriteStorage])?;
+        let url = format!("{}/storage/uploads/{}", self.base_url, upload_id);
+        self.get(&url).await
+    }
+
+    /// Send the chunks of `reader` the service does not hold yet, then finish the blob
+    ///
+    /// `reader` must yield the same bytes from the start on every attempt; a chunk
+    /// that differs from the one already received is sent again.
+    pub async fn resume_upload<R: AsyncRead + Unpin>(&self, session: &UploadSession, mut reader: R) -> Result<BlobManifest> {
+        let status = self.upload_status(&session.upload_id).await?;
+        let received: HashMap<u32, String> = status.received.into_iter().map(|chunk| (chunk.index, chunk.hash)).collect();
+
+        let mut manifest = ManifestBuilder::new(session.chunk_size);
+        let mut buf = vec![0u8; session.chunk_size as usize];
+        loop {
+            let len = read_chunk(&mut reader, &mut buf).await?;
+            if len == 0 {
+                break;
+            }
+            let (index, hash) = manifest.push(&buf[..len]);
+            if received.get(&index) != Some(&hash) {
+                self.put_chunk(&session.upload_id, index, &hash, &buf[..len]).await?;
+            }
+            if len < buf.len() {
+                break;
+            }
+        }
+
+        let manifest = manifest.finish();
+        let url = format!("{}/storage/uploads/{}/complete", self.base_url, session.upload_id);
+        let stored: BlobManifest = self.post(&url, &manifest).await?;
+        if stored.blob_id != manifest.blob_id {
+            return Err(EtherlinkError::Integrity(format!(
+                "storage finished upload {} as blob {}, expected {}",
+                session.upload_id, stored.blob_id, manifest.blob_id
+            )));
+        }
+        Ok(manifest)
+    }
+
+    /// Manifest of `blob_id`, checked against the id
+    pub async fn get_manifest(&self, blob_id: &str) -> Result<BlobManifest> {
+        self.context.require(&[Permission::ReadStorage])?;
+        let url = format!("{}/storage/blobs/{}", self.base_url, blob_id);
+        let manifest: BlobManifest = self.get(&url).await?;
+        if manifest.blob_id != blob_id || !manifest.is_consistent() {
+            return Err(EtherlinkError::Integrity(format!("manifest served for blob {} does not hash to it", blob_id)));
+        }
+        Ok(manifest)
+    }
+
+    /// Bytes of the chunk with `hash`, checked against the hash
+    ///
+    /// A chunk longer than [`MAX_CHUNK_SIZE`] is refused without being read in full.
+    pub async fn download_chunk(&self, hash: &str) -> Result<Vec<u8>> {
+        self.fetch_chunk(hash, u64::from(MAX_CHUNK_SIZE)).await
+    }
+
+    /// Write blob `blob_id` to `writer` chunk by chunk
+    ///
+    /// Each chunk is verified before it is written, so `writer` never sees bytes
+    /// that do not belong to the blob. Every chunk but the last must be exactly
+    /// `chunk_size` long and the blob exactly `size` bytes, as the manifest says.
+    pub async fn download_to<W: AsyncWrite + Unpin>(&self, blob_id: &str, mut writer: W) -> Result<BlobManifest> {
+        let manifest = self.get_manifest(blob_id).await?;
+        if manifest.chunk_size > MAX_CHUNK_SIZE {
+            return Err(EtherlinkError::Integrity(format!(
+                "manifest of blob {} declares {}-byte chunks, more than the {} allowed",
+                blob_id, manifest.chunk_size, MAX_CHUNK_SIZE
+            )));
+        }
+        let chunk_size = u64::from(manifest.chunk_size);
+        let mut written = 0u64;
+        for (index, hash) in manifest.chunks.iter().enumerate() {
+            let last = index + 1 == manifest.chunks.len();
+            let chunk = self.fetch_chunk(hash, chunk_size.min(manifest.size - written)).await?;
+            if !last && chunk.len() as u64 != chunk_size {
+                return Err(EtherlinkError::Integrity(format!(
+                    "chunk {} of blob {} is {} bytes, expected {}",
+                    index, blob_id, chunk.len(), chunk_size
+                )));
+            }
+            writer
+                .write_all(&chunk)
+                .await
+                .map_err(|e| EtherlinkError::Network(format!("Failed to write blob {}: {}", blob_id, e)))?;
+            written += chunk.len() as u64;
+        }
+        if written != manifest.size {
+            return Err(EtherlinkError::Integrity(format!(
+                "blob {} is {} bytes, its manifest says {}",
+                blob_id, written, manifest.size
+            )));
+        }
+        writer
+            .flush()
+            .await
+            .map_err(|e| EtherlinkError::Network(format!("Failed to write blob {}: {}", blob_id, e)))?;
+        Ok(manifest)
+    }
+
+    /// Bytes of the chunk with `hash`, refused once they run past `limit`
+    async fn fetch_chunk(&self, hash: &str, limit: u64) -> Result<Vec<u8>> {
+        self.context.require(&[Permission::ReadStorage])?;
+        let url = format!("{}/storage/chunks/{}", self.base_url, hash);
+        let response = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .error_for_status()
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        let too_long = || EtherlinkError::Integrity(format!("chunk served for {} is longer than {} bytes", hash, limit));
+        if response.content_length().is_some_and(|len| len > limit) {
+            return Err(too_long());
+        }
+        let bytes = read_limited(response, limit).await?.ok_or_else(too_long)?;
+        if chunk_hash(&bytes) != hash {
+            return Err(EtherlinkError::Integrity(format!("chunk served for {} does not hash to it", hash)));
+        }
+        Ok(bytes)
+    }
+
+    /// Whole blob `blob_id` in memory
+    pub async fn download(&self, blob_id: &str) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.download_to(blob_id, &mut data).await?;
+        Ok(data)
+    }
+
+    /// Point `domain`'s content hash at the stored blob, keeping its other records
+    pub async fn link_domain(&self, cns: &CnsClient, domain: &str, blob_id: &str) -> Result<TxHash> {
+        let manifest = self.get_manifest(blob_id).await?;
+        let mut records = cns.resolve_domain(domain).await?.records;
+        records.content_hash = Some(manifest.content_uri());
+        cns.update_domain_records(domain, records).await
+    }
+
+    async fn put_chunk(&self, upload_id: &str, index: u32, hash: &str, chunk: &[u8]) -> Result<()> {
+        let url = format!("{}/storage/uploads/{}/chunks/{}", self.base_url, upload_id, index);
+        let response: ApiResponse<ReceivedChunk> = self.http_client
+            .put(&url)
+            .with_context(&self.context)
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header("x-chunk-hash", hash)
+            .body(chunk.to_vec())
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        let receipt = response.into_result()?;
+        if receipt.index != index || receipt.hash != hash {
+            return Err(EtherlinkError::Integrity(format!(
+                "storage acknowledged chunk {} as {}, sent {}",
+                index, receipt.hash, hash
+            )));
+        }
+        Ok(())
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response: ApiResponse<T> = self.http_client
+            .get(url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    async fn post<B: Serialize, T: serde::de::DeserializeOwned>(&self, url: &str, body: &B) -> Result<T> {
+        let response: ApiResponse<T> = self.http_client
+            .post(url)
+            .with_context(&self.context)
+            .json(body)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+}
+
+#[async_trait::async_trait]
+impl ServiceClient for StorageClient {
+    fn service_name(&self) -> &'static str {
+        "storage"
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn health_check(&self) -> Result<serde_json::Value> {
+        let url = format!("{}/health", self.base_url);
+        self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))
+    }
+
+    async fn status(&self) -> Result<serde_json::Value> {
+        let url = format!("{}/storage/status", self.base_url);
+        self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))
+    }
+}
+
+/// Fill `buf` from `reader`, stopping early only at end of input
+async fn read_chunk<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let read = reader
+            .read(&mut buf[filled..])
+            .await
+            .map_err(|e| EtherlinkError::Network(format!("Failed to read upload data: {}", e)))?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    Ok(filled)
+}
+
+/// Lowercase hex BLAKE3 hash of a chunk
+pub fn chunk_hash(chunk: &[u8]) -> String {
+    hex::encode(Hasher::digest(HashAlgorithm::Blake3, chunk))
+}
+
+/// Hashes a blob's chunks in order as they are read
+struct ManifestBuilder {
+    chunk_size: u32,
+    size: u64,
+    chunks: Vec<String>,
+}
+
+impl ManifestBuilder {
+    fn new(chunk_size: u32) -> Self {
+        Self { chunk_size, size: 0, chunks: Vec::new() }
+    }
+
+    /// Add the next chunk, returning its index and hash
+    fn push(&mut self, chunk: &[u8]) -> (u32, String) {
+        let hash = chunk_hash(chunk);
+        self.size += chunk.len() as u64;
+        self.chunks.push(hash.clone());
+        (self.chunks.len() as u32 - 1, hash)
+    }
+
+    fn finish(self) -> BlobManifest {
+        BlobManifest {
+            blob_id: blob_id(self.size, self.chunk_size, &self.chunks),
+            size: self.size,
+            chunk_size: self.chunk_size,
+            chunks: self.chunks,
+        }
+    }
+}
+
+/// Hash committing to a blob's size, chunk size and chunk hashes in order
+fn blob_id(size: u64, chunk_size: u32, chunks: &[String]) -> String {
+    let mut hasher = Hasher::with_domain(HashAlgorithm::Blake3, HashDomain::Blob);
+    hasher.update(size.to_be_bytes()).update(chunk_size.to_be_bytes());
+    for chunk in chunks {
+        hasher.update(hex::decode(chunk).unwrap_or_default());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Body of `response`, or `None` once it runs past `limit` bytes
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_limited(mut response: reqwest::Response, limit: u64) -> Result<Option<Vec<u8>>> {
+    let mut body = Vec::new();
+    while let Some(part) = response.chunk().await.map_err(|e| EtherlinkError::Network(e.to_string()))? {
+        if (body.len() + part.len()) as u64 > limit {
+            return Ok(None);
+        }
+        body.extend_from_slice(&part);
+    }
+    Ok(Some(body))
+}
+
+/// Body of `response`, or `None` if it is longer than `limit` bytes; the browser
+/// hands over the body whole
+#[cfg(target_arch = "wasm32")]
+async fn read_limited(response: reqwest::Response, limit: u64) -> Result<Option<Vec<u8>>> {
+    let body = response.bytes().await.map_err(|e| EtherlinkError::Network(e.to_string()))?;
+    Ok((body.len() as u64 <= limit).then(|| body.to_vec()))
+}
+
+// Data structures for the storage API
+
+/// Upload in progress on the storage service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub upload_id: String,
+    pub chunk_size: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadStatus {
+    pub upload_id: String,
+    pub chunk_size: u32,
+    pub received: Vec<ReceivedChunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceivedChunk {
+    pub index: u32,
+    pub hash: String,
+}
+
+/// Chunk hashes of a stored blob, in order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobManifest {
+    /// Lowercase hex [`HashDomain::Blob`] BLAKE3 hash of the rest of the manifest
+    pub blob_id: String,
+    /// Blob length in bytes
+    pub size: u64,
+    /// Length of every chunk but the last
+    pub chunk_size: u32,
+    /// Lowercase hex BLAKE3 hash of each chunk
+    pub chunks: Vec<String>,
+}
+
+impl BlobManifest {
+    /// Manifest of `data` split into `chunk_size` chunks
+    pub fn for_bytes(data: &[u8], chunk_size: u32) -> Self {
+        let mut manifest = ManifestBuilder::new(chunk_size);
+        for chunk in data.chunks(chunk_size.max(1) as usize) {
+            manifest.push(chunk);
+        }
+        manifest.finish()
+    }
+
+    /// `gstore://` URI to store in a domain's `CONTENTHASH` record
+    pub fn content_uri(&self) -> String {
+        format!("{}{}", CONTENT_SCHEME, self.blob_id)
+    }
+
+    /// Whether `blob_id` is the hash of the other fields
+    pub fn is_consistent(&self) -> bool {
+        self.blob_id == blob_id(self.size, self.chunk_size, &self.chunks)
+    }
+}
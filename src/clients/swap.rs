@@ -0,0 +1,313 @@
+//! Atomic token swaps over GLEDGER hash-timelocks
+//!
+//! The initiator picks a secret, locks `amount_a` of `token_a` for the counterparty
+//! under its SHA-256 hash, and waits for the counterparty to lock `amount_b` of
+//! `token_b` under the same hash with an earlier expiry. Claiming that lock reveals
+//! the secret on GLEDGER, which the counterparty then uses to claim the first one.
+//! Either both transfers happen or, once the locks expire, both are refunded.
+//!
+//! The counterparty's lock must expire at least half the swap timeout before the
+//! initiator's, leaving it time to claim after the secret is revealed. Progress is
+//! published as [`EtherlinkEvent::SwapStatusChanged`].
+//!
+//! The initiator's secret is written to the [`AtomicSwap::with_secret_store`] store,
+//! under `swap:<hash lock>`, before anything is locked, so a crash while waiting
+//! does not leave funds locked under a hash nobody can open. The receipt carries it
+//! too.
+
+use crate::{Result, EtherlinkError, Address, TokenType, TxHash};
+use crate::clients::gledger::{EscrowReceipt, GledgerClient, HashLock, HtlcInfo, HtlcState};
+use crate::clock::{self, SharedClock};
+use crate::events::{EtherlinkEvent, EventBus};
+use crate::hash::{HashAlgorithm, Hasher};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::auth::SecretBackend;
+use serde::{Serialize, Deserialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// Timing of a swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapConfig {
+    /// Lifetime of the initiator's lock; the counterparty's lives at most half as long
+    pub timeout: Duration,
+    /// Delay between GLEDGER checks while waiting on the other side
+    pub poll_interval: Duration,
+}
+
+impl Default for SwapConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(3600),
+            poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapStatus {
+    /// This side's funds are locked
+    Locked,
+    /// The other side's funds are locked under the same hash
+    CounterpartyLocked,
+    /// This side claimed the other side's funds
+    Completed,
+    /// The swap timed out and this side's funds were returned
+    Refunded,
+}
+
+/// How a swap ended for this side
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapReceipt {
+    /// Hex hash lock shared by both sides
+    pub swap_id: String,
+    pub status: SwapStatus,
+    /// This side's lock
+    pub lock: EscrowReceipt,
+    pub claim_tx: Option<TxHash>,
+    pub refund_tx: Option<TxHash>,
+    /// Hex secret behind the hash lock, once this side knows it
+    pub secret: Option<String>,
+}
+
+/// Swaps tokens of `account` with counterparties through hash-timelocks
+#[derive(Debug, Clone)]
+pub struct AtomicSwap {
+    gledger: GledgerClient,
+    account: Address,
+    config: SwapConfig,
+    clock: SharedClock,
+    events: Option<EventBus>,
+    #[cfg(not(target_arch = "wasm32"))]
+    secrets: Option<Arc<dyn SecretBackend>>,
+}
+
+impl AtomicSwap {
+    pub fn new(gledger: GledgerClient, account: Address) -> Self {
+        Self {
+            gledger,
+            account,
+            config: SwapConfig::default(),
+            clock: clock::system(),
+            events: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            secrets: None,
+        }
+    }
+
+    pub fn with_config(mut self, config: SwapConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Read lock expiry against `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Publish [`EtherlinkEvent::SwapStatusChanged`] to `events`
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Keep the secret of each initiated swap in `secrets` before locking
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_secret_store(mut self, secrets: Arc<dyn SecretBackend>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Give `amount_a` of `token_a` for `amount_b` of `token_b` from `counterparty`
+    ///
+    /// Returns once this side has claimed, or has been refunded after the timeout.
+    pub async fn swap(&self, token_a: TokenType, amount_a: u64, token_b: TokenType, amount_b: u64, counterparty: &Address) -> Result<SwapReceipt> {
+        let secret: [u8; 32] = rand::random();
+        let hash_lock = hash_lock(&secret);
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(secrets) = &self.secrets {
+            secrets.set(&secret_name(&hash_lock), &secret)?;
+        }
+        let expires_at = self.clock.now() + self.config.timeout.as_secs();
+        let lock = self.lock(counterparty, token_a, amount_a, &hash_lock, expires_at).await?;
+
+        // The counterparty's lock must leave it half the timeout to claim after we do
+        let latest_expiry = expires_at - self.config.timeout.as_secs() / 2;
+        let (id, token_b) = (&hash_lock, &token_b);
+        let found = self
+            .poll(latest_expiry, move || async move {
+                let locks = self.gledger.get_htlcs(id).await?;
+                let now = self.clock.now();
+                Ok(locks.into_iter().find(|htlc| {
+                    htlc.state == HtlcState::Locked
+                        && htlc.lock.hash_lock == *id
+                        && htlc.lock.from == *counterparty
+                        && htlc.lock.to == self.account
+                        && htlc.lock.token_type == *token_b
+                        && htlc.lock.amount >= amount_b
+                        && htlc.lock.expires_at > now
+                        && htlc.lock.expires_at <= latest_expiry
+                }))
+            })
+            .await?;
+
+        match found {
+            Some(theirs) => {
+                self.publish(&hash_lock, SwapStatus::CounterpartyLocked);
+                let claim_tx = self.gledger.claim_htlc(&theirs.escrow_id, &hex::encode(secret)).await?;
+                self.publish(&hash_lock, SwapStatus::Completed);
+                Ok(SwapReceipt {
+                    swap_id: hash_lock,
+                    status: SwapStatus::Completed,
+                    lock,
+                    claim_tx: Some(claim_tx),
+                    refund_tx: None,
+                    secret: Some(hex::encode(secret)),
+                })
+            }
+            None => {
+                let receipt = self.refund_after(hash_lock, lock, expires_at).await?;
+                Ok(SwapReceipt { secret: Some(hex::encode(secret)), ..receipt })
+            }
+        }
+    }
+
+    /// Answer the swap locked under `hash_lock` by `initiator`
+    ///
+    /// Gives `amount_a` of `token_a` for the initiator's `amount_b` of `token_b`, which
+    /// must already be locked for this account under `hash_lock`, and not expire
+    /// before this side could claim it.
+    pub async fn accept(&self, hash_lock: &str, token_a: TokenType, amount_a: u64, token_b: TokenType, amount_b: u64, initiator: &Address) -> Result<SwapReceipt> {
+        if hash_lock.len() != 64 || !hash_lock.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+            return Err(EtherlinkError::Configuration(format!("Hash lock {} is not a lowercase hex SHA-256", hash_lock)));
+        }
+        let now = self.clock.now();
+        let theirs = self
+            .gledger
+            .get_htlcs(hash_lock)
+            .await?
+            .into_iter()
+            .find(|htlc| {
+                htlc.state == HtlcState::Locked
+                    && htlc.lock.hash_lock == hash_lock
+                    && htlc.lock.from == *initiator
+                    && htlc.lock.to == self.account
+                    && htlc.lock.token_type == token_b
+                    && htlc.lock.amount >= amount_b
+                    && htlc.lock.expires_at > now
+            })
+            .ok_or_else(|| EtherlinkError::Api(format!("No matching lock from {} under {}", initiator, hash_lock)))?;
+
+        let expires_at = theirs.lock.expires_at.saturating_sub(self.config.timeout.as_secs() / 2);
+        if expires_at <= now + self.config.timeout.as_secs() / 4 {
+            return Err(EtherlinkError::Timeout(format!("Swap {} expires too soon to answer safely", hash_lock)));
+        }
+        let lock = self.lock(initiator, token_a, amount_a, hash_lock, expires_at).await?;
+        self.publish(hash_lock, SwapStatus::CounterpartyLocked);
+
+        // Wait for the initiator to claim our lock, revealing the secret
+        let escrow_id = &lock.escrow_id;
+        let claimed = self
+            .poll(expires_at, move || async move {
+                let locks = self.gledger.get_htlcs(hash_lock).await?;
+                Ok(locks.into_iter().find(|htlc| htlc.escrow_id == *escrow_id && htlc.state == HtlcState::Claimed))
+            })
+            .await?;
+
+        match claimed {
+            Some(claimed) => {
+                if !reveals_secret(&claimed, hash_lock) {
+                    return Err(EtherlinkError::Integrity(format!("Preimage revealed for swap {} does not match its hash lock", hash_lock)));
+                }
+                let preimage = claimed.preimage.unwrap_or_default();
+                let claim_tx = self.gledger.claim_htlc(&theirs.escrow_id, &preimage).await?;
+                self.publish(hash_lock, SwapStatus::Completed);
+                Ok(SwapReceipt {
+                    swap_id: hash_lock.to_string(),
+                    status: SwapStatus::Completed,
+                    lock,
+                    claim_tx: Some(claim_tx),
+                    refund_tx: None,
+                    secret: Some(preimage),
+                })
+            }
+            None => self.refund_after(hash_lock.to_string(), lock, expires_at).await,
+        }
+    }
+
+    async fn lock(&self, to: &Address, token_type: TokenType, amount: u64, hash_lock: &str, expires_at: u64) -> Result<EscrowReceipt> {
+        let receipt = self
+            .gledger
+            .lock_htlc(&HashLock {
+                from: self.account.clone(),
+                to: to.clone(),
+                token_type,
+                amount,
+                hash_lock: hash_lock.to_string(),
+                expires_at,
+            })
+            .await?;
+        self.publish(hash_lock, SwapStatus::Locked);
+        Ok(receipt)
+    }
+
+    /// Call `check` every poll interval until it finds something or `deadline` passes
+    ///
+    /// Failed checks are logged and retried, since giving up early would strand the lock.
+    async fn poll<T, F, Fut>(&self, deadline: u64, check: F) -> Result<Option<T>>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<Option<T>>>,
+    {
+        loop {
+            match check().await {
+                Ok(Some(found)) => return Ok(Some(found)),
+                Ok(None) => {}
+                Err(e) => warn!("Swap status check failed: {}", e),
+            }
+            if self.clock.now() >= deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(self.config.poll_interval).await;
+        }
+    }
+
+    /// Wait out `expires_at`, then take this side's funds back
+    async fn refund_after(&self, swap_id: String, lock: EscrowReceipt, expires_at: u64) -> Result<SwapReceipt> {
+        while self.clock.now() <= expires_at {
+            tokio::time::sleep(self.config.poll_interval).await;
+        }
+        let refund_tx = self.gledger.refund_escrow(&lock.escrow_id).await?;
+        self.publish(&swap_id, SwapStatus::Refunded);
+        Ok(SwapReceipt { swap_id, status: SwapStatus::Refunded, lock, claim_tx: None, refund_tx: Some(refund_tx), secret: None })
+    }
+
+    fn publish(&self, swap_id: &str, status: SwapStatus) {
+        if let Some(events) = &self.events {
+            events.publish(EtherlinkEvent::SwapStatusChanged {
+                swap_id: swap_id.to_string(),
+                status,
+                correlation_id: crate::correlation::current(),
+            });
+        }
+    }
+}
+
+/// Name the secret of the swap locked under `hash_lock` is stored under
+pub fn secret_name(hash_lock: &str) -> String {
+    format!("swap:{}", hash_lock)
+}
+
+/// Hex SHA-256 of a swap secret
+pub fn hash_lock(secret: &[u8]) -> String {
+    hex::encode(Hasher::digest(HashAlgorithm::Sha256, secret))
+}
+
+/// Whether a claimed lock reveals the secret of `hash_lock`
+pub fn reveals_secret(htlc: &HtlcInfo, hash_lock: &str) -> bool {
+    htlc.preimage.as_deref().and_then(|preimage| hex::decode(preimage).ok()).is_some_and(|secret| self::hash_lock(&secret) == hash_lock)
+}
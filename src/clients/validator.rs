@@ -0,0 +1,283 @@
+//! Validator operations client for staking operators
+//!
+//! Validator state is served by GHOSTD under `/validators`; this client covers
+//! registration, stake bonding, attestation and missed-block metrics, and slashing
+//! history.
+
+use crate::{Result, EtherlinkConfig, EtherlinkError, Address, BlockHeight};
+use crate::clients::{ServiceClient, ApiResponse};
+use crate::clients::context::{CallContext, WithContext};
+use crate::auth::Permission;
+use crate::validation::{ConfigErrors, Validator};
+use reqwest::Client as HttpClient;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+
+/// Highest commission a validator can charge, in basis points
+pub const MAX_COMMISSION_BPS: u16 = 10_000;
+
+/// Client for validator registration, staking and slashing queries
+#[derive(Debug, Clone)]
+pub struct ValidatorClient {
+    base_url: String,
+    http_client: Arc<HttpClient>,
+    context: CallContext,
+}
+
+impl ValidatorClient {
+    /// Create a new validator client against GHOSTD
+    pub fn new(config: &EtherlinkConfig, http_client: Arc<HttpClient>) -> Self {
+        let base_url = format!("{}/api/v1", config.ghostd_endpoint.trim_end_matches('/'));
+        Self {
+            base_url,
+            http_client,
+            context: CallContext::default(),
+        }
+    }
+
+    /// Client for calls with a different endpoint, auth token or timeout
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            base_url: context.base_url().unwrap_or_else(|| self.base_url.clone()),
+            http_client: self.http_client.clone(),
+            context,
+        }
+    }
+
+    /// Register a new validator, bonding its initial self-stake
+    pub async fn register_validator(&self, registration: ValidatorRegistration) -> Result<ValidatorInfo> {
+        self.context.require(&[Permission::ManageValidator])?;
+        registration.validate()?;
+        let url = format!("{}/validators", self.base_url);
+        self.post(&url, &registration).await
+    }
+
+    /// Bond `amount` more stake to `validator`
+    pub async fn bond_stake(&self, validator: &Address, amount: u64) -> Result<StakeChange> {
+        self.change_stake(validator, "bond", amount).await
+    }
+
+    /// Start unbonding `amount` of stake; it is released after the unbonding period
+    pub async fn unbond_stake(&self, validator: &Address, amount: u64) -> Result<StakeChange> {
+        self.change_stake(validator, "unbond", amount).await
+    }
+
+    async fn change_stake(&self, validator: &Address, action: &str, amount: u64) -> Result<StakeChange> {
+        self.context.require(&[Permission::ManageValidator])?;
+        if amount == 0 {
+            return Err(EtherlinkError::Configuration(format!("Cannot {} zero stake", action)));
+        }
+        let url = format!("{}/validators/{}/{}", self.base_url, validator.as_str(), action);
+        self.post(&url, &serde_json::json!({ "amount": amount })).await
+    }
+
+    /// Get a validator's status, stake and commission
+    pub async fn get_validator(&self, validator: &Address) -> Result<ValidatorInfo> {
+        self.context.require(&[Permission::ReadValidators])?;
+        let url = format!("{}/validators/{}", self.base_url, validator.as_str());
+        self.get(&url).await
+    }
+
+    /// List validators in the active set
+    pub async fn list_validators(&self) -> Result<Vec<ValidatorInfo>> {
+        self.context.require(&[Permission::ReadValidators])?;
+        let url = format!("{}/validators", self.base_url);
+        self.get(&url).await
+    }
+
+    /// Get whether the validator attested in the current epoch
+    pub async fn get_attestation_status(&self, validator: &Address) -> Result<AttestationStatus> {
+        self.context.require(&[Permission::ReadValidators])?;
+        let url = format!("{}/validators/{}/attestations", self.base_url, validator.as_str());
+        self.get(&url).await
+    }
+
+    /// Get signed and missed blocks over the last `window_blocks` blocks
+    pub async fn get_missed_blocks(&self, validator: &Address, window_blocks: u64) -> Result<MissedBlockMetrics> {
+        self.context.require(&[Permission::ReadValidators])?;
+        let url = format!("{}/validators/{}/missed-blocks?window={}", self.base_url, validator.as_str(), window_blocks);
+        self.get(&url).await
+    }
+
+    /// Get every slashing event recorded against the validator, oldest first
+    pub async fn get_slashing_events(&self, validator: &Address) -> Result<Vec<SlashingEvent>> {
+        self.context.require(&[Permission::ReadValidators])?;
+        let url = format!("{}/validators/{}/slashing", self.base_url, validator.as_str());
+        self.get(&url).await
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response: ApiResponse<T> = self.http_client
+            .get(url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    async fn post<B: Serialize, T: serde::de::DeserializeOwned>(&self, url: &str, body: &B) -> Result<T> {
+        let response: ApiResponse<T> = self.http_client
+            .post(url)
+            .with_context(&self.context)
+            .json(body)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl ServiceClient for ValidatorClient {
+    fn service_name(&self) -> &'static str {
+        "validator"
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn health_check(&self) -> Result<serde_json::Value> {
+        let url = format!("{}/health", self.base_url);
+        self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))
+    }
+
+    async fn status(&self) -> Result<serde_json::Value> {
+        let url = format!("{}/validators/status", self.base_url);
+        self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))
+    }
+}
+
+// Data structures for validator operations
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorRegistration {
+    /// Operator account that owns the validator and its self-stake
+    pub address: Address,
+    /// Hex-encoded key that signs blocks and attestations
+    pub consensus_public_key: String,
+    pub moniker: String,
+    /// Share of delegator rewards kept by the operator, in basis points
+    pub commission_bps: u16,
+    pub self_stake: u64,
+}
+
+impl ValidatorRegistration {
+    /// Check every field, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        let key = hex::decode(self.consensus_public_key.trim_start_matches("0x"));
+        v.check(key.is_ok_and(|key| !key.is_empty()), "consensus_public_key", "must be a hex-encoded public key");
+        v.check(!self.moniker.trim().is_empty(), "moniker", "must not be empty");
+        v.check(self.commission_bps <= MAX_COMMISSION_BPS, "commission_bps", &format!("must be at most {}", MAX_COMMISSION_BPS));
+        v.check(self.self_stake > 0, "self_stake", "must be greater than zero");
+        v.finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidatorStatus {
+    /// In the active set, producing and attesting blocks
+    Active,
+    /// Registered but outside the active set
+    Inactive,
+    /// Removed from the active set after a slashing event
+    Jailed,
+    /// Leaving, with its stake waiting out the unbonding period
+    Unbonding,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorInfo {
+    pub address: Address,
+    pub moniker: String,
+    pub consensus_public_key: String,
+    pub status: ValidatorStatus,
+    pub bonded_stake: u64,
+    pub commission_bps: u16,
+    /// Height at which a jailed validator may rejoin
+    pub jailed_until: Option<BlockHeight>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakeChange {
+    pub tx_hash: String,
+    pub bonded_stake: u64,
+    /// Stake still waiting out the unbonding period
+    pub unbonding_stake: u64,
+    /// Height at which the most recently unbonded stake is released
+    pub release_height: Option<BlockHeight>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttestationStatus {
+    pub epoch: u64,
+    /// Whether the validator has attested in `epoch`
+    pub attested: bool,
+    pub last_attested_height: Option<BlockHeight>,
+    /// Fraction of the last epochs attested, between 0 and 1
+    pub participation_rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissedBlockMetrics {
+    pub window_blocks: u64,
+    pub signed: u64,
+    pub missed: u64,
+    /// Most recent missed heights, newest last
+    pub missed_heights: Vec<BlockHeight>,
+}
+
+impl MissedBlockMetrics {
+    /// Share of blocks in the window that were signed, between 0 and 1
+    pub fn uptime(&self) -> f64 {
+        match self.signed + self.missed {
+            0 => 1.0,
+            total => self.signed as f64 / total as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlashReason {
+    DoubleSign,
+    Downtime,
+    InvalidAttestation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashingEvent {
+    pub height: BlockHeight,
+    pub reason: SlashReason,
+    /// Stake burned by the slash
+    pub amount: u64,
+    pub jailed_until: Option<BlockHeight>,
+}
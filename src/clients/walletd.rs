@@ -1,7 +1,15 @@
 //! WALLETD (Wallet Service) client implementation
 
-use crate::{Result, EtherlinkConfig, EtherlinkError, Address, TxHash};
+use crate::{Result, EtherlinkConfig, EtherlinkError, Address, SecretString, TxHash};
 use crate::clients::{ServiceClient, ApiResponse};
+use crate::clients::context::{CallContext, WithContext};
+use crate::auth::Permission;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::clients::grpc::{CallKind, GrpcRoute};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::proto::walletd::v1::{self as walletd_pb, wallet_service_client::WalletServiceClient};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::ChannelManager;
 use reqwest::Client as HttpClient;
 use serde::{Serialize, Deserialize};
 use std::sync::Arc;
@@ -12,6 +20,9 @@ use std::collections::HashMap;
 pub struct WalletdClient {
     base_url: String,
     http_client: Arc<HttpClient>,
+    context: CallContext,
+    #[cfg(not(target_arch = "wasm32"))]
+    grpc: Option<GrpcRoute>,
 }
 
 impl WalletdClient {
@@ -21,16 +32,49 @@ impl WalletdClient {
         Self {
             base_url,
             http_client,
+            context: CallContext::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            grpc: None,
+        }
+    }
+
+    /// Call WALLETD over gRPC at `endpoint` first, see [`crate::clients::grpc`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_grpc(mut self, channels: ChannelManager, endpoint: impl Into<String>) -> Self {
+        self.grpc = Some(GrpcRoute::new(channels, endpoint.into()));
+        self
+    }
+
+    /// Client for calls with a different endpoint, auth token or timeout
+    pub fn with_context(&self, context: CallContext) -> Self {
+        Self {
+            base_url: context.base_url().unwrap_or_else(|| self.base_url.clone()),
+            http_client: self.http_client.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            grpc: self.grpc.as_ref().and_then(|grpc| grpc.with_context(&context)),
+            context,
         }
     }
 
     /// Create a new wallet
     pub async fn create_wallet(&self, request: CreateWalletRequest) -> Result<WalletInfo> {
+        self.context.require(&[Permission::WriteWallet])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = walletd_pb::CreateWalletRequest::from(request.clone());
+            let wallet = grpc.call(&self.context, CallKind::Write, message, |channel, request| async move {
+                WalletServiceClient::new(channel).create_wallet(request).await
+            }).await?;
+            if let Some(wallet) = wallet {
+                return wallet.try_into();
+            }
+        }
         let url = format!("{}/wallets", self.base_url);
         let response: ApiResponse<WalletInfo> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&request)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -42,10 +86,21 @@ impl WalletdClient {
 
     /// List all wallets
     pub async fn list_wallets(&self) -> Result<Vec<WalletInfo>> {
+        self.context.require(&[Permission::ReadWallet])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let response = grpc.call(&self.context, CallKind::Read, (), |channel, request| async move {
+                WalletServiceClient::new(channel).list_wallets(request).await
+            }).await?;
+            if let Some(response) = response {
+                return response.wallets.into_iter().map(WalletInfo::try_from).collect();
+            }
+        }
         let url = format!("{}/wallets", self.base_url);
         let response: ApiResponse<Vec<WalletInfo>> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -57,11 +112,23 @@ impl WalletdClient {
 
     /// Sign a transaction
     pub async fn sign_transaction(&self, request: SignTransactionRequest) -> Result<SignedTransaction> {
+        self.context.require(&[Permission::SignTransaction])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = walletd_pb::SignTransactionRequest::try_from(request.clone())?;
+            let signed = grpc.call(&self.context, CallKind::Write, message, |channel, request| async move {
+                WalletServiceClient::new(channel).sign_transaction(request).await
+            }).await?;
+            if let Some(signed) = signed {
+                return signed.try_into();
+            }
+        }
         let url = format!("{}/wallets/{}/sign", self.base_url, request.wallet_id);
         let response: ApiResponse<SignedTransaction> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&request)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -73,10 +140,22 @@ impl WalletdClient {
 
     /// Get wallet addresses
     pub async fn get_addresses(&self, wallet_id: &str) -> Result<Vec<WalletAddress>> {
+        self.context.require(&[Permission::ReadWallet])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = walletd_pb::GetAddressesRequest { wallet_id: wallet_id.to_string() };
+            let response = grpc.call(&self.context, CallKind::Read, message, |channel, request| async move {
+                WalletServiceClient::new(channel).get_addresses(request).await
+            }).await?;
+            if let Some(response) = response {
+                return Ok(response.addresses.into_iter().map(WalletAddress::from).collect());
+            }
+        }
         let url = format!("{}/wallets/{}/addresses", self.base_url, wallet_id);
         let response: ApiResponse<Vec<WalletAddress>> = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -88,12 +167,27 @@ impl WalletdClient {
 
     /// Generate new address for wallet
     pub async fn generate_address(&self, wallet_id: &str, derivation_path: Option<String>) -> Result<WalletAddress> {
+        self.context.require(&[Permission::WriteWallet])?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(grpc) = &self.grpc {
+            let message = walletd_pb::GenerateAddressRequest {
+                wallet_id: wallet_id.to_string(),
+                derivation_path: derivation_path.clone().unwrap_or_default(),
+            };
+            let address = grpc.call(&self.context, CallKind::Write, message, |channel, request| async move {
+                WalletServiceClient::new(channel).generate_address(request).await
+            }).await?;
+            if let Some(address) = address {
+                return Ok(address.into());
+            }
+        }
         let url = format!("{}/wallets/{}/addresses", self.base_url, wallet_id);
         let request = GenerateAddressRequest { derivation_path };
         let response: ApiResponse<WalletAddress> = self.http_client
             .post(&url)
+            .with_context(&self.context)
             .json(&request)
-            .send()
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -104,7 +198,8 @@ impl WalletdClient {
     }
 }
 
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl ServiceClient for WalletdClient {
     fn service_name(&self) -> &'static str {
         "walletd"
@@ -118,7 +213,8 @@ impl ServiceClient for WalletdClient {
         let url = format!("{}/health", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -132,7 +228,8 @@ impl ServiceClient for WalletdClient {
         let url = format!("{}/status", self.base_url);
         let response = self.http_client
             .get(&url)
-            .send()
+            .with_context(&self.context)
+            .send_logged()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?
             .json()
@@ -149,8 +246,8 @@ impl ServiceClient for WalletdClient {
 pub struct CreateWalletRequest {
     pub name: String,
     pub algorithm: CryptoAlgorithm,
-    pub mnemonic: Option<String>, // If provided, restore from mnemonic
-    pub passphrase: Option<String>,
+    pub mnemonic: Option<SecretString>, // If provided, restore from mnemonic
+    pub passphrase: Option<SecretString>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,73 @@
+//! Time sources for expiry logic
+//!
+//! Token, cache and batch expiry read the time through a [`Clock`] instead of the
+//! system clock directly, so tests can swap in a [`MockClock`] and move time forward
+//! deterministically.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Source of the current wall-clock time
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Milliseconds since the unix epoch
+    fn now_millis(&self) -> u64;
+
+    /// Seconds since the unix epoch
+    fn now(&self) -> u64 {
+        self.now_millis() / 1000
+    }
+}
+
+/// A clock shared between the components it is injected into
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        chrono::Utc::now().timestamp_millis().max(0) as u64
+    }
+}
+
+/// The system clock as a [`SharedClock`], the default everywhere a clock is injected
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// A clock that only moves when told to
+#[derive(Debug, Default)]
+pub struct MockClock {
+    millis: AtomicU64,
+}
+
+impl MockClock {
+    /// Start at `secs` seconds since the unix epoch
+    pub fn new(secs: u64) -> Arc<Self> {
+        Arc::new(Self { millis: AtomicU64::new(secs.saturating_mul(1000)) })
+    }
+
+    /// Start at the current system time
+    pub fn starting_now() -> Arc<Self> {
+        Arc::new(Self { millis: AtomicU64::new(SystemClock.now_millis()) })
+    }
+
+    /// Move time forward
+    pub fn advance(&self, by: Duration) {
+        self.millis.fetch_add(by.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    /// Jump to `millis` since the unix epoch, which may be in the past
+    pub fn set_millis(&self, millis: u64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
@@ -1,9 +1,25 @@
-use crate::{EtherlinkError, Result, Address};
+use crate::{EtherlinkError, Result, Address, SecretString};
+use crate::clock::{self, SharedClock};
+use crate::events::{EtherlinkEvent, EventBus};
+use crate::coalesce::{CoalesceSnapshot, SingleFlight};
+use crate::address;
+use crate::idn::{self, HomographPolicy};
+use crate::validation::{ConfigErrors, Validator};
+use crate::proto::cns::v1::{self as cns_pb, cns_service_client::CnsServiceClient};
+use crate::transport::ChannelManager;
+use crate::clients::context::WithContext;
+use futures::{stream, StreamExt};
+use reqwest::Client as HttpClient;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tokio_stream::StreamExt;
-use tonic::{Request, Response, Status};
+use crate::queue::{self, QueueConfig, QueueReceiver};
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tonic::{Code, Request, Status, Streaming};
 use tracing::{debug, info, warn};
 
 /// CNS (Cryptographic Name Service) client for domain resolution
@@ -11,6 +27,34 @@ use tracing::{debug, info, warn};
 pub struct CNSClient {
     config: CNSConfig,
     cache: std::sync::Arc<RwLock<DomainCache>>,
+    /// Domains pointing to each address, keyed by address
+    reverse_cache: Arc<RwLock<DomainCache<Vec<String>>>>,
+    /// Live cache TTL, shared by clones so it can be changed at runtime
+    cache_ttl: std::sync::Arc<AtomicU64>,
+    inflight: SingleFlight<String, DomainResolution>,
+    channels: ChannelManager,
+    /// For bridges reached over HTTP, such as the Unstoppable Domains Resolution API
+    http_client: Arc<HttpClient>,
+    clock: SharedClock,
+    events: Option<EventBus>,
+}
+
+/// Deadline for one CNS call unless configured otherwise
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 5000;
+
+fn default_request_timeout_ms() -> u64 {
+    DEFAULT_REQUEST_TIMEOUT_MS
+}
+
+/// Unstoppable Domains Resolution API
+pub const DEFAULT_UNSTOPPABLE_ENDPOINT: &str = "https://api.unstoppabledomains.com/resolve";
+
+fn default_cache_cleanup_interval_seconds() -> u64 {
+    60
+}
+
+fn default_unstoppable_endpoint() -> String {
+    DEFAULT_UNSTOPPABLE_ENDPOINT.to_string()
 }
 
 /// CNS configuration
@@ -20,9 +64,71 @@ pub struct CNSConfig {
     pub enable_cache: bool,
     pub cache_ttl_seconds: u64,
     pub max_cache_entries: usize,
+    /// How often expired resolutions are swept from the cache
+    #[serde(default = "default_cache_cleanup_interval_seconds")]
+    pub cache_cleanup_interval_seconds: u64,
     pub supported_tlds: Vec<String>,
     pub enable_ens_bridge: bool,
     pub enable_unstoppable_bridge: bool,
+    /// How lookalike (homograph) domains are treated at registration and resolution
+    #[serde(default)]
+    pub homograph_policy: HomographPolicy,
+    /// Deadline for each call to the CNS service
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+    /// Resolution API serving `.crypto`, `.nft` and `.x`, without the `/domains` suffix
+    #[serde(default = "default_unstoppable_endpoint")]
+    pub unstoppable_endpoint: String,
+    /// Sent as `Authorization: Bearer <key>` to the Resolution API
+    #[serde(default)]
+    pub unstoppable_api_key: Option<SecretString>,
+    /// Delay between attempts to resubscribe a dropped domain change stream
+    #[serde(default)]
+    pub subscription_backoff: SubscriptionBackoff,
+    /// Buffering of changes between the stream reader and a slow subscriber
+    #[serde(default)]
+    pub subscription_queue: QueueConfig,
+    /// Proxy for HTTP bridges such as the Resolution API
+    #[serde(default)]
+    pub proxy: Option<crate::transport::ProxyConfig>,
+}
+
+/// Delay between attempts to reopen a dropped subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubscriptionBackoff {
+    /// Delay before the first attempt
+    pub initial_ms: u64,
+    /// Upper bound on the doubling delay
+    pub max_ms: u64,
+    /// Give up after this many failed attempts in a row, counting streams that end
+    /// without delivering a change; `None` retries forever
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for SubscriptionBackoff {
+    fn default() -> Self {
+        Self {
+            initial_ms: 200,
+            max_ms: 30_000,
+            max_attempts: None,
+        }
+    }
+}
+
+impl SubscriptionBackoff {
+    /// Delay before the given attempt, counting from zero
+    pub fn delay(&self, attempt: u32) -> Duration {
+        Duration::from_millis(self.initial_ms.saturating_mul(2u64.saturating_pow(attempt)).min(self.max_ms))
+    }
+
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.initial_ms > 0, "initial_ms", "must be greater than zero");
+        v.check(self.max_ms >= self.initial_ms, "max_ms", "must not be less than initial_ms");
+        v.check(self.max_attempts != Some(0), "max_attempts", "must be greater than zero when set");
+        v.finish()
+    }
 }
 
 impl Default for CNSConfig {
@@ -32,6 +138,7 @@ impl Default for CNSConfig {
             enable_cache: true,
             cache_ttl_seconds: 3600,
             max_cache_entries: 10000,
+            cache_cleanup_interval_seconds: default_cache_cleanup_interval_seconds(),
             supported_tlds: vec![
                 "ghost".to_string(),
                 "gcc".to_string(),
@@ -41,69 +148,214 @@ impl Default for CNSConfig {
             ],
             enable_ens_bridge: true,
             enable_unstoppable_bridge: true,
+            homograph_policy: HomographPolicy::default(),
+            request_timeout_ms: DEFAULT_REQUEST_TIMEOUT_MS,
+            unstoppable_endpoint: default_unstoppable_endpoint(),
+            unstoppable_api_key: None,
+            subscription_backoff: SubscriptionBackoff::default(),
+            subscription_queue: QueueConfig::default(),
+            proxy: None,
         }
     }
 }
 
-/// Domain cache for performance
+impl CNSConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.endpoint("endpoint", &self.endpoint, &["http", "https", "unix", "npipe"], true, true);
+        v.timeout("request_timeout_ms", self.request_timeout_ms);
+        v.nested("subscription_backoff", self.subscription_backoff.validate());
+        v.nested("subscription_queue", self.subscription_queue.validate());
+        if let Some(proxy) = &self.proxy {
+            v.nested("proxy", proxy.validate());
+        }
+        if self.enable_unstoppable_bridge {
+            v.endpoint("unstoppable_endpoint", &self.unstoppable_endpoint, &["http", "https"], true, true);
+        }
+        v.check(
+            self.unstoppable_api_key.as_ref().is_none_or(|key| !key.expose_secret().is_empty()),
+            "unstoppable_api_key",
+            "must not be empty when set",
+        );
+        if self.enable_cache {
+            v.check(self.cache_ttl_seconds > 0, "cache_ttl_seconds", "must be greater than zero while enable_cache is set");
+            v.check(self.max_cache_entries > 0, "max_cache_entries", "must be greater than zero while enable_cache is set");
+            v.check(
+                self.cache_cleanup_interval_seconds > 0,
+                "cache_cleanup_interval_seconds",
+                "must be greater than zero while enable_cache is set",
+            );
+        }
+
+        v.check(!self.supported_tlds.is_empty(), "supported_tlds", "must list at least one TLD");
+        let mut seen = HashSet::new();
+        for (i, tld) in self.supported_tlds.iter().enumerate() {
+            let field = format!("supported_tlds[{}]", i);
+            if tld.starts_with('.') {
+                v.push(&field, format!("`{}` must not start with a dot", tld));
+            } else if tld.is_empty() || tld.starts_with('-') || tld.ends_with('-') || !tld.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+                v.push(&field, format!("`{}` must be lowercase letters, digits and inner hyphens", tld));
+            } else if !seen.insert(tld.as_str()) {
+                v.push(&field, format!("`{}` is listed more than once", tld));
+            }
+        }
+        v.finish()
+    }
+}
+
+/// Least-recently-used cache of lookups, each expiring at its own time
 #[derive(Debug, Clone)]
-struct DomainCache {
-    entries: HashMap<String, CacheEntry>,
+struct DomainCache<V = DomainResolution> {
+    entries: HashMap<String, CacheEntry<V>>,
+    /// Domains by the tick they were last used at, least recent first
+    recency: BTreeMap<u64, String>,
+    next_tick: u64,
     max_entries: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expirations: u64,
 }
 
 #[derive(Debug, Clone)]
-struct CacheEntry {
-    resolution: DomainResolution,
+struct CacheEntry<V> {
+    value: V,
     expires_at: u64,
+    last_used: u64,
 }
 
-impl DomainCache {
+/// Snapshot of the resolution cache, see [`CNSClient::cache_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that went to the service, including for expired entries
+    pub misses: u64,
+    /// Live entries dropped to make room for new ones
+    pub evictions: u64,
+    /// Entries dropped because they expired
+    pub expirations: u64,
+}
+
+impl CacheStats {
+    /// Share of lookups answered from the cache; 0 before the first lookup
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+impl<V: Clone> DomainCache<V> {
     fn new(max_entries: usize) -> Self {
         Self {
             entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_tick: 0,
             max_entries,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            expirations: 0,
         }
     }
 
-    fn get(&self, domain: &str) -> Option<DomainResolution> {
-        let now = chrono::Utc::now().timestamp() as u64;
-        if let Some(entry) = self.entries.get(domain) {
-            if entry.expires_at > now {
-                return Some(entry.resolution.clone());
+    /// Live entry for `domain`, marking it most recently used
+    fn get(&mut self, domain: &str, now: u64) -> Option<V> {
+        let expired = match self.entries.get(domain) {
+            None => {
+                self.misses += 1;
+                return None;
             }
+            Some(entry) => entry.expires_at <= now,
+        };
+        if expired {
+            self.remove(domain);
+            self.expirations += 1;
+            self.misses += 1;
+            return None;
         }
-        None
-    }
 
-    fn insert(&mut self, domain: String, resolution: DomainResolution, ttl: u64) {
-        let now = chrono::Utc::now().timestamp() as u64;
+        self.hits += 1;
+        let tick = self.tick();
+        let entry = self.entries.get_mut(domain)?;
+        self.recency.remove(&entry.last_used);
+        self.recency.insert(tick, domain.to_string());
+        entry.last_used = tick;
+        Some(entry.value.clone())
+    }
 
-        // Simple LRU eviction
+    /// Store `value` until `expires_at`, evicting the least recently used entry when full
+    fn insert(&mut self, domain: String, value: V, expires_at: u64) {
+        self.remove(&domain);
         if self.entries.len() >= self.max_entries {
-            if let Some(oldest_key) = self.entries.keys().next().cloned() {
-                self.entries.remove(&oldest_key);
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+                self.evictions += 1;
             }
         }
 
-        self.entries.insert(domain, CacheEntry {
-            resolution,
-            expires_at: now + ttl,
-        });
+        let tick = self.tick();
+        self.recency.insert(tick, domain.clone());
+        self.entries.insert(domain, CacheEntry { value, expires_at, last_used: tick });
+    }
+
+    fn remove(&mut self, domain: &str) -> bool {
+        match self.entries.remove(domain) {
+            Some(entry) => {
+                self.recency.remove(&entry.last_used);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
     }
 
-    fn clear_expired(&mut self) {
-        let now = chrono::Utc::now().timestamp() as u64;
-        self.entries.retain(|_, entry| entry.expires_at > now);
+    fn clear_expired(&mut self, now: u64) {
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.expires_at <= now)
+            .map(|(domain, _)| domain.clone())
+            .collect();
+        for domain in expired {
+            self.remove(&domain);
+            self.expirations += 1;
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            capacity: self.max_entries,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            expirations: self.expirations,
+        }
     }
 }
 
 /// Domain resolution result
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub struct DomainResolution {
     pub domain: String,
     pub owner: Address,
-    pub records: BTreeMap<String, String>,
+    pub records: RecordSet,
     pub metadata: HashMap<String, String>,
     pub expires_at: u64,
     pub service_type: ServiceType,
@@ -114,6 +366,7 @@ pub struct DomainResolution {
 
 /// Service type for domain routing
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub enum ServiceType {
     Blockchain,
     Wallet,
@@ -132,6 +385,266 @@ pub struct DnsRecord {
     pub priority: Option<u16>,
 }
 
+/// Separator between the values of a multi-valued record type in the wire map
+pub const RECORD_VALUE_SEPARATOR: &str = "\n";
+
+/// Longest TXT value, the DNS character-string limit
+pub const MAX_TXT_LEN: usize = 255;
+
+/// Content hash schemes accepted by [`RecordSetBuilder::content_hash`]
+const CONTENT_HASH_SCHEMES: &[&str] = &["ipfs://", "ipns://", "bzz://", "ar://", "gstore://"];
+
+/// Records of a domain, keyed by upper-case record type
+///
+/// On the wire this is a flat `type -> value` map; types with several values
+/// (more than one `A` or `TXT`, say) join them with [`RECORD_VALUE_SEPARATOR`].
+/// Chain addresses live under `ADDR:<CHAIN>` and the content hash under
+/// `CONTENTHASH`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<String, String>", into = "BTreeMap<String, String>")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+pub struct RecordSet {
+    records: BTreeMap<String, Vec<String>>,
+}
+
+impl RecordSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn builder() -> RecordSetBuilder {
+        RecordSetBuilder::default()
+    }
+
+    /// First value of `record_type`, as stored
+    pub fn get(&self, record_type: &str) -> Option<&String> {
+        self.values(record_type).first()
+    }
+
+    /// Every value of `record_type`, as stored
+    pub fn values(&self, record_type: &str) -> &[String] {
+        self.records.get(&record_type.to_ascii_uppercase()).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// `(record type, value)` pairs, one per value
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.records
+            .iter()
+            .flat_map(|(record_type, values)| values.iter().map(move |value| (record_type.as_str(), value.as_str())))
+    }
+
+    /// IPv4 addresses, skipping values that do not parse
+    pub fn a_records(&self) -> Vec<Ipv4Addr> {
+        self.values("A").iter().filter_map(|value| value.parse().ok()).collect()
+    }
+
+    /// IPv6 addresses, skipping values that do not parse
+    pub fn aaaa_records(&self) -> Vec<Ipv6Addr> {
+        self.values("AAAA").iter().filter_map(|value| value.parse().ok()).collect()
+    }
+
+    pub fn cname(&self) -> Option<&str> {
+        self.get("CNAME").map(String::as_str)
+    }
+
+    pub fn txt(&self) -> Vec<&str> {
+        self.values("TXT").iter().map(String::as_str).collect()
+    }
+
+    /// Mail exchangers as `(preference, host)`, lowest preference first
+    pub fn mx(&self) -> Vec<(u16, &str)> {
+        let mut exchangers: Vec<(u16, &str)> = self
+            .values("MX")
+            .iter()
+            .filter_map(|value| {
+                let (preference, host) = value.split_once(' ')?;
+                Some((preference.parse().ok()?, host))
+            })
+            .collect();
+        exchangers.sort();
+        exchangers
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.get("PORT").and_then(|port| port.parse().ok())
+    }
+
+    /// Address the domain points to on `chain`, e.g. `eth` or `ghost`
+    pub fn address_for_chain(&self, chain: &str) -> Option<Address> {
+        self.get(&address_key(chain)).cloned().map(Address::new)
+    }
+
+    /// `ipfs://`, `ipns://`, `bzz://`, `ar://` or `gstore://` URI of the domain's content
+    pub fn content_hash(&self) -> Option<&str> {
+        self.get("CONTENTHASH").map(String::as_str)
+    }
+
+    /// Check every value against its record type
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        for (record_type, values) in &self.records {
+            for (i, value) in values.iter().enumerate() {
+                let field = format!("{}[{}]", record_type, i);
+                if let Err(message) = check_record(record_type, value) {
+                    v.push(field, message);
+                }
+            }
+        }
+        v.finish()
+    }
+
+    /// Flat `type -> value` map as sent to and received from CNS
+    pub fn to_wire(&self) -> BTreeMap<String, String> {
+        self.records
+            .iter()
+            .map(|(record_type, values)| (record_type.clone(), values.join(RECORD_VALUE_SEPARATOR)))
+            .collect()
+    }
+
+    fn push(&mut self, record_type: &str, value: String) {
+        self.records.entry(record_type.to_ascii_uppercase()).or_default().push(value);
+    }
+}
+
+impl From<BTreeMap<String, String>> for RecordSet {
+    fn from(wire: BTreeMap<String, String>) -> Self {
+        wire.into_iter().collect()
+    }
+}
+
+impl From<RecordSet> for BTreeMap<String, String> {
+    fn from(records: RecordSet) -> Self {
+        records.to_wire()
+    }
+}
+
+impl FromIterator<(String, String)> for RecordSet {
+    /// Collect wire pairs, splitting joined values and keeping repeated types
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(pairs: I) -> Self {
+        let mut records = RecordSet::new();
+        for (record_type, value) in pairs {
+            for value in value.split(RECORD_VALUE_SEPARATOR) {
+                records.push(&record_type, value.to_string());
+            }
+        }
+        records
+    }
+}
+
+fn address_key(chain: &str) -> String {
+    format!("ADDR:{}", chain.to_ascii_uppercase())
+}
+
+/// Why `value` is not a valid `record_type` record, if it is not
+fn check_record(record_type: &str, value: &str) -> std::result::Result<(), String> {
+    let host_ok = |host: &str| idn::to_ascii(host).is_ok_and(|host| host.contains('.') && !host.contains(".."));
+    match record_type {
+        "A" => value.parse::<Ipv4Addr>().map(drop).map_err(|_| format!("`{}` is not an IPv4 address", value)),
+        "AAAA" => value.parse::<Ipv6Addr>().map(drop).map_err(|_| format!("`{}` is not an IPv6 address", value)),
+        "CNAME" if !host_ok(value) => Err(format!("`{}` is not a domain name", value)),
+        "TXT" if value.len() > MAX_TXT_LEN => Err(format!("is {} bytes; TXT values are at most {}", value.len(), MAX_TXT_LEN)),
+        "MX" => match value.split_once(' ') {
+            Some((preference, host)) if preference.parse::<u16>().is_ok() && host_ok(host) => Ok(()),
+            _ => Err(format!("`{}` must be `<preference> <host>`", value)),
+        },
+        "PORT" => match value.parse::<u16>() {
+            Ok(port) if port > 0 => Ok(()),
+            _ => Err(format!("`{}` is not a port between 1 and 65535", value)),
+        },
+        "CONTENTHASH" if !CONTENT_HASH_SCHEMES.iter().any(|scheme| value.len() > scheme.len() && value.starts_with(scheme)) => {
+            Err(format!("`{}` must be an ipfs://, ipns://, bzz://, ar:// or gstore:// URI", value))
+        }
+        _ => match record_type.strip_prefix("ADDR:") {
+            Some("") => Err("chain name must not be empty".to_string()),
+            Some("ETH") if address::evm_bytes(&Address::new(value.to_string())).is_err() => {
+                Err(format!("`{}` is not an EVM address", value))
+            }
+            Some("GHOST") if address::native_bytes(&Address::new(value.to_string())).is_err() => {
+                Err(format!("`{}` is not a GhostChain address", value))
+            }
+            Some(_) if value.is_empty() => Err("address must not be empty".to_string()),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Builds a [`RecordSet`], checking each record against its type
+#[derive(Debug, Clone, Default)]
+pub struct RecordSetBuilder {
+    records: RecordSet,
+}
+
+impl RecordSetBuilder {
+    pub fn a(mut self, address: Ipv4Addr) -> Self {
+        self.records.push("A", address.to_string());
+        self
+    }
+
+    pub fn aaaa(mut self, address: Ipv6Addr) -> Self {
+        self.records.push("AAAA", address.to_string());
+        self
+    }
+
+    pub fn cname(mut self, target: impl Into<String>) -> Self {
+        self.records.push("CNAME", target.into());
+        self
+    }
+
+    pub fn txt(mut self, text: impl Into<String>) -> Self {
+        self.records.push("TXT", text.into());
+        self
+    }
+
+    pub fn mx(mut self, preference: u16, host: impl Into<String>) -> Self {
+        self.records.push("MX", format!("{} {}", preference, host.into()));
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.records.push("PORT", port.to_string());
+        self
+    }
+
+    /// Point the domain at `address` on `chain`
+    pub fn address(mut self, chain: &str, address: Address) -> Self {
+        self.records.push(&address_key(chain), address.0);
+        self
+    }
+
+    pub fn content_hash(mut self, uri: impl Into<String>) -> Self {
+        self.records.push("CONTENTHASH", uri.into());
+        self
+    }
+
+    /// Record of any other type; its value is stored as given
+    pub fn record(mut self, record_type: &str, value: impl Into<String>) -> Self {
+        self.records.push(record_type, value.into());
+        self
+    }
+
+    /// Check every record, reporting all problems with their `records.TYPE[index]` paths
+    pub fn build(self) -> std::result::Result<RecordSet, ConfigErrors> {
+        let mut v = Validator::new();
+        v.nested("records", self.records.validate());
+        for (record_type, values) in &self.records.records {
+            for (i, value) in values.iter().enumerate() {
+                v.check(
+                    !value.contains(RECORD_VALUE_SEPARATOR),
+                    &format!("records.{}[{}]", record_type, i),
+                    "must not contain a line break",
+                );
+            }
+        }
+        v.check(self.records.values("CNAME").len() <= 1, "records.CNAME", "at most one CNAME is allowed");
+        v.finish()?;
+        Ok(self.records)
+    }
+}
+
 /// Domain registration request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DomainRegistration {
@@ -143,6 +656,68 @@ pub struct DomainRegistration {
     pub payment_amount: u64,
 }
 
+/// What registering a domain would cost, as quoted by CNS
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistrationQuote {
+    pub domain: String,
+    pub available: bool,
+    /// Why the domain cannot be registered, if it cannot
+    pub reason: Option<String>,
+    pub cost: u64,
+    pub token: crate::TokenType,
+}
+
+/// Availability checks and registrations a bulk request runs at once
+const BULK_REGISTRATION_CONCURRENCY: usize = 8;
+
+/// Outcome for one domain of [`CNSClient::register_domains`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    /// Free to register; only reported by dry runs
+    Available,
+    /// Already registered by someone
+    Unavailable,
+    Registered { tx_hash: String },
+    /// Invalid, duplicated, or rejected during registration
+    Failed { error: String },
+}
+
+impl BulkItemStatus {
+    /// Whether the item counts towards the batch's total cost
+    pub fn is_payable(&self) -> bool {
+        matches!(self, BulkItemStatus::Available | BulkItemStatus::Registered { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkRegistrationItem {
+    pub domain: String,
+    pub token: crate::TokenType,
+    pub cost: u64,
+    pub status: BulkItemStatus,
+}
+
+/// Per-domain results of a bulk registration, in request order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkRegistrationReport {
+    pub dry_run: bool,
+    pub items: Vec<BulkRegistrationItem>,
+    /// Cost of the available (dry run) or registered domains, by payment token
+    pub total_cost: HashMap<crate::TokenType, u64>,
+}
+
+impl BulkRegistrationReport {
+    pub fn item(&self, domain: &str) -> Option<&BulkRegistrationItem> {
+        self.items.iter().find(|item| item.domain == domain)
+    }
+
+    /// Items that could not be priced or registered
+    pub fn failures(&self) -> impl Iterator<Item = &BulkRegistrationItem> {
+        self.items.iter().filter(|item| matches!(item.status, BulkItemStatus::Failed { .. }))
+    }
+}
+
 /// Domain subscription for real-time updates
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DomainSubscription {
@@ -159,9 +734,12 @@ pub struct DomainChangeEvent {
     pub timestamp: u64,
     pub old_value: Option<String>,
     pub new_value: Option<String>,
+    /// Transaction that made the change, if it was made on chain
+    #[serde(default)]
+    pub tx_hash: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChangeEventType {
     Registered,
     Updated,
@@ -174,9 +752,62 @@ impl CNSClient {
     /// Create a new CNS client
     pub fn new(config: CNSConfig) -> Self {
         let cache = DomainCache::new(config.max_cache_entries);
+        let reverse_cache = DomainCache::new(config.max_cache_entries);
+        let http_settings = crate::EtherlinkConfig {
+            timeout_ms: config.request_timeout_ms,
+            proxy: config.proxy.clone(),
+            ..crate::EtherlinkConfig::default()
+        };
+        // CNSConfig::validate rejects proxies that fail here
+        let http_client = crate::clients::build_http_client(&http_settings).unwrap_or_else(|e| {
+            warn!("Ignoring CNS proxy settings: {}", e);
+            HttpClient::new()
+        });
         Self {
+            cache_ttl: std::sync::Arc::new(AtomicU64::new(config.cache_ttl_seconds)),
             config,
             cache: std::sync::Arc::new(RwLock::new(cache)),
+            reverse_cache: Arc::new(RwLock::new(reverse_cache)),
+            inflight: SingleFlight::new(),
+            channels: ChannelManager::with_defaults(),
+            http_client: Arc::new(http_client),
+            clock: clock::system(),
+            events: None,
+        }
+    }
+
+    /// Reach HTTP bridges with `http_client`, sharing its connection pool and proxy settings
+    pub fn with_http_client(mut self, http_client: Arc<HttpClient>) -> Self {
+        self.http_client = http_client;
+        self
+    }
+
+    /// Reach the CNS service over `channels`, sharing their connections and health tracking
+    pub fn with_channels(mut self, channels: ChannelManager) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Expire cached resolutions by `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Publish [`EtherlinkEvent::DomainChanged`] for changes made through this client
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn publish_change(&self, domain: &str, change: ChangeEventType, tx_hash: &str) {
+        if let Some(events) = &self.events {
+            events.publish(EtherlinkEvent::DomainChanged {
+                domain: domain.to_string(),
+                change,
+                tx_hash: tx_hash.to_string(),
+                correlation_id: crate::correlation::current(),
+            });
         }
     }
 
@@ -203,29 +834,42 @@ impl CNSClient {
     /// Resolve a domain name
     pub async fn resolve_domain(&self, domain: &str) -> Result<DomainResolution> {
         debug!("Resolving domain: {}", domain);
+        let domain = &self.normalize_domain(domain)?;
 
         // Check cache first
         if self.config.enable_cache {
-            let cache = self.cache.read().await;
-            if let Some(cached) = cache.get(domain) {
+            let mut cache = self.cache.write().await;
+            if let Some(cached) = cache.get(domain, self.clock.now()) {
                 debug!("Domain {} resolved from cache", domain);
                 return Ok(cached);
             }
         }
 
-        // Route to appropriate resolver based on TLD
-        let resolution = self.resolve_domain_by_tld(domain).await?;
+        // Route to appropriate resolver based on TLD, sharing identical in-flight lookups
+        let resolution = self.inflight
+            .run(domain.to_string(), || self.resolve_domain_by_tld(domain))
+            .await?;
 
-        // Cache the result
-        if self.config.enable_cache {
+        // Cache the result, never past the registration's expiry
+        let ttl = self.ttl_for(&resolution);
+        if self.config.enable_cache && ttl > 0 {
             let mut cache = self.cache.write().await;
-            cache.insert(domain.to_string(), resolution.clone(), self.config.cache_ttl_seconds);
+            cache.insert(domain.to_string(), resolution.clone(), self.clock.now() + ttl);
         }
 
         debug!("Domain {} resolved successfully", domain);
         Ok(resolution)
     }
 
+    /// Resolve `domain` from the service, bypassing and leaving alone the resolution cache
+    ///
+    /// For read-modify-write record updates, where a cached record set would put back
+    /// records that have since changed.
+    pub async fn resolve_domain_uncached(&self, domain: &str) -> Result<DomainResolution> {
+        let domain = &self.normalize_domain(domain)?;
+        self.resolve_domain_by_tld(domain).await
+    }
+
     /// Resolve domain based on TLD
     async fn resolve_domain_by_tld(&self, domain: &str) -> Result<DomainResolution> {
         let tld = domain.split('.').last()
@@ -247,29 +891,174 @@ impl CNSClient {
         }
     }
 
-    /// Resolve native GhostChain domain
+    /// Resolve native GhostChain domain through the CNS service's `ResolveDomain`
+    ///
+    /// The call carries `request_timeout_ms` as its deadline. An unregistered domain
+    /// becomes [`EtherlinkError::NotFound`] and other answers about the domain itself
+    /// (invalid, not resolvable) [`EtherlinkError::CnsResolution`]; an unreachable or
+    /// slow service is reported as such, so it is never mistaken for a free name. An
+    /// answer about any other domain than the one asked for is rejected.
     async fn resolve_native_domain(&self, domain: &str) -> Result<DomainResolution> {
         debug!("Resolving native domain: {}", domain);
 
-        // TODO: Query actual CNS service via gRPC
-        // For now, return a placeholder resolution
-
-        Ok(DomainResolution {
+        let endpoint = &self.config.endpoint;
+        let channel = self.channels.get_channel(endpoint).await?;
+        let mut request = Request::new(cns_pb::CnsResolveRequest {
             domain: domain.to_string(),
-            owner: Address::new("0x1234567890123456789012345678901234567890".to_string()),
-            records: {
-                let mut records = BTreeMap::new();
-                records.insert("A".to_string(), "127.0.0.1".to_string());
-                records.insert("AAAA".to_string(), "::1".to_string());
-                records
-            },
-            metadata: HashMap::new(),
-            expires_at: (chrono::Utc::now().timestamp() + 365 * 24 * 3600) as u64,
-            service_type: ServiceType::Blockchain,
-            blockchain_address: Some(Address::new("0x1234567890123456789012345678901234567890".to_string())),
-            ipfs_hash: None,
-            web5_did: None,
-        })
+            record_types: Vec::new(),
+            include_metadata: true,
+            use_cache: self.config.enable_cache,
+            max_ttl: u32::try_from(self.cache_ttl_seconds()).unwrap_or(u32::MAX),
+            resolver_config: Some(cns_pb::CnsResolverConfig {
+                upstream_resolvers: Vec::new(),
+                enable_dnssec: false,
+                enable_ens_bridge: self.config.enable_ens_bridge,
+                enable_unstoppable_bridge: self.config.enable_unstoppable_bridge,
+                timeout_ms: u32::try_from(self.config.request_timeout_ms).unwrap_or(u32::MAX),
+            }),
+        });
+        request.set_timeout(Duration::from_millis(self.config.request_timeout_ms));
+        crate::correlation::tag_grpc_request(&mut request, None);
+
+        match CnsServiceClient::new(channel).resolve_domain(request).await {
+            Ok(response) => {
+                self.channels.report_success(endpoint).await;
+                let resolution = DomainResolution::try_from(response.into_inner())?;
+                if !resolution.domain.eq_ignore_ascii_case(domain) {
+                    return Err(EtherlinkError::CnsResolution(format!(
+                        "CNS answered for {} when asked to resolve {}",
+                        resolution.domain, domain
+                    )));
+                }
+                Ok(resolution)
+            }
+            Err(status) => {
+                if matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled) {
+                    self.channels.report_failure(endpoint).await;
+                }
+                Err(self.resolution_error(domain, status))
+            }
+        }
+    }
+
+    /// Map a failed `ResolveDomain` call to the error callers act on
+    fn resolution_error(&self, domain: &str, status: Status) -> EtherlinkError {
+        match status.code() {
+            Code::NotFound => EtherlinkError::NotFound(format!("domain {}", domain)),
+            Code::InvalidArgument | Code::OutOfRange => {
+                EtherlinkError::CnsResolution(format!("Invalid domain {}: {}", domain, status.message()))
+            }
+            Code::FailedPrecondition => {
+                EtherlinkError::CnsResolution(format!("Domain {} cannot be resolved: {}", domain, status.message()))
+            }
+            // tonic reports our own deadline expiring as CANCELLED
+            Code::DeadlineExceeded | Code::Cancelled => EtherlinkError::Timeout(format!(
+                "CNS did not resolve {} within {} ms",
+                domain, self.config.request_timeout_ms
+            )),
+            Code::Unavailable => {
+                EtherlinkError::ServiceUnavailable(format!("CNS at {}: {}", self.config.endpoint, status.message()))
+            }
+            Code::Unauthenticated | Code::PermissionDenied => EtherlinkError::Authentication(status.message().to_string()),
+            _ => EtherlinkError::Status(status),
+        }
+    }
+
+    /// Domains whose records point to `address`, sorted
+    ///
+    /// Answers are cached for the cache TTL in a cache of their own. Any change to a
+    /// domain may point it at or away from any address, so a change made through
+    /// this client or received by a subscription drops every cached answer. An
+    /// address no domain points to has an empty list.
+    pub async fn reverse_resolve(&self, address: &Address) -> Result<Vec<String>> {
+        debug!("Reverse resolving address: {}", address);
+        let key = address.as_str().to_string();
+        if self.config.enable_cache {
+            if let Some(domains) = self.reverse_cache.write().await.get(&key, self.clock.now()) {
+                debug!("Address {} reverse resolved from cache", address);
+                return Ok(domains);
+            }
+        }
+
+        let domains = self.reverse_resolve_native(address).await?;
+        if self.config.enable_cache {
+            let expires_at = self.clock.now() + self.cache_ttl_seconds();
+            self.reverse_cache.write().await.insert(key, domains.clone(), expires_at);
+        }
+        Ok(domains)
+    }
+
+    async fn reverse_resolve_native(&self, address: &Address) -> Result<Vec<String>> {
+        let endpoint = &self.config.endpoint;
+        let channel = self.channels.get_channel(endpoint).await?;
+        let mut request = Request::new(cns_pb::CnsReverseResolveRequest { address: address.as_str().to_string() });
+        request.set_timeout(Duration::from_millis(self.config.request_timeout_ms));
+        crate::correlation::tag_grpc_request(&mut request, None);
+
+        match CnsServiceClient::new(channel).reverse_resolve(request).await {
+            Ok(response) => {
+                self.channels.report_success(endpoint).await;
+                let mut domains = response.into_inner().domains;
+                domains.sort();
+                domains.dedup();
+                Ok(domains)
+            }
+            Err(status) => {
+                if matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled) {
+                    self.channels.report_failure(endpoint).await;
+                }
+                match status.code() {
+                    Code::NotFound => Ok(Vec::new()),
+                    Code::InvalidArgument => Err(EtherlinkError::CnsResolution(format!(
+                        "Invalid address {}: {}",
+                        address,
+                        status.message()
+                    ))),
+                    _ => Err(self.resolution_error(address.as_str(), status)),
+                }
+            }
+        }
+    }
+
+    /// Ask CNS whether `domain` can be registered and at what price
+    ///
+    /// Uses the service's `CheckAvailability`, so the price is the registry's own.
+    pub async fn quote_registration(&self, domain: &str) -> Result<RegistrationQuote> {
+        let domain = self.validate_domain_format(domain)?;
+        let endpoint = &self.config.endpoint;
+        let channel = self.channels.get_channel(endpoint).await?;
+        let mut request = Request::new(cns_pb::CnsAvailabilityRequest { domain: domain.clone() });
+        request.set_timeout(Duration::from_millis(self.config.request_timeout_ms));
+        crate::correlation::tag_grpc_request(&mut request, None);
+
+        match CnsServiceClient::new(channel).check_availability(request).await {
+            Ok(response) => {
+                self.channels.report_success(endpoint).await;
+                let response = response.into_inner();
+                let token = crate::TokenType::from_symbol(&response.cost_token).ok_or_else(|| {
+                    EtherlinkError::Codec(format!("CNS quoted {} in unknown token {:?}", domain, response.cost_token))
+                })?;
+                Ok(RegistrationQuote {
+                    domain,
+                    available: response.available,
+                    reason: Some(response.reason).filter(|reason| !reason.is_empty()),
+                    cost: response.estimated_cost,
+                    token,
+                })
+            }
+            Err(status) => {
+                if matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled) {
+                    self.channels.report_failure(endpoint).await;
+                }
+                Err(self.resolution_error(&domain, status))
+            }
+        }
+    }
+
+    /// Drop cached state `domain` may have changed: its resolution, and every reverse lookup
+    async fn forget(&self, domain: &str) {
+        self.cache.write().await.remove(domain);
+        self.reverse_cache.write().await.clear();
     }
 
     /// Resolve ENS domain (.eth)
@@ -280,12 +1069,47 @@ impl CNSClient {
         Err(EtherlinkError::CnsResolution("ENS bridge not implemented".to_string()))
     }
 
-    /// Resolve Unstoppable Domains (.crypto, .nft, etc.)
+    /// Resolve Unstoppable Domains (.crypto, .nft, .x) through the Resolution API
+    ///
+    /// `crypto.<TICKER>.address` records become `ADDR:<TICKER>` records and the IPFS
+    /// hash the `CONTENTHASH`; every raw record is kept in the metadata. As with native
+    /// domains, only an unregistered domain is reported as [`EtherlinkError::NotFound`].
     async fn resolve_unstoppable_domain(&self, domain: &str) -> Result<DomainResolution> {
         debug!("Resolving Unstoppable domain: {}", domain);
 
-        // TODO: Bridge to Unstoppable Domains resolver
-        Err(EtherlinkError::CnsResolution("Unstoppable bridge not implemented".to_string()))
+        // A third-party service: only its own key is sent, never a correlation ID
+        let url = format!("{}/domains/{}", self.config.unstoppable_endpoint.trim_end_matches('/'), domain);
+        let mut request = self.http_client.get(&url).timeout(Duration::from_millis(self.config.request_timeout_ms));
+        if let Some(key) = &self.config.unstoppable_api_key {
+            request = request.bearer_auth(key.expose_secret());
+        }
+        let response = request
+            .send_logged()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    EtherlinkError::Timeout(format!("Unstoppable Domains did not answer for {}", domain))
+                } else {
+                    EtherlinkError::Network(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(match status.as_u16() {
+                404 => EtherlinkError::NotFound(format!("domain {}", domain)),
+                400 | 422 => EtherlinkError::CnsResolution(format!("Invalid domain {}: {}", domain, body)),
+                401 | 403 => EtherlinkError::Authentication(format!("Unstoppable Domains rejected the API key: {}", body)),
+                429 | 500..=599 => EtherlinkError::ServiceUnavailable(format!("Unstoppable Domains answered {}: {}", status, body)),
+                _ => EtherlinkError::Api(format!("Unstoppable Domains answered {}: {}", status, body)),
+            });
+        }
+        let resolution: UdResolution = response
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Codec(format!("Invalid Unstoppable Domains response for {}: {}", domain, e)))?;
+        resolution.into_domain_resolution(domain)
     }
 
     /// Register a new domain
@@ -293,29 +1117,95 @@ impl CNSClient {
         info!("Registering domain: {}", registration.domain);
 
         // Validate domain format
-        self.validate_domain_format(&registration.domain)?;
+        let domain = self.validate_domain_format(&registration.domain)?;
+        let registration = DomainRegistration { domain, ..registration };
 
         // Check if domain is available
-        if self.is_domain_available(&registration.domain).await? {
+        if !self.is_domain_available(&registration.domain).await? {
             return Err(EtherlinkError::CnsResolution(
                 format!("Domain {} is not available", registration.domain)
             ));
         }
 
+        self.submit_registration(&registration).await
+    }
+
+    async fn submit_registration(&self, registration: &DomainRegistration) -> Result<String> {
         // TODO: Submit registration via gRPC
         let tx_hash = "0xabcdef1234567890".to_string();
 
+        self.forget(&registration.domain).await;
+
+        self.publish_change(&registration.domain, ChangeEventType::Registered, &tx_hash);
         info!("Domain {} registered with tx hash: {}", registration.domain, tx_hash);
         Ok(tx_hash)
     }
 
+    /// Register many domains at once, or with `dry_run` only price them
+    ///
+    /// Every registration gets its own [`BulkItemStatus`]: invalid names, duplicates
+    /// within the batch and taken domains are reported per item without stopping the
+    /// rest. A dry run checks format, then asks CNS for each domain's availability and
+    /// price (see [`CNSClient::quote_registration`]) and totals what the available
+    /// domains would cost by token; otherwise the available domains are registered
+    /// with their given payment and the total covers those that succeeded.
+    pub async fn register_domains(&self, registrations: Vec<DomainRegistration>, dry_run: bool) -> BulkRegistrationReport {
+        info!("{} {} domains", if dry_run { "Pricing" } else { "Registering" }, registrations.len());
+
+        let mut seen = HashSet::new();
+        let checks = registrations.into_iter().map(|registration| {
+            // Spellings that normalize to the same name are duplicates too
+            let normalized = self.validate_domain_format(&registration.domain);
+            let duplicate = normalized.as_ref().is_ok_and(|domain| !seen.insert(domain.clone()));
+            async move {
+                let mut item = BulkRegistrationItem {
+                    domain: registration.domain.clone(),
+                    token: registration.payment_token.clone(),
+                    cost: registration.payment_amount,
+                    status: BulkItemStatus::Unavailable,
+                };
+                item.status = match normalized {
+                    _ if duplicate => BulkItemStatus::Failed { error: "Listed more than once in this batch".to_string() },
+                    Err(e) => BulkItemStatus::Failed { error: e.to_string() },
+                    Ok(domain) if dry_run => match self.quote_registration(&domain).await {
+                        Ok(quote) if quote.available => {
+                            (item.token, item.cost) = (quote.token, quote.cost);
+                            BulkItemStatus::Available
+                        }
+                        Ok(_) => BulkItemStatus::Unavailable,
+                        Err(e) => BulkItemStatus::Failed { error: e.to_string() },
+                    },
+                    Ok(domain) => match self.is_domain_available(&domain).await {
+                        Ok(true) => match self.submit_registration(&DomainRegistration { domain, ..registration }).await {
+                            Ok(tx_hash) => BulkItemStatus::Registered { tx_hash },
+                            Err(e) => BulkItemStatus::Failed { error: e.to_string() },
+                        },
+                        Ok(false) => BulkItemStatus::Unavailable,
+                        Err(e) => BulkItemStatus::Failed { error: e.to_string() },
+                    },
+                };
+                item
+            }
+        });
+        let items = stream::iter(checks)
+            .buffered(BULK_REGISTRATION_CONCURRENCY)
+            .collect::<Vec<BulkRegistrationItem>>()
+            .await;
+
+        let mut total_cost = HashMap::new();
+        for item in items.iter().filter(|item| item.status.is_payable()) {
+            *total_cost.entry(item.token.clone()).or_insert(0u64) += item.cost;
+        }
+        BulkRegistrationReport { dry_run, items, total_cost }
+    }
+
     /// Check if a domain is available for registration
     pub async fn is_domain_available(&self, domain: &str) -> Result<bool> {
         debug!("Checking availability for domain: {}", domain);
 
         match self.resolve_domain(domain).await {
             Ok(_) => Ok(false), // Domain exists, not available
-            Err(EtherlinkError::CnsResolution(_)) => Ok(true), // Domain not found, available
+            Err(EtherlinkError::NotFound(_)) => Ok(true), // Domain not found, available
             Err(e) => Err(e), // Other error
         }
     }
@@ -338,26 +1228,79 @@ impl CNSClient {
         // TODO: Submit update via gRPC
         let tx_hash = "0xfedcba0987654321".to_string();
 
-        // Invalidate cache
-        if self.config.enable_cache {
-            let mut cache = self.cache.write().await;
-            cache.entries.remove(domain);
-        }
+        self.forget(domain).await;
 
+        self.publish_change(domain, ChangeEventType::Updated, &tx_hash);
         info!("Domain {} records updated with tx hash: {}", domain, tx_hash);
         Ok(tx_hash)
     }
 
-    /// Subscribe to domain changes
+    /// Subscribe to changes of `subscription.domains`, or of every domain when empty
+    ///
+    /// The CNS service filters by domain and by `record_types` itself. Each change
+    /// evicts the domain from the resolution cache before it is yielded, so the next
+    /// lookup sees the new state. A dropped stream is reopened with
+    /// `subscription_backoff`; the subscription yields one error and ends when the
+    /// service refuses it or the attempts run out. Changes made while it was
+    /// disconnected are not replayed.
+    ///
+    /// A background task reads the stream into a queue configured by
+    /// `subscription_queue`, so a slow subscriber is handled by its overflow policy
+    /// instead of holding up the connection; the queue's metrics show how far it lags.
+    /// Dropping the receiver ends the subscription.
     pub async fn subscribe_domain_changes(
         &self,
         subscription: DomainSubscription,
-    ) -> crate::Result<impl StreamExt<Item = std::result::Result<DomainChangeEvent, Status>>> {
+    ) -> Result<QueueReceiver<Result<DomainChangeEvent>>> {
         info!("Subscribing to changes for {} domains", subscription.domains.len());
 
-        // TODO: Implement actual gRPC streaming subscription
-        // For now, return an empty stream
-        Ok(tokio_stream::empty())
+        let request = cns_pb::CnsDomainSubscription {
+            domains: subscription
+                .domains
+                .iter()
+                .map(|domain| self.normalize_domain(domain))
+                .collect::<Result<_>>()?,
+            record_types: subscription.record_types.iter().map(|record_type| record_type.to_ascii_uppercase()).collect(),
+            include_metadata: subscription.include_metadata,
+        };
+        let stream = self.open_subscription(&request).await?;
+        let mut feed = DomainChangeFeed { client: self.clone(), request, stream: Some(stream), failures: 0 };
+
+        let (sender, receiver) = queue::bounded(self.config.subscription_queue.clone());
+        tokio::spawn(async move {
+            loop {
+                let next = tokio::select! {
+                    next = feed.next() => next,
+                    _ = sender.closed() => break,
+                };
+                let last = next.is_err();
+                if sender.send(next).await.is_err() || last {
+                    break;
+                }
+            }
+            debug!("Domain change subscription ended");
+        });
+        Ok(receiver)
+    }
+
+    async fn open_subscription(&self, request: &cns_pb::CnsDomainSubscription) -> Result<Streaming<cns_pb::CnsDomainChangeEvent>> {
+        let endpoint = &self.config.endpoint;
+        let channel = self.channels.get_channel(endpoint).await?;
+        let mut request = Request::new(request.clone());
+        crate::correlation::tag_grpc_request(&mut request, None);
+
+        match CnsServiceClient::new(channel).subscribe_domain_changes(request).await {
+            Ok(response) => {
+                self.channels.report_success(endpoint).await;
+                Ok(response.into_inner())
+            }
+            Err(status) => {
+                if is_transient(status.code()) {
+                    self.channels.report_failure(endpoint).await;
+                }
+                Err(status.into())
+            }
+        }
     }
 
     /// Transfer domain ownership
@@ -378,12 +1321,9 @@ impl CNSClient {
         // TODO: Submit transfer via gRPC
         let tx_hash = "0x1122334455667788".to_string();
 
-        // Invalidate cache
-        if self.config.enable_cache {
-            let mut cache = self.cache.write().await;
-            cache.entries.remove(domain);
-        }
+        self.forget(domain).await;
 
+        self.publish_change(domain, ChangeEventType::Transferred, &tx_hash);
         info!("Domain {} transferred with tx hash: {}", domain, tx_hash);
         Ok(tx_hash)
     }
@@ -407,15 +1347,26 @@ impl CNSClient {
         // TODO: Submit renewal via gRPC
         let tx_hash = "0x9988776655443322".to_string();
 
+        self.forget(domain).await;
+
+        self.publish_change(domain, ChangeEventType::Renewed, &tx_hash);
         info!("Domain {} renewed with tx hash: {}", domain, tx_hash);
         Ok(tx_hash)
     }
 
-    /// Validate domain format
-    fn validate_domain_format(&self, domain: &str) -> Result<()> {
+    /// UTS-46 normalize a domain to its ASCII form and apply the homograph policy
+    fn normalize_domain(&self, domain: &str) -> Result<String> {
+        let normalized = idn::to_ascii(domain)?;
+        self.config.homograph_policy.enforce(&normalized)?;
+        Ok(normalized)
+    }
+
+    /// Validate domain format, returning its normalized ASCII form
+    fn validate_domain_format(&self, domain: &str) -> Result<String> {
         if domain.is_empty() {
             return Err(EtherlinkError::CnsResolution("Domain cannot be empty".to_string()));
         }
+        let domain = self.normalize_domain(domain)?;
 
         if !domain.contains('.') {
             return Err(EtherlinkError::CnsResolution("Domain must contain a TLD".to_string()));
@@ -433,27 +1384,69 @@ impl CNSClient {
             return Err(EtherlinkError::CnsResolution(format!("Unsupported TLD: {}", tld)));
         }
 
-        Ok(())
+        Ok(domain)
     }
 
     /// Clear expired cache entries
     pub async fn cleanup_cache(&self) {
         if self.config.enable_cache {
-            let mut cache = self.cache.write().await;
-            cache.clear_expired();
+            let now = self.clock.now();
+            self.cache.write().await.clear_expired(now);
+            self.reverse_cache.write().await.clear_expired(now);
+        }
+    }
+
+    /// Clear expired cache entries every `cache_cleanup_interval_seconds` until `token` is cancelled
+    pub async fn run_cache_cleanup(&self, token: CancellationToken) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.cache_cleanup_interval_seconds));
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = interval.tick() => self.cleanup_cache().await,
+            }
         }
     }
 
-    /// Get cache statistics
-    pub async fn cache_stats(&self) -> (usize, usize) {
+    /// Run [`CNSClient::run_cache_cleanup`] in the background until the guard is dropped
+    ///
+    /// For clients used outside [`crate::Etherlink`], which runs the cleanup itself.
+    pub fn spawn_cache_cleanup(&self) -> DropGuard {
+        let token = CancellationToken::new();
+        let client = self.clone();
+        let cancelled = token.clone();
+        tokio::spawn(async move { client.run_cache_cleanup(cancelled).await });
+        token.drop_guard()
+    }
+
+    /// Drop a cached resolution so the next lookup queries CNS again
+    pub async fn invalidate(&self, domain: &str) -> bool {
+        let domain = idn::to_ascii(domain).unwrap_or_else(|_| domain.to_string());
+        self.cache.write().await.remove(&domain)
+    }
+
+    /// Size, hit/miss and eviction counts of the resolution cache; all zero while disabled
+    pub async fn cache_stats(&self) -> CacheStats {
         if self.config.enable_cache {
-            let cache = self.cache.read().await;
-            (cache.entries.len(), cache.max_entries)
+            self.cache.read().await.stats()
         } else {
-            (0, 0)
+            CacheStats::default()
         }
     }
 
+    /// Statistics of the reverse-lookup cache used by [`CNSClient::reverse_resolve`]
+    pub async fn reverse_cache_stats(&self) -> CacheStats {
+        if self.config.enable_cache {
+            self.reverse_cache.read().await.stats()
+        } else {
+            CacheStats::default()
+        }
+    }
+
+    /// Get request coalescing statistics for domain resolution
+    pub fn coalesce_stats(&self) -> CoalesceSnapshot {
+        self.inflight.stats()
+    }
+
     /// Get configuration
     pub fn config(&self) -> &CNSConfig {
         &self.config
@@ -461,8 +1454,34 @@ impl CNSClient {
 
     /// Update configuration
     pub fn update_config(&mut self, config: CNSConfig) {
+        self.cache_ttl.store(config.cache_ttl_seconds, Ordering::Relaxed);
         self.config = config;
     }
+
+    /// TTL applied to newly cached resolutions
+    pub fn cache_ttl_seconds(&self) -> u64 {
+        self.cache_ttl.load(Ordering::Relaxed)
+    }
+
+    /// Seconds `resolution` may be cached downstream: the cache TTL, cut short by expiry
+    pub fn ttl_for(&self, resolution: &DomainResolution) -> u64 {
+        self.cache_ttl_seconds().min(resolution.expires_at.saturating_sub(self.clock.now()))
+    }
+
+    /// Whether names under `tld` are resolved natively or through an enabled bridge
+    pub fn serves_tld(&self, tld: &str) -> bool {
+        let tld = tld.to_ascii_lowercase();
+        self.config.supported_tlds.contains(&tld)
+            || (tld == "eth" && self.config.enable_ens_bridge)
+            || (["crypto", "nft", "x"].contains(&tld.as_str()) && self.config.enable_unstoppable_bridge)
+    }
+
+    /// Change the cache TTL for this client and all its clones
+    ///
+    /// Entries already cached keep the expiry they were stored with.
+    pub fn set_cache_ttl_seconds(&self, ttl: u64) {
+        self.cache_ttl.store(ttl, Ordering::Relaxed);
+    }
 }
 
 impl Default for CNSClient {
@@ -471,15 +1490,189 @@ impl Default for CNSClient {
     }
 }
 
+/// Whether a subscription failing with `code` is worth reopening
+fn is_transient(code: Code) -> bool {
+    matches!(
+        code,
+        Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled | Code::Aborted | Code::Internal | Code::Unknown | Code::ResourceExhausted
+    )
+}
+
+/// State of a domain change subscription between events
+struct DomainChangeFeed {
+    client: CNSClient,
+    request: cns_pb::CnsDomainSubscription,
+    /// `None` while disconnected
+    stream: Option<Streaming<cns_pb::CnsDomainChangeEvent>>,
+    /// Reconnection attempts failed in a row
+    failures: u32,
+}
+
+impl DomainChangeFeed {
+    /// Next change, reconnecting as needed; an error is final
+    async fn next(&mut self) -> Result<DomainChangeEvent> {
+        let backoff = self.client.config.subscription_backoff.clone();
+        loop {
+            let Some(stream) = &mut self.stream else {
+                tokio::time::sleep(backoff.delay(self.failures)).await;
+                match self.client.open_subscription(&self.request).await {
+                    Ok(stream) => {
+                        info!("Resubscribed to domain changes after {} failed attempts", self.failures);
+                        self.stream = Some(stream);
+                    }
+                    Err(EtherlinkError::Status(status)) if is_transient(status.code()) => {
+                        self.count_failure(&backoff, status.message())?;
+                        warn!("Resubscribing to domain changes failed (attempt {}): {}", self.failures, status.message());
+                    }
+                    Err(e) => return Err(e),
+                }
+                continue;
+            };
+
+            match stream.message().await {
+                Ok(Some(message)) => {
+                    self.failures = 0;
+                    let event = match DomainChangeEvent::try_from(message) {
+                        Ok(event) => event,
+                        Err(e) => {
+                            warn!("Skipping undecodable domain change: {}", e);
+                            continue;
+                        }
+                    };
+                    self.client.forget(&event.domain).await;
+                    // The service should filter by domain; don't rely on it
+                    if self.request.domains.is_empty() || self.request.domains.contains(&event.domain) {
+                        return Ok(event);
+                    }
+                }
+                // A stream ending without a change counts like a failed resubscription,
+                // so a service that keeps closing it is backed off from and given up on
+                Ok(None) => {
+                    debug!("Domain change stream ended, resubscribing");
+                    self.stream = None;
+                    self.count_failure(&backoff, "the stream ended without a change")?;
+                }
+                Err(status) if is_transient(status.code()) => {
+                    warn!("Domain change stream dropped, resubscribing: {}", status.message());
+                    self.client.channels.report_failure(&self.client.config.endpoint).await;
+                    self.stream = None;
+                    self.count_failure(&backoff, status.message())?;
+                }
+                Err(status) => return Err(status.into()),
+            }
+        }
+    }
+
+    /// Count a failed attempt at keeping the subscription open, giving up past `max_attempts`
+    fn count_failure(&mut self, backoff: &SubscriptionBackoff, reason: &str) -> Result<()> {
+        self.failures += 1;
+        if backoff.max_attempts.is_some_and(|max| self.failures >= max) {
+            return Err(EtherlinkError::ServiceUnavailable(format!(
+                "Gave up resubscribing to domain changes after {} attempts: {}",
+                self.failures, reason
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Answer of the Unstoppable Domains Resolution API for one domain
+#[derive(Debug, Deserialize)]
+struct UdResolution {
+    meta: UdMeta,
+    #[serde(default)]
+    records: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UdMeta {
+    /// `None` for a domain nobody has minted
+    owner: Option<String>,
+    #[serde(default)]
+    blockchain: Option<String>,
+    #[serde(default)]
+    network_id: Option<u64>,
+    #[serde(default)]
+    resolver: Option<String>,
+    #[serde(default)]
+    registry: Option<String>,
+    #[serde(default)]
+    token_id: Option<String>,
+}
+
+impl UdResolution {
+    fn into_domain_resolution(self, domain: &str) -> Result<DomainResolution> {
+        let owner = match self.meta.owner.as_deref() {
+            Some(owner) if !owner.is_empty() && owner != ZERO_EVM_ADDRESS => owner.to_string(),
+            _ => return Err(EtherlinkError::CnsResolution(format!("Domain {} not found", domain))),
+        };
+
+        let mut records = RecordSet::new();
+        let mut ipfs_hash = None;
+        for (key, value) in &self.records {
+            if value.is_empty() {
+                continue;
+            }
+            if let Some(chain) = ud_address_chain(key) {
+                records.push(&address_key(&chain), value.clone());
+            } else if key == "dweb.ipfs.hash" || (key == "ipfs.html.value" && ipfs_hash.is_none()) {
+                ipfs_hash = Some(value.clone());
+            }
+        }
+        if let Some(hash) = &ipfs_hash {
+            records.push("CONTENTHASH", format!("ipfs://{}", hash));
+        }
+
+        let mut metadata: HashMap<String, String> = self.records.into_iter().filter(|(_, value)| !value.is_empty()).collect();
+        let meta = [
+            ("ud.blockchain", self.meta.blockchain),
+            ("ud.network_id", self.meta.network_id.map(|id| id.to_string())),
+            ("ud.resolver", self.meta.resolver),
+            ("ud.registry", self.meta.registry),
+            ("ud.token_id", self.meta.token_id),
+        ];
+        metadata.extend(meta.into_iter().filter_map(|(key, value)| Some((key.to_string(), value?))));
+
+        Ok(DomainResolution {
+            domain: domain.to_string(),
+            blockchain_address: records.address_for_chain("eth"),
+            owner: Address::new(owner),
+            records,
+            metadata,
+            // Unstoppable domains are owned outright and never expire
+            expires_at: u64::MAX,
+            service_type: ServiceType::Bridge,
+            ipfs_hash,
+            web5_did: None,
+        })
+    }
+}
+
+/// Owner the Resolution API reports for burned or unminted names
+const ZERO_EVM_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+/// Chain of a `crypto.<TICKER>.address` or `crypto.<TICKER>.version.<VERSION>.address` record
+fn ud_address_chain(key: &str) -> Option<String> {
+    let ticker = key.strip_prefix("crypto.")?.strip_suffix(".address")?;
+    match ticker.split_once(".version.") {
+        Some((ticker, version)) if !ticker.is_empty() && !version.is_empty() => Some(format!("{}.{}", ticker, version)),
+        None if !ticker.is_empty() && !ticker.contains('.') => Some(ticker.to_string()),
+        _ => None,
+    }
+}
+
 /// Builder for CNS client
 pub struct CNSClientBuilder {
     config: CNSConfig,
+    clock: SharedClock,
 }
 
 impl CNSClientBuilder {
     pub fn new() -> Self {
         Self {
             config: CNSConfig::default(),
+            clock: clock::system(),
         }
     }
 
@@ -503,6 +1696,11 @@ impl CNSClientBuilder {
         self
     }
 
+    pub fn cache_cleanup_interval_seconds(mut self, interval: u64) -> Self {
+        self.config.cache_cleanup_interval_seconds = interval;
+        self
+    }
+
     pub fn supported_tlds(mut self, tlds: Vec<String>) -> Self {
         self.config.supported_tlds = tlds;
         self
@@ -518,8 +1716,43 @@ impl CNSClientBuilder {
         self
     }
 
+    pub fn homograph_policy(mut self, policy: HomographPolicy) -> Self {
+        self.config.homograph_policy = policy;
+        self
+    }
+
+    pub fn request_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.config.request_timeout_ms = timeout_ms;
+        self
+    }
+
+    pub fn unstoppable_endpoint<S: Into<String>>(mut self, endpoint: S) -> Self {
+        self.config.unstoppable_endpoint = endpoint.into();
+        self
+    }
+
+    pub fn unstoppable_api_key(mut self, key: impl Into<SecretString>) -> Self {
+        self.config.unstoppable_api_key = Some(key.into());
+        self
+    }
+
+    pub fn subscription_backoff(mut self, backoff: SubscriptionBackoff) -> Self {
+        self.config.subscription_backoff = backoff;
+        self
+    }
+
+    pub fn subscription_queue(mut self, queue: QueueConfig) -> Self {
+        self.config.subscription_queue = queue;
+        self
+    }
+
+    pub fn clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub fn build(self) -> CNSClient {
-        CNSClient::new(self.config)
+        CNSClient::new(self.config).with_clock(self.clock)
     }
 }
 
@@ -0,0 +1,217 @@
+//! Single-flight request coalescing
+//!
+//! Concurrent identical reads (the same domain resolution, the same balance query)
+//! share one in-flight request instead of stampeding a recovering backend.
+
+use crate::{EtherlinkError, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Outcome shared between the leader and followers of a coalesced call
+type Shared<V> = std::result::Result<V, Arc<EtherlinkError>>;
+
+/// Counters for coalesced requests
+#[derive(Debug, Default)]
+pub struct CoalesceStats {
+    executed: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+impl CoalesceStats {
+    /// Get a point-in-time copy of the counters
+    pub fn snapshot(&self) -> CoalesceSnapshot {
+        CoalesceSnapshot {
+            executed: self.executed.load(Ordering::Relaxed),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Point-in-time coalescing counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CoalesceSnapshot {
+    /// Requests that were actually sent to the backend
+    pub executed: u64,
+    /// Requests that were served by joining an identical in-flight request
+    pub coalesced: u64,
+}
+
+/// Deduplicates concurrent calls with the same key
+pub struct SingleFlight<K, V> {
+    inflight: Arc<Mutex<HashMap<K, broadcast::Sender<Shared<V>>>>>,
+    stats: Arc<CoalesceStats>,
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    /// Create a new single-flight group with its own statistics
+    pub fn new() -> Self {
+        Self::with_stats(Arc::new(CoalesceStats::default()))
+    }
+
+    /// Create a new single-flight group reporting into shared statistics
+    pub fn with_stats(stats: Arc<CoalesceStats>) -> Self {
+        Self {
+            inflight: Arc::new(Mutex::new(HashMap::new())),
+            stats,
+        }
+    }
+
+    /// Run `call` for `key`, or wait for the identical call already in flight
+    pub async fn run<F, Fut>(&self, key: K, call: F) -> Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V>>,
+    {
+        let follower = {
+            let mut inflight = self.inflight.lock().unwrap();
+            match inflight.get(&key) {
+                Some(sender) => Some(sender.subscribe()),
+                None => {
+                    let (sender, _) = broadcast::channel(1);
+                    inflight.insert(key.clone(), sender);
+                    None
+                }
+            }
+        };
+
+        if let Some(mut receiver) = follower {
+            // If the leader was cancelled the channel closes and we fall back to our own call
+            if let Ok(shared) = receiver.recv().await {
+                self.stats.coalesced.fetch_add(1, Ordering::Relaxed);
+                return shared.map_err(|e| duplicate_error(&e));
+            }
+            self.stats.executed.fetch_add(1, Ordering::Relaxed);
+            return call().await;
+        }
+
+        let guard = InflightGuard {
+            inflight: &self.inflight,
+            key: Some(key),
+        };
+
+        self.stats.executed.fetch_add(1, Ordering::Relaxed);
+        let result = call().await;
+
+        let (result, shared) = match result {
+            Ok(value) => (Ok(value.clone()), Ok(value)),
+            Err(e) => {
+                let shared = Arc::new(e);
+                (Err(duplicate_error(&shared)), Err(shared))
+            }
+        };
+
+        if let Some(sender) = guard.finish() {
+            let _ = sender.send(shared);
+        }
+
+        result
+    }
+
+    /// Number of distinct keys currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.inflight.lock().unwrap().len()
+    }
+
+    /// Get the statistics for this group
+    pub fn stats(&self) -> CoalesceSnapshot {
+        self.stats.snapshot()
+    }
+}
+
+impl<K, V> Clone for SingleFlight<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inflight: self.inflight.clone(),
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> std::fmt::Debug for SingleFlight<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SingleFlight")
+            .field("stats", &self.stats.snapshot())
+            .finish()
+    }
+}
+
+/// Removes the in-flight entry even if the leader's future is dropped mid-call
+struct InflightGuard<'a, K: Eq + Hash, V> {
+    inflight: &'a Mutex<HashMap<K, broadcast::Sender<Shared<V>>>>,
+    key: Option<K>,
+}
+
+impl<K: Eq + Hash, V> InflightGuard<'_, K, V> {
+    fn finish(mut self) -> Option<broadcast::Sender<Shared<V>>> {
+        let key = self.key.take()?;
+        self.inflight.lock().unwrap().remove(&key)
+    }
+}
+
+impl<K: Eq + Hash, V> Drop for InflightGuard<'_, K, V> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.inflight.lock().unwrap().remove(&key);
+        }
+    }
+}
+
+/// Produce an owned copy of a shared error for a coalesced caller
+///
+/// Variants wrapping foreign error types cannot be cloned, so they are carried over
+/// by message while keeping the closest matching category.
+pub(crate) fn duplicate_error(error: &EtherlinkError) -> EtherlinkError {
+    match error {
+        #[cfg(not(target_arch = "wasm32"))]
+        EtherlinkError::Transport(e) => EtherlinkError::Network(e.to_string()),
+        #[cfg(not(target_arch = "wasm32"))]
+        EtherlinkError::Status(status) => EtherlinkError::Status(status.clone()),
+        #[cfg(feature = "quic-quinn")]
+        EtherlinkError::Quic(e) => EtherlinkError::Quic(e.clone()),
+        EtherlinkError::Serialization(e) => EtherlinkError::Api(format!("Serialization error: {}", e)),
+        EtherlinkError::Ffi(msg) => EtherlinkError::Ffi(msg.clone()),
+        EtherlinkError::CnsResolution(msg) => EtherlinkError::CnsResolution(msg.clone()),
+        EtherlinkError::RvmExecution(msg) => EtherlinkError::RvmExecution(msg.clone()),
+        EtherlinkError::ContractExecution(msg) => EtherlinkError::ContractExecution(msg.clone()),
+        EtherlinkError::Configuration(msg) => EtherlinkError::Configuration(msg.clone()),
+        EtherlinkError::InvalidConfig(errors) => EtherlinkError::InvalidConfig(errors.clone()),
+        EtherlinkError::Network(msg) => EtherlinkError::Network(msg.clone()),
+        EtherlinkError::Authentication(msg) => EtherlinkError::Authentication(msg.clone()),
+        EtherlinkError::General(e) => EtherlinkError::General(anyhow::anyhow!(e.to_string())),
+        EtherlinkError::Crypto(msg) => EtherlinkError::Crypto(msg.clone()),
+        EtherlinkError::Api(msg) => EtherlinkError::Api(msg.clone()),
+        EtherlinkError::Timeout(msg) => EtherlinkError::Timeout(msg.clone()),
+        EtherlinkError::Codec(msg) => EtherlinkError::Codec(msg.clone()),
+        EtherlinkError::Unsupported(msg) => EtherlinkError::Unsupported(msg.clone()),
+        EtherlinkError::TxPool(msg) => EtherlinkError::TxPool(msg.clone()),
+        EtherlinkError::BridgeDown(msg) => EtherlinkError::BridgeDown(msg.clone()),
+        EtherlinkError::ServiceUnavailable(msg) => EtherlinkError::ServiceUnavailable(msg.clone()),
+        EtherlinkError::Index(msg) => EtherlinkError::Index(msg.clone()),
+        EtherlinkError::SessionPolicy(msg) => EtherlinkError::SessionPolicy(msg.clone()),
+        EtherlinkError::Integrity(msg) => EtherlinkError::Integrity(msg.clone()),
+        EtherlinkError::InvalidAddress(e) => EtherlinkError::InvalidAddress(e.clone()),
+        EtherlinkError::StateInvariantViolation(e) => EtherlinkError::StateInvariantViolation(e.clone()),
+        EtherlinkError::SimulatedRevert { reason, gas_used } => EtherlinkError::SimulatedRevert { reason: reason.clone(), gas_used: *gas_used },
+        EtherlinkError::Lifecycle(msg) => EtherlinkError::Lifecycle(msg.clone()),
+        EtherlinkError::Portfolio(msg) => EtherlinkError::Portfolio(msg.clone()),
+        EtherlinkError::PermissionDenied { required } => EtherlinkError::PermissionDenied { required: required.clone() },
+    }
+}
@@ -0,0 +1,124 @@
+//! How final a transaction must be before an operation counts as done
+//!
+//! Applications pick a [`ConfirmationPolicy`] per [`OperationClass`] once, in
+//! [`ConfirmationPolicies`], and the `wait_for_*` helpers look it up instead of
+//! taking confirmation depths per call: a coffee payment may be accepted on
+//! inclusion while an admin change waits for a ZK-verified L2 batch.
+
+use crate::validation::{ConfigErrors, Validator};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Kind of operation a transaction performs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationClass {
+    Payment,
+    /// Domain registration, transfer and record updates
+    Domain,
+    Admin,
+}
+
+/// Finality required for one class of operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfirmationPolicy {
+    /// L1 blocks built on top of the including block; 0 accepts inclusion
+    pub l1_confirmations: u64,
+    /// Wait for an L2 transaction's batch to be finalized on L1
+    pub l2_finalized: bool,
+    /// Additionally require the batch's ZK proof
+    pub zk_verified: bool,
+}
+
+impl Default for ConfirmationPolicy {
+    fn default() -> Self {
+        Self::inclusion()
+    }
+}
+
+impl ConfirmationPolicy {
+    /// Done once the transaction is included
+    pub fn inclusion() -> Self {
+        Self { l1_confirmations: 0, l2_finalized: false, zk_verified: false }
+    }
+
+    pub fn l1_confirmations(mut self, confirmations: u64) -> Self {
+        self.l1_confirmations = confirmations;
+        self
+    }
+
+    pub fn l2_finalized(mut self, finalized: bool) -> Self {
+        self.l2_finalized = finalized;
+        self
+    }
+
+    /// Require a ZK-verified batch, which implies L2 finalization
+    pub fn zk_verified(mut self, verified: bool) -> Self {
+        self.zk_verified = verified;
+        self.l2_finalized |= verified;
+        self
+    }
+
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(!self.zk_verified || self.l2_finalized, "zk_verified", "requires l2_finalized = true");
+        v.finish()
+    }
+}
+
+/// Confirmation policy per operation class, and how long to wait for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfirmationPolicies {
+    pub payments: ConfirmationPolicy,
+    pub domains: ConfirmationPolicy,
+    pub admin: ConfirmationPolicy,
+    /// Delay between status polls
+    pub poll_interval_ms: u64,
+    /// Give up waiting after this long
+    pub timeout_ms: u64,
+}
+
+impl Default for ConfirmationPolicies {
+    fn default() -> Self {
+        Self {
+            payments: ConfirmationPolicy::inclusion().l1_confirmations(1),
+            domains: ConfirmationPolicy::inclusion().l1_confirmations(3),
+            admin: ConfirmationPolicy::inclusion().l1_confirmations(6).zk_verified(true),
+            poll_interval_ms: 1000,
+            timeout_ms: 120_000,
+        }
+    }
+}
+
+impl ConfirmationPolicies {
+    /// Policy for an operation class
+    pub fn policy(&self, class: OperationClass) -> &ConfirmationPolicy {
+        match class {
+            OperationClass::Payment => &self.payments,
+            OperationClass::Domain => &self.domains,
+            OperationClass::Admin => &self.admin,
+        }
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.nested("payments", self.payments.validate());
+        v.nested("domains", self.domains.validate());
+        v.nested("admin", self.admin.validate());
+        v.check(self.poll_interval_ms > 0, "poll_interval_ms", "must be greater than zero");
+        v.timeout("timeout_ms", self.timeout_ms);
+        v.check(self.poll_interval_ms <= self.timeout_ms, "poll_interval_ms", "must not exceed timeout_ms");
+        v.finish()
+    }
+}
@@ -0,0 +1,121 @@
+//! Correlation IDs for tracing one operation across Rust and Zig
+//!
+//! A payment passes through several service clients, gRPC calls and the GhostPlane
+//! FFI bridge. Run it inside [`scope`] and everything it does carries the same
+//! [`CorrelationId`]: log lines are emitted within an `operation` span holding it,
+//! REST calls send it as the [`CORRELATION_HEADER`] header, gRPC calls as metadata
+//! of the same name, FFI calls in their [`crate::ffi::FfiCallContext`], and the
+//! events it causes in their `correlation_id` field. Third-party services, such as
+//! Unstoppable Domains and pinning services, are never sent it.
+//!
+//! ```no_run
+//! # async fn example(clients: &etherlink::ServiceClients) -> etherlink::Result<()> {
+//! use etherlink::correlation::{self, CorrelationId};
+//!
+//! let id = CorrelationId::new();
+//! let height = correlation::scope(id, clients.ghostd.get_blockchain_height()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use tracing::Instrument;
+
+/// Header, and gRPC metadata key, carrying the correlation ID
+pub const CORRELATION_HEADER: &str = "x-correlation-id";
+
+/// Longest ID kept from elsewhere
+const MAX_LEN: usize = 128;
+
+/// Identifies one logical operation across both runtimes
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct CorrelationId(String);
+
+impl CorrelationId {
+    /// A fresh random ID
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// An ID received from elsewhere, e.g. an incoming request's header
+    ///
+    /// Only visible ASCII is kept, up to 128 characters, so the ID is always a valid
+    /// header, gRPC metadata value and C string; an ID with nothing left is replaced
+    /// by a fresh one.
+    pub fn from_string(id: impl Into<String>) -> Self {
+        let id: String = id.into().chars().filter(char::is_ascii_graphic).take(MAX_LEN).collect();
+        if id.is_empty() {
+            Self::new()
+        } else {
+            Self(id)
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for CorrelationId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<String> for CorrelationId {
+    fn from(id: String) -> Self {
+        Self::from_string(id)
+    }
+}
+
+impl From<CorrelationId> for String {
+    fn from(id: CorrelationId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+tokio::task_local! {
+    static CURRENT: CorrelationId;
+}
+
+/// Span the log lines of operation `id` are emitted in
+pub fn span(id: &CorrelationId) -> tracing::Span {
+    tracing::info_span!("operation", correlation_id = %id)
+}
+
+/// Run `future` as operation `id`
+///
+/// Requests, events and log lines produced while it runs carry `id`. Work spawned
+/// onto other tasks does not inherit it; wrap that in its own `scope`.
+pub async fn scope<F: Future>(id: CorrelationId, future: F) -> F::Output {
+    let span = span(&id);
+    CURRENT.scope(id, future.instrument(span)).await
+}
+
+/// ID of the operation the current task is running, if any
+pub fn current() -> Option<CorrelationId> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+/// Send `id`, else the current operation's ID, as metadata of an outgoing gRPC request
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn tag_grpc_request<T>(request: &mut tonic::Request<T>, id: Option<CorrelationId>) {
+    let Some(id) = id.or_else(current) else {
+        return;
+    };
+    match id.as_str().parse() {
+        Ok(value) => {
+            request.metadata_mut().insert(CORRELATION_HEADER, value);
+        }
+        Err(_) => tracing::warn!("Correlation ID {} is not a valid metadata value, not sending it", id),
+    }
+}
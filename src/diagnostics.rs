@@ -0,0 +1,283 @@
+//! Request recording for diagnostic captures
+//!
+//! Every REST call made by the service clients goes through `send_logged`, which
+//! logs it on the [`HTTP_LOG_TARGET`] target and hands it to each active
+//! [`DiagnosticCapture`] watching the request's origin, so one runtime's capture
+//! never sees another client's traffic. A capture keeps the most recent requests in
+//! a bounded ring and costs nothing once dropped. [`crate::Etherlink::capture_diagnostics`] wraps
+//! one in a time-boxed, redacted [`DiagnosticBundle`] for bug reports.
+
+use crate::clock::{Clock, SystemClock};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, Weak};
+
+/// `tracing` target of the per-request events logged by the service clients
+pub const HTTP_LOG_TARGET: &str = "etherlink::http";
+
+/// Requests kept per capture before the oldest are dropped
+pub const DEFAULT_CAPTURE_CAPACITY: usize = 1024;
+
+/// Placeholder substituted for secret values
+pub const REDACTED: &str = "<redacted>";
+
+/// Key names marking a value as secret
+///
+/// Matched against the end of a key, ignoring case, `_` and `-`, so `refresh_token`
+/// and `accessToken` are secret but `token_lifetime_seconds` is not.
+const SECRET_KEYS: &[&str] = &["token", "tokenid", "secret", "password", "signature", "privatekey", "mnemonic", "seed", "authorization", "apikey"];
+
+/// Captures currently recording; dropped captures are pruned on the next request
+static ACTIVE: Mutex<Vec<Weak<Ring>>> = Mutex::new(Vec::new());
+
+/// One REST call as seen by a service client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestRecord {
+    /// Unix milliseconds when the request was sent
+    pub started_at_ms: u64,
+    pub method: String,
+    /// Request URL with credentials and secret query parameters redacted
+    pub url: String,
+    /// HTTP status, if a response arrived
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    /// Transport error, if no response arrived
+    pub error: Option<String>,
+}
+
+impl RequestRecord {
+    /// Whether the request failed outright or got a non-success status
+    pub fn is_error(&self) -> bool {
+        self.error.is_some() || self.status.is_some_and(|status| !(200..300).contains(&status))
+    }
+}
+
+#[derive(Debug)]
+struct Ring {
+    capacity: usize,
+    state: Mutex<RingState>,
+    /// Origins (`scheme://host:port`) whose requests are recorded
+    origins: Mutex<HashSet<String>>,
+}
+
+#[derive(Debug, Default)]
+struct RingState {
+    records: VecDeque<RequestRecord>,
+    dropped: u64,
+}
+
+/// Records every request to the watched endpoints made while it is alive
+#[derive(Debug)]
+pub struct DiagnosticCapture {
+    ring: Arc<Ring>,
+}
+
+impl DiagnosticCapture {
+    /// Start recording, keeping at most `capacity` requests
+    ///
+    /// Nothing is recorded until an endpoint is watched with [`DiagnosticCapture::watch`].
+    pub fn start(capacity: usize) -> Self {
+        let ring = Arc::new(Ring {
+            capacity: capacity.max(1),
+            state: Mutex::new(RingState::default()),
+            origins: Mutex::new(HashSet::new()),
+        });
+        ACTIVE.lock().unwrap().push(Arc::downgrade(&ring));
+        Self { ring }
+    }
+
+    /// Record every request to `endpoint`'s origin
+    ///
+    /// An endpoint that is not a URL is ignored.
+    pub fn watch(&self, endpoint: &str) {
+        if let Some(origin) = origin(endpoint) {
+            self.ring.origins.lock().unwrap().insert(origin);
+        }
+    }
+
+    /// Requests recorded so far, oldest first
+    pub fn records(&self) -> Vec<RequestRecord> {
+        self.ring.state.lock().unwrap().records.iter().cloned().collect()
+    }
+
+    /// Requests that were pushed out of the ring by newer ones
+    pub fn dropped(&self) -> u64 {
+        self.ring.state.lock().unwrap().dropped
+    }
+
+    /// Stop recording and return what was captured, oldest first
+    pub fn finish(self) -> Vec<RequestRecord> {
+        self.ring.state.lock().unwrap().records.drain(..).collect()
+    }
+}
+
+fn origin(url: &str) -> Option<String> {
+    reqwest::Url::parse(url).ok().map(|url| url.origin().ascii_serialization())
+}
+
+/// Hand a finished request to every active capture watching its origin
+pub(crate) fn record(record: RequestRecord) {
+    let Some(origin) = origin(&record.url) else {
+        return;
+    };
+    let rings: Vec<Arc<Ring>> = {
+        let mut active = ACTIVE.lock().unwrap();
+        active.retain(|ring| ring.strong_count() > 0);
+        active
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|ring| ring.origins.lock().unwrap().contains(&origin))
+            .collect()
+    };
+    for ring in rings {
+        let mut state = ring.state.lock().unwrap();
+        if state.records.len() == ring.capacity {
+            state.records.pop_front();
+            state.dropped += 1;
+        }
+        state.records.push_back(record.clone());
+    }
+}
+
+/// Whether any capture is recording, so callers can skip building records
+pub(crate) fn is_capturing() -> bool {
+    ACTIVE.lock().unwrap().iter().any(|ring| ring.strong_count() > 0)
+}
+
+/// Current unix milliseconds, for timing requests on every target
+pub(crate) fn now_millis() -> u64 {
+    SystemClock.now_millis()
+}
+
+/// Summary of captured requests for one service path
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub requests: usize,
+    pub errors: usize,
+    pub max_latency_ms: u64,
+    pub mean_latency_ms: u64,
+}
+
+/// Everything recorded by a time-boxed capture, safe to attach to a bug report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticBundle {
+    pub etherlink_version: String,
+    /// Unix milliseconds when the capture started
+    pub started_at_ms: u64,
+    pub duration_ms: u64,
+    /// Log filter in effect during the capture, if verbosity could be raised
+    pub log_filter: Option<String>,
+    /// Running settings with secrets redacted
+    pub settings: serde_json::Value,
+    /// Service health at the end of the capture
+    pub health: serde_json::Value,
+    pub requests: Vec<RequestRecord>,
+    /// Requests pushed out of the ring by newer ones
+    pub dropped_requests: u64,
+    /// Per `METHOD host/path` summary, query strings excluded
+    pub latency: std::collections::BTreeMap<String, LatencySummary>,
+}
+
+impl DiagnosticBundle {
+    /// Captured requests that failed or got a non-success status
+    pub fn errors(&self) -> impl Iterator<Item = &RequestRecord> {
+        self.requests.iter().filter(|request| request.is_error())
+    }
+
+    /// Pretty-printed JSON for attaching to a bug report
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("diagnostic bundle is always serializable")
+    }
+
+    /// Group requests by method and path, ignoring query strings
+    pub fn summarize(requests: &[RequestRecord]) -> std::collections::BTreeMap<String, LatencySummary> {
+        let mut totals: std::collections::BTreeMap<String, (LatencySummary, u64)> = Default::default();
+        for request in requests {
+            let path = request.url.split('?').next().unwrap_or_default();
+            let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+            let (summary, total) = totals.entry(format!("{} {}", request.method, path)).or_default();
+            summary.requests += 1;
+            summary.errors += usize::from(request.is_error());
+            summary.max_latency_ms = summary.max_latency_ms.max(request.latency_ms);
+            *total += request.latency_ms;
+        }
+        totals
+            .into_iter()
+            .map(|(key, (mut summary, total))| {
+                summary.mean_latency_ms = total / summary.requests as u64;
+                (key, summary)
+            })
+            .collect()
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key: String = key.chars().filter(|c| !matches!(c, '_' | '-')).collect::<String>().to_ascii_lowercase();
+    SECRET_KEYS.iter().any(|secret| key.ends_with(secret))
+}
+
+/// Replace the value of every object key that names a secret, recursively
+///
+/// Other string values go through [`redact_text`], so credentials embedded in an
+/// endpoint or proxy URL are removed too.
+pub fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) && !value.is_null() {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        serde_json::Value::String(text) => *text = redact_text(text),
+        _ => {}
+    }
+}
+
+/// Run every URL embedded in free text, such as an error message, through [`redact_url`]
+pub fn redact_text(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("http") {
+        let (before, candidate) = rest.split_at(start);
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || matches!(c, ')' | '(' | '"' | '\'' | '<' | '>' | ','))
+            .unwrap_or(candidate.len());
+        let (word, after) = candidate.split_at(end);
+        redacted.push_str(before);
+        // Only credentials and queries are rewritten, leaving plain URLs as written
+        if word.contains("://") && (word.contains('@') || word.contains('?')) {
+            redacted.push_str(&redact_url(word));
+        } else {
+            redacted.push_str(word);
+        }
+        rest = after;
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// Strip userinfo and redact secret query parameters from a URL
+pub fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else {
+        return url.split('?').next().unwrap_or_default().to_string();
+    };
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        let _ = parsed.set_username(REDACTED);
+        let _ = parsed.set_password(None);
+    }
+    if parsed.query().is_some() {
+        let pairs: Vec<(String, String)> = parsed
+            .query_pairs()
+            .map(|(key, value)| {
+                let value = if is_secret_key(&key) { REDACTED.to_string() } else { value.into_owned() };
+                (key.into_owned(), value)
+            })
+            .collect();
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    parsed.to_string()
+}
@@ -5,9 +5,11 @@ pub type Result<T> = std::result::Result<T, EtherlinkError>;
 #[derive(Error, Debug)]
 pub enum EtherlinkError {
     #[error("gRPC transport error: {0}")]
+    #[cfg(not(target_arch = "wasm32"))]
     Transport(#[from] tonic::transport::Error),
 
     #[error("gRPC status error: {0}")]
+    #[cfg(not(target_arch = "wasm32"))]
     Status(#[from] tonic::Status),
 
     #[error("QUIC connection error: {0}")]
@@ -32,6 +34,9 @@ pub enum EtherlinkError {
     #[error("Configuration error: {0}")]
     Configuration(String),
 
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(#[from] crate::validation::ConfigErrors),
+
     #[error("Network error: {0}")]
     Network(String),
 
@@ -46,4 +51,72 @@ pub enum EtherlinkError {
 
     #[error("API error: {0}")]
     Api(String),
-}
\ No newline at end of file
+
+    /// The service answered that the requested item does not exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
+    #[error("Codec error: {0}")]
+    Codec(String),
+
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+
+    #[error("Transaction pool rejected transaction: {0}")]
+    TxPool(String),
+
+    #[error("Zig bridge is down: {0}")]
+    BridgeDown(String),
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    #[error("Local index error: {0}")]
+    Index(String),
+
+    #[error("Session policy violation: {0}")]
+    SessionPolicy(String),
+
+    #[error("Content integrity error: {0}")]
+    Integrity(String),
+
+    #[error("Invalid address: {0}")]
+    InvalidAddress(#[from] crate::address::AddressError),
+
+    #[error("State invariant violated: {0}")]
+    StateInvariantViolation(#[from] crate::invariants::StateInvariantViolation),
+
+    #[error("Simulation predicts the transaction reverts: {}", .reason.as_deref().unwrap_or("no reason given"))]
+    SimulatedRevert { reason: Option<String>, gas_used: crate::Gas },
+
+    #[error("Transaction lifecycle error: {0}")]
+    Lifecycle(String),
+
+    #[error("Portfolio error: {0}")]
+    Portfolio(String),
+
+    #[error("Permission denied: token lacks {required:?}")]
+    PermissionDenied { required: Vec<crate::auth::Permission> },
+}
+
+impl EtherlinkError {
+    /// Whether the same call may succeed if retried later, e.g. once a bridge is back up
+    pub fn is_retriable(&self) -> bool {
+        matches!(
+            self,
+            EtherlinkError::BridgeDown(_)
+                | EtherlinkError::ServiceUnavailable(_)
+                | EtherlinkError::Timeout(_)
+                | EtherlinkError::Network(_)
+        )
+    }
+}
+
+impl From<crate::primitives::SigningError> for EtherlinkError {
+    fn from(err: crate::primitives::SigningError) -> Self {
+        EtherlinkError::Crypto(err.to_string())
+    }
+}
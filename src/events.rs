@@ -0,0 +1,131 @@
+//! Crate-wide notifications
+//!
+//! Modules publish an [`EtherlinkEvent`] to an [`EventBus`] they were handed, instead
+//! of each exposing its own callback or channel. The runtime facade owns one bus and
+//! passes it to every client it creates, so applications subscribe once with
+//! [`crate::Etherlink::subscribe_events`].
+//!
+//! Events caused by an operation run in [`crate::correlation::scope`] carry its
+//! correlation ID.
+
+use crate::cns::ChangeEventType;
+use crate::correlation::CorrelationId;
+use crate::lifecycle::TxState;
+use crate::watcher::Direction;
+use crate::{Address, BlockHeight, TokenType, TxHash};
+use crate::queue::{Fanout, QueueConfig, QueueReceiver};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before the oldest are dropped
+const EVENT_BUS_CAPACITY: usize = 256;
+
+/// Something that happened in one of Etherlink's clients or background tasks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EtherlinkEvent {
+    /// A shared gRPC channel was connected, or crossed its failure budget either way
+    ConnectionStatusChanged { endpoint: String, healthy: bool },
+    /// A Guardian token was replaced ahead of expiry
+    TokenRefreshed { identity: String, expires_at: u64 },
+    /// A GhostPlane batch was committed to L1
+    BatchFinalized { batch_id: String, transactions: usize, l1_commitment: String },
+    /// A domain was registered, updated, transferred or renewed through the CNS client
+    DomainChanged {
+        domain: String,
+        change: ChangeEventType,
+        tx_hash: String,
+        /// Operation the change was made in, see [`crate::correlation::scope`]
+        #[serde(default)]
+        correlation_id: Option<CorrelationId>,
+    },
+    /// A marketplace offer was accepted and the domain changed hands
+    DomainSold {
+        domain: String,
+        seller: Address,
+        buyer: Address,
+        price: u64,
+        token: TokenType,
+        tx_hash: String,
+        /// Operation the change was made in, see [`crate::correlation::scope`]
+        #[serde(default)]
+        correlation_id: Option<CorrelationId>,
+    },
+    /// A watched account sent or received a transfer
+    BalanceChanged { address: Address, token: TokenType, amount: u64, direction: Direction, block_height: BlockHeight },
+    /// An atomic swap moved to a new stage on this side
+    SwapStatusChanged {
+        swap_id: String,
+        status: crate::clients::swap::SwapStatus,
+        /// Operation the change was made in, see [`crate::correlation::scope`]
+        #[serde(default)]
+        correlation_id: Option<CorrelationId>,
+    },
+    /// The pinning service failed to pin IPFS content a watched domain points at
+    ContentUnpinned { domain: String, cid: String },
+    /// A new block was seen at the head of the chain
+    NewBlock { height: BlockHeight, hash: String },
+    /// An indexed block was replaced by a different one at the same height
+    ReorgDetected { height: BlockHeight, old_hash: String, new_hash: String },
+    /// A tracked transaction moved to a new lifecycle state
+    TransactionStateChanged {
+        id: String,
+        tx_hash: Option<TxHash>,
+        from: TxState,
+        to: TxState,
+        /// Operation the change was made in, see [`crate::correlation::scope`]
+        #[serde(default)]
+        correlation_id: Option<CorrelationId>,
+    },
+}
+
+/// Broadcast channel for [`EtherlinkEvent`]s; clones publish to the same subscribers
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<EtherlinkEvent>,
+    /// Subscribers with their own bounded queue, see [`EventBus::subscribe_bounded`]
+    bounded: Fanout<EtherlinkEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::with_capacity(EVENT_BUS_CAPACITY)
+    }
+
+    /// A bus buffering `capacity` events per subscriber
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { sender: broadcast::channel(capacity).0, bounded: Fanout::new() }
+    }
+
+    /// Deliver an event to every current subscriber; without subscribers it is dropped
+    pub fn publish(&self, event: EtherlinkEvent) {
+        self.bounded.try_publish(event.clone());
+        let _ = self.sender.send(event);
+    }
+
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<EtherlinkEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Receive every event published from now on through a queue of its own
+    ///
+    /// Unlike [`EventBus::subscribe`], where a lagging receiver loses events it only
+    /// learns about on its next receive, the queue's policy decides what a slow
+    /// subscriber loses and its metrics count it. Publishing never waits, so a full
+    /// queue with [`crate::queue::OverflowPolicy::Block`] drops the new event.
+    pub fn subscribe_bounded(&self, config: QueueConfig) -> QueueReceiver<EtherlinkEvent> {
+        self.bounded.subscribe(config)
+    }
+
+    /// Number of live subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count() + self.bounded.subscriber_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,544 @@
+//! Bulk export of chain history for indexers
+//!
+//! [`Exporter`] walks a block range in parts of [`ExportConfig::blocks_per_part`]
+//! blocks. For each part it writes one file per dataset: the part's transactions,
+//! a balance snapshot of every account they touch as of the part's last block, and
+//! the domains those accounts own (when a CNS client is attached). Files are named
+//! `{dataset}-{first}-{last}.{csv|parquet}`.
+//!
+//! A part is recorded in `manifest.json` only after all of its files are written, so
+//! an interrupted export resumes at the first incomplete part and simply rewrites it.
+//! Column layouts are versioned by [`SCHEMA_VERSION`], stored in the manifest and in
+//! Parquet file metadata; resuming into a directory written with a different schema
+//! version, format or range is refused rather than mixing layouts.
+//!
+//! Parquet output requires the `parquet` feature.
+
+use crate::clients::cns::CnsClient;
+use crate::clients::ghostd::{Block, GhostdClient};
+use crate::pagination::PageConfig;
+use crate::validation::{ConfigErrors, Validator};
+use crate::{Address, BlockHeight, EtherlinkError, Result};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::info;
+
+/// Version of the exported column layouts; bumped whenever a column changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Name of the progress file kept in the output directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Parquet key-value metadata entry holding [`SCHEMA_VERSION`]
+pub const SCHEMA_VERSION_KEY: &str = "etherlink.schema_version";
+
+/// Output file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = EtherlinkError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "parquet" => Ok(ExportFormat::Parquet),
+            other => Err(EtherlinkError::Configuration(format!("Unknown export format: {} (expected csv or parquet)", other))),
+        }
+    }
+}
+
+/// Kind of rows in an exported file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dataset {
+    Transactions,
+    Balances,
+    Domains,
+}
+
+impl Dataset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dataset::Transactions => "transactions",
+            Dataset::Balances => "balances",
+            Dataset::Domains => "domains",
+        }
+    }
+
+    /// File name for the part covering `blocks`
+    pub fn file_name(&self, blocks: &RangeInclusive<BlockHeight>, format: ExportFormat) -> String {
+        format!("{}-{}-{}.{}", self.name(), blocks.start(), blocks.end(), format.extension())
+    }
+}
+
+/// What to export and where
+#[derive(Debug, Clone)]
+pub struct ExportConfig {
+    pub out_dir: PathBuf,
+    pub format: ExportFormat,
+    pub from_block: BlockHeight,
+    pub to_block: BlockHeight,
+    /// Blocks per output part, and so per resume step
+    pub blocks_per_part: u64,
+    /// Blocks fetched per request
+    pub page_size: u32,
+    /// Continue from an existing manifest in `out_dir` instead of starting over
+    pub resume: bool,
+}
+
+impl ExportConfig {
+    /// Export `blocks` as CSV into `out_dir`
+    pub fn new(out_dir: impl Into<PathBuf>, blocks: RangeInclusive<BlockHeight>) -> Self {
+        Self {
+            out_dir: out_dir.into(),
+            format: ExportFormat::Csv,
+            from_block: *blocks.start(),
+            to_block: *blocks.end(),
+            blocks_per_part: 1000,
+            page_size: 100,
+            resume: false,
+        }
+    }
+
+    pub fn format(mut self, format: ExportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn blocks_per_part(mut self, blocks: u64) -> Self {
+        self.blocks_per_part = blocks;
+        self
+    }
+
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.from_block <= self.to_block, "from_block", "must not exceed to_block");
+        v.check(self.blocks_per_part > 0, "blocks_per_part", "must be greater than zero");
+        v.check(self.page_size > 0, "page_size", "must be greater than zero");
+        v.check(
+            self.format != ExportFormat::Parquet || cfg!(feature = "parquet"),
+            "format",
+            "parquet output requires the `parquet` feature",
+        );
+        v.finish()
+    }
+}
+
+/// Progress of an export, persisted as [`MANIFEST_FILE`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub schema_version: u32,
+    pub format: ExportFormat,
+    pub from_block: BlockHeight,
+    pub to_block: BlockHeight,
+    /// First block not yet exported
+    pub next_block: BlockHeight,
+    /// Completed files, relative to the output directory
+    pub files: Vec<String>,
+}
+
+impl ExportManifest {
+    fn new(config: &ExportConfig) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            format: config.format,
+            from_block: config.from_block,
+            to_block: config.to_block,
+            next_block: config.from_block,
+            files: Vec::new(),
+        }
+    }
+
+    /// Read the manifest in `dir`, if there is one
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to read {}: {}", path.display(), e)))?;
+        Ok(Some(serde_json::from_str(&contents)?))
+    }
+
+    /// Write atomically, so a crash never leaves a truncated manifest behind
+    fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(MANIFEST_FILE);
+        let tmp = dir.join(format!("{}.tmp", MANIFEST_FILE));
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .and_then(|_| std::fs::rename(&tmp, &path))
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.next_block > self.to_block
+    }
+
+    fn check_compatible(&self, config: &ExportConfig) -> Result<()> {
+        if self.schema_version != SCHEMA_VERSION {
+            return Err(EtherlinkError::Configuration(format!(
+                "Export in {} uses schema version {}, this build writes version {}",
+                config.out_dir.display(), self.schema_version, SCHEMA_VERSION
+            )));
+        }
+        if self.format != config.format || self.from_block != config.from_block || self.to_block != config.to_block {
+            return Err(EtherlinkError::Configuration(format!(
+                "Export in {} covers blocks {}..={} as {}, cannot resume it as blocks {}..={} as {}",
+                config.out_dir.display(), self.from_block, self.to_block, self.format.extension(),
+                config.from_block, config.to_block, config.format.extension()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// One transaction of an exported block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionRow {
+    pub block_height: BlockHeight,
+    pub block_hash: String,
+    pub timestamp: u64,
+    /// Position within the block
+    pub tx_index: u64,
+    pub from_address: String,
+    pub to_address: String,
+    pub amount: u64,
+    pub gas_limit: u64,
+    pub gas_price: u64,
+    pub nonce: u64,
+}
+
+impl TransactionRow {
+    /// Rows for every transaction in `block`
+    pub fn from_block(block: &Block) -> Vec<Self> {
+        block
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(index, tx)| Self {
+                block_height: block.height,
+                block_hash: block.hash.clone(),
+                timestamp: block.timestamp,
+                tx_index: index as u64,
+                from_address: tx.from.to_string(),
+                to_address: tx.to.to_string(),
+                amount: tx.amount,
+                gas_limit: tx.gas_limit,
+                gas_price: tx.gas_price,
+                nonce: tx.nonce,
+            })
+            .collect()
+    }
+}
+
+/// Balance of an account as of a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceRow {
+    pub block_height: BlockHeight,
+    pub address: String,
+    pub balance: u64,
+}
+
+/// Domain owned by an account as of a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainRow {
+    pub block_height: BlockHeight,
+    pub domain: String,
+    pub owner: String,
+    pub tld: String,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ColumnType {
+    U64,
+    Text,
+}
+
+#[derive(Debug, Clone)]
+enum Cell {
+    U64(u64),
+    Text(String),
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::U64(value) => write!(f, "{}", value),
+            Cell::Text(value) => f.write_str(value),
+        }
+    }
+}
+
+/// Fixed column layout shared by the CSV and Parquet writers
+trait Row {
+    const COLUMNS: &'static [(&'static str, ColumnType)];
+
+    fn cells(&self) -> Vec<Cell>;
+}
+
+impl Row for TransactionRow {
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("block_height", ColumnType::U64),
+        ("block_hash", ColumnType::Text),
+        ("timestamp", ColumnType::U64),
+        ("tx_index", ColumnType::U64),
+        ("from_address", ColumnType::Text),
+        ("to_address", ColumnType::Text),
+        ("amount", ColumnType::U64),
+        ("gas_limit", ColumnType::U64),
+        ("gas_price", ColumnType::U64),
+        ("nonce", ColumnType::U64),
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::U64(self.block_height),
+            Cell::Text(self.block_hash.clone()),
+            Cell::U64(self.timestamp),
+            Cell::U64(self.tx_index),
+            Cell::Text(self.from_address.clone()),
+            Cell::Text(self.to_address.clone()),
+            Cell::U64(self.amount),
+            Cell::U64(self.gas_limit),
+            Cell::U64(self.gas_price),
+            Cell::U64(self.nonce),
+        ]
+    }
+}
+
+impl Row for BalanceRow {
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("block_height", ColumnType::U64),
+        ("address", ColumnType::Text),
+        ("balance", ColumnType::U64),
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![Cell::U64(self.block_height), Cell::Text(self.address.clone()), Cell::U64(self.balance)]
+    }
+}
+
+impl Row for DomainRow {
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("block_height", ColumnType::U64),
+        ("domain", ColumnType::Text),
+        ("owner", ColumnType::Text),
+        ("tld", ColumnType::Text),
+        ("created_at", ColumnType::U64),
+        ("expires_at", ColumnType::U64),
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::U64(self.block_height),
+            Cell::Text(self.domain.clone()),
+            Cell::Text(self.owner.clone()),
+            Cell::Text(self.tld.clone()),
+            Cell::U64(self.created_at),
+            Cell::U64(self.expires_at),
+        ]
+    }
+}
+
+fn write_rows<R: Row>(path: &Path, dataset: Dataset, format: ExportFormat, rows: &[R]) -> Result<()> {
+    match format {
+        ExportFormat::Csv => write_csv(path, rows),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => write_parquet(path, dataset, rows),
+        #[cfg(not(feature = "parquet"))]
+        ExportFormat::Parquet => {
+            let _ = dataset;
+            Err(EtherlinkError::Unsupported("Parquet export requires the `parquet` feature".to_string()))
+        }
+    }
+}
+
+fn write_csv<R: Row>(path: &Path, rows: &[R]) -> Result<()> {
+    let io_error = |e: csv::Error| EtherlinkError::Configuration(format!("Failed to write {}: {}", path.display(), e));
+    let mut writer = csv::Writer::from_path(path).map_err(io_error)?;
+    writer.write_record(R::COLUMNS.iter().map(|(name, _)| name)).map_err(io_error)?;
+    for row in rows {
+        writer.write_record(row.cells().iter().map(Cell::to_string)).map_err(io_error)?;
+    }
+    writer.flush().map_err(|e| EtherlinkError::Configuration(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet<R: Row>(path: &Path, dataset: Dataset, rows: &[R]) -> Result<()> {
+    use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::format::KeyValue;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    let parquet_error = |e: parquet::errors::ParquetError| EtherlinkError::Codec(format!("Failed to write {}: {}", path.display(), e));
+
+    let fields: Vec<String> = R::COLUMNS
+        .iter()
+        .map(|(name, kind)| match kind {
+            ColumnType::U64 => format!("REQUIRED INT64 {} (INTEGER(64,false));", name),
+            ColumnType::Text => format!("REQUIRED BYTE_ARRAY {} (UTF8);", name),
+        })
+        .collect();
+    let schema = parse_message_type(&format!("message {} {{ {} }}", dataset.name(), fields.join(" "))).map_err(parquet_error)?;
+    let properties = WriterProperties::builder()
+        .set_key_value_metadata(Some(vec![KeyValue::new(SCHEMA_VERSION_KEY.to_string(), SCHEMA_VERSION.to_string())]))
+        .build();
+
+    let file = std::fs::File::create(path)
+        .map_err(|e| EtherlinkError::Configuration(format!("Failed to create {}: {}", path.display(), e)))?;
+    let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties)).map_err(parquet_error)?;
+    let rows: Vec<Vec<Cell>> = rows.iter().map(Row::cells).collect();
+
+    let mut group = writer.next_row_group().map_err(parquet_error)?;
+    for index in 0..R::COLUMNS.len() {
+        let mut column = group
+            .next_column()
+            .map_err(parquet_error)?
+            .ok_or_else(|| EtherlinkError::Codec(format!("Parquet schema for {} is missing column {}", dataset.name(), index)))?;
+        match R::COLUMNS[index].1 {
+            ColumnType::U64 => {
+                let values: Vec<i64> = rows.iter().map(|cells| match &cells[index] {
+                    Cell::U64(value) => *value as i64,
+                    Cell::Text(_) => unreachable!("column layout mismatch"),
+                }).collect();
+                column.typed::<Int64Type>().write_batch(&values, None, None).map_err(parquet_error)?;
+            }
+            ColumnType::Text => {
+                let values: Vec<ByteArray> = rows.iter().map(|cells| ByteArray::from(cells[index].to_string().into_bytes())).collect();
+                column.typed::<ByteArrayType>().write_batch(&values, None, None).map_err(parquet_error)?;
+            }
+        }
+        column.close().map_err(parquet_error)?;
+    }
+    group.close().map_err(parquet_error)?;
+    writer.close().map_err(parquet_error)?;
+    Ok(())
+}
+
+/// Writes a block range to files, resumably
+pub struct Exporter {
+    ghostd: GhostdClient,
+    cns: Option<CnsClient>,
+    config: ExportConfig,
+}
+
+impl Exporter {
+    pub fn new(ghostd: GhostdClient, config: ExportConfig) -> Self {
+        Self { ghostd, cns: None, config }
+    }
+
+    /// Also export the domains owned by touched accounts
+    pub fn with_cns(mut self, cns: CnsClient) -> Self {
+        self.cns = Some(cns);
+        self
+    }
+
+    /// Export every remaining part and return the final manifest
+    pub async fn run(&self) -> Result<ExportManifest> {
+        self.config.validate()?;
+        let dir = &self.config.out_dir;
+        std::fs::create_dir_all(dir)
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to create {}: {}", dir.display(), e)))?;
+
+        let mut manifest = match ExportManifest::load(dir)? {
+            Some(manifest) if self.config.resume => {
+                manifest.check_compatible(&self.config)?;
+                info!("Resuming export into {} at block {}", dir.display(), manifest.next_block);
+                manifest
+            }
+            _ => ExportManifest::new(&self.config),
+        };
+
+        while !manifest.is_complete() {
+            let first = manifest.next_block;
+            let last = first.saturating_add(self.config.blocks_per_part - 1).min(self.config.to_block);
+            let files = self.export_part(first..=last).await?;
+            manifest.files.extend(files);
+            manifest.next_block = last + 1;
+            manifest.save(dir)?;
+            info!("Exported blocks {}..={} to {}", first, last, dir.display());
+        }
+        Ok(manifest)
+    }
+
+    async fn export_part(&self, blocks: RangeInclusive<BlockHeight>) -> Result<Vec<String>> {
+        let snapshot_height = *blocks.end();
+        let mut transactions = Vec::new();
+        let mut accounts = BTreeSet::new();
+
+        let mut stream = self.ghostd.stream_blocks(blocks.clone(), PageConfig::default().page_size(self.config.page_size));
+        while let Some(block) = stream.next().await {
+            for row in TransactionRow::from_block(&block?) {
+                accounts.insert(row.from_address.clone());
+                accounts.insert(row.to_address.clone());
+                transactions.push(row);
+            }
+        }
+
+        let ghostd = self.ghostd.at_block(snapshot_height);
+        let mut balances = Vec::with_capacity(accounts.len());
+        for address in &accounts {
+            let balance = ghostd.get_balance(&Address::new(address.clone())).await?;
+            balances.push(BalanceRow { block_height: snapshot_height, address: address.clone(), balance });
+        }
+
+        let mut files = vec![self.write(Dataset::Transactions, &blocks, &transactions)?, self.write(Dataset::Balances, &blocks, &balances)?];
+
+        if let Some(cns) = &self.cns {
+            let cns = cns.at_block(snapshot_height);
+            let mut domains = Vec::new();
+            for address in &accounts {
+                for domain in cns.get_domains_by_owner(&Address::new(address.clone())).await? {
+                    let info = cns.get_domain_info(&domain).await?;
+                    domains.push(DomainRow {
+                        block_height: snapshot_height,
+                        domain: info.domain,
+                        owner: info.owner.to_string(),
+                        tld: info.tld,
+                        created_at: info.created_at,
+                        expires_at: info.expires_at,
+                    });
+                }
+            }
+            files.push(self.write(Dataset::Domains, &blocks, &domains)?);
+        }
+        Ok(files)
+    }
+
+    fn write<R: Row>(&self, dataset: Dataset, blocks: &RangeInclusive<BlockHeight>, rows: &[R]) -> Result<String> {
+        let name = dataset.file_name(blocks, self.config.format);
+        write_rows(&self.config.out_dir.join(&name), dataset, self.config.format, rows)?;
+        Ok(name)
+    }
+}
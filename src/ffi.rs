@@ -1,21 +1,366 @@
+use crate::cassette::Cassette;
+use crate::correlation;
+use crate::proto::ghostplane::v1 as ghostplane_pb;
+use crate::shm::{SharedMemoryConfig, SharedRing};
+use crate::validation::{ConfigErrors, Validator};
 use crate::{EtherlinkError, Result};
 use libc::{c_char, c_int, c_void};
+use serde::{Deserialize, Serialize};
 use std::ffi::{CStr, CString};
+use std::future::Future;
 use std::ptr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
 use tracing::{debug, error, warn};
 
 /// FFI bridge for Rust ↔ Zig interoperability
 #[derive(Debug)]
 pub struct ZigBridge {
     initialized: bool,
+    chunking: ChunkingConfig,
+    shared_memory: Option<(SharedRing, Duration)>,
+    link: BridgeLink,
+    cassette: Option<Arc<Cassette>>,
+}
+
+/// Whether the Zig side is usable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BridgeStatus {
+    Up,
+    /// Crashed or unloaded; calls fail fast with [`EtherlinkError::BridgeDown`]
+    Down,
+    /// A supervisor is bringing the bridge back
+    Reinitializing,
+}
+
+/// Liveness of a bridge as seen by callers and its supervisor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkState {
+    pub status: BridgeStatus,
+    /// Why the bridge last went down
+    pub reason: Option<String>,
+    /// Successful reinitializations so far
+    pub restarts: u64,
+}
+
+/// Shared handle on a bridge's liveness
+///
+/// Whatever notices the Zig side is gone (a failed call, an unload callback) marks the
+/// link down; every bridge call then fails fast instead of reaching into a dead library,
+/// until a supervisor reinitializes it.
+#[derive(Debug, Clone)]
+pub struct BridgeLink {
+    state: Arc<watch::Sender<LinkState>>,
+}
+
+impl Default for BridgeLink {
+    fn default() -> Self {
+        Self { state: Arc::new(watch::Sender::new(LinkState { status: BridgeStatus::Up, reason: None, restarts: 0 })) }
+    }
+}
+
+impl BridgeLink {
+    /// Current status
+    pub fn status(&self) -> BridgeStatus {
+        self.state.borrow().status
+    }
+
+    /// Current status with the reason and restart count
+    pub fn state(&self) -> LinkState {
+        self.state.borrow().clone()
+    }
+
+    /// Record that the Zig side crashed or was unloaded
+    pub fn mark_down(&self, reason: impl Into<String>) {
+        let reason = reason.into();
+        self.state.send_if_modified(|state| {
+            if state.status == BridgeStatus::Down {
+                return false;
+            }
+            error!("Zig bridge went down: {}", reason);
+            state.status = BridgeStatus::Down;
+            state.reason = Some(reason);
+            true
+        });
+    }
+
+    pub(crate) fn mark_reinitializing(&self) {
+        self.state.send_modify(|state| state.status = BridgeStatus::Reinitializing);
+    }
+
+    pub(crate) fn mark_up(&self) {
+        self.state.send_modify(|state| {
+            if state.status != BridgeStatus::Up {
+                state.status = BridgeStatus::Up;
+                state.restarts += 1;
+            }
+        });
+    }
+
+    /// Fail with [`EtherlinkError::BridgeDown`] unless the bridge is up
+    pub fn check(&self) -> Result<()> {
+        let state = self.state.borrow();
+        match state.status {
+            BridgeStatus::Up => Ok(()),
+            _ => Err(EtherlinkError::BridgeDown(state.reason.clone().unwrap_or_else(|| "unknown cause".to_string()))),
+        }
+    }
+
+    /// Wait until the bridge is down
+    pub async fn wait_down(&self) {
+        let mut rx = self.state.subscribe();
+        let _ = rx.wait_for(|state| state.status == BridgeStatus::Down).await;
+    }
+
+    /// Wait until the bridge is up
+    pub async fn wait_up(&self) {
+        let mut rx = self.state.subscribe();
+        let _ = rx.wait_for(|state| state.status == BridgeStatus::Up).await;
+    }
+}
+
+/// When and how large payloads are split across several FFI calls
+///
+/// Payloads above `threshold_bytes` are sent as a begin/append/commit transfer
+/// instead of a single call, optionally zstd-compressed first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkingConfig {
+    /// Payloads larger than this are transferred in chunks
+    pub threshold_bytes: usize,
+    /// Size of each appended chunk
+    pub chunk_size: usize,
+    /// Compress chunked payloads with zstd
+    pub compress: bool,
+    /// zstd level, 1 (fastest) to 22
+    pub compression_level: i32,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            threshold_bytes: 1024 * 1024,
+            chunk_size: 256 * 1024,
+            compress: true,
+            compression_level: 3,
+        }
+    }
+}
+
+impl ChunkingConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.threshold_bytes > 0, "threshold_bytes", "must be greater than zero");
+        v.check(self.chunk_size > 0, "chunk_size", "must be greater than zero");
+        v.check((1..=22).contains(&self.compression_level), "compression_level", "must be between 1 and 22");
+        v.finish()
+    }
+}
+
+/// Describes a chunked transfer; passed to the begin call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferManifest {
+    /// Length of the original payload
+    pub total_len: u64,
+    /// Length after compression, equal to `total_len` when uncompressed
+    pub encoded_len: u64,
+    pub chunk_count: u32,
+    pub compressed: bool,
+    /// CRC-32 of the original payload, checked after reassembly
+    pub checksum: u32,
+}
+
+/// One appended piece of a chunked transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub index: u32,
+    pub data: Vec<u8>,
+    /// CRC-32 of `data`
+    pub checksum: u32,
+}
+
+/// A payload split up for a begin/append/commit transfer
+#[derive(Debug, Clone)]
+pub struct ChunkedPayload {
+    pub manifest: TransferManifest,
+    pub chunks: Vec<Chunk>,
+}
+
+impl ChunkedPayload {
+    /// Compress (if configured) and split a payload into checksummed chunks
+    pub fn encode(payload: &[u8], config: &ChunkingConfig) -> Result<Self> {
+        let encoded = if config.compress {
+            zstd::bulk::compress(payload, config.compression_level)
+                .map_err(|e| EtherlinkError::Ffi(format!("Failed to compress payload: {}", e)))?
+        } else {
+            payload.to_vec()
+        };
+
+        let chunks: Vec<Chunk> = encoded
+            .chunks(config.chunk_size.max(1))
+            .enumerate()
+            .map(|(index, data)| Chunk { index: index as u32, data: data.to_vec(), checksum: crc32fast::hash(data) })
+            .collect();
+
+        Ok(Self {
+            manifest: TransferManifest {
+                total_len: payload.len() as u64,
+                encoded_len: encoded.len() as u64,
+                chunk_count: chunks.len() as u32,
+                compressed: config.compress,
+                checksum: crc32fast::hash(payload),
+            },
+            chunks,
+        })
+    }
+}
+
+/// Most a [`ChunkAssembler`] reserves before any chunk arrives
+pub const MAX_TRANSFER_PREALLOC: usize = 4 * 1024 * 1024;
+
+/// Receiving side of a chunked transfer, reassembling and verifying the payload
+///
+/// Chunks must arrive in order; any checksum or length mismatch fails the transfer.
+#[derive(Debug)]
+pub struct ChunkAssembler {
+    manifest: TransferManifest,
+    buffer: Vec<u8>,
+    next_index: u32,
+}
+
+impl ChunkAssembler {
+    /// Start a transfer
+    ///
+    /// The manifest comes from the other side of the bridge, so at most
+    /// [`MAX_TRANSFER_PREALLOC`] bytes are reserved up front whatever it claims.
+    pub fn begin(manifest: TransferManifest) -> Self {
+        let capacity = usize::try_from(manifest.encoded_len).unwrap_or(usize::MAX).min(MAX_TRANSFER_PREALLOC);
+        Self { manifest, buffer: Vec::with_capacity(capacity), next_index: 0 }
+    }
+
+    /// Append the next chunk
+    pub fn append(&mut self, index: u32, data: &[u8], checksum: u32) -> Result<()> {
+        if index != self.next_index {
+            return Err(EtherlinkError::Ffi(format!("Expected chunk {}, got {}", self.next_index, index)));
+        }
+        if crc32fast::hash(data) != checksum {
+            return Err(EtherlinkError::Ffi(format!("Checksum mismatch in chunk {}", index)));
+        }
+        if (self.buffer.len() + data.len()) as u64 > self.manifest.encoded_len {
+            return Err(EtherlinkError::Ffi(format!("Chunk {} overruns the {} byte transfer", index, self.manifest.encoded_len)));
+        }
+        self.buffer.extend_from_slice(data);
+        self.next_index += 1;
+        Ok(())
+    }
+
+    /// Finish the transfer, returning the verified original payload
+    pub fn commit(self) -> Result<Vec<u8>> {
+        if self.next_index != self.manifest.chunk_count || self.buffer.len() as u64 != self.manifest.encoded_len {
+            return Err(EtherlinkError::Ffi(format!(
+                "Transfer incomplete: {} of {} chunks received",
+                self.next_index, self.manifest.chunk_count
+            )));
+        }
+
+        let payload = if self.manifest.compressed {
+            zstd::bulk::decompress(&self.buffer, self.manifest.total_len as usize)
+                .map_err(|e| EtherlinkError::Ffi(format!("Failed to decompress payload: {}", e)))?
+        } else {
+            self.buffer
+        };
+
+        if payload.len() as u64 != self.manifest.total_len || crc32fast::hash(&payload) != self.manifest.checksum {
+            return Err(EtherlinkError::Ffi("Checksum mismatch in reassembled payload".to_string()));
+        }
+        Ok(payload)
+    }
 }
 
 impl ZigBridge {
     /// Create a new Zig bridge instance
     pub fn new() -> Self {
+        Self::with_chunking(ChunkingConfig::default())
+    }
+
+    /// Create a bridge that chunks large payloads according to `chunking`
+    pub fn with_chunking(chunking: ChunkingConfig) -> Self {
         Self {
             initialized: false,
+            chunking,
+            shared_memory: None,
+            link: BridgeLink::default(),
+            cassette: None,
+        }
+    }
+
+    /// Record bridge calls to, or replay them from, a cassette
+    pub fn set_cassette(&mut self, cassette: Arc<Cassette>) {
+        self.cassette = Some(cassette);
+    }
+
+    /// Liveness handle, shared with whatever supervises this bridge
+    pub fn link(&self) -> BridgeLink {
+        self.link.clone()
+    }
+
+    /// Bring the bridge back after the Zig side crashed or was unloaded
+    ///
+    /// Transactions the old instance had accepted are not carried over; see
+    /// [`crate::ghostplane::GhostPlaneClient::recover_bridge`].
+    pub fn reinitialize(&self) -> Result<()> {
+        self.restart()?;
+        self.link.mark_up();
+        Ok(())
+    }
+
+    /// Reinitialize, leaving the link reinitializing until the caller marks it up
+    pub(crate) fn restart(&self) -> Result<()> {
+        if !self.initialized {
+            return Err(EtherlinkError::Ffi("Bridge not initialized".to_string()));
         }
+
+        debug!("Reinitializing Zig bridge");
+        self.link.mark_reinitializing();
+
+        // TODO: Reload the library and call low_level::init_ghostplane, then re-attach
+        // the shared memory ring, once ghostplane is integrated
+        debug!("Zig bridge reinitialized");
+        Ok(())
+    }
+
+    /// Negotiate a shared-memory ring with the Zig side for transaction submission
+    ///
+    /// Once attached, transactions are written to the ring instead of going through
+    /// a call each; a full ring holds submissions back for up to `send_timeout_ms`.
+    pub fn attach_shared_memory(&mut self, config: &SharedMemoryConfig) -> Result<()> {
+        if !self.initialized {
+            return Err(EtherlinkError::Ffi("Bridge not initialized".to_string()));
+        }
+
+        let ring = SharedRing::create(config.resolve_path(), config.capacity_bytes)?;
+        debug!("Negotiating shared memory ring {} ({} bytes)", ring.path().display(), ring.capacity());
+
+        // Without the `ghostplane-ffi` feature there is no Zig library to hand the ring to
+        #[cfg(feature = "ghostplane-ffi")]
+        unsafe {
+            low_level::attach_ring_raw(&ring)?;
+        }
+        self.shared_memory = Some((ring, Duration::from_millis(config.send_timeout_ms)));
+        Ok(())
+    }
+
+    /// The negotiated shared-memory ring, if any
+    pub fn shared_memory(&self) -> Option<&SharedRing> {
+        self.shared_memory.as_ref().map(|(ring, _)| ring)
+    }
+
+    /// Chunking settings for large payloads
+    pub fn chunking(&self) -> &ChunkingConfig {
+        &self.chunking
     }
 
     /// Initialize the Zig bridge
@@ -44,24 +389,106 @@ impl ZigBridge {
         if !self.initialized {
             return Err(EtherlinkError::Ffi("Bridge not initialized".to_string()));
         }
+        self.link.check()?;
 
-        debug!("Calling Zig function: {}", function_name);
+        let context = FfiContext::current()?;
+        debug!(correlation_id = context.correlation_id(), "Calling Zig function: {}", function_name);
+        self.exchange(function_name, params, self.guarded(self.call_raw(params))).await
+    }
 
-        // TODO: Implement actual Zig FFI calls once ghostplane is integrated
+    async fn call_raw(&self, params: &[u8]) -> Result<Vec<u8>> {
+        if params.len() > self.chunking.threshold_bytes {
+            self.send_chunked(params)?;
+        }
+
+        // TODO: Implement actual Zig FFI calls, passing the caller's FfiContext, once ghostplane is integrated
         // For now, return empty response
         Ok(Vec::new())
     }
 
+    /// Run a call into the Zig side, marking the link down if it fails
+    async fn guarded<Fut>(&self, call: Fut) -> Result<Vec<u8>>
+    where
+        Fut: Future<Output = Result<Vec<u8>>>,
+    {
+        let result = call.await;
+        if let Err(e) = &result {
+            self.link.mark_down(e.to_string());
+        }
+        result
+    }
+
+    /// Perform a call, or record or replay it when a cassette is attached
+    async fn exchange<Fut>(&self, function: &str, request: &[u8], call: Fut) -> Result<Vec<u8>>
+    where
+        Fut: Future<Output = Result<Vec<u8>>>,
+    {
+        match &self.cassette {
+            Some(cassette) => cassette.ffi(function, request, call).await,
+            None => call.await,
+        }
+    }
+
+    /// Transfer a payload too large for a single call as begin/append/commit
+    ///
+    /// Without the `ghostplane-ffi` feature there is no Zig library to receive it, and
+    /// the transfer is only encoded.
+    fn send_chunked(&self, payload: &[u8]) -> Result<()> {
+        let transfer = ChunkedPayload::encode(payload, &self.chunking)?;
+        debug!(
+            "Transferring {} byte payload as {} chunks ({} bytes encoded)",
+            transfer.manifest.total_len, transfer.manifest.chunk_count, transfer.manifest.encoded_len
+        );
+
+        #[cfg(feature = "ghostplane-ffi")]
+        return unsafe { low_level::transfer_chunked(&transfer) };
+        #[cfg(not(feature = "ghostplane-ffi"))]
+        {
+            let _ = transfer;
+            Ok(())
+        }
+    }
+
     /// Submit a transaction to GhostPlane via FFI
     pub async fn submit_ghostplane_transaction(&self, tx_data: &[u8]) -> Result<String> {
         if !self.initialized {
             return Err(EtherlinkError::Ffi("Bridge not initialized".to_string()));
         }
+        self.link.check()?;
+        self.submit_unchecked(tx_data).await
+    }
 
-        debug!("Submitting transaction to GhostPlane");
+    /// Resubmit a transaction while the link is still reinitializing, as part of recovery
+    pub(crate) async fn replay_ghostplane_transaction(&self, tx_data: &[u8]) -> Result<String> {
+        if !self.initialized {
+            return Err(EtherlinkError::Ffi("Bridge not initialized".to_string()));
+        }
+        self.submit_unchecked(tx_data).await
+    }
+
+    async fn submit_unchecked(&self, tx_data: &[u8]) -> Result<String> {
+        let context = FfiContext::current()?;
+        debug!(correlation_id = context.correlation_id(), "Submitting transaction to GhostPlane");
+        let response = self.exchange("ghostplane_submit_tx", tx_data, self.guarded(self.submit_raw(&context, tx_data))).await?;
+        String::from_utf8(response).map_err(|e| EtherlinkError::Ffi(format!("Invalid UTF-8 in transaction hash: {}", e)))
+    }
 
-        // TODO: Implement actual GhostPlane transaction submission
-        Ok("0x1234567890abcdef".to_string())
+    async fn submit_raw(&self, context: &FfiContext, tx_data: &[u8]) -> Result<Vec<u8>> {
+        if let Some((ring, timeout)) = &self.shared_memory
+            && tx_data.len() <= ring.max_payload()
+        {
+            ring.push(tx_data, *timeout).await?;
+        } else if tx_data.len() > self.chunking.threshold_bytes {
+            self.send_chunked(tx_data)?;
+        } else {
+            #[cfg(feature = "ghostplane-ffi")]
+            return unsafe { low_level::submit_transaction_raw(context, tx_data) }.map(String::into_bytes);
+        }
+
+        // TODO: Report the hash of a transaction sent over the ring or in chunks, and
+        // pass `context` along with it, once ghostplane is integrated
+        let _ = context;
+        Ok(b"0x1234567890abcdef".to_vec())
     }
 
     /// Query GhostPlane state via FFI
@@ -69,11 +496,68 @@ impl ZigBridge {
         if !self.initialized {
             return Err(EtherlinkError::Ffi("Bridge not initialized".to_string()));
         }
+        self.link.check()?;
+
+        let context = FfiContext::current()?;
+        debug!(correlation_id = context.correlation_id(), "Querying GhostPlane state: {}", query);
+        let response = self.exchange("ghostplane_query_state", query.as_bytes(), self.guarded(async {
+            #[cfg(feature = "ghostplane-ffi")]
+            return unsafe { low_level::query_state_raw(&context, query) }.map(String::into_bytes);
+            #[cfg(not(feature = "ghostplane-ffi"))]
+            {
+                Ok(b"{}".to_vec())
+            }
+        }))
+        .await?;
+        String::from_utf8(response).map_err(|e| EtherlinkError::Ffi(format!("Invalid UTF-8 in state query result: {}", e)))
+    }
 
-        debug!("Querying GhostPlane state: {}", query);
+    /// Read an L2 account's balance, nonce, code and requested storage slots via FFI
+    pub async fn get_l2_state(&self, request: &ghostplane_pb::GetL2StateRequest) -> Result<ghostplane_pb::L2StateResponse> {
+        self.proto_call("ghostplane_get_state", request, |request| async move {
+            #[cfg(feature = "ghostplane-ffi")]
+            return unsafe { low_level::get_state_raw(&FfiContext::current()?, &request) };
+            #[cfg(not(feature = "ghostplane-ffi"))]
+            {
+                let _ = request;
+                Err(EtherlinkError::Unsupported("L2 state reads need the GhostPlane library (the `ghostplane-ffi` feature)".to_string()))
+            }
+        })
+        .await
+    }
 
-        // TODO: Implement actual GhostPlane state query
-        Ok("{}".to_string())
+    /// Read a single L2 contract storage slot via FFI
+    pub async fn query_l2_storage(&self, request: &ghostplane_pb::QueryL2StorageRequest) -> Result<ghostplane_pb::QueryL2StorageResponse> {
+        self.proto_call("ghostplane_query_storage", request, |request| async move {
+            #[cfg(feature = "ghostplane-ffi")]
+            return unsafe { low_level::query_storage_raw(&FfiContext::current()?, &request) };
+            #[cfg(not(feature = "ghostplane-ffi"))]
+            {
+                let _ = request;
+                Err(EtherlinkError::Unsupported("L2 storage reads need the GhostPlane library (the `ghostplane-ffi` feature)".to_string()))
+            }
+        })
+        .await
+    }
+
+    /// Perform a call exchanging protobuf-encoded request and response messages
+    async fn proto_call<Req, Resp, F, Fut>(&self, function: &str, request: &Req, call: F) -> Result<Resp>
+    where
+        Req: prost::Message,
+        Resp: prost::Message + Default,
+        F: FnOnce(Vec<u8>) -> Fut,
+        Fut: Future<Output = Result<Vec<u8>>>,
+    {
+        if !self.initialized {
+            return Err(EtherlinkError::Ffi("Bridge not initialized".to_string()));
+        }
+        self.link.check()?;
+
+        let context = FfiContext::current()?;
+        debug!(correlation_id = context.correlation_id(), "Calling {} via FFI", function);
+        let encoded = request.encode_to_vec();
+        let response = self.exchange(function, &encoded, call(encoded.clone())).await?;
+        Resp::decode(response.as_slice()).map_err(|e| EtherlinkError::Codec(format!("Invalid {} response: {}", function, e)))
     }
 
     /// Shutdown the Zig bridge
@@ -84,6 +568,8 @@ impl ZigBridge {
 
         debug!("Shutting down Zig bridge");
 
+        self.shared_memory = None;
+
         // TODO: Cleanup Zig FFI resources
         self.initialized = false;
 
@@ -108,6 +594,40 @@ impl Drop for ZigBridge {
     }
 }
 
+/// Per-call context passed to GhostPlane with each call, as seen from C
+///
+/// `correlation_id` is a NUL-terminated string, empty when the call is not part of a
+/// traced operation. It is only valid for the duration of the call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiCallContext {
+    pub correlation_id: *const c_char,
+}
+
+/// Owns the strings an [`FfiCallContext`] points to
+#[derive(Debug, Clone)]
+pub struct FfiContext {
+    correlation_id: CString,
+}
+
+impl FfiContext {
+    /// Context of the current operation, see [`correlation::scope`]
+    pub fn current() -> Result<Self> {
+        let id = correlation::current().map(|id| id.to_string()).unwrap_or_default();
+        Ok(Self { correlation_id: ffi_helpers::rust_to_c_string(&id)? })
+    }
+
+    /// Correlation ID the call is traced under; empty outside an operation
+    pub fn correlation_id(&self) -> &str {
+        self.correlation_id.to_str().unwrap_or_default()
+    }
+
+    /// View for passing across the FFI boundary; borrows `self`
+    pub fn as_raw(&self) -> FfiCallContext {
+        FfiCallContext { correlation_id: self.correlation_id.as_ptr() }
+    }
+}
+
 /// Safe FFI helpers for C string conversion
 pub mod ffi_helpers {
     use super::*;
@@ -153,9 +673,16 @@ pub mod ffi_helpers {
 unsafe extern "C" {
     // Placeholder for future Zig FFI functions
     fn ghostplane_init() -> c_int;
-    fn ghostplane_submit_tx(data: *const c_void, len: usize) -> *const c_char;
-    fn ghostplane_query_state(query: *const c_char) -> *const c_char;
+    fn ghostplane_submit_tx(context: *const FfiCallContext, data: *const c_void, len: usize) -> *const c_char;
+    fn ghostplane_query_state(context: *const FfiCallContext, query: *const c_char) -> *const c_char;
+    fn ghostplane_get_state(context: *const FfiCallContext, request: *const c_void, len: usize, response_len: *mut usize) -> *const u8;
+    fn ghostplane_query_storage(context: *const FfiCallContext, request: *const c_void, len: usize, response_len: *mut usize) -> *const u8;
     fn ghostplane_cleanup() -> c_int;
+    fn ghostplane_transfer_begin(manifest: *const TransferManifest) -> u64;
+    fn ghostplane_transfer_append(transfer_id: u64, index: u32, data: *const c_void, len: usize, checksum: u32) -> c_int;
+    fn ghostplane_transfer_commit(transfer_id: u64) -> c_int;
+    fn ghostplane_transfer_abort(transfer_id: u64);
+    fn ghostplane_attach_ring(path: *const c_char, capacity: u64) -> c_int;
 }
 
 /// Low-level FFI interface (unsafe, for internal use only)
@@ -173,18 +700,74 @@ pub mod low_level {
     }
 
     /// Submit transaction to GhostPlane via FFI (unsafe)
-    pub unsafe fn submit_transaction_raw(data: &[u8]) -> Result<String> {
-        let result_ptr = unsafe { ghostplane_submit_tx(data.as_ptr() as *const c_void, data.len()) };
+    pub unsafe fn submit_transaction_raw(context: &FfiContext, data: &[u8]) -> Result<String> {
+        let raw = context.as_raw();
+        let result_ptr = unsafe { ghostplane_submit_tx(&raw, data.as_ptr() as *const c_void, data.len()) };
         unsafe { ffi_helpers::c_to_rust_string(result_ptr) }
     }
 
     /// Query GhostPlane state via FFI (unsafe)
-    pub unsafe fn query_state_raw(query: &str) -> Result<String> {
+    pub unsafe fn query_state_raw(context: &FfiContext, query: &str) -> Result<String> {
+        let raw = context.as_raw();
         let c_query = ffi_helpers::rust_to_c_string(query)?;
-        let result_ptr = unsafe { ghostplane_query_state(c_query.as_ptr()) };
+        let result_ptr = unsafe { ghostplane_query_state(&raw, c_query.as_ptr()) };
         unsafe { ffi_helpers::c_to_rust_string(result_ptr) }
     }
 
+    /// Read L2 account state via FFI; request and response are protobuf-encoded (unsafe)
+    pub unsafe fn get_state_raw(context: &FfiContext, request: &[u8]) -> Result<Vec<u8>> {
+        let raw = context.as_raw();
+        let mut response_len = 0usize;
+        let response = unsafe { ghostplane_get_state(&raw, request.as_ptr() as *const c_void, request.len(), &mut response_len) };
+        unsafe { ffi_helpers::c_buffer_to_bytes(response, response_len) }
+    }
+
+    /// Read an L2 storage slot via FFI; request and response are protobuf-encoded (unsafe)
+    pub unsafe fn query_storage_raw(context: &FfiContext, request: &[u8]) -> Result<Vec<u8>> {
+        let raw = context.as_raw();
+        let mut response_len = 0usize;
+        let response = unsafe { ghostplane_query_storage(&raw, request.as_ptr() as *const c_void, request.len(), &mut response_len) };
+        unsafe { ffi_helpers::c_buffer_to_bytes(response, response_len) }
+    }
+
+    /// Send a chunked payload via begin/append/commit FFI calls (unsafe)
+    ///
+    /// The transfer is aborted on the Zig side if any append fails.
+    pub unsafe fn transfer_chunked(payload: &ChunkedPayload) -> Result<()> {
+        let transfer_id = unsafe { ghostplane_transfer_begin(&payload.manifest) };
+        if transfer_id == 0 {
+            return Err(EtherlinkError::Ffi("GhostPlane transfer begin failed".to_string()));
+        }
+
+        for chunk in &payload.chunks {
+            let result = unsafe {
+                ghostplane_transfer_append(transfer_id, chunk.index, chunk.data.as_ptr() as *const c_void, chunk.data.len(), chunk.checksum)
+            };
+            if result != 0 {
+                unsafe { ghostplane_transfer_abort(transfer_id) };
+                return Err(EtherlinkError::Ffi(format!("GhostPlane transfer append of chunk {} failed with code: {}", chunk.index, result)));
+            }
+        }
+
+        let result = unsafe { ghostplane_transfer_commit(transfer_id) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(EtherlinkError::Ffi(format!("GhostPlane transfer commit failed with code: {}", result)))
+        }
+    }
+
+    /// Hand a shared-memory ring to GhostPlane to consume from (unsafe)
+    pub unsafe fn attach_ring_raw(ring: &SharedRing) -> Result<()> {
+        let c_path = ffi_helpers::rust_to_c_string(&ring.path().to_string_lossy())?;
+        let result = unsafe { ghostplane_attach_ring(c_path.as_ptr(), ring.capacity() as u64) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(EtherlinkError::Ffi(format!("GhostPlane ring attach failed with code: {}", result)))
+        }
+    }
+
     /// Cleanup GhostPlane via FFI (unsafe)
     pub unsafe fn cleanup_ghostplane() -> Result<()> {
         let result = unsafe { ghostplane_cleanup() };
@@ -1,15 +1,40 @@
-use crate::{ffi::ZigBridge, EtherlinkError, Result, Address, TxHash, BlockHeight};
+use crate::{ffi::{BridgeLink, ChunkingConfig, ZigBridge}, EtherlinkError, Result, Address, TxHash, BlockHeight};
+use crate::clients::GhostdClient;
+use crate::clock::{self, SharedClock};
+use crate::events::{EtherlinkEvent, EventBus};
+use crate::confirmation::{ConfirmationPolicies, OperationClass};
+use crate::hash::{self, HashAlgorithm, HashDomain, Hasher};
+use crate::invariants;
+use crate::merkle::{merkle_root, MerkleProof};
+use crate::resubmit::{self, ResubmitPolicy, SubmissionAttempt};
+use crate::proto::ghostplane::v1 as ghostplane_pb;
+use crate::shm::SharedMemoryConfig;
+use crate::txpool::{BatchPolicy, TxPool, TxPoolConfig, TxPoolMetrics};
+use crate::validation::{ConfigErrors, Validator};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
 /// GhostPlane L2 client for high-performance Zig-based execution
-#[derive(Debug)]
+///
+/// A cheap handle: clones share the same bridge and L2 state, so one client can be
+/// handed to any number of tasks.
+#[derive(Debug, Clone)]
 pub struct GhostPlaneClient {
-    bridge: ZigBridge,
+    inner: Arc<GhostPlaneShared>,
+}
+
+/// Bridge and L2 state behind every handle of one [`GhostPlaneClient`]
+#[derive(Debug)]
+struct GhostPlaneShared {
+    bridge: RwLock<ZigBridge>,
+    link: BridgeLink,
     config: GhostPlaneConfig,
     state: RwLock<GhostPlaneState>,
+    clock: SharedClock,
+    events: Option<EventBus>,
 }
 
 /// Configuration for GhostPlane L2
@@ -18,8 +43,30 @@ pub struct GhostPlaneConfig {
     pub endpoint: String,
     pub chain_id: u64,
     pub batch_size: usize,
+    /// Gas, size and age limits applied on top of `batch_size`
+    #[serde(default)]
+    pub batch_policy: BatchPolicy,
     pub finalization_timeout_ms: u64,
     pub enable_zk_proofs: bool,
+    /// Local fee market ordering transactions into batches
+    #[serde(default)]
+    pub pool: TxPoolConfig,
+    /// Splitting and compression of large payloads sent through the bridge
+    #[serde(default)]
+    pub chunking: ChunkingConfig,
+    /// Submit through a shared-memory ring instead of one FFI call per transaction
+    #[serde(default)]
+    pub shared_memory: SharedMemoryConfig,
+    /// Batch finality awaited per operation class by [`GhostPlaneClient::wait_for_l2_confirmation`]
+    #[serde(default)]
+    pub confirmations: ConfirmationPolicies,
+    /// Nonce refreshes and fee bumps for [`GhostPlaneClient::submit_with_resubmission`]
+    #[serde(default)]
+    pub resubmit: ResubmitPolicy,
+    /// L1 address that posts batch commitments; [`GhostPlaneClient::verify_l2_inclusion`]
+    /// ignores commitments sent from anywhere else and refuses to run without it
+    #[serde(default)]
+    pub sequencer_address: Option<Address>,
 }
 
 impl Default for GhostPlaneConfig {
@@ -28,9 +75,37 @@ impl Default for GhostPlaneConfig {
             endpoint: "localhost:9090".to_string(),
             chain_id: 1337,
             batch_size: 1000,
+            batch_policy: BatchPolicy::default(),
             finalization_timeout_ms: 30000,
             enable_zk_proofs: true,
+            pool: TxPoolConfig::default(),
+            chunking: ChunkingConfig::default(),
+            shared_memory: SharedMemoryConfig::default(),
+            confirmations: ConfirmationPolicies::default(),
+            resubmit: ResubmitPolicy::default(),
+            sequencer_address: None,
+        }
+    }
+}
+
+impl GhostPlaneConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.endpoint("endpoint", &self.endpoint, &["http", "https", "unix", "npipe"], false, true);
+        v.check(self.chain_id != 0, "chain_id", "must be non-zero");
+        v.check(self.batch_size > 0, "batch_size", "must be greater than zero");
+        v.nested("batch_policy", self.batch_policy.validate());
+        v.timeout("finalization_timeout_ms", self.finalization_timeout_ms);
+        v.nested("pool", self.pool.validate());
+        v.nested("chunking", self.chunking.validate());
+        v.nested("shared_memory", self.shared_memory.validate());
+        v.nested("confirmations", self.confirmations.validate());
+        v.nested("resubmit", self.resubmit.validate());
+        if let Some(sequencer) = &self.sequencer_address {
+            v.check(!sequencer.as_str().trim().is_empty(), "sequencer_address", "must not be empty");
         }
+        v.finish()
     }
 }
 
@@ -41,21 +116,37 @@ pub struct GhostPlaneState {
     pub pending_transactions: HashMap<TxHash, L2Transaction>,
     pub finalized_batches: Vec<BatchInfo>,
     pub total_transactions: u64,
+    /// Priority ordering of `pending_transactions` for the next batch
+    pub pool: TxPool,
 }
 
-impl Default for GhostPlaneState {
-    fn default() -> Self {
+impl GhostPlaneState {
+    /// Create an empty state whose pool uses the given configuration
+    pub fn with_pool(config: TxPoolConfig) -> Self {
+        Self::with_pool_and_clock(config, clock::system())
+    }
+
+    /// Create an empty state whose pool ages transactions by `clock`
+    pub fn with_pool_and_clock(config: TxPoolConfig, clock: SharedClock) -> Self {
         Self {
             current_block: 0,
             pending_transactions: HashMap::new(),
             finalized_batches: Vec::new(),
             total_transactions: 0,
+            pool: TxPool::new(config).with_clock(clock),
         }
     }
 }
 
+impl Default for GhostPlaneState {
+    fn default() -> Self {
+        Self::with_pool(TxPoolConfig::default())
+    }
+}
+
 /// Layer 2 transaction structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub struct L2Transaction {
     pub from: Address,
     pub to: Address,
@@ -78,6 +169,90 @@ pub struct BatchInfo {
     pub finalized_at: u64,
 }
 
+impl BatchInfo {
+    /// What is published on L1 for this batch
+    pub fn commitment(&self) -> BatchCommitment {
+        BatchCommitment { batch_id: self.batch_id.clone(), merkle_root: self.merkle_root.clone() }
+    }
+
+    /// Merkle proof that `tx_hash` is part of this batch
+    pub fn inclusion_proof(&self, tx_hash: &TxHash) -> Option<MerkleProof> {
+        let index = self.transactions.iter().position(|hash| hash == tx_hash)?;
+        MerkleProof::build(&self.transactions, index)
+    }
+}
+
+/// A batch's Merkle root as committed on L1, carried in the `data` of an L1 transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchCommitment {
+    pub batch_id: String,
+    pub merkle_root: String,
+}
+
+impl BatchCommitment {
+    /// Encoding placed in the L1 transaction's `data`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    /// Decode a commitment from L1 transaction data, if it holds one
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        serde_json::from_slice(data).ok()
+    }
+
+    /// Hash identifying the commitment, recorded as the batch's `l1_commitment_hash`
+    pub fn hash(&self) -> String {
+        hash::to_hex(&Hasher::domain_digest(HashAlgorithm::Sha256, HashDomain::Batch, self.to_bytes()))
+    }
+}
+
+/// Outcome of checking an L2 transaction against its batch's L1 commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InclusionStatus {
+    /// The proof links the transaction to the root committed on L1
+    Verified,
+    /// The transaction is not part of the batch
+    NotInBatch,
+    /// The batch's transactions do not hash to its claimed root
+    RootMismatch,
+    /// The L1 block holds no commitment for the batch from the sequencer
+    CommitmentMissing,
+    /// The root committed on L1 differs from the batch's
+    CommitmentMismatch,
+}
+
+/// Report of [`GhostPlaneClient::verify_l2_inclusion`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionReport {
+    pub tx_hash: TxHash,
+    pub batch_id: String,
+    pub l1_block: BlockHeight,
+    pub status: InclusionStatus,
+    pub proof: Option<MerkleProof>,
+    /// Root implied by the proof
+    pub computed_root: Option<String>,
+    /// Root found in the L1 commitment
+    pub committed_root: Option<String>,
+}
+
+impl InclusionReport {
+    pub fn is_verified(&self) -> bool {
+        self.status == InclusionStatus::Verified
+    }
+}
+
+/// An L2 account's state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L2AccountState {
+    pub address: Address,
+    pub balance: u64,
+    pub nonce: u64,
+    /// Contract bytecode, empty for plain accounts
+    pub code: Vec<u8>,
+    /// Requested storage slots by key
+    pub storage: HashMap<String, Vec<u8>>,
+}
+
 /// Transaction execution result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct L2ExecutionResult {
@@ -92,10 +267,25 @@ pub struct L2ExecutionResult {
 impl GhostPlaneClient {
     /// Create a new GhostPlane client
     pub fn new(config: GhostPlaneConfig) -> Self {
+        Self::with_clock(config, clock::system())
+    }
+
+    /// Create a client that ages batches and timestamps finalization by `clock`
+    pub fn with_clock(config: GhostPlaneConfig, clock: SharedClock) -> Self {
+        Self::assemble(config, clock, None)
+    }
+
+    fn assemble(config: GhostPlaneConfig, clock: SharedClock, events: Option<EventBus>) -> Self {
+        let bridge = ZigBridge::with_chunking(config.chunking.clone());
         Self {
-            bridge: ZigBridge::new(),
-            config,
-            state: RwLock::new(GhostPlaneState::default()),
+            inner: Arc::new(GhostPlaneShared {
+                link: bridge.link(),
+                bridge: RwLock::new(bridge),
+                state: RwLock::new(GhostPlaneState::with_pool_and_clock(config.pool.clone(), clock.clone())),
+                config,
+                clock,
+                events,
+            }),
         }
     }
 
@@ -105,44 +295,144 @@ impl GhostPlaneClient {
     }
 
     /// Initialize the GhostPlane client and Zig bridge
-    pub async fn initialize(&mut self) -> Result<()> {
+    pub async fn initialize(&self) -> Result<()> {
         info!("Initializing GhostPlane client");
 
-        self.bridge.initialize()?;
+        {
+            let mut bridge = self.inner.bridge.write().await;
+            bridge.initialize()?;
+            if self.inner.config.shared_memory.enabled {
+                bridge.attach_shared_memory(&self.inner.config.shared_memory)?;
+            }
+        }
 
         // Initialize L2 state
         {
-            let mut state = self.state.write().await;
-            *state = GhostPlaneState::default();
+            let mut state = self.inner.state.write().await;
+            *state = GhostPlaneState::with_pool_and_clock(self.inner.config.pool.clone(), self.inner.clock.clone());
         }
 
         info!("GhostPlane client initialized successfully");
         Ok(())
     }
 
+    /// Record bridge calls to, or replay them from, a cassette
+    pub async fn set_cassette(&self, cassette: Arc<crate::cassette::Cassette>) {
+        self.inner.bridge.write().await.set_cassette(cassette);
+    }
+
+    /// Liveness of the Zig bridge, for supervision
+    pub fn bridge_link(&self) -> BridgeLink {
+        self.inner.link.clone()
+    }
+
+    /// Reinitialize a crashed bridge and resubmit the transactions it had accepted
+    ///
+    /// Pending transactions are replayed in nonce order; returns how many were resubmitted.
+    /// The bridge is only marked up once the replay is done, so new submissions cannot
+    /// overtake it; if the replay fails, the bridge is left down for another attempt.
+    pub async fn recover_bridge(&self) -> Result<usize> {
+        let bridge = self.inner.bridge.read().await;
+        bridge.restart()?;
+
+        let mut pending: Vec<L2Transaction> = self.inner.state.read().await.pending_transactions.values().cloned().collect();
+        pending.sort_by_key(|tx| tx.nonce);
+        for tx in &pending {
+            bridge.replay_ghostplane_transaction(&serde_json::to_vec(tx)?).await?;
+        }
+        bridge.link().mark_up();
+
+        if !pending.is_empty() {
+            info!("Replayed {} pending L2 transactions after bridge recovery", pending.len());
+        }
+        Ok(pending.len())
+    }
+
     /// Submit a transaction to GhostPlane L2
+    ///
+    /// Its batch priority follows from its gas price; fails with
+    /// [`EtherlinkError::TxPool`] if the local pool will not take it, or with the
+    /// retriable [`EtherlinkError::BridgeDown`] while the bridge is being recovered.
     pub async fn submit_transaction(&self, tx: L2Transaction) -> Result<TxHash> {
+        self.submit(tx, None).await
+    }
+
+    /// Submit a transaction in an explicit priority lane (0 is the lowest)
+    pub async fn submit_transaction_with_priority(&self, tx: L2Transaction, priority: usize) -> Result<TxHash> {
+        self.submit(tx, Some(priority)).await
+    }
+
+    async fn submit(&self, tx: L2Transaction, priority: Option<usize>) -> Result<TxHash> {
         debug!("Submitting L2 transaction from {} to {}", tx.from, tx.to);
+        self.inner.config.batch_policy.check(&tx)?;
+        self.inner.state.read().await.pool.check(&tx, priority)?;
 
         // Serialize transaction for Zig
         let tx_bytes = serde_json::to_vec(&tx)
             .map_err(|e| EtherlinkError::Serialization(e))?;
 
         // Submit via FFI bridge
-        let tx_hash_str = self.bridge.submit_ghostplane_transaction(&tx_bytes).await?;
+        let tx_hash_str = self.inner.bridge.read().await.submit_ghostplane_transaction(&tx_bytes).await?;
         let tx_hash = TxHash::new(tx_hash_str);
 
-        // Update local state
+        // Update local state; the pool is re-checked since other submissions may have filled it.
+        // The bridge has already accepted the transaction, so a failure here is only logged:
+        // returning an error would invite the caller to submit it again.
         {
-            let mut state = self.state.write().await;
+            let mut state = self.inner.state.write().await;
+            match state.pool.insert(tx_hash.clone(), tx.clone(), priority) {
+                Ok(Some(evicted)) => {
+                    warn!("Evicted underpriced L2 transaction {}", evicted.as_str());
+                    state.pending_transactions.remove(&evicted);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("L2 transaction {} was accepted but is not in the local pool: {}", tx_hash.as_str(), e),
+            }
             state.pending_transactions.insert(tx_hash.clone(), tx);
-            state.total_transactions += 1;
+            match invariants::add(state.total_transactions, 1, "L2 transaction count") {
+                Ok(total_transactions) => state.total_transactions = total_transactions,
+                Err(e) => warn!("L2 transaction {} was accepted but not counted: {}", tx_hash.as_str(), e),
+            }
         }
 
         debug!("L2 transaction submitted with hash: {}", tx_hash.as_str());
         Ok(tx_hash)
     }
 
+    /// Submit, re-signing with `sign` and resubmitting after a stale nonce or low fee
+    ///
+    /// A fresh nonce is read with [`GhostPlaneClient::get_l2_nonce`], unless the same
+    /// transaction at the stale nonce is already pending, in which case its hash is
+    /// returned; the gas price is bumped within the configured [`ResubmitPolicy`].
+    /// Returns the accepted hash and every attempt made.
+    pub async fn submit_with_resubmission<F>(&self, tx: L2Transaction, sign: F) -> Result<(TxHash, Vec<SubmissionAttempt>)>
+    where
+        F: Fn(&mut L2Transaction) -> Result<()>,
+    {
+        let (nonce, gas_price, from) = (tx.nonce, tx.gas_price, tx.from.clone());
+        let (tx, sign, from) = (&tx, &sign, &from);
+        let (result, attempts) = resubmit::submit_with_resubmission(
+            &self.inner.config.resubmit,
+            nonce,
+            gas_price,
+            move |nonce, gas_price| async move {
+                let mut tx = L2Transaction { nonce, gas_price, ..tx.clone() };
+                sign(&mut tx)?;
+                self.submit_transaction(tx).await
+            },
+            move |nonce, gas_price| async move {
+                let state = self.inner.state.read().await;
+                Ok(state.pending_transactions.iter().find_map(|(hash, pending)| {
+                    let same = pending.from == tx.from && pending.to == tx.to && pending.value == tx.value && pending.data == tx.data;
+                    (same && pending.nonce == nonce && pending.gas_price == gas_price).then(|| hash.clone())
+                }))
+            },
+            move || self.get_l2_nonce(from),
+        )
+        .await;
+        Ok((result?, attempts))
+    }
+
     /// Execute a transaction on GhostPlane and get the result
     pub async fn execute_transaction(&self, tx: L2Transaction) -> Result<L2ExecutionResult> {
         let tx_hash = self.submit_transaction(tx).await?;
@@ -161,7 +451,7 @@ impl GhostPlaneClient {
 
     /// Get transaction status
     pub async fn get_transaction_status(&self, tx_hash: &TxHash) -> Result<Option<L2ExecutionResult>> {
-        let state = self.state.read().await;
+        let state = self.inner.state.read().await;
 
         if state.pending_transactions.contains_key(tx_hash) {
             // Transaction is pending
@@ -172,18 +462,29 @@ impl GhostPlaneClient {
         }
     }
 
-    /// Create a batch of pending transactions
+    /// Whether enough is pending, or has waited long enough, to create a batch
+    pub async fn batch_due(&self) -> bool {
+        self.inner.state.read().await.pool.batch_due(self.inner.config.batch_size, &self.inner.config.batch_policy)
+    }
+
+    /// Create a batch of pending transactions, highest priority first, within
+    /// `batch_size` and the batch policy
     pub async fn create_batch(&self) -> Result<BatchInfo> {
-        let mut state = self.state.write().await;
+        let mut state = self.inner.state.write().await;
 
-        let pending_txs: Vec<TxHash> = state.pending_transactions.keys().cloned().collect();
+        let pending_txs: Vec<TxHash> = state
+            .pool
+            .take_batch(self.inner.config.batch_size, &self.inner.config.batch_policy)
+            .into_iter()
+            .map(|(tx_hash, _)| tx_hash)
+            .collect();
 
         if pending_txs.is_empty() {
             return Err(EtherlinkError::General(anyhow::anyhow!("No pending transactions for batch")));
         }
 
         let batch_id = uuid::Uuid::new_v4().to_string();
-        let merkle_root = self.calculate_merkle_root(&pending_txs).await?;
+        let merkle_root = merkle_root(&pending_txs);
 
         let batch = BatchInfo {
             batch_id,
@@ -205,7 +506,7 @@ impl GhostPlaneClient {
 
     /// Generate ZK proof for a batch (via Zig)
     pub async fn generate_batch_proof(&self, batch: &BatchInfo) -> Result<Vec<u8>> {
-        if !self.config.enable_zk_proofs {
+        if !self.inner.config.enable_zk_proofs {
             warn!("ZK proofs disabled in configuration");
             return Ok(Vec::new());
         }
@@ -220,64 +521,183 @@ impl GhostPlaneClient {
     /// Submit batch to L1 for finalization
     pub async fn finalize_batch(&self, mut batch: BatchInfo, proof: Vec<u8>) -> Result<String> {
         batch.zk_proof = Some(proof);
-        batch.finalized_at = chrono::Utc::now().timestamp() as u64;
+        batch.finalized_at = self.inner.clock.now();
 
-        // TODO: Submit to L1 via bridge
-        let l1_commitment = format!("0x{}", hex::encode(&batch.batch_id));
+        // TODO: Submit the commitment to L1 via bridge
+        let l1_commitment = batch.commitment().hash();
         batch.l1_commitment_hash = Some(l1_commitment.clone());
 
+        let finalized = EtherlinkEvent::BatchFinalized {
+            batch_id: batch.batch_id.clone(),
+            transactions: batch.transactions.len(),
+            l1_commitment: l1_commitment.clone(),
+        };
+
         // Update state
         {
-            let mut state = self.state.write().await;
+            let mut state = self.inner.state.write().await;
+            state.current_block = invariants::add(state.current_block, 1, "L2 block height")?;
             state.finalized_batches.push(batch);
-            state.current_block += 1;
+        }
+
+        if let Some(events) = &self.inner.events {
+            events.publish(finalized);
         }
 
         info!("Batch finalized with L1 commitment: {}", l1_commitment);
         Ok(l1_commitment)
     }
 
+    /// Verify that an L2 transaction is part of a batch committed in L1 block `l1_block`
+    ///
+    /// The transaction's Merkle proof is checked against the batch root, and that root
+    /// against the commitment read from the L1 block via `ghostd`, so a deposit can be
+    /// credited without trusting the batch's claims. Only commitments sent by the
+    /// configured [`GhostPlaneConfig::sequencer_address`] count, since anyone can put
+    /// commitment-shaped data in an L1 transaction. Failed checks are reported in the
+    /// status; errors are reserved for a missing sequencer address or failing to read
+    /// the L1 block.
+    pub async fn verify_l2_inclusion(&self, ghostd: &GhostdClient, tx_hash: &TxHash, batch: &BatchInfo, l1_block: BlockHeight) -> Result<InclusionReport> {
+        let Some(sequencer) = &self.inner.config.sequencer_address else {
+            return Err(EtherlinkError::Configuration(
+                "Verifying L2 inclusion needs the sequencer address that posts batch commitments".to_string(),
+            ));
+        };
+        let mut report = InclusionReport {
+            tx_hash: tx_hash.clone(),
+            batch_id: batch.batch_id.clone(),
+            l1_block,
+            status: InclusionStatus::NotInBatch,
+            proof: batch.inclusion_proof(tx_hash),
+            computed_root: None,
+            committed_root: None,
+        };
+        let Some(proof) = &report.proof else {
+            return Ok(report);
+        };
+        report.computed_root = proof.compute_root(tx_hash);
+        if report.computed_root.as_deref() != Some(batch.merkle_root.as_str()) {
+            report.status = InclusionStatus::RootMismatch;
+            return Ok(report);
+        }
+
+        let block = ghostd.get_block(l1_block).await?;
+        report.committed_root = block
+            .transactions
+            .iter()
+            .filter(|tx| tx.from.as_str().eq_ignore_ascii_case(sequencer.as_str()))
+            .filter_map(|tx| tx.data.as_deref().and_then(BatchCommitment::from_bytes))
+            .find(|commitment| commitment.batch_id == batch.batch_id)
+            .map(|commitment| commitment.merkle_root);
+        report.status = match &report.committed_root {
+            None => InclusionStatus::CommitmentMissing,
+            Some(root) if proof.verify(tx_hash, root) => InclusionStatus::Verified,
+            Some(_) => InclusionStatus::CommitmentMismatch,
+        };
+        debug!("L2 inclusion of {} in batch {}: {:?}", tx_hash.as_str(), batch.batch_id, report.status);
+        Ok(report)
+    }
+
+    /// Wait until an L2 transaction's batch meets the configured policy for `class`
+    ///
+    /// Returns the finalized batch holding the transaction, or `None` straight away
+    /// if the policy does not ask for L2 finalization.
+    pub async fn wait_for_l2_confirmation(&self, tx_hash: &TxHash, class: OperationClass) -> Result<Option<BatchInfo>> {
+        let policy = self.inner.config.confirmations.policy(class);
+        if !policy.l2_finalized && !policy.zk_verified {
+            return Ok(None);
+        }
+
+        let (poll_interval, timeout) = (self.inner.config.confirmations.poll_interval(), self.inner.config.confirmations.timeout());
+        let started = tokio::time::Instant::now();
+        loop {
+            let batch = {
+                let state = self.inner.state.read().await;
+                state
+                    .finalized_batches
+                    .iter()
+                    .find(|batch| batch.transactions.contains(tx_hash))
+                    .filter(|batch| !policy.zk_verified || batch.zk_proof.as_ref().is_some_and(|proof| !proof.is_empty()))
+                    .cloned()
+            };
+            if batch.is_some() {
+                return Ok(batch);
+            }
+            if started.elapsed() + poll_interval > timeout {
+                let requirement = if policy.zk_verified { "ZK-verified" } else { "finalized" };
+                return Err(EtherlinkError::Timeout(format!("L2 transaction {} not {} after {:?}", tx_hash.as_str(), requirement, timeout)));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
     /// Get current L2 state information
     pub async fn get_state_info(&self) -> GhostPlaneState {
-        self.state.read().await.clone()
+        self.inner.state.read().await.clone()
     }
 
-    /// Query L2 state via Zig bridge
+    /// Run a free-form L2 state query via Zig bridge
+    ///
+    /// Account state has typed queries, e.g. [`GhostPlaneClient::get_l2_balance`].
     pub async fn query_state(&self, query: &str) -> Result<String> {
         debug!("Querying GhostPlane state: {}", query);
-        self.bridge.query_ghostplane_state(query).await
+        self.inner.bridge.read().await.query_ghostplane_state(query).await
     }
 
-    /// Get pending transaction count
-    pub async fn pending_transaction_count(&self) -> usize {
-        self.state.read().await.pending_transactions.len()
+    /// Get an L2 account's balance, nonce and code, plus the given storage slots
+    pub async fn get_l2_account(&self, address: &Address, storage_keys: &[String]) -> Result<L2AccountState> {
+        let request = ghostplane_pb::GetL2StateRequest { address: address.as_str().to_string(), storage_keys: storage_keys.to_vec() };
+        let response = self.inner.bridge.read().await.get_l2_state(&request).await?;
+        if !response.address.is_empty() && response.address != address.as_str() {
+            return Err(EtherlinkError::Ffi(format!("State query for {} answered for {}", address, response.address)));
+        }
+        Ok(L2AccountState { address: address.clone(), ..response.into() })
     }
 
-    /// Get total transaction count
-    pub async fn total_transaction_count(&self) -> u64 {
-        self.state.read().await.total_transactions
+    /// Get an L2 account's balance
+    pub async fn get_l2_balance(&self, address: &Address) -> Result<u64> {
+        Ok(self.get_l2_account(address, &[]).await?.balance)
+    }
+
+    /// Get an L2 account's nonce, i.e. its number of executed transactions
+    pub async fn get_l2_nonce(&self, address: &Address) -> Result<u64> {
+        Ok(self.get_l2_account(address, &[]).await?.nonce)
     }
 
-    /// Calculate merkle root for transactions (placeholder implementation)
-    async fn calculate_merkle_root(&self, tx_hashes: &[TxHash]) -> Result<String> {
-        // TODO: Implement proper merkle tree calculation
-        let combined = tx_hashes.iter()
-            .map(|h| h.as_str())
-            .collect::<Vec<_>>()
-            .join("");
+    /// Get an L2 contract's bytecode; empty for plain accounts
+    pub async fn get_l2_code(&self, address: &Address) -> Result<Vec<u8>> {
+        Ok(self.get_l2_account(address, &[]).await?.code)
+    }
 
-        use sha2::{Sha256, Digest};
-        let mut hasher = Sha256::new();
-        hasher.update(combined.as_bytes());
-        let result = hasher.finalize();
+    /// Get an L2 contract storage slot at the latest block; empty if unset
+    pub async fn get_l2_storage(&self, address: &Address, key: &str) -> Result<Vec<u8>> {
+        let request = ghostplane_pb::QueryL2StorageRequest {
+            contract_address: address.as_str().to_string(),
+            storage_key: key.to_string(),
+            block_number: 0,
+        };
+        Ok(self.inner.bridge.read().await.query_l2_storage(&request).await?.value)
+    }
 
-        Ok(format!("0x{}", hex::encode(result)))
+    /// Get pending transaction count
+    pub async fn pending_transaction_count(&self) -> usize {
+        self.inner.state.read().await.pending_transactions.len()
+    }
+
+    /// Get queue depth and wait time metrics for the local fee market
+    pub async fn pool_metrics(&self) -> TxPoolMetrics {
+        self.inner.state.read().await.pool.metrics()
+    }
+
+    /// Get total transaction count
+    pub async fn total_transaction_count(&self) -> u64 {
+        self.inner.state.read().await.total_transactions
     }
 
     /// Shutdown the GhostPlane client
-    pub async fn shutdown(&mut self) -> Result<()> {
+    pub async fn shutdown(&self) -> Result<()> {
         info!("Shutting down GhostPlane client");
-        self.bridge.shutdown()?;
+        self.inner.bridge.write().await.shutdown()?;
         Ok(())
     }
 }
@@ -291,20 +711,35 @@ impl Default for GhostPlaneClient {
 /// Builder for GhostPlane client
 pub struct GhostPlaneClientBuilder {
     config: GhostPlaneConfig,
+    clock: SharedClock,
+    events: Option<EventBus>,
 }
 
 impl GhostPlaneClientBuilder {
     pub fn new() -> Self {
         Self {
             config: GhostPlaneConfig::default(),
+            clock: clock::system(),
+            events: None,
         }
     }
 
+    /// Start from a complete configuration
+    pub fn from_config(config: GhostPlaneConfig) -> Self {
+        Self { config, ..Self::new() }
+    }
+
     pub fn endpoint<S: Into<String>>(mut self, endpoint: S) -> Self {
         self.config.endpoint = endpoint.into();
         self
     }
 
+    /// L1 address whose batch commitments are trusted
+    pub fn sequencer_address(mut self, address: Address) -> Self {
+        self.config.sequencer_address = Some(address);
+        self
+    }
+
     pub fn chain_id(mut self, chain_id: u64) -> Self {
         self.config.chain_id = chain_id;
         self
@@ -325,8 +760,44 @@ impl GhostPlaneClientBuilder {
         self
     }
 
+    pub fn batch_policy(mut self, policy: BatchPolicy) -> Self {
+        self.config.batch_policy = policy;
+        self
+    }
+
+    pub fn pool(mut self, pool: TxPoolConfig) -> Self {
+        self.config.pool = pool;
+        self
+    }
+
+    pub fn chunking(mut self, chunking: ChunkingConfig) -> Self {
+        self.config.chunking = chunking;
+        self
+    }
+
+    pub fn shared_memory(mut self, shared_memory: SharedMemoryConfig) -> Self {
+        self.config.shared_memory = shared_memory;
+        self
+    }
+
+    pub fn confirmations(mut self, confirmations: ConfirmationPolicies) -> Self {
+        self.config.confirmations = confirmations;
+        self
+    }
+
+    pub fn clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Publish [`EtherlinkEvent::BatchFinalized`] to `events`
+    pub fn events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
     pub fn build(self) -> GhostPlaneClient {
-        GhostPlaneClient::new(self.config)
+        GhostPlaneClient::assemble(self.config, self.clock, self.events)
     }
 }
 
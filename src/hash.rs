@@ -0,0 +1,141 @@
+//! Hashing with optional domain separation
+//!
+//! [`Hasher`] wraps SHA-256, Keccak-256 and BLAKE3 behind one interface. A hasher
+//! created with [`Hasher::with_domain`] first absorbs a length-prefixed tag for the
+//! message type, so a transaction hash can never collide with, say, a batch
+//! commitment over the same bytes. Untagged hashers are for formats fixed by someone
+//! else (ECDSA message digests, addresses, HTTP body hashes).
+
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+/// 32-byte digest
+pub type Hash = [u8; 32];
+
+/// Hash function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha256,
+    Keccak256,
+    Blake3,
+}
+
+/// Message type a domain-separated hash commits to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashDomain {
+    /// Layer 1 and layer 2 transactions
+    Tx,
+    /// GhostPlane batch commitments
+    Batch,
+    /// CNS domain records and ownership
+    Domain,
+    /// Authentication tokens and payloads
+    Auth,
+    /// Chunked blob manifests in storage
+    Blob,
+    /// Off-chain orderbook orders and cancellations
+    Order,
+    /// Private keys of the public development accounts
+    Dev,
+}
+
+impl HashDomain {
+    /// Tag absorbed before the message
+    pub fn tag(&self) -> &'static str {
+        match self {
+            HashDomain::Tx => "etherlink/tx/v1",
+            HashDomain::Batch => "etherlink/batch/v1",
+            HashDomain::Domain => "etherlink/domain/v1",
+            HashDomain::Auth => "etherlink/auth/v1",
+            HashDomain::Blob => "etherlink/blob/v1",
+            HashDomain::Order => "etherlink/order/v1",
+            HashDomain::Dev => "etherlink/dev/v1",
+        }
+    }
+}
+
+#[derive(Clone)]
+enum State {
+    Sha256(sha2::Sha256),
+    Keccak256(Box<sha3::Keccak256>),
+    Blake3(Box<blake3::Hasher>),
+}
+
+/// Incremental hasher over one of the supported algorithms
+#[derive(Clone)]
+pub struct Hasher {
+    algorithm: HashAlgorithm,
+    state: State,
+}
+
+impl Hasher {
+    /// Plain hasher with no domain tag
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        let state = match algorithm {
+            HashAlgorithm::Sha256 => State::Sha256(sha2::Sha256::new()),
+            HashAlgorithm::Keccak256 => State::Keccak256(Box::new(sha3::Keccak256::new())),
+            HashAlgorithm::Blake3 => State::Blake3(Box::new(blake3::Hasher::new())),
+        };
+        Self { algorithm, state }
+    }
+
+    /// Hasher that starts with the `u32` big-endian length and bytes of `domain`'s tag
+    pub fn with_domain(algorithm: HashAlgorithm, domain: HashDomain) -> Self {
+        let tag = domain.tag().as_bytes();
+        let mut hasher = Self::new(algorithm);
+        hasher.update((tag.len() as u32).to_be_bytes());
+        hasher.update(tag);
+        hasher
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn update(&mut self, data: impl AsRef<[u8]>) -> &mut Self {
+        let data = data.as_ref();
+        match &mut self.state {
+            State::Sha256(hasher) => hasher.update(data),
+            State::Keccak256(hasher) => hasher.update(data),
+            State::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+        self
+    }
+
+    pub fn finalize(self) -> Hash {
+        match self.state {
+            State::Sha256(hasher) => hasher.finalize().into(),
+            State::Keccak256(hasher) => hasher.finalize().into(),
+            State::Blake3(hasher) => hasher.finalize().into(),
+        }
+    }
+
+    /// Untagged hash of `data`
+    pub fn digest(algorithm: HashAlgorithm, data: impl AsRef<[u8]>) -> Hash {
+        let mut hasher = Self::new(algorithm);
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    /// Hash of `data` tagged with `domain`
+    pub fn domain_digest(algorithm: HashAlgorithm, domain: HashDomain, data: impl AsRef<[u8]>) -> Hash {
+        let mut hasher = Self::with_domain(algorithm, domain);
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+impl std::fmt::Debug for Hasher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hasher").field("algorithm", &self.algorithm).finish_non_exhaustive()
+    }
+}
+
+/// `0x`-prefixed hex of a digest
+pub fn to_hex(hash: &Hash) -> String {
+    format!("0x{}", hex::encode(hash))
+}
@@ -0,0 +1,158 @@
+//! Internationalized domain names and homograph detection
+//!
+//! CNS names are stored in their ASCII form: [`to_ascii`] applies UTS-46 mapping
+//! (case folding, width and compatibility normalization) and punycode-encodes any
+//! non-ASCII label, so `Alice.GHOST` and `alice.ghost` are the same name and
+//! `xn--` labels round-trip. [`detect_homographs`] looks at the Unicode form for
+//! labels that mix lookalike scripts or spell an ASCII word with confusable
+//! characters, and a [`HomographPolicy`] decides whether those are allowed,
+//! logged or rejected.
+
+use crate::{EtherlinkError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tracing::warn;
+
+/// Scripts whose letters are commonly mistaken for each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => Some(Script::Latin),
+            '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Some(Script::Greek),
+            '\u{0400}'..='\u{052F}' | '\u{2DE0}'..='\u{2DFF}' | '\u{A640}'..='\u{A69F}' => Some(Script::Cyrillic),
+            '\u{0530}'..='\u{058F}' => Some(Script::Armenian),
+            _ => None,
+        }
+    }
+}
+
+/// ASCII letter a confusable character is drawn like, after UTS-46 lowercasing
+fn ascii_lookalike(c: char) -> Option<char> {
+    Some(match c {
+        // Cyrillic
+        'а' => 'a', 'с' => 'c', 'ԁ' => 'd', 'е' => 'e', 'һ' => 'h', 'і' => 'i', 'ј' => 'j',
+        'к' => 'k', 'ӏ' => 'l', 'о' => 'o', 'р' => 'p', 'ԛ' => 'q', 'ѕ' => 's', 'у' => 'y',
+        'ԝ' => 'w', 'х' => 'x',
+        // Greek
+        'α' => 'a', 'ι' => 'i', 'κ' => 'k', 'ν' => 'v', 'ο' => 'o', 'ρ' => 'p', 'τ' => 't',
+        'υ' => 'u', 'χ' => 'x',
+        // Armenian
+        'ց' => 'g', 'հ' => 'h', 'օ' => 'o', 'զ' => 'q', 'ս' => 'u',
+        // Latin letters without their usual marks
+        'ı' => 'i', 'ȷ' => 'j', 'ɡ' => 'g', 'ɑ' => 'a',
+        _ => return None,
+    })
+}
+
+/// Why a label looks like something it is not
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HomographReason {
+    /// Letters from more than one lookalike script in one label
+    MixedScript { scripts: Vec<Script> },
+    /// Non-ASCII label drawn the same as the ASCII `lookalike`
+    Confusable { lookalike: String },
+}
+
+/// One suspicious label of a domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HomographFinding {
+    /// Label in Unicode form
+    pub label: String,
+    pub reason: HomographReason,
+}
+
+impl std::fmt::Display for HomographFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.reason {
+            HomographReason::MixedScript { scripts } => write!(f, "`{}` mixes scripts {:?}", self.label, scripts),
+            HomographReason::Confusable { lookalike } => write!(f, "`{}` looks like `{}`", self.label, lookalike),
+        }
+    }
+}
+
+/// What to do with a domain that has homograph findings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HomographPolicy {
+    /// Accept lookalike domains silently
+    Allow,
+    /// Accept lookalike domains but log each finding
+    #[default]
+    Warn,
+    /// Refuse lookalike domains
+    Reject,
+}
+
+impl HomographPolicy {
+    /// Check `domain` and apply the policy, returning what was found if it is accepted
+    pub fn enforce(self, domain: &str) -> Result<Vec<HomographFinding>> {
+        if self == HomographPolicy::Allow {
+            return Ok(Vec::new());
+        }
+        let findings = detect_homographs(domain);
+        if findings.is_empty() {
+            return Ok(findings);
+        }
+        let summary = findings.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ");
+        match self {
+            HomographPolicy::Reject => Err(EtherlinkError::CnsResolution(format!(
+                "Domain {} looks like another name: {}",
+                to_unicode(domain),
+                summary
+            ))),
+            _ => {
+                warn!("Possible homograph domain {}: {}", to_unicode(domain), summary);
+                Ok(findings)
+            }
+        }
+    }
+}
+
+/// UTS-46 normalize `domain` and punycode-encode its non-ASCII labels
+///
+/// A single trailing dot (the DNS root) is dropped.
+pub fn to_ascii(domain: &str) -> Result<String> {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    idna::domain_to_ascii(domain)
+        .map_err(|e| EtherlinkError::CnsResolution(format!("Invalid internationalized domain {}: {}", domain, e)))
+}
+
+/// Decode punycode labels of `domain` for display
+///
+/// Labels that fail to decode are left as they were.
+pub fn to_unicode(domain: &str) -> String {
+    idna::domain_to_unicode(domain).0
+}
+
+/// Labels of `domain` that mix lookalike scripts or imitate an ASCII label
+///
+/// `domain` may be in ASCII (punycode) or Unicode form.
+pub fn detect_homographs(domain: &str) -> Vec<HomographFinding> {
+    to_unicode(domain)
+        .split('.')
+        .filter(|label| !label.is_ascii())
+        .filter_map(|label| {
+            let scripts: BTreeSet<Script> = label.chars().filter_map(Script::of).collect();
+            let reason = if scripts.len() > 1 {
+                HomographReason::MixedScript { scripts: scripts.into_iter().collect() }
+            } else {
+                let lookalike: String = label.chars().map(|c| ascii_lookalike(c).unwrap_or(c)).collect();
+                if !lookalike.is_ascii() {
+                    return None;
+                }
+                HomographReason::Confusable { lookalike }
+            };
+            Some(HomographFinding { label: label.to_string(), reason })
+        })
+        .collect()
+}
@@ -0,0 +1,466 @@
+//! Embedded SQLite index of observed chain data
+//!
+//! [`ChainIndex`] keeps blocks, their transactions and balance snapshots of the
+//! accounts they touch in a local SQLite database, so balance history and
+//! transactions by address can be answered without a round trip to GHOSTD. The
+//! daemon keeps it current with [`ChainIndex::sync`] from a supervised task when
+//! [`crate::runtime::DaemonConfig::index`] is set. Each sync first compares the
+//! newest indexed blocks with the node's and rolls back any the chain has replaced.
+//!
+//! The schema is versioned through SQLite's `user_version` and upgraded in place by
+//! the ordered [`MIGRATIONS`] when a database is opened. A database written by a
+//! newer release is refused rather than modified. Old blocks can be pruned with
+//! [`IndexConfig::retain_blocks`]; the latest balance of every account is kept past
+//! the cutoff so point-in-time lookups still work.
+//!
+//! Requires the `sqlite-index` feature.
+
+use crate::clients::ghostd::{Block, GhostdClient};
+use crate::events::{EtherlinkEvent, EventBus};
+use crate::pagination::PageConfig;
+use crate::validation::{ConfigErrors, Validator};
+use crate::{Address, BlockHeight, EtherlinkError, Result};
+use futures::StreamExt;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Schema upgrades, applied in order; entry `n` moves the database to version `n + 1`
+pub const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE blocks (
+        height INTEGER PRIMARY KEY,
+        hash TEXT NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+    CREATE TABLE transactions (
+        block_height INTEGER NOT NULL,
+        tx_index INTEGER NOT NULL,
+        from_address TEXT NOT NULL,
+        to_address TEXT NOT NULL,
+        amount INTEGER NOT NULL,
+        gas_limit INTEGER NOT NULL,
+        gas_price INTEGER NOT NULL,
+        nonce INTEGER NOT NULL,
+        PRIMARY KEY (block_height, tx_index)
+    );
+    CREATE INDEX transactions_from ON transactions (from_address, block_height);
+    CREATE INDEX transactions_to ON transactions (to_address, block_height);
+    CREATE TABLE balances (
+        address TEXT NOT NULL,
+        block_height INTEGER NOT NULL,
+        balance INTEGER NOT NULL,
+        PRIMARY KEY (address, block_height)
+    );",
+];
+
+/// Schema version this build reads and writes
+pub fn schema_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+/// Where the index lives and how it is kept up to date
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexConfig {
+    /// Database file, created if missing
+    pub path: PathBuf,
+    /// Delay between checks for new blocks
+    pub poll_interval_ms: u64,
+    /// Blocks fetched per request while catching up
+    pub page_size: u32,
+    /// Keep only this many most recent blocks; 0 keeps everything
+    pub retain_blocks: u64,
+    /// First block to index when the database is empty; defaults to the current height
+    pub start_block: Option<BlockHeight>,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("etherlink-index.sqlite"),
+            poll_interval_ms: 2000,
+            page_size: 100,
+            retain_blocks: 0,
+            start_block: None,
+        }
+    }
+}
+
+impl IndexConfig {
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms)
+    }
+
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(!self.path.as_os_str().is_empty(), "path", "must not be empty");
+        v.check(self.poll_interval_ms > 0, "poll_interval_ms", "must be greater than zero");
+        v.check(self.page_size > 0, "page_size", "must be greater than zero");
+        v.finish()
+    }
+}
+
+/// A transaction as stored in the index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedTransaction {
+    pub block_height: BlockHeight,
+    pub tx_index: u32,
+    pub from: Address,
+    pub to: Address,
+    pub amount: u64,
+    pub gas_limit: u64,
+    pub gas_price: u64,
+    pub nonce: u64,
+}
+
+/// Balance of an account as of a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalancePoint {
+    pub block_height: BlockHeight,
+    pub balance: u64,
+}
+
+fn db_error(e: rusqlite::Error) -> EtherlinkError {
+    EtherlinkError::Index(e.to_string())
+}
+
+/// Handle to an open index database; clones share the connection
+#[derive(Clone)]
+pub struct ChainIndex {
+    conn: Arc<Mutex<Connection>>,
+    config: IndexConfig,
+    events: Option<EventBus>,
+}
+
+impl fmt::Debug for ChainIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainIndex").field("path", &self.config.path).finish()
+    }
+}
+
+impl ChainIndex {
+    /// Open (or create) the database at `config.path` and migrate it to the current schema
+    pub fn open(config: IndexConfig) -> Result<Self> {
+        config.validate()?;
+        let conn = Connection::open(&config.path)
+            .map_err(|e| EtherlinkError::Index(format!("Failed to open {}: {}", config.path.display(), e)))?;
+        Self::with_connection(conn, config)
+    }
+
+    /// Index held in memory only, for tests and short-lived tools
+    pub fn open_in_memory(config: IndexConfig) -> Result<Self> {
+        config.validate()?;
+        Self::with_connection(Connection::open_in_memory().map_err(db_error)?, config)
+    }
+
+    fn with_connection(mut conn: Connection, config: IndexConfig) -> Result<Self> {
+        migrate(&mut conn)?;
+        Ok(Self { conn: Arc::new(Mutex::new(conn)), config, events: None })
+    }
+
+    /// Publish [`EtherlinkEvent::ReorgDetected`] when an indexed block is replaced
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn config(&self) -> &IndexConfig {
+        &self.config
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.config.path
+    }
+
+    /// Schema version of the open database
+    pub fn schema_version(&self) -> Result<u32> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(db_error)
+    }
+
+    /// Highest indexed block
+    pub fn latest_height(&self) -> Result<Option<BlockHeight>> {
+        let conn = self.conn.lock().unwrap();
+        let height: Option<i64> = conn.query_row("SELECT MAX(height) FROM blocks", [], |row| row.get(0)).map_err(db_error)?;
+        Ok(height.map(|height| height as BlockHeight))
+    }
+
+    /// Hash of an indexed block
+    pub fn block_hash(&self, height: BlockHeight) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT hash FROM blocks WHERE height = ?1", params![height as i64], |row| row.get(0))
+            .optional()
+            .map_err(db_error)
+    }
+
+    /// Store a block and its transactions
+    ///
+    /// If a different block is already indexed at this height, the chain was
+    /// reorganized: it and everything above it are dropped first.
+    pub fn index_block(&self, block: &Block) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        let existing: Option<String> = tx
+            .query_row("SELECT hash FROM blocks WHERE height = ?1", params![block.height as i64], |row| row.get(0))
+            .optional()
+            .map_err(db_error)?;
+        match existing {
+            Some(hash) if hash == block.hash => return Ok(()),
+            Some(hash) => {
+                info!("Block {} changed from {} to {}, dropping indexed blocks from there", block.height, hash, block.hash);
+                truncate_from(&tx, block.height)?;
+                if let Some(events) = &self.events {
+                    events.publish(EtherlinkEvent::ReorgDetected { height: block.height, old_hash: hash, new_hash: block.hash.clone() });
+                }
+            }
+            None => {}
+        }
+
+        tx.execute(
+            "INSERT INTO blocks (height, hash, timestamp) VALUES (?1, ?2, ?3)",
+            params![block.height as i64, block.hash, block.timestamp as i64],
+        )
+        .map_err(db_error)?;
+        for (index, transaction) in block.transactions.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO transactions (block_height, tx_index, from_address, to_address, amount, gas_limit, gas_price, nonce)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    block.height as i64,
+                    index as i64,
+                    transaction.from.as_str(),
+                    transaction.to.as_str(),
+                    transaction.amount as i64,
+                    transaction.gas_limit as i64,
+                    transaction.gas_price as i64,
+                    transaction.nonce as i64,
+                ],
+            )
+            .map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)
+    }
+
+    /// Record an account's balance as of a block
+    pub fn record_balance(&self, address: &Address, height: BlockHeight, balance: u64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO balances (address, block_height, balance) VALUES (?1, ?2, ?3)",
+            params![address.as_str(), height as i64, balance as i64],
+        )
+        .map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Transactions sent or received by `address`, newest first
+    pub fn transactions_by_address(&self, address: &Address, limit: u32) -> Result<Vec<IndexedTransaction>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn
+            .prepare(
+                "SELECT block_height, tx_index, from_address, to_address, amount, gas_limit, gas_price, nonce FROM transactions
+                 WHERE from_address = ?1 OR to_address = ?1
+                 ORDER BY block_height DESC, tx_index DESC LIMIT ?2",
+            )
+            .map_err(db_error)?;
+        let rows = statement
+            .query_map(params![address.as_str(), limit as i64], |row| {
+                Ok(IndexedTransaction {
+                    block_height: row.get::<_, i64>(0)? as BlockHeight,
+                    tx_index: row.get(1)?,
+                    from: Address::new(row.get(2)?),
+                    to: Address::new(row.get(3)?),
+                    amount: row.get::<_, i64>(4)? as u64,
+                    gas_limit: row.get::<_, i64>(5)? as u64,
+                    gas_price: row.get::<_, i64>(6)? as u64,
+                    nonce: row.get::<_, i64>(7)? as u64,
+                })
+            })
+            .map_err(db_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(db_error)
+    }
+
+    /// Every recorded balance of `address`, oldest first
+    pub fn balance_history(&self, address: &Address) -> Result<Vec<BalancePoint>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn
+            .prepare("SELECT block_height, balance FROM balances WHERE address = ?1 ORDER BY block_height")
+            .map_err(db_error)?;
+        let rows = statement
+            .query_map(params![address.as_str()], |row| {
+                Ok(BalancePoint { block_height: row.get::<_, i64>(0)? as BlockHeight, balance: row.get::<_, i64>(1)? as u64 })
+            })
+            .map_err(db_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(db_error)
+    }
+
+    /// Balance of `address` as of `height`, from the latest snapshot at or below it
+    pub fn balance_at(&self, address: &Address, height: BlockHeight) -> Result<Option<u64>> {
+        let conn = self.conn.lock().unwrap();
+        let balance: Option<i64> = conn
+            .query_row(
+                "SELECT balance FROM balances WHERE address = ?1 AND block_height <= ?2 ORDER BY block_height DESC LIMIT 1",
+                params![address.as_str(), height as i64],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_error)?;
+        Ok(balance.map(|balance| balance as u64))
+    }
+
+    /// Drop blocks and transactions below `height`, returning how many blocks were removed
+    ///
+    /// Balance snapshots below `height` are dropped too, except the newest one per
+    /// account, which still answers [`ChainIndex::balance_at`] for later heights.
+    pub fn prune_below(&self, height: BlockHeight) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        let cutoff = height as i64;
+        let removed = tx.execute("DELETE FROM blocks WHERE height < ?1", params![cutoff]).map_err(db_error)?;
+        tx.execute("DELETE FROM transactions WHERE block_height < ?1", params![cutoff]).map_err(db_error)?;
+        tx.execute(
+            "DELETE FROM balances WHERE block_height < ?1 AND EXISTS (
+                SELECT 1 FROM balances AS newer
+                WHERE newer.address = balances.address AND newer.block_height > balances.block_height AND newer.block_height <= ?1
+            )",
+            params![cutoff],
+        )
+        .map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+        Ok(removed)
+    }
+
+    /// Index every block GHOSTD has that the index does not, returning how many were added
+    ///
+    /// Indexed blocks the node has since replaced are rolled back first, publishing
+    /// [`EtherlinkEvent::ReorgDetected`]. Balances of the accounts touched by each block are
+    /// snapshotted as of that block. Afterwards, blocks beyond
+    /// [`IndexConfig::retain_blocks`] are pruned. Database work runs on the blocking pool.
+    pub async fn sync(&self, ghostd: &GhostdClient) -> Result<u64> {
+        let head = ghostd.get_blockchain_height().await?;
+        let start = match self.blocking(|index| index.latest_height()).await? {
+            Some(latest) => self.rewind(ghostd, latest, head).await?,
+            None => self.config.start_block.unwrap_or(head),
+        };
+        if start > head {
+            return Ok(0);
+        }
+
+        let mut indexed = 0;
+        let mut blocks = ghostd.stream_blocks(start..=head, PageConfig::default().page_size(self.config.page_size));
+        while let Some(block) = blocks.next().await {
+            let block = Arc::new(block?);
+            let stored = block.clone();
+            self.blocking(move |index| index.index_block(&stored)).await?;
+
+            let accounts: BTreeSet<&str> = block.transactions.iter().flat_map(|tx| [tx.from.as_str(), tx.to.as_str()]).collect();
+            let at_block = ghostd.at_block(block.height);
+            for account in accounts {
+                let address = Address::new(account.to_string());
+                let balance = at_block.get_balance(&address).await?;
+                let height = block.height;
+                self.blocking(move |index| index.record_balance(&address, height, balance)).await?;
+            }
+            indexed += 1;
+        }
+
+        if self.config.retain_blocks > 0 && head >= self.config.retain_blocks {
+            let cutoff = head + 1 - self.config.retain_blocks;
+            let pruned = self.blocking(move |index| index.prune_below(cutoff)).await?;
+            if pruned > 0 {
+                debug!("Pruned {} blocks from the index", pruned);
+            }
+        }
+        Ok(indexed)
+    }
+
+    /// Roll back indexed blocks the node no longer has, returning the height to resume from
+    ///
+    /// Walks down from the newest indexed block the node still reaches until a stored
+    /// hash matches the node's, then drops everything above that block. Blocks above
+    /// the node's head are dropped too. Pruned heights are not compared.
+    async fn rewind(&self, ghostd: &GhostdClient, latest: BlockHeight, head: BlockHeight) -> Result<BlockHeight> {
+        let mut replaced = None;
+        let mut height = latest.min(head);
+        loop {
+            let Some(stored) = self.blocking(move |index| index.block_hash(height)).await? else {
+                break;
+            };
+            let canonical = ghostd.get_block(height).await?;
+            if canonical.hash == stored {
+                break;
+            }
+            replaced = Some((height, stored, canonical.hash));
+            match height.checked_sub(1) {
+                Some(below) => height = below,
+                None => break,
+            }
+        }
+
+        let Some((height, old_hash, new_hash)) = replaced else {
+            if latest > head {
+                info!("Index is ahead of the node's head {}, dropping blocks above it", head);
+                self.blocking(move |index| index.truncate_from(head + 1)).await?;
+                return Ok(head + 1);
+            }
+            return Ok(latest + 1);
+        };
+        info!("Block {} changed from {} to {}, dropping indexed blocks from there", height, old_hash, new_hash);
+        self.blocking(move |index| index.truncate_from(height)).await?;
+        if let Some(events) = &self.events {
+            events.publish(EtherlinkEvent::ReorgDetected { height, old_hash, new_hash });
+        }
+        Ok(height)
+    }
+
+    fn truncate_from(&self, height: BlockHeight) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        truncate_from(&tx, height)?;
+        tx.commit().map_err(db_error)
+    }
+
+    /// Run a database call on the blocking pool, off the async workers
+    async fn blocking<T, F>(&self, call: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&ChainIndex) -> Result<T> + Send + 'static,
+    {
+        let index = self.clone();
+        tokio::task::spawn_blocking(move || call(&index))
+            .await
+            .map_err(|e| EtherlinkError::Index(format!("Index task failed: {}", e)))?
+    }
+}
+
+fn truncate_from(conn: &Connection, height: BlockHeight) -> Result<()> {
+    for statement in ["DELETE FROM blocks WHERE height >= ?1", "DELETE FROM transactions WHERE block_height >= ?1", "DELETE FROM balances WHERE block_height >= ?1"] {
+        conn.execute(statement, params![height as i64]).map_err(db_error)?;
+    }
+    Ok(())
+}
+
+/// Bring the database up to [`schema_version`], one migration per transaction
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let current: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(db_error)?;
+    let target = schema_version();
+    if current > target {
+        return Err(EtherlinkError::Unsupported(format!(
+            "Index schema version {} is newer than this build supports ({})",
+            current, target
+        )));
+    }
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let tx = conn.transaction().map_err(db_error)?;
+        tx.execute_batch(migration).map_err(db_error)?;
+        tx.pragma_update(None, "user_version", version as u32 + 1).map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+        info!("Migrated index schema to version {}", version + 1);
+    }
+    Ok(())
+}
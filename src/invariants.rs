@@ -0,0 +1,68 @@
+//! Checked arithmetic for state transitions
+//!
+//! Balances, nonces and counters in [`crate::revm`], [`crate::rvm`] and
+//! [`crate::ghostplane`] are updated through these helpers, so a value that would wrap
+//! is reported as a [`StateInvariantViolation`] and the update is refused. Supply
+//! conservation is checked before every EVM transaction's updates are written, the
+//! same check [`crate::revm::REVMClient::check_invariants`] runs on demand.
+
+use crate::Address;
+
+/// A state transition that would break an invariant
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StateInvariantViolation {
+    /// A value would exceed its type's maximum
+    #[error("{context} overflows")]
+    Overflow { context: String },
+    /// A value would drop below zero
+    #[error("{context} underflows")]
+    Underflow { context: String },
+    /// A balance change would leave an account with less than nothing
+    #[error("balance of {address} is {balance}, cannot apply {delta}")]
+    NegativeBalance { address: Address, balance: u64, delta: i128 },
+    /// Balances plus burned fees no longer match what was issued
+    #[error("supply not conserved at block {block}: expected {expected}, found {actual}")]
+    SupplyNotConserved { block: u64, expected: u128, actual: u128 },
+}
+
+/// `a + b`, or [`StateInvariantViolation::Overflow`] naming `context`
+pub fn add(a: u64, b: u64, context: &str) -> Result<u64, StateInvariantViolation> {
+    a.checked_add(b).ok_or_else(|| StateInvariantViolation::Overflow { context: context.to_string() })
+}
+
+/// `a - b`, or [`StateInvariantViolation::Underflow`] naming `context`
+pub fn sub(a: u64, b: u64, context: &str) -> Result<u64, StateInvariantViolation> {
+    a.checked_sub(b).ok_or_else(|| StateInvariantViolation::Underflow { context: context.to_string() })
+}
+
+/// `a * b`, or [`StateInvariantViolation::Overflow`] naming `context`
+pub fn mul(a: u64, b: u64, context: &str) -> Result<u64, StateInvariantViolation> {
+    a.checked_mul(b).ok_or_else(|| StateInvariantViolation::Overflow { context: context.to_string() })
+}
+
+/// Apply a signed change to an unsigned value
+pub fn apply_delta(value: u64, delta: i64, context: &str) -> Result<u64, StateInvariantViolation> {
+    if delta < 0 {
+        sub(value, delta.unsigned_abs(), context)
+    } else {
+        add(value, delta as u64, context)
+    }
+}
+
+/// Debit `amount` from the balance of `address`
+pub fn debit(address: &Address, balance: u64, amount: u64) -> Result<u64, StateInvariantViolation> {
+    balance.checked_sub(amount).ok_or_else(|| StateInvariantViolation::NegativeBalance {
+        address: address.clone(),
+        balance,
+        delta: -(amount as i128),
+    })
+}
+
+/// Apply a signed change to the balance of `address`
+pub fn apply_balance_delta(address: &Address, balance: u64, delta: i64) -> Result<u64, StateInvariantViolation> {
+    if delta < 0 {
+        debit(address, balance, delta.unsigned_abs())
+    } else {
+        add(balance, delta as u64, &format!("balance of {}", address))
+    }
+}
@@ -4,28 +4,110 @@
 //!
 //! Etherlink provides secure and performant communication between Rust-based services
 //! (GhostChain Core, GWallet, GhostBridge) and Zig-based execution layers like GhostPlane.
+//!
+//! ## WebAssembly
+//!
+//! On `wasm32-unknown-unknown` (`cargo build --lib --target wasm32-unknown-unknown`)
+//! only the REST service clients, authentication, core types and codecs are built.
+//! HTTP goes through the browser's `fetch`; signing is delegated to the user's wallet
+//! with [`auth::InjectedSigner`]. gRPC, QUIC, the Zig FFI bridge and the runtime are
+//! native-only.
 
+extern crate alloc;
+
+#[cfg(not(target_arch = "wasm32"))]
 pub mod client;
 pub mod clients;
 pub mod transport;
+pub mod address;
 pub mod auth;
+pub mod bech32;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod ffi;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod capi;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cassette;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod ghostplane;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod txpool;
 pub mod rvm;
 pub mod revm;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod cns;
+pub mod cache;
+pub mod canonical;
+pub mod clock;
+pub mod coalesce;
+pub mod confirmation;
+pub mod correlation;
+pub mod diagnostics;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod export;
+pub mod hash;
+pub mod idn;
+#[cfg(all(feature = "sqlite-index", not(target_arch = "wasm32")))]
+pub mod index;
+pub mod invariants;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lifecycle;
+pub mod merkle;
+pub mod messaging;
+pub mod primitives;
+pub mod queue;
+pub mod resubmit;
+pub mod rlp;
+pub mod secret;
+pub mod timesync;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pagination;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod portfolio;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod proto;
+#[cfg(all(feature = "python", not(target_arch = "wasm32")))]
+pub mod python;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod runtime;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shm;
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub mod testing;
+pub mod validation;
+pub mod version;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod watcher;
 pub mod error;
 pub mod types;
 
 // Re-export commonly used types
+#[cfg(not(target_arch = "wasm32"))]
 pub use client::*;
 pub use clients::*;
 pub use transport::*;
 pub use auth::*;
+#[cfg(not(target_arch = "wasm32"))]
 pub use cns::CNSClient;
+#[cfg(not(target_arch = "wasm32"))]
+pub use events::{EtherlinkEvent, EventBus};
+#[cfg(not(target_arch = "wasm32"))]
 pub use ghostplane::GhostPlaneClient;
+#[cfg(not(target_arch = "wasm32"))]
+pub use lifecycle::{OperationTracker, TxState};
+#[cfg(not(target_arch = "wasm32"))]
+pub use runtime::{Etherlink, TaskSupervisor, RestartPolicy};
+pub use clock::{Clock, MockClock, SharedClock, SystemClock};
+pub use correlation::CorrelationId;
 pub use error::{EtherlinkError, Result};
 pub use types::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use pagination::{Checkpoint, PageConfig, PageStream};
+pub use validation::{ConfigErrors, ConfigIssue};
+pub use resubmit::ResubmitPolicy;
+pub use secret::{Secret, SecretString};
 
 /// Initialize the Etherlink library with default configuration
 pub fn init() -> Result<()> {
@@ -39,4 +121,24 @@ pub fn init_with_tracing(filter: &str) -> Result<()> {
         .with_env_filter(filter)
         .init();
     Ok(())
+}
+
+/// Initialize tracing with a filter that can be changed later, e.g. by a config reload
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init_with_reloadable_tracing(filter: &str) -> Result<runtime::LogFilterHandle> {
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_filter_reloading();
+    let handle = builder.reload_handle();
+    builder
+        .try_init()
+        .map_err(|e| EtherlinkError::Configuration(format!("Failed to install tracing subscriber: {}", e)))?;
+
+    Ok(runtime::LogFilterHandle::new(move |filter| {
+        let filter = tracing_subscriber::EnvFilter::try_new(filter)
+            .map_err(|e| EtherlinkError::Configuration(format!("Invalid log filter {}: {}", filter, e)))?;
+        handle
+            .reload(filter)
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to update log filter: {}", e)))
+    }))
 }
\ No newline at end of file
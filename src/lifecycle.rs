@@ -0,0 +1,334 @@
+//! Lifecycle of submitted transactions
+//!
+//! Every transaction submitted with an [`OperationTracker`] gets an operation id and
+//! moves through [`TxState`]:
+//!
+//! ```text
+//! Created → Signed → Broadcast → Pending → Confirmed → Finalized
+//!             │          └──────────┴──────────┴──→ Failed | Replaced
+//!             └→ Unknown ──→ Broadcast | Pending | Confirmed | Failed | Replaced
+//! ```
+//!
+//! A transaction can fail at any point before it is finalized, and be replaced (by
+//! another with the same nonce) at any point after it was sent. One whose submission
+//! ended without a clear answer, e.g. on a timeout, is [`TxState::Unknown`] until
+//! following it shows what the node did. Each state change is checked, kept in the
+//! operation's history, written to the tracker's directory if it has one, and
+//! published as [`EtherlinkEvent::TransactionStateChanged`].
+
+use crate::clock::{self, SharedClock};
+use crate::correlation;
+use crate::events::{EtherlinkEvent, EventBus};
+use crate::{Address, BlockHeight, EtherlinkError, Result, TxHash};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Stage of a tracked transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxState {
+    Created,
+    Signed,
+    /// Accepted by a node or relay
+    Broadcast,
+    /// Sent without a clear answer; the node may or may not have accepted it
+    Unknown,
+    /// Seen in the mempool, not yet included
+    Pending,
+    /// Included in a block and executed successfully
+    Confirmed,
+    /// Deep enough to meet the confirmation policy for its operation class
+    Finalized,
+    Failed,
+    /// Superseded by another transaction with the same nonce
+    Replaced,
+}
+
+impl TxState {
+    /// Whether no further transition is possible
+    pub fn is_terminal(self) -> bool {
+        matches!(self, TxState::Finalized | TxState::Failed | TxState::Replaced)
+    }
+
+    /// Whether a transaction in this state may move to `next`
+    pub fn can_transition_to(self, next: TxState) -> bool {
+        use TxState::*;
+        match (self, next) {
+            (Created, Signed) | (Signed, Broadcast | Unknown) | (Broadcast, Pending) => true,
+            (Unknown, Broadcast | Pending) => true,
+            // Inclusion may be the first thing observed after broadcasting
+            (Broadcast | Unknown | Pending, Confirmed) | (Confirmed, Finalized) => true,
+            (Created | Signed | Broadcast | Unknown | Pending | Confirmed, Failed) => true,
+            (Broadcast | Unknown | Pending | Confirmed, Replaced) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A requested state change, with what became known along with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub to: TxState,
+    /// The transaction's hash, or the replacement's when moving to [`TxState::Replaced`]
+    pub tx_hash: Option<TxHash>,
+    pub block_height: Option<BlockHeight>,
+    /// Nonce the transaction was last signed with, when a resubmission changed it
+    pub nonce: Option<u64>,
+    /// Why, e.g. the node's error for [`TxState::Failed`]
+    pub detail: Option<String>,
+}
+
+impl Transition {
+    pub fn to(state: TxState) -> Self {
+        Self { to: state, tx_hash: None, block_height: None, nonce: None, detail: None }
+    }
+
+    pub fn tx_hash(mut self, tx_hash: TxHash) -> Self {
+        self.tx_hash = Some(tx_hash);
+        self
+    }
+
+    pub fn block_height(mut self, height: BlockHeight) -> Self {
+        self.block_height = Some(height);
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// One recorded state change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateChange {
+    pub from: TxState,
+    pub to: TxState,
+    pub at: u64,
+    pub detail: Option<String>,
+}
+
+/// A transaction's current state and how it got there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedTransaction {
+    pub id: String,
+    pub from: Address,
+    /// Nonce the transaction was last signed with; a resubmission may move it past the one it was built with
+    pub nonce: u64,
+    pub state: TxState,
+    /// Known once broadcast
+    pub tx_hash: Option<TxHash>,
+    /// Block the transaction was included in
+    pub block_height: Option<BlockHeight>,
+    /// The transaction that took this one's nonce
+    pub replaced_by: Option<TxHash>,
+    /// Detail of the transition to [`TxState::Failed`]
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub history: Vec<StateChange>,
+}
+
+/// Tracks transaction lifecycles by operation id, optionally persisting them
+///
+/// Clones share the same operations. A tracker opened on a directory keeps one JSON
+/// file per operation there, written on the blocking pool, and reloads them on open,
+/// so lifecycles survive a restart.
+#[derive(Debug, Clone)]
+pub struct OperationTracker {
+    operations: Arc<Mutex<HashMap<String, TrackedTransaction>>>,
+    /// Held across a transition, so each is checked against the state the last one left
+    writes: Arc<tokio::sync::Mutex<()>>,
+    dir: Option<PathBuf>,
+    clock: SharedClock,
+    events: Option<EventBus>,
+}
+
+impl OperationTracker {
+    /// A tracker keeping operations in memory only
+    pub fn new() -> Self {
+        Self {
+            operations: Arc::new(Mutex::new(HashMap::new())),
+            writes: Arc::new(tokio::sync::Mutex::new(())),
+            dir: None,
+            clock: clock::system(),
+            events: None,
+        }
+    }
+
+    /// A tracker persisting operations in `dir`, loading those already there
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let mut operations = HashMap::new();
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => Some(entries),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(EtherlinkError::Lifecycle(format!("Failed to read {}: {}", dir.display(), e))),
+        };
+        for entry in entries.into_iter().flatten() {
+            let path = entry.map_err(|e| EtherlinkError::Lifecycle(e.to_string()))?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = std::fs::read(&path)
+                .map_err(|e| EtherlinkError::Lifecycle(format!("Failed to read {}: {}", path.display(), e)))?;
+            let operation: TrackedTransaction = serde_json::from_slice(&bytes)
+                .map_err(|e| EtherlinkError::Lifecycle(format!("Corrupt operation file {}: {}", path.display(), e)))?;
+            operations.insert(operation.id.clone(), operation);
+        }
+        debug!("Loaded {} operations from {}", operations.len(), dir.display());
+
+        Ok(Self {
+            operations: Arc::new(Mutex::new(operations)),
+            writes: Arc::new(tokio::sync::Mutex::new(())),
+            dir: Some(dir),
+            clock: clock::system(),
+            events: None,
+        })
+    }
+
+    /// Timestamp state changes by `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Publish [`EtherlinkEvent::TransactionStateChanged`] to `events`
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Start tracking a transaction from `from` with `nonce`, in [`TxState::Created`]
+    pub async fn create(&self, from: &Address, nonce: u64) -> Result<TrackedTransaction> {
+        let now = self.clock.now();
+        let operation = TrackedTransaction {
+            id: uuid::Uuid::new_v4().to_string(),
+            from: from.clone(),
+            nonce,
+            state: TxState::Created,
+            tx_hash: None,
+            block_height: None,
+            replaced_by: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+            history: Vec::new(),
+        };
+        self.persist(&operation).await?;
+        self.operations.lock().unwrap().insert(operation.id.clone(), operation.clone());
+        Ok(operation)
+    }
+
+    /// Move operation `id` to a new state
+    ///
+    /// Fails with [`EtherlinkError::Lifecycle`] for an unknown id or a transition its
+    /// current state does not allow, leaving the operation unchanged.
+    pub async fn transition(&self, id: &str, transition: Transition) -> Result<TrackedTransaction> {
+        let _write = self.writes.lock().await;
+        let current = self.get(id).ok_or_else(|| EtherlinkError::Lifecycle(format!("Unknown operation {}", id)))?;
+        let from = current.state;
+        if !from.can_transition_to(transition.to) {
+            return Err(EtherlinkError::Lifecycle(format!(
+                "Operation {} cannot move from {:?} to {:?}",
+                id, from, transition.to
+            )));
+        }
+
+        let mut operation = current;
+        let now = self.clock.now();
+        operation.state = transition.to;
+        operation.updated_at = now;
+        match transition.to {
+            TxState::Replaced => operation.replaced_by = transition.tx_hash,
+            _ => operation.tx_hash = transition.tx_hash.or(operation.tx_hash),
+        }
+        operation.block_height = transition.block_height.or(operation.block_height);
+        operation.nonce = transition.nonce.unwrap_or(operation.nonce);
+        if transition.to == TxState::Failed {
+            operation.error = transition.detail.clone();
+        }
+        operation.history.push(StateChange { from, to: transition.to, at: now, detail: transition.detail });
+
+        // Persist before committing, so memory never runs ahead of disk
+        self.persist(&operation).await?;
+        self.operations.lock().unwrap().insert(id.to_string(), operation.clone());
+
+        debug!("Operation {} moved from {:?} to {:?}", id, from, operation.state);
+        if let Some(events) = &self.events {
+            events.publish(EtherlinkEvent::TransactionStateChanged {
+                id: id.to_string(),
+                tx_hash: operation.tx_hash.clone(),
+                from,
+                to: operation.state,
+                correlation_id: correlation::current(),
+            });
+        }
+        Ok(operation)
+    }
+
+    /// Operation `id`, if tracked
+    pub fn get(&self, id: &str) -> Option<TrackedTransaction> {
+        self.operations.lock().unwrap().get(id).cloned()
+    }
+
+    /// Operation whose transaction has `tx_hash`
+    pub fn find_by_hash(&self, tx_hash: &TxHash) -> Option<TrackedTransaction> {
+        self.operations
+            .lock()
+            .unwrap()
+            .values()
+            .find(|operation| operation.tx_hash.as_ref() == Some(tx_hash))
+            .cloned()
+    }
+
+    /// Every tracked operation, oldest first
+    pub fn operations(&self) -> Vec<TrackedTransaction> {
+        let mut operations: Vec<TrackedTransaction> = self.operations.lock().unwrap().values().cloned().collect();
+        operations.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        operations
+    }
+
+    /// Operations not yet finalized, failed or replaced, oldest first
+    pub fn active(&self) -> Vec<TrackedTransaction> {
+        self.operations().into_iter().filter(|operation| !operation.state.is_terminal()).collect()
+    }
+
+    /// Directory operations are persisted in, if any
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    async fn persist(&self, operation: &TrackedTransaction) -> Result<()> {
+        let Some(dir) = self.dir.clone() else {
+            return Ok(());
+        };
+        let path = dir.join(format!("{}.json", operation.id));
+        let bytes = serde_json::to_vec_pretty(operation)?;
+        tokio::task::spawn_blocking(move || {
+            let write_error = |path: &Path, e: std::io::Error| {
+                EtherlinkError::Lifecycle(format!("Failed to write {}: {}", path.display(), e))
+            };
+            std::fs::create_dir_all(&dir).map_err(|e| write_error(&dir, e))?;
+            let partial = path.with_extension("json.partial");
+            std::fs::write(&partial, bytes).map_err(|e| write_error(&partial, e))?;
+            std::fs::rename(&partial, &path).map_err(|e| write_error(&path, e))
+        })
+        .await
+        .map_err(|e| EtherlinkError::Lifecycle(format!("Persisting operation {} failed: {}", operation.id, e)))?
+    }
+}
+
+impl Default for OperationTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,8 +1,521 @@
-use etherlink::{EtherlinkClient, EtherlinkClientBuilder, CNSClient, GhostPlaneClient};
+use etherlink::{EtherlinkClient, EtherlinkClientBuilder, EtherlinkConfig, EtherlinkError, CNSClient, GhostPlaneClient};
+use etherlink::runtime::{CheckStatus, ConfigWatcher, DaemonConfig, Etherlink, ServerConfig, ServiceState};
+use etherlink::export::{ExportConfig, ExportFormat, Exporter};
+use etherlink::watcher::{Rule, StdoutSink, WatchTarget, Watcher, WebhookSink};
+use std::sync::Arc;
 use tracing::{info, error};
 
+const USAGE: &str = "Usage: etherlink [status [--ghostd <url>] [--cns <url>] [--ghostplane <url>] [--json]]\n       etherlink doctor [--config <file>] [--ghostd <url>] [--cns <url>] [--ghostplane <url>] [--json]\n       etherlink serve [--config <file>] [--listen <addr>] [--ghostd <url>] [--cns <url>] [--ghostplane <url>] [--no-reflection] [--probes <addr>] [--dns <addr>] [--http-gateway <addr>]\n       etherlink export --from-block <n> [--to-block <n>] [--out <dir>] [--format csv|parquet] [--blocks-per-part <n>] [--resume] [--ghostd <url>] [--cns <url>]\n       etherlink watch (--address <addr> | --domain <name>)... [--rule <expr>] [--webhook <url>] [--pinning <url>] [--interval-ms <n>] [--ghostd <url>] [--cns <url>]\n       etherlink validator (status | slashing) <address> [--window <blocks>] [--ghostd <url>] [--json]\n       etherlink snapshot fetch [--height <n>] [--out <file>] [--ghostd <url>]";
+
 #[tokio::main]
 async fn main() -> etherlink::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("status") => status(&args[1..]).await,
+        Some("doctor") => doctor(&args[1..]).await,
+        Some("serve") => serve(&args[1..]).await,
+        Some("export") => export(&args[1..]).await,
+        Some("watch") => watch(&args[1..]).await,
+        Some("validator") => validator(&args[1..]).await,
+        Some("snapshot") => snapshot(&args[1..]).await,
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => Err(EtherlinkError::Configuration(format!("Unknown command: {}\n{}", other, USAGE))),
+        None => demo().await,
+    }
+}
+
+/// `etherlink status`: print the aggregated health of all services
+async fn status(args: &[String]) -> etherlink::Result<()> {
+    etherlink::init_with_tracing("etherlink=warn")?;
+
+    let mut config = EtherlinkConfig::default();
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| EtherlinkError::Configuration(format!("{} requires a value\n{}", arg, USAGE)))
+        };
+        match arg.as_str() {
+            "--ghostd" => config.ghostd_endpoint = value()?,
+            "--cns" => config.cns_endpoint = Some(value()?),
+            "--ghostplane" => config.ghostplane_endpoint = Some(value()?),
+            "--json" => json = true,
+            other => return Err(EtherlinkError::Configuration(format!("Unknown option: {}\n{}", other, USAGE))),
+        }
+    }
+
+    let runtime = Etherlink::new(config)?;
+    let report = runtime.health_report().await;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{:<12} {:<10} {:>9}  {:<10} DETAIL", "SERVICE", "STATE", "LATENCY", "VERSION");
+        for service in &report.services {
+            println!(
+                "{:<12} {:<10} {:>7}ms  {:<10} {}",
+                service.service,
+                format!("{:?}", service.state).to_lowercase(),
+                service.latency_ms,
+                service.version.as_deref().unwrap_or("-"),
+                service.error.as_deref().unwrap_or(""),
+            );
+        }
+        println!("\noverall: {:?}", report.overall());
+    }
+
+    if report.overall() == ServiceState::Unhealthy {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// `etherlink doctor`: run the startup preflight checks and suggest fixes
+async fn doctor(args: &[String]) -> etherlink::Result<()> {
+    etherlink::init_with_tracing("etherlink=error")?;
+
+    let config_path = args.iter().position(|arg| arg == "--config").and_then(|i| args.get(i + 1)).cloned();
+    let mut settings = match &config_path {
+        Some(path) => DaemonConfig::load(path)?,
+        None => DaemonConfig::default(),
+    };
+    let config = &mut settings.etherlink;
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| EtherlinkError::Configuration(format!("{} requires a value\n{}", arg, USAGE)))
+        };
+        match arg.as_str() {
+            "--ghostd" => config.ghostd_endpoint = value()?,
+            "--cns" => config.cns_endpoint = Some(value()?),
+            "--ghostplane" => config.ghostplane_endpoint = Some(value()?),
+            "--json" => json = true,
+            "--config" => {
+                value()?;
+            }
+            other => return Err(EtherlinkError::Configuration(format!("Unknown option: {}\n{}", other, USAGE))),
+        }
+    }
+
+    let runtime = Etherlink::from_daemon_config(settings)?;
+    let report = runtime.preflight().await;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for check in &report.checks {
+            println!(
+                "[{:<4}] {:<12} {:<24} {}",
+                format!("{:?}", check.status).to_lowercase(),
+                check.category,
+                check.name,
+                check.detail
+            );
+            if let Some(fix) = &check.fix {
+                println!("{:>45} fix: {}", "", fix);
+            }
+        }
+        println!("\noverall: {:?}", report.status());
+    }
+
+    if report.status() == CheckStatus::Fail {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// `etherlink serve`: run the gRPC health and reflection server until Ctrl-C
+///
+/// `--probes` also serves HTTP `/healthz` and `/readyz` on the given address.
+///
+/// With `--config`, the file is watched and changes are applied without restarting;
+/// endpoint flags only override the file's values at startup. `--dns` (or a `dns`
+/// section in the file) also answers DNS queries for CNS names when built with the
+/// `dns-gateway` feature, and `--http-gateway` (or `http_gateway`) serves `.ghost`
+/// sites with the `http-gateway` feature.
+async fn serve(args: &[String]) -> etherlink::Result<()> {
+    let config_path = args.iter().position(|arg| arg == "--config").and_then(|i| args.get(i + 1)).cloned();
+    let mut settings = match &config_path {
+        Some(path) => DaemonConfig::load(path)?,
+        None => DaemonConfig::default(),
+    };
+    let log_filter = etherlink::init_with_reloadable_tracing(&settings.log_filter)?;
+
+    #[cfg(feature = "dns-gateway")]
+    let dns = &mut settings.dns;
+    #[cfg(feature = "http-gateway")]
+    let http_gateway = &mut settings.http_gateway;
+    let config = &mut settings.etherlink;
+    let mut server_config = ServerConfig::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| EtherlinkError::Configuration(format!("{} requires a value\n{}", arg, USAGE)))
+        };
+        match arg.as_str() {
+            "--listen" => {
+                server_config.listen_addr = value()?
+                    .parse()
+                    .map_err(|e| EtherlinkError::Configuration(format!("Invalid listen address: {}", e)))?
+            }
+            "--ghostd" => config.ghostd_endpoint = value()?,
+            "--cns" => config.cns_endpoint = Some(value()?),
+            "--ghostplane" => config.ghostplane_endpoint = Some(value()?),
+            "--no-reflection" => server_config.enable_reflection = false,
+            "--probes" => {
+                server_config.probe_addr = Some(
+                    value()?
+                        .parse()
+                        .map_err(|e| EtherlinkError::Configuration(format!("Invalid probe address: {}", e)))?,
+                )
+            }
+            #[cfg(feature = "dns-gateway")]
+            "--dns" => {
+                dns.get_or_insert_with(Default::default).listen_addr = value()?
+                    .parse()
+                    .map_err(|e| EtherlinkError::Configuration(format!("Invalid DNS address: {}", e)))?
+            }
+            #[cfg(feature = "http-gateway")]
+            "--http-gateway" => {
+                http_gateway.get_or_insert_with(Default::default).listen_addr = value()?
+                    .parse()
+                    .map_err(|e| EtherlinkError::Configuration(format!("Invalid HTTP gateway address: {}", e)))?
+            }
+            "--config" => {
+                value()?;
+            }
+            other => return Err(EtherlinkError::Configuration(format!("Unknown option: {}\n{}", other, USAGE))),
+        }
+    }
+
+    let runtime = Etherlink::from_daemon_config(settings)?.with_log_filter(log_filter);
+    runtime.start().await?;
+
+    let shutdown = async {
+        let _ = tokio::signal::ctrl_c().await;
+        info!("Received Ctrl-C, shutting down");
+    };
+    let reloads = async {
+        match config_path {
+            Some(path) => runtime.watch_config(ConfigWatcher::new(path)?).await,
+            None => std::future::pending().await,
+        }
+        Ok(())
+    };
+    let dns = async {
+        #[cfg(feature = "dns-gateway")]
+        if let Some(dns) = runtime.settings().dns {
+            return runtime.serve_dns(&dns, std::future::pending()).await;
+        }
+        std::future::pending().await
+    };
+    let http_gateway = async {
+        #[cfg(feature = "http-gateway")]
+        if let Some(gateway) = runtime.settings().http_gateway {
+            return runtime.serve_http_gateway(&gateway, std::future::pending()).await;
+        }
+        std::future::pending().await
+    };
+    let result = tokio::select! {
+        result = runtime.serve(&server_config, shutdown) => result,
+        result = reloads => result,
+        result = dns => result,
+        result = http_gateway => result,
+    };
+
+    runtime.shutdown(std::time::Duration::from_secs(5)).await?;
+    result
+}
+
+/// `etherlink export`: dump transactions, balance snapshots and domains of a block range
+///
+/// `--to-block` defaults to the current height. With `--resume`, an interrupted export
+/// in the same directory continues from its manifest.
+async fn export(args: &[String]) -> etherlink::Result<()> {
+    etherlink::init_with_tracing("etherlink=info")?;
+
+    let mut config = EtherlinkConfig::default();
+    let mut from_block = None;
+    let mut to_block = None;
+    let mut out_dir = "export".to_string();
+    let mut format = ExportFormat::Csv;
+    let mut blocks_per_part = None;
+    let mut resume = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| EtherlinkError::Configuration(format!("{} requires a value\n{}", arg, USAGE)))
+        };
+        let block = |value: String| value.parse::<u64>().map_err(|e| EtherlinkError::Configuration(format!("Invalid {} {}: {}", arg, value, e)));
+        match arg.as_str() {
+            "--from-block" => from_block = Some(block(value()?)?),
+            "--to-block" => to_block = Some(block(value()?)?),
+            "--blocks-per-part" => blocks_per_part = Some(block(value()?)?),
+            "--out" => out_dir = value()?,
+            "--format" => format = value()?.parse()?,
+            "--resume" => resume = true,
+            "--ghostd" => config.ghostd_endpoint = value()?,
+            "--cns" => config.cns_endpoint = Some(value()?),
+            other => return Err(EtherlinkError::Configuration(format!("Unknown option: {}\n{}", other, USAGE))),
+        }
+    }
+    let from_block = from_block.ok_or_else(|| EtherlinkError::Configuration(format!("--from-block is required\n{}", USAGE)))?;
+
+    let http_client = Arc::new(etherlink::clients::build_http_client(&config)?);
+    let services = etherlink::ServiceClients::new(&config, http_client);
+    let to_block = match to_block {
+        Some(height) => height,
+        None => services.ghostd.get_blockchain_height().await?,
+    };
+
+    let mut export_config = ExportConfig::new(&out_dir, from_block..=to_block).format(format).resume(resume);
+    if let Some(blocks) = blocks_per_part {
+        export_config = export_config.blocks_per_part(blocks);
+    }
+    let mut exporter = Exporter::new(services.ghostd.clone(), export_config);
+    if config.cns_endpoint.is_some() {
+        exporter = exporter.with_cns(services.cns.clone());
+    }
+
+    let manifest = exporter.run().await?;
+    println!(
+        "Exported blocks {}..={} to {} ({} files, schema v{})",
+        manifest.from_block, manifest.to_block, out_dir, manifest.files.len(), manifest.schema_version
+    );
+    Ok(())
+}
+
+/// `etherlink watch`: print (and optionally POST) matching activity of addresses and domains
+///
+/// `--rule` applies to the targets that follow it, e.g.
+/// `--rule "amount >= 1000" --address ghost1a --rule "*" --domain shop.ghost`.
+async fn watch(args: &[String]) -> etherlink::Result<()> {
+    etherlink::init_with_tracing("etherlink=warn")?;
+
+    let mut config = EtherlinkConfig::default();
+    let mut watcher = Watcher::new().with_sink(Arc::new(StdoutSink));
+    let mut targets = Vec::new();
+    let mut webhooks = Vec::new();
+    let mut rule = Rule::any();
+    let mut interval_ms = 2000;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| EtherlinkError::Configuration(format!("{} requires a value\n{}", arg, USAGE)))
+        };
+        match arg.as_str() {
+            "--address" => targets.push((WatchTarget::Address(etherlink::Address::new(value()?)), rule.clone())),
+            "--domain" => targets.push((WatchTarget::Domain(value()?), rule.clone())),
+            "--rule" => rule = value()?.parse()?,
+            "--webhook" => webhooks.push(value()?),
+            "--pinning" => config.pinning = Some(etherlink::PinningConfig::new(value()?)),
+            "--interval-ms" => {
+                interval_ms = value()?
+                    .parse()
+                    .map_err(|e| EtherlinkError::Configuration(format!("Invalid --interval-ms: {}", e)))?
+            }
+            "--ghostd" => config.ghostd_endpoint = value()?,
+            "--cns" => config.cns_endpoint = Some(value()?),
+            other => return Err(EtherlinkError::Configuration(format!("Unknown option: {}\n{}", other, USAGE))),
+        }
+    }
+    if targets.is_empty() {
+        return Err(EtherlinkError::Configuration(format!("Nothing to watch: pass --address or --domain\n{}", USAGE)));
+    }
+    for url in webhooks {
+        watcher = watcher.with_sink(Arc::new(WebhookSink::new(url, &config)?));
+    }
+    for (target, rule) in targets {
+        match target {
+            WatchTarget::Address(address) => watcher.watch_address(address, rule),
+            WatchTarget::Domain(domain) => watcher.watch_domain(domain, rule),
+        };
+    }
+
+    let http_client = Arc::new(etherlink::clients::build_http_client(&config)?);
+    let services = etherlink::ServiceClients::new(&config, http_client);
+    if let Some(pinning) = services.pinning.clone() {
+        watcher = watcher.with_pinning(pinning);
+    }
+    let mut interval = tokio::time::interval(std::time::Duration::from_millis(interval_ms));
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = interval.tick() => {
+                if let Err(e) = watcher.poll(&services.ghostd, Some(&services.cns)).await {
+                    error!("Watch poll failed: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// `etherlink validator`: print a validator's status, uptime and slashing history
+async fn validator(args: &[String]) -> etherlink::Result<()> {
+    etherlink::init_with_tracing("etherlink=warn")?;
+
+    let mut config = EtherlinkConfig::default();
+    let mut json = false;
+    let mut window = 1000;
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| EtherlinkError::Configuration(format!("{} requires a value\n{}", arg, USAGE)))
+        };
+        match arg.as_str() {
+            "--window" => {
+                window = value()?
+                    .parse()
+                    .map_err(|e| EtherlinkError::Configuration(format!("Invalid --window: {}", e)))?
+            }
+            "--ghostd" => config.ghostd_endpoint = value()?,
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                return Err(EtherlinkError::Configuration(format!("Unknown option: {}\n{}", other, USAGE)));
+            }
+            other => positional.push(other.to_string()),
+        }
+    }
+    let [command, address] = positional.as_slice() else {
+        return Err(EtherlinkError::Configuration(format!("Expected a subcommand and an address\n{}", USAGE)));
+    };
+    let address = etherlink::Address::new(address.clone());
+    let client = etherlink::ServiceClients::from_config(&config)?.validator;
+
+    match command.as_str() {
+        "status" => {
+            let (info, attestation, missed) = tokio::try_join!(
+                client.get_validator(&address),
+                client.get_attestation_status(&address),
+                client.get_missed_blocks(&address, window),
+            )?;
+            if json {
+                let report = serde_json::json!({ "validator": info, "attestation": attestation, "missed_blocks": missed });
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{} ({})", info.moniker, info.address);
+                println!("status:      {:?}", info.status);
+                println!("stake:       {}", info.bonded_stake);
+                println!("commission:  {:.2}%", info.commission_bps as f64 / 100.0);
+                println!("attested:    {} (epoch {}, participation {:.1}%)", attestation.attested, attestation.epoch, attestation.participation_rate * 100.0);
+                println!("uptime:      {:.2}% ({} missed of last {} blocks)", missed.uptime() * 100.0, missed.missed, missed.window_blocks);
+                if let Some(height) = info.jailed_until {
+                    println!("jailed until block {}", height);
+                }
+            }
+        }
+        "slashing" => {
+            let events = client.get_slashing_events(&address).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&events)?);
+            } else if events.is_empty() {
+                println!("No slashing events for {}", address);
+            } else {
+                println!("{:>10}  {:<20} {:>12}  JAILED UNTIL", "HEIGHT", "REASON", "AMOUNT");
+                for event in &events {
+                    println!(
+                        "{:>10}  {:<20} {:>12}  {}",
+                        event.height,
+                        format!("{:?}", event.reason),
+                        event.amount,
+                        event.jailed_until.map_or("-".to_string(), |height| height.to_string()),
+                    );
+                }
+            }
+        }
+        other => return Err(EtherlinkError::Configuration(format!("Unknown validator command: {}\n{}", other, USAGE))),
+    }
+    Ok(())
+}
+
+/// `etherlink snapshot fetch`: download a verified state snapshot, resuming a partial file
+async fn snapshot(args: &[String]) -> etherlink::Result<()> {
+    etherlink::init_with_tracing("etherlink=warn")?;
+
+    if args.first().map(String::as_str) != Some("fetch") {
+        return Err(EtherlinkError::Configuration(format!("Expected `snapshot fetch`\n{}", USAGE)));
+    }
+    let mut config = EtherlinkConfig::default();
+    let mut height = None;
+    let mut out = None;
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| EtherlinkError::Configuration(format!("{} requires a value\n{}", arg, USAGE)))
+        };
+        match arg.as_str() {
+            "--height" => {
+                height = Some(value()?
+                    .parse()
+                    .map_err(|e| EtherlinkError::Configuration(format!("Invalid --height: {}", e)))?)
+            }
+            "--out" => out = Some(std::path::PathBuf::from(value()?)),
+            "--ghostd" => config.ghostd_endpoint = value()?,
+            other => return Err(EtherlinkError::Configuration(format!("Unknown option: {}\n{}", other, USAGE))),
+        }
+    }
+
+    let ghostd = etherlink::ServiceClients::from_config(&config)?.ghostd;
+    let manifest = match height {
+        Some(height) => ghostd.get_snapshot_manifest(height).await?,
+        None => ghostd.latest_snapshot().await?,
+    };
+    let out = out.unwrap_or_else(|| format!("snapshot-{}.bin", manifest.height).into());
+    let io_error = |e: std::io::Error| EtherlinkError::Configuration(format!("Failed to open {}: {}", out.display(), e));
+
+    // Drop any partial trailing chunk; the rest is checked again before resuming
+    let existing = std::fs::metadata(&out).map(|meta| meta.len()).unwrap_or(0);
+    let resume_from = manifest.resume_offset(existing);
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .truncate(false)
+        .open(&out)
+        .map_err(io_error)?;
+    file.set_len(resume_from).map_err(io_error)?;
+    let mut file = tokio::fs::File::from_std(file);
+    if resume_from > 0 {
+        eprintln!("Resuming {} at {} of {} bytes", out.display(), resume_from, manifest.size_bytes);
+    }
+
+    ghostd
+        .download_snapshot(manifest.height, &mut file, resume_from, |progress| {
+            eprint!(
+                "\rheight {}: {}/{} bytes ({:.1}%)",
+                progress.height,
+                progress.downloaded_bytes,
+                progress.total_bytes,
+                progress.downloaded_bytes as f64 * 100.0 / progress.total_bytes.max(1) as f64,
+            );
+        })
+        .await?;
+    eprintln!();
+    println!("{} (height {}, state root {})", out.display(), manifest.height, manifest.state_root);
+    Ok(())
+}
+
+async fn demo() -> etherlink::Result<()> {
     // Initialize tracing
     etherlink::init_with_tracing("etherlink=debug")?;
 
@@ -35,7 +548,7 @@ async fn main() -> etherlink::Result<()> {
     }
 
     // Create GhostPlane client
-    let mut ghostplane_client = GhostPlaneClient::with_defaults();
+    let ghostplane_client = GhostPlaneClient::with_defaults();
     match ghostplane_client.initialize().await {
         Ok(_) => info!("Successfully initialized GhostPlane client"),
         Err(e) => {
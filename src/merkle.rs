@@ -0,0 +1,103 @@
+//! Merkle trees over transaction hashes
+//!
+//! Leaves and inner nodes are SHA-256 hashes with distinct one-byte prefixes, so a
+//! leaf can never be passed off as an inner node. A node without a sibling is carried
+//! up to the next level unchanged instead of being paired with itself. Roots and
+//! proof hashes are `0x`-prefixed hex, matching [`crate::ghostplane::BatchInfo::merkle_root`].
+
+use crate::TxHash;
+use crate::hash::{self, Hash, HashAlgorithm, Hasher};
+use serde::{Deserialize, Serialize};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+fn hash_leaf(tx_hash: &TxHash) -> Hash {
+    let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+    hasher.update([LEAF_PREFIX]).update(tx_hash.as_str().as_bytes());
+    hasher.finalize()
+}
+
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+    hasher.update([NODE_PREFIX]).update(left).update(right);
+    hasher.finalize()
+}
+
+fn from_hex(hash: &str) -> Option<Hash> {
+    hex::decode(hash.trim_start_matches("0x")).ok()?.try_into().ok()
+}
+
+fn next_level(level: &[Hash]) -> Vec<Hash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Root of the tree over `leaves`; the hash of nothing for an empty list
+pub fn merkle_root(leaves: &[TxHash]) -> String {
+    if leaves.is_empty() {
+        return hash::to_hex(&Hasher::digest(HashAlgorithm::Sha256, []));
+    }
+    let mut level: Vec<Hash> = leaves.iter().map(hash_leaf).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    hash::to_hex(&level[0])
+}
+
+/// One sibling on the path from a leaf to the root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleStep {
+    pub hash: String,
+    /// Whether the sibling is hashed in on the left
+    pub left: bool,
+}
+
+/// Path proving a leaf is part of a tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub leaf_index: usize,
+    pub steps: Vec<MerkleStep>,
+}
+
+impl MerkleProof {
+    /// Build the proof for the leaf at `index`
+    pub fn build(leaves: &[TxHash], index: usize) -> Option<Self> {
+        if index >= leaves.len() {
+            return None;
+        }
+        let mut level: Vec<Hash> = leaves.iter().map(hash_leaf).collect();
+        let mut position = index;
+        let mut steps = Vec::new();
+        while level.len() > 1 {
+            let sibling = position ^ 1;
+            if sibling < level.len() {
+                steps.push(MerkleStep { hash: hash::to_hex(&level[sibling]), left: sibling < position });
+            }
+            level = next_level(&level);
+            position /= 2;
+        }
+        Some(Self { leaf_index: index, steps })
+    }
+
+    /// Root implied by this proof for `tx_hash`, or `None` if a step is malformed
+    pub fn compute_root(&self, tx_hash: &TxHash) -> Option<String> {
+        let mut node = hash_leaf(tx_hash);
+        for step in &self.steps {
+            let sibling = from_hex(&step.hash)?;
+            node = if step.left { hash_node(&sibling, &node) } else { hash_node(&node, &sibling) };
+        }
+        Some(hash::to_hex(&node))
+    }
+
+    /// Whether this proof links `tx_hash` to `root`
+    pub fn verify(&self, tx_hash: &TxHash, root: &str) -> bool {
+        self.compute_root(tx_hash).is_some_and(|computed| computed.eq_ignore_ascii_case(root))
+    }
+}
@@ -0,0 +1,295 @@
+//! Encrypted messaging between identities (DID to DID)
+//!
+//! A message is sealed to the recipient DID's key-agreement key with
+//! [`CryptoProvider::seal`] (X25519 key wrapping, ChaCha20-Poly1305 payload) and the
+//! sender identity signs the resulting [`Envelope`]. Envelopes travel through a relay
+//! that only ever sees ciphertext. This is the transport behind the CNS `Web5`
+//! service type.
+
+use crate::auth::crypto::{CryptoProvider, EncryptedEnvelope, KeyPair, SymmetricAlgorithm};
+use crate::clients::ApiResponse;
+use crate::clients::context::WithContext;
+use crate::clients::gid::{GidClient, IdentityDocument, VerificationMethod};
+use crate::{EtherlinkError, Result};
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tracing::warn;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// Envelope format version
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Multicodec prefix of an X25519 public key in `publicKeyMultibase`
+pub const X25519_MULTICODEC: [u8; 2] = [0xec, 0x01];
+/// Multicodec prefix of an Ed25519 public key in `publicKeyMultibase`
+pub const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// X25519 key an identity publishes under `keyAgreement` to receive messages
+#[derive(Clone)]
+pub struct KeyAgreementKey {
+    secret: StaticSecret,
+}
+
+impl KeyAgreementKey {
+    /// Generate a new key
+    pub fn generate() -> Self {
+        Self { secret: StaticSecret::from(rand::random::<[u8; 32]>()) }
+    }
+
+    /// Restore a key from its 32 secret bytes
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self { secret: StaticSecret::from(bytes) }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        PublicKey::from(&self.secret).to_bytes()
+    }
+
+    /// Public key as published in a DID document (`z` + base58btc, multicodec-tagged)
+    pub fn public_key_multibase(&self) -> String {
+        encode_multibase(&X25519_MULTICODEC, &self.public_key())
+    }
+}
+
+impl fmt::Debug for KeyAgreementKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyAgreementKey")
+            .field("public_key", &hex::encode(self.public_key()))
+            .finish()
+    }
+}
+
+/// The local identity: its DID, signing key and key-agreement key
+#[derive(Debug, Clone)]
+pub struct MessagingIdentity {
+    pub did: String,
+    /// Ed25519 key listed under the DID's `authentication`
+    pub signing_key: KeyPair,
+    pub agreement_key: KeyAgreementKey,
+}
+
+impl MessagingIdentity {
+    pub fn new(did: impl Into<String>, signing_key: KeyPair, agreement_key: KeyAgreementKey) -> Self {
+        Self { did: did.into(), signing_key, agreement_key }
+    }
+}
+
+/// Encrypted, signed message as stored by the relay
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u8,
+    pub id: String,
+    /// Sender DID
+    pub from: String,
+    /// Recipient DID
+    pub to: String,
+    /// Verification method of the recipient the message is sealed to
+    pub recipient_key_id: String,
+    /// Message body sealed to the recipient's key-agreement key
+    pub payload: EncryptedEnvelope,
+    /// Unix seconds
+    pub sent_at: u64,
+    /// Hex signature by the sender's authentication key over [`Envelope::signing_payload`]
+    pub signature: String,
+}
+
+impl Envelope {
+    /// Bytes covered by the sender signature: the canonical encoding with `signature` unset
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let unsigned = Envelope { signature: String::new(), ..self.clone() };
+        crate::canonical::to_bytes(&unsigned).unwrap_or_default()
+    }
+}
+
+/// Decrypted message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedMessage {
+    pub id: String,
+    pub from: String,
+    pub sent_at: u64,
+    pub payload: Vec<u8>,
+}
+
+/// Envelope that could not be opened, with the reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedEnvelope {
+    pub id: String,
+    pub from: String,
+    pub error: String,
+}
+
+/// Result of one [`Messenger::receive`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inbox {
+    pub messages: Vec<ReceivedMessage>,
+    /// Envelopes whose sender could not be resolved or that failed to verify or decrypt
+    pub rejected: Vec<RejectedEnvelope>,
+}
+
+/// Sends and receives encrypted messages through a relay
+#[derive(Debug, Clone)]
+pub struct Messenger {
+    identity: MessagingIdentity,
+    gid: GidClient,
+    http_client: Arc<HttpClient>,
+    relay_url: String,
+}
+
+impl Messenger {
+    /// Messenger resolving DIDs through `gid` and relaying via `relay_endpoint`
+    pub fn new(identity: MessagingIdentity, gid: GidClient, http_client: Arc<HttpClient>, relay_endpoint: &str) -> Self {
+        Self {
+            identity,
+            gid,
+            http_client,
+            relay_url: format!("{}/api/v1", relay_endpoint.trim_end_matches('/')),
+        }
+    }
+
+    pub fn identity(&self) -> &MessagingIdentity {
+        &self.identity
+    }
+
+    /// Encrypt `payload` to the key-agreement key in `recipient` and sign it
+    pub fn seal(&self, recipient: &IdentityDocument, payload: &[u8]) -> Result<Envelope> {
+        let method = find_method(recipient, &recipient.key_agreement)
+            .ok_or_else(|| EtherlinkError::Crypto(format!("{} has no key agreement key", recipient.id)))?;
+        let recipient_key = decode_multibase(&method.public_key_multibase, &X25519_MULTICODEC)?;
+
+        let provider = CryptoProvider::new();
+        let id = uuid::Uuid::new_v4().to_string();
+        let aad = associated_data(ENVELOPE_VERSION, &id, &self.identity.did, &recipient.id);
+        let mut envelope = Envelope {
+            version: ENVELOPE_VERSION,
+            id,
+            from: self.identity.did.clone(),
+            to: recipient.id.clone(),
+            recipient_key_id: method.id.clone(),
+            payload: provider.seal(SymmetricAlgorithm::ChaCha20Poly1305, &[recipient_key], payload, &aad)?,
+            sent_at: chrono::Utc::now().timestamp() as u64,
+            signature: String::new(),
+        };
+
+        let key = &self.identity.signing_key;
+        envelope.signature = provider.sign_message(&envelope.signing_payload(), key.private_key.expose_secret(), &key.algorithm)?;
+        Ok(envelope)
+    }
+
+    /// Verify `envelope` against the sender's document and decrypt it with our agreement key
+    pub fn open(&self, envelope: &Envelope, sender: &IdentityDocument) -> Result<ReceivedMessage> {
+        if envelope.version != ENVELOPE_VERSION {
+            return Err(EtherlinkError::Crypto(format!("Unsupported envelope version {}", envelope.version)));
+        }
+        if envelope.to != self.identity.did {
+            return Err(EtherlinkError::Crypto(format!("Envelope is addressed to {}", envelope.to)));
+        }
+        if envelope.from != sender.id {
+            return Err(EtherlinkError::Crypto(format!("Envelope is from {}, not {}", envelope.from, sender.id)));
+        }
+        verify_sender(envelope, sender)?;
+
+        let payload = CryptoProvider::new()
+            .open(&envelope.payload, &self.identity.agreement_key.to_bytes(), &associated_data(envelope.version, &envelope.id, &envelope.from, &envelope.to))?;
+
+        Ok(ReceivedMessage { id: envelope.id.clone(), from: envelope.from.clone(), sent_at: envelope.sent_at, payload })
+    }
+
+    /// Resolve `to`, seal `payload` and hand it to the relay; returns the message id
+    pub async fn send(&self, to: &str, payload: &[u8]) -> Result<String> {
+        let recipient = self.gid.resolve_identity(to).await?;
+        let envelope = self.seal(&recipient, payload)?;
+        let url = format!("{}/messages", self.relay_url);
+        let response: ApiResponse<serde_json::Value> = self.http_client
+            .post(&url)
+            .json(&envelope)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()?;
+        Ok(envelope.id)
+    }
+
+    /// Fetch our inbox from the relay and open every envelope
+    ///
+    /// Each sender DID is resolved to check its signature. An envelope that cannot be
+    /// resolved, verified or decrypted is reported in [`Inbox::rejected`] and the rest
+    /// are still delivered; only a failed relay fetch fails the call.
+    pub async fn receive(&self) -> Result<Inbox> {
+        let url = format!("{}/messages/{}", self.relay_url, self.identity.did);
+        let response: ApiResponse<Vec<Envelope>> = self.http_client
+            .get(&url)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        let mut inbox = Inbox::default();
+        for envelope in response.into_result()? {
+            let opened = match self.gid.resolve_identity(&envelope.from).await {
+                Ok(sender) => self.open(&envelope, &sender),
+                Err(e) => Err(e),
+            };
+            match opened {
+                Ok(message) => inbox.messages.push(message),
+                Err(e) => {
+                    warn!("Rejected envelope {} from {}: {}", envelope.id, envelope.from, e);
+                    inbox.rejected.push(RejectedEnvelope { id: envelope.id, from: envelope.from, error: e.to_string() });
+                }
+            }
+        }
+        Ok(inbox)
+    }
+}
+
+/// Check the envelope signature against any of the sender's authentication keys
+fn verify_sender(envelope: &Envelope, sender: &IdentityDocument) -> Result<()> {
+    let payload = envelope.signing_payload();
+    let provider = CryptoProvider::new();
+    for method in sender.verification_method.iter().filter(|m| sender.authentication.contains(&m.id)) {
+        let Ok(public_key) = decode_multibase(&method.public_key_multibase, &ED25519_MULTICODEC) else {
+            continue;
+        };
+        if provider.verify_signature(&payload, &envelope.signature, &hex::encode(public_key), &crate::CryptoAlgorithm::Ed25519)? {
+            return Ok(());
+        }
+    }
+    Err(EtherlinkError::Crypto(format!("Envelope is not signed by an authentication key of {}", sender.id)))
+}
+
+/// Additional data bound into the payload so the ciphertext cannot be re-addressed
+fn associated_data(version: u8, id: &str, from: &str, to: &str) -> Vec<u8> {
+    format!("{}\n{}\n{}\n{}", version, id, from, to).into_bytes()
+}
+
+/// Verification method referenced by one of `ids`
+fn find_method<'a>(doc: &'a IdentityDocument, ids: &[String]) -> Option<&'a VerificationMethod> {
+    ids.iter().find_map(|id| doc.verification_method.iter().find(|m| &m.id == id))
+}
+
+/// `z` + base58btc of the multicodec prefix and key
+pub fn encode_multibase(codec: &[u8; 2], key: &[u8; 32]) -> String {
+    format!("z{}", bs58::encode([codec.as_slice(), key.as_slice()].concat()).into_string())
+}
+
+/// Decode a 32-byte key from `z` (base58btc) or `f` (hex) multibase, stripping `codec` if present
+fn decode_multibase(value: &str, codec: &[u8; 2]) -> Result<[u8; 32]> {
+    let bytes = match value.split_at_checked(1) {
+        Some(("z", rest)) => bs58::decode(rest).into_vec().map_err(|e| EtherlinkError::Crypto(format!("Invalid base58 key: {}", e)))?,
+        Some(("f", rest)) => hex::decode(rest).map_err(|e| EtherlinkError::Crypto(format!("Invalid hex key: {}", e)))?,
+        _ => return Err(EtherlinkError::Crypto(format!("Unsupported multibase key {}", value))),
+    };
+    let key = bytes.strip_prefix(codec.as_slice()).unwrap_or(&bytes);
+    key.try_into().map_err(|_| EtherlinkError::Crypto(format!("Expected a 32-byte key, got {} bytes", key.len())))
+}
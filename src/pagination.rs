@@ -0,0 +1,177 @@
+//! Lazy, deadline-aware iteration over paginated APIs
+//!
+//! [`PageStream`] turns a page fetcher into a `Stream` of items. The next page is
+//! only requested once the consumer has drained the current one, so a slow consumer
+//! never has more than one page buffered. Every paginated listing (domains by owner,
+//! token history, block ranges) is exposed this way so they share the same deadline
+//! and resume behaviour.
+
+use crate::{EtherlinkError, Result};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Position of the next item to be yielded, for resuming an interrupted listing
+///
+/// For offset-based listings this is the item index; for block ranges it is the height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub position: u64,
+}
+
+/// Paging behaviour shared by all paginated listings
+#[derive(Debug, Clone)]
+pub struct PageConfig {
+    /// Items requested per page; a shorter page ends the listing
+    pub page_size: u32,
+    /// Budget for the whole listing; once spent no further pages are fetched
+    pub deadline: Option<Duration>,
+    /// Continue from a checkpoint instead of the start of the listing
+    pub resume_from: Option<Checkpoint>,
+}
+
+impl Default for PageConfig {
+    fn default() -> Self {
+        Self {
+            page_size: 100,
+            deadline: None,
+            resume_from: None,
+        }
+    }
+}
+
+impl PageConfig {
+    /// Set the number of items requested per page
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Bound the whole listing by a total deadline
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Resume from a previously saved checkpoint
+    pub fn resume_from(mut self, checkpoint: Checkpoint) -> Self {
+        self.resume_from = Some(checkpoint);
+        self
+    }
+}
+
+/// Lazy stream of items fetched page by page
+///
+/// Yields `Err(EtherlinkError::Timeout)` and ends if the deadline passes while a page
+/// is outstanding; items already fetched are still yielded first. After an error,
+/// [`PageStream::checkpoint`] points at the first item not yet yielded.
+pub struct PageStream<T> {
+    inner: Pin<Box<dyn Stream<Item = Result<T>> + Send>>,
+    position: Arc<AtomicU64>,
+}
+
+struct PageState<F> {
+    fetch: F,
+    /// Position just past the last fetched item
+    position: u64,
+    page_size: u32,
+    deadline: Option<Instant>,
+    done: bool,
+}
+
+impl<T: Send + 'static> PageStream<T> {
+    /// Create a stream starting at `start` unless `config` resumes from a checkpoint
+    ///
+    /// `fetch(position, page_size)` returns up to `page_size` items starting at `position`.
+    pub fn new<F, Fut>(start: u64, config: PageConfig, fetch: F) -> Self
+    where
+        F: Fn(u64, u32) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Vec<T>>> + Send + 'static,
+    {
+        let start = config.resume_from.map_or(start, |checkpoint| checkpoint.position);
+        let position = Arc::new(AtomicU64::new(start));
+        let page_size = config.page_size.max(1);
+        let deadline = config.deadline.map(|deadline| Instant::now() + deadline);
+        let shared = position.clone();
+
+        let state = (
+            PageState { fetch, position: start, page_size, deadline, done: false },
+            VecDeque::<T>::new(),
+        );
+        let inner = futures::stream::unfold(state, move |(mut state, mut items)| {
+            let shared = shared.clone();
+            async move {
+                if items.is_empty() {
+                    if state.done {
+                        return None;
+                    }
+                    match state.next_page().await {
+                        Ok(page) => {
+                            state.position += page.len() as u64;
+                            items = page.into();
+                        }
+                        Err(e) => {
+                            state.done = true;
+                            return Some((Err(e), (state, items)));
+                        }
+                    }
+                }
+
+                let item = items.pop_front()?;
+                shared.store(state.position - items.len() as u64, Ordering::SeqCst);
+                Some((Ok(item), (state, items)))
+            }
+        });
+
+        Self { inner: Box::pin(inner), position }
+    }
+}
+
+impl<T> PageStream<T> {
+    /// Where to resume from to continue after the last yielded item
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint { position: self.position.load(Ordering::SeqCst) }
+    }
+}
+
+impl<F> PageState<F> {
+    async fn next_page<T, Fut>(&mut self) -> Result<Vec<T>>
+    where
+        F: Fn(u64, u32) -> Fut,
+        Fut: Future<Output = Result<Vec<T>>>,
+    {
+        let fetch = (self.fetch)(self.position, self.page_size);
+        let page = match self.deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, fetch)
+                .await
+                .map_err(|_| EtherlinkError::Timeout(format!("Listing deadline passed at position {}", self.position)))??,
+            None => fetch.await?,
+        };
+
+        if page.len() < self.page_size as usize {
+            self.done = true;
+        }
+        Ok(page)
+    }
+}
+
+impl<T> Stream for PageStream<T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl<T> std::fmt::Debug for PageStream<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PageStream").field("checkpoint", &self.checkpoint()).finish_non_exhaustive()
+    }
+}
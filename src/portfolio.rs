@@ -0,0 +1,396 @@
+//! Portfolio valuation and profit and loss
+//!
+//! [`Portfolio`] replays an address's GLEDGER transaction history up to a point in
+//! time and prices it with a [`PriceOracle`]. Every token received opens a lot at
+//! the market price of the moment it arrived; every token sent closes lots in
+//! [`LotMethod`] order, realizing the difference between the price it left at and
+//! the lots' cost. A fee paid on a transfer closes lots too, without proceeds, so its
+//! cost counts toward the disposal's cost basis. What is still held is valued at the
+//! price of the valuation time, giving the unrealized PnL. A [`PnlReport`] can be
+//! written as CSV for accounting.
+//!
+//! [`Portfolio::value`] only needs the balances, so it asks the oracle for prices at
+//! the valuation time alone.
+//!
+//! Prices are quote units per token unit, and all amounts are in the token's
+//! smallest unit, so values are exact integers.
+
+use crate::clients::gledger::{GledgerClient, TokenTransaction};
+use crate::pagination::PageConfig;
+use crate::{Address, EtherlinkError, Result, TokenType};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Source of token prices at points in time
+#[async_trait::async_trait]
+pub trait PriceOracle: Send + Sync + fmt::Debug {
+    /// Price of one unit of `token`, in quote units, at unix time `at`
+    async fn price(&self, token: &TokenType, at: u64) -> Result<u64>;
+}
+
+/// Price history held in memory, e.g. loaded from a market data export
+///
+/// The price at a time is the last one recorded at or before it.
+#[derive(Debug, Clone, Default)]
+pub struct PriceTable {
+    prices: HashMap<TokenType, BTreeMap<u64, u64>>,
+}
+
+impl PriceTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `token` traded at `price` from unix time `at`
+    pub fn with_price(mut self, token: TokenType, at: u64, price: u64) -> Self {
+        self.insert(token, at, price);
+        self
+    }
+
+    pub fn insert(&mut self, token: TokenType, at: u64, price: u64) {
+        self.prices.entry(token).or_default().insert(at, price);
+    }
+}
+
+#[async_trait::async_trait]
+impl PriceOracle for PriceTable {
+    async fn price(&self, token: &TokenType, at: u64) -> Result<u64> {
+        self.prices
+            .get(token)
+            .and_then(|history| history.range(..=at).next_back())
+            .map(|(_, price)| *price)
+            .ok_or_else(|| EtherlinkError::Portfolio(format!("No {} price known at {}", token, at)))
+    }
+}
+
+/// Which lots a disposal closes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LotMethod {
+    /// Oldest lots first
+    #[default]
+    Fifo,
+    /// Newest lots first
+    Lifo,
+}
+
+/// Tokens acquired together at one price
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lot {
+    pub token: TokenType,
+    pub amount: u64,
+    /// Price per unit when acquired
+    pub cost_price: u64,
+    pub acquired_at: u64,
+    pub tx_hash: String,
+}
+
+/// Part of a lot sent away, with the PnL it realized
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Disposal {
+    pub token: TokenType,
+    pub amount: u64,
+    /// `None` for tokens sent beyond what the history shows being received; their cost is zero
+    pub acquired_at: Option<u64>,
+    pub disposed_at: u64,
+    /// Units paid as the transfer's fee, whose cost is included in `cost_basis`
+    pub fee: u64,
+    pub cost_basis: u128,
+    pub proceeds: u128,
+    pub realized_pnl: i128,
+    pub tx_hash: String,
+}
+
+/// Open lots per token, closed in [`LotMethod`] order
+#[derive(Debug, Clone, Default)]
+pub struct LotBook {
+    method: LotMethod,
+    lots: HashMap<TokenType, VecDeque<Lot>>,
+}
+
+impl LotBook {
+    pub fn new(method: LotMethod) -> Self {
+        Self { method, lots: HashMap::new() }
+    }
+
+    /// Open a lot of `amount` bought at `price`
+    pub fn acquire(&mut self, token: TokenType, amount: u64, price: u64, at: u64, tx_hash: &str) {
+        if amount == 0 {
+            return;
+        }
+        self.lots.entry(token.clone()).or_default().push_back(Lot {
+            token,
+            amount,
+            cost_price: price,
+            acquired_at: at,
+            tx_hash: tx_hash.to_string(),
+        });
+    }
+
+    /// Close `amount` of `token` at `price`, plus `fee` units paid for the transfer,
+    /// returning one disposal per lot touched
+    ///
+    /// The fee closes lots after the amount and earns nothing; its cost is added to
+    /// the last disposal.
+    pub fn dispose(&mut self, token: &TokenType, amount: u64, fee: u64, price: u64, at: u64, tx_hash: &str) -> Vec<Disposal> {
+        let mut disposals: Vec<Disposal> = self
+            .take(token, amount)
+            .into_iter()
+            .map(|(taken, lot)| disposal(token, taken, lot, price, at, tx_hash))
+            .collect();
+        if fee == 0 {
+            return disposals;
+        }
+        let fee_cost: u128 = self
+            .take(token, fee)
+            .into_iter()
+            .map(|(taken, lot)| lot.map_or(0, |(cost_price, _)| taken as u128 * cost_price as u128))
+            .sum();
+        let mut last = disposals.pop().unwrap_or_else(|| disposal(token, 0, None, price, at, tx_hash));
+        last.fee = fee;
+        last.cost_basis += fee_cost;
+        last.realized_pnl -= fee_cost as i128;
+        disposals.push(last);
+        disposals
+    }
+
+    /// Remove `amount` of `token` from the open lots, returning the amount taken from
+    /// each lot with its cost price and acquisition time
+    ///
+    /// Whatever the lots do not cover comes last, with no lot.
+    fn take(&mut self, token: &TokenType, amount: u64) -> Vec<(u64, Option<(u64, u64)>)> {
+        let mut remaining = amount;
+        let mut taken_from = Vec::new();
+        let lots = self.lots.entry(token.clone()).or_default();
+        while remaining > 0 {
+            let lot = match self.method {
+                LotMethod::Fifo => lots.front_mut(),
+                LotMethod::Lifo => lots.back_mut(),
+            };
+            let Some(lot) = lot else {
+                break;
+            };
+            let taken = remaining.min(lot.amount);
+            taken_from.push((taken, Some((lot.cost_price, lot.acquired_at))));
+            lot.amount -= taken;
+            remaining -= taken;
+            if lot.amount == 0 {
+                match self.method {
+                    LotMethod::Fifo => lots.pop_front(),
+                    LotMethod::Lifo => lots.pop_back(),
+                };
+            }
+        }
+        if remaining > 0 {
+            taken_from.push((remaining, None));
+        }
+        taken_from
+    }
+
+    /// Lots still open, per token in acquisition order
+    pub fn open_lots(&self) -> Vec<Lot> {
+        let mut lots: Vec<Lot> = self.lots.values().flatten().cloned().collect();
+        lots.sort_by(|a, b| a.acquired_at.cmp(&b.acquired_at).then_with(|| a.token.symbol().cmp(b.token.symbol())));
+        lots
+    }
+}
+
+fn disposal(token: &TokenType, amount: u64, lot: Option<(u64, u64)>, price: u64, at: u64, tx_hash: &str) -> Disposal {
+    let cost_basis = lot.map_or(0, |(cost_price, _)| amount as u128 * cost_price as u128);
+    let proceeds = amount as u128 * price as u128;
+    Disposal {
+        token: token.clone(),
+        amount,
+        acquired_at: lot.map(|(_, acquired_at)| acquired_at),
+        disposed_at: at,
+        fee: 0,
+        cost_basis,
+        proceeds,
+        realized_pnl: proceeds as i128 - cost_basis as i128,
+        tx_hash: tx_hash.to_string(),
+    }
+}
+
+/// Holding of one token at the valuation time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Holding {
+    pub token: TokenType,
+    pub amount: u64,
+    pub price: u64,
+    pub value: u128,
+}
+
+/// Value of an address's holdings at a point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Valuation {
+    pub address: Address,
+    pub at: u64,
+    /// Tokens with a non-zero balance, by symbol
+    pub holdings: Vec<Holding>,
+    pub total_value: u128,
+}
+
+/// Realized and unrealized PnL of an address up to a point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PnlReport {
+    pub valuation: Valuation,
+    pub method: LotMethod,
+    pub disposals: Vec<Disposal>,
+    pub open_lots: Vec<Lot>,
+    pub realized_pnl: i128,
+    pub unrealized_pnl: i128,
+}
+
+impl PnlReport {
+    /// Write one row per disposal and per open lot
+    ///
+    /// Columns: `kind` (`realized` or `unrealized`), `token`, `amount`, `acquired_at`,
+    /// `disposed_at` (empty for open lots), `cost_basis`, `value` (proceeds, or
+    /// market value of the open lot), `pnl` and `tx_hash` (the disposal's, or the
+    /// lot's acquisition).
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<()> {
+        let csv_error = |e: csv::Error| EtherlinkError::Portfolio(format!("Failed to write CSV: {}", e));
+        let mut writer = csv::Writer::from_writer(writer);
+        writer
+            .write_record(["kind", "token", "amount", "acquired_at", "disposed_at", "cost_basis", "value", "pnl", "tx_hash"])
+            .map_err(csv_error)?;
+        for disposal in &self.disposals {
+            writer
+                .write_record([
+                    "realized".to_string(),
+                    disposal.token.symbol().to_string(),
+                    disposal.amount.to_string(),
+                    disposal.acquired_at.map(|at| at.to_string()).unwrap_or_default(),
+                    disposal.disposed_at.to_string(),
+                    disposal.cost_basis.to_string(),
+                    disposal.proceeds.to_string(),
+                    disposal.realized_pnl.to_string(),
+                    disposal.tx_hash.clone(),
+                ])
+                .map_err(csv_error)?;
+        }
+        for lot in &self.open_lots {
+            let price = self.valuation.holdings.iter().find(|h| h.token == lot.token).map_or(0, |h| h.price);
+            let cost_basis = lot.amount as u128 * lot.cost_price as u128;
+            let value = lot.amount as u128 * price as u128;
+            writer
+                .write_record([
+                    "unrealized".to_string(),
+                    lot.token.symbol().to_string(),
+                    lot.amount.to_string(),
+                    lot.acquired_at.to_string(),
+                    String::new(),
+                    cost_basis.to_string(),
+                    value.to_string(),
+                    (value as i128 - cost_basis as i128).to_string(),
+                    lot.tx_hash.clone(),
+                ])
+                .map_err(csv_error)?;
+        }
+        writer.flush().map_err(|e| EtherlinkError::Portfolio(format!("Failed to write CSV: {}", e)))
+    }
+}
+
+/// Values holdings and computes PnL from GLEDGER history and a [`PriceOracle`]
+#[derive(Debug, Clone)]
+pub struct Portfolio {
+    gledger: GledgerClient,
+    oracle: Arc<dyn PriceOracle>,
+    method: LotMethod,
+}
+
+impl Portfolio {
+    pub fn new(gledger: GledgerClient, oracle: Arc<dyn PriceOracle>) -> Self {
+        Self { gledger, oracle, method: LotMethod::default() }
+    }
+
+    /// Close lots in `method` order instead of FIFO
+    pub fn with_method(mut self, method: LotMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// What `address` held at unix time `at`, priced at that time
+    ///
+    /// Only prices at `at` are needed, not those of past transfers.
+    pub async fn value(&self, address: &Address, at: u64) -> Result<Valuation> {
+        let history = self.history(address, at).await?;
+        self.valuation(address, at, &history).await
+    }
+
+    /// Realized PnL of transfers up to unix time `at`, and unrealized PnL of what was
+    /// still held then
+    ///
+    /// Transfers between the address and itself only dispose of their fee.
+    pub async fn pnl(&self, address: &Address, at: u64) -> Result<PnlReport> {
+        let history = self.history(address, at).await?;
+        let valuation = self.valuation(address, at, &history).await?;
+
+        let mut book = LotBook::new(self.method);
+        let mut disposals = Vec::new();
+        for tx in &history {
+            let price = self.oracle.price(&tx.token_type, tx.timestamp).await?;
+            if tx.from != *address {
+                book.acquire(tx.token_type.clone(), tx.amount, price, tx.timestamp, &tx.tx_hash);
+            } else {
+                let sent = if tx.to == *address { 0 } else { tx.amount };
+                disposals.extend(book.dispose(&tx.token_type, sent, tx.fee, price, tx.timestamp, &tx.tx_hash));
+            }
+        }
+
+        let open_lots = book.open_lots();
+        let cost_basis: u128 = open_lots
+            .iter()
+            .filter(|lot| valuation.holdings.iter().any(|holding| holding.token == lot.token))
+            .map(|lot| lot.amount as u128 * lot.cost_price as u128)
+            .sum();
+        let realized_pnl = disposals.iter().map(|d| d.realized_pnl).sum();
+        let unrealized_pnl = valuation.total_value as i128 - cost_basis as i128;
+        Ok(PnlReport {
+            valuation,
+            method: self.method,
+            disposals,
+            open_lots,
+            realized_pnl,
+            unrealized_pnl,
+        })
+    }
+
+    /// Transfers of `address` up to unix time `at`, oldest first
+    async fn history(&self, address: &Address, at: u64) -> Result<Vec<TokenTransaction>> {
+        let mut history: Vec<TokenTransaction> = self
+            .gledger
+            .stream_transaction_history(address, PageConfig::default())
+            .try_collect()
+            .await?;
+        history.retain(|tx| tx.timestamp <= at && (tx.from != tx.to || tx.fee > 0));
+        history.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.block_height.cmp(&b.block_height)));
+        Ok(history)
+    }
+
+    /// Balances left by `history`, priced at `at`
+    async fn valuation(&self, address: &Address, at: u64, history: &[TokenTransaction]) -> Result<Valuation> {
+        let mut balances: BTreeMap<&'static str, (TokenType, u64)> = BTreeMap::new();
+        for tx in history {
+            let balance = &mut balances.entry(tx.token_type.symbol()).or_insert((tx.token_type.clone(), 0)).1;
+            if tx.to == *address {
+                *balance = balance.saturating_add(tx.amount);
+            }
+            if tx.from == *address {
+                *balance = balance.saturating_sub(tx.amount.saturating_add(tx.fee));
+            }
+        }
+
+        let mut holdings = Vec::new();
+        for (token, amount) in balances.into_values().filter(|(_, amount)| *amount > 0) {
+            let price = self.oracle.price(&token, at).await?;
+            holdings.push(Holding { token, amount, price, value: amount as u128 * price as u128 });
+        }
+        let total_value = holdings.iter().map(|h| h.value).sum();
+        Ok(Valuation { address: address.clone(), at, holdings, total_value })
+    }
+}
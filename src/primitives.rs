@@ -0,0 +1,195 @@
+//! Core types shared with embedded and WASM tooling
+//!
+//! Everything here depends only on `core`, `alloc`, `serde` and (optionally) `borsh`:
+//! no tokio, reqwest, tonic or `std`. Keep it that way so the module can be lifted
+//! into a standalone `no_std` crate without changes. The rest of the client re-exports
+//! these types through [`crate::types`] and [`crate::auth`].
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Address type for blockchain addresses
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+pub struct Address(pub String);
+
+impl Address {
+    pub fn new(addr: String) -> Self {
+        Self(addr)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Transaction hash type
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+pub struct TxHash(pub String);
+
+impl TxHash {
+    pub fn new(hash: String) -> Self {
+        Self(hash)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Block height type
+pub type BlockHeight = u64;
+
+/// Gas limit and gas used types
+pub type Gas = u64;
+
+/// Token amount in the token's smallest unit
+pub type Amount = u64;
+
+/// Token types supported by GhostChain
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+pub enum TokenType {
+    GCC,    // Gas & transaction fees
+    SPIRIT, // Governance & voting
+    MANA,   // Utility & rewards
+    GHOST,  // Brand & collectibles
+}
+
+impl TokenType {
+    /// Ticker symbol as used by the ledger APIs
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            TokenType::GCC => "GCC",
+            TokenType::SPIRIT => "SPIRIT",
+            TokenType::MANA => "MANA",
+            TokenType::GHOST => "GHOST",
+        }
+    }
+
+    /// Token with ticker `symbol`, ignoring case
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol.to_ascii_uppercase().as_str() {
+            "GCC" => Some(TokenType::GCC),
+            "SPIRIT" => Some(TokenType::SPIRIT),
+            "MANA" => Some(TokenType::MANA),
+            "GHOST" => Some(TokenType::GHOST),
+            _ => None,
+        }
+    }
+}
+
+/// GCC, the token fees are paid in
+impl Default for TokenType {
+    fn default() -> Self {
+        TokenType::GCC
+    }
+}
+
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+/// An amount of a specific token
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+pub struct TokenAmount {
+    pub token: TokenType,
+    pub amount: Amount,
+}
+
+impl TokenAmount {
+    pub fn new(token: TokenType, amount: Amount) -> Self {
+        Self { token, amount }
+    }
+
+    /// Add two amounts of the same token, `None` on overflow or token mismatch
+    pub fn checked_add(&self, other: &TokenAmount) -> Option<TokenAmount> {
+        if self.token != other.token {
+            return None;
+        }
+        self.amount.checked_add(other.amount).map(|amount| TokenAmount::new(self.token.clone(), amount))
+    }
+
+    /// Subtract an amount of the same token, `None` on underflow or token mismatch
+    pub fn checked_sub(&self, other: &TokenAmount) -> Option<TokenAmount> {
+        if self.token != other.token {
+            return None;
+        }
+        self.amount.checked_sub(other.amount).map(|amount| TokenAmount::new(self.token.clone(), amount))
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.token)
+    }
+}
+
+/// Cryptographic algorithm types
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+pub enum CryptoAlgorithm {
+    Ed25519,
+    Secp256k1,
+    Bls12381,
+}
+
+/// Raw signature bytes tagged with the algorithm that produced them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signature {
+    pub algorithm: CryptoAlgorithm,
+    pub bytes: Vec<u8>,
+}
+
+impl Signature {
+    pub fn new(algorithm: CryptoAlgorithm, bytes: Vec<u8>) -> Self {
+        Self { algorithm, bytes }
+    }
+}
+
+/// Why a [`Signer`] could not produce a signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SigningError {
+    /// The key material is malformed
+    InvalidKey(String),
+    /// The signer does not implement this algorithm
+    UnsupportedAlgorithm(CryptoAlgorithm),
+    /// The backend failed, e.g. a hardware token was removed
+    Backend(String),
+}
+
+impl fmt::Display for SigningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SigningError::InvalidKey(msg) => write!(f, "invalid key: {}", msg),
+            SigningError::UnsupportedAlgorithm(algorithm) => write!(f, "unsupported algorithm: {:?}", algorithm),
+            SigningError::Backend(msg) => write!(f, "signer backend error: {}", msg),
+        }
+    }
+}
+
+impl core::error::Error for SigningError {}
+
+/// Something that can sign messages with a single key
+pub trait Signer {
+    /// Algorithm of the underlying key
+    fn algorithm(&self) -> CryptoAlgorithm;
+
+    /// Raw public key bytes; fails if the key material is unreadable
+    fn public_key(&self) -> core::result::Result<Vec<u8>, SigningError>;
+
+    /// Sign a message
+    fn sign(&self, message: &[u8]) -> core::result::Result<Signature, SigningError>;
+}
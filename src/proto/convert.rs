@@ -0,0 +1,772 @@
+//! Conversions between domain types and their protobuf counterparts
+//!
+//! Conversions that can lose information or hit unset enum values are `TryFrom`
+//! and fail with [`EtherlinkError::Codec`]; the rest are plain `From`.
+
+use crate::auth::session::{Delegation, SessionMethod, SessionPolicy};
+use crate::clients::ghostd::Transaction;
+use crate::clients::gid::{
+    CreateIdentityRequest, Identity, IdentityDocument, IdentityType, PolicyDecision, PolicyRequest, ServiceEndpoint,
+    VerificationMethod,
+};
+use crate::clients::gledger::{TokenBalances, TokenBurn, TokenMint, TokenTransaction, TokenTransfer};
+use crate::clients::gsig::{AlgorithmInfo, SignRequest, SignatureResponse, VerificationResult, VerifyRequest};
+use crate::clients::walletd::{
+    CreateWalletRequest, CryptoAlgorithm, SignTransactionRequest, SignedTransaction, WalletAddress, WalletInfo,
+};
+use crate::cns::{ChangeEventType, DomainChangeEvent, DomainResolution, RecordSet, ServiceType};
+use crate::ghostplane::{L2AccountState, L2Transaction};
+use crate::proto::{
+    cns::v1 as cns_pb, ghostchain::v1 as ghostchain_pb, ghostplane::v1 as ghostplane_pb, gid::v1 as gid_pb,
+    gledger::v1 as gledger_pb, gsig::v1 as gsig_pb, walletd::v1 as walletd_pb,
+};
+use crate::primitives::CryptoAlgorithm as SignatureAlgorithm;
+use crate::{Address, EtherlinkError, Result, TokenType};
+use std::collections::HashMap;
+
+fn empty_to_none(value: String) -> Option<String> {
+    if value.is_empty() { None } else { Some(value) }
+}
+
+// Token types
+
+impl From<TokenType> for ghostchain_pb::TokenType {
+    fn from(token: TokenType) -> Self {
+        match token {
+            TokenType::GCC => ghostchain_pb::TokenType::Gcc,
+            TokenType::SPIRIT => ghostchain_pb::TokenType::Spirit,
+            TokenType::MANA => ghostchain_pb::TokenType::Mana,
+            TokenType::GHOST => ghostchain_pb::TokenType::Ghost,
+        }
+    }
+}
+
+impl TryFrom<ghostchain_pb::TokenType> for TokenType {
+    type Error = EtherlinkError;
+
+    fn try_from(token: ghostchain_pb::TokenType) -> Result<Self> {
+        match token {
+            ghostchain_pb::TokenType::Gcc => Ok(TokenType::GCC),
+            ghostchain_pb::TokenType::Spirit => Ok(TokenType::SPIRIT),
+            ghostchain_pb::TokenType::Mana => Ok(TokenType::MANA),
+            ghostchain_pb::TokenType::Ghost => Ok(TokenType::GHOST),
+            ghostchain_pb::TokenType::Unspecified => Err(EtherlinkError::Codec("Token type is unspecified".to_string())),
+        }
+    }
+}
+
+fn token_type(value: i32) -> Result<TokenType> {
+    ghostchain_pb::TokenType::try_from(value)
+        .map_err(|_| EtherlinkError::Codec(format!("Unknown token type: {}", value)))?
+        .try_into()
+}
+
+// Layer 1 transactions
+
+impl TryFrom<Transaction> for ghostchain_pb::Transaction {
+    type Error = EtherlinkError;
+
+    /// Fails if the signature is not hex-encoded
+    fn try_from(tx: Transaction) -> Result<Self> {
+        let signature = match &tx.signature {
+            Some(signature) => hex::decode(signature.trim_start_matches("0x"))
+                .map_err(|e| EtherlinkError::Codec(format!("Invalid transaction signature: {}", e)))?,
+            None => Vec::new(),
+        };
+
+        let tx_type = if tx.data.as_ref().is_some_and(|data| !data.is_empty()) {
+            ghostchain_pb::TransactionType::ContractCall
+        } else {
+            ghostchain_pb::TransactionType::Transfer
+        };
+
+        Ok(Self {
+            from: tx.from.0,
+            to: tx.to.0,
+            value: tx.amount,
+            data: tx.data.unwrap_or_default(),
+            gas_limit: tx.gas_limit,
+            gas_price: tx.gas_price,
+            nonce: tx.nonce,
+            tx_type: tx_type as i32,
+            token_type: ghostchain_pb::TokenType::from(tx.token_type) as i32,
+            signature,
+            delegation: tx.delegation.map(Into::into),
+            ..Default::default()
+        })
+    }
+}
+
+impl TryFrom<ghostchain_pb::Transaction> for Transaction {
+    type Error = EtherlinkError;
+
+    /// The signature comes back as `0x`-prefixed hex; fails on an unknown token type
+    /// or a malformed delegation
+    fn try_from(tx: ghostchain_pb::Transaction) -> Result<Self> {
+        Ok(Self {
+            from: Address::new(tx.from),
+            to: Address::new(tx.to),
+            amount: tx.value,
+            gas_limit: tx.gas_limit,
+            gas_price: tx.gas_price,
+            nonce: tx.nonce,
+            data: if tx.data.is_empty() { None } else { Some(tx.data) },
+            signature: if tx.signature.is_empty() { None } else { Some(format!("0x{}", hex::encode(tx.signature))) },
+            delegation: tx.delegation.map(Delegation::try_from).transpose()?,
+            token_type: token_type(tx.token_type)?,
+        })
+    }
+}
+
+// Session delegations
+
+impl From<SignatureAlgorithm> for ghostchain_pb::SignatureAlgorithm {
+    fn from(algorithm: SignatureAlgorithm) -> Self {
+        match algorithm {
+            SignatureAlgorithm::Ed25519 => ghostchain_pb::SignatureAlgorithm::Ed25519,
+            SignatureAlgorithm::Secp256k1 => ghostchain_pb::SignatureAlgorithm::Secp256k1,
+            SignatureAlgorithm::Bls12381 => ghostchain_pb::SignatureAlgorithm::Bls12381,
+        }
+    }
+}
+
+impl TryFrom<ghostchain_pb::SignatureAlgorithm> for SignatureAlgorithm {
+    type Error = EtherlinkError;
+
+    fn try_from(algorithm: ghostchain_pb::SignatureAlgorithm) -> Result<Self> {
+        match algorithm {
+            ghostchain_pb::SignatureAlgorithm::Ed25519 => Ok(SignatureAlgorithm::Ed25519),
+            ghostchain_pb::SignatureAlgorithm::Secp256k1 => Ok(SignatureAlgorithm::Secp256k1),
+            ghostchain_pb::SignatureAlgorithm::Bls12381 => Ok(SignatureAlgorithm::Bls12381),
+            ghostchain_pb::SignatureAlgorithm::Unspecified => {
+                Err(EtherlinkError::Codec("Signature algorithm is unspecified".to_string()))
+            }
+        }
+    }
+}
+
+impl From<SessionMethod> for ghostchain_pb::TransactionType {
+    fn from(method: SessionMethod) -> Self {
+        match method {
+            SessionMethod::Transfer => ghostchain_pb::TransactionType::Transfer,
+            SessionMethod::ContractCall => ghostchain_pb::TransactionType::ContractCall,
+        }
+    }
+}
+
+fn session_method(value: i32) -> Result<SessionMethod> {
+    match ghostchain_pb::TransactionType::try_from(value) {
+        Ok(ghostchain_pb::TransactionType::Transfer) => Ok(SessionMethod::Transfer),
+        Ok(ghostchain_pb::TransactionType::ContractCall) => Ok(SessionMethod::ContractCall),
+        _ => Err(EtherlinkError::Codec(format!("Transaction type {} cannot be delegated", value))),
+    }
+}
+
+impl From<Delegation> for ghostchain_pb::Delegation {
+    fn from(delegation: Delegation) -> Self {
+        Self {
+            issuer: delegation.issuer.0,
+            issuer_public_key: delegation.issuer_public_key,
+            session_public_key: delegation.session_public_key,
+            algorithm: ghostchain_pb::SignatureAlgorithm::from(delegation.algorithm) as i32,
+            policy: Some(ghostchain_pb::SessionPolicy {
+                max_spend: delegation.policy.max_spend,
+                expires_at: delegation.policy.expires_at,
+                allowed_methods: delegation.policy.allowed_methods.into_iter()
+                    .map(|method| ghostchain_pb::TransactionType::from(method) as i32)
+                    .collect(),
+            }),
+            signature: delegation.signature,
+        }
+    }
+}
+
+impl TryFrom<ghostchain_pb::Delegation> for Delegation {
+    type Error = EtherlinkError;
+
+    /// Fails on a missing policy or an algorithm or method a session cannot use
+    fn try_from(delegation: ghostchain_pb::Delegation) -> Result<Self> {
+        let policy = delegation.policy
+            .ok_or_else(|| EtherlinkError::Codec("Delegation has no policy".to_string()))?;
+        let algorithm = ghostchain_pb::SignatureAlgorithm::try_from(delegation.algorithm)
+            .map_err(|_| EtherlinkError::Codec(format!("Unknown signature algorithm: {}", delegation.algorithm)))?
+            .try_into()?;
+        Ok(Self {
+            issuer: Address::new(delegation.issuer),
+            issuer_public_key: delegation.issuer_public_key,
+            session_public_key: delegation.session_public_key,
+            algorithm,
+            policy: SessionPolicy {
+                max_spend: policy.max_spend,
+                expires_at: policy.expires_at,
+                allowed_methods: policy.allowed_methods.into_iter().map(session_method).collect::<Result<_>>()?,
+            },
+            signature: delegation.signature,
+        })
+    }
+}
+
+// Layer 2 transactions
+
+impl From<L2Transaction> for ghostplane_pb::L2Transaction {
+    fn from(tx: L2Transaction) -> Self {
+        Self {
+            from: tx.from.0,
+            to: tx.to.0,
+            value: tx.value,
+            data: tx.data,
+            gas_limit: tx.gas_limit,
+            gas_price: tx.gas_price,
+            nonce: tx.nonce,
+            signature: tx.signature,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<ghostplane_pb::L2Transaction> for L2Transaction {
+    fn from(tx: ghostplane_pb::L2Transaction) -> Self {
+        Self {
+            from: Address::new(tx.from),
+            to: Address::new(tx.to),
+            value: tx.value,
+            data: tx.data,
+            gas_limit: tx.gas_limit,
+            gas_price: tx.gas_price,
+            nonce: tx.nonce,
+            signature: tx.signature,
+        }
+    }
+}
+
+impl From<ghostplane_pb::L2StateResponse> for L2AccountState {
+    fn from(state: ghostplane_pb::L2StateResponse) -> Self {
+        Self {
+            address: Address::new(state.address),
+            balance: state.balance,
+            nonce: state.nonce,
+            code: state.code,
+            storage: state.storage.into_iter().collect(),
+        }
+    }
+}
+
+// CNS
+
+impl From<ServiceType> for cns_pb::ServiceType {
+    fn from(service_type: ServiceType) -> Self {
+        match service_type {
+            ServiceType::Blockchain => cns_pb::ServiceType::Blockchain,
+            ServiceType::Wallet => cns_pb::ServiceType::Wallet,
+            ServiceType::L2 => cns_pb::ServiceType::L2,
+            ServiceType::Storage => cns_pb::ServiceType::Storage,
+            ServiceType::Web5 => cns_pb::ServiceType::Web5,
+            ServiceType::Bridge => cns_pb::ServiceType::Bridge,
+        }
+    }
+}
+
+impl TryFrom<cns_pb::ServiceType> for ServiceType {
+    type Error = EtherlinkError;
+
+    fn try_from(service_type: cns_pb::ServiceType) -> Result<Self> {
+        match service_type {
+            cns_pb::ServiceType::Blockchain => Ok(ServiceType::Blockchain),
+            cns_pb::ServiceType::Wallet => Ok(ServiceType::Wallet),
+            cns_pb::ServiceType::L2 => Ok(ServiceType::L2),
+            cns_pb::ServiceType::Storage => Ok(ServiceType::Storage),
+            cns_pb::ServiceType::Web5 => Ok(ServiceType::Web5),
+            cns_pb::ServiceType::Bridge => Ok(ServiceType::Bridge),
+            cns_pb::ServiceType::Unspecified => Err(EtherlinkError::Codec("Service type is unspecified".to_string())),
+        }
+    }
+}
+
+impl From<DomainResolution> for cns_pb::CnsResolveResponse {
+    fn from(resolution: DomainResolution) -> Self {
+        Self {
+            domain: resolution.domain,
+            owner_address: resolution.owner.0,
+            records: resolution
+                .records
+                .iter()
+                .map(|(record_type, value)| cns_pb::DnsRecord {
+                    record_type: record_type.to_string(),
+                    value: value.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            metadata: resolution.metadata,
+            expires_at: resolution.expires_at,
+            service_type: cns_pb::ServiceType::from(resolution.service_type) as i32,
+            blockchain_address: resolution.blockchain_address.map(|a| a.0).unwrap_or_default(),
+            ipfs_hash: resolution.ipfs_hash.unwrap_or_default(),
+            web5_did: resolution.web5_did.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<cns_pb::CnsResolveResponse> for DomainResolution {
+    type Error = EtherlinkError;
+
+    /// Fails on an unknown or unspecified service type
+    ///
+    /// Repeated records of one type are all kept, in order.
+    fn try_from(response: cns_pb::CnsResolveResponse) -> Result<Self> {
+        let service_type = cns_pb::ServiceType::try_from(response.service_type)
+            .map_err(|_| EtherlinkError::Codec(format!("Unknown service type: {}", response.service_type)))?;
+
+        let records: RecordSet = response
+            .records
+            .into_iter()
+            .map(|record| (record.record_type, record.value))
+            .collect();
+
+        Ok(Self {
+            domain: response.domain,
+            owner: Address::new(response.owner_address),
+            records,
+            metadata: response.metadata,
+            expires_at: response.expires_at,
+            service_type: ServiceType::try_from(service_type)?,
+            blockchain_address: empty_to_none(response.blockchain_address).map(Address::new),
+            ipfs_hash: empty_to_none(response.ipfs_hash),
+            web5_did: empty_to_none(response.web5_did),
+        })
+    }
+}
+
+impl From<ChangeEventType> for cns_pb::ChangeEventType {
+    fn from(event_type: ChangeEventType) -> Self {
+        match event_type {
+            ChangeEventType::Registered => cns_pb::ChangeEventType::Registered,
+            ChangeEventType::Updated => cns_pb::ChangeEventType::Updated,
+            ChangeEventType::Transferred => cns_pb::ChangeEventType::Transferred,
+            ChangeEventType::Expired => cns_pb::ChangeEventType::Expired,
+            ChangeEventType::Renewed => cns_pb::ChangeEventType::Renewed,
+        }
+    }
+}
+
+impl TryFrom<cns_pb::ChangeEventType> for ChangeEventType {
+    type Error = EtherlinkError;
+
+    fn try_from(event_type: cns_pb::ChangeEventType) -> Result<Self> {
+        match event_type {
+            cns_pb::ChangeEventType::Registered => Ok(ChangeEventType::Registered),
+            cns_pb::ChangeEventType::Updated => Ok(ChangeEventType::Updated),
+            cns_pb::ChangeEventType::Transferred => Ok(ChangeEventType::Transferred),
+            cns_pb::ChangeEventType::Expired => Ok(ChangeEventType::Expired),
+            cns_pb::ChangeEventType::Renewed => Ok(ChangeEventType::Renewed),
+            cns_pb::ChangeEventType::Unspecified => Err(EtherlinkError::Codec("Change event type is unspecified".to_string())),
+        }
+    }
+}
+
+impl From<DomainChangeEvent> for cns_pb::CnsDomainChangeEvent {
+    fn from(event: DomainChangeEvent) -> Self {
+        Self {
+            domain: event.domain,
+            event_type: cns_pb::ChangeEventType::from(event.event_type) as i32,
+            timestamp: event.timestamp,
+            old_value: event.old_value.unwrap_or_default(),
+            new_value: event.new_value.unwrap_or_default(),
+            transaction_hash: event.tx_hash.unwrap_or_default(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+impl TryFrom<cns_pb::CnsDomainChangeEvent> for DomainChangeEvent {
+    type Error = EtherlinkError;
+
+    /// Fails on an unknown or unspecified event type
+    fn try_from(event: cns_pb::CnsDomainChangeEvent) -> Result<Self> {
+        let event_type = cns_pb::ChangeEventType::try_from(event.event_type)
+            .map_err(|_| EtherlinkError::Codec(format!("Unknown change event type: {}", event.event_type)))?;
+
+        Ok(Self {
+            domain: event.domain,
+            event_type: ChangeEventType::try_from(event_type)?,
+            timestamp: event.timestamp,
+            old_value: empty_to_none(event.old_value),
+            new_value: empty_to_none(event.new_value),
+            tx_hash: empty_to_none(event.transaction_hash),
+        })
+    }
+}
+
+// Crypto algorithms, shared by WALLETD and GSIG
+
+impl From<CryptoAlgorithm> for walletd_pb::CryptoAlgorithm {
+    fn from(algorithm: CryptoAlgorithm) -> Self {
+        match algorithm {
+            CryptoAlgorithm::Ed25519 => walletd_pb::CryptoAlgorithm::Ed25519,
+            CryptoAlgorithm::Secp256k1 => walletd_pb::CryptoAlgorithm::Secp256k1,
+            CryptoAlgorithm::Bls12381 => walletd_pb::CryptoAlgorithm::Bls12381,
+        }
+    }
+}
+
+impl TryFrom<walletd_pb::CryptoAlgorithm> for CryptoAlgorithm {
+    type Error = EtherlinkError;
+
+    fn try_from(algorithm: walletd_pb::CryptoAlgorithm) -> Result<Self> {
+        match algorithm {
+            walletd_pb::CryptoAlgorithm::Ed25519 => Ok(CryptoAlgorithm::Ed25519),
+            walletd_pb::CryptoAlgorithm::Secp256k1 => Ok(CryptoAlgorithm::Secp256k1),
+            walletd_pb::CryptoAlgorithm::Bls12381 => Ok(CryptoAlgorithm::Bls12381),
+            walletd_pb::CryptoAlgorithm::Unspecified => Err(EtherlinkError::Codec("Crypto algorithm is unspecified".to_string())),
+        }
+    }
+}
+
+fn crypto_algorithm(value: i32) -> Result<CryptoAlgorithm> {
+    walletd_pb::CryptoAlgorithm::try_from(value)
+        .map_err(|_| EtherlinkError::Codec(format!("Unknown crypto algorithm: {}", value)))?
+        .try_into()
+}
+
+fn missing(field: &str) -> EtherlinkError {
+    EtherlinkError::Codec(format!("Missing {} in response", field))
+}
+
+// WALLETD
+
+impl From<CreateWalletRequest> for walletd_pb::CreateWalletRequest {
+    fn from(request: CreateWalletRequest) -> Self {
+        Self {
+            name: request.name,
+            algorithm: walletd_pb::CryptoAlgorithm::from(request.algorithm) as i32,
+            mnemonic: request.mnemonic.map(|m| m.expose_secret().clone()).unwrap_or_default(),
+            passphrase: request.passphrase.map(|p| p.expose_secret().clone()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<walletd_pb::WalletInfo> for WalletInfo {
+    type Error = EtherlinkError;
+
+    fn try_from(wallet: walletd_pb::WalletInfo) -> Result<Self> {
+        Ok(Self {
+            id: wallet.id,
+            name: wallet.name,
+            algorithm: crypto_algorithm(wallet.algorithm)?,
+            created_at: wallet.created_at,
+            address_count: wallet.address_count,
+            is_hardware: wallet.is_hardware,
+        })
+    }
+}
+
+impl From<walletd_pb::WalletAddress> for WalletAddress {
+    fn from(address: walletd_pb::WalletAddress) -> Self {
+        Self {
+            address: Address::new(address.address),
+            derivation_path: address.derivation_path,
+            public_key: address.public_key,
+            address_index: address.address_index,
+            created_at: address.created_at,
+        }
+    }
+}
+
+impl TryFrom<SignTransactionRequest> for walletd_pb::SignTransactionRequest {
+    type Error = EtherlinkError;
+
+    /// Fails where the transaction conversion does
+    fn try_from(request: SignTransactionRequest) -> Result<Self> {
+        Ok(Self {
+            wallet_id: request.wallet_id,
+            transaction: Some(request.transaction.try_into()?),
+            address_index: request.address_index,
+        })
+    }
+}
+
+impl TryFrom<walletd_pb::SignedTransaction> for SignedTransaction {
+    type Error = EtherlinkError;
+
+    fn try_from(signed: walletd_pb::SignedTransaction) -> Result<Self> {
+        Ok(Self {
+            transaction: signed.transaction.ok_or_else(|| missing("transaction"))?.into(),
+            signature: signed.signature,
+            public_key: signed.public_key,
+            signature_algorithm: crypto_algorithm(signed.signature_algorithm)?,
+        })
+    }
+}
+
+// GID
+//
+// Free-form values travel as JSON text, one string per map entry.
+
+fn to_json_map(values: HashMap<String, serde_json::Value>) -> HashMap<String, String> {
+    values.into_iter().map(|(key, value)| (key, value.to_string())).collect()
+}
+
+fn from_json_map(values: HashMap<String, String>) -> Result<HashMap<String, serde_json::Value>> {
+    values
+        .into_iter()
+        .map(|(key, value)| {
+            let parsed = serde_json::from_str(&value)
+                .map_err(|e| EtherlinkError::Codec(format!("Invalid JSON for {}: {}", key, e)))?;
+            Ok((key, parsed))
+        })
+        .collect()
+}
+
+fn zero_to_none(value: u64) -> Option<u64> {
+    if value == 0 { None } else { Some(value) }
+}
+
+impl From<IdentityType> for gid_pb::IdentityType {
+    fn from(identity_type: IdentityType) -> Self {
+        match identity_type {
+            IdentityType::Personal => gid_pb::IdentityType::Personal,
+            IdentityType::Organization => gid_pb::IdentityType::Organization,
+            IdentityType::Service => gid_pb::IdentityType::Service,
+            IdentityType::Device => gid_pb::IdentityType::Device,
+            IdentityType::Ephemeral => gid_pb::IdentityType::Ephemeral,
+        }
+    }
+}
+
+impl TryFrom<gid_pb::IdentityType> for IdentityType {
+    type Error = EtherlinkError;
+
+    fn try_from(identity_type: gid_pb::IdentityType) -> Result<Self> {
+        match identity_type {
+            gid_pb::IdentityType::Personal => Ok(IdentityType::Personal),
+            gid_pb::IdentityType::Organization => Ok(IdentityType::Organization),
+            gid_pb::IdentityType::Service => Ok(IdentityType::Service),
+            gid_pb::IdentityType::Device => Ok(IdentityType::Device),
+            gid_pb::IdentityType::Ephemeral => Ok(IdentityType::Ephemeral),
+            gid_pb::IdentityType::Unspecified => Err(EtherlinkError::Codec("Identity type is unspecified".to_string())),
+        }
+    }
+}
+
+impl From<CreateIdentityRequest> for gid_pb::CreateIdentityRequest {
+    fn from(request: CreateIdentityRequest) -> Self {
+        Self {
+            address: request.address.0,
+            identity_type: gid_pb::IdentityType::from(request.identity_type) as i32,
+            metadata: request.metadata.map(to_json_map).unwrap_or_default(),
+            ephemeral: request.ephemeral,
+        }
+    }
+}
+
+impl TryFrom<gid_pb::Identity> for Identity {
+    type Error = EtherlinkError;
+
+    fn try_from(identity: gid_pb::Identity) -> Result<Self> {
+        let identity_type = gid_pb::IdentityType::try_from(identity.identity_type)
+            .map_err(|_| EtherlinkError::Codec(format!("Unknown identity type: {}", identity.identity_type)))?;
+        Ok(Self {
+            did: identity.did,
+            address: Address::new(identity.address),
+            identity_type: identity_type.try_into()?,
+            created_at: identity.created_at,
+            updated_at: identity.updated_at,
+            ephemeral: identity.ephemeral,
+            expires_at: zero_to_none(identity.expires_at),
+        })
+    }
+}
+
+impl TryFrom<gid_pb::IdentityDocument> for IdentityDocument {
+    type Error = EtherlinkError;
+
+    /// Fails if a metadata value is not valid JSON
+    fn try_from(document: gid_pb::IdentityDocument) -> Result<Self> {
+        Ok(Self {
+            context: document.context,
+            id: document.id,
+            verification_method: document
+                .verification_method
+                .into_iter()
+                .map(|method| VerificationMethod {
+                    id: method.id,
+                    method_type: method.method_type,
+                    controller: method.controller,
+                    public_key_multibase: method.public_key_multibase,
+                })
+                .collect(),
+            authentication: document.authentication,
+            assertion_method: document.assertion_method,
+            key_agreement: document.key_agreement,
+            capability_invocation: document.capability_invocation,
+            capability_delegation: document.capability_delegation,
+            service: document
+                .service
+                .into_iter()
+                .map(|service| ServiceEndpoint {
+                    id: service.id,
+                    service_type: service.service_type,
+                    service_endpoint: service.service_endpoint,
+                })
+                .collect(),
+            metadata: from_json_map(document.metadata)?,
+        })
+    }
+}
+
+impl From<PolicyRequest> for gid_pb::PolicyRequest {
+    fn from(request: PolicyRequest) -> Self {
+        Self {
+            identity: request.identity,
+            action: request.action,
+            resource: request.resource,
+            context: to_json_map(request.context),
+        }
+    }
+}
+
+impl From<gid_pb::PolicyDecision> for PolicyDecision {
+    fn from(decision: gid_pb::PolicyDecision) -> Self {
+        Self {
+            allowed: decision.allowed,
+            reason: decision.reason,
+            conditions: decision.conditions,
+            expires_at: zero_to_none(decision.expires_at),
+        }
+    }
+}
+
+// GSIG
+
+impl From<SignRequest> for gsig_pb::SignRequest {
+    fn from(request: SignRequest) -> Self {
+        Self {
+            message: request.message,
+            algorithm: walletd_pb::CryptoAlgorithm::from(request.algorithm) as i32,
+            private_key: request.private_key.map(|key| key.expose_secret().clone()).unwrap_or_default(),
+            key_id: request.key_id.unwrap_or_default(),
+            address: request.address.map(|address| address.0).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<gsig_pb::SignatureResponse> for SignatureResponse {
+    type Error = EtherlinkError;
+
+    fn try_from(response: gsig_pb::SignatureResponse) -> Result<Self> {
+        Ok(Self {
+            signature: response.signature,
+            public_key: response.public_key,
+            algorithm: crypto_algorithm(response.algorithm)?,
+            message_hash: response.message_hash,
+            signature_id: empty_to_none(response.signature_id),
+        })
+    }
+}
+
+impl From<VerifyRequest> for gsig_pb::VerifyRequest {
+    fn from(request: VerifyRequest) -> Self {
+        Self {
+            message: request.message,
+            signature: request.signature,
+            public_key: request.public_key,
+            algorithm: walletd_pb::CryptoAlgorithm::from(request.algorithm) as i32,
+        }
+    }
+}
+
+impl TryFrom<gsig_pb::VerificationResult> for VerificationResult {
+    type Error = EtherlinkError;
+
+    fn try_from(result: gsig_pb::VerificationResult) -> Result<Self> {
+        Ok(Self {
+            valid: result.valid,
+            algorithm: crypto_algorithm(result.algorithm)?,
+            message_hash: result.message_hash,
+            verification_time_ms: result.verification_time_ms,
+            error: empty_to_none(result.error),
+        })
+    }
+}
+
+impl TryFrom<gsig_pb::AlgorithmInfo> for AlgorithmInfo {
+    type Error = EtherlinkError;
+
+    fn try_from(info: gsig_pb::AlgorithmInfo) -> Result<Self> {
+        Ok(Self {
+            algorithm: crypto_algorithm(info.algorithm)?,
+            name: info.name,
+            description: info.description,
+            key_size_bits: info.key_size_bits,
+            signature_size_bytes: info.signature_size_bytes,
+            post_quantum: info.post_quantum,
+            supported_operations: info.supported_operations,
+        })
+    }
+}
+
+// GLEDGER
+
+impl From<TokenTransfer> for gledger_pb::TransferTokensRequest {
+    fn from(transfer: TokenTransfer) -> Self {
+        Self {
+            from: transfer.from.0,
+            to: transfer.to.0,
+            token_type: ghostchain_pb::TokenType::from(transfer.token_type) as i32,
+            amount: transfer.amount,
+            memo: transfer.memo.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<TokenMint> for gledger_pb::MintTokensRequest {
+    fn from(mint: TokenMint) -> Self {
+        Self {
+            to: mint.to.0,
+            token_type: ghostchain_pb::TokenType::from(mint.token_type) as i32,
+            amount: mint.amount,
+            reason: mint.reason,
+        }
+    }
+}
+
+impl From<TokenBurn> for gledger_pb::BurnTokensRequest {
+    fn from(burn: TokenBurn) -> Self {
+        Self {
+            from: burn.from.0,
+            token_type: ghostchain_pb::TokenType::from(burn.token_type) as i32,
+            amount: burn.amount,
+            reason: burn.reason,
+        }
+    }
+}
+
+impl From<gledger_pb::TokenBalances> for TokenBalances {
+    fn from(balances: gledger_pb::TokenBalances) -> Self {
+        Self {
+            address: balances.address,
+            gcc: balances.gcc,
+            spirit: balances.spirit,
+            mana: balances.mana,
+            ghost: balances.ghost,
+        }
+    }
+}
+
+impl TryFrom<gledger_pb::TokenTransaction> for TokenTransaction {
+    type Error = EtherlinkError;
+
+    fn try_from(tx: gledger_pb::TokenTransaction) -> Result<Self> {
+        Ok(Self {
+            tx_hash: tx.tx_hash,
+            from: Address::new(tx.from),
+            to: Address::new(tx.to),
+            token_type: token_type(tx.token_type)?,
+            amount: tx.amount,
+            timestamp: tx.timestamp,
+            block_height: tx.block_height,
+            memo: empty_to_none(tx.memo),
+            fee: tx.fee,
+        })
+    }
+}
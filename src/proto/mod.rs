@@ -0,0 +1,47 @@
+//! Generated protobuf types for the GhostChain gRPC services
+//!
+//! Conversions to and from the crate's domain types live in [`convert`].
+
+pub mod convert;
+
+pub mod cns {
+    pub mod v1 {
+        tonic::include_proto!("cns.v1");
+    }
+}
+
+pub mod ghostchain {
+    pub mod v1 {
+        tonic::include_proto!("ghostchain.v1");
+    }
+}
+
+pub mod ghostplane {
+    pub mod v1 {
+        tonic::include_proto!("ghostplane.v1");
+    }
+}
+
+pub mod walletd {
+    pub mod v1 {
+        tonic::include_proto!("walletd.v1");
+    }
+}
+
+pub mod gid {
+    pub mod v1 {
+        tonic::include_proto!("gid.v1");
+    }
+}
+
+pub mod gsig {
+    pub mod v1 {
+        tonic::include_proto!("gsig.v1");
+    }
+}
+
+pub mod gledger {
+    pub mod v1 {
+        tonic::include_proto!("gledger.v1");
+    }
+}
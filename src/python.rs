@@ -0,0 +1,116 @@
+//! Python bindings for ops tooling (feature `python`)
+//!
+//! Build a wheel with `maturin build --release` (see `pyproject.toml`), then:
+//!
+//! ```python
+//! import etherlink
+//! client = etherlink.Etherlink(ghostd_endpoint="http://localhost:8545")
+//! client.balances("ghost1...")          # {"gcc": ..., "spirit": ..., ...}
+//! client.health()["services"]
+//! ```
+//!
+//! Results are returned as plain `dict`s, decoded from the same JSON the REST
+//! services speak. Calls release the GIL while waiting on the network.
+
+use crate::clients::ghostd::Transaction;
+use crate::runtime::Etherlink as Facade;
+use crate::{Address, EtherlinkConfig};
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::Serialize;
+use tokio::runtime::Runtime;
+
+create_exception!(etherlink, EtherlinkError, PyException, "Error raised by Etherlink service calls");
+
+impl From<crate::EtherlinkError> for PyErr {
+    fn from(error: crate::EtherlinkError) -> Self {
+        EtherlinkError::new_err(error.to_string())
+    }
+}
+
+fn to_python<'py, T: Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+    let json = serde_json::to_string(value).map_err(crate::EtherlinkError::from)?;
+    py.import("json")?.call_method1("loads", (json,))
+}
+
+fn from_python<T: serde::de::DeserializeOwned>(py: Python<'_>, value: &Bound<'_, PyAny>) -> PyResult<T> {
+    let json: String = py.import("json")?.call_method1("dumps", (value,))?.extract()?;
+    Ok(serde_json::from_str(&json).map_err(crate::EtherlinkError::from)?)
+}
+
+/// Blocking handle to the Etherlink runtime facade
+#[pyclass(name = "Etherlink", module = "etherlink")]
+pub struct PyEtherlink {
+    runtime: Runtime,
+    inner: Facade,
+}
+
+#[pymethods]
+impl PyEtherlink {
+    /// Create a client; unspecified settings use the library defaults
+    #[new]
+    #[pyo3(signature = (ghostd_endpoint=None, cns_endpoint=None, ghostplane_endpoint=None, timeout_ms=None))]
+    fn new(ghostd_endpoint: Option<String>, cns_endpoint: Option<String>, ghostplane_endpoint: Option<String>, timeout_ms: Option<u64>) -> PyResult<Self> {
+        let mut config = EtherlinkConfig::default();
+        if let Some(endpoint) = ghostd_endpoint {
+            config.ghostd_endpoint = endpoint;
+        }
+        config.cns_endpoint = cns_endpoint.or(config.cns_endpoint);
+        config.ghostplane_endpoint = ghostplane_endpoint.or(config.ghostplane_endpoint);
+        if let Some(timeout_ms) = timeout_ms {
+            config.timeout_ms = timeout_ms;
+        }
+
+        let runtime = Runtime::new().map_err(|e| EtherlinkError::new_err(format!("Failed to start runtime: {}", e)))?;
+        let inner = {
+            let _guard = runtime.enter();
+            Facade::new(config)?
+        };
+        Ok(Self { runtime, inner })
+    }
+
+    /// Resolve a CNS domain
+    fn resolve<'py>(&self, py: Python<'py>, domain: &str) -> PyResult<Bound<'py, PyAny>> {
+        let resolution = py.allow_threads(|| self.runtime.block_on(self.inner.services().cns.resolve_domain(domain)))?;
+        to_python(py, &resolution)
+    }
+
+    /// GCC balance of an address, as reported by ghostd
+    fn balance(&self, py: Python<'_>, address: &str) -> PyResult<u64> {
+        let address = Address::new(address.to_string());
+        Ok(py.allow_threads(|| self.runtime.block_on(self.inner.services().ghostd.get_balance(&address)))?)
+    }
+
+    /// Balances of every token type for an address
+    fn balances<'py>(&self, py: Python<'py>, address: &str) -> PyResult<Bound<'py, PyAny>> {
+        let address = Address::new(address.to_string());
+        let balances = py.allow_threads(|| self.runtime.block_on(self.inner.services().gledger.get_all_balances(&address)))?;
+        to_python(py, &balances)
+    }
+
+    /// Submit a signed transaction given as a dict, returning its hash
+    fn submit_tx(&self, py: Python<'_>, tx: &Bound<'_, PyDict>) -> PyResult<String> {
+        let tx: Transaction = from_python(py, tx.as_any())?;
+        let tx_hash = py.allow_threads(|| self.runtime.block_on(self.inner.services().ghostd.submit_transaction(tx)))?;
+        Ok(tx_hash.0)
+    }
+
+    /// Aggregated health of all services
+    fn health<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let report = py.allow_threads(|| self.runtime.block_on(self.inner.health_report()));
+        to_python(py, &report)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Etherlink(ghostd_endpoint={:?})", self.inner.config().ghostd_endpoint)
+    }
+}
+
+#[pymodule]
+fn etherlink(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyEtherlink>()?;
+    m.add("EtherlinkError", m.py().get_type::<EtherlinkError>())?;
+    Ok(())
+}
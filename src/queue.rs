@@ -0,0 +1,402 @@
+//! Bounded queues for event streams
+//!
+//! A subscriber that reads slower than events arrive must not grow memory without
+//! bound, nor stall whatever produces the events — typically a transport read loop.
+//! [`bounded`] creates a single-consumer queue of fixed capacity whose
+//! [`OverflowPolicy`] decides what a full queue does: discard the new item, discard
+//! the oldest one, or make the producer wait. [`Fanout`] gives each subscriber its
+//! own such queue, so one slow subscriber only affects itself. Every queue keeps
+//! [`QueueMetrics`] of how far its consumer lags and what it lost.
+
+use crate::validation::{ConfigErrors, Validator};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tracing::warn;
+
+/// Capacity used by [`QueueConfig::default`]
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+/// What a full queue does with a new item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Discard the new item; the consumer sees a gap after what it has buffered
+    DropNewest,
+    /// Discard the oldest buffered item; the consumer always sees the latest items
+    DropOldest,
+    /// Make the producer wait until the consumer catches up
+    Block,
+}
+
+/// Capacity and overflow behavior of a queue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueConfig {
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { capacity: DEFAULT_QUEUE_CAPACITY, policy: OverflowPolicy::DropOldest }
+    }
+}
+
+impl QueueConfig {
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self { capacity, policy }
+    }
+
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.capacity > 0, "capacity", "must be greater than zero");
+        v.finish()
+    }
+}
+
+/// Snapshot of a queue's counters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct QueueMetrics {
+    pub capacity: usize,
+    /// Items buffered and not yet received: how far the consumer lags
+    pub depth: usize,
+    /// Highest depth seen
+    pub high_water: usize,
+    /// Items accepted into the queue
+    pub enqueued: u64,
+    /// Items the consumer received
+    pub delivered: u64,
+    /// Items discarded by the overflow policy
+    pub dropped: u64,
+    /// Sends that had to wait for space under [`OverflowPolicy::Block`]
+    pub blocked: u64,
+}
+
+/// The consumer is gone; the item that could not be sent is handed back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueClosed<T>(pub T);
+
+impl<T> fmt::Display for QueueClosed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("queue receiver was dropped")
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for QueueClosed<T> {}
+
+/// Why [`QueueSender::try_send`] did not enqueue an item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The queue is full and its policy is [`OverflowPolicy::Block`]
+    Full(T),
+    Closed(T),
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    config: QueueConfig,
+    items: Mutex<VecDeque<T>>,
+    /// Signalled when an item is pushed or the last sender is dropped
+    item_ready: Notify,
+    /// Signalled when an item is taken or the receiver is dropped
+    space_ready: Notify,
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+    high_water: AtomicUsize,
+    enqueued: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    blocked: AtomicU64,
+}
+
+impl<T> Shared<T> {
+    fn is_closed(&self) -> bool {
+        !self.receiver_alive.load(Ordering::Acquire)
+    }
+
+    /// Push into a queue known to have room, or apply a non-blocking policy;
+    /// gives the item back if the queue is full under [`OverflowPolicy::Block`]
+    fn push(&self, item: T) -> std::result::Result<(), T> {
+        let mut items = self.items.lock().unwrap();
+        if items.len() >= self.config.capacity {
+            match self.config.policy {
+                OverflowPolicy::Block => return Err(item),
+                OverflowPolicy::DropNewest => {
+                    self.record_drop();
+                    return Ok(());
+                }
+                OverflowPolicy::DropOldest => {
+                    items.pop_front();
+                    self.record_drop();
+                }
+            }
+        }
+        items.push_back(item);
+        self.high_water.fetch_max(items.len(), Ordering::Relaxed);
+        self.enqueued.fetch_add(1, Ordering::Relaxed);
+        drop(items);
+        self.item_ready.notify_one();
+        Ok(())
+    }
+
+    fn record_drop(&self) {
+        // Warn on the first drop and then every 1000th, not per item
+        let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+        if dropped == 1 || dropped % 1000 == 0 {
+            warn!(
+                "Queue consumer is lagging ({} items buffered); {} items dropped so far",
+                self.config.capacity, dropped
+            );
+        }
+    }
+
+    fn metrics(&self) -> QueueMetrics {
+        QueueMetrics {
+            capacity: self.config.capacity,
+            depth: self.items.lock().unwrap().len(),
+            high_water: self.high_water.load(Ordering::Relaxed),
+            enqueued: self.enqueued.load(Ordering::Relaxed),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            blocked: self.blocked.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Create a queue with `config`'s capacity and overflow policy
+///
+/// A capacity of zero is treated as one.
+pub fn bounded<T>(config: QueueConfig) -> (QueueSender<T>, QueueReceiver<T>) {
+    let config = QueueConfig { capacity: config.capacity.max(1), ..config };
+    let shared = Arc::new(Shared {
+        items: Mutex::new(VecDeque::with_capacity(config.capacity.min(DEFAULT_QUEUE_CAPACITY))),
+        config,
+        item_ready: Notify::new(),
+        space_ready: Notify::new(),
+        senders: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+        high_water: AtomicUsize::new(0),
+        enqueued: AtomicU64::new(0),
+        delivered: AtomicU64::new(0),
+        dropped: AtomicU64::new(0),
+        blocked: AtomicU64::new(0),
+    });
+    (QueueSender { shared: shared.clone() }, QueueReceiver { shared })
+}
+
+/// Producing half of a [`bounded`] queue; clones feed the same queue
+#[derive(Debug)]
+pub struct QueueSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> QueueSender<T> {
+    /// Enqueue `item`, waiting for space only under [`OverflowPolicy::Block`]
+    pub async fn send(&self, item: T) -> std::result::Result<(), QueueClosed<T>> {
+        let mut item = item;
+        let mut waited = false;
+        loop {
+            let space = self.shared.space_ready.notified();
+            if self.shared.is_closed() {
+                return Err(QueueClosed(item));
+            }
+            match self.shared.push(item) {
+                Ok(()) => return Ok(()),
+                Err(rejected) => item = rejected,
+            }
+            if !waited {
+                waited = true;
+                self.shared.blocked.fetch_add(1, Ordering::Relaxed);
+            }
+            space.await;
+        }
+    }
+
+    /// Enqueue `item` without waiting
+    ///
+    /// Under the drop policies this only fails once the receiver is gone.
+    pub fn try_send(&self, item: T) -> std::result::Result<(), TrySendError<T>> {
+        if self.shared.is_closed() {
+            return Err(TrySendError::Closed(item));
+        }
+        self.shared.push(item).map_err(TrySendError::Full)
+    }
+
+    /// Whether the receiver was dropped
+    pub fn is_closed(&self) -> bool {
+        self.shared.is_closed()
+    }
+
+    /// Wait until the receiver is dropped, e.g. to stop a producer that is idle
+    pub async fn closed(&self) {
+        loop {
+            let dropped = self.shared.space_ready.notified();
+            if self.shared.is_closed() {
+                return;
+            }
+            dropped.await;
+        }
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        self.shared.metrics()
+    }
+}
+
+impl<T> Clone for QueueSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::AcqRel);
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for QueueSender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.item_ready.notify_one();
+        }
+    }
+}
+
+/// Consuming half of a [`bounded`] queue
+#[derive(Debug)]
+pub struct QueueReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> QueueReceiver<T> {
+    /// Next item; `None` once every sender is dropped and the queue is drained
+    pub async fn recv(&mut self) -> Option<T> {
+        let shared = self.shared.clone();
+        loop {
+            let ready = shared.item_ready.notified();
+            if let Some(item) = self.try_recv() {
+                return Some(item);
+            }
+            if shared.senders.load(Ordering::Acquire) == 0 {
+                // A sender may have pushed just before dropping
+                return self.try_recv();
+            }
+            ready.await;
+        }
+    }
+
+    /// Next buffered item, without waiting
+    pub fn try_recv(&mut self) -> Option<T> {
+        let item = self.shared.items.lock().unwrap().pop_front()?;
+        self.shared.delivered.fetch_add(1, Ordering::Relaxed);
+        self.shared.space_ready.notify_one();
+        Some(item)
+    }
+
+    /// Items buffered and not yet received
+    pub fn lag(&self) -> usize {
+        self.shared.items.lock().unwrap().len()
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        self.shared.metrics()
+    }
+
+    /// Receive as a [`Stream`], e.g. to hand out as a subscription
+    pub fn into_stream(self) -> impl Stream<Item = T> + Send + 'static
+    where
+        T: Send + 'static,
+    {
+        futures::stream::unfold(self, |mut receiver| async move {
+            let item = receiver.recv().await?;
+            Some((item, receiver))
+        })
+    }
+}
+
+impl<T> Drop for QueueReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.store(false, Ordering::Release);
+        self.shared.space_ready.notify_waiters();
+    }
+}
+
+/// Delivers items to any number of subscribers, each through its own bounded queue
+///
+/// Clones publish to the same subscribers. Subscribers whose receiver was dropped are
+/// removed on the next publish.
+#[derive(Debug)]
+pub struct Fanout<T> {
+    subscribers: Arc<Mutex<Vec<QueueSender<T>>>>,
+}
+
+impl<T> Clone for Fanout<T> {
+    fn clone(&self) -> Self {
+        Self { subscribers: self.subscribers.clone() }
+    }
+}
+
+impl<T> Default for Fanout<T> {
+    fn default() -> Self {
+        Self { subscribers: Arc::new(Mutex::new(Vec::new())) }
+    }
+}
+
+impl<T: Clone> Fanout<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive every item published from now on, buffered per `config`
+    pub fn subscribe(&self, config: QueueConfig) -> QueueReceiver<T> {
+        let (sender, receiver) = bounded(config);
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Deliver `item` to every subscriber, waiting for those with
+    /// [`OverflowPolicy::Block`] that are full
+    pub async fn publish(&self, item: T) {
+        let subscribers: Vec<QueueSender<T>> = {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            subscribers.retain(|subscriber| !subscriber.is_closed());
+            subscribers.clone()
+        };
+        for subscriber in subscribers {
+            let _ = subscriber.send(item.clone()).await;
+        }
+    }
+
+    /// Deliver `item` without waiting
+    ///
+    /// A full subscriber with [`OverflowPolicy::Block`] misses the item, which is
+    /// counted as dropped in its metrics.
+    pub fn try_publish(&self, item: T) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| !subscriber.is_closed());
+        for subscriber in subscribers.iter() {
+            if let Err(TrySendError::Full(_)) = subscriber.try_send(item.clone()) {
+                subscriber.shared.record_drop();
+            }
+        }
+    }
+
+    /// Number of live subscribers
+    pub fn subscriber_count(&self) -> usize {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| !subscriber.is_closed());
+        subscribers.len()
+    }
+
+    /// Metrics of every live subscriber's queue
+    pub fn metrics(&self) -> Vec<QueueMetrics> {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|subscriber| !subscriber.is_closed())
+            .map(QueueSender::metrics)
+            .collect()
+    }
+}
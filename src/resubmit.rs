@@ -0,0 +1,178 @@
+//! Resubmission of transactions rejected for their nonce or fee
+//!
+//! GHOSTD and GhostPlane reject a transaction whose nonce has already been used or
+//! whose gas price is too low to enter the pool. [`RejectionReason::classify`]
+//! recognizes those rejections; the submission paths then refresh the nonce from the
+//! chain or bump the gas price by [`ResubmitPolicy::fee_bump_percent`], re-sign and
+//! send again, up to [`ResubmitPolicy::max_attempts`]. Resubmission is off by default:
+//! a re-signed transaction is a new transaction, so it is only sent once the node shows
+//! that none of the earlier attempts was included. Every attempt is recorded as a
+//! [`SubmissionAttempt`] and logged.
+
+use crate::{Result, EtherlinkError};
+use crate::validation::{ConfigErrors, Validator};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use tracing::warn;
+
+/// Rejection a submission can recover from by changing the transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionReason {
+    /// The nonce has already been used; resubmit with a fresh one
+    NonceTooLow,
+    /// The gas price is below the pool minimum or does not replace a pending transaction
+    Underpriced,
+}
+
+impl RejectionReason {
+    /// Recognize a recoverable rejection from GHOSTD or GhostPlane by its message
+    pub fn classify(error: &EtherlinkError) -> Option<Self> {
+        let message = match error {
+            EtherlinkError::Api(msg) | EtherlinkError::TxPool(msg) => msg.to_ascii_lowercase(),
+            #[cfg(not(target_arch = "wasm32"))]
+            EtherlinkError::Status(status) => status.message().to_ascii_lowercase(),
+            _ => return None,
+        };
+        const NONCE_TOO_LOW: &[&str] = &["nonce too low", "nonce is too low", "nonce has already been used"];
+        const UNDERPRICED: &[&str] = &["underpriced", "gas price too low", "fee too low", "below the pool minimum", "does not outbid"];
+        if NONCE_TOO_LOW.iter().any(|pattern| message.contains(pattern)) {
+            Some(RejectionReason::NonceTooLow)
+        } else if UNDERPRICED.iter().any(|pattern| message.contains(pattern)) {
+            Some(RejectionReason::Underpriced)
+        } else {
+            None
+        }
+    }
+}
+
+/// Bounds on resubmitting rejected transactions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResubmitPolicy {
+    /// Submissions per transaction, the first included; 1, the default, disables resubmission
+    pub max_attempts: u32,
+    /// Gas price increase per underpriced rejection
+    pub fee_bump_percent: u64,
+    /// Never bump the gas price above this; unbounded when unset
+    pub max_gas_price: Option<u64>,
+}
+
+impl Default for ResubmitPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            fee_bump_percent: 10,
+            max_gas_price: None,
+        }
+    }
+}
+
+impl ResubmitPolicy {
+    /// No resubmission: the first rejection is final
+    pub fn disabled() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.max_attempts > 0, "max_attempts", "must be greater than zero");
+        v.check(self.fee_bump_percent > 0, "fee_bump_percent", "must be greater than zero");
+        v.check(self.max_gas_price != Some(0), "max_gas_price", "must be greater than zero when set");
+        v.finish()
+    }
+
+    /// Gas price after one bump, at least one unit higher; `None` past `max_gas_price`
+    pub fn bump(&self, gas_price: u64) -> Option<u64> {
+        let bumped = gas_price.checked_mul(100 + self.fee_bump_percent)? / 100;
+        let bumped = bumped.max(gas_price.checked_add(1)?);
+        self.max_gas_price.is_none_or(|max| bumped <= max).then_some(bumped)
+    }
+}
+
+/// One try at submitting a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmissionAttempt {
+    /// 1 for the first submission
+    pub attempt: u32,
+    pub nonce: u64,
+    pub gas_price: u64,
+    /// Why the node refused it, when that was recognized
+    pub rejection: Option<RejectionReason>,
+    /// The node's error; `None` if this attempt was accepted
+    pub error: Option<String>,
+}
+
+impl SubmissionAttempt {
+    pub fn is_accepted(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Call `submit(nonce, gas_price)` until it is accepted or the policy gives up
+///
+/// A nonce-too-low rejection first asks `included(nonce, gas_price)` about every
+/// attempt made at the rejected nonce, and returns the first one the chain already
+/// holds instead of signing again. Otherwise it moves to exactly `next_nonce()`, the
+/// node's pending nonce, and ends with the rejection if that is not past the rejected
+/// one. An underpriced rejection bumps the gas price. Any other error, or running out
+/// of attempts or fee headroom, ends it with the last error. The attempts are returned
+/// either way.
+pub(crate) async fn submit_with_resubmission<T, S, SFut, I, IFut, N, NFut>(
+    policy: &ResubmitPolicy,
+    mut nonce: u64,
+    mut gas_price: u64,
+    mut submit: S,
+    mut included: I,
+    mut next_nonce: N,
+) -> (Result<T>, Vec<SubmissionAttempt>)
+where
+    S: FnMut(u64, u64) -> SFut,
+    SFut: Future<Output = Result<T>>,
+    I: FnMut(u64, u64) -> IFut,
+    IFut: Future<Output = Result<Option<T>>>,
+    N: FnMut() -> NFut,
+    NFut: Future<Output = Result<u64>>,
+{
+    let mut attempts: Vec<SubmissionAttempt> = Vec::new();
+    loop {
+        let attempt = attempts.len() as u32 + 1;
+        let error = match submit(nonce, gas_price).await {
+            Ok(accepted) => {
+                attempts.push(SubmissionAttempt { attempt, nonce, gas_price, rejection: None, error: None });
+                return (Ok(accepted), attempts);
+            }
+            Err(e) => e,
+        };
+
+        let rejection = RejectionReason::classify(&error);
+        warn!("Submission attempt {} (nonce {}, gas price {}) rejected: {}", attempt, nonce, gas_price, error);
+        attempts.push(SubmissionAttempt { attempt, nonce, gas_price, rejection, error: Some(error.to_string()) });
+        if attempt >= policy.max_attempts {
+            return (Err(error), attempts);
+        }
+
+        match rejection {
+            Some(RejectionReason::NonceTooLow) => {
+                let tried: Vec<u64> = attempts.iter().filter(|a| a.nonce == nonce).map(|a| a.gas_price).collect();
+                for price in tried {
+                    match included(nonce, price).await {
+                        Ok(Some(landed)) => return (Ok(landed), attempts),
+                        Ok(None) => {}
+                        Err(e) => return (Err(e), attempts),
+                    }
+                }
+                match next_nonce().await {
+                    Ok(fresh) if fresh > nonce => nonce = fresh,
+                    Ok(_) => return (Err(error), attempts),
+                    Err(e) => return (Err(e), attempts),
+                }
+            }
+            Some(RejectionReason::Underpriced) => match policy.bump(gas_price) {
+                Some(bumped) => gas_price = bumped,
+                None => return (Err(error), attempts),
+            },
+            None => return (Err(error), attempts),
+        }
+    }
+}
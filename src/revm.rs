@@ -1,6 +1,12 @@
 use crate::{EtherlinkError, Result, Address, TxHash, Gas};
+use crate::address::evm_bytes;
+use crate::auth::crypto::{recover_address, sign_recoverable, KeyPair};
+use crate::hash::{self, Hash, HashAlgorithm, Hasher};
+use crate::invariants::{self, StateInvariantViolation};
+use crate::rlp::{Decodable, Encodable, Item};
+use crate::validation::{ConfigErrors, Validator};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use tracing::{debug, info, warn};
 
 /// rEVM (Rust Ethereum Virtual Machine) integration for EVM compatibility
@@ -8,10 +14,13 @@ use tracing::{debug, info, warn};
 pub struct REVMClient {
     config: REVMConfig,
     state: EvmState,
+    /// Snapshots of sealed blocks, oldest first
+    history: VecDeque<EvmState>,
 }
 
 /// Configuration for rEVM execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct REVMConfig {
     pub chain_id: u64,
     pub gas_limit: Gas,
@@ -20,6 +29,9 @@ pub struct REVMConfig {
     pub enable_shanghai_hardfork: bool,
     pub enable_cancun_hardfork: bool,
     pub precompiles_enabled: bool,
+    /// Sealed blocks whose state stays readable through [`REVMClient::at_block`]
+    pub history_blocks: usize,
+    pub fee_schedule: FeeSchedule,
 }
 
 impl Default for REVMConfig {
@@ -32,10 +44,28 @@ impl Default for REVMConfig {
             enable_shanghai_hardfork: true,
             enable_cancun_hardfork: false,
             precompiles_enabled: true,
+            history_blocks: 256,
+            fee_schedule: FeeSchedule::default(),
         }
     }
 }
 
+impl REVMConfig {
+    /// Check every setting, reporting all problems with their field paths
+    ///
+    /// See [`crate::validation::validate_chain_ids`] to check it against the GhostPlane chain.
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.chain_id != 0, "chain_id", "must be non-zero");
+        v.check(self.gas_limit > 0, "gas_limit", "must be greater than zero");
+        v.check(!self.enable_shanghai_hardfork || self.enable_london_hardfork, "enable_shanghai_hardfork", "requires enable_london_hardfork");
+        v.check(!self.enable_cancun_hardfork || self.enable_shanghai_hardfork, "enable_cancun_hardfork", "requires enable_shanghai_hardfork");
+        v.check(self.fee_schedule.refund_quotient > 0, "fee_schedule.refund_quotient", "must be greater than zero");
+        v.check(self.gas_price >= self.fee_schedule.base_fee, "gas_price", "must cover fee_schedule.base_fee");
+        v.finish()
+    }
+}
+
 /// EVM state management
 #[derive(Debug, Clone)]
 pub struct EvmState {
@@ -45,6 +75,10 @@ pub struct EvmState {
     pub block_number: u64,
     pub block_timestamp: u64,
     pub block_gas_limit: Gas,
+    /// Total balance ever granted with [`REVMClient::set_balance`]
+    pub issued: u128,
+    /// Total fees destroyed
+    pub burned: u128,
 }
 
 impl Default for EvmState {
@@ -56,10 +90,101 @@ impl Default for EvmState {
             block_number: 0,
             block_timestamp: chrono::Utc::now().timestamp() as u64,
             block_gas_limit: 30_000_000,
+            issued: 0,
+            burned: 0,
+        }
+    }
+}
+
+/// How transactions are charged for gas and where the fees go
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeSchedule {
+    /// Gas every transaction pays before execution
+    pub tx_gas: Gas,
+    /// Additional intrinsic gas for contract creation
+    pub create_gas: Gas,
+    /// Intrinsic gas per zero byte of call data
+    pub zero_byte_gas: Gas,
+    /// Intrinsic gas per non-zero byte of call data
+    pub nonzero_byte_gas: Gas,
+    /// Refunds are capped at `gas_used / refund_quotient` (5 since EIP-3529, 2 before)
+    pub refund_quotient: u64,
+    /// Part of the gas price that is burned; the remainder is the priority fee
+    pub base_fee: Gas,
+    /// Receives priority fees; without one they are burned along with the base fee
+    pub coinbase: Option<Address>,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self {
+            tx_gas: 21_000,
+            create_gas: 32_000,
+            zero_byte_gas: 4,
+            nonzero_byte_gas: 16,
+            refund_quotient: 5,
+            base_fee: 0,
+            coinbase: None,
         }
     }
 }
 
+impl FeeSchedule {
+    /// Gas charged before any code runs: the base cost plus call data
+    pub fn intrinsic_gas(&self, tx: &EvmTransaction) -> Result<Gas> {
+        let zeros = tx.data.iter().filter(|b| **b == 0).count() as Gas;
+        let nonzeros = tx.data.len() as Gas - zeros;
+        let create = if tx.to.is_none() { self.create_gas } else { 0 };
+        zeros.checked_mul(self.zero_byte_gas)
+            .and_then(|gas| nonzeros.checked_mul(self.nonzero_byte_gas)?.checked_add(gas))
+            .and_then(|gas| gas.checked_add(self.tx_gas)?.checked_add(create))
+            .ok_or_else(|| overflow("intrinsic gas"))
+    }
+
+    /// Most a transaction can be charged: its value plus the full gas limit
+    pub fn max_cost(&self, tx: &EvmTransaction) -> Result<u64> {
+        tx.gas_limit.checked_mul(tx.gas_price)
+            .and_then(|gas| gas.checked_add(tx.value))
+            .ok_or_else(|| overflow("transaction cost"))
+    }
+
+    /// Split the fee for `gas_used` at `gas_price`, after applying the capped refund
+    pub fn settle(&self, gas_price: Gas, gas_used: Gas, gas_refunded: Gas) -> Result<FeeSettlement> {
+        let quotient = self.refund_quotient.max(1);
+        let refunded = gas_refunded.min(gas_used / quotient);
+        let charged = gas_used - refunded;
+        let priority_price = gas_price.checked_sub(self.base_fee)
+            .ok_or_else(|| EtherlinkError::ContractExecution(format!("Gas price {} is below the base fee {}", gas_price, self.base_fee)))?;
+        let total = charged.checked_mul(gas_price).ok_or_else(|| overflow("fee"))?;
+        let priority = charged.checked_mul(priority_price).ok_or_else(|| overflow("priority fee"))?;
+        let (priority_fee, burned) = match self.coinbase {
+            Some(_) => (priority, total - priority),
+            None => (0, total),
+        };
+        Ok(FeeSettlement { gas_charged: charged, gas_refunded: refunded, total_fee: total, burned, priority_fee })
+    }
+}
+
+/// What a transaction paid for gas
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeSettlement {
+    /// Gas paid for after the refund
+    pub gas_charged: Gas,
+    /// Refund applied, after the cap
+    pub gas_refunded: Gas,
+    /// Amount taken from the sender
+    pub total_fee: u64,
+    /// Amount destroyed
+    pub burned: u64,
+    /// Amount credited to the coinbase
+    pub priority_fee: u64,
+}
+
+fn overflow(what: &str) -> EtherlinkError {
+    StateInvariantViolation::Overflow { context: what.to_string() }.into()
+}
+
 /// Account information in EVM state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
@@ -113,6 +238,9 @@ pub struct EvmExecutionResult {
     pub state_changes: HashMap<Address, AccountChange>,
     pub created_address: Option<Address>,
     pub revert_reason: Option<String>,
+    /// Fees charged, filled in by [`REVMClient::execute_transaction`]
+    #[serde(default)]
+    pub fee: FeeSettlement,
 }
 
 /// EVM log entry
@@ -143,12 +271,321 @@ pub struct EvmCallParams {
     pub is_static: bool,
 }
 
+/// Root of an empty Merkle-Patricia trie, the storage root of accounts without storage
+pub const EMPTY_TRIE_ROOT: [u8; 32] = hex_literal(b"56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421");
+/// Keccak-256 of empty code
+pub const EMPTY_CODE_HASH: [u8; 32] = hex_literal(b"c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+
+const fn hex_literal(hex: &[u8; 64]) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("invalid hex digit"),
+        }
+    }
+    let mut out = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        out[i] = (nibble(hex[2 * i]) << 4) | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    out
+}
+
+/// Receipt of an executed transaction in Ethereum's post-Byzantium layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvmReceipt {
+    pub success: bool,
+    /// Gas used in the block up to and including this transaction
+    pub cumulative_gas_used: Gas,
+    pub logs: Vec<EvmLog>,
+}
+
+impl EvmReceipt {
+    pub fn new(result: &EvmExecutionResult, cumulative_gas_used: Gas) -> Self {
+        Self { success: result.success, cumulative_gas_used, logs: result.logs.clone() }
+    }
+
+    /// 2048-bit bloom filter over the log addresses and topics
+    pub fn logs_bloom(&self) -> Result<[u8; 256]> {
+        let mut bloom = [0u8; 256];
+        for log in &self.logs {
+            let mut entries = vec![evm_bytes(&log.address)?.to_vec()];
+            for topic in &log.topics {
+                entries.push(topic_bytes(topic)?.to_vec());
+            }
+            for entry in entries {
+                let hash = Hasher::digest(HashAlgorithm::Keccak256, entry);
+                for pair in hash[..6].chunks(2) {
+                    let bit = (((pair[0] as usize) << 8) | pair[1] as usize) & 0x7ff;
+                    bloom[255 - bit / 8] |= 1 << (bit % 8);
+                }
+            }
+        }
+        Ok(bloom)
+    }
+}
+
+impl EvmTransaction {
+    /// Bytes whose Keccak-256 is signed: EIP-155 fields when `chain_id` is set,
+    /// the original six fields otherwise
+    pub fn signing_payload(&self) -> Result<Vec<u8>> {
+        let mut fields = self.unsigned_fields()?;
+        if self.chain_id != 0 {
+            fields.extend([Item::from(self.chain_id), Item::uint(0), Item::uint(0)]);
+        }
+        Ok(Item::List(fields).encode())
+    }
+
+    pub fn signing_hash(&self) -> Result<Hash> {
+        Ok(Hasher::digest(HashAlgorithm::Keccak256, self.signing_payload()?))
+    }
+
+    /// Keccak-256 of the signed encoding
+    pub fn hash(&self) -> Result<TxHash> {
+        Ok(TxHash::new(hash::to_hex(&Hasher::digest(HashAlgorithm::Keccak256, self.rlp_bytes()?))))
+    }
+
+    /// Sign with a secp256k1 key, setting `from` to the key's address
+    pub fn sign(&mut self, key: &KeyPair) -> Result<()> {
+        self.from = key.address()?;
+        let signature = sign_recoverable(&self.signing_hash()?, key.private_key.expose_secret())?;
+        let recovery_id = signature[64] as u64;
+        let v = if self.chain_id != 0 {
+            self.chain_id.checked_mul(2)
+                .and_then(|v| v.checked_add(35 + recovery_id))
+                .ok_or_else(|| EtherlinkError::Codec(format!("Chain id {} is too large for an EIP-155 signature", self.chain_id)))?
+        } else {
+            27 + recovery_id
+        };
+        self.signature = EvmSignature {
+            v,
+            r: signature[..32].to_vec(),
+            s: signature[32..64].to_vec(),
+        };
+        Ok(())
+    }
+
+    fn unsigned_fields(&self) -> Result<Vec<Item>> {
+        Ok(vec![
+            Item::from(self.nonce),
+            Item::from(self.gas_price),
+            Item::from(self.gas_limit),
+            match &self.to {
+                Some(to) => Item::address(to)?,
+                None => Item::Bytes(Vec::new()),
+            },
+            Item::from(self.value),
+            Item::from(self.data.clone()),
+        ])
+    }
+}
+
+impl Encodable for EvmTransaction {
+    fn rlp_item(&self) -> Result<Item> {
+        let mut fields = self.unsigned_fields()?;
+        fields.extend([
+            Item::from(self.signature.v),
+            Item::from(strip_zeros(&self.signature.r)),
+            Item::from(strip_zeros(&self.signature.s)),
+        ]);
+        Ok(Item::List(fields))
+    }
+}
+
+/// Decodes a signed legacy transaction, recovering `from` from the signature
+impl Decodable for EvmTransaction {
+    fn from_rlp_item(item: &Item) -> Result<Self> {
+        let fields = item.as_fields(9)?;
+        let v = fields[6].as_u64()?;
+        let (chain_id, recovery_id) = match v {
+            27 | 28 => (0, v - 27),
+            v if v >= 35 => ((v - 35) / 2, (v - 35) % 2),
+            v => return Err(EtherlinkError::Codec(format!("RLP: invalid signature v {}", v))),
+        };
+        let mut tx = EvmTransaction {
+            from: Address::new(String::new()),
+            to: match fields[3].as_bytes()? {
+                [] => None,
+                _ => Some(fields[3].as_address()?),
+            },
+            value: fields[4].as_u64()?,
+            data: fields[5].as_bytes()?.to_vec(),
+            gas_limit: fields[2].as_u64()?,
+            gas_price: fields[1].as_u64()?,
+            nonce: fields[0].as_u64()?,
+            chain_id,
+            signature: EvmSignature { v, r: left_pad(fields[7].as_bytes()?)?, s: left_pad(fields[8].as_bytes()?)? },
+        };
+        let mut signature = [0u8; 65];
+        signature[..32].copy_from_slice(&tx.signature.r);
+        signature[32..64].copy_from_slice(&tx.signature.s);
+        signature[64] = recovery_id as u8;
+        tx.from = recover_address(&tx.signing_hash()?, &signature)?;
+        Ok(tx)
+    }
+}
+
+impl Encodable for EvmLog {
+    fn rlp_item(&self) -> Result<Item> {
+        let topics = self.topics.iter()
+            .map(|topic| Ok(Item::from(topic_bytes(topic)?.to_vec())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Item::List(vec![Item::address(&self.address)?, Item::List(topics), Item::from(self.data.clone())]))
+    }
+}
+
+impl Decodable for EvmLog {
+    fn from_rlp_item(item: &Item) -> Result<Self> {
+        let fields = item.as_fields(3)?;
+        Ok(EvmLog {
+            address: fields[0].as_address()?,
+            topics: fields[1].as_list()?.iter()
+                .map(|topic| Ok(hash::to_hex(&topic.as_array()?)))
+                .collect::<Result<Vec<_>>>()?,
+            data: fields[2].as_bytes()?.to_vec(),
+        })
+    }
+}
+
+impl Encodable for EvmReceipt {
+    fn rlp_item(&self) -> Result<Item> {
+        Ok(Item::List(vec![
+            Item::uint(self.success as u128),
+            Item::from(self.cumulative_gas_used),
+            Item::from(self.logs_bloom()?.to_vec()),
+            Item::List(self.logs.iter().map(EvmLog::rlp_item).collect::<Result<Vec<_>>>()?),
+        ]))
+    }
+}
+
+/// Rejects receipts whose bloom does not match their logs
+impl Decodable for EvmReceipt {
+    fn from_rlp_item(item: &Item) -> Result<Self> {
+        let fields = item.as_fields(4)?;
+        let receipt = EvmReceipt {
+            success: match fields[0].as_u64()? {
+                0 => false,
+                1 => true,
+                status => return Err(EtherlinkError::Codec(format!("RLP: invalid receipt status {}", status))),
+            },
+            cumulative_gas_used: fields[1].as_u64()?,
+            logs: fields[3].as_list()?.iter().map(EvmLog::from_rlp_item).collect::<Result<Vec<_>>>()?,
+        };
+        if fields[2].as_array::<256>()? != receipt.logs_bloom()? {
+            return Err(EtherlinkError::Codec("RLP: receipt bloom does not match its logs".to_string()));
+        }
+        Ok(receipt)
+    }
+}
+
+/// State trie account node: `[nonce, balance, storage_root, code_hash]`
+impl Encodable for AccountInfo {
+    fn rlp_item(&self) -> Result<Item> {
+        let root = |value: &Option<String>, default: [u8; 32]| -> Result<Item> {
+            Ok(Item::from(value.as_deref().map(topic_bytes).transpose()?.unwrap_or(default).to_vec()))
+        };
+        Ok(Item::List(vec![
+            Item::from(self.nonce),
+            Item::from(self.balance),
+            root(&self.storage_root, EMPTY_TRIE_ROOT)?,
+            root(&self.code_hash, EMPTY_CODE_HASH)?,
+        ]))
+    }
+}
+
+impl Decodable for AccountInfo {
+    fn from_rlp_item(item: &Item) -> Result<Self> {
+        let fields = item.as_fields(4)?;
+        let root = |item: &Item, empty: [u8; 32]| -> Result<Option<String>> {
+            let value = item.as_array::<32>()?;
+            Ok((value != empty).then(|| hash::to_hex(&value)))
+        };
+        Ok(AccountInfo {
+            nonce: fields[0].as_u64()?,
+            balance: fields[1].as_u64()?,
+            storage_root: root(&fields[2], EMPTY_TRIE_ROOT)?,
+            code_hash: root(&fields[3], EMPTY_CODE_HASH)?,
+        })
+    }
+}
+
+/// 32 bytes of a `0x`-prefixed hex topic or hash
+fn topic_bytes(value: &str) -> Result<[u8; 32]> {
+    hex::decode(value.trim_start_matches("0x")).ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| EtherlinkError::Codec(format!("Invalid 32-byte hex value {}", value)))
+}
+
+/// Selector of Solidity's `Error(string)` revert
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of Solidity's `Panic(uint256)` revert
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Human-readable reason from the output of a reverted call
+///
+/// Decodes `Error(string)` and `Panic(uint256)`; custom errors and malformed data
+/// give `None`.
+pub fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    let (selector, data) = output.split_first_chunk::<4>()?;
+    match *selector {
+        ERROR_SELECTOR => {
+            let offset = abi_usize(data.get(..32)?)?;
+            let start = offset.checked_add(32)?;
+            let len = abi_usize(data.get(offset..start)?)?;
+            let message = data.get(start..start.checked_add(len)?)?;
+            String::from_utf8(message.to_vec()).ok()
+        }
+        PANIC_SELECTOR => {
+            let code = abi_usize(data.get(..32)?)?;
+            let meaning = match code {
+                0x01 => "assertion failed",
+                0x11 => "arithmetic overflow or underflow",
+                0x12 => "division or modulo by zero",
+                0x21 => "invalid enum value",
+                0x31 => "pop on empty array",
+                0x32 => "array index out of bounds",
+                0x41 => "out of memory",
+                0x51 => "call to uninitialized function",
+                _ => "unknown panic",
+            };
+            Some(format!("panic 0x{:02x}: {}", code, meaning))
+        }
+        _ => None,
+    }
+}
+
+/// A 32-byte ABI word that fits in a `usize`
+fn abi_usize(word: &[u8]) -> Option<usize> {
+    let (high, low) = word.split_at(24);
+    if high.iter().any(|b| *b != 0) {
+        return None;
+    }
+    usize::try_from(u64::from_be_bytes(low.try_into().ok()?)).ok()
+}
+
+fn strip_zeros(bytes: &[u8]) -> Vec<u8> {
+    let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    bytes[first..].to_vec()
+}
+
+fn left_pad(bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.len() > 32 || bytes.first() == Some(&0) {
+        return Err(EtherlinkError::Codec("RLP: invalid signature scalar".to_string()));
+    }
+    let mut out = vec![0u8; 32 - bytes.len()];
+    out.extend_from_slice(bytes);
+    Ok(out)
+}
+
 impl REVMClient {
     /// Create a new rEVM client
     pub fn new(config: REVMConfig) -> Self {
         Self {
             config,
             state: EvmState::default(),
+            history: VecDeque::new(),
         }
     }
 
@@ -163,6 +600,7 @@ impl REVMClient {
 
         // Set up initial state
         self.state.block_number = 0;
+        self.history.clear();
         self.state.block_timestamp = chrono::Utc::now().timestamp() as u64;
         self.state.block_gas_limit = self.config.gas_limit;
 
@@ -182,35 +620,60 @@ impl REVMClient {
         // Validate transaction
         self.validate_transaction(&tx)?;
 
-        // Check account balance and nonce
+        // Check nonce, intrinsic gas and that the sender can cover the worst case
+        let schedule = self.config.fee_schedule.clone();
         let sender_account = self.get_or_create_account(&tx.from);
         if sender_account.nonce != tx.nonce {
             return Err(EtherlinkError::ContractExecution(
                 format!("Invalid nonce: expected {}, got {}", sender_account.nonce, tx.nonce)
             ));
         }
+        let balance = sender_account.balance;
 
-        let total_cost = tx.value + (tx.gas_limit * tx.gas_price);
-        if sender_account.balance < total_cost {
+        let intrinsic_gas = schedule.intrinsic_gas(&tx)?;
+        if tx.gas_limit < intrinsic_gas {
+            return Err(EtherlinkError::ContractExecution(format!("Gas limit {} is below the intrinsic gas {}", tx.gas_limit, intrinsic_gas)));
+        }
+        if balance < schedule.max_cost(&tx)? {
             return Err(EtherlinkError::ContractExecution("Insufficient balance".to_string()));
         }
 
         // Execute transaction
-        let result = if tx.to.is_some() {
-            self.execute_call(&tx).await?
+        let mut result = if tx.to.is_some() {
+            self.execute_call(&tx, intrinsic_gas).await?
         } else {
-            self.execute_create(&tx).await?
+            self.execute_create(&tx, intrinsic_gas).await?
         };
-
-        // Apply state changes
-        if result.success {
-            self.apply_state_changes(&tx, &result).await?;
+        if result.gas_used > tx.gas_limit {
+            result = EvmExecutionResult {
+                success: false,
+                gas_used: tx.gas_limit,
+                gas_refunded: 0,
+                output: Vec::new(),
+                logs: Vec::new(),
+                state_changes: HashMap::new(),
+                created_address: None,
+                revert_reason: Some("out of gas".to_string()),
+                fee: FeeSettlement::default(),
+            };
         }
 
+        // Fees are charged whether or not execution succeeded
+        result.fee = schedule.settle(tx.gas_price, result.gas_used, result.gas_refunded)?;
+        self.apply_state_changes(&tx, &result).await?;
+
         debug!("EVM transaction executed, gas used: {}", result.gas_used);
         Ok(result)
     }
 
+    /// Execute a transaction against a fork of the current state, leaving it untouched
+    ///
+    /// Nonce, balance and gas checks apply as in [`REVMClient::execute_transaction`].
+    pub async fn simulate(&self, tx: EvmTransaction) -> Result<EvmExecutionResult> {
+        let mut fork = REVMClient { config: self.config.clone(), state: self.state.clone(), history: VecDeque::new() };
+        fork.execute_transaction(tx).await
+    }
+
     /// Call a contract method (read-only)
     pub async fn call_contract(&self, params: EvmCallParams) -> Result<Vec<u8>> {
         debug!("Calling EVM contract at {} (read-only)", params.to);
@@ -288,9 +751,21 @@ impl REVMClient {
     }
 
     /// Set account balance (for testing)
+    ///
+    /// The difference from the previous balance counts as issued (or withdrawn) supply.
     pub fn set_balance(&mut self, address: Address, balance: u64) {
         let account = self.get_or_create_account(&address);
+        let previous = account.balance;
         account.balance = balance;
+        self.state.issued = (self.state.issued + balance as u128).saturating_sub(previous as u128);
+    }
+
+    /// Check that balances plus burned fees add up to the issued supply
+    ///
+    /// Every transaction's updates pass the same check before they are written.
+    pub fn check_invariants(&self) -> Result<()> {
+        let held: u128 = self.state.accounts.values().map(|account| account.balance as u128).sum();
+        check_supply(&self.state, held + self.state.burned)
     }
 
     /// Get contract code
@@ -303,24 +778,51 @@ impl REVMClient {
         self.state.storage.get(address)?.get(key)
     }
 
+    /// Seal the current block and start the next one, returning the sealed height
+    ///
+    /// The sealed block's state is kept for [`REVMClient::at_block`], up to
+    /// `history_blocks` blocks back.
+    pub fn seal_block(&mut self) -> u64 {
+        debug_assert!(self.check_invariants().is_ok(), "sealing EVM block {} with unconserved supply", self.state.block_number);
+        let sealed = self.state.block_number;
+        self.history.push_back(self.state.clone());
+        while self.history.len() > self.config.history_blocks {
+            self.history.pop_front();
+        }
+        self.state.block_number += 1;
+        self.state.block_timestamp = chrono::Utc::now().timestamp() as u64;
+        debug!("Sealed EVM block {}", sealed);
+        sealed
+    }
+
+    /// Read-only view of the state as of block `height`
+    ///
+    /// The current, unsealed block is readable as well as sealed blocks still in history.
+    pub fn at_block(&self, height: u64) -> Result<EvmStateView<'_>> {
+        if height == self.state.block_number {
+            return Ok(EvmStateView { state: &self.state });
+        }
+        if height > self.state.block_number {
+            return Err(EtherlinkError::Unsupported(format!("EVM block {} has not been produced yet", height)));
+        }
+        self.history
+            .iter()
+            .find(|state| state.block_number == height)
+            .map(|state| EvmStateView { state })
+            .ok_or_else(|| EtherlinkError::Unsupported(format!("EVM state at block {} has been pruned", height)))
+    }
+
     /// Estimate gas for a transaction
     pub async fn estimate_gas(&self, tx: &EvmTransaction) -> Result<Gas> {
         debug!("Estimating gas for EVM transaction");
 
         // TODO: Implement actual gas estimation
-        // For now, return a conservative estimate based on transaction type
-        let base_gas = if tx.to.is_none() {
-            53000 // Contract creation
-        } else {
-            21000 // Simple transfer
-        };
-
-        let data_gas = tx.data.len() as Gas * 16; // 16 gas per byte of data
-        Ok(base_gas + data_gas)
+        // For now, only the intrinsic gas of the transaction is counted
+        self.config.fee_schedule.intrinsic_gas(tx)
     }
 
     /// Execute a contract call transaction
-    async fn execute_call(&self, tx: &EvmTransaction) -> Result<EvmExecutionResult> {
+    async fn execute_call(&self, tx: &EvmTransaction, intrinsic_gas: Gas) -> Result<EvmExecutionResult> {
         let to = tx.to.as_ref().unwrap();
 
         // Get contract code
@@ -337,40 +839,44 @@ impl REVMClient {
                     gas_limit: tx.gas_limit,
                     is_static: false,
                 };
-                return self.execute_code(&params, code).await;
+                let mut result = self.execute_code(&params, code).await?;
+                result.gas_used = result.gas_used.checked_add(intrinsic_gas).ok_or_else(|| overflow("gas used"))?;
+                return Ok(result);
             }
         }
 
         // Simple transfer
         Ok(EvmExecutionResult {
             success: true,
-            gas_used: 21000,
+            gas_used: intrinsic_gas,
             gas_refunded: 0,
             output: Vec::new(),
             logs: Vec::new(),
             state_changes: HashMap::new(),
             created_address: None,
             revert_reason: None,
+            fee: FeeSettlement::default(),
         })
     }
 
     /// Execute a contract creation transaction
-    async fn execute_create(&self, tx: &EvmTransaction) -> Result<EvmExecutionResult> {
+    async fn execute_create(&self, tx: &EvmTransaction, intrinsic_gas: Gas) -> Result<EvmExecutionResult> {
         // Generate contract address
-        let contract_address = self.generate_contract_address(&tx.from, tx.nonce);
+        let contract_address = crate::address::create_address(&tx.from, tx.nonce)?;
 
         // TODO: Execute constructor and deploy code
         debug!("Creating contract at {}", contract_address);
 
         Ok(EvmExecutionResult {
             success: true,
-            gas_used: 53000,
+            gas_used: intrinsic_gas,
             gas_refunded: 0,
             output: Vec::new(),
             logs: Vec::new(),
             state_changes: HashMap::new(),
             created_address: Some(contract_address),
             revert_reason: None,
+            fee: FeeSettlement::default(),
         })
     }
 
@@ -390,6 +896,7 @@ impl REVMClient {
             state_changes: HashMap::new(),
             created_address: None,
             revert_reason: None,
+            fee: FeeSettlement::default(),
         })
     }
 
@@ -415,43 +922,68 @@ impl REVMClient {
         self.state.accounts.entry(address.clone()).or_insert_with(AccountInfo::default)
     }
 
-    /// Generate contract address
-    fn generate_contract_address(&self, deployer: &Address, nonce: u64) -> Address {
-        // TODO: Implement proper CREATE address generation
-        use sha2::{Sha256, Digest};
-        let mut hasher = Sha256::new();
-        hasher.update(deployer.as_str().as_bytes());
-        hasher.update(nonce.to_le_bytes());
-        let hash = hasher.finalize();
-        Address::new(format!("0x{}", hex::encode(&hash[..20])))
-    }
-
-    /// Apply state changes after successful execution
+    /// Charge fees and, if execution succeeded, apply its effects
+    ///
+    /// The sender always pays `result.fee` and has its nonce bumped; the value transfer
+    /// and contract state changes only happen on success. Balance changes in
+    /// `state_changes` must net to zero, since execution moves value but cannot mint it.
     async fn apply_state_changes(&mut self, tx: &EvmTransaction, result: &EvmExecutionResult) -> Result<()> {
-        // Update sender account
-        let sender = self.get_or_create_account(&tx.from);
-        sender.nonce += 1;
-        sender.balance -= tx.gas_limit * tx.gas_price; // Deduct gas cost
-        sender.balance += (tx.gas_limit - result.gas_used) * tx.gas_price; // Refund unused gas
-
-        if let Some(to) = &tx.to {
-            // Update recipient balance
-            let recipient = self.get_or_create_account(to);
-            recipient.balance += tx.value;
+        let fee = result.fee;
+        if result.success {
+            let net: i128 = result.state_changes.values().filter_map(|change| change.balance_change).map(i128::from).sum();
+            if net != 0 {
+                let issued = self.state.issued;
+                return Err(StateInvariantViolation::SupplyNotConserved {
+                    block: self.state.block_number,
+                    expected: issued,
+                    actual: issued.saturating_add_signed(net),
+                }.into());
+            }
         }
 
-        // Apply other state changes
-        for (address, change) in &result.state_changes {
-            let account = self.get_or_create_account(address);
-
-            if let Some(balance_change) = change.balance_change {
-                account.balance = (account.balance as i64 + balance_change) as u64;
+        // Work out every account update before writing any, so a failed check leaves the state untouched
+        let mut updated: HashMap<Address, AccountInfo> = HashMap::new();
+        let debit = if result.success { invariants::add(fee.total_fee, tx.value, "transaction cost")? } else { fee.total_fee };
+        let sender = staged(&mut updated, &self.state.accounts, &tx.from);
+        sender.balance = invariants::debit(&tx.from, sender.balance, debit)?;
+        sender.nonce = invariants::add(sender.nonce, 1, "nonce")?;
+        let burned = self.state.burned.checked_add(fee.burned as u128)
+            .ok_or_else(|| overflow("burned fee total"))?;
+
+        if fee.priority_fee > 0
+            && let Some(coinbase) = &self.config.fee_schedule.coinbase
+        {
+            credit(staged(&mut updated, &self.state.accounts, coinbase), fee.priority_fee)?;
+        }
+        if result.success {
+            if let Some(to) = &tx.to {
+                credit(staged(&mut updated, &self.state.accounts, to), tx.value)?;
             }
-
-            if let Some(nonce_change) = change.nonce_change {
-                account.nonce = (account.nonce as i64 + nonce_change) as u64;
+            for (address, change) in &result.state_changes {
+                let account = staged(&mut updated, &self.state.accounts, address);
+                if let Some(balance_change) = change.balance_change {
+                    account.balance = invariants::apply_balance_delta(address, account.balance, balance_change)?;
+                }
+                if let Some(nonce_change) = change.nonce_change {
+                    account.nonce = invariants::apply_delta(account.nonce, nonce_change, "nonce")?;
+                }
             }
+        }
 
+        // Supply must still be conserved once the updates land, or none of them are written
+        let held: u128 = self.state.accounts.iter()
+            .filter(|(address, _)| !updated.contains_key(*address))
+            .map(|(_, account)| account.balance as u128)
+            .chain(updated.values().map(|account| account.balance as u128))
+            .sum();
+        check_supply(&self.state, held + burned)?;
+
+        self.state.accounts.extend(updated);
+        self.state.burned = burned;
+        if !result.success {
+            return Ok(());
+        }
+        for (address, change) in &result.state_changes {
             if let Some(code) = &change.code_change {
                 self.state.codes.insert(address.clone(), code.clone());
             }
@@ -487,6 +1019,52 @@ impl REVMClient {
     }
 }
 
+/// Pending copy of `address`'s account in `updated`, taken from `accounts` on first use
+fn staged<'a>(updated: &'a mut HashMap<Address, AccountInfo>, accounts: &HashMap<Address, AccountInfo>, address: &Address) -> &'a mut AccountInfo {
+    updated.entry(address.clone()).or_insert_with(|| accounts.get(address).cloned().unwrap_or_default())
+}
+
+/// [`StateInvariantViolation::SupplyNotConserved`] unless `actual` matches the issued supply
+fn check_supply(state: &EvmState, actual: u128) -> Result<()> {
+    if actual != state.issued {
+        return Err(StateInvariantViolation::SupplyNotConserved { block: state.block_number, expected: state.issued, actual }.into());
+    }
+    Ok(())
+}
+
+fn credit(account: &mut AccountInfo, amount: u64) -> Result<()> {
+    account.balance = invariants::add(account.balance, amount, "balance")?;
+    Ok(())
+}
+
+/// EVM state as of one block, from [`REVMClient::at_block`]
+#[derive(Debug, Clone, Copy)]
+pub struct EvmStateView<'a> {
+    state: &'a EvmState,
+}
+
+impl<'a> EvmStateView<'a> {
+    pub fn block_number(&self) -> u64 {
+        self.state.block_number
+    }
+
+    pub fn get_balance(&self, address: &Address) -> u64 {
+        self.state.accounts.get(address).map(|acc| acc.balance).unwrap_or(0)
+    }
+
+    pub fn get_account_nonce(&self, address: &Address) -> u64 {
+        self.state.accounts.get(address).map(|acc| acc.nonce).unwrap_or(0)
+    }
+
+    pub fn get_code(&self, address: &Address) -> Option<&'a Vec<u8>> {
+        self.state.codes.get(address)
+    }
+
+    pub fn get_storage(&self, address: &Address, key: &str) -> Option<&'a Vec<u8>> {
+        self.state.storage.get(address)?.get(key)
+    }
+}
+
 impl Default for REVMClient {
     fn default() -> Self {
         Self::with_defaults()
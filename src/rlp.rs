@@ -0,0 +1,243 @@
+//! Recursive Length Prefix encoding
+//!
+//! RLP is the wire format of EVM transactions, receipts and state trie nodes. Values
+//! are built as an [`Item`] tree (byte strings and lists) and encoded in one pass;
+//! [`decode`] is strict and rejects anything a conforming encoder would not produce
+//! (non-minimal lengths, single bytes wrapped in a prefix, leading zeros in integers,
+//! trailing data), so a decoded value re-encodes to the same bytes.
+
+use crate::{Address, EtherlinkError, Result};
+
+/// Deepest list nesting [`decode`] accepts
+///
+/// Conforming payloads nest a handful of levels; the limit keeps a hostile payload
+/// from exhausting the stack.
+pub const MAX_DEPTH: usize = 256;
+
+/// Decoded or to-be-encoded RLP value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Bytes(Vec<u8>),
+    List(Vec<Item>),
+}
+
+impl Item {
+    /// Scalar as a big-endian byte string without leading zeros (zero is empty)
+    pub fn uint(value: u128) -> Self {
+        let bytes = value.to_be_bytes();
+        let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        Item::Bytes(bytes[first..].to_vec())
+    }
+
+    /// 20 raw bytes of a `0x` address
+    pub fn address(address: &Address) -> Result<Self> {
+        Ok(Item::Bytes(crate::address::evm_bytes(address)?.to_vec()))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_to(&mut out);
+        out
+    }
+
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        match self {
+            Item::Bytes(bytes) if bytes.len() == 1 && bytes[0] < 0x80 => out.push(bytes[0]),
+            Item::Bytes(bytes) => {
+                write_header(out, 0x80, bytes.len());
+                out.extend_from_slice(bytes);
+            }
+            Item::List(items) => {
+                let mut payload = Vec::new();
+                for item in items {
+                    item.encode_to(&mut payload);
+                }
+                write_header(out, 0xc0, payload.len());
+                out.extend_from_slice(&payload);
+            }
+        }
+    }
+
+    pub fn as_bytes(&self) -> Result<&[u8]> {
+        match self {
+            Item::Bytes(bytes) => Ok(bytes),
+            Item::List(_) => Err(codec("expected a byte string, found a list")),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[Item]> {
+        match self {
+            Item::List(items) => Ok(items),
+            Item::Bytes(_) => Err(codec("expected a list, found a byte string")),
+        }
+    }
+
+    /// List with exactly `len` elements
+    pub fn as_fields(&self, len: usize) -> Result<&[Item]> {
+        let items = self.as_list()?;
+        if items.len() != len {
+            return Err(codec(&format!("expected {} fields, found {}", len, items.len())));
+        }
+        Ok(items)
+    }
+
+    pub fn as_u64(&self) -> Result<u64> {
+        u64::try_from(self.as_u128()?).map_err(|_| codec("integer overflows u64"))
+    }
+
+    pub fn as_u128(&self) -> Result<u128> {
+        let bytes = self.as_bytes()?;
+        if bytes.len() > 16 {
+            return Err(codec("integer overflows u128"));
+        }
+        if bytes.first() == Some(&0) {
+            return Err(codec("integer has leading zeros"));
+        }
+        Ok(bytes.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128))
+    }
+
+    /// Fixed-size byte string such as a hash or address
+    pub fn as_array<const N: usize>(&self) -> Result<[u8; N]> {
+        let bytes = self.as_bytes()?;
+        bytes.try_into().map_err(|_| codec(&format!("expected {} bytes, found {}", N, bytes.len())))
+    }
+
+    /// EIP-55 address from a 20-byte string
+    pub fn as_address(&self) -> Result<Address> {
+        Ok(crate::address::evm_address(&self.as_array()?))
+    }
+}
+
+impl From<Vec<u8>> for Item {
+    fn from(bytes: Vec<u8>) -> Self {
+        Item::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Item {
+    fn from(bytes: &[u8]) -> Self {
+        Item::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<&str> for Item {
+    fn from(value: &str) -> Self {
+        Item::Bytes(value.as_bytes().to_vec())
+    }
+}
+
+impl From<u64> for Item {
+    fn from(value: u64) -> Self {
+        Item::uint(value as u128)
+    }
+}
+
+impl From<Vec<Item>> for Item {
+    fn from(items: Vec<Item>) -> Self {
+        Item::List(items)
+    }
+}
+
+/// Types with an RLP representation
+pub trait Encodable {
+    fn rlp_item(&self) -> Result<Item>;
+
+    fn rlp_bytes(&self) -> Result<Vec<u8>> {
+        Ok(self.rlp_item()?.encode())
+    }
+}
+
+/// Types that can be rebuilt from their RLP representation
+pub trait Decodable: Sized {
+    fn from_rlp_item(item: &Item) -> Result<Self>;
+
+    fn from_rlp_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::from_rlp_item(&decode(bytes)?)
+    }
+}
+
+/// Decode exactly one item spanning all of `bytes`
+pub fn decode(bytes: &[u8]) -> Result<Item> {
+    let (item, rest) = decode_prefix(bytes)?;
+    if !rest.is_empty() {
+        return Err(codec(&format!("{} trailing bytes", rest.len())));
+    }
+    Ok(item)
+}
+
+/// Decode the first item of `bytes`, returning the unread remainder
+pub fn decode_prefix(bytes: &[u8]) -> Result<(Item, &[u8])> {
+    decode_nested(bytes, 0)
+}
+
+/// [`decode_prefix`] for an item inside `depth` enclosing lists
+fn decode_nested(bytes: &[u8], depth: usize) -> Result<(Item, &[u8])> {
+    let (&prefix, rest) = bytes.split_first().ok_or_else(|| codec("unexpected end of input"))?;
+    match prefix {
+        0x00..=0x7f => Ok((Item::Bytes(vec![prefix]), rest)),
+        0x80..=0xbf => {
+            let (payload, rest) = read_payload(prefix, 0x80, rest)?;
+            if payload.len() == 1 && payload[0] < 0x80 {
+                return Err(codec("single byte below 0x80 must not carry a prefix"));
+            }
+            Ok((Item::Bytes(payload.to_vec()), rest))
+        }
+        0xc0..=0xff => {
+            if depth >= MAX_DEPTH {
+                return Err(codec(&format!("lists nested deeper than {}", MAX_DEPTH)));
+            }
+            let (mut payload, rest) = read_payload(prefix, 0xc0, rest)?;
+            let mut items = Vec::new();
+            while !payload.is_empty() {
+                let (item, remaining) = decode_nested(payload, depth + 1)?;
+                items.push(item);
+                payload = remaining;
+            }
+            Ok((Item::List(items), rest))
+        }
+    }
+}
+
+/// Split the payload announced by `prefix` off `rest`
+fn read_payload(prefix: u8, offset: u8, rest: &[u8]) -> Result<(&[u8], &[u8])> {
+    let short = prefix - offset;
+    let (len, rest) = if short <= 55 {
+        (short as usize, rest)
+    } else {
+        let len_of_len = (short - 55) as usize;
+        if rest.len() < len_of_len {
+            return Err(codec("truncated length"));
+        }
+        let (len_bytes, rest) = rest.split_at(len_of_len);
+        if len_bytes[0] == 0 {
+            return Err(codec("length has leading zeros"));
+        }
+        if len_of_len > size_of::<usize>() {
+            return Err(codec("length overflows usize"));
+        }
+        let len = len_bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        if len <= 55 {
+            return Err(codec("long form used for a short payload"));
+        }
+        (len, rest)
+    };
+    if rest.len() < len {
+        return Err(codec(&format!("payload needs {} bytes, {} left", len, rest.len())));
+    }
+    Ok(rest.split_at(len))
+}
+
+fn write_header(out: &mut Vec<u8>, offset: u8, len: usize) {
+    if len <= 55 {
+        out.push(offset + len as u8);
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let first = len_bytes.iter().position(|b| *b != 0).unwrap_or(len_bytes.len() - 1);
+        out.push(offset + 55 + (len_bytes.len() - first) as u8);
+        out.extend_from_slice(&len_bytes[first..]);
+    }
+}
+
+fn codec(message: &str) -> EtherlinkError {
+    EtherlinkError::Codec(format!("RLP: {}", message))
+}
@@ -0,0 +1,89 @@
+//! Supervision of the Zig bridge
+//!
+//! [`supervise_bridge`] waits for the bridge's [`BridgeLink`] to go down and retries
+//! recovery with exponential backoff until it succeeds. Calls made in the meantime
+//! fail fast with the retriable [`crate::EtherlinkError::BridgeDown`].
+
+use crate::Result;
+use crate::ffi::{BridgeLink, BridgeStatus};
+use std::future::Future;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Delay between bridge recovery attempts
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeBackoff {
+    /// Delay before the first attempt
+    pub initial: Duration,
+    /// Upper bound on the doubling delay
+    pub max: Duration,
+    /// Give up after this many failed attempts; `None` retries until shutdown
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for BridgeBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl BridgeBackoff {
+    /// Delay before the given attempt, counting from zero
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial.saturating_mul(2u32.saturating_pow(attempt)).min(self.max)
+    }
+}
+
+/// Recover the bridge each time it goes down, until `token` is cancelled
+///
+/// `recover` reinitializes the bridge and replays whatever it lost, e.g.
+/// [`crate::ghostplane::GhostPlaneClient::recover_bridge`]. If attempts are exhausted
+/// the bridge stays down and this returns.
+pub async fn supervise_bridge<F, Fut, T>(link: BridgeLink, backoff: BridgeBackoff, token: CancellationToken, recover: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = link.wait_down() => {}
+        }
+
+        let mut attempt = 0;
+        loop {
+            let delay = backoff.delay(attempt);
+            tokio::select! {
+                _ = token.cancelled() => return,
+                _ = tokio::time::sleep(delay) => {}
+            }
+
+            link.mark_reinitializing();
+            match recover().await {
+                Ok(_) => {
+                    info!("Zig bridge recovered after {} attempt(s)", attempt + 1);
+                    break;
+                }
+                Err(e) => {
+                    attempt += 1;
+                    if link.status() == BridgeStatus::Up {
+                        // Reinitialized, but replay failed; leave it to the callers
+                        warn!("Zig bridge recovered but replay failed: {}", e);
+                        break;
+                    }
+                    link.mark_down(e.to_string());
+                    if backoff.max_attempts.is_some_and(|max| attempt >= max) {
+                        warn!("Giving up on Zig bridge recovery after {} attempts: {}", attempt, e);
+                        return;
+                    }
+                    warn!("Zig bridge recovery attempt {} failed: {}", attempt, e);
+                }
+            }
+        }
+    }
+}
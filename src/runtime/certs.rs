@@ -0,0 +1,517 @@
+//! Certificates for `.ghost` names from the GhostChain CA
+//!
+//! Issuance follows the ACME DNS-01 flow with CNS standing in for DNS: the CA hands
+//! out a challenge token, the domain owner publishes it as a
+//! `ghost-acme-challenge=<token>` TXT record, the CA checks the record, and a CSR
+//! for a freshly generated key is exchanged for the certificate. [`CertManager`]
+//! runs the flow, keeps certificates in a [`CertStore`] and serves them to the
+//! HTTP gateway through a [`ManagedCertResolver`]; the daemon renews them on a
+//! schedule before they expire.
+
+use super::gateway::certified_key_from_pem;
+use crate::clients::ApiResponse;
+use crate::clients::context::{CallContext, WithContext};
+use crate::clock::{self, SharedClock};
+use crate::cns::{CNSClient, DnsRecord};
+use crate::validation::{ConfigErrors, Validator};
+use crate::{Address, EtherlinkError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tracing::{debug, info, warn};
+
+/// Prefix of the TXT value that proves control of a domain
+pub const CHALLENGE_TXT_PREFIX: &str = "ghost-acme-challenge=";
+
+/// TTL of the published challenge record, short so it disappears quickly
+const CHALLENGE_RECORD_TTL: u32 = 60;
+
+/// TTL the domain's other records are written back with, as CNS does not report theirs
+const KEPT_RECORD_TTL: u32 = 3600;
+
+/// Settings for certificates issued by the GhostChain CA
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateConfig {
+    /// CA service endpoint
+    pub ca_endpoint: String,
+    /// Directory issued certificates and their keys are kept in
+    pub store_dir: PathBuf,
+    /// Domains to keep certificates for
+    pub domains: Vec<String>,
+    /// Owner of the domains, who publishes the challenge records
+    pub owner: Address,
+    /// Renew once a certificate has less than this many days left
+    pub renew_before_days: u64,
+    /// How often certificates are checked for renewal
+    pub check_interval_secs: u64,
+    /// How often a pending order is polled
+    pub poll_interval_ms: u64,
+    /// How long an order may stay pending before issuance is abandoned
+    pub order_timeout_ms: u64,
+}
+
+impl Default for CertificateConfig {
+    fn default() -> Self {
+        Self {
+            ca_endpoint: "http://localhost:8560".to_string(),
+            store_dir: PathBuf::from("certs"),
+            domains: Vec::new(),
+            owner: Address::new(String::new()),
+            renew_before_days: 30,
+            check_interval_secs: 12 * 3600,
+            poll_interval_ms: 2000,
+            order_timeout_ms: 120_000,
+        }
+    }
+}
+
+impl CertificateConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.endpoint("ca_endpoint", &self.ca_endpoint, &["http", "https"], true, true);
+        v.check(!self.domains.is_empty(), "domains", "must list at least one domain");
+        for (i, domain) in self.domains.iter().enumerate() {
+            v.check(domain.contains('.') && !domain.starts_with('.'), &format!("domains[{}]", i), "must be a domain name with a TLD");
+        }
+        v.check(!self.owner.as_str().is_empty(), "owner", "must not be empty");
+        v.check(self.check_interval_secs > 0, "check_interval_secs", "must be greater than zero");
+        v.check(self.poll_interval_ms > 0, "poll_interval_ms", "must be greater than zero");
+        v.timeout("order_timeout_ms", self.order_timeout_ms);
+        v.finish()
+    }
+
+    pub fn renew_before(&self) -> Duration {
+        Duration::from_secs(self.renew_before_days * 24 * 3600)
+    }
+}
+
+/// Stage of a certificate order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// Waiting for the challenge record
+    Pending,
+    /// Challenge passed; waiting for a CSR
+    Ready,
+    /// CSR received; certificate being issued
+    Processing,
+    /// Certificate issued
+    Valid,
+    /// Challenge or issuance failed
+    Invalid,
+}
+
+/// A certificate order with the CA
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertOrder {
+    pub order_id: String,
+    pub domain: String,
+    pub status: OrderStatus,
+    /// Token to publish in the challenge TXT record
+    pub challenge_token: String,
+    /// Unix seconds after which the order can no longer be completed
+    pub expires_at: u64,
+    /// PEM chain, once the order is valid
+    pub certificate_pem: Option<String>,
+    /// Unix seconds the certificate expires at, once the order is valid
+    pub not_after: Option<u64>,
+    /// Why the order is invalid
+    pub error: Option<String>,
+}
+
+impl CertOrder {
+    /// TXT value proving control of the domain for this order
+    pub fn challenge_record(&self) -> String {
+        format!("{}{}", CHALLENGE_TXT_PREFIX, self.challenge_token)
+    }
+}
+
+/// REST client for the GhostChain certificate authority
+#[derive(Debug, Clone)]
+pub struct CertificateAuthorityClient {
+    base_url: String,
+    http_client: Arc<reqwest::Client>,
+    context: CallContext,
+}
+
+impl CertificateAuthorityClient {
+    pub fn new(endpoint: &str, http_client: Arc<reqwest::Client>) -> Self {
+        Self {
+            base_url: format!("{}/api/v1", endpoint.trim_end_matches('/')),
+            http_client,
+            context: CallContext::default(),
+        }
+    }
+
+    /// Open an order for `domain`, receiving its challenge token
+    pub async fn create_order(&self, domain: &str) -> Result<CertOrder> {
+        let url = format!("{}/orders", self.base_url);
+        self.post(&url, &serde_json::json!({ "domain": domain })).await
+    }
+
+    /// Tell the CA the challenge record is published
+    pub async fn submit_challenge(&self, order_id: &str) -> Result<CertOrder> {
+        let url = format!("{}/orders/{}/challenge", self.base_url, order_id);
+        self.post(&url, &serde_json::json!({})).await
+    }
+
+    /// Exchange a PEM certificate signing request for the certificate
+    pub async fn finalize_order(&self, order_id: &str, csr_pem: &str) -> Result<CertOrder> {
+        let url = format!("{}/orders/{}/finalize", self.base_url, order_id);
+        self.post(&url, &serde_json::json!({ "csr_pem": csr_pem })).await
+    }
+
+    pub async fn get_order(&self, order_id: &str) -> Result<CertOrder> {
+        let url = format!("{}/orders/{}", self.base_url, order_id);
+        let response: ApiResponse<CertOrder> = self.http_client
+            .get(&url)
+            .with_context(&self.context)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+
+    async fn post<B: Serialize>(&self, url: &str, body: &B) -> Result<CertOrder> {
+        let response: ApiResponse<CertOrder> = self.http_client
+            .post(url)
+            .with_context(&self.context)
+            .json(body)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        response.into_result()
+    }
+}
+
+/// An issued certificate with its private key
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredCertificate {
+    pub domain: String,
+    pub order_id: String,
+    pub certificate_pem: String,
+    pub private_key_pem: String,
+    /// Unix seconds
+    pub issued_at: u64,
+    /// Unix seconds
+    pub not_after: u64,
+}
+
+impl StoredCertificate {
+    /// Whether less than `renew_before` is left at `now` (unix seconds)
+    pub fn needs_renewal(&self, now: u64, renew_before: Duration) -> bool {
+        self.not_after.saturating_sub(now) < renew_before.as_secs()
+    }
+}
+
+impl fmt::Debug for StoredCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoredCertificate")
+            .field("domain", &self.domain)
+            .field("order_id", &self.order_id)
+            .field("issued_at", &self.issued_at)
+            .field("not_after", &self.not_after)
+            .finish_non_exhaustive()
+    }
+}
+
+/// One JSON file per domain; readable by the owner only on Unix
+#[derive(Debug, Clone)]
+pub struct CertStore {
+    dir: PathBuf,
+}
+
+impl CertStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, domain: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", domain))
+    }
+
+    /// Certificate stored for `domain`, if any
+    pub fn load(&self, domain: &str) -> Result<Option<StoredCertificate>> {
+        let path = self.path(domain);
+        match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| EtherlinkError::Configuration(format!("Corrupt certificate file {}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(EtherlinkError::Configuration(format!("Failed to read {}: {}", path.display(), e))),
+        }
+    }
+
+    /// Replace the certificate stored for its domain
+    pub fn save(&self, certificate: &StoredCertificate) -> Result<()> {
+        let write_error = |path: &std::path::Path, e: std::io::Error| {
+            EtherlinkError::Configuration(format!("Failed to write {}: {}", path.display(), e))
+        };
+        std::fs::create_dir_all(&self.dir).map_err(|e| write_error(&self.dir, e))?;
+        let path = self.path(&certificate.domain);
+        let partial = path.with_extension("json.partial");
+        let json = serde_json::to_vec_pretty(certificate)?;
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&partial).map_err(|e| write_error(&partial, e))?;
+        std::io::Write::write_all(&mut file, &json).map_err(|e| write_error(&partial, e))?;
+        file.sync_all().map_err(|e| write_error(&partial, e))?;
+        std::fs::rename(&partial, &path).map_err(|e| write_error(&path, e))
+    }
+}
+
+/// Picks the managed certificate matching the TLS server name
+#[derive(Default)]
+pub struct ManagedCertResolver {
+    keys: RwLock<HashMap<String, Arc<CertifiedKey>>>,
+}
+
+impl ManagedCertResolver {
+    /// Serve `certificate` for its domain from the next handshake on
+    pub fn install(&self, certificate: &StoredCertificate) -> Result<()> {
+        let key = certified_key_from_pem(certificate.certificate_pem.as_bytes(), certificate.private_key_pem.as_bytes())
+            .map_err(|e| EtherlinkError::Configuration(format!("Invalid certificate for {}: {}", certificate.domain, e)))?;
+        self.keys.write().unwrap().insert(certificate.domain.to_ascii_lowercase(), Arc::new(key));
+        Ok(())
+    }
+
+    /// Domains with a certificate installed
+    pub fn domains(&self) -> Vec<String> {
+        let mut domains: Vec<String> = self.keys.read().unwrap().keys().cloned().collect();
+        domains.sort();
+        domains
+    }
+}
+
+impl ResolvesServerCert for ManagedCertResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        let name = client_hello.server_name()?.trim_end_matches('.').to_ascii_lowercase();
+        self.keys.read().unwrap().get(&name).cloned()
+    }
+}
+
+impl fmt::Debug for ManagedCertResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ManagedCertResolver").field("domains", &self.domains()).finish()
+    }
+}
+
+/// Obtains, stores and renews certificates for the configured domains
+#[derive(Debug, Clone)]
+pub struct CertManager {
+    ca: CertificateAuthorityClient,
+    cns: CNSClient,
+    store: CertStore,
+    resolver: Arc<ManagedCertResolver>,
+    config: CertificateConfig,
+    clock: SharedClock,
+}
+
+impl CertManager {
+    /// Create a manager, serving any certificates already in the store
+    pub fn new(cns: CNSClient, config: CertificateConfig) -> Result<Self> {
+        config.validate()?;
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.order_timeout_ms))
+            .build()
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        let manager = Self {
+            ca: CertificateAuthorityClient::new(&config.ca_endpoint, Arc::new(http_client)),
+            cns,
+            store: CertStore::new(&config.store_dir),
+            resolver: Arc::new(ManagedCertResolver::default()),
+            config,
+            clock: clock::system(),
+        };
+        for domain in &manager.config.domains {
+            match manager.store.load(domain) {
+                Ok(Some(certificate)) => manager.resolver.install(&certificate)?,
+                Ok(None) => {}
+                Err(e) => warn!("Ignoring stored certificate for {}: {}", domain, e),
+            }
+        }
+        Ok(manager)
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Resolver for the gateway's TLS acceptor
+    pub fn resolver(&self) -> Arc<ManagedCertResolver> {
+        self.resolver.clone()
+    }
+
+    pub fn store(&self) -> &CertStore {
+        &self.store
+    }
+
+    pub fn config(&self) -> &CertificateConfig {
+        &self.config
+    }
+
+    /// Check for due certificates every `check_interval_secs`, starting now
+    ///
+    /// Runs until dropped; failures are logged and retried at the next check.
+    pub async fn run_renewals(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.check_interval_secs));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            for (domain, outcome) in self.renew_due().await {
+                if let Err(e) = outcome {
+                    warn!("Certificate renewal for {} failed: {}", domain, e);
+                }
+            }
+        }
+    }
+
+    /// Issue certificates that are missing or due for renewal
+    ///
+    /// Every domain is attempted; failures are returned alongside the domain.
+    pub async fn renew_due(&self) -> Vec<(String, Result<StoredCertificate>)> {
+        let now = self.clock.now();
+        let mut outcomes = Vec::new();
+        for domain in &self.config.domains {
+            let due = match self.store.load(domain) {
+                Ok(Some(certificate)) => certificate.needs_renewal(now, self.config.renew_before()),
+                Ok(None) => true,
+                Err(e) => {
+                    warn!("Replacing unreadable certificate for {}: {}", domain, e);
+                    true
+                }
+            };
+            if due {
+                outcomes.push((domain.clone(), self.issue(domain).await));
+            }
+        }
+        outcomes
+    }
+
+    /// Prove control of `domain` and obtain, store and install a new certificate
+    pub async fn issue(&self, domain: &str) -> Result<StoredCertificate> {
+        info!("Requesting certificate for {}", domain);
+        let order = self.ca.create_order(domain).await?;
+        let challenge = order.challenge_record();
+        let tx_hash = self.publish_challenge(domain, &challenge, true).await?;
+        debug!("Published challenge for {} in {}", domain, tx_hash);
+
+        let result = self.complete_order(domain, order).await;
+        // The record only matters while the CA checks it
+        if let Err(e) = self.publish_challenge(domain, &challenge, false).await {
+            warn!("Failed to remove challenge record for {}: {}", domain, e);
+        }
+
+        let certificate = result?;
+        self.store.save(&certificate)?;
+        self.resolver.install(&certificate)?;
+        info!("Certificate for {} issued, valid until {}", domain, certificate.not_after);
+        Ok(certificate)
+    }
+
+    async fn complete_order(&self, domain: &str, order: CertOrder) -> Result<StoredCertificate> {
+        let order = self.ca.submit_challenge(&order.order_id).await?;
+        let order = self.wait_for(order, &[OrderStatus::Ready, OrderStatus::Valid]).await?;
+
+        let (csr_pem, private_key_pem) = certificate_request(domain)?;
+        let order = self.ca.finalize_order(&order.order_id, &csr_pem).await?;
+        let order = self.wait_for(order, &[OrderStatus::Valid]).await?;
+
+        let (Some(certificate_pem), Some(not_after)) = (order.certificate_pem, order.not_after) else {
+            return Err(EtherlinkError::Api(format!("CA marked order {} valid without a certificate", order.order_id)));
+        };
+        Ok(StoredCertificate {
+            domain: domain.to_string(),
+            order_id: order.order_id,
+            certificate_pem,
+            private_key_pem,
+            issued_at: self.clock.now(),
+            not_after,
+        })
+    }
+
+    /// Poll `order` until it reaches one of `statuses`, fails, or times out
+    async fn wait_for(&self, mut order: CertOrder, statuses: &[OrderStatus]) -> Result<CertOrder> {
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(self.config.order_timeout_ms);
+        loop {
+            if statuses.contains(&order.status) {
+                return Ok(order);
+            }
+            if order.status == OrderStatus::Invalid {
+                return Err(EtherlinkError::Api(format!(
+                    "Certificate order {} for {} failed: {}",
+                    order.order_id,
+                    order.domain,
+                    order.error.as_deref().unwrap_or("no reason given")
+                )));
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(EtherlinkError::Timeout(format!("Certificate order {} still {:?}", order.order_id, order.status)));
+            }
+            tokio::time::sleep(Duration::from_millis(self.config.poll_interval_ms)).await;
+            order = self.ca.get_order(&order.order_id).await?;
+        }
+    }
+
+    /// Add or remove the `challenge` TXT value, leaving every other record as it is
+    ///
+    /// The records are read past the resolution cache right before the update, so
+    /// changes made while the CA was checking survive the removal, and removing puts
+    /// the TXT values back the way they were before the challenge was added.
+    ///
+    /// [`CNSClient::update_domain_records`] does not submit anything to CNS yet, so
+    /// until it does the challenge is never actually published.
+    async fn publish_challenge(&self, domain: &str, challenge: &str, present: bool) -> Result<String> {
+        let resolution = self.cns.resolve_domain_uncached(domain).await?;
+        let mut records: Vec<DnsRecord> = resolution
+            .records
+            .iter()
+            .filter(|(record_type, value)| !(*record_type == "TXT" && *value == challenge))
+            .map(|(record_type, value)| DnsRecord {
+                record_type: record_type.to_string(),
+                value: value.to_string(),
+                ttl: KEPT_RECORD_TTL,
+                priority: None,
+            })
+            .collect();
+        if present {
+            records.push(DnsRecord {
+                record_type: "TXT".to_string(),
+                value: challenge.to_string(),
+                ttl: CHALLENGE_RECORD_TTL,
+                priority: None,
+            });
+        }
+        self.cns.update_domain_records(domain, &self.config.owner, records).await
+    }
+}
+
+/// PEM CSR for `domain` and the PEM private key it was made with
+fn certificate_request(domain: &str) -> Result<(String, String)> {
+    let mut params = rcgen::CertificateParams::new(vec![domain.to_string()]);
+    params.distinguished_name.push(rcgen::DnType::CommonName, domain);
+    let request = rcgen::Certificate::from_params(params)
+        .map_err(|e| EtherlinkError::Crypto(format!("Failed to generate key for {}: {}", domain, e)))?;
+    let csr_pem = request
+        .serialize_request_pem()
+        .map_err(|e| EtherlinkError::Crypto(format!("Failed to build CSR for {}: {}", domain, e)))?;
+    Ok((csr_pem, request.serialize_private_key_pem()))
+}
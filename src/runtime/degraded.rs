@@ -0,0 +1,166 @@
+//! Running with optional services down
+//!
+//! Services listed in [`DegradationConfig::optional`] may be unreachable without
+//! failing the runtime: construction goes ahead, [`super::Etherlink::health_report`]
+//! reports them as degraded rather than unhealthy, and calls made through
+//! [`super::Etherlink::call_optional`] are rejected or held back per
+//! [`DegradedCallPolicy`] until a background probe sees the service answer again.
+
+use crate::validation::{ConfigErrors, Validator};
+use crate::{EtherlinkError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Services that can be marked optional
+pub const OPTIONAL_SERVICES: &[&str] = &["cns", "ghostplane", "gledger", "gsig"];
+
+/// What happens to a call for an optional service while it is down
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "mode")]
+pub enum DegradedCallPolicy {
+    /// Fail at once with [`EtherlinkError::ServiceUnavailable`]
+    #[default]
+    Reject,
+    /// Hold the call until the service is back, failing after `max_wait_ms`
+    Queue { max_wait_ms: u64 },
+}
+
+/// Which services may be down and how calls to them behave meanwhile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DegradationConfig {
+    /// Names from [`OPTIONAL_SERVICES`]
+    pub optional: BTreeSet<String>,
+    pub policy: DegradedCallPolicy,
+    /// Interval between health probes of services that are down
+    pub probe_interval_ms: u64,
+}
+
+impl Default for DegradationConfig {
+    fn default() -> Self {
+        Self {
+            optional: BTreeSet::new(),
+            policy: DegradedCallPolicy::default(),
+            probe_interval_ms: 5000,
+        }
+    }
+}
+
+impl DegradationConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        for service in &self.optional {
+            if !OPTIONAL_SERVICES.contains(&service.as_str()) {
+                v.push("optional", format!("`{}` cannot be optional; expected one of {}", service, OPTIONAL_SERVICES.join(", ")));
+            }
+        }
+        if let DegradedCallPolicy::Queue { max_wait_ms } = self.policy {
+            v.check(max_wait_ms > 0, "policy.max_wait_ms", "must be greater than zero");
+        }
+        v.check(self.probe_interval_ms > 0, "probe_interval_ms", "must be greater than zero");
+        v.finish()
+    }
+
+    pub fn is_optional(&self, service: &str) -> bool {
+        self.optional.contains(service)
+    }
+
+    pub fn probe_interval(&self) -> Duration {
+        Duration::from_millis(self.probe_interval_ms)
+    }
+}
+
+/// Last known reachability of each optional service, shared by the runtime's tasks
+#[derive(Debug, Clone, Default)]
+pub struct ServiceAvailability {
+    /// `true` while the service is up; services never marked are assumed up
+    states: Arc<Mutex<HashMap<String, watch::Sender<bool>>>>,
+}
+
+impl ServiceAvailability {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn sender(&self, service: &str) -> watch::Sender<bool> {
+        self.states
+            .lock()
+            .unwrap()
+            .entry(service.to_string())
+            .or_insert_with(|| watch::channel(true).0)
+            .clone()
+    }
+
+    /// Record that `service` could not be reached
+    pub fn mark_down(&self, service: &str, reason: &str) {
+        if self.sender(service).send_replace(false) {
+            warn!("Optional service {} is down, continuing degraded: {}", service, reason);
+        }
+    }
+
+    /// Record that `service` answered again, releasing queued calls
+    pub fn mark_up(&self, service: &str) {
+        if !self.sender(service).send_replace(true) {
+            info!("Optional service {} recovered", service);
+        }
+    }
+
+    pub fn is_available(&self, service: &str) -> bool {
+        self.states.lock().unwrap().get(service).is_none_or(|state| *state.borrow())
+    }
+
+    /// Services currently marked down
+    pub fn unavailable(&self) -> Vec<String> {
+        let mut down: Vec<String> = self
+            .states
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, state)| !*state.borrow())
+            .map(|(service, _)| service.clone())
+            .collect();
+        down.sort();
+        down
+    }
+
+    /// Wait until `service` may be called, per `policy`
+    pub async fn admit(&self, service: &str, policy: DegradedCallPolicy) -> Result<()> {
+        let mut state = self.sender(service).subscribe();
+        if *state.borrow_and_update() {
+            return Ok(());
+        }
+        match policy {
+            DegradedCallPolicy::Reject => Err(EtherlinkError::ServiceUnavailable(format!("{} is down", service))),
+            DegradedCallPolicy::Queue { max_wait_ms } => {
+                match tokio::time::timeout(Duration::from_millis(max_wait_ms), state.wait_for(|up| *up)).await {
+                    Ok(Ok(_)) => Ok(()),
+                    _ => Err(EtherlinkError::ServiceUnavailable(format!(
+                        "{} is down and did not recover within {}ms",
+                        service, max_wait_ms
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// Run `call` once `service` is admitted, marking it down if the call cannot reach it
+    pub async fn call<T, F>(&self, service: &str, policy: DegradedCallPolicy, call: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        self.admit(service, policy).await?;
+        let result = call.await;
+        if let Err(e) = &result
+            && e.is_retriable()
+        {
+            self.mark_down(service, &e.to_string());
+        }
+        result
+    }
+}
@@ -0,0 +1,399 @@
+//! DNS gateway: answer standard DNS queries for CNS names
+//!
+//! With the `dns-gateway` feature, [`super::Etherlink::serve_dns`] answers `A`,
+//! `AAAA`, `CNAME`, `TXT` and `MX` queries over UDP and TCP for `.ghost` and bridged
+//! TLDs by resolving through [`CNSClient`], so a stub resolver (e.g. a
+//! systemd-resolved routing domain) can send GhostChain names to a local daemon.
+//! Answer TTLs never outlive the CNS cache entry or the domain's registration.
+//! Names outside CNS are refused, letting the OS fall through to its usual resolver.
+//! At most `max_concurrent_queries` queries are answered at once; UDP queries beyond
+//! that are dropped for the client to retry and extra TCP connections are closed.
+
+use crate::cns::{CNSClient, RecordSet};
+use crate::validation::{ConfigErrors, Validator};
+use crate::{EtherlinkError, idn};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, info, warn};
+
+/// Largest UDP response without EDNS; longer ones are truncated so clients retry over TCP
+pub const MAX_UDP_RESPONSE: usize = 512;
+
+/// How long an idle TCP connection is kept open between queries
+const TCP_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const HEADER_LEN: usize = 12;
+/// Compression pointer to the question name, which always follows the header
+const QUESTION_NAME: [u8; 2] = [0xC0, HEADER_LEN as u8];
+
+const TYPE_A: u16 = 1;
+const TYPE_CNAME: u16 = 5;
+const TYPE_SOA: u16 = 6;
+const TYPE_MX: u16 = 15;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+const CLASS_ANY: u16 = 255;
+
+const RCODE_NOERROR: u8 = 0;
+const RCODE_FORMERR: u8 = 1;
+const RCODE_SERVFAIL: u8 = 2;
+const RCODE_NXDOMAIN: u8 = 3;
+const RCODE_NOTIMP: u8 = 4;
+const RCODE_REFUSED: u8 = 5;
+
+/// Default port, next to the mDNS port 5353 that avahi and mDNSResponder usually hold
+pub const DEFAULT_DNS_PORT: u16 = 5354;
+
+/// Settings for the DNS gateway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DnsGatewayConfig {
+    /// Address answered on, over both UDP and TCP
+    pub listen_addr: SocketAddr,
+    /// Upper bound on answer TTLs
+    pub max_ttl_seconds: u32,
+    /// TTL clients may cache "no such name" and "no such record" answers for
+    pub negative_ttl_seconds: u32,
+    /// Queries (UDP) and connections (TCP) served at once
+    pub max_concurrent_queries: usize,
+}
+
+impl Default for DnsGatewayConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: SocketAddr::from(([127, 0, 0, 1], DEFAULT_DNS_PORT)),
+            max_ttl_seconds: 300,
+            negative_ttl_seconds: 30,
+            max_concurrent_queries: 256,
+        }
+    }
+}
+
+impl DnsGatewayConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.max_ttl_seconds > 0, "max_ttl_seconds", "must be greater than zero");
+        v.check(
+            self.negative_ttl_seconds <= self.max_ttl_seconds,
+            "negative_ttl_seconds",
+            "must not exceed max_ttl_seconds",
+        );
+        v.check(self.max_concurrent_queries > 0, "max_concurrent_queries", "must be greater than zero");
+        v.finish()
+    }
+}
+
+/// The question of a query, with its wire form for echoing back
+#[derive(Debug)]
+struct Question {
+    name: String,
+    qtype: u16,
+    qclass: u16,
+    wire: Vec<u8>,
+}
+
+/// Parse the single question following the header; compressed names are not valid here
+fn parse_question(bytes: &[u8]) -> Option<Question> {
+    let mut labels = Vec::new();
+    let mut pos = 0;
+    loop {
+        let len = usize::from(*bytes.get(pos)?);
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        if len > 63 {
+            return None;
+        }
+        labels.push(std::str::from_utf8(bytes.get(pos..pos + len)?).ok()?);
+        pos += len;
+    }
+    if pos > 255 {
+        return None;
+    }
+    let fixed = bytes.get(pos..pos + 4)?;
+    Some(Question {
+        name: labels.join("."),
+        qtype: u16::from_be_bytes([fixed[0], fixed[1]]),
+        qclass: u16::from_be_bytes([fixed[2], fixed[3]]),
+        wire: bytes[..pos + 4].to_vec(),
+    })
+}
+
+/// Encode `name` as uncompressed labels, or `None` if it is not a valid DNS name
+fn encode_name(name: &str) -> Option<Vec<u8>> {
+    let name = idn::to_ascii(name).ok()?;
+    let mut wire = Vec::with_capacity(name.len() + 2);
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        if label.len() > 63 {
+            return None;
+        }
+        wire.push(label.len() as u8);
+        wire.extend_from_slice(label.as_bytes());
+    }
+    wire.push(0);
+    (wire.len() <= 255).then_some(wire)
+}
+
+/// A response being assembled: answers first, then authority records
+#[derive(Debug)]
+struct Response {
+    id: u16,
+    recursion_desired: bool,
+    rcode: u8,
+    question: Option<Vec<u8>>,
+    answers: Vec<Vec<u8>>,
+    authority: Vec<Vec<u8>>,
+}
+
+impl Response {
+    fn new(id: u16, recursion_desired: bool) -> Self {
+        Self {
+            id,
+            recursion_desired,
+            rcode: RCODE_NOERROR,
+            question: None,
+            answers: Vec::new(),
+            authority: Vec::new(),
+        }
+    }
+
+    fn rcode(mut self, rcode: u8) -> Self {
+        self.rcode = rcode;
+        self
+    }
+
+    fn record(owner: &[u8], rtype: u16, ttl: u32, rdata: &[u8]) -> Vec<u8> {
+        let mut record = Vec::with_capacity(owner.len() + 10 + rdata.len());
+        record.extend_from_slice(owner);
+        record.extend_from_slice(&rtype.to_be_bytes());
+        record.extend_from_slice(&CLASS_IN.to_be_bytes());
+        record.extend_from_slice(&ttl.to_be_bytes());
+        record.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        record.extend_from_slice(rdata);
+        record
+    }
+
+    fn answer(&mut self, rtype: u16, ttl: u32, rdata: &[u8]) {
+        self.answers.push(Self::record(&QUESTION_NAME, rtype, ttl, rdata));
+    }
+
+    /// Synthetic SOA for `zone`, whose minimum tells resolvers how long to cache a negative answer
+    fn soa(&mut self, zone: &str, negative_ttl: u32) {
+        let (Some(owner), Some(mname), Some(rname)) = (encode_name(zone), encode_name("ns.etherlink"), encode_name("hostmaster.etherlink")) else {
+            return;
+        };
+        let mut rdata = [mname, rname].concat();
+        for value in [1u32, 3600, 600, 86400, negative_ttl] {
+            rdata.extend_from_slice(&value.to_be_bytes());
+        }
+        self.authority.push(Self::record(&owner, TYPE_SOA, negative_ttl, &rdata));
+    }
+
+    /// Wire form, dropping every record and setting TC if it would exceed `max_len`
+    fn to_bytes(&self, max_len: usize) -> Vec<u8> {
+        let question = self.question.as_deref().unwrap_or_default();
+        let records_len: usize = self.answers.iter().chain(&self.authority).map(Vec::len).sum();
+        let truncated = HEADER_LEN + question.len() + records_len > max_len;
+        let (answers, authority) = if truncated { (&[][..], &[][..]) } else { (&self.answers[..], &self.authority[..]) };
+
+        let mut flags = 0x8400 | u16::from(self.rcode); // QR and AA
+        if truncated {
+            flags |= 0x0200;
+        }
+        if self.recursion_desired {
+            flags |= 0x0100;
+        }
+        let mut wire = Vec::with_capacity(max_len.min(HEADER_LEN + question.len() + records_len));
+        for field in [
+            self.id,
+            flags,
+            u16::from(self.question.is_some()),
+            answers.len() as u16,
+            authority.len() as u16,
+            0,
+        ] {
+            wire.extend_from_slice(&field.to_be_bytes());
+        }
+        wire.extend_from_slice(question);
+        answers.iter().chain(authority).for_each(|record| wire.extend_from_slice(record));
+        wire
+    }
+}
+
+/// Answers queries by resolving through one CNS client
+#[derive(Debug)]
+struct Gateway {
+    cns: CNSClient,
+    config: DnsGatewayConfig,
+}
+
+impl Gateway {
+    /// Response to `packet`, or `None` if it is not a query worth answering
+    async fn respond(&self, packet: &[u8]) -> Option<Response> {
+        let header = packet.get(..HEADER_LEN)?;
+        if header[2] & 0x80 != 0 {
+            return None;
+        }
+        let response = Response::new(u16::from_be_bytes([header[0], header[1]]), header[2] & 0x01 != 0);
+        if (header[2] >> 3) & 0x0f != 0 {
+            return Some(response.rcode(RCODE_NOTIMP));
+        }
+        let question = match u16::from_be_bytes([header[4], header[5]]) {
+            1 => parse_question(&packet[HEADER_LEN..]),
+            _ => None,
+        };
+        let Some(question) = question else {
+            return Some(response.rcode(RCODE_FORMERR));
+        };
+
+        let mut response = Response { question: Some(question.wire.clone()), ..response };
+        let name = question.name.to_ascii_lowercase();
+        let tld = name.rsplit('.').next().unwrap_or_default();
+        if !matches!(question.qclass, CLASS_IN | CLASS_ANY) || !name.contains('.') || !self.cns.serves_tld(tld) {
+            return Some(response.rcode(RCODE_REFUSED));
+        }
+
+        let resolution = match self.cns.resolve_domain(&name).await {
+            Ok(resolution) => resolution,
+            Err(e @ (EtherlinkError::NotFound(_) | EtherlinkError::CnsResolution(_))) => {
+                debug!("DNS query for {} found nothing: {}", name, e);
+                response.soa(tld, self.config.negative_ttl_seconds);
+                return Some(response.rcode(RCODE_NXDOMAIN));
+            }
+            Err(e) => {
+                warn!("DNS query for {} failed: {}", name, e);
+                return Some(response.rcode(RCODE_SERVFAIL));
+            }
+        };
+
+        let ttl = self.cns.ttl_for(&resolution).min(u64::from(self.config.max_ttl_seconds)) as u32;
+        for (rtype, rdata) in answers(&resolution.records, question.qtype) {
+            response.answer(rtype, ttl, &rdata);
+        }
+        if response.answers.is_empty() {
+            response.soa(tld, self.config.negative_ttl_seconds.min(ttl));
+        }
+        Some(response)
+    }
+}
+
+/// Answer records of `qtype`, falling back to the CNAME for address queries
+fn answers(records: &RecordSet, qtype: u16) -> Vec<(u16, Vec<u8>)> {
+    let wants = |rtype: u16| qtype == rtype || qtype == TYPE_ANY;
+    let mut answers = Vec::new();
+    if wants(TYPE_A) {
+        answers.extend(records.a_records().into_iter().map(|ip| (TYPE_A, ip.octets().to_vec())));
+    }
+    if wants(TYPE_AAAA) {
+        answers.extend(records.aaaa_records().into_iter().map(|ip| (TYPE_AAAA, ip.octets().to_vec())));
+    }
+    if wants(TYPE_TXT) {
+        answers.extend(
+            records
+                .txt()
+                .into_iter()
+                .filter(|text| text.len() <= 255)
+                .map(|text| (TYPE_TXT, [&[text.len() as u8][..], text.as_bytes()].concat())),
+        );
+    }
+    if wants(TYPE_MX) {
+        answers.extend(records.mx().into_iter().filter_map(|(preference, host)| {
+            Some((TYPE_MX, [&preference.to_be_bytes()[..], &encode_name(host)?].concat()))
+        }));
+    }
+    if (answers.is_empty() || qtype == TYPE_ANY)
+        && let Some(target) = records.cname().and_then(encode_name)
+    {
+        answers.push((TYPE_CNAME, target));
+    }
+    answers
+}
+
+/// Answer DNS queries on `udp` and `tcp` until `shutdown` completes
+pub(crate) async fn run<S>(udp: UdpSocket, tcp: TcpListener, cns: CNSClient, config: &DnsGatewayConfig, shutdown: S)
+where
+    S: Future<Output = ()>,
+{
+    let gateway = Arc::new(Gateway { cns, config: config.clone() });
+    let workers = Arc::new(Semaphore::new(config.max_concurrent_queries));
+    let udp = Arc::new(udp);
+    if let (Ok(udp_addr), Ok(tcp_addr)) = (udp.local_addr(), tcp.local_addr()) {
+        info!("DNS gateway listening on udp://{} and tcp://{}", udp_addr, tcp_addr);
+    }
+
+    let udp_loop = async {
+        let mut buf = [0u8; 4096];
+        loop {
+            let (len, peer) = match udp.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    debug!("DNS UDP receive failed: {}", e);
+                    continue;
+                }
+            };
+            let Ok(permit) = workers.clone().try_acquire_owned() else {
+                debug!("DNS gateway busy; dropping UDP query from {}", peer);
+                continue;
+            };
+            let (gateway, udp, packet) = (gateway.clone(), udp.clone(), buf[..len].to_vec());
+            tokio::spawn(async move {
+                if let Some(response) = gateway.respond(&packet).await {
+                    let _ = udp.send_to(&response.to_bytes(MAX_UDP_RESPONSE), peer).await;
+                }
+                drop(permit);
+            });
+        }
+    };
+    let tcp_loop = async {
+        loop {
+            match tcp.accept().await {
+                Ok((stream, peer)) => match workers.clone().try_acquire_owned() {
+                    Ok(permit) => {
+                        tokio::spawn(serve_tcp(gateway.clone(), stream, permit));
+                    }
+                    Err(_) => debug!("DNS gateway busy; closing TCP connection from {}", peer),
+                },
+                Err(e) => debug!("DNS TCP accept failed: {}", e),
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = shutdown => {}
+        _ = udp_loop => {}
+        _ = tcp_loop => {}
+    }
+    info!("DNS gateway stopped");
+}
+
+/// Answer length-prefixed queries on one TCP connection until it closes or idles out
+///
+/// `_permit` holds the connection's worker slot until it ends.
+async fn serve_tcp(gateway: Arc<Gateway>, mut stream: TcpStream, _permit: OwnedSemaphorePermit) {
+    loop {
+        let Ok(Ok(len)) = tokio::time::timeout(TCP_IDLE_TIMEOUT, stream.read_u16()).await else {
+            return;
+        };
+        let mut packet = vec![0; usize::from(len)];
+        if stream.read_exact(&mut packet).await.is_err() {
+            return;
+        }
+        let Some(response) = gateway.respond(&packet).await else {
+            return;
+        };
+        let bytes = response.to_bytes(usize::from(u16::MAX));
+        if stream.write_u16(bytes.len() as u16).await.is_err() || stream.write_all(&bytes).await.is_err() {
+            return;
+        }
+    }
+}
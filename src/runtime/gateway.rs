@@ -0,0 +1,499 @@
+//! HTTP gateway: serve `.ghost` sites to ordinary browsers
+//!
+//! With the `http-gateway` feature, [`HttpGateway`] maps `Host: name.ghost` to what
+//! the domain points at. A `CONTENTHASH` record (or the legacy `ipfs_hash`) is
+//! fetched from an IPFS HTTP gateway; otherwise the request is proxied to the
+//! domain's `A`/`AAAA` address and `PORT`. HTTPS certificates come from a rustls
+//! [`ResolvesServerCert`], by default a [`StaticCertResolver`] loaded from PEM files.
+//!
+//! Domain owners choose the addresses, so the gateway refuses to proxy to loopback,
+//! private and link-local addresses unless they are allowlisted. The visitor's
+//! cookies and credentials are never forwarded, redirects are passed back to the
+//! browser rather than followed, and bodies are capped in both directions.
+
+use super::certs::CertificateConfig;
+use crate::cns::{CNSClient, DomainResolution};
+use crate::validation::{ConfigErrors, Validator};
+use crate::{EtherlinkError, Result};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode, header};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio_rustls::rustls::sign::{self, CertifiedKey};
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tracing::{debug, info, warn};
+
+/// Headers describing one connection, never forwarded in either direction
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Request headers carrying the visitor's credentials for the gateway's own origin
+const CREDENTIAL_HEADERS: &[header::HeaderName] = &[header::COOKIE, header::AUTHORIZATION];
+
+/// Default cap on request and response bodies
+pub const DEFAULT_MAX_BODY_BYTES: u64 = 16 * 1024 * 1024;
+
+fn default_max_body_bytes() -> u64 {
+    DEFAULT_MAX_BODY_BYTES
+}
+
+/// Request headers passed on to the IPFS gateway; the rest describe the `.ghost` site
+const IPFS_REQUEST_HEADERS: &[header::HeaderName] = &[
+    header::ACCEPT,
+    header::ACCEPT_ENCODING,
+    header::RANGE,
+    header::IF_NONE_MATCH,
+    header::IF_MODIFIED_SINCE,
+];
+
+/// Settings for the HTTP gateway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpGatewayConfig {
+    pub listen_addr: SocketAddr,
+    /// IPFS HTTP gateway content hashes are fetched from, e.g. `https://ipfs.io`
+    pub ipfs_gateway: String,
+    /// Timeout for one IPFS fetch or upstream request
+    pub upstream_timeout_ms: u64,
+    /// Serve HTTPS with this certificate instead of plain HTTP
+    pub tls: Option<GatewayTlsConfig>,
+    /// Serve HTTPS with certificates obtained from the GhostChain CA
+    #[serde(default)]
+    pub certificates: Option<CertificateConfig>,
+    /// Loopback, private or link-local addresses domains may still be proxied to,
+    /// e.g. an intranet host; every other such address is refused
+    #[serde(default)]
+    pub allowed_private_upstreams: Vec<IpAddr>,
+    /// Largest request or response body passed through, in bytes
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: u64,
+}
+
+impl Default for HttpGatewayConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            ipfs_gateway: "https://ipfs.io".to_string(),
+            upstream_timeout_ms: 30000,
+            tls: None,
+            certificates: None,
+            allowed_private_upstreams: Vec::new(),
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+        }
+    }
+}
+
+impl HttpGatewayConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.endpoint("ipfs_gateway", &self.ipfs_gateway, &["http", "https"], true, true);
+        v.timeout("upstream_timeout_ms", self.upstream_timeout_ms);
+        v.check(self.max_body_bytes > 0, "max_body_bytes", "must be greater than zero");
+        if let Some(tls) = &self.tls {
+            v.check(tls.cert_path.is_file(), "tls.cert_path", "must be a readable PEM file");
+            v.check(tls.key_path.is_file(), "tls.key_path", "must be a readable PEM file");
+        }
+        if let Some(certificates) = &self.certificates {
+            v.check(self.tls.is_none(), "certificates", "cannot be combined with tls");
+            v.nested("certificates", certificates.validate());
+        }
+        v.finish()
+    }
+}
+
+/// PEM certificate chain and private key for the gateway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GatewayTlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// Where a domain's site is served from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GatewayTarget {
+    /// Content on the IPFS gateway under `ipfs/<cid>` or `ipns/<name>`
+    Ipfs { path: String },
+    /// A host serving the site itself
+    Upstream { addr: SocketAddr },
+}
+
+impl GatewayTarget {
+    /// Content hash first, then the legacy IPFS hash, then the domain's address
+    ///
+    /// Content hashes the gateway cannot fetch (`bzz://`, `ar://`, `gstore://`) fall through to
+    /// the address.
+    pub fn for_resolution(resolution: &DomainResolution) -> Option<Self> {
+        let content_hash = resolution.records.content_hash().and_then(|hash| {
+            [("ipfs://", "ipfs"), ("ipns://", "ipns")]
+                .into_iter()
+                .find_map(|(scheme, namespace)| Some(format!("{}/{}", namespace, hash.strip_prefix(scheme)?.trim_end_matches('/'))))
+        });
+        let legacy = resolution.ipfs_hash.as_deref().filter(|cid| !cid.is_empty()).map(|cid| format!("ipfs/{}", cid));
+        if let Some(path) = content_hash.or(legacy) {
+            return Some(GatewayTarget::Ipfs { path });
+        }
+
+        let records = &resolution.records;
+        let ip = records
+            .a_records()
+            .first()
+            .map(|ip| IpAddr::V4(*ip))
+            .or_else(|| records.aaaa_records().first().map(|ip| IpAddr::V6(*ip)))?;
+        Some(GatewayTarget::Upstream { addr: SocketAddr::new(ip, records.port().unwrap_or(80)) })
+    }
+}
+
+/// Serves one certificate for every name
+pub struct StaticCertResolver {
+    key: Arc<CertifiedKey>,
+}
+
+impl StaticCertResolver {
+    /// Load a PEM certificate chain and its PKCS#8, RSA or SEC1 private key
+    pub fn from_pem_files(cert_path: &Path, key_path: &Path) -> Result<Self> {
+        let read = |path: &Path| {
+            std::fs::read(path).map_err(|e| EtherlinkError::Configuration(format!("Failed to read {}: {}", path.display(), e)))
+        };
+        let key = certified_key_from_pem(&read(cert_path)?, &read(key_path)?).map_err(|e| {
+            EtherlinkError::Configuration(format!("Invalid certificate {} or key {}: {}", cert_path.display(), key_path.display(), e))
+        })?;
+        Ok(Self { key: Arc::new(key) })
+    }
+}
+
+/// Build a signing certificate from a PEM chain and a PEM private key
+pub(crate) fn certified_key_from_pem(cert_pem: &[u8], key_pem: &[u8]) -> std::result::Result<CertifiedKey, String> {
+    let parse = |pem: &[u8]| rustls_pemfile::read_all(&mut &pem[..]).map_err(|e| format!("invalid PEM: {}", e));
+    let certs: Vec<Certificate> = parse(cert_pem)?
+        .into_iter()
+        .filter_map(|item| match item {
+            rustls_pemfile::Item::X509Certificate(der) => Some(Certificate(der)),
+            _ => None,
+        })
+        .collect();
+    if certs.is_empty() {
+        return Err("no certificate found".to_string());
+    }
+    let key = parse(key_pem)?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(der) | rustls_pemfile::Item::RSAKey(der) | rustls_pemfile::Item::ECKey(der) => Some(PrivateKey(der)),
+            _ => None,
+        })
+        .ok_or_else(|| "no private key found".to_string())?;
+    let key = sign::any_supported_type(&key).map_err(|e| format!("unsupported private key: {}", e))?;
+    Ok(CertifiedKey::new(certs, key))
+}
+
+impl ResolvesServerCert for StaticCertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.key.clone())
+    }
+}
+
+impl fmt::Debug for StaticCertResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticCertResolver").field("chain_len", &self.key.cert.len()).finish()
+    }
+}
+
+/// Reverse proxy resolving the requested host through CNS
+#[derive(Clone)]
+pub struct HttpGateway {
+    cns: CNSClient,
+    http_client: reqwest::Client,
+    config: HttpGatewayConfig,
+    cert_resolver: Option<Arc<dyn ResolvesServerCert>>,
+}
+
+impl fmt::Debug for HttpGateway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpGateway")
+            .field("config", &self.config)
+            .field("tls", &self.cert_resolver.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl HttpGateway {
+    /// Create a gateway, loading the configured certificate if TLS is set
+    pub fn new(cns: CNSClient, config: HttpGatewayConfig) -> Result<Self> {
+        config.validate()?;
+        // A redirect is the site's answer to the browser, not an instruction to the gateway
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.upstream_timeout_ms))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        let cert_resolver = match &config.tls {
+            Some(tls) => Some(Arc::new(StaticCertResolver::from_pem_files(&tls.cert_path, &tls.key_path)?) as Arc<dyn ResolvesServerCert>),
+            None => None,
+        };
+        Ok(Self { cns, http_client, config, cert_resolver })
+    }
+
+    /// Serve HTTPS with certificates from `resolver`, e.g. one per domain
+    pub fn with_cert_resolver(mut self, resolver: Arc<dyn ResolvesServerCert>) -> Self {
+        self.cert_resolver = Some(resolver);
+        self
+    }
+
+    /// Accept connections on `listener` until `shutdown` completes
+    pub async fn serve<S>(&self, listener: TcpListener, shutdown: S) -> Result<()>
+    where
+        S: Future<Output = ()>,
+    {
+        let acceptor = self.cert_resolver.clone().map(|resolver| {
+            let mut tls = ServerConfig::builder()
+                .with_safe_defaults()
+                .with_no_client_auth()
+                .with_cert_resolver(resolver);
+            tls.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+            TlsAcceptor::from(Arc::new(tls))
+        });
+        let scheme = if acceptor.is_some() { "https" } else { "http" };
+        let local_addr = listener.local_addr().map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        info!("HTTP gateway listening on {}://{}", scheme, local_addr);
+
+        let accept_loop = async {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        debug!("HTTP gateway accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let (gateway, acceptor) = (self.clone(), acceptor.clone());
+                tokio::spawn(async move {
+                    let service = service_fn(move |request| {
+                        let gateway = gateway.clone();
+                        async move { Ok::<_, Infallible>(gateway.handle(request, peer, scheme).await) }
+                    });
+                    let served = match acceptor {
+                        Some(acceptor) => match acceptor.accept(stream).await {
+                            Ok(stream) => Http::new().serve_connection(stream, service).await,
+                            Err(e) => {
+                                debug!("TLS handshake with {} failed: {}", peer, e);
+                                return;
+                            }
+                        },
+                        None => Http::new().serve_connection(stream, service).await,
+                    };
+                    if let Err(e) = served {
+                        debug!("HTTP gateway connection from {} failed: {}", peer, e);
+                    }
+                });
+            }
+        };
+
+        tokio::select! {
+            _ = shutdown => {}
+            _ = accept_loop => {}
+        }
+        info!("HTTP gateway stopped");
+        Ok(())
+    }
+
+    async fn handle(&self, request: Request<Body>, peer: SocketAddr, scheme: &str) -> Response<Body> {
+        let Some(host) = request_host(&request) else {
+            return error_response(StatusCode::BAD_REQUEST, "Missing Host header");
+        };
+        let tld = host.rsplit('.').next().unwrap_or_default();
+        if !host.contains('.') || !self.cns.serves_tld(tld) {
+            return error_response(StatusCode::NOT_FOUND, &format!("{} is not a CNS name", host));
+        }
+
+        let resolution = match self.cns.resolve_domain(&host).await {
+            Ok(resolution) => resolution,
+            Err(e @ (EtherlinkError::NotFound(_) | EtherlinkError::CnsResolution(_))) => {
+                return error_response(StatusCode::NOT_FOUND, &format!("{} does not resolve: {}", host, e));
+            }
+            Err(e) => return error_response(StatusCode::BAD_GATEWAY, &e.to_string()),
+        };
+        let Some(target) = GatewayTarget::for_resolution(&resolution) else {
+            return error_response(StatusCode::NOT_FOUND, &format!("{} has no content hash or address", host));
+        };
+
+        if let GatewayTarget::Upstream { addr } = &target
+            && !is_public(addr.ip())
+            && !self.config.allowed_private_upstreams.contains(&addr.ip())
+        {
+            warn!("HTTP gateway refused to proxy {} to non-public address {}", host, addr);
+            return error_response(StatusCode::FORBIDDEN, &format!("{} points at a non-public address", host));
+        }
+
+        debug!("HTTP gateway routing {} {}{} to {:?}", request.method(), host, request.uri().path(), target);
+        let result = match &target {
+            GatewayTarget::Ipfs { path } => self.fetch_ipfs(path, request).await,
+            GatewayTarget::Upstream { addr } => self.proxy(*addr, &host, request, peer, scheme).await,
+        };
+        result.unwrap_or_else(|e| {
+            warn!("HTTP gateway request for {} failed: {}", host, e);
+            error_response(StatusCode::BAD_GATEWAY, &e.to_string())
+        })
+    }
+
+    async fn fetch_ipfs(&self, path: &str, request: Request<Body>) -> Result<Response<Body>> {
+        if *request.method() != Method::GET && *request.method() != Method::HEAD {
+            return Ok(error_response(StatusCode::METHOD_NOT_ALLOWED, "Content-addressed sites are read-only"));
+        }
+        let url = format!("{}/{}{}", self.config.ipfs_gateway.trim_end_matches('/'), path, path_and_query(&request));
+        let mut fetch = self.http_client.request(request.method().clone(), url);
+        for name in IPFS_REQUEST_HEADERS {
+            if let Some(value) = request.headers().get(name) {
+                fetch = fetch.header(name, value);
+            }
+        }
+        let response = fetch.send().await.map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        into_response(response, self.config.max_body_bytes).await
+    }
+
+    async fn proxy(&self, addr: SocketAddr, host: &str, request: Request<Body>, peer: SocketAddr, scheme: &str) -> Result<Response<Body>> {
+        let url = format!("http://{}{}", addr, path_and_query(&request));
+        let method = request.method().clone();
+        let headers = request.headers().clone();
+        let Some(body) = read_capped(request.into_body(), self.config.max_body_bytes).await? else {
+            return Ok(error_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large"));
+        };
+
+        let mut forward = self.http_client.request(method, url).body(body);
+        let dropped = connection_headers(&headers);
+        for (name, value) in headers
+            .iter()
+            .filter(|(name, _)| !is_hop_by_hop(name) && !dropped.contains(*name) && !CREDENTIAL_HEADERS.contains(*name))
+        {
+            forward = forward.header(name, value);
+        }
+        forward = forward
+            .header("x-forwarded-for", peer.ip().to_string())
+            .header("x-forwarded-host", host)
+            .header("x-forwarded-proto", scheme);
+        let response = forward.send().await.map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        into_response(response, self.config.max_body_bytes).await
+    }
+}
+
+/// Whether `ip` is reachable from the internet at large
+///
+/// Loopback, private (RFC 1918 and IPv6 unique local), link-local, shared (CGNAT),
+/// unspecified, multicast and broadcast addresses are not; IPv4-mapped IPv6
+/// addresses are judged as IPv4.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            let shared = a == 100 && (64..128).contains(&b);
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_multicast() || ip.is_broadcast() || shared)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(mapped));
+            }
+            let first = ip.segments()[0];
+            let unique_local = first & 0xfe00 == 0xfc00;
+            let link_local = first & 0xffc0 == 0xfe80;
+            !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+        }
+    }
+}
+
+/// Headers a `Connection` header names, which are hop-by-hop for that message
+fn connection_headers(headers: &header::HeaderMap) -> Vec<header::HeaderName> {
+    headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| header::HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect()
+}
+
+/// Read a request body, or `None` if it is longer than `limit` bytes
+async fn read_capped(mut body: Body, limit: u64) -> Result<Option<Vec<u8>>> {
+    use hyper::body::HttpBody;
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| EtherlinkError::Network(format!("Failed to read request body: {}", e)))?;
+        if (bytes.len() + chunk.len()) as u64 > limit {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Some(bytes))
+}
+
+/// Requested host, lower-cased and without port or trailing dot
+fn request_host(request: &Request<Body>) -> Option<String> {
+    let host = match request.uri().host() {
+        Some(host) => host.to_string(),
+        None => {
+            let host = request.headers().get(header::HOST)?.to_str().ok()?;
+            match host.rsplit_once(':') {
+                Some((name, port)) if port.bytes().all(|b| b.is_ascii_digit()) => name.to_string(),
+                _ => host.to_string(),
+            }
+        }
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+fn path_and_query(request: &Request<Body>) -> &str {
+    request.uri().path_and_query().map_or("/", |p| p.as_str())
+}
+
+fn is_hop_by_hop(name: &header::HeaderName) -> bool {
+    HOP_BY_HOP.contains(&name.as_str())
+}
+
+/// Pass an upstream response back, failing if its body is longer than `limit` bytes
+async fn into_response(mut response: reqwest::Response, limit: u64) -> Result<Response<Body>> {
+    let too_large = || EtherlinkError::Network(format!("Upstream response is larger than {} bytes", limit));
+    if response.content_length().is_some_and(|length| length > limit) {
+        return Err(too_large());
+    }
+    let mut builder = Response::builder().status(response.status());
+    let dropped = connection_headers(response.headers());
+    for (name, value) in response.headers().iter().filter(|(name, _)| !is_hop_by_hop(name) && !dropped.contains(*name)) {
+        builder = builder.header(name, value);
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| EtherlinkError::Network(e.to_string()))? {
+        if (body.len() + chunk.len()) as u64 > limit {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    builder
+        .body(Body::from(body))
+        .map_err(|e| EtherlinkError::Network(format!("Invalid upstream response: {}", e)))
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(format!("{}\n", message)));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, header::HeaderValue::from_static("text/plain; charset=utf-8"));
+    response
+}
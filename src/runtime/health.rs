@@ -0,0 +1,107 @@
+//! Aggregated health reporting across all GhostChain services
+
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Health state of a single service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceState {
+    Healthy,
+    /// The service answered but reported itself as not fully healthy
+    Degraded,
+    Unhealthy,
+}
+
+/// Health check outcome for one service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceHealth {
+    pub service: String,
+    pub state: ServiceState,
+    pub latency_ms: u64,
+    pub version: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Aggregate health of all services, as served by `/readyz` and `etherlink status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub services: Vec<ServiceHealth>,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl HealthReport {
+    /// Worst state across all services
+    pub fn overall(&self) -> ServiceState {
+        if self.services.iter().any(|s| s.state == ServiceState::Unhealthy) {
+            ServiceState::Unhealthy
+        } else if self.services.iter().any(|s| s.state == ServiceState::Degraded) {
+            ServiceState::Degraded
+        } else {
+            ServiceState::Healthy
+        }
+    }
+
+    /// Whether every service is healthy
+    pub fn is_ready(&self) -> bool {
+        self.overall() == ServiceState::Healthy
+    }
+
+    /// Look up one service
+    pub fn service(&self, name: &str) -> Option<&ServiceHealth> {
+        self.services.iter().find(|s| s.service == name)
+    }
+}
+
+/// Run one health check with a timeout and classify its JSON response
+pub(crate) async fn check_service<F>(service: &str, timeout: Duration, check: F) -> ServiceHealth
+where
+    F: Future<Output = Result<serde_json::Value>>,
+{
+    let start = Instant::now();
+    let result = tokio::time::timeout(timeout, check).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(Ok(body)) => {
+            let version = body
+                .get("version")
+                .or_else(|| body.get("data").and_then(|d| d.get("version")))
+                .and_then(|v| v.as_str())
+                .map(str::to_string);
+            let reported = body
+                .get("status")
+                .or_else(|| body.get("data").and_then(|d| d.get("status")))
+                .and_then(|v| v.as_str());
+
+            let state = match reported {
+                None | Some("ok" | "healthy" | "up" | "serving") => ServiceState::Healthy,
+                Some(_) => ServiceState::Degraded,
+            };
+
+            ServiceHealth {
+                service: service.to_string(),
+                state,
+                latency_ms,
+                version,
+                error: reported.filter(|_| state == ServiceState::Degraded).map(|s| format!("reported status: {}", s)),
+            }
+        }
+        Ok(Err(e)) => ServiceHealth {
+            service: service.to_string(),
+            state: ServiceState::Unhealthy,
+            latency_ms,
+            version: None,
+            error: Some(e.to_string()),
+        },
+        Err(_) => ServiceHealth {
+            service: service.to_string(),
+            state: ServiceState::Unhealthy,
+            latency_ms,
+            version: None,
+            error: Some(format!("health check timed out after {}ms", timeout.as_millis())),
+        },
+    }
+}
@@ -0,0 +1,1091 @@
+//! Runtime facade tying the GhostChain clients to their background tasks
+
+pub mod bridge;
+#[cfg(feature = "http-gateway")]
+pub mod certs;
+pub mod degraded;
+#[cfg(feature = "dns-gateway")]
+pub mod dns;
+#[cfg(feature = "http-gateway")]
+pub mod gateway;
+pub mod health;
+pub mod preflight;
+pub mod reload;
+pub mod resolver;
+pub mod search;
+pub mod server;
+pub mod supervisor;
+
+pub use bridge::{BridgeBackoff, supervise_bridge};
+#[cfg(feature = "http-gateway")]
+pub use certs::{CertManager, CertStore, CertificateAuthorityClient, CertificateConfig, ManagedCertResolver};
+pub use degraded::{DegradationConfig, DegradedCallPolicy, ServiceAvailability};
+#[cfg(feature = "dns-gateway")]
+pub use dns::DnsGatewayConfig;
+#[cfg(feature = "http-gateway")]
+pub use gateway::{GatewayTlsConfig, HttpGateway, HttpGatewayConfig};
+pub use health::{HealthReport, ServiceHealth, ServiceState};
+pub use preflight::{CheckCategory, CheckStatus, PreflightCheck, PreflightReport};
+pub use reload::{ConfigWatcher, DaemonConfig, LogFilterHandle, ReloadEvent, ReloadReport};
+pub use resolver::EndpointResolver;
+pub use search::{SearchQuery, SearchResult};
+pub use server::ServerConfig;
+pub use supervisor::{RestartPolicy, TaskInfo, TaskSupervisor};
+
+use crate::auth::GuardianAuthManager;
+use crate::clock::{self, SharedClock};
+use crate::cns::{CNSClient, CNSConfig};
+use crate::diagnostics::{self, DiagnosticBundle, DiagnosticCapture};
+use crate::events::{EtherlinkEvent, EventBus};
+use crate::ghostplane::{GhostPlaneClient, GhostPlaneClientBuilder, GhostPlaneConfig};
+use crate::lifecycle::OperationTracker;
+use crate::timesync::SkewEstimator;
+use crate::transport::{BandwidthAccounting, ChannelConfig, ChannelManager, HostOverrides};
+use crate::validation::ConfigIssue;
+use crate::{EtherlinkClient, EtherlinkConfig, EtherlinkError, Result, ServiceClient, ServiceClients};
+use crate::clients::build_http_client_with_overrides;
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, watch};
+use tonic::transport::Channel;
+use tracing::{info, warn};
+
+/// Capacity of the reload event channel
+const RELOAD_EVENT_CAPACITY: usize = 16;
+
+/// High-level Etherlink runtime owning all service clients and background tasks
+#[derive(Debug)]
+pub struct Etherlink {
+    /// Settings currently in effect, replaced by [`Etherlink::reload`]
+    settings: std::sync::RwLock<DaemonConfig>,
+    channels: ChannelManager,
+    /// Shared with the optional-service probe task
+    services: Arc<std::sync::RwLock<Arc<ServiceClients>>>,
+    cns: CNSClient,
+    resolver: EndpointResolver,
+    ghostplane: GhostPlaneClient,
+    supervisor: TaskSupervisor,
+    accounting: BandwidthAccounting,
+    log_filter: Option<LogFilterHandle>,
+    /// Held for the whole of [`Etherlink::reload`] so reloads don't interleave
+    reload_lock: tokio::sync::Mutex<()>,
+    reload_events: broadcast::Sender<ReloadEvent>,
+    events: EventBus,
+    availability: ServiceAvailability,
+    /// Interval of the optional-service probe task, updated on reload
+    probe_interval: watch::Sender<Duration>,
+    operations: OperationTracker,
+    skew: SkewEstimator,
+    /// Local time before skew correction
+    local_clock: SharedClock,
+    #[cfg(feature = "sqlite-index")]
+    index: Option<crate::index::ChainIndex>,
+}
+
+impl Etherlink {
+    /// Create a new runtime from the given configuration
+    ///
+    /// Endpoints are used as given; see [`Etherlink::new_resolved`] for `.ghost` endpoints.
+    /// Fails with [`EtherlinkError::InvalidConfig`] listing every invalid setting.
+    pub fn new(config: EtherlinkConfig) -> Result<Self> {
+        config.validate()?;
+        Self::build(DaemonConfig { etherlink: config, ..DaemonConfig::default() }, HostOverrides::new())
+    }
+
+    /// Create a runtime from a daemon configuration file's contents
+    pub fn from_daemon_config(settings: DaemonConfig) -> Result<Self> {
+        settings.validate()?;
+        Self::build(settings, HostOverrides::new())
+    }
+
+    /// Build the runtime, dialing CNS-resolved hosts at the addresses in `overrides`
+    fn build(settings: DaemonConfig, overrides: HostOverrides) -> Result<Self> {
+        let config = &settings.etherlink;
+        let events = EventBus::new();
+        let channels = ChannelManager::new(ChannelConfig::from(config))
+            .with_events(events.clone())
+            .with_host_overrides(overrides.clone());
+        let http_client = Arc::new(build_http_client_with_overrides(config, &overrides)?);
+        let services = ServiceClients::with_channel_manager(config, http_client.clone(), channels.clone());
+        let local_clock = clock::system();
+        let skew = SkewEstimator::new(Duration::from_secs(settings.clock_skew_warn_seconds)).with_clock(local_clock.clone());
+        skew.watch(&config.ghostd_endpoint);
+        let cns = CNSClient::new(CNSConfig {
+            cache_ttl_seconds: settings.cns_cache_ttl_seconds,
+            ..Self::cns_config(config)
+        })
+        .with_channels(channels.clone())
+        .with_clock(skew.clock(local_clock.clone()))
+        .with_http_client(http_client)
+        .with_events(events.clone());
+
+        let mut ghostplane_config = GhostPlaneConfig {
+            chain_id: settings.chain_id,
+            confirmations: config.confirmations.clone(),
+            resubmit: config.resubmit.clone(),
+            ..GhostPlaneConfig::default()
+        };
+        if let Some(endpoint) = &config.ghostplane_endpoint {
+            ghostplane_config.endpoint = endpoint.clone();
+        }
+
+        let operations = match &settings.operations_dir {
+            Some(dir) => OperationTracker::open(dir)?,
+            None => OperationTracker::new(),
+        }
+        .with_events(events.clone());
+
+        let accounting = BandwidthAccounting::new();
+        route_services(&accounting, config);
+        for (service, quota) in &settings.quotas {
+            accounting.set_quota(service.clone(), quota.clone());
+        }
+        for (service, limit) in &settings.rate_limits {
+            accounting.set_rate_limit(service.clone(), Some(limit.clone()));
+        }
+
+        #[cfg(feature = "sqlite-index")]
+        let index = settings
+            .index
+            .clone()
+            .map(|index| crate::index::ChainIndex::open(index).map(|index| index.with_events(events.clone())))
+            .transpose()?;
+
+        Ok(Self {
+            channels,
+            services: Arc::new(std::sync::RwLock::new(Arc::new(services))),
+            resolver: EndpointResolver::new(cns.clone()).with_overrides(overrides).with_proxy(config.proxy.clone()),
+            cns,
+            ghostplane: GhostPlaneClientBuilder::from_config(ghostplane_config).events(events.clone()).build(),
+            supervisor: TaskSupervisor::new(),
+            accounting,
+            log_filter: None,
+            reload_lock: tokio::sync::Mutex::new(()),
+            reload_events: broadcast::channel(RELOAD_EVENT_CAPACITY).0,
+            events,
+            availability: ServiceAvailability::new(),
+            probe_interval: watch::channel(settings.degradation.probe_interval()).0,
+            operations,
+            skew,
+            local_clock,
+            #[cfg(feature = "sqlite-index")]
+            index,
+            settings: std::sync::RwLock::new(settings),
+        })
+    }
+
+    /// Let [`Etherlink::reload`] change the global log filter
+    pub fn with_log_filter(mut self, handle: LogFilterHandle) -> Self {
+        self.log_filter = Some(handle);
+        self
+    }
+
+    /// Create a runtime after resolving CNS-named endpoints (e.g. `http://ghostd.ghost:8545`)
+    ///
+    /// The CNS endpoint itself must be a regular address, since it is needed to resolve the others.
+    pub async fn new_resolved(config: EtherlinkConfig) -> Result<Self> {
+        Self::resolve_and_build(DaemonConfig { etherlink: config, ..DaemonConfig::default() }).await
+    }
+
+    /// Like [`Etherlink::new_resolved`], for a daemon configuration
+    ///
+    /// If CNS cannot resolve the GhostPlane endpoint and `ghostplane` is optional, the
+    /// runtime starts with GhostPlane marked down instead of failing.
+    pub async fn from_daemon_config_resolved(settings: DaemonConfig) -> Result<Self> {
+        Self::resolve_and_build(settings).await
+    }
+
+    async fn resolve_and_build(mut settings: DaemonConfig) -> Result<Self> {
+        let config = &mut settings.etherlink;
+        let overrides = HostOverrides::new();
+        let resolver = EndpointResolver::new(CNSClient::new(Self::cns_config(config)))
+            .with_overrides(overrides.clone())
+            .with_proxy(config.proxy.clone());
+
+        if let Some(cns_endpoint) = &config.cns_endpoint
+            && resolver.cns_domain(cns_endpoint).is_some()
+        {
+            return Err(EtherlinkError::Configuration(format!(
+                "CNS endpoint {} cannot itself be a CNS domain",
+                cns_endpoint
+            )));
+        }
+
+        config.ghostd_endpoint = resolver.resolve_rest(&config.ghostd_endpoint).await?;
+        let mut ghostplane_down = None;
+        if let Some(endpoint) = &config.ghostplane_endpoint {
+            match resolver.resolve(endpoint).await {
+                Ok(resolved) => config.ghostplane_endpoint = Some(resolved),
+                Err(e) if settings.degradation.is_optional("ghostplane") => ghostplane_down = Some(e.to_string()),
+                Err(e) => return Err(e),
+            }
+        }
+
+        settings.validate()?;
+        let runtime = Self::build(settings, overrides)?;
+        if let Some(reason) = ghostplane_down {
+            runtime.availability.mark_down("ghostplane", &reason);
+        }
+        Ok(runtime)
+    }
+
+    fn cns_config(config: &EtherlinkConfig) -> CNSConfig {
+        let mut cns_config = CNSConfig { proxy: config.proxy.clone(), ..CNSConfig::default() };
+        if let Some(endpoint) = &config.cns_endpoint {
+            cns_config.endpoint = endpoint.clone();
+        }
+        cns_config
+    }
+
+    /// Create a runtime with default configuration
+    pub fn with_defaults() -> Result<Self> {
+        Self::new(EtherlinkConfig::default())
+    }
+
+    /// Start the runtime's background tasks
+    pub async fn start(&self) -> Result<()> {
+        info!("Starting Etherlink runtime");
+
+        let versions = self.services().negotiate_versions().await;
+        info!("Negotiated API versions with {} services", versions.len());
+
+        let cns = self.cns.clone();
+        self.supervisor
+            .spawn("cns-cache-cleanup", RestartPolicy::default(), move |token| {
+                let cns = cns.clone();
+                async move { cns.run_cache_cleanup(token).await }
+            })
+            .await?;
+
+        let ghostplane = self.ghostplane.clone();
+        let link = ghostplane.bridge_link();
+        self.supervisor
+            .spawn("ghostplane-bridge", RestartPolicy::default(), move |token| {
+                let ghostplane = ghostplane.clone();
+                supervise_bridge(link.clone(), BridgeBackoff::default(), token, move || {
+                    let ghostplane = ghostplane.clone();
+                    async move { ghostplane.recover_bridge().await }
+                })
+            })
+            .await?;
+
+        // Always running, so services made optional by a reload are probed too; with
+        // nothing marked down a tick does no work
+        let (services, ghostplane, availability) = (self.services.clone(), self.ghostplane.clone(), self.availability.clone());
+        let probe_interval = self.probe_interval.subscribe();
+        self.supervisor
+            .spawn("optional-service-probe", RestartPolicy::default(), move |token| {
+                let (services, ghostplane, availability) = (services.clone(), ghostplane.clone(), availability.clone());
+                let mut probe_interval = probe_interval.clone();
+                async move {
+                    loop {
+                        let period = *probe_interval.borrow_and_update();
+                        tokio::select! {
+                            _ = token.cancelled() => break,
+                            // A reloaded interval takes effect from the next tick
+                            changed = probe_interval.changed() => if changed.is_err() {
+                                break;
+                            },
+                            _ = tokio::time::sleep(period) => {
+                                let current = services.read().unwrap().clone();
+                                for service in availability.unavailable() {
+                                    if probe(&service, &current, &ghostplane).await.is_ok() {
+                                        availability.mark_up(&service);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            })
+            .await?;
+
+        #[cfg(feature = "sqlite-index")]
+        if let Some(index) = self.index.clone() {
+            let services = self.services.clone();
+            self.supervisor
+                .spawn("chain-index", RestartPolicy::default(), move |token| {
+                    let (index, services) = (index.clone(), services.clone());
+                    async move {
+                        let mut interval = tokio::time::interval(index.config().poll_interval());
+                        loop {
+                            tokio::select! {
+                                _ = token.cancelled() => break,
+                                _ = interval.tick() => {
+                                    // Read the client each time so a reloaded endpoint is followed
+                                    let ghostd = services.read().unwrap().ghostd.clone();
+                                    if let Err(e) = index.sync(&ghostd).await {
+                                        warn!("Chain index sync failed: {}", e);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stop all background tasks and release bridge resources
+    ///
+    /// Tasks still running once `timeout` elapses are aborted and reported as an error.
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        info!("Shutting down Etherlink runtime");
+
+        let result = self.supervisor.shutdown(timeout).await;
+
+        if let Err(e) = self.ghostplane.shutdown().await {
+            warn!("GhostPlane shutdown failed: {}", e);
+        }
+
+        self.channels.clear().await;
+
+        result
+    }
+
+    /// Health-check every service concurrently and aggregate the results
+    ///
+    /// Each check is bounded by the configured request timeout, so one hung
+    /// service cannot stall the whole report. An unreachable optional service is
+    /// reported as degraded. The report only observes; see
+    /// [`Etherlink::check_availability`] to act on it.
+    pub async fn health_report(&self) -> HealthReport {
+        let timeout = Duration::from_millis(self.config().timeout_ms);
+        let services = &*self.services();
+
+        let ghostplane = async {
+            let state = self.ghostplane.query_state("health").await?;
+            Ok(serde_json::from_str(&state).unwrap_or(serde_json::Value::Null))
+        };
+
+        let (ghostd, walletd, gid, cns, gsig, gledger, ghostplane) = tokio::join!(
+            health::check_service("ghostd", timeout, services.ghostd.health_check()),
+            health::check_service("walletd", timeout, services.walletd.health_check()),
+            health::check_service("gid", timeout, services.gid.health_check()),
+            health::check_service("cns", timeout, services.cns.health_check()),
+            health::check_service("gsig", timeout, services.gsig.health_check()),
+            health::check_service("gledger", timeout, services.gledger.health_check()),
+            health::check_service("ghostplane", timeout, ghostplane),
+        );
+
+        let degradation = self.settings().degradation;
+        let mut services = vec![ghostd, walletd, gid, cns, gsig, gledger, ghostplane];
+        for health in services.iter_mut().filter(|health| degradation.is_optional(&health.service)) {
+            if health.state == ServiceState::Unhealthy {
+                health.state = ServiceState::Degraded;
+            }
+        }
+
+        HealthReport {
+            services,
+            checked_at: chrono::Utc::now(),
+        }
+    }
+
+    /// Run [`Etherlink::health_report`] and record each optional service's reachability
+    ///
+    /// Unreachable optional services are marked down, so calls to them are held back
+    /// until they answer again; reachable ones are marked up.
+    pub async fn check_availability(&self) -> HealthReport {
+        let report = self.health_report().await;
+        let degradation = self.settings().degradation;
+        for health in report.services.iter().filter(|health| degradation.is_optional(&health.service)) {
+            if health.state == ServiceState::Healthy {
+                self.availability.mark_up(&health.service);
+            } else {
+                self.availability.mark_down(&health.service, health.error.as_deref().unwrap_or("unreachable"));
+            }
+        }
+        report
+    }
+
+    /// Check configuration, connectivity, TLS, chain ID and clock skew before going live
+    ///
+    /// Never fails: every problem becomes a [`PreflightCheck`] with a suggested fix.
+    /// Auth is skipped; see [`Etherlink::preflight_with_token`].
+    pub async fn preflight(&self) -> PreflightReport {
+        self.preflight_with_token(None).await
+    }
+
+    /// Like [`Etherlink::preflight`], also checking the Guardian token the application will use
+    pub async fn preflight_with_token(&self, token: Option<&crate::auth::AuthToken>) -> PreflightReport {
+        let settings = self.settings();
+        let mut report = PreflightReport::default();
+
+        match settings.validate() {
+            Ok(()) => report.checks.push(PreflightCheck::pass(CheckCategory::Config, "configuration", "all settings are valid")),
+            Err(errors) => report.checks.extend(errors.issues.iter().map(|issue| {
+                PreflightCheck::fail(CheckCategory::Config, issue.field.clone(), issue.message.clone(), format!("Correct `{}` in the configuration", issue.field))
+            })),
+        }
+
+        let health = self.health_report().await;
+        for service in &health.services {
+            let error = service.error.clone().unwrap_or_default();
+            report.checks.push(match service.state {
+                ServiceState::Healthy => PreflightCheck::pass(CheckCategory::Connectivity, service.service.clone(), format!("reachable in {}ms", service.latency_ms)),
+                ServiceState::Degraded => PreflightCheck::warn(
+                    CheckCategory::Connectivity,
+                    service.service.clone(),
+                    error,
+                    format!("Check the logs of {}; the runtime runs without it meanwhile", service.service),
+                ),
+                ServiceState::Unhealthy => PreflightCheck::fail(
+                    CheckCategory::Connectivity,
+                    service.service.clone(),
+                    error,
+                    format!("Start {} or correct its endpoint", service.service),
+                ),
+            });
+        }
+
+        let config = &settings.etherlink;
+        let endpoints = [
+            ("ghostd", Some(&config.ghostd_endpoint)),
+            ("cns", config.cns_endpoint.as_ref()),
+            ("ghostplane", config.ghostplane_endpoint.as_ref()),
+        ];
+        let http_client = build_http_client_with_overrides(config, self.resolver.overrides());
+        let timeout = Duration::from_millis(config.timeout_ms);
+        for (service, endpoint) in endpoints {
+            let Some(endpoint) = endpoint else { continue };
+            let rejected = match &http_client {
+                Ok(http_client) if endpoint.starts_with("https://") => preflight::tls_rejection(http_client, endpoint, timeout).await,
+                _ => None,
+            };
+            report.checks.push(if let Some(error) = rejected {
+                PreflightCheck::fail(CheckCategory::Tls, service, error, format!("Renew the certificate of {} or trust its CA", endpoint))
+            } else if config.enable_tls && endpoint.starts_with("http://") && !is_loopback(endpoint) {
+                PreflightCheck::warn(CheckCategory::Tls, service, format!("{} is not encrypted although enable_tls is set", endpoint), "Use an https:// endpoint")
+            } else {
+                PreflightCheck::pass(CheckCategory::Tls, service, endpoint.clone())
+            });
+        }
+
+        let reachable = |service: &str| health.service(service).is_some_and(|health| health.state == ServiceState::Healthy);
+        let services = self.services();
+
+        report.checks.push(if !reachable("ghostd") {
+            PreflightCheck::skipped(CheckCategory::Chain, "chain id", "ghostd is unreachable")
+        } else {
+            match services.ghostd.get_metrics().await {
+                Ok(metrics) if metrics.chain_id == settings.chain_id => {
+                    PreflightCheck::pass(CheckCategory::Chain, "chain id", format!("ghostd serves chain {}", metrics.chain_id))
+                }
+                Ok(metrics) => PreflightCheck::fail(
+                    CheckCategory::Chain,
+                    "chain id",
+                    format!("ghostd serves chain {} but chain_id is {}", metrics.chain_id, settings.chain_id),
+                    format!("Set chain_id to {} or point ghostd_endpoint at chain {}", metrics.chain_id, settings.chain_id),
+                ),
+                Err(e) => PreflightCheck::warn(CheckCategory::Chain, "chain id", e.to_string(), "Enable the ghostd metrics endpoint"),
+            }
+        });
+
+        report.checks.push(if !reachable("gid") {
+            PreflightCheck::skipped(CheckCategory::Clock, "clock skew", "gid is unreachable")
+        } else {
+            match services.gid.get_request_signing_policy().await {
+                // Measured against the uncorrected local clock, which is what drifted
+                Ok(policy) => preflight::check_clock_skew(self.local_clock.now(), policy.server_time, policy.max_skew_seconds),
+                Err(e) => PreflightCheck::warn(CheckCategory::Clock, "clock skew", e.to_string(), "Enable the Guardian signing-policy endpoint"),
+            }
+        });
+
+        report.checks.push(match token {
+            Some(token) => preflight::check_token(token, self.clock().now(), settings.auth.refresh_threshold_seconds),
+            None => PreflightCheck::skipped(CheckCategory::Auth, "token", "no token given"),
+        });
+
+        report
+    }
+
+    /// Look up a block height, transaction hash, address or domain typed into a search box
+    ///
+    /// The input is classified with [`SearchQuery::classify`] and sent to the matching
+    /// client. Lookups the service answers with "not found" become
+    /// [`SearchResult::NotFound`]; network and permission errors are returned as is.
+    pub async fn search(&self, query: &str) -> Result<SearchResult> {
+        let Some(classified) = SearchQuery::classify(query) else {
+            return Ok(SearchResult::Unrecognized { input: query.to_string() });
+        };
+        let services = self.services();
+        let result = match &classified {
+            SearchQuery::Block(height) => services
+                .ghostd
+                .get_block(*height)
+                .await
+                .map(|block| SearchResult::Block { block: Box::new(block) }),
+            SearchQuery::Transaction(hash) => services
+                .ghostd
+                .get_transaction_receipt(hash)
+                .await
+                .map(|receipt| SearchResult::Transaction { receipt: Box::new(receipt) }),
+            SearchQuery::Address(address) => services
+                .ghostd
+                .get_balance(address)
+                .await
+                .map(|balance| SearchResult::Account { address: address.clone(), balance }),
+            SearchQuery::Domain(domain) => self
+                .cns
+                .resolve_domain(domain)
+                .await
+                .map(|resolution| SearchResult::Domain { resolution: Box::new(resolution) }),
+        };
+        match result {
+            Err(e) if search::is_not_found(&e) => Ok(SearchResult::NotFound { query: classified }),
+            result => result,
+        }
+    }
+
+    /// Record every request to this runtime's services for `duration` and return a
+    /// redacted bundle for a bug report
+    ///
+    /// With a filter handle from [`Etherlink::with_log_filter`], Etherlink's own logs are
+    /// raised to debug for the duration and put back to the configured filter afterwards.
+    /// The bundle ends with a health report, whose requests are captured too.
+    pub async fn capture_diagnostics(&self, duration: Duration) -> Result<DiagnosticBundle> {
+        let capture = DiagnosticCapture::start(diagnostics::DEFAULT_CAPTURE_CAPACITY);
+        let config = self.config();
+        let endpoints = [
+            Some(&config.ghostd_endpoint),
+            config.cns_endpoint.as_ref(),
+            config.grpc_endpoint.as_ref(),
+            config.pinning.as_ref().map(|pinning| &pinning.endpoint),
+        ];
+        for endpoint in endpoints.into_iter().flatten() {
+            capture.watch(endpoint);
+        }
+        let started_at_ms = diagnostics::now_millis();
+        let log_filter = match &self.log_filter {
+            Some(handle) => {
+                let raised = format!("{},etherlink=debug", self.settings().log_filter);
+                handle.set(&raised)?;
+                Some(raised)
+            }
+            None => None,
+        };
+
+        tokio::time::sleep(duration).await;
+        let health = self.health_report().await;
+
+        if let Some(handle) = &self.log_filter
+            && let Err(e) = handle.set(&self.settings().log_filter)
+        {
+            warn!("Failed to restore log filter after diagnostic capture: {}", e);
+        }
+
+        let dropped_requests = capture.dropped();
+        let requests = capture.finish();
+        let mut settings = serde_json::to_value(self.settings())?;
+        diagnostics::redact_json(&mut settings);
+        Ok(DiagnosticBundle {
+            etherlink_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at_ms,
+            duration_ms: diagnostics::now_millis().saturating_sub(started_at_ms),
+            log_filter,
+            settings,
+            health: serde_json::to_value(&health)?,
+            latency: DiagnosticBundle::summarize(&requests),
+            requests,
+            dropped_requests,
+        })
+    }
+
+    /// Run a call to `service`, honouring the degradation policy if it is optional
+    ///
+    /// While an optional service is down the call is rejected with
+    /// [`EtherlinkError::ServiceUnavailable`] or held until it recovers, per
+    /// [`DegradedCallPolicy`]. A retriable failure marks the service down.
+    pub async fn call_optional<T, F>(&self, service: &str, call: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let degradation = self.settings().degradation;
+        if !degradation.is_optional(service) {
+            return call.await;
+        }
+        self.availability.call(service, degradation.policy, call).await
+    }
+
+    /// Domain marketplace publishing completed sales to the runtime's event bus
+    pub fn marketplace(&self) -> crate::clients::DomainMarketplace {
+        self.services().marketplace().with_events(self.events.clone())
+    }
+
+    /// Get the last known reachability of optional services
+    pub fn availability(&self) -> &ServiceAvailability {
+        &self.availability
+    }
+
+    /// Run the gRPC health and reflection server until `shutdown` completes or the runtime shuts down
+    pub async fn serve<F>(&self, config: &ServerConfig, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        let listener = TcpListener::bind(config.listen_addr)
+            .await
+            .map_err(|e| EtherlinkError::Network(format!("Failed to bind {}: {}", config.listen_addr, e)))?;
+        self.serve_with_listener(listener, config, shutdown).await
+    }
+
+    /// Like [`Etherlink::serve`], on an already bound listener (e.g. an ephemeral port)
+    pub async fn serve_with_listener<F>(&self, listener: TcpListener, config: &ServerConfig, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        config.validate()?;
+        let token = self.supervisor.token();
+        let shutdown = async move {
+            tokio::select! {
+                _ = shutdown => {}
+                _ = token.cancelled() => {}
+            }
+        };
+
+        let probes = match config.probe_addr {
+            Some(addr) => Some(
+                TcpListener::bind(addr)
+                    .await
+                    .map_err(|e| EtherlinkError::Network(format!("Failed to bind {}: {}", addr, e)))?,
+            ),
+            None => None,
+        };
+        server::run(listener, probes, config, || self.health_report(), shutdown).await
+    }
+
+    /// Answer DNS queries for CNS names until `shutdown` completes or the runtime shuts down
+    #[cfg(feature = "dns-gateway")]
+    pub async fn serve_dns<F>(&self, config: &DnsGatewayConfig, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        let bind_error = |e: std::io::Error| {
+            // 5353 in particular is usually held by an mDNS responder
+            let hint = if e.kind() == std::io::ErrorKind::AddrInUse {
+                " (another resolver or an mDNS responder such as avahi holds the port; set another `listen_addr`)"
+            } else {
+                ""
+            };
+            EtherlinkError::Network(format!("Failed to bind {}: {}{}", config.listen_addr, e, hint))
+        };
+        let udp = tokio::net::UdpSocket::bind(config.listen_addr).await.map_err(bind_error)?;
+        let tcp = TcpListener::bind(config.listen_addr).await.map_err(bind_error)?;
+        self.serve_dns_with_sockets(udp, tcp, config, shutdown).await
+    }
+
+    /// Like [`Etherlink::serve_dns`], on already bound sockets (e.g. ephemeral ports)
+    #[cfg(feature = "dns-gateway")]
+    pub async fn serve_dns_with_sockets<F>(
+        &self,
+        udp: tokio::net::UdpSocket,
+        tcp: TcpListener,
+        config: &DnsGatewayConfig,
+        shutdown: F,
+    ) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        config.validate()?;
+        let token = self.supervisor.token();
+        let shutdown = async move {
+            tokio::select! {
+                _ = shutdown => {}
+                _ = token.cancelled() => {}
+            }
+        };
+
+        dns::run(udp, tcp, self.cns.clone(), config, shutdown).await;
+        Ok(())
+    }
+
+    /// Serve `.ghost` sites over HTTP(S) until `shutdown` completes or the runtime shuts down
+    ///
+    /// With `certificates` configured, certificates are obtained from the GhostChain CA
+    /// and renewed in the background for as long as the gateway runs.
+    #[cfg(feature = "http-gateway")]
+    pub async fn serve_http_gateway<F>(&self, config: &HttpGatewayConfig, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        let mut gateway = HttpGateway::new(self.cns.clone(), config.clone())?;
+        let certificates = match &config.certificates {
+            Some(certificates) => {
+                let manager = CertManager::new(self.cns.clone(), certificates.clone())?;
+                gateway = gateway.with_cert_resolver(manager.resolver());
+                Some(manager)
+            }
+            None => None,
+        };
+
+        let listener = TcpListener::bind(config.listen_addr)
+            .await
+            .map_err(|e| EtherlinkError::Network(format!("Failed to bind {}: {}", config.listen_addr, e)))?;
+        let serve = self.serve_http_gateway_with_listener(listener, gateway, shutdown);
+        match certificates {
+            Some(manager) => tokio::select! {
+                result = serve => result,
+                _ = manager.run_renewals() => Ok(()),
+            },
+            None => serve.await,
+        }
+    }
+
+    /// Like [`Etherlink::serve_http_gateway`], for a prepared gateway (e.g. with its own
+    /// certificate resolver) on an already bound listener
+    #[cfg(feature = "http-gateway")]
+    pub async fn serve_http_gateway_with_listener<F>(&self, listener: TcpListener, gateway: HttpGateway, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        let token = self.supervisor.token();
+        let shutdown = async move {
+            tokio::select! {
+                _ = shutdown => {}
+                _ = token.cancelled() => {}
+            }
+        };
+
+        gateway.serve(listener, shutdown).await
+    }
+
+    /// Apply a new configuration to the running runtime
+    ///
+    /// Safe changes take effect immediately: in-flight requests finish on the old
+    /// service clients. Changes that need a restart are reported in
+    /// [`ReloadReport::rejected`] and broadcast as [`ReloadEvent::Rejected`]; the running
+    /// value is kept. An invalid configuration, or one whose clients cannot be built,
+    /// changes nothing. Concurrent reloads are applied one after the other.
+    pub async fn reload(&self, new: DaemonConfig) -> Result<ReloadReport> {
+        let _reloading = self.reload_lock.lock().await;
+        match self.apply_reload(new).await {
+            Ok(report) => Ok(report),
+            Err(e) => {
+                let _ = self.reload_events.send(ReloadEvent::Failed { error: e.to_string() });
+                Err(e)
+            }
+        }
+    }
+
+    async fn apply_reload(&self, new: DaemonConfig) -> Result<ReloadReport> {
+        new.validate()?;
+
+        let current = self.settings();
+        let mut next = new;
+        let mut report = ReloadReport::default();
+
+        if next.chain_id != current.chain_id {
+            report.rejected.push(ConfigIssue {
+                field: "chain_id".to_string(),
+                message: format!("cannot change from {} to {} without a restart", current.chain_id, next.chain_id),
+            });
+            next.chain_id = current.chain_id;
+        }
+        keep_running("etherlink.enable_tls", &current.etherlink.enable_tls, &mut next.etherlink.enable_tls, &mut report);
+        keep_running("etherlink.use_quic", &current.etherlink.use_quic, &mut next.etherlink.use_quic, &mut report);
+        keep_running("etherlink.proxy", &current.etherlink.proxy, &mut next.etherlink.proxy, &mut report);
+        // The runtime's CNS client and endpoint resolver are built once at startup
+        keep_running("etherlink.cns_endpoint", &current.etherlink.cns_endpoint, &mut next.etherlink.cns_endpoint, &mut report);
+        keep_running("etherlink.ghostplane_endpoint", &current.etherlink.ghostplane_endpoint, &mut next.etherlink.ghostplane_endpoint, &mut report);
+        keep_running("operations_dir", &current.operations_dir, &mut next.operations_dir, &mut report);
+        #[cfg(feature = "sqlite-index")]
+        keep_running("index", &current.index, &mut next.index, &mut report);
+        #[cfg(feature = "dns-gateway")]
+        keep_running("dns", &current.dns, &mut next.dns, &mut report);
+        #[cfg(feature = "http-gateway")]
+        keep_running("http_gateway", &current.http_gateway, &mut next.http_gateway, &mut report);
+        if next.log_filter != current.log_filter && self.log_filter.is_none() {
+            keep_running("log_filter", &current.log_filter, &mut next.log_filter, &mut report);
+        }
+
+        // Build everything that can fail before touching the running runtime
+        let (old, new) = (&current.etherlink, &next.etherlink);
+        let client_changes = [
+            ("etherlink.ghostd_endpoint", old.ghostd_endpoint != new.ghostd_endpoint),
+            ("etherlink.timeout_ms", old.timeout_ms != new.timeout_ms),
+            ("etherlink.retry_attempts", old.retry_attempts != new.retry_attempts),
+            ("etherlink.private_relay", old.private_relay != new.private_relay),
+            ("etherlink.simulate_before_send", old.simulate_before_send != new.simulate_before_send),
+            ("etherlink.resubmit", old.resubmit != new.resubmit),
+            ("etherlink.grpc_endpoint", old.grpc_endpoint != new.grpc_endpoint),
+            ("etherlink.service_transports", old.service_transports != new.service_transports),
+            ("etherlink.pinning", old.pinning != new.pinning),
+        ];
+        let services = if client_changes.iter().any(|(_, changed)| *changed) {
+            let http_client = Arc::new(build_http_client_with_overrides(new, self.resolver.overrides())?);
+            let services = ServiceClients::with_channel_manager(new, http_client, self.channels.clone());
+            services.negotiate_versions().await;
+            Some(services)
+        } else {
+            None
+        };
+        if next.log_filter != current.log_filter
+            && let Some(handle) = &self.log_filter
+        {
+            // The last step that can fail; everything after it is infallible
+            handle.set(&next.log_filter)?;
+            report.applied.push("log_filter".to_string());
+        }
+
+        let mut settings = self.settings.write().unwrap();
+        if let Some(services) = services {
+            if next.etherlink.ghostd_endpoint != current.etherlink.ghostd_endpoint {
+                self.skew.unwatch(&current.etherlink.ghostd_endpoint);
+                self.skew.watch(&next.etherlink.ghostd_endpoint);
+            }
+            route_services(&self.accounting, &next.etherlink);
+            *self.services.write().unwrap() = Arc::new(services);
+            report.applied.extend(client_changes.iter().filter(|(_, changed)| *changed).map(|(field, _)| field.to_string()));
+        }
+
+        if next.cns_cache_ttl_seconds != current.cns_cache_ttl_seconds {
+            self.cns.set_cache_ttl_seconds(next.cns_cache_ttl_seconds);
+            report.applied.push("cns_cache_ttl_seconds".to_string());
+        }
+
+        if next.clock_skew_warn_seconds != current.clock_skew_warn_seconds {
+            self.skew.set_warn_threshold(Duration::from_secs(next.clock_skew_warn_seconds));
+            report.applied.push("clock_skew_warn_seconds".to_string());
+        }
+
+        if next.degradation != current.degradation {
+            self.probe_interval.send_replace(next.degradation.probe_interval());
+            report.applied.push("degradation".to_string());
+        }
+
+        if next.auth != current.auth {
+            report.applied.push("auth".to_string());
+        }
+
+        let quota_services: BTreeSet<&String> = current.quotas.keys().chain(next.quotas.keys()).collect();
+        for service in quota_services {
+            let quota = next.quotas.get(service);
+            if current.quotas.get(service) != quota {
+                // A removed quota becomes an empty one, i.e. unlimited
+                self.accounting.set_quota(service.clone(), quota.cloned().unwrap_or_default());
+                report.applied.push(format!("quotas.{}", service));
+            }
+        }
+
+        let limited_services: BTreeSet<&String> = current.rate_limits.keys().chain(next.rate_limits.keys()).collect();
+        for service in limited_services {
+            let limit = next.rate_limits.get(service);
+            if current.rate_limits.get(service) != limit {
+                self.accounting.set_rate_limit(service.clone(), limit.cloned());
+                report.applied.push(format!("rate_limits.{}", service));
+            }
+        }
+
+        *settings = next;
+        drop(settings);
+
+        if !report.applied.is_empty() {
+            info!("Configuration reloaded: {}", report.applied.join(", "));
+            let _ = self.reload_events.send(ReloadEvent::Applied { fields: report.applied.clone() });
+        }
+        for issue in &report.rejected {
+            warn!("Configuration change rejected: {}", issue);
+            let _ = self.reload_events.send(ReloadEvent::Rejected { issue: issue.clone() });
+        }
+
+        Ok(report)
+    }
+
+    /// Load a configuration file and [`reload`](Etherlink::reload) it
+    pub async fn reload_from(&self, path: impl AsRef<Path>) -> Result<ReloadReport> {
+        match DaemonConfig::load(path) {
+            Ok(config) => self.reload(config).await,
+            Err(e) => {
+                let _ = self.reload_events.send(ReloadEvent::Failed { error: e.to_string() });
+                Err(e)
+            }
+        }
+    }
+
+    /// Reload every change to a watched configuration file until the watcher stops
+    ///
+    /// Invalid files are logged and skipped; the runtime keeps its current settings.
+    pub async fn watch_config(&self, mut watcher: ConfigWatcher) {
+        while let Some(loaded) = watcher.changed().await {
+            let result = match loaded {
+                Ok(config) => self.reload(config).await,
+                Err(e) => {
+                    let _ = self.reload_events.send(ReloadEvent::Failed { error: e.to_string() });
+                    Err(e)
+                }
+            };
+            if let Err(e) = result {
+                warn!("Ignoring configuration change in {}: {}", watcher.path().display(), e);
+            }
+        }
+    }
+
+    /// Subscribe to configuration reload events
+    pub fn subscribe_reloads(&self) -> broadcast::Receiver<ReloadEvent> {
+        self.reload_events.subscribe()
+    }
+
+    /// Subscribe to events from every client the runtime owns
+    pub fn subscribe_events(&self) -> broadcast::Receiver<EtherlinkEvent> {
+        self.events.subscribe()
+    }
+
+    /// Get the runtime's event bus, to hand to clients created outside it
+    /// (e.g. [`crate::auth::GuardianAuthManager::with_events`] or [`crate::watcher::Watcher::with_events`])
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Get the client configuration currently in effect
+    pub fn config(&self) -> EtherlinkConfig {
+        self.settings.read().unwrap().etherlink.clone()
+    }
+
+    /// Get all runtime settings currently in effect
+    pub fn settings(&self) -> DaemonConfig {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Get the usage ledger holding the configured soft quotas and rate limits
+    ///
+    /// REST calls to the GHOSTD, CNS and pinning endpoints are accounted as `ghostd`,
+    /// `cns` and `pinning`.
+    pub fn accounting(&self) -> &BandwidthAccounting {
+        &self.accounting
+    }
+
+    /// Get the channel manager shared by all gRPC-based clients
+    pub fn channels(&self) -> &ChannelManager {
+        &self.channels
+    }
+
+    /// Get a connected gRPC channel, resolving CNS-named endpoints first
+    ///
+    /// If dialing the resolved address fails, the name is resolved again once
+    /// in case the service moved.
+    pub async fn channel(&self, endpoint: &str) -> Result<Channel> {
+        let channels = self.channels.clone();
+        self.resolver
+            .with_endpoint(endpoint, |resolved| {
+                let channels = channels.clone();
+                async move { channels.connect(&resolved).await }
+            })
+            .await
+    }
+
+    /// Get the endpoint resolver used for CNS-named endpoints
+    pub fn resolver(&self) -> &EndpointResolver {
+        &self.resolver
+    }
+
+    /// Create a gRPC client for ghostd that shares the runtime's channels
+    pub fn grpc_client(&self) -> EtherlinkClient {
+        EtherlinkClient::with_channel_manager(self.config(), self.channels.clone())
+    }
+
+    /// Get the REST service clients
+    ///
+    /// Clients are replaced when a reload changes their endpoints, so fetch them per
+    /// operation rather than holding on to them.
+    pub fn services(&self) -> Arc<ServiceClients> {
+        self.services.read().unwrap().clone()
+    }
+
+    /// Get the CNS client
+    pub fn cns(&self) -> &CNSClient {
+        &self.cns
+    }
+
+    /// Get the tracker of submitted transactions' lifecycles, publishing to the runtime's event bus
+    pub fn operations(&self) -> &OperationTracker {
+        &self.operations
+    }
+
+    /// Get the estimate of the local clock's skew against the services
+    pub fn clock_skew(&self) -> &SkewEstimator {
+        &self.skew
+    }
+
+    /// The system clock corrected by the estimated skew, as used for CNS cache and token expiry
+    pub fn clock(&self) -> SharedClock {
+        self.skew.clock(self.local_clock.clone())
+    }
+
+    /// A Guardian auth manager for the runtime's GID client, under the current `auth` settings
+    ///
+    /// Token expiry and refreshes are judged by [`Etherlink::clock`], and refreshes are
+    /// published on the runtime's event bus.
+    pub fn auth_manager(&self) -> GuardianAuthManager {
+        let auth = self.settings.read().unwrap().auth.clone();
+        GuardianAuthManager::new(Arc::new(self.services().gid.clone()), auth)
+            .with_clock(self.clock())
+            .with_events(self.events.clone())
+    }
+
+    /// Get the GhostPlane client
+    pub fn ghostplane(&self) -> &GhostPlaneClient {
+        &self.ghostplane
+    }
+
+    /// Get the local chain index, if one is configured
+    #[cfg(feature = "sqlite-index")]
+    pub fn index(&self) -> Option<&crate::index::ChainIndex> {
+        self.index.as_ref()
+    }
+
+    /// Get the task supervisor, for spawning application tasks alongside Etherlink's own
+    pub fn supervisor(&self) -> &TaskSupervisor {
+        &self.supervisor
+    }
+}
+
+/// Whether an endpoint stays on this machine, where plain HTTP is acceptable
+fn is_loopback(endpoint: &str) -> bool {
+    let authority = endpoint.split("://").nth(1).unwrap_or(endpoint);
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => authority.split(['/', ':']).next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Check whether an optional service answers its health endpoint
+async fn probe(service: &str, services: &ServiceClients, ghostplane: &GhostPlaneClient) -> Result<()> {
+    match service {
+        "cns" => services.cns.health_check().await.map(|_| ()),
+        "gledger" => services.gledger.health_check().await.map(|_| ()),
+        "gsig" => services.gsig.health_check().await.map(|_| ()),
+        "ghostplane" => ghostplane.query_state("health").await.map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+/// Account REST calls to the configured endpoints under the service names quotas use
+fn route_services(accounting: &BandwidthAccounting, config: &EtherlinkConfig) {
+    let prefix = |endpoint: &str| format!("{}/", endpoint.trim_end_matches('/'));
+    accounting.clear_routes();
+    accounting.route(prefix(&config.ghostd_endpoint), "ghostd");
+    if let Some(endpoint) = &config.cns_endpoint {
+        accounting.route(prefix(endpoint), "cns");
+    }
+    if let Some(pinning) = &config.pinning {
+        accounting.route(prefix(&pinning.endpoint), "pinning");
+    }
+}
+
+/// Revert a restart-only setting to its running value, recording the rejection
+fn keep_running<T: PartialEq + Clone>(field: &str, running: &T, requested: &mut T, report: &mut ReloadReport) {
+    if running != requested {
+        report.rejected.push(ConfigIssue {
+            field: field.to_string(),
+            message: "requires a restart; keeping the running value".to_string(),
+        });
+        *requested = running.clone();
+    }
+}
@@ -0,0 +1,163 @@
+//! Startup checks run by [`super::Etherlink::preflight`] and `etherlink doctor`
+//!
+//! Each check lands in a [`CheckCategory`] and carries a suggested fix when it does
+//! not pass, so a misconfigured deployment can be sorted out from the report alone.
+
+use crate::auth::AuthToken;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Area a preflight check covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckCategory {
+    Config,
+    Connectivity,
+    Tls,
+    Chain,
+    Clock,
+    Auth,
+}
+
+impl fmt::Display for CheckCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckCategory::Config => "config",
+            CheckCategory::Connectivity => "connectivity",
+            CheckCategory::Tls => "tls",
+            CheckCategory::Chain => "chain",
+            CheckCategory::Clock => "clock",
+            CheckCategory::Auth => "auth",
+        })
+    }
+}
+
+/// Outcome of one check, from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    /// Not run, e.g. because a check it depends on failed
+    Skipped,
+    Warn,
+    Fail,
+}
+
+/// Result of one preflight check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreflightCheck {
+    pub category: CheckCategory,
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to change when the check does not pass
+    pub fix: Option<String>,
+}
+
+impl PreflightCheck {
+    pub fn pass(category: CheckCategory, name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { category, name: name.into(), status: CheckStatus::Pass, detail: detail.into(), fix: None }
+    }
+
+    pub fn skipped(category: CheckCategory, name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { category, name: name.into(), status: CheckStatus::Skipped, detail: detail.into(), fix: None }
+    }
+
+    pub fn warn(category: CheckCategory, name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { category, name: name.into(), status: CheckStatus::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    pub fn fail(category: CheckCategory, name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { category, name: name.into(), status: CheckStatus::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// Every check run by a preflight, in the order they ran
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    /// Worst status across all checks
+    pub fn status(&self) -> CheckStatus {
+        self.checks.iter().map(|check| check.status).max().unwrap_or(CheckStatus::Pass)
+    }
+
+    /// Whether nothing failed; warnings are allowed
+    pub fn passed(&self) -> bool {
+        self.status() != CheckStatus::Fail
+    }
+
+    pub fn category(&self, category: CheckCategory) -> impl Iterator<Item = &PreflightCheck> {
+        self.checks.iter().filter(move |check| check.category == category)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &PreflightCheck> {
+        self.checks.iter().filter(|check| check.status == CheckStatus::Fail)
+    }
+}
+
+/// Compare local and server time against the server's accepted skew
+///
+/// More than half the allowed skew is a warning, since request signatures start
+/// failing once ordinary network delay is added on top.
+pub fn check_clock_skew(local: u64, server: u64, max_skew_seconds: u64) -> PreflightCheck {
+    let skew = local.abs_diff(server);
+    let detail = format!("local clock is {}s {} the server (allowed {}s)", skew, if local >= server { "ahead of" } else { "behind" }, max_skew_seconds);
+    let fix = "Synchronize the system clock with NTP (e.g. `timedatectl set-ntp true`)";
+    if skew > max_skew_seconds {
+        PreflightCheck::fail(CheckCategory::Clock, "clock skew", detail, fix)
+    } else if skew * 2 > max_skew_seconds {
+        PreflightCheck::warn(CheckCategory::Clock, "clock skew", detail, fix)
+    } else {
+        PreflightCheck::pass(CheckCategory::Clock, "clock skew", detail)
+    }
+}
+
+/// Check an auth token's expiry and refresh headroom at `now` (unix seconds)
+pub fn check_token(token: &AuthToken, now: u64, refresh_threshold_seconds: u64) -> PreflightCheck {
+    let name = format!("token for {}", token.identity);
+    if token.is_expired_at(now) {
+        return PreflightCheck::fail(
+            CheckCategory::Auth,
+            name,
+            format!("expired {}s ago", now - token.expires_at),
+            "Authenticate again to obtain a fresh Guardian token",
+        );
+    }
+    let remaining = token.expires_at - now;
+    if token.needs_refresh_at(refresh_threshold_seconds, now) {
+        PreflightCheck::warn(
+            CheckCategory::Auth,
+            name,
+            format!("expires in {}s", remaining),
+            "Enable auto_refresh or start GuardianAuthManager::start_auto_refresh",
+        )
+    } else {
+        PreflightCheck::pass(CheckCategory::Auth, name, format!("valid for {}s", remaining))
+    }
+}
+
+/// Why the TLS handshake with `endpoint` was refused, if it was
+///
+/// rustls reports certificate and protocol failures as an `InvalidData` I/O error
+/// under the connect error; anything else (refused connections, timeouts, HTTP
+/// errors) is left to the connectivity checks.
+pub(crate) async fn tls_rejection(http_client: &reqwest::Client, endpoint: &str, timeout: Duration) -> Option<String> {
+    let error = http_client.head(endpoint).timeout(timeout).send().await.err()?;
+    if !error.is_connect() {
+        return None;
+    }
+    let mut cause = std::error::Error::source(&error);
+    while let Some(current) = cause {
+        if let Some(io) = current.downcast_ref::<std::io::Error>()
+            && io.kind() == std::io::ErrorKind::InvalidData
+        {
+            return Some(io.to_string());
+        }
+        cause = current.source();
+    }
+    None
+}
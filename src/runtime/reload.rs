@@ -0,0 +1,239 @@
+//! Daemon configuration file and runtime reloading
+//!
+//! [`ConfigWatcher`] watches the file and [`super::Etherlink::reload`] applies what
+//! can change on a live runtime: service endpoints, timeouts, soft quotas, rate limits, the CNS
+//! cache TTL, the clock skew threshold, auth refresh settings, optional services and the log filter. Settings baked into connections or the Zig bridge at
+//! startup (chain ID, TLS, QUIC, proxy, CNS and GhostPlane endpoints) are rejected with a
+//! [`ReloadEvent::Rejected`] and keep their running value until restart.
+
+use super::degraded::DegradationConfig;
+use crate::auth::AuthConfig;
+use crate::cns::CNSConfig;
+use crate::ghostplane::GhostPlaneConfig;
+use crate::timesync::DEFAULT_SKEW_WARN_THRESHOLD;
+use crate::transport::{RateLimit, ServiceQuota};
+use crate::validation::{ConfigErrors, ConfigIssue, Validator};
+use crate::{EtherlinkConfig, EtherlinkError, Result};
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Quiet period after a file change before it is read, so editors can finish writing
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Contents of the daemon configuration file (TOML, JSON or YAML, by extension)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    pub etherlink: EtherlinkConfig,
+    /// Chain the daemon serves; fixed for the lifetime of the process
+    pub chain_id: u64,
+    /// `tracing` filter directive, e.g. `etherlink=debug`
+    pub log_filter: String,
+    pub cns_cache_ttl_seconds: u64,
+    /// Soft per-service quotas, keyed by service name
+    pub quotas: BTreeMap<String, ServiceQuota>,
+    /// Per-service request rate limits, keyed by service name
+    pub rate_limits: BTreeMap<String, RateLimit>,
+    /// Services the runtime may run without
+    pub degradation: DegradationConfig,
+    /// Directory persisting tracked transaction lifecycles; kept in memory when unset
+    pub operations_dir: Option<PathBuf>,
+    /// Clock skew against the services tolerated before warning
+    pub clock_skew_warn_seconds: u64,
+    /// Guardian token lifetime and refresh settings
+    pub auth: AuthConfig,
+    /// Local SQLite index kept current from GHOSTD; disabled when unset
+    #[cfg(feature = "sqlite-index")]
+    pub index: Option<crate::index::IndexConfig>,
+    /// DNS gateway answering queries for CNS names; disabled when unset
+    #[cfg(feature = "dns-gateway")]
+    pub dns: Option<super::dns::DnsGatewayConfig>,
+    /// HTTP gateway serving `.ghost` sites; disabled when unset
+    #[cfg(feature = "http-gateway")]
+    pub http_gateway: Option<super::gateway::HttpGatewayConfig>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            etherlink: EtherlinkConfig::default(),
+            chain_id: GhostPlaneConfig::default().chain_id,
+            log_filter: "etherlink=info".to_string(),
+            cns_cache_ttl_seconds: CNSConfig::default().cache_ttl_seconds,
+            quotas: BTreeMap::new(),
+            rate_limits: BTreeMap::new(),
+            degradation: DegradationConfig::default(),
+            operations_dir: None,
+            clock_skew_warn_seconds: DEFAULT_SKEW_WARN_THRESHOLD.as_secs(),
+            auth: AuthConfig::default(),
+            #[cfg(feature = "sqlite-index")]
+            index: None,
+            #[cfg(feature = "dns-gateway")]
+            dns: None,
+            #[cfg(feature = "http-gateway")]
+            http_gateway: None,
+        }
+    }
+}
+
+impl DaemonConfig {
+    /// Read and validate a configuration file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let config: Self = config::Config::builder()
+            .add_source(config::File::from(path))
+            .build()
+            .and_then(|source| source.try_deserialize())
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to load {}: {}", path.display(), e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.nested("etherlink", self.etherlink.validate());
+        v.check(self.chain_id != 0, "chain_id", "must be non-zero");
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.log_filter) {
+            v.push("log_filter", format!("invalid filter directive: {}", e));
+        }
+        v.check(self.cns_cache_ttl_seconds > 0, "cns_cache_ttl_seconds", "must be greater than zero");
+        v.check(self.clock_skew_warn_seconds > 0, "clock_skew_warn_seconds", "must be greater than zero");
+        for (service, limit) in &self.rate_limits {
+            v.nested(&format!("rate_limits.{}", service), limit.validate());
+        }
+        v.nested("degradation", self.degradation.validate());
+        #[cfg(feature = "sqlite-index")]
+        if let Some(index) = &self.index {
+            v.nested("index", index.validate());
+        }
+        #[cfg(feature = "dns-gateway")]
+        if let Some(dns) = &self.dns {
+            v.nested("dns", dns.validate());
+        }
+        #[cfg(feature = "http-gateway")]
+        if let Some(gateway) = &self.http_gateway {
+            v.nested("http_gateway", gateway.validate());
+        }
+        v.finish()
+    }
+}
+
+/// Outcome of applying a new configuration to a running runtime
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReloadReport {
+    /// Field paths whose new value is now in effect
+    pub applied: Vec<String>,
+    /// Changes that were ignored because they need a restart
+    pub rejected: Vec<ConfigIssue>,
+}
+
+impl ReloadReport {
+    /// Whether the new configuration differed from the running one at all
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty() && self.rejected.is_empty()
+    }
+}
+
+/// Broadcast to [`super::Etherlink::subscribe_reloads`] subscribers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReloadEvent {
+    /// Changes now in effect
+    Applied { fields: Vec<String> },
+    /// A change that cannot be made without restarting
+    Rejected { issue: ConfigIssue },
+    /// The new configuration was invalid and nothing was changed
+    Failed { error: String },
+}
+
+type SetFilterFn = dyn Fn(&str) -> Result<()> + Send + Sync;
+
+/// Handle for changing the global log filter, from [`crate::init_with_reloadable_tracing`]
+#[derive(Clone)]
+pub struct LogFilterHandle {
+    set: Arc<SetFilterFn>,
+}
+
+impl LogFilterHandle {
+    pub(crate) fn new(set: impl Fn(&str) -> Result<()> + Send + Sync + 'static) -> Self {
+        Self { set: Arc::new(set) }
+    }
+
+    /// Replace the active filter directive
+    pub fn set(&self, filter: &str) -> Result<()> {
+        (self.set)(filter)
+    }
+}
+
+impl fmt::Debug for LogFilterHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogFilterHandle").finish_non_exhaustive()
+    }
+}
+
+/// Watches a configuration file and yields its new contents after each change
+///
+/// The parent directory is watched rather than the file itself, so editors that save
+/// by writing a temporary file and renaming it over the original are picked up.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    changes: mpsc::UnboundedReceiver<()>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    /// Start watching `path`
+    pub fn new(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_os_string())
+            .ok_or_else(|| EtherlinkError::Configuration(format!("Not a file path: {}", path.display())))?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let (tx, changes) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event
+                && !event.kind.is_access()
+                && event.paths.iter().any(|changed| changed.file_name() == Some(file_name.as_os_str()))
+            {
+                let _ = tx.send(());
+            }
+        })
+        .map_err(|e| EtherlinkError::Configuration(format!("Failed to watch {}: {}", path.display(), e)))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to watch {}: {}", dir.display(), e)))?;
+
+        Ok(Self { path, changes, _watcher: watcher })
+    }
+
+    /// Path of the watched file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Wait for the file to change and load it
+    ///
+    /// Returns `None` once the watcher stops; a file that fails to load or validate
+    /// is returned as an error so the caller can keep running on the old settings.
+    pub async fn changed(&mut self) -> Option<Result<DaemonConfig>> {
+        self.changes.recv().await?;
+        tokio::time::sleep(RELOAD_DEBOUNCE).await;
+        while self.changes.try_recv().is_ok() {}
+
+        debug!("Configuration file {} changed", self.path.display());
+        Some(DaemonConfig::load(&self.path))
+    }
+}
@@ -0,0 +1,185 @@
+//! DNS-over-CNS: resolve `.ghost` (and other CNS TLD) endpoints before dialing
+
+use crate::cns::CNSClient;
+use crate::transport::{HostOverrides, ProxyConfig};
+use crate::{EtherlinkError, Result};
+use reqwest::Url;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use tracing::{debug, warn};
+
+/// Resolves endpoints whose host is a CNS domain into the addresses to dial
+///
+/// The endpoint keeps its hostname, so TLS still verifies the server against it; the
+/// domain's A and AAAA records go into [`HostOverrides`], which the runtime's HTTP
+/// client and gRPC channels dial instead of asking the system resolver.
+///
+/// Resolutions are cached by the underlying [`CNSClient`]; when a call against a
+/// resolved address fails at the network level the cached entry is dropped and the
+/// domain is resolved again before a single retry.
+#[derive(Debug, Clone)]
+pub struct EndpointResolver {
+    cns: CNSClient,
+    overrides: HostOverrides,
+    proxy: Option<ProxyConfig>,
+}
+
+impl EndpointResolver {
+    /// Create a resolver backed by a CNS client
+    pub fn new(cns: CNSClient) -> Self {
+        Self { cns, overrides: HostOverrides::new(), proxy: None }
+    }
+
+    /// Account for REST clients reaching endpoints through `proxy`, see [`EndpointResolver::resolve_rest`]
+    pub fn with_proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Record resolved addresses in `overrides`, shared with the connectors that dial them
+    pub fn with_overrides(mut self, overrides: HostOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Addresses resolved so far, by CNS domain
+    pub fn overrides(&self) -> &HostOverrides {
+        &self.overrides
+    }
+
+    /// Get the CNS domain an endpoint points at, if any
+    pub fn cns_domain(&self, endpoint: &str) -> Option<String> {
+        let url = Url::parse(endpoint).ok()?;
+        let host = url.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+        let tld = host.rsplit('.').next()?;
+
+        self.cns
+            .config()
+            .supported_tlds
+            .iter()
+            .any(|supported| supported.eq_ignore_ascii_case(tld))
+            .then_some(host)
+    }
+
+    /// Resolve an endpoint's CNS domain into [`EndpointResolver::overrides`]
+    ///
+    /// Returns the endpoint to use: unchanged, except that the domain's port record
+    /// fills in a missing port. Endpoints on other hosts are returned as given.
+    pub async fn resolve(&self, endpoint: &str) -> Result<String> {
+        let Some(domain) = self.cns_domain(endpoint) else {
+            return Ok(endpoint.to_string());
+        };
+
+        let resolution = self.cns.resolve_domain(&domain).await?;
+        let mut url = Url::parse(endpoint)
+            .map_err(|e| EtherlinkError::Configuration(format!("Invalid endpoint {}: {}", endpoint, e)))?;
+        if url.port().is_none()
+            && let Some(port) = resolution.records.port()
+        {
+            let _ = url.set_port(Some(port));
+        }
+        let port = url.port_or_known_default().unwrap_or(80);
+
+        let addrs: Vec<SocketAddr> = resolution
+            .records
+            .a_records()
+            .into_iter()
+            .map(IpAddr::V4)
+            .chain(resolution.records.aaaa_records().into_iter().map(IpAddr::V6))
+            .map(|ip| SocketAddr::new(ip, port))
+            .collect();
+        if addrs.is_empty() {
+            return Err(EtherlinkError::CnsResolution(format!("{} has no A or AAAA record", domain)));
+        }
+        debug!("Resolved {} to {:?}", domain, addrs);
+        self.overrides.set(&domain, addrs);
+
+        // Url always normalizes an empty path to "/", which endpoints don't carry
+        let mut resolved = url.to_string();
+        if !endpoint.ends_with('/') && url.path() == "/" {
+            resolved.pop();
+        }
+        Ok(resolved)
+    }
+
+    /// Like [`EndpointResolver::resolve`], for an endpoint the REST clients also use
+    ///
+    /// A proxy cannot resolve CNS names and reqwest hands it the URL's host, so a
+    /// proxied `http` endpoint is rewritten to the domain's first address, as the gRPC
+    /// proxy connector does. A proxied `https` endpoint is refused: TLS would have to
+    /// verify the server against that address instead of its name.
+    pub async fn resolve_rest(&self, endpoint: &str) -> Result<String> {
+        let resolved = self.resolve(endpoint).await?;
+        let (Some(domain), Some(proxy)) = (self.cns_domain(endpoint), &self.proxy) else {
+            return Ok(resolved);
+        };
+        if proxy.bypasses(&domain) {
+            return Ok(resolved);
+        }
+
+        let mut url = Url::parse(&resolved)
+            .map_err(|e| EtherlinkError::Configuration(format!("Invalid endpoint {}: {}", resolved, e)))?;
+        if url.scheme() == "https" {
+            return Err(EtherlinkError::Configuration(format!(
+                "{} cannot be reached over TLS through the proxy, which cannot resolve CNS names; add {} to proxy.no_proxy",
+                endpoint, domain
+            )));
+        }
+        let address = self
+            .overrides
+            .get(&domain)
+            .and_then(|addrs| addrs.first().map(|addr| addr.ip()))
+            .ok_or_else(|| EtherlinkError::CnsResolution(format!("{} has no A or AAAA record", domain)))?;
+        let host = match address {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{}]", ip),
+        };
+        url.set_host(Some(&host))
+            .map_err(|e| EtherlinkError::CnsResolution(format!("Invalid address {} for {}: {}", address, domain, e)))?;
+
+        let mut rewritten = url.to_string();
+        if !resolved.ends_with('/') && url.path() == "/" {
+            rewritten.pop();
+        }
+        debug!("Proxied REST endpoint {} rewritten to {}", endpoint, rewritten);
+        Ok(rewritten)
+    }
+
+    /// Forget the cached resolution for an endpoint
+    pub async fn invalidate(&self, endpoint: &str) {
+        if let Some(domain) = self.cns_domain(endpoint) {
+            self.cns.invalidate(&domain).await;
+            self.overrides.remove(&domain);
+        }
+    }
+
+    /// Run `call` against the resolved endpoint, re-resolving once on network failure
+    pub async fn with_endpoint<T, F, Fut>(&self, endpoint: &str, call: F) -> Result<T>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let resolved = self.resolve(endpoint).await?;
+        match call(resolved.clone()).await {
+            Err(e) if self.cns_domain(endpoint).is_some() && is_connection_error(&e) => {
+                warn!("Call to {} ({}) failed, re-resolving: {}", endpoint, resolved, e);
+                self.invalidate(endpoint).await;
+                let resolved = self.resolve(endpoint).await?;
+                call(resolved).await
+            }
+            result => result,
+        }
+    }
+
+    /// Get the underlying CNS client
+    pub fn cns(&self) -> &CNSClient {
+        &self.cns
+    }
+}
+
+fn is_connection_error(error: &EtherlinkError) -> bool {
+    matches!(
+        error,
+        EtherlinkError::Transport(_) | EtherlinkError::Network(_) | EtherlinkError::Timeout(_)
+    )
+}
@@ -0,0 +1,84 @@
+//! Explorer-style search over blocks, transactions, accounts and domains
+//!
+//! [`SearchQuery::classify`] decides what a free-form input looks like and
+//! [`super::Etherlink::search`] looks it up with the matching client.
+
+use crate::address;
+use crate::clients::ghostd::{Block, TransactionReceipt};
+use crate::cns::DomainResolution;
+use crate::{Address, BlockHeight, EtherlinkError, TxHash};
+use serde::{Deserialize, Serialize};
+
+/// What a search input was recognised as
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum SearchQuery {
+    /// Decimal height, optionally prefixed with `#`
+    Block(BlockHeight),
+    /// `0x` followed by 64 hex digits
+    Transaction(TxHash),
+    /// `0x` EVM address or native bech32m address
+    Address(Address),
+    /// Name with a TLD, e.g. `alice.ghost`
+    Domain(String),
+}
+
+impl SearchQuery {
+    /// Recognise `input`, or `None` if it looks like nothing searchable
+    pub fn classify(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let digits = input.strip_prefix('#').unwrap_or(input);
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            return digits.parse().ok().map(SearchQuery::Block);
+        }
+
+        if let Some(hex) = input.strip_prefix("0x").or_else(|| input.strip_prefix("0X"))
+            && hex.len() == 64
+            && hex.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return Some(SearchQuery::Transaction(TxHash::new(input.to_lowercase())));
+        }
+
+        let candidate = Address::new(input.to_string());
+        if address::evm_bytes(&candidate).is_ok() || address::native_bytes(&candidate).is_ok() {
+            return Some(SearchQuery::Address(candidate));
+        }
+
+        let domain = input.to_lowercase();
+        let labels: Vec<&str> = domain.split('.').collect();
+        let valid_label = |label: &&str| {
+            !label.is_empty() && !label.starts_with('-') && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        };
+        if labels.len() >= 2 && labels.iter().all(valid_label) && !labels.last().unwrap().chars().all(|c| c.is_ascii_digit()) {
+            return Some(SearchQuery::Domain(domain));
+        }
+        None
+    }
+}
+
+/// What a search found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SearchResult {
+    Block { block: Box<Block> },
+    Transaction { receipt: Box<TransactionReceipt> },
+    Account { address: Address, balance: u64 },
+    Domain { resolution: Box<DomainResolution> },
+    /// The input was recognised but nothing exists under it
+    NotFound { query: SearchQuery },
+    /// The input is not a height, hash, address or domain
+    Unrecognized { input: String },
+}
+
+impl SearchResult {
+    pub fn is_found(&self) -> bool {
+        !matches!(self, SearchResult::NotFound { .. } | SearchResult::Unrecognized { .. })
+    }
+}
+
+/// Whether a lookup error means "no such thing" rather than a failed request
+///
+/// Only the service's own not-found answer counts; any other rejection is a failure.
+pub(crate) fn is_not_found(error: &EtherlinkError) -> bool {
+    matches!(error, EtherlinkError::NotFound(_))
+}
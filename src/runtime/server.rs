@@ -0,0 +1,195 @@
+//! gRPC server mode: standard health checking and server reflection
+//!
+//! Serves `grpc.health.v1.Health` (as used by Kubernetes gRPC probes) and both
+//! versions of `grpc.reflection` so `grpcurl` works without local proto files.
+//! Reflection describes only those services; the GhostChain protos this crate
+//! consumes as a client are not served and so are not advertised.
+//! With [`ServerConfig::probe_addr`] set, the same health is also served over plain
+//! HTTP as `/healthz` (liveness) and `/readyz` (readiness) for probes that cannot
+//! speak gRPC.
+
+use super::health::{HealthReport, ServiceState};
+use crate::validation::{ConfigErrors, Validator};
+use crate::{EtherlinkError, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic_health::ServingStatus;
+use tonic_health::server::HealthReporter;
+use tracing::{debug, info};
+
+/// Settings for the Etherlink gRPC server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub listen_addr: SocketAddr,
+    /// How often upstream service health is re-checked and republished
+    pub health_interval_ms: u64,
+    pub enable_reflection: bool,
+    /// Serve HTTP `/healthz` and `/readyz` here as well
+    #[serde(default)]
+    pub probe_addr: Option<SocketAddr>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: SocketAddr::from(([0, 0, 0, 0], 50051)),
+            health_interval_ms: 10000,
+            enable_reflection: true,
+            probe_addr: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.health_interval_ms > 0, "health_interval_ms", "must be greater than zero");
+        v.finish()
+    }
+}
+
+/// Serving status reported for a service state
+///
+/// Degraded services still answer, so they stay `SERVING` and do not fail probes.
+pub fn serving_status(state: ServiceState) -> ServingStatus {
+    match state {
+        ServiceState::Healthy | ServiceState::Degraded => ServingStatus::Serving,
+        ServiceState::Unhealthy => ServingStatus::NotServing,
+    }
+}
+
+/// Publish a health report: the overall state under `""`, each upstream service under its name
+pub(crate) async fn publish(reporter: &mut HealthReporter, report: &HealthReport) {
+    reporter.set_service_status("", serving_status(report.overall())).await;
+    for service in &report.services {
+        reporter.set_service_status(&service.service, serving_status(service.state)).await;
+    }
+}
+
+/// Run the gRPC server on `listener` until `shutdown` completes
+///
+/// `refresh` produces a fresh health report every `health_interval_ms`; `probes`, if
+/// given, serves the latest one over HTTP.
+pub(crate) async fn run<R, RFut, S>(listener: TcpListener, probes: Option<TcpListener>, config: &ServerConfig, refresh: R, shutdown: S) -> Result<()>
+where
+    R: Fn() -> RFut,
+    RFut: Future<Output = HealthReport>,
+    S: Future<Output = ()>,
+{
+    let (mut reporter, health_service) = tonic_health::server::health_reporter();
+    // Not ready until the first report is in
+    reporter.set_service_status("", ServingStatus::NotServing).await;
+
+    let mut router = tonic::transport::Server::builder().add_service(health_service);
+    if config.enable_reflection {
+        // Only what this server actually serves; the GhostChain client protos are not
+        let reflection_v1 = tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+            .register_encoded_file_descriptor_set(tonic_reflection::pb::v1::FILE_DESCRIPTOR_SET)
+            .build_v1()
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to build reflection service: {}", e)))?;
+        let reflection_v1alpha = tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+            .register_encoded_file_descriptor_set(tonic_reflection::pb::v1alpha::FILE_DESCRIPTOR_SET)
+            .build_v1alpha()
+            .map_err(|e| EtherlinkError::Configuration(format!("Failed to build reflection service: {}", e)))?;
+        router = router.add_service(reflection_v1).add_service(reflection_v1alpha);
+    }
+
+    let local_addr = listener.local_addr().map_err(|e| EtherlinkError::Network(e.to_string()))?;
+    info!("Etherlink gRPC server listening on {}", local_addr);
+
+    let server = router.serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown);
+
+    let latest: Arc<RwLock<Option<HealthReport>>> = Arc::default();
+    let interval = Duration::from_millis(config.health_interval_ms);
+    let health_updates = async {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let report = refresh().await;
+            debug!("Publishing health: overall {:?}", report.overall());
+            publish(&mut reporter, &report).await;
+            *latest.write().unwrap() = Some(report);
+        }
+    };
+    let probes = async {
+        match probes {
+            Some(listener) => serve_probes(listener, latest.clone()).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = server => result.map_err(EtherlinkError::from),
+        _ = health_updates => Ok(()),
+        _ = probes => Ok(()),
+    }
+}
+
+/// Answer `/healthz` and `/readyz` from the latest published report
+async fn serve_probes(listener: TcpListener, latest: Arc<RwLock<Option<HealthReport>>>) {
+    if let Ok(local_addr) = listener.local_addr() {
+        info!("Health probes listening on http://{}", local_addr);
+    }
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                debug!("Health probe accept failed: {}", e);
+                continue;
+            }
+        };
+        let latest = latest.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let response = probe_response(&request, latest.read().unwrap().as_ref());
+                async move { Ok::<_, Infallible>(response) }
+            });
+            if let Err(e) = Http::new().serve_connection(stream, service).await {
+                debug!("Health probe connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// `/readyz` is 200 with the report while the overall state is `SERVING` over gRPC,
+/// and 503 before the first report or once a service is unhealthy
+fn probe_response(request: &Request<Body>, report: Option<&HealthReport>) -> Response<Body> {
+    if request.method() != Method::GET {
+        return json_response(StatusCode::METHOD_NOT_ALLOWED, serde_json::json!({ "error": "only GET is supported" }));
+    }
+    match request.uri().path() {
+        "/healthz" => json_response(StatusCode::OK, serde_json::json!({ "status": "ok" })),
+        "/readyz" => match report {
+            Some(report) => {
+                let status = match serving_status(report.overall()) {
+                    ServingStatus::Serving => StatusCode::OK,
+                    _ => StatusCode::SERVICE_UNAVAILABLE,
+                };
+                json_response(status, serde_json::to_value(report).unwrap_or_default())
+            }
+            None => json_response(StatusCode::SERVICE_UNAVAILABLE, serde_json::json!({ "error": "no health report yet" })),
+        },
+        path => json_response(StatusCode::NOT_FOUND, serde_json::json!({ "error": format!("no route for {}", path) })),
+    }
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static("application/json"));
+    response
+}
@@ -0,0 +1,255 @@
+//! Structured concurrency for Etherlink background tasks
+
+use crate::{EtherlinkError, Result};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Restart behaviour applied when a supervised task panics
+#[derive(Debug, Clone, PartialEq)]
+pub enum RestartPolicy {
+    /// Let the task die and log the panic
+    Never,
+    /// Restart the task after `backoff`, at most `max_restarts` times
+    OnPanic { max_restarts: u32, backoff: Duration },
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self::OnPanic {
+            max_restarts: 5,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Snapshot of a supervised task
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    pub name: String,
+    pub restarts: u32,
+    pub finished: bool,
+}
+
+/// A task owned by the supervisor
+struct SupervisedTask {
+    name: String,
+    token: CancellationToken,
+    handle: JoinHandle<()>,
+    restarts: Arc<std::sync::atomic::AtomicU32>,
+}
+
+/// Aborts the wrapped task when dropped, so aborting a supervisor loop also stops its task
+struct AbortOnDrop(tokio::task::AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Owns every background task spawned by Etherlink and shuts them down in order
+///
+/// Tasks receive their own [`CancellationToken`], a child of the supervisor's, and are
+/// expected to return promptly once it is cancelled. Shutdown cancels tasks in reverse
+/// spawn order so that consumers stop before the producers they depend on.
+#[derive(Clone)]
+pub struct TaskSupervisor {
+    /// Parent of every task token, cancelled once shutdown has stopped the tasks
+    root: CancellationToken,
+    /// Cancelled as soon as shutdown begins
+    shutting_down: CancellationToken,
+    tasks: Arc<Mutex<Vec<SupervisedTask>>>,
+}
+
+impl TaskSupervisor {
+    /// Create a new supervisor with no tasks
+    pub fn new() -> Self {
+        Self {
+            root: CancellationToken::new(),
+            shutting_down: CancellationToken::new(),
+            tasks: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Spawn a supervised task
+    ///
+    /// `task` is invoked once per (re)start with a cancellation token scoped to this task.
+    /// Fails once shutdown has begun.
+    pub async fn spawn<F, Fut>(&self, name: &str, policy: RestartPolicy, task: F) -> Result<()>
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        // Checked under the task list lock so shutdown either sees this task or refuses it
+        let mut tasks = self.tasks.lock().await;
+        if self.shutting_down.is_cancelled() {
+            return Err(EtherlinkError::General(anyhow::anyhow!(
+                "Cannot spawn task {} after shutdown", name
+            )));
+        }
+
+        let token = self.root.child_token();
+        let restarts = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let handle = tokio::spawn(Self::run_supervised(
+            name.to_string(),
+            policy,
+            token.clone(),
+            restarts.clone(),
+            task,
+        ));
+
+        debug!("Spawned supervised task {}", name);
+        tasks.push(SupervisedTask {
+            name: name.to_string(),
+            token,
+            handle,
+            restarts,
+        });
+        Ok(())
+    }
+
+    async fn run_supervised<F, Fut>(
+        name: String,
+        policy: RestartPolicy,
+        token: CancellationToken,
+        restarts: Arc<std::sync::atomic::AtomicU32>,
+        task: F,
+    ) where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        use std::sync::atomic::Ordering;
+
+        loop {
+            let inner = tokio::spawn(task(token.clone()));
+            let _abort = AbortOnDrop(inner.abort_handle());
+            let result = inner.await;
+
+            let err = match result {
+                Ok(()) => return,
+                Err(e) if e.is_panic() => e,
+                Err(_) => return, // Aborted during shutdown
+            };
+
+            if token.is_cancelled() {
+                return;
+            }
+
+            match &policy {
+                RestartPolicy::OnPanic { max_restarts, backoff } => {
+                    let attempt = restarts.load(Ordering::SeqCst);
+                    if attempt >= *max_restarts {
+                        error!("Task {} panicked and exhausted {} restarts: {}", name, max_restarts, err);
+                        return;
+                    }
+                    restarts.store(attempt + 1, Ordering::SeqCst);
+                    warn!("Task {} panicked, restarting in {:?} (attempt {}): {}", name, backoff, attempt + 1, err);
+
+                    tokio::select! {
+                        _ = token.cancelled() => return,
+                        _ = tokio::time::sleep(*backoff) => {}
+                    }
+                }
+                RestartPolicy::Never => {
+                    error!("Task {} panicked: {}", name, err);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Get a token that is cancelled as soon as shutdown begins
+    pub fn token(&self) -> CancellationToken {
+        self.shutting_down.clone()
+    }
+
+    /// Check if shutdown has been requested
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.is_cancelled()
+    }
+
+    /// List the supervised tasks
+    pub async fn tasks(&self) -> Vec<TaskInfo> {
+        self.tasks
+            .lock()
+            .await
+            .iter()
+            .map(|t| TaskInfo {
+                name: t.name.clone(),
+                restarts: t.restarts.load(std::sync::atomic::Ordering::SeqCst),
+                finished: t.handle.is_finished(),
+            })
+            .collect()
+    }
+
+    /// Cancel and await a single task by name
+    pub async fn stop(&self, name: &str) -> bool {
+        let task = {
+            let mut tasks = self.tasks.lock().await;
+            tasks.iter().position(|t| t.name == name).map(|i| tasks.remove(i))
+        };
+
+        match task {
+            Some(task) => {
+                task.token.cancel();
+                let _ = task.handle.await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel all tasks in reverse spawn order, aborting any still running after `timeout`
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        let mut tasks: Vec<SupervisedTask> = {
+            let mut tasks = self.tasks.lock().await;
+            self.shutting_down.cancel();
+            tasks.drain(..).collect()
+        };
+        info!("Shutting down {} supervised tasks", tasks.len());
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut timed_out = Vec::new();
+
+        while let Some(mut task) = tasks.pop() {
+            task.token.cancel();
+            match tokio::time::timeout_at(deadline, &mut task.handle).await {
+                Ok(_) => debug!("Task {} stopped", task.name),
+                Err(_) => {
+                    warn!("Task {} did not stop before deadline, aborting", task.name);
+                    task.handle.abort();
+                    timed_out.push(task.name);
+                }
+            }
+        }
+        self.root.cancel();
+
+        if timed_out.is_empty() {
+            Ok(())
+        } else {
+            Err(EtherlinkError::Timeout(format!(
+                "Tasks aborted during shutdown: {}",
+                timed_out.join(", ")
+            )))
+        }
+    }
+}
+
+impl Default for TaskSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for TaskSupervisor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskSupervisor")
+            .field("shutting_down", &self.shutting_down.is_cancelled())
+            .finish()
+    }
+}
@@ -44,10 +44,10 @@ impl GasMeter {
     }
 
     pub fn consume(&mut self, amount: Gas) -> Result<()> {
-        if self.used + amount > self.limit {
-            return Err(EtherlinkError::RvmExecution("Out of gas".to_string()));
+        match self.used.checked_add(amount) {
+            Some(used) if used <= self.limit => self.used = used,
+            _ => return Err(EtherlinkError::RvmExecution("Out of gas".to_string())),
         }
-        self.used += amount;
         Ok(())
     }
 
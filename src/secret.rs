@@ -0,0 +1,104 @@
+//! Wrapper for key material and credentials
+//!
+//! A [`Secret`] wipes its contents with `zeroize` when dropped and prints as
+//! [`REDACTED`] in `Debug` and `Display`, so private keys, mnemonics and passphrases
+//! neither linger in freed memory nor leak into logs. Reading the value takes an
+//! explicit [`Secret::expose_secret`], which keeps every use easy to find.
+//!
+//! Serialization is transparent: secrets still travel to the services that need
+//! them, and [`crate::diagnostics`] redacts them by field name in bundles.
+//! Equality runs in constant time, see [`constant_time_eq`].
+
+use crate::diagnostics::REDACTED;
+use core::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+/// Compare secrets, MACs or signatures without leaking where they first differ
+///
+/// Only the length is compared in variable time.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && bool::from(a.ct_eq(b))
+}
+
+/// A value that is zeroized on drop and never printed
+pub struct Secret<T: Zeroize> {
+    inner: T,
+}
+
+/// The common case: a hex key, mnemonic or password
+pub type SecretString = Secret<alloc::string::String>;
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// Borrow the secret value
+    pub fn expose_secret(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.inner.zeroize();
+    }
+}
+
+impl<T: Zeroize + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone())
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(self.inner.as_ref(), other.inner.as_ref())
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Eq for Secret<T> {}
+
+impl<T: Zeroize + Default> Default for Secret<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(inner: T) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(inner: &str) -> Self {
+        Self::new(inner.into())
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: Zeroize> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: Zeroize + Serialize> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner.serialize(serializer)
+    }
+}
+
+impl<'de, T: Zeroize + Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
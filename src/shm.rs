@@ -0,0 +1,338 @@
+//! Shared-memory ring buffer between Rust and Zig
+//!
+//! For high submission rates the per-call FFI overhead dominates. Once the bridge
+//! has negotiated a ring via [`crate::ffi::ZigBridge::attach_shared_memory`], payloads
+//! are written into a memory-mapped file that the Zig side drains on its own thread.
+//!
+//! The file holds a header followed by the data area. The producer owns the `head`
+//! cursor and the consumer owns `tail`; both count bytes written over the ring's
+//! lifetime, so `head - tail` is the number of bytes in flight. Each record is an
+//! 8-byte `[len: u32][crc32: u32]` header followed by the payload, padded to 8 bytes.
+//! A record that would straddle the end of the data area is preceded by a wrap
+//! marker and written at the start instead. A full ring refuses writes until the
+//! consumer catches up, and any record whose length or checksum does not add up is
+//! reported as corruption rather than returned.
+
+use crate::validation::{ConfigErrors, Validator};
+use crate::{EtherlinkError, Result};
+use memmap2::MmapMut;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const MAGIC: u32 = u32::from_le_bytes(*b"ELRB");
+const VERSION: u32 = 1;
+/// Cursors live on their own cache lines so producer and consumer do not contend
+const HEAD_OFFSET: usize = 64;
+const TAIL_OFFSET: usize = 128;
+const HEADER_SIZE: usize = 192;
+const RECORD_HEADER: usize = 8;
+const WRAP_MARKER: u32 = u32::MAX;
+/// Smallest data area accepted
+pub const MIN_RING_CAPACITY: usize = 4096;
+
+/// Shared-memory submission channel settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SharedMemoryConfig {
+    /// Negotiate a ring with the Zig side at initialization
+    pub enabled: bool,
+    /// Backing file; a fresh file in the temp directory when unset
+    pub path: Option<PathBuf>,
+    /// Size of the data area, rounded up to a multiple of 8
+    pub capacity_bytes: usize,
+    /// How long a write waits for the consumer to free space
+    pub send_timeout_ms: u64,
+}
+
+impl Default for SharedMemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            capacity_bytes: 8 * 1024 * 1024,
+            send_timeout_ms: 1000,
+        }
+    }
+}
+
+impl SharedMemoryConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        if self.capacity_bytes < MIN_RING_CAPACITY {
+            v.push("capacity_bytes", format!("must be at least {}", MIN_RING_CAPACITY));
+        }
+        v.timeout("send_timeout_ms", self.send_timeout_ms);
+        v.finish()
+    }
+
+    /// Backing file to create, generating one in the temp directory if unset
+    pub fn resolve_path(&self) -> PathBuf {
+        self.path.clone().unwrap_or_else(|| {
+            std::env::temp_dir().join(format!("etherlink-{}-{}.ring", std::process::id(), uuid::Uuid::new_v4()))
+        })
+    }
+}
+
+/// One end of a memory-mapped single-producer, single-consumer ring
+///
+/// Both ends may push and pop; within a process, pushes and pops are each serialized,
+/// so the ring stays single-producer/single-consumer across the mapping.
+#[derive(Debug)]
+pub struct SharedRing {
+    map: MmapMut,
+    base: *mut u8,
+    capacity: u64,
+    path: PathBuf,
+    /// Whether this end created the file and removes it on drop
+    owner: bool,
+    push_lock: Mutex<()>,
+    pop_lock: Mutex<()>,
+}
+
+// The mapping is only accessed through the cursors' acquire/release protocol, with
+// pushes and pops each serialized by their own lock.
+unsafe impl Send for SharedRing {}
+unsafe impl Sync for SharedRing {}
+
+impl SharedRing {
+    /// Create a ring backed by a new file, replacing any existing one
+    pub fn create(path: impl Into<PathBuf>, capacity_bytes: usize) -> Result<Self> {
+        let path = path.into();
+        let capacity = capacity_bytes.max(MIN_RING_CAPACITY).next_multiple_of(8);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .and_then(|file| file.set_len((HEADER_SIZE + capacity) as u64).map(|_| file))
+            .map_err(|e| EtherlinkError::Ffi(format!("Failed to create ring {}: {}", path.display(), e)))?;
+        let mut map = unsafe { MmapMut::map_mut(&file) }
+            .map_err(|e| EtherlinkError::Ffi(format!("Failed to map ring {}: {}", path.display(), e)))?;
+
+        map[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        map[4..8].copy_from_slice(&VERSION.to_le_bytes());
+        map[8..16].copy_from_slice(&(capacity as u64).to_le_bytes());
+        let base = map.as_mut_ptr();
+        let ring = Self::from_map(map, base, capacity as u64, path, true);
+        ring.head().store(0, Ordering::Release);
+        ring.tail().store(0, Ordering::Release);
+        Ok(ring)
+    }
+
+    /// Attach to a ring created by the other side
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(|e| EtherlinkError::Ffi(format!("Failed to open ring {}: {}", path.display(), e)))?;
+        let mut map = unsafe { MmapMut::map_mut(&file) }
+            .map_err(|e| EtherlinkError::Ffi(format!("Failed to map ring {}: {}", path.display(), e)))?;
+
+        if map.len() < HEADER_SIZE {
+            return Err(corrupt("file is smaller than the ring header"));
+        }
+        let magic = u32::from_le_bytes(map[0..4].try_into().unwrap());
+        let version = u32::from_le_bytes(map[4..8].try_into().unwrap());
+        let capacity = u64::from_le_bytes(map[8..16].try_into().unwrap());
+        if magic != MAGIC {
+            return Err(corrupt("bad magic"));
+        }
+        if version != VERSION {
+            return Err(EtherlinkError::Ffi(format!("Unsupported ring version {}", version)));
+        }
+        if capacity % 8 != 0 || HEADER_SIZE as u64 + capacity != map.len() as u64 {
+            return Err(corrupt("capacity does not match the file size"));
+        }
+
+        let base = map.as_mut_ptr();
+        let ring = Self::from_map(map, base, capacity, path, false);
+        ring.in_flight()?;
+        Ok(ring)
+    }
+
+    fn from_map(map: MmapMut, base: *mut u8, capacity: u64, path: PathBuf, owner: bool) -> Self {
+        Self { map, base, capacity, path, owner, push_lock: Mutex::new(()), pop_lock: Mutex::new(()) }
+    }
+
+    /// Path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Size of the data area
+    pub fn capacity(&self) -> usize {
+        self.capacity as usize
+    }
+
+    /// Largest payload a single record can hold
+    pub fn max_payload(&self) -> usize {
+        self.capacity as usize - RECORD_HEADER
+    }
+
+    /// Bytes written but not yet consumed, including record framing
+    pub fn in_flight(&self) -> Result<usize> {
+        let head = self.head().load(Ordering::Acquire);
+        let tail = self.tail().load(Ordering::Acquire);
+        match head.checked_sub(tail) {
+            Some(used) if used <= self.capacity => Ok(used as usize),
+            _ => Err(corrupt(&format!("cursors out of range (head {}, tail {})", head, tail))),
+        }
+    }
+
+    /// Write a record if there is room, returning `false` when the ring is full
+    pub fn try_push(&self, payload: &[u8]) -> Result<bool> {
+        if payload.len() > self.max_payload() {
+            return Err(EtherlinkError::Ffi(format!(
+                "{} byte payload exceeds the ring's {} byte record limit",
+                payload.len(),
+                self.max_payload()
+            )));
+        }
+
+        let _guard = self.push_lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut head = self.head().load(Ordering::Relaxed);
+        let tail = self.tail().load(Ordering::Acquire);
+        let record = record_size(payload.len());
+        let mut pos = head % self.capacity;
+        let to_end = self.capacity - pos;
+        if record > to_end {
+            // Pad out the end with a wrap marker on its own, so that the record can
+            // start from 0 once the consumer has drained what is ahead of it, even if
+            // the record and the padding would never fit in the ring together
+            if head.wrapping_sub(tail) + to_end > self.capacity {
+                return Ok(false);
+            }
+            self.write_bytes(pos, &WRAP_MARKER.to_le_bytes());
+            head += to_end;
+            self.head().store(head, Ordering::Release);
+            pos = 0;
+        }
+        if head.wrapping_sub(tail) + record > self.capacity {
+            return Ok(false);
+        }
+
+        self.write_bytes(pos, &(payload.len() as u32).to_le_bytes());
+        self.write_bytes(pos + 4, &crc32fast::hash(payload).to_le_bytes());
+        self.write_bytes(pos + RECORD_HEADER as u64, payload);
+        self.head().store(head + record, Ordering::Release);
+        Ok(true)
+    }
+
+    /// Write a record, waiting up to `timeout` for the consumer to free space
+    pub async fn push(&self, payload: &[u8], timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        while !self.try_push(payload)? {
+            if Instant::now() >= deadline {
+                return Err(EtherlinkError::Timeout(format!(
+                    "Shared memory ring {} stayed full for {:?}",
+                    self.path.display(),
+                    timeout
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        Ok(())
+    }
+
+    /// Read the next record, if any
+    pub fn try_pop(&self) -> Result<Option<Vec<u8>>> {
+        let _guard = self.pop_lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut tail = self.tail().load(Ordering::Relaxed);
+        let head = self.head().load(Ordering::Acquire);
+
+        loop {
+            if tail == head {
+                return Ok(None);
+            }
+            if head.wrapping_sub(tail) > self.capacity {
+                return Err(corrupt(&format!("cursors out of range (head {}, tail {})", head, tail)));
+            }
+
+            let pos = tail % self.capacity;
+            let len = u32::from_le_bytes(self.read_bytes(pos, 4).try_into().unwrap());
+            if len == WRAP_MARKER {
+                tail += self.capacity - pos;
+                self.tail().store(tail, Ordering::Release);
+                continue;
+            }
+
+            let record = record_size(len as usize);
+            if record > self.capacity - pos || tail + record > head {
+                return Err(corrupt(&format!("record of {} bytes at offset {} overruns the ring", len, pos)));
+            }
+            let checksum = u32::from_le_bytes(self.read_bytes(pos + 4, 4).try_into().unwrap());
+            let payload = self.read_bytes(pos + RECORD_HEADER as u64, len as usize);
+            if crc32fast::hash(&payload) != checksum {
+                return Err(corrupt(&format!("checksum mismatch in record at offset {}", pos)));
+            }
+
+            self.tail().store(tail + record, Ordering::Release);
+            return Ok(Some(payload));
+        }
+    }
+
+    /// Read the next record, waiting up to `timeout` for one to arrive
+    pub async fn pop(&self, timeout: Duration) -> Result<Option<Vec<u8>>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(payload) = self.try_pop()? {
+                return Ok(Some(payload));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    fn head(&self) -> &AtomicU64 {
+        unsafe { &*(self.base.add(HEAD_OFFSET) as *const AtomicU64) }
+    }
+
+    fn tail(&self) -> &AtomicU64 {
+        unsafe { &*(self.base.add(TAIL_OFFSET) as *const AtomicU64) }
+    }
+
+    fn write_bytes(&self, offset: u64, bytes: &[u8]) {
+        debug_assert!(offset as usize + bytes.len() <= self.capacity as usize);
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), self.base.add(HEADER_SIZE + offset as usize), bytes.len());
+        }
+    }
+
+    fn read_bytes(&self, offset: u64, len: usize) -> Vec<u8> {
+        debug_assert!(offset as usize + len <= self.capacity as usize);
+        let mut bytes = vec![0; len];
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.base.add(HEADER_SIZE + offset as usize), bytes.as_mut_ptr(), len);
+        }
+        bytes
+    }
+}
+
+impl Drop for SharedRing {
+    fn drop(&mut self) {
+        if self.owner {
+            let _ = self.map.flush();
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Framed size of a record carrying `len` payload bytes
+fn record_size(len: usize) -> u64 {
+    (RECORD_HEADER + len).next_multiple_of(8) as u64
+}
+
+fn corrupt(detail: &str) -> EtherlinkError {
+    EtherlinkError::Ffi(format!("Shared memory ring corrupted: {}", detail))
+}
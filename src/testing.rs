@@ -0,0 +1,417 @@
+//! In-process GhostChain for examples and integration tests
+//!
+//! [`MockGhostChain`] serves the GHOSTD, GID Guardian and CNS REST endpoints the
+//! service clients use, backed by in-memory balances, domains and a block producer.
+//! Submitted transactions stay `pending` until the next block, which is produced
+//! lazily once `block_time` has passed, so receipt polling behaves as on a real node.
+//!
+//! Submitting a transaction requires a Guardian token with the `SubmitTransaction`
+//! permission, a signature and the sender's next nonce. Signatures are required but
+//! not verified, since transactions do not carry the sender's public key.
+//!
+//! [`DevAccounts`] are keypairs derived from a public seed, the same on every run, so
+//! tests and examples can name `alice` and `bob` instead of generating or hard-coding
+//! keys; [`MockGhostChain::fund_dev_accounts`] credits them all.
+
+use crate::auth::crypto::{CryptoAlgorithm, KeyPair};
+use crate::auth::Permission;
+use crate::clients::cns::{DomainRecords, DomainResolution};
+use crate::clients::ghostd::{BalanceResponse, HeightResponse, Transaction, TransactionReceipt, TransactionResponse};
+use crate::clients::gid::{AccessToken, GuardianTokenRequest};
+use crate::{Address, EtherlinkConfig, EtherlinkError, Gas, Network, Result};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::Serialize;
+use crate::hash::{HashAlgorithm, HashDomain, Hasher};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Gas charged for a plain transfer
+pub const TRANSFER_GAS: Gas = 21_000;
+
+/// Names of the [`DevAccounts`], in index order
+pub const DEV_ACCOUNT_NAMES: [&str; 10] = ["alice", "bob", "carol", "dave", "eve", "frank", "grace", "heidi", "ivan", "judy"];
+
+/// Seed the default [`DevAccounts`] are derived from
+pub const DEFAULT_DEV_SEED: &str = "etherlink dev accounts";
+
+/// Balance [`MockGhostChain::fund_dev_accounts`] credits each account with
+pub const DEV_ACCOUNT_BALANCE: u64 = 1_000_000_000_000;
+
+/// One of the [`DevAccounts`]
+#[derive(Debug, Clone)]
+pub struct DevAccount {
+    pub index: usize,
+    pub name: &'static str,
+    pub keypair: KeyPair,
+    address: Address,
+}
+
+impl DevAccount {
+    pub fn address(&self) -> Address {
+        self.address.clone()
+    }
+}
+
+/// Named keypairs derived deterministically from a seed, for tests and examples
+///
+/// The private keys follow from the seed alone and are therefore public: never fund
+/// them anywhere but a local or mock chain.
+#[derive(Debug, Clone)]
+pub struct DevAccounts {
+    accounts: Vec<DevAccount>,
+}
+
+impl DevAccounts {
+    /// The accounts for [`DEFAULT_DEV_SEED`]
+    pub fn new(algorithm: &CryptoAlgorithm) -> Result<Self> {
+        Self::from_seed(DEFAULT_DEV_SEED, algorithm)
+    }
+
+    /// The accounts derived from `seed`, one per name in [`DEV_ACCOUNT_NAMES`]
+    pub fn from_seed(seed: &str, algorithm: &CryptoAlgorithm) -> Result<Self> {
+        let accounts = DEV_ACCOUNT_NAMES
+            .iter()
+            .enumerate()
+            .map(|(index, &name)| {
+                let secret = Hasher::domain_digest(
+                    HashAlgorithm::Sha256,
+                    HashDomain::Dev,
+                    format!("{}/{:?}/{}", seed, algorithm, index),
+                );
+                let keypair = KeyPair::from_private_key(&hex::encode(secret), algorithm)?;
+                let address = keypair.address()?;
+                Ok(DevAccount { index, name, keypair, address })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { accounts })
+    }
+
+    /// The default accounts, refusing every network but [`Network::Local`]
+    ///
+    /// A custom network may hold real funds as well as a public one.
+    pub fn for_network(network: &Network, algorithm: &CryptoAlgorithm) -> Result<Self> {
+        match network {
+            Network::Local => Self::new(algorithm),
+            Network::Testnet | Network::Mainnet | Network::Custom(_) => Err(EtherlinkError::Configuration(format!(
+                "Dev accounts have public private keys and must not be used on {:?}",
+                network
+            ))),
+        }
+    }
+
+    /// Account at `index`
+    pub fn get(&self, index: usize) -> Option<&DevAccount> {
+        self.accounts.get(index)
+    }
+
+    /// Account called `name`, ignoring case
+    pub fn by_name(&self, name: &str) -> Option<&DevAccount> {
+        self.accounts.iter().find(|account| account.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DevAccount> {
+        self.accounts.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+}
+
+/// A local GhostChain node serving the REST APIs; stops when dropped
+#[derive(Debug)]
+pub struct MockGhostChain {
+    addr: SocketAddr,
+    state: Arc<Mutex<ChainState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+#[derive(Debug)]
+struct ChainState {
+    block_time: Duration,
+    last_block: Instant,
+    height: u64,
+    balances: HashMap<Address, u64>,
+    next_nonces: HashMap<Address, u64>,
+    domains: HashMap<String, Address>,
+    tokens: HashMap<String, Vec<Permission>>,
+    pending: Vec<(String, Transaction)>,
+    receipts: HashMap<String, TransactionReceipt>,
+}
+
+impl MockGhostChain {
+    /// Start a node on a free local port, producing a block every `block_time`
+    pub async fn start(block_time: Duration) -> Result<Self> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| EtherlinkError::Network(format!("Failed to bind mock chain: {}", e)))?;
+        let addr = listener.local_addr().map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        let state = Arc::new(Mutex::new(ChainState {
+            block_time,
+            last_block: Instant::now(),
+            height: 0,
+            balances: HashMap::new(),
+            next_nonces: HashMap::new(),
+            domains: HashMap::new(),
+            tokens: HashMap::new(),
+            pending: Vec::new(),
+            receipts: HashMap::new(),
+        }));
+        let shared = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = shared.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request))) }
+        });
+
+        let incoming = hyper::server::conn::AddrIncoming::from_listener(listener)
+            .map_err(|e| EtherlinkError::Network(format!("Failed to start mock chain: {}", e)))?;
+        let (shutdown, stopped) = oneshot::channel();
+        let server = hyper::Server::builder(incoming)
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            });
+        let task = tokio::spawn(async move {
+            if let Err(e) = server.await {
+                warn!("Mock chain failed: {}", e);
+            }
+        });
+
+        debug!("Mock GhostChain listening on {}", addr);
+        Ok(Self { addr, state, shutdown: Some(shutdown), task })
+    }
+
+    /// Base URL of the node, e.g. `http://127.0.0.1:40123`
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Configuration pointing GHOSTD and CNS at this node
+    pub fn config(&self) -> EtherlinkConfig {
+        EtherlinkConfig {
+            ghostd_endpoint: self.endpoint(),
+            cns_endpoint: Some(self.endpoint()),
+            enable_tls: false,
+            ..Default::default()
+        }
+    }
+
+    /// Credit an account
+    pub fn fund(&self, address: &Address, amount: u64) {
+        *self.state.lock().unwrap().balances.entry(address.clone()).or_default() += amount;
+    }
+
+    /// Credit every dev account with [`DEV_ACCOUNT_BALANCE`]
+    pub fn fund_dev_accounts(&self, accounts: &DevAccounts) {
+        for account in accounts.iter() {
+            self.fund(&account.address(), DEV_ACCOUNT_BALANCE);
+        }
+    }
+
+    /// Register a domain resolving to `owner`
+    pub fn register_domain(&self, domain: &str, owner: &Address) {
+        self.state.lock().unwrap().domains.insert(domain.to_string(), owner.clone());
+    }
+
+    /// Confirmed balance of an account
+    pub fn balance(&self, address: &Address) -> u64 {
+        self.state.lock().unwrap().balances.get(address).copied().unwrap_or(0)
+    }
+
+    /// Current block height
+    pub fn height(&self) -> u64 {
+        self.state.lock().unwrap().height
+    }
+
+    /// Stop accepting requests and wait for in-flight ones to finish
+    pub async fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for MockGhostChain {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl ChainState {
+    /// Produce a block with the pending transactions if one is due
+    fn mine_due(&mut self) {
+        if self.pending.is_empty() || self.last_block.elapsed() < self.block_time {
+            return;
+        }
+        self.height += 1;
+        self.last_block = Instant::now();
+        for (tx_hash, tx) in std::mem::take(&mut self.pending) {
+            let cost = tx.amount.saturating_add(TRANSFER_GAS.saturating_mul(tx.gas_price));
+            let balance = self.balances.get(&tx.from).copied().unwrap_or(0);
+            let error = if balance < cost {
+                Some(format!("insufficient balance: {} < {}", balance, cost))
+            } else {
+                self.balances.insert(tx.from.clone(), balance - cost);
+                *self.balances.entry(tx.to.clone()).or_default() += tx.amount;
+                None
+            };
+            let receipt = TransactionReceipt {
+                tx_hash: tx_hash.clone(),
+                status: if error.is_none() { "success" } else { "failed" }.to_string(),
+                block_height: Some(self.height),
+                gas_used: TRANSFER_GAS,
+                error,
+            };
+            self.receipts.insert(tx_hash, receipt);
+        }
+    }
+
+    fn submit(&mut self, bearer: Option<&str>, tx: Transaction) -> std::result::Result<TransactionResponse, String> {
+        let authorized = bearer
+            .and_then(|token| self.tokens.get(token))
+            .is_some_and(|permissions| permissions.contains(&Permission::SubmitTransaction));
+        if !authorized {
+            return Err("missing Guardian token with SubmitTransaction permission".to_string());
+        }
+        if tx.signature.as_deref().is_none_or(str::is_empty) {
+            return Err("transaction is not signed".to_string());
+        }
+        if tx.gas_limit < TRANSFER_GAS {
+            return Err(format!("gas limit {} below transfer cost {}", tx.gas_limit, TRANSFER_GAS));
+        }
+        let expected = self.next_nonces.get(&tx.from).copied().unwrap_or(0);
+        if tx.nonce != expected {
+            return Err(format!("invalid nonce {}, expected {}", tx.nonce, expected));
+        }
+
+        let tx_hash = tx.hash().as_str().to_string();
+        self.next_nonces.insert(tx.from.clone(), expected + 1);
+        self.receipts.insert(tx_hash.clone(), TransactionReceipt {
+            tx_hash: tx_hash.clone(),
+            status: "pending".to_string(),
+            block_height: None,
+            gas_used: 0,
+            error: None,
+        });
+        self.pending.push((tx_hash.clone(), tx));
+        Ok(TransactionResponse { tx_hash, status: "pending".to_string() })
+    }
+
+    fn issue_token(&mut self, request: GuardianTokenRequest) -> AccessToken {
+        let token_id = uuid::Uuid::new_v4().to_string();
+        let issued_at = chrono::Utc::now().timestamp() as u64;
+        self.tokens.insert(token_id.clone(), request.permissions.clone());
+        AccessToken {
+            signature: hex::encode(Hasher::domain_digest(HashAlgorithm::Sha256, HashDomain::Auth, token_id.as_bytes())),
+            token_id,
+            identity: request.identity,
+            permissions: request.permissions,
+            issued_at,
+            expires_at: issued_at + request.duration_seconds.unwrap_or(3600),
+        }
+    }
+
+    fn resolve(&self, domain: &str) -> Option<DomainResolution> {
+        let owner = self.domains.get(domain)?;
+        let now = chrono::Utc::now().timestamp() as u64;
+        Some(DomainResolution {
+            domain: domain.to_string(),
+            owner: owner.clone(),
+            records: DomainRecords {
+                addresses: HashMap::from([("ghostchain".to_string(), owner.as_str().to_string())]),
+                content_hash: None,
+                text_records: HashMap::new(),
+                avatar: None,
+                website: None,
+                email: None,
+                description: None,
+            },
+            expires_at: now + 365 * 24 * 3600,
+            created_at: now,
+            last_updated: now,
+            resolver: "mock".to_string(),
+        })
+    }
+}
+
+async fn handle(state: Arc<Mutex<ChainState>>, request: Request<Body>) -> std::result::Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().trim_start_matches("/api/v1").to_string();
+    let bearer = request
+        .headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(e) => return Ok(error(StatusCode::BAD_REQUEST, &e.to_string())),
+    };
+
+    let mut state = state.lock().unwrap();
+    state.mine_due();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let response = match (&method, segments.as_slice()) {
+        (&Method::GET, ["health"]) => ok(serde_json::json!({ "status": "healthy" })),
+        (&Method::GET, ["blockchain", "height"]) => ok(HeightResponse { height: state.height }),
+        (&Method::GET, ["accounts", address, "balance"]) => {
+            let address = Address::new(address.to_string());
+            let balance = state.balances.get(&address).copied().unwrap_or(0);
+            ok(BalanceResponse { balance, address: address.as_str().to_string() })
+        }
+        (&Method::POST, ["guardian", "tokens"]) => match serde_json::from_slice::<GuardianTokenRequest>(&body) {
+            Ok(request) => ok(state.issue_token(request)),
+            Err(e) => error(StatusCode::BAD_REQUEST, &e.to_string()),
+        },
+        (&Method::GET, ["domains", "resolve", domain]) => match state.resolve(domain) {
+            Some(resolution) => ok(resolution),
+            None => error(StatusCode::NOT_FOUND, &format!("domain not found: {}", domain)),
+        },
+        (&Method::POST, ["transactions"]) => match serde_json::from_slice::<Transaction>(&body) {
+            Ok(tx) => match state.submit(bearer.as_deref(), tx) {
+                Ok(response) => ok(response),
+                Err(message) => error(StatusCode::BAD_REQUEST, &message),
+            },
+            Err(e) => error(StatusCode::BAD_REQUEST, &e.to_string()),
+        },
+        (&Method::GET, ["transactions", tx_hash, "receipt"]) => match state.receipts.get(*tx_hash) {
+            Some(receipt) => ok(receipt.clone()),
+            None => error(StatusCode::NOT_FOUND, &format!("unknown transaction: {}", tx_hash)),
+        },
+        _ => error(StatusCode::NOT_FOUND, &format!("no mock route for {} {}", method, path)),
+    };
+    Ok(response)
+}
+
+fn ok<T: Serialize>(data: T) -> Response<Body> {
+    json_response(StatusCode::OK, serde_json::json!({ "success": true, "data": data, "error": null }))
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(status, serde_json::json!({ "success": false, "data": null, "error": message }))
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static("application/json"));
+    response
+}
@@ -0,0 +1,214 @@
+//! Clock skew estimation and compensation
+//!
+//! Token expiry and cache TTLs compare server-issued timestamps with the local clock,
+//! so a drifting host expires tokens early or serves stale entries. A [`SkewEstimator`]
+//! learns how far the local clock is from the services': from the `Date` header of
+//! REST responses from the endpoints it [watches](SkewEstimator::watch), and from
+//! explicit time checks such as [`crate::clients::ghostd::GhostdClient::sync_clock`].
+//! It warns once the skew exceeds its threshold, and [`SkewEstimator::clock`] wraps a
+//! clock so that everything reading it sees server time.
+
+use crate::clock::{self, Clock, SharedClock};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Skew tolerated before warning
+pub const DEFAULT_SKEW_WARN_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Recent offset samples the estimate is the median of
+const SKEW_SAMPLES: usize = 16;
+
+/// Estimators sampling response `Date` headers; dropped ones are pruned on the next response
+static ACTIVE: Mutex<Vec<Weak<Estimate>>> = Mutex::new(Vec::new());
+
+#[derive(Debug)]
+struct Estimate {
+    /// Server minus local milliseconds, most recent last
+    samples: Mutex<VecDeque<i64>>,
+    offset_ms: AtomicI64,
+    warn_threshold_ms: AtomicU64,
+    warned: AtomicBool,
+    /// Origins (`scheme://host:port`) whose `Date` headers are sampled
+    origins: Mutex<HashSet<String>>,
+    /// Local clock the offset is measured against
+    local: Mutex<SharedClock>,
+}
+
+/// Estimates the offset between the local clock and the services' clocks
+///
+/// Clones share the same estimate.
+#[derive(Debug, Clone)]
+pub struct SkewEstimator {
+    estimate: Arc<Estimate>,
+}
+
+impl SkewEstimator {
+    /// Start estimating, warning once the skew exceeds `warn_threshold`
+    pub fn new(warn_threshold: Duration) -> Self {
+        let estimate = Arc::new(Estimate {
+            samples: Mutex::new(VecDeque::with_capacity(SKEW_SAMPLES)),
+            offset_ms: AtomicI64::new(0),
+            warn_threshold_ms: AtomicU64::new(warn_threshold.as_millis() as u64),
+            warned: AtomicBool::new(false),
+            origins: Mutex::new(HashSet::new()),
+            local: Mutex::new(clock::system()),
+        });
+        ACTIVE.lock().unwrap().push(Arc::downgrade(&estimate));
+        Self { estimate }
+    }
+
+    /// Measure the offset against `clock` instead of the system clock
+    pub fn with_clock(self, clock: SharedClock) -> Self {
+        *self.estimate.local.lock().unwrap() = clock;
+        self
+    }
+
+    /// Local time in Unix milliseconds, as the offset is measured against
+    pub fn local_millis(&self) -> u64 {
+        self.estimate.local.lock().unwrap().now_millis()
+    }
+
+    /// Sample the `Date` header of every response from `endpoint`'s origin
+    ///
+    /// Only watch services whose clocks are trusted; an endpoint that is not a URL is ignored.
+    pub fn watch(&self, endpoint: &str) {
+        if let Some(origin) = origin(endpoint) {
+            self.estimate.origins.lock().unwrap().insert(origin);
+        }
+    }
+
+    /// Stop sampling `endpoint`'s origin, e.g. once a reload moves the service elsewhere
+    pub fn unwatch(&self, endpoint: &str) {
+        if let Some(origin) = origin(endpoint) {
+            self.estimate.origins.lock().unwrap().remove(&origin);
+        }
+    }
+
+    /// Record a server reading of `server_ms`, taken by a request sent at local time
+    /// `sent_ms` and answered at `received_ms`
+    ///
+    /// The server is assumed to have read its clock halfway through the round trip.
+    pub fn observe(&self, server_ms: u64, sent_ms: u64, received_ms: u64) {
+        let midpoint = sent_ms / 2 + received_ms / 2;
+        let sample = server_ms as i64 - midpoint as i64;
+        let offset = {
+            let mut samples = self.estimate.samples.lock().unwrap();
+            if samples.len() == SKEW_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(sample);
+            let mut sorted: Vec<i64> = samples.iter().copied().collect();
+            sorted.sort_unstable();
+            sorted[sorted.len() / 2]
+        };
+        self.estimate.offset_ms.store(offset, Ordering::Relaxed);
+        self.check_threshold(offset);
+    }
+
+    /// Server minus local clock in milliseconds; 0 until a sample arrives
+    pub fn offset_ms(&self) -> i64 {
+        self.estimate.offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// Samples the estimate is currently based on
+    pub fn samples(&self) -> usize {
+        self.estimate.samples.lock().unwrap().len()
+    }
+
+    /// Whether the skew currently exceeds the warning threshold
+    pub fn is_skewed(&self) -> bool {
+        self.offset_ms().unsigned_abs() > self.estimate.warn_threshold_ms.load(Ordering::Relaxed)
+    }
+
+    /// Change the warning threshold, e.g. on a configuration reload
+    pub fn set_warn_threshold(&self, threshold: Duration) {
+        self.estimate.warn_threshold_ms.store(threshold.as_millis() as u64, Ordering::Relaxed);
+        self.check_threshold(self.offset_ms());
+    }
+
+    /// `inner` corrected by the estimated offset, for expiry checks against server time
+    pub fn clock(&self, inner: SharedClock) -> SharedClock {
+        Arc::new(SkewedClock { inner, estimator: self.clone() })
+    }
+
+    /// Warn when the skew crosses the threshold, and note when it is back within it
+    fn check_threshold(&self, offset: i64) {
+        let skewed = offset.unsigned_abs() > self.estimate.warn_threshold_ms.load(Ordering::Relaxed);
+        if self.estimate.warned.swap(skewed, Ordering::Relaxed) == skewed {
+            return;
+        }
+        let direction = if offset < 0 { "ahead of" } else { "behind" };
+        if skewed {
+            warn!(
+                "Local clock is {} ms {} the services; expiry checks are compensated, but synchronize it with NTP",
+                offset.unsigned_abs(),
+                direction
+            );
+        } else {
+            info!("Local clock is back within {} ms of the services", offset.unsigned_abs());
+        }
+    }
+}
+
+impl Default for SkewEstimator {
+    fn default() -> Self {
+        Self::new(DEFAULT_SKEW_WARN_THRESHOLD)
+    }
+}
+
+/// A clock shifted by a [`SkewEstimator`]'s current offset
+#[derive(Debug)]
+pub struct SkewedClock {
+    inner: SharedClock,
+    estimator: SkewEstimator,
+}
+
+impl Clock for SkewedClock {
+    fn now_millis(&self) -> u64 {
+        self.inner.now_millis().saturating_add_signed(self.estimator.offset_ms())
+    }
+}
+
+fn origin(url: &str) -> Option<String> {
+    reqwest::Url::parse(url).ok().map(|url| url.origin().ascii_serialization())
+}
+
+/// Feed the `Date` header of a response from `url` to every live estimator watching it
+///
+/// The header has one-second resolution, so the server time is taken as the middle
+/// of that second. `sent_ms` and `received_ms` are system time, and are moved onto
+/// each estimator's local clock.
+pub(crate) fn observe_date_header(url: &str, date: &str, sent_ms: u64, received_ms: u64) {
+    let Some(origin) = origin(url) else {
+        return;
+    };
+    let estimates: Vec<Arc<Estimate>> = {
+        let mut active = ACTIVE.lock().unwrap();
+        active.retain(|estimate| estimate.strong_count() > 0);
+        active
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|estimate| estimate.origins.lock().unwrap().contains(&origin))
+            .collect()
+    };
+    if estimates.is_empty() {
+        return;
+    }
+    let Ok(date) = chrono::DateTime::parse_from_rfc2822(date) else {
+        return;
+    };
+    let server_ms = date.timestamp_millis().max(0) as u64 + 500;
+    for estimate in estimates {
+        let estimator = SkewEstimator { estimate };
+        let shift = estimator.local_millis() as i64 - clock::SystemClock.now_millis() as i64;
+        estimator.observe(server_ms, sent_ms.saturating_add_signed(shift), received_ms.saturating_add_signed(shift));
+    }
+}
+
+/// Whether any estimator is sampling, so callers can skip reading the header
+pub(crate) fn is_sampling() -> bool {
+    ACTIVE.lock().unwrap().iter().any(|estimate| estimate.strong_count() > 0)
+}
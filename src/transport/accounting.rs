@@ -0,0 +1,385 @@
+//! Per-service bandwidth accounting, soft quotas and rate limits
+//!
+//! [`TransportStats`] only aggregates per transport. [`AccountingTransport`] wraps any
+//! transport and attributes every request to the service it targets, so usage can be
+//! tracked against metered gateway plans. REST calls made by the service clients are
+//! accounted too: each ledger counts the calls to the endpoints it has a
+//! [route](BandwidthAccounting::route) for, and holds them to that service's rate limit.
+
+use crate::Result;
+use crate::transport::{ContentType, Transport, TransportStats};
+use crate::validation::{ConfigErrors, Validator};
+use async_trait::async_trait;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// Capacity of the quota event channel
+const QUOTA_EVENT_CAPACITY: usize = 64;
+
+/// Ledgers accounting REST calls; dropped ones are pruned on the next request
+static ACTIVE: Mutex<Vec<Weak<Ledger>>> = Mutex::new(Vec::new());
+
+/// Usage recorded for a single service
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServiceUsage {
+    pub requests: u64,
+    pub failed_requests: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl ServiceUsage {
+    /// Total bytes in both directions
+    pub fn total_bytes(&self) -> u64 {
+        self.bytes_sent + self.bytes_received
+    }
+}
+
+/// Soft limits for one accounting period; exceeding them only emits warnings and events
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceQuota {
+    pub max_requests: Option<u64>,
+    pub max_bytes: Option<u64>,
+}
+
+/// Hard request rate for one service; requests over it wait for capacity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    /// Requests allowed back to back after a quiet period
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+impl RateLimit {
+    /// Check the rate and burst are usable
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(
+            self.requests_per_second.is_finite() && self.requests_per_second > 0.0,
+            "requests_per_second",
+            "must be greater than zero",
+        );
+        v.check(self.burst > 0, "burst", "must be greater than zero");
+        v.finish()
+    }
+}
+
+/// Token bucket enforcing a [`RateLimit`]
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Self { tokens: limit.burst as f64, limit, updated: Instant::now() }
+    }
+
+    /// Take a token, or return how long until one is available
+    fn take(&mut self) -> std::result::Result<(), Duration> {
+        let now = Instant::now();
+        let refill = now.duration_since(self.updated).as_secs_f64() * self.limit.requests_per_second;
+        self.tokens = (self.tokens + refill).min(self.limit.burst as f64);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.limit.requests_per_second))
+        }
+    }
+}
+
+/// Which quota limit was crossed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaKind {
+    Requests,
+    Bytes,
+}
+
+/// Emitted the first time a service exceeds a quota within a period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaEvent {
+    pub service: String,
+    pub kind: QuotaKind,
+    pub limit: u64,
+    pub used: u64,
+}
+
+#[derive(Debug, Default)]
+struct ServiceAccount {
+    usage: ServiceUsage,
+    quota: Option<ServiceQuota>,
+    rate: Option<Bucket>,
+    requests_exceeded: bool,
+    bytes_exceeded: bool,
+}
+
+#[derive(Debug, Default)]
+struct AccountingState {
+    accounts: HashMap<String, ServiceAccount>,
+    /// Endpoint prefixes mapped to service names
+    routes: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+struct Ledger {
+    state: Mutex<AccountingState>,
+    events: broadcast::Sender<QuotaEvent>,
+}
+
+/// Shared per-service usage ledger
+#[derive(Debug, Clone)]
+pub struct BandwidthAccounting {
+    ledger: Arc<Ledger>,
+}
+
+impl BandwidthAccounting {
+    /// Create an empty ledger
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(QUOTA_EVENT_CAPACITY);
+        let ledger = Arc::new(Ledger {
+            state: Mutex::new(AccountingState::default()),
+            events,
+        });
+        ACTIVE.lock().unwrap().push(Arc::downgrade(&ledger));
+        Self { ledger }
+    }
+
+    /// Attribute requests to endpoints starting with `prefix` to `service`
+    pub fn route(&self, prefix: impl Into<String>, service: impl Into<String>) {
+        let mut state = self.ledger.state.lock().unwrap();
+        state.routes.push((prefix.into(), service.into()));
+        // Longest prefix wins
+        state.routes.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    }
+
+    /// Remove every route, e.g. before re-routing changed endpoints
+    pub fn clear_routes(&self) {
+        self.ledger.state.lock().unwrap().routes.clear();
+    }
+
+    /// Service an endpoint is explicitly routed to
+    fn routed_service(&self, endpoint: &str) -> Option<String> {
+        let state = self.ledger.state.lock().unwrap();
+        state
+            .routes
+            .iter()
+            .find(|(prefix, _)| endpoint.starts_with(prefix.as_str()))
+            .map(|(_, service)| service.clone())
+    }
+
+    /// Service name an endpoint is accounted under
+    ///
+    /// Falls back to the endpoint's authority when no route matches.
+    pub fn service_for(&self, endpoint: &str) -> String {
+        if let Some(service) = self.routed_service(endpoint) {
+            return service;
+        }
+
+        let rest = endpoint.split_once("://").map(|(_, rest)| rest).unwrap_or(endpoint);
+        rest.split('/').next().unwrap_or(rest).to_string()
+    }
+
+    /// Set a soft quota for a service
+    pub fn set_quota(&self, service: impl Into<String>, quota: ServiceQuota) {
+        let mut state = self.ledger.state.lock().unwrap();
+        state.accounts.entry(service.into()).or_default().quota = Some(quota);
+    }
+
+    /// Set or, with `None`, remove a service's rate limit
+    ///
+    /// Replacing a limit starts the service with a full burst under the new one.
+    pub fn set_rate_limit(&self, service: impl Into<String>, limit: Option<RateLimit>) {
+        let mut state = self.ledger.state.lock().unwrap();
+        state.accounts.entry(service.into()).or_default().rate = limit.map(Bucket::new);
+    }
+
+    /// Wait until the service's rate limit allows another request
+    pub async fn acquire(&self, service: &str) {
+        loop {
+            let wait = {
+                let mut state = self.ledger.state.lock().unwrap();
+                match state.accounts.get_mut(service).and_then(|account| account.rate.as_mut()) {
+                    Some(bucket) => match bucket.take() {
+                        Ok(()) => return,
+                        Err(wait) => wait,
+                    },
+                    None => return,
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Record one request against a service
+    pub fn record(&self, service: &str, bytes_sent: u64, bytes_received: u64, success: bool) {
+        let mut exceeded = Vec::new();
+
+        {
+            let mut state = self.ledger.state.lock().unwrap();
+            let account = state.accounts.entry(service.to_string()).or_default();
+            account.usage.requests += 1;
+            account.usage.bytes_sent += bytes_sent;
+            account.usage.bytes_received += bytes_received;
+            if !success {
+                account.usage.failed_requests += 1;
+            }
+
+            if let Some(quota) = account.quota.clone() {
+                if let Some(limit) = quota.max_requests
+                    && account.usage.requests > limit
+                    && !account.requests_exceeded
+                {
+                    account.requests_exceeded = true;
+                    exceeded.push((QuotaKind::Requests, limit, account.usage.requests));
+                }
+                if let Some(limit) = quota.max_bytes
+                    && account.usage.total_bytes() > limit
+                    && !account.bytes_exceeded
+                {
+                    account.bytes_exceeded = true;
+                    exceeded.push((QuotaKind::Bytes, limit, account.usage.total_bytes()));
+                }
+            }
+        }
+
+        for (kind, limit, used) in exceeded {
+            warn!("Service {} exceeded {:?} quota: {} > {}", service, kind, used, limit);
+            let _ = self.ledger.events.send(QuotaEvent {
+                service: service.to_string(),
+                kind,
+                limit,
+                used,
+            });
+        }
+    }
+
+    /// Usage for one service
+    pub fn usage(&self, service: &str) -> ServiceUsage {
+        self.ledger
+            .state
+            .lock()
+            .unwrap()
+            .accounts
+            .get(service)
+            .map(|account| account.usage.clone())
+            .unwrap_or_default()
+    }
+
+    /// Usage for all services
+    pub fn snapshot(&self) -> HashMap<String, ServiceUsage> {
+        self.ledger
+            .state
+            .lock()
+            .unwrap()
+            .accounts
+            .iter()
+            .map(|(service, account)| (service.clone(), account.usage.clone()))
+            .collect()
+    }
+
+    /// Start a new accounting period, keeping quotas, rate limits and routes
+    pub fn reset(&self) {
+        let mut state = self.ledger.state.lock().unwrap();
+        for account in state.accounts.values_mut() {
+            account.usage = ServiceUsage::default();
+            account.requests_exceeded = false;
+            account.bytes_exceeded = false;
+        }
+    }
+
+    /// Subscribe to quota events
+    pub fn subscribe(&self) -> broadcast::Receiver<QuotaEvent> {
+        self.ledger.events.subscribe()
+    }
+}
+
+/// Wait for the rate limit of every live ledger routing `url`, returning those ledgers
+/// with the service each attributes the call to so the caller can [record] it
+///
+/// [record]: BandwidthAccounting::record
+pub(crate) async fn acquire_routed(url: &str) -> Vec<(BandwidthAccounting, String)> {
+    let ledgers: Vec<BandwidthAccounting> = {
+        let mut active = ACTIVE.lock().unwrap();
+        active.retain(|ledger| ledger.strong_count() > 0);
+        active.iter().filter_map(Weak::upgrade).map(|ledger| BandwidthAccounting { ledger }).collect()
+    };
+    let routed: Vec<(BandwidthAccounting, String)> = ledgers
+        .into_iter()
+        .filter_map(|accounting| accounting.routed_service(url).map(|service| (accounting, service)))
+        .collect();
+    for (accounting, service) in &routed {
+        accounting.acquire(service).await;
+    }
+    routed
+}
+
+impl Default for BandwidthAccounting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Transport wrapper recording per-service usage into a [`BandwidthAccounting`] ledger
+///
+/// Requests to a rate-limited service wait for [`BandwidthAccounting::acquire`] first.
+#[derive(Debug, Clone)]
+pub struct AccountingTransport<T> {
+    inner: T,
+    accounting: BandwidthAccounting,
+}
+
+impl<T: Transport> AccountingTransport<T> {
+    /// Wrap a transport
+    pub fn new(inner: T, accounting: BandwidthAccounting) -> Self {
+        Self { inner, accounting }
+    }
+
+    /// Get the accounting ledger
+    pub fn accounting(&self) -> &BandwidthAccounting {
+        &self.accounting
+    }
+
+    /// Get the wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<T: Transport> Transport for AccountingTransport<T> {
+    async fn send_request(&self, endpoint: &str, content_type: ContentType, body: Bytes) -> Result<Bytes> {
+        let service = self.accounting.service_for(endpoint);
+        let sent = body.len() as u64;
+
+        self.accounting.acquire(&service).await;
+        let result = self.inner.send_request(endpoint, content_type, body).await;
+        match &result {
+            Ok(response) => self.accounting.record(&service, sent, response.len() as u64, true),
+            Err(_) => self.accounting.record(&service, sent, 0, false),
+        }
+
+        result
+    }
+
+    async fn health_check(&self, endpoint: &str) -> Result<()> {
+        self.inner.health_check(endpoint).await
+    }
+
+    async fn get_stats(&self) -> Result<TransportStats> {
+        self.inner.get_stats().await
+    }
+}
@@ -0,0 +1,385 @@
+//! Shared gRPC channel management
+//!
+//! Tonic channels multiplex many requests over one HTTP/2 connection, so every
+//! gRPC-based client talking to the same endpoint should share one channel
+//! instead of dialing its own.
+
+use crate::events::{EtherlinkEvent, EventBus};
+use crate::transport::dial::HostOverrides;
+use crate::transport::proxy::ProxyConfig;
+use crate::transport::uds::{LocalEndpoint, LocalStream};
+use crate::{EtherlinkError, Result};
+use hyper_util::rt::TokioIo;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::RwLock;
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tracing::{debug, info, warn};
+
+/// Placeholder authority for channels dialed over a local socket
+const LOCAL_AUTHORITY: &str = "http://localhost";
+
+/// Configuration for shared gRPC channels
+#[derive(Debug, Clone)]
+pub struct ChannelConfig {
+    pub enable_tls: bool,
+    pub connect_timeout_ms: u64,
+    pub request_timeout_ms: u64,
+    pub keepalive_interval_ms: u64,
+    /// Maximum number of concurrent requests (streams) per channel
+    pub max_concurrent_streams: Option<usize>,
+    /// Consecutive failures before a channel is considered unhealthy and redialed
+    pub max_failures: u32,
+    pub proxy: Option<ProxyConfig>,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            enable_tls: true,
+            connect_timeout_ms: 10000,
+            request_timeout_ms: 30000,
+            keepalive_interval_ms: 30000,
+            max_concurrent_streams: Some(100),
+            max_failures: 3,
+            proxy: None,
+        }
+    }
+}
+
+impl From<&crate::EtherlinkConfig> for ChannelConfig {
+    fn from(config: &crate::EtherlinkConfig) -> Self {
+        Self {
+            enable_tls: config.enable_tls,
+            request_timeout_ms: config.timeout_ms,
+            proxy: config.proxy.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Cached channel with health bookkeeping
+#[derive(Debug, Clone)]
+struct ChannelEntry {
+    channel: Channel,
+    created_at: Instant,
+    consecutive_failures: u32,
+}
+
+/// Per-endpoint channel statistics
+#[derive(Debug, Clone)]
+pub struct ChannelInfo {
+    pub endpoint: String,
+    pub age: Duration,
+    pub consecutive_failures: u32,
+    pub healthy: bool,
+}
+
+/// Caches tonic channels per endpoint and shares them between clients
+///
+/// Channels are created lazily: the connection is only established on first use and
+/// tonic transparently reconnects it afterwards. Clients report failures through
+/// [`ChannelManager::report_failure`]; once an endpoint exceeds the configured
+/// failure budget its channel is dropped and redialed on next use.
+#[derive(Debug, Clone)]
+pub struct ChannelManager {
+    config: ChannelConfig,
+    channels: Arc<RwLock<HashMap<String, ChannelEntry>>>,
+    events: Option<EventBus>,
+    overrides: Option<HostOverrides>,
+}
+
+impl ChannelManager {
+    /// Create a new channel manager
+    pub fn new(config: ChannelConfig) -> Self {
+        Self {
+            config,
+            channels: Arc::new(RwLock::new(HashMap::new())),
+            events: None,
+            overrides: None,
+        }
+    }
+
+    /// Dial hosts found in `overrides` at their registered addresses
+    ///
+    /// The endpoint keeps its hostname for TLS; only the TCP connection is redirected.
+    pub fn with_host_overrides(mut self, overrides: HostOverrides) -> Self {
+        self.overrides = Some(overrides);
+        self
+    }
+
+    /// Publish [`EtherlinkEvent::ConnectionStatusChanged`] as channels connect and change health
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn publish_status(&self, endpoint: &str, healthy: bool) {
+        if let Some(events) = &self.events {
+            events.publish(EtherlinkEvent::ConnectionStatusChanged { endpoint: endpoint.to_string(), healthy });
+        }
+    }
+
+    /// Create a channel manager with default configuration
+    pub fn with_defaults() -> Self {
+        Self::new(ChannelConfig::default())
+    }
+
+    /// Get a shared channel for an endpoint, creating it if needed
+    pub async fn get_channel(&self, endpoint: &str) -> Result<Channel> {
+        {
+            let channels = self.channels.read().await;
+            if let Some(entry) = channels.get(endpoint)
+                && entry.consecutive_failures < self.config.max_failures
+            {
+                return Ok(entry.channel.clone());
+            }
+        }
+
+        let mut channels = self.channels.write().await;
+
+        // Another task may have replaced the channel while we waited for the lock
+        let redial = match channels.get(endpoint) {
+            Some(entry) if entry.consecutive_failures < self.config.max_failures => return Ok(entry.channel.clone()),
+            Some(_) => {
+                warn!("Redialing unhealthy channel to {}", endpoint);
+                true
+            }
+            None => false,
+        };
+
+        let channel = self.dial_lazy(endpoint)?;
+        channels.insert(endpoint.to_string(), ChannelEntry {
+            channel: channel.clone(),
+            created_at: Instant::now(),
+            consecutive_failures: 0,
+        });
+        drop(channels);
+
+        // Subscribers saw the channel go unhealthy; tell them it was replaced
+        if redial {
+            self.publish_status(endpoint, true);
+        }
+        debug!("Created shared gRPC channel to {}", endpoint);
+        Ok(channel)
+    }
+
+    /// Eagerly connect to an endpoint and cache the resulting channel
+    pub async fn connect(&self, endpoint: &str) -> Result<Channel> {
+        info!("Connecting shared gRPC channel to {}", endpoint);
+        let channel = if LocalEndpoint::is_local(endpoint) {
+            let (local, _) = LocalEndpoint::parse(endpoint)?;
+            self.build_endpoint(LOCAL_AUTHORITY, false)?
+                .connect_with_connector(local_connector(local))
+                .await?
+        } else if let Some(proxy) = self.proxy_for(endpoint)? {
+            self.build_endpoint(endpoint, self.config.enable_tls)?
+                .connect_with_connector(proxy_connector(proxy, self.overrides.clone()))
+                .await?
+        } else if let Some(overrides) = self.overrides_for(endpoint)? {
+            self.build_endpoint(endpoint, self.config.enable_tls)?
+                .connect_with_connector(override_connector(overrides))
+                .await?
+        } else {
+            self.build_endpoint(endpoint, self.config.enable_tls)?.connect().await?
+        };
+
+        self.channels.write().await.insert(endpoint.to_string(), ChannelEntry {
+            channel: channel.clone(),
+            created_at: Instant::now(),
+            consecutive_failures: 0,
+        });
+        self.publish_status(endpoint, true);
+
+        Ok(channel)
+    }
+
+    /// Record a successful call on an endpoint's channel
+    pub async fn report_success(&self, endpoint: &str) {
+        let recovered = match self.channels.write().await.get_mut(endpoint) {
+            Some(entry) => {
+                let unhealthy = entry.consecutive_failures >= self.config.max_failures;
+                entry.consecutive_failures = 0;
+                unhealthy
+            }
+            None => false,
+        };
+        if recovered {
+            self.publish_status(endpoint, true);
+        }
+    }
+
+    /// Record a failed call on an endpoint's channel
+    pub async fn report_failure(&self, endpoint: &str) {
+        let failed = match self.channels.write().await.get_mut(endpoint) {
+            Some(entry) => {
+                entry.consecutive_failures += 1;
+                entry.consecutive_failures == self.config.max_failures
+            }
+            None => false,
+        };
+        if failed {
+            warn!("Channel to {} marked unhealthy after {} failures", endpoint, self.config.max_failures);
+            self.publish_status(endpoint, false);
+        }
+    }
+
+    /// Drop the cached channel for an endpoint
+    pub async fn evict(&self, endpoint: &str) -> bool {
+        self.channels.write().await.remove(endpoint).is_some()
+    }
+
+    /// Drop all cached channels
+    pub async fn clear(&self) {
+        self.channels.write().await.clear();
+    }
+
+    /// Get information about all cached channels
+    pub async fn channel_info(&self) -> Vec<ChannelInfo> {
+        self.channels
+            .read()
+            .await
+            .iter()
+            .map(|(endpoint, entry)| ChannelInfo {
+                endpoint: endpoint.clone(),
+                age: entry.created_at.elapsed(),
+                consecutive_failures: entry.consecutive_failures,
+                healthy: entry.consecutive_failures < self.config.max_failures,
+            })
+            .collect()
+    }
+
+    /// Get the channel configuration
+    pub fn config(&self) -> &ChannelConfig {
+        &self.config
+    }
+
+    fn dial_lazy(&self, endpoint: &str) -> Result<Channel> {
+        if LocalEndpoint::is_local(endpoint) {
+            let (local, _) = LocalEndpoint::parse(endpoint)?;
+            return Ok(self.build_endpoint(LOCAL_AUTHORITY, false)?.connect_with_connector_lazy(local_connector(local)));
+        }
+
+        if let Some(proxy) = self.proxy_for(endpoint)? {
+            let connector = proxy_connector(proxy, self.overrides.clone());
+            return Ok(self.build_endpoint(endpoint, self.config.enable_tls)?.connect_with_connector_lazy(connector));
+        }
+
+        if let Some(overrides) = self.overrides_for(endpoint)? {
+            return Ok(self.build_endpoint(endpoint, self.config.enable_tls)?.connect_with_connector_lazy(override_connector(overrides)));
+        }
+
+        Ok(self.build_endpoint(endpoint, self.config.enable_tls)?.connect_lazy())
+    }
+
+    /// Proxy to use for an endpoint, unless it is on the no-proxy list
+    fn proxy_for(&self, endpoint: &str) -> Result<Option<ProxyConfig>> {
+        let Some(proxy) = &self.config.proxy else {
+            return Ok(None);
+        };
+
+        let uri: tonic::transport::Uri = endpoint
+            .parse()
+            .map_err(|e| EtherlinkError::Configuration(format!("Invalid endpoint {}: {}", endpoint, e)))?;
+        let host = uri.host().unwrap_or_default();
+
+        if proxy.bypasses(host) {
+            Ok(None)
+        } else {
+            proxy.kind()?;
+            Ok(Some(proxy.clone()))
+        }
+    }
+
+    /// Host overrides to dial through, if any cover the endpoint's host
+    fn overrides_for(&self, endpoint: &str) -> Result<Option<HostOverrides>> {
+        let Some(overrides) = &self.overrides else {
+            return Ok(None);
+        };
+        let uri: tonic::transport::Uri = endpoint
+            .parse()
+            .map_err(|e| EtherlinkError::Configuration(format!("Invalid endpoint {}: {}", endpoint, e)))?;
+        Ok(uri.host().and_then(|host| overrides.get(host)).map(|_| overrides.clone()))
+    }
+
+    fn build_endpoint(&self, endpoint: &str, enable_tls: bool) -> Result<Endpoint> {
+        let mut builder = Endpoint::from_shared(endpoint.to_string())
+            .map_err(|e| EtherlinkError::Configuration(format!("Invalid endpoint {}: {}", endpoint, e)))?
+            .connect_timeout(Duration::from_millis(self.config.connect_timeout_ms))
+            .timeout(Duration::from_millis(self.config.request_timeout_ms))
+            .tcp_keepalive(Some(Duration::from_millis(self.config.keepalive_interval_ms)))
+            .http2_keep_alive_interval(Duration::from_millis(self.config.keepalive_interval_ms))
+            .keep_alive_while_idle(true);
+
+        if let Some(limit) = self.config.max_concurrent_streams {
+            builder = builder.concurrency_limit(limit);
+        }
+
+        if enable_tls {
+            builder = builder.tls_config(ClientTlsConfig::new())?;
+        }
+
+        Ok(builder)
+    }
+}
+
+/// Connector dialing a Unix socket or named pipe instead of TCP
+fn local_connector(local: LocalEndpoint) -> impl tower::Service<tonic::transport::Uri, Response = TokioIo<LocalStream>, Error = EtherlinkError, Future = impl Send> + Clone + Send + 'static {
+    tower::service_fn(move |_: tonic::transport::Uri| {
+        let local = local.clone();
+        async move { local.connect().await.map(TokioIo::new) }
+    })
+}
+
+/// Connector tunneling through an HTTP CONNECT or SOCKS5 proxy
+///
+/// Overridden hosts are handed to the proxy as their first registered address, since
+/// the proxy cannot resolve them either.
+fn proxy_connector(proxy: ProxyConfig, overrides: Option<HostOverrides>) -> impl tower::Service<tonic::transport::Uri, Response = TokioIo<TcpStream>, Error = EtherlinkError, Future = impl Send> + Clone + Send + 'static {
+    tower::service_fn(move |uri: tonic::transport::Uri| {
+        let proxy = proxy.clone();
+        let overrides = overrides.clone();
+        async move {
+            let (host, port) = host_and_port(&uri)?;
+            let host = overrides
+                .and_then(|overrides| overrides.get(&host))
+                .and_then(|addrs| addrs.first().map(|addr| addr.ip().to_string()))
+                .unwrap_or(host);
+            proxy.connect(&host, port).await.map(TokioIo::new)
+        }
+    })
+}
+
+/// Connector dialing overridden hosts at their registered addresses
+fn override_connector(overrides: HostOverrides) -> impl tower::Service<tonic::transport::Uri, Response = TokioIo<TcpStream>, Error = EtherlinkError, Future = impl Send> + Clone + Send + 'static {
+    tower::service_fn(move |uri: tonic::transport::Uri| {
+        let overrides = overrides.clone();
+        async move {
+            let (host, port) = host_and_port(&uri)?;
+            let addrs = overrides.lookup(&host, port).await?;
+            let stream = TcpStream::connect(addrs.as_slice())
+                .await
+                .map_err(|e| EtherlinkError::Network(format!("Failed to connect to {}: {}", uri, e)))?;
+            Ok(TokioIo::new(stream))
+        }
+    })
+}
+
+fn host_and_port(uri: &tonic::transport::Uri) -> Result<(String, u16)> {
+    let host = uri
+        .host()
+        .ok_or_else(|| EtherlinkError::Configuration(format!("Endpoint {} has no host", uri)))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("https") { 443 } else { 80 });
+    Ok((host, port))
+}
+
+impl Default for ChannelManager {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
@@ -0,0 +1,229 @@
+//! Fault injection for resilience testing
+//!
+//! [`ChaosTransport`] wraps any transport and, according to a [`FaultProfile`],
+//! delays requests, drops them, answers with 5xx errors or resets the connection.
+//! Faults are drawn from a seeded generator, so a failing test can be rerun with
+//! exactly the same sequence of faults. Errors look like the ones [`super::HttpTransport`]
+//! produces for the real failure, so retry and failover logic cannot tell the difference.
+
+use crate::{EtherlinkError, Result};
+use crate::transport::{ContentType, Transport, TransportStats};
+use async_trait::async_trait;
+use bytes::Bytes;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Which faults to inject and how often
+///
+/// Rates are probabilities between 0 and 1, checked in the order drop, reset,
+/// server error; at most one fault is injected per request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultProfile {
+    /// Added to every request
+    pub latency: Duration,
+    /// Random extra latency, up to this much
+    pub jitter: Duration,
+    /// Requests that are never answered and time out after `drop_timeout`
+    pub drop_rate: f64,
+    pub drop_timeout: Duration,
+    /// Requests failing with a connection reset before reaching the server
+    pub reset_rate: f64,
+    /// Requests answered with a 5xx status
+    pub server_error_rate: f64,
+    pub server_error_status: u16,
+    /// Fault sequence seed; the same seed injects the same faults
+    pub seed: u64,
+}
+
+impl Default for FaultProfile {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            drop_rate: 0.0,
+            drop_timeout: Duration::from_secs(1),
+            reset_rate: 0.0,
+            server_error_rate: 0.0,
+            server_error_status: 503,
+            seed: 0,
+        }
+    }
+}
+
+impl FaultProfile {
+    /// A profile injecting no faults
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub fn latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn drops(mut self, rate: f64, timeout: Duration) -> Self {
+        self.drop_rate = rate;
+        self.drop_timeout = timeout;
+        self
+    }
+
+    pub fn resets(mut self, rate: f64) -> Self {
+        self.reset_rate = rate;
+        self
+    }
+
+    pub fn server_errors(mut self, rate: f64, status: u16) -> Self {
+        self.server_error_rate = rate;
+        self.server_error_status = status;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// A fault injected into a single request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    Drop,
+    Reset,
+    ServerError(u16),
+}
+
+/// Counts of injected faults
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    pub requests: u64,
+    pub passed: u64,
+    pub dropped: u64,
+    pub resets: u64,
+    pub server_errors: u64,
+}
+
+/// Transport wrapper injecting faults from a [`FaultProfile`]
+#[derive(Debug)]
+pub struct ChaosTransport<T> {
+    inner: T,
+    profile: Mutex<FaultProfile>,
+    rng: Mutex<StdRng>,
+    requests: AtomicU64,
+    passed: AtomicU64,
+    dropped: AtomicU64,
+    resets: AtomicU64,
+    server_errors: AtomicU64,
+}
+
+impl<T: Transport> ChaosTransport<T> {
+    /// Wrap a transport
+    pub fn new(inner: T, profile: FaultProfile) -> Self {
+        Self {
+            inner,
+            rng: Mutex::new(StdRng::seed_from_u64(profile.seed)),
+            profile: Mutex::new(profile),
+            requests: AtomicU64::new(0),
+            passed: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            resets: AtomicU64::new(0),
+            server_errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Switch to another profile mid-test, e.g. to let a circuit breaker recover
+    ///
+    /// The fault sequence continues from the current generator state.
+    pub fn set_profile(&self, profile: FaultProfile) {
+        *self.profile.lock().unwrap() = profile;
+    }
+
+    /// Counts of injected faults so far
+    pub fn stats(&self) -> ChaosStats {
+        ChaosStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            passed: self.passed.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            resets: self.resets.load(Ordering::Relaxed),
+            server_errors: self.server_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Get the wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Decide the delay and fault for the next request
+    fn draw(&self) -> (Duration, Option<Fault>, Duration) {
+        let profile = self.profile.lock().unwrap().clone();
+        let mut rng = self.rng.lock().unwrap();
+
+        let jitter = if profile.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            profile.jitter.mul_f64(rng.r#gen::<f64>())
+        };
+        let roll: f64 = rng.r#gen();
+        let fault = if roll < profile.drop_rate {
+            Some(Fault::Drop)
+        } else if roll < profile.drop_rate + profile.reset_rate {
+            Some(Fault::Reset)
+        } else if roll < profile.drop_rate + profile.reset_rate + profile.server_error_rate {
+            Some(Fault::ServerError(profile.server_error_status))
+        } else {
+            None
+        };
+        (profile.latency + jitter, fault, profile.drop_timeout)
+    }
+
+    /// Inject the next fault, or return `Ok` if the request should go through
+    async fn inject(&self, endpoint: &str) -> Result<()> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let (delay, fault, drop_timeout) = self.draw();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        match fault {
+            None => {
+                self.passed.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Some(Fault::Drop) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(drop_timeout).await;
+                Err(EtherlinkError::Timeout(format!("Request to {} timed out after {:?}", endpoint, drop_timeout)))
+            }
+            Some(Fault::Reset) => {
+                self.resets.fetch_add(1, Ordering::Relaxed);
+                Err(EtherlinkError::Network(format!("Connection to {} reset by peer", endpoint)))
+            }
+            Some(Fault::ServerError(status)) => {
+                self.server_errors.fetch_add(1, Ordering::Relaxed);
+                let status = reqwest::StatusCode::from_u16(status).unwrap_or(reqwest::StatusCode::SERVICE_UNAVAILABLE);
+                Err(EtherlinkError::Network(format!("HTTP request failed with status: {}", status)))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Transport> Transport for ChaosTransport<T> {
+    async fn send_request(&self, endpoint: &str, content_type: ContentType, body: Bytes) -> Result<Bytes> {
+        self.inject(endpoint).await?;
+        self.inner.send_request(endpoint, content_type, body).await
+    }
+
+    async fn health_check(&self, endpoint: &str) -> Result<()> {
+        self.inject(endpoint).await?;
+        self.inner.health_check(endpoint).await
+    }
+
+    async fn get_stats(&self) -> Result<TransportStats> {
+        self.inner.get_stats().await
+    }
+}
@@ -0,0 +1,111 @@
+//! Wire body encodings supported by the transport layer
+
+use crate::{Result, EtherlinkError};
+use bytes::Bytes;
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Encoding of a request or response body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ContentType {
+    #[default]
+    Json,
+    Protobuf,
+    MessagePack,
+}
+
+impl ContentType {
+    /// MIME type sent in `Content-Type` / `Accept` headers
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ContentType::Json => "application/json",
+            ContentType::Protobuf => "application/x-protobuf",
+            ContentType::MessagePack => "application/msgpack",
+        }
+    }
+
+    /// Single-byte tag identifying the encoding on framed transports such as GQUIC
+    pub fn wire_tag(&self) -> u8 {
+        match self {
+            ContentType::Json => 0,
+            ContentType::Protobuf => 1,
+            ContentType::MessagePack => 2,
+        }
+    }
+
+    /// Parse a wire tag
+    pub fn from_wire_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(ContentType::Json),
+            1 => Some(ContentType::Protobuf),
+            2 => Some(ContentType::MessagePack),
+            _ => None,
+        }
+    }
+
+    /// Parse a MIME type, ignoring parameters such as `charset`
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        let essence = mime.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "application/json" => Some(ContentType::Json),
+            "application/x-protobuf" | "application/protobuf" => Some(ContentType::Protobuf),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(ContentType::MessagePack),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.mime())
+    }
+}
+
+/// Encode a value as JSON
+pub fn encode_json<T: Serialize>(value: &T) -> Result<Bytes> {
+    Ok(Bytes::from(serde_json::to_vec(value)?))
+}
+
+/// Decode a JSON body
+pub fn decode_json<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    Ok(serde_json::from_slice(body)?)
+}
+
+/// Encode a protobuf message
+pub fn encode_protobuf<M: prost::Message>(message: &M) -> Bytes {
+    Bytes::from(message.encode_to_vec())
+}
+
+/// Decode a protobuf body
+pub fn decode_protobuf<M: prost::Message + Default>(body: &[u8]) -> Result<M> {
+    M::decode(body).map_err(|e| EtherlinkError::Codec(format!("Invalid protobuf body: {}", e)))
+}
+
+/// Encode a value as MessagePack, using named fields so it stays compatible with JSON schemas
+pub fn encode_msgpack<T: Serialize>(value: &T) -> Result<Bytes> {
+    rmp_serde::to_vec_named(value)
+        .map(Bytes::from)
+        .map_err(|e| EtherlinkError::Codec(format!("MessagePack encoding failed: {}", e)))
+}
+
+/// Decode a MessagePack body
+pub fn decode_msgpack<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    rmp_serde::from_slice(body).map_err(|e| EtherlinkError::Codec(format!("Invalid MessagePack body: {}", e)))
+}
+
+/// Encode a serde value with a serde-compatible content type
+pub fn encode_serde<T: Serialize>(content_type: ContentType, value: &T) -> Result<Bytes> {
+    match content_type {
+        ContentType::Json => encode_json(value),
+        ContentType::MessagePack => encode_msgpack(value),
+        ContentType::Protobuf => Err(EtherlinkError::Codec("Protobuf bodies require a prost message type".to_string())),
+    }
+}
+
+/// Decode a serde value from a serde-compatible content type
+pub fn decode_serde<T: DeserializeOwned>(content_type: ContentType, body: &[u8]) -> Result<T> {
+    match content_type {
+        ContentType::Json => decode_json(body),
+        ContentType::MessagePack => decode_msgpack(body),
+        ContentType::Protobuf => Err(EtherlinkError::Codec("Protobuf bodies require a prost message type".to_string())),
+    }
+}
@@ -0,0 +1,259 @@
+//! Dual-stack address resolution and happy-eyeballs connection racing (RFC 8305)
+
+use crate::{Result, EtherlinkError};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Which IP family to try first, or exclusively
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressFamilyPreference {
+    /// Try IPv6 first, falling back to IPv4 (RFC 8305 default)
+    #[default]
+    PreferIpv6,
+    /// Try IPv4 first, falling back to IPv6
+    PreferIpv4,
+    Ipv6Only,
+    Ipv4Only,
+}
+
+/// Connection establishment settings
+#[derive(Debug, Clone)]
+pub struct DialConfig {
+    pub family: AddressFamilyPreference,
+    /// Delay before racing the next address while earlier attempts are still pending
+    pub attempt_delay_ms: u64,
+    /// How long a failed address is tried after healthy ones
+    pub failure_memory_ms: u64,
+}
+
+impl Default for DialConfig {
+    fn default() -> Self {
+        Self {
+            family: AddressFamilyPreference::default(),
+            attempt_delay_ms: 250,
+            failure_memory_ms: 60000,
+        }
+    }
+}
+
+/// Resolves endpoints to all their addresses and races connection attempts
+#[derive(Debug, Clone)]
+pub struct Dialer {
+    config: DialConfig,
+    failures: Arc<Mutex<HashMap<SocketAddr, Instant>>>,
+}
+
+impl Dialer {
+    /// Create a new dialer
+    pub fn new(config: DialConfig) -> Self {
+        Self {
+            config,
+            failures: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Resolve a `host:port` endpoint into candidate addresses in dialing order
+    ///
+    /// Families are interleaved starting with the preferred one, and addresses that
+    /// failed recently are moved to the end.
+    pub async fn resolve(&self, endpoint: &str) -> Result<Vec<SocketAddr>> {
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host(endpoint)
+            .await
+            .map_err(|e| EtherlinkError::Network(format!("Failed to resolve {}: {}", endpoint, e)))?
+            .collect();
+
+        let ordered = self.order(addrs);
+        if ordered.is_empty() {
+            return Err(EtherlinkError::Network(format!(
+                "No usable addresses for {} with {:?}",
+                endpoint, self.config.family
+            )));
+        }
+
+        Ok(ordered)
+    }
+
+    /// Apply the family preference and failure memory to a set of addresses
+    pub fn order(&self, addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+        let (v6, v4): (Vec<_>, Vec<_>) = addrs.into_iter().partition(|a| a.is_ipv6());
+
+        let (first, second) = match self.config.family {
+            AddressFamilyPreference::PreferIpv6 => (v6, v4),
+            AddressFamilyPreference::PreferIpv4 => (v4, v6),
+            AddressFamilyPreference::Ipv6Only => (v6, Vec::new()),
+            AddressFamilyPreference::Ipv4Only => (v4, Vec::new()),
+        };
+
+        let mut interleaved = Vec::with_capacity(first.len() + second.len());
+        let mut first = first.into_iter();
+        let mut second = second.into_iter();
+        loop {
+            match (first.next(), second.next()) {
+                (None, None) => break,
+                (a, b) => interleaved.extend(a.into_iter().chain(b)),
+            }
+        }
+
+        // Stable partition keeps the family interleaving within each group
+        let (healthy, failed): (Vec<_>, Vec<_>) = interleaved
+            .into_iter()
+            .partition(|addr| !self.recently_failed(addr));
+        healthy.into_iter().chain(failed).collect()
+    }
+
+    /// Race connection attempts, starting a new one every attempt delay or as soon as one fails
+    ///
+    /// Returns the first successful connection; the remaining attempts are dropped.
+    pub async fn race<T, F, Fut>(&self, addrs: &[SocketAddr], connect: F) -> Result<(SocketAddr, T)>
+    where
+        F: Fn(SocketAddr) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let delay = Duration::from_millis(self.config.attempt_delay_ms);
+        let mut pending = addrs.iter().copied();
+        let mut attempts = FuturesUnordered::new();
+        let mut last_error = None;
+
+        let start = |addr: SocketAddr| {
+            let attempt = connect(addr);
+            async move { (addr, attempt.await) }
+        };
+
+        match pending.next() {
+            Some(addr) => attempts.push(start(addr)),
+            None => return Err(EtherlinkError::Network("No addresses to dial".to_string())),
+        }
+
+        loop {
+            let next_attempt = tokio::time::sleep(delay);
+            tokio::pin!(next_attempt);
+
+            tokio::select! {
+                Some((addr, result)) = attempts.next() => match result {
+                    Ok(conn) => {
+                        self.failures.lock().unwrap().remove(&addr);
+                        debug!("Connected to {}", addr);
+                        return Ok((addr, conn));
+                    }
+                    Err(e) => {
+                        debug!("Connection attempt to {} failed: {}", addr, e);
+                        self.failures.lock().unwrap().insert(addr, Instant::now());
+                        last_error = Some(e);
+                    }
+                },
+                _ = &mut next_attempt => {}
+            }
+
+            if let Some(addr) = pending.next() {
+                attempts.push(start(addr));
+            } else if attempts.is_empty() {
+                return Err(last_error.unwrap_or_else(|| EtherlinkError::Network("All connection attempts failed".to_string())));
+            }
+        }
+    }
+
+    /// Forget all remembered failures
+    pub fn reset_failures(&self) {
+        self.failures.lock().unwrap().clear();
+    }
+
+    /// Get the dial configuration
+    pub fn config(&self) -> &DialConfig {
+        &self.config
+    }
+
+    fn recently_failed(&self, addr: &SocketAddr) -> bool {
+        let memory = Duration::from_millis(self.config.failure_memory_ms);
+        self.failures
+            .lock()
+            .unwrap()
+            .get(addr)
+            .is_some_and(|failed_at| failed_at.elapsed() < memory)
+    }
+}
+
+impl Default for Dialer {
+    fn default() -> Self {
+        Self::new(DialConfig::default())
+    }
+}
+
+/// Addresses to dial for hosts the system resolver does not know, such as `.ghost` names
+///
+/// Endpoints keep their hostname, so TLS still sends it as SNI and checks the
+/// certificate against it; only the TCP connection goes to the addresses registered
+/// here. The runtime's HTTP client consults it through [`reqwest::dns::Resolve`] and
+/// its gRPC channels through [`crate::ChannelManager::with_host_overrides`].
+///
+/// Every lookup, overridden or not, is ordered by the [`Dialer`]'s family preference
+/// and failure memory.
+#[derive(Debug, Clone, Default)]
+pub struct HostOverrides {
+    hosts: Arc<std::sync::RwLock<HashMap<String, Vec<SocketAddr>>>>,
+    dialer: Dialer,
+}
+
+impl HostOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Order addresses with `dialer` instead of the default one
+    pub fn with_dialer(mut self, dialer: Dialer) -> Self {
+        self.dialer = dialer;
+        self
+    }
+
+    /// Dial `addrs` for `host`, replacing earlier addresses
+    pub fn set(&self, host: &str, addrs: Vec<SocketAddr>) {
+        self.hosts.write().unwrap().insert(normalize_host(host), addrs);
+    }
+
+    /// Go back to the system resolver for `host`
+    pub fn remove(&self, host: &str) -> bool {
+        self.hosts.write().unwrap().remove(&normalize_host(host)).is_some()
+    }
+
+    /// Addresses registered for `host`
+    pub fn get(&self, host: &str) -> Option<Vec<SocketAddr>> {
+        self.hosts.read().unwrap().get(&normalize_host(host)).cloned()
+    }
+
+    /// Addresses to dial for `host:port`: the registered ones on `port`, or a system lookup
+    pub async fn lookup(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+        let Some(addrs) = self.get(host) else {
+            let host = normalize_host(host);
+            let endpoint = if host.contains(':') { format!("[{}]:{}", host, port) } else { format!("{}:{}", host, port) };
+            return self.dialer.resolve(&endpoint).await;
+        };
+        let ordered = self.dialer.order(addrs.into_iter().map(|addr| SocketAddr::new(addr.ip(), port)).collect());
+        if ordered.is_empty() {
+            return Err(EtherlinkError::Network(format!(
+                "No usable addresses for {} with {:?}",
+                host,
+                self.dialer.config().family
+            )));
+        }
+        Ok(ordered)
+    }
+}
+
+impl reqwest::dns::Resolve for HostOverrides {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let overrides = self.clone();
+        Box::pin(async move {
+            // reqwest puts the URL's port on the addresses itself
+            let addrs = overrides.lookup(name.as_str(), 0).await?;
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim_start_matches('[').trim_end_matches(']').trim_end_matches('.').to_ascii_lowercase()
+}
@@ -1,14 +1,17 @@
 //! GQUIC transport implementation using the gquic crate
 
 use crate::{Result, EtherlinkError};
-use crate::transport::{Transport, TransportConfig, TransportStats};
+use crate::transport::{ContentType, Transport, TransportConfig, TransportStats};
 use async_trait::async_trait;
+use bytes::Bytes;
 use serde::{Serialize, de::DeserializeOwned};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::RwLock;
 
+#[cfg(feature = "gquic")]
+use crate::transport::Dialer;
 #[cfg(feature = "gquic")]
 use gquic::prelude::*;
 
@@ -19,6 +22,8 @@ pub struct GQuicTransport {
     client: Arc<QuicClient>,
     #[cfg(feature = "gquic")]
     pool: Arc<ConnectionPool>,
+    #[cfg(feature = "gquic")]
+    dialer: Dialer,
     config: TransportConfig,
     stats: Arc<RwLock<TransportStats>>,
 }
@@ -53,6 +58,7 @@ impl GQuicTransport {
             Ok(Self {
                 client: Arc::new(client),
                 pool: Arc::new(pool),
+                dialer: Dialer::new(config.dial.clone()),
                 config,
                 stats: Arc::new(RwLock::new(stats)),
             })
@@ -90,21 +96,20 @@ impl GQuicTransport {
 
 #[async_trait]
 impl Transport for GQuicTransport {
-    async fn send_json_request(&self, endpoint: &str, request: serde_json::Value) -> Result<serde_json::Value> {
+    async fn send_request(&self, endpoint: &str, content_type: ContentType, body: Bytes) -> Result<Bytes> {
         #[cfg(feature = "gquic")]
         {
             let start_time = Instant::now();
 
             // Parse endpoint to socket address
-            let addr: SocketAddr = endpoint.parse()
-                .map_err(|e| EtherlinkError::Configuration(format!("Invalid endpoint: {}", e)))?;
+            // Resolve all A/AAAA records and race connections across them
+            let addrs = self.dialer.resolve(endpoint).await?;
+            let (_, conn) = self.dialer.race(&addrs, |addr| self.get_connection(addr)).await?;
 
-            // Get connection
-            let conn = self.get_connection(addr).await?;
-
-            // Serialize request
-            let request_data = serde_json::to_vec(&request)
-                .map_err(|e| EtherlinkError::Serialization(e))?;
+            // Frame the request with a one-byte content type tag
+            let mut request_data = Vec::with_capacity(body.len() + 1);
+            request_data.push(content_type.wire_tag());
+            request_data.extend_from_slice(&body);
 
             // Open bidirectional stream
             let mut stream = self.client.open_bi_stream(&conn).await
@@ -120,9 +125,17 @@ impl Transport for GQuicTransport {
             let response_data = stream.read_to_end(64 * 1024).await
                 .map_err(|e| EtherlinkError::Network(e.to_string()))?;
 
-            // Deserialize response
-            let response: serde_json::Value = serde_json::from_slice(&response_data)
-                .map_err(|e| EtherlinkError::Serialization(e))?;
+            // The response echoes the request's content type tag
+            let (&tag, payload) = response_data
+                .split_first()
+                .ok_or_else(|| EtherlinkError::Codec("Empty GQUIC response".to_string()))?;
+            if ContentType::from_wire_tag(tag) != Some(content_type) {
+                return Err(EtherlinkError::Codec(format!(
+                    "GQUIC response tagged {} for a {:?} request",
+                    tag, content_type
+                )));
+            }
+            let response = Bytes::copy_from_slice(payload);
 
             // Update stats
             let mut stats = self.stats.write().await;
@@ -145,11 +158,10 @@ impl Transport for GQuicTransport {
     async fn health_check(&self, endpoint: &str) -> Result<()> {
         #[cfg(feature = "gquic")]
         {
-            let addr: SocketAddr = endpoint.parse()
-                .map_err(|e| EtherlinkError::Configuration(format!("Invalid endpoint: {}", e)))?;
+            let addrs = self.dialer.resolve(endpoint).await?;
 
             // Try to establish connection
-            let _conn = self.get_connection(addr).await?;
+            let _conn = self.dialer.race(&addrs, |addr| self.get_connection(addr)).await?;
             Ok(())
         }
 
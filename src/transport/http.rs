@@ -1,13 +1,15 @@
 //! HTTP transport implementation as fallback
 
 use crate::{Result, EtherlinkError};
-use crate::transport::{Transport, TransportConfig, TransportStats};
+use crate::transport::{ContentType, Transport, TransportConfig, TransportStats};
 use async_trait::async_trait;
+use bytes::Bytes;
 use serde::{Serialize, de::DeserializeOwned};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use reqwest::Client;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
 
 /// HTTP transport implementation for standard REST API communication
 #[derive(Debug, Clone)]
@@ -20,9 +22,15 @@ pub struct HttpTransport {
 impl HttpTransport {
     /// Create a new HTTP transport
     pub fn new(config: TransportConfig) -> Result<Self> {
-        let client = Client::builder()
+        let mut builder = Client::builder()
             .timeout(Duration::from_millis(config.timeout_ms))
-            .tcp_keepalive(Duration::from_millis(config.keepalive_interval_ms))
+            .tcp_keepalive(Duration::from_millis(config.keepalive_interval_ms));
+
+        if let Some(proxy) = &config.proxy {
+            builder = proxy.apply(builder)?;
+        }
+
+        let client = builder
             .build()
             .map_err(|e| EtherlinkError::Network(e.to_string()))?;
 
@@ -45,26 +53,26 @@ impl HttpTransport {
 
 #[async_trait]
 impl Transport for HttpTransport {
-    async fn send_json_request(&self, endpoint: &str, request: serde_json::Value) -> Result<serde_json::Value> {
+    async fn send_request(&self, endpoint: &str, content_type: ContentType, body: Bytes) -> Result<Bytes> {
         let start_time = Instant::now();
+        let request_size = body.len() as u64;
 
         // Send HTTP POST request
         let response = self.client
             .post(endpoint)
-            .json(&request)
+            .header(CONTENT_TYPE, content_type.mime())
+            .header(ACCEPT, content_type.mime())
+            .body(body)
             .send()
-            .await
-            .map_err(|e| {
-                // Update failed request stats
-                tokio::spawn({
-                    let stats = self.stats.clone();
-                    async move {
-                        let mut stats = stats.write().await;
-                        stats.failed_requests += 1;
-                    }
-                });
-                EtherlinkError::Network(e.to_string())
-            })?;
+            .await;
+
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                self.stats.write().await.failed_requests += 1;
+                return Err(EtherlinkError::Network(e.to_string()));
+            }
+        };
 
         // Check if request was successful
         if !response.status().is_success() {
@@ -76,19 +84,28 @@ impl Transport for HttpTransport {
             )));
         }
 
-        // Get response size for stats
-        let content_length = response.content_length().unwrap_or(0);
+        // Servers answering in a different encoding than we asked for can't be decoded by the caller
+        if let Some(returned) = response.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok())
+            && let Some(returned) = ContentType::from_mime(returned)
+            && returned != content_type
+        {
+            self.stats.write().await.failed_requests += 1;
+            return Err(EtherlinkError::Codec(format!(
+                "Expected {} response, got {}",
+                content_type, returned
+            )));
+        }
 
-        // Parse response
-        let result: serde_json::Value = response
-            .json()
+        let result = response
+            .bytes()
             .await
             .map_err(|e| EtherlinkError::Network(e.to_string()))?;
 
         // Update stats
         let mut stats = self.stats.write().await;
         stats.total_requests += 1;
-        stats.bytes_received += content_length;
+        stats.bytes_sent += request_size;
+        stats.bytes_received += result.len() as u64;
 
         let latency = start_time.elapsed().as_millis() as f64;
         stats.average_latency_ms = (stats.average_latency_ms * (stats.total_requests - 1) as f64 + latency) / stats.total_requests as f64;
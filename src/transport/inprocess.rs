@@ -0,0 +1,127 @@
+//! In-process transport routing requests straight to registered Rust handlers
+//!
+//! Lets test suites and monolithic deployments that embed ghostd components run
+//! the exact same client code without opening any sockets.
+
+use crate::{Result, EtherlinkError};
+use crate::transport::{codec, ContentType, Transport, TransportStats};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+
+/// Boxed future returned by in-process handlers
+pub type HandlerFuture = Pin<Box<dyn Future<Output = Result<Bytes>> + Send>>;
+
+/// Handler invoked for requests to a registered endpoint
+pub type Handler = Arc<dyn Fn(ContentType, Bytes) -> HandlerFuture + Send + Sync>;
+
+/// Transport dispatching requests to in-process handler functions
+#[derive(Clone, Default)]
+pub struct InProcessTransport {
+    handlers: Arc<RwLock<HashMap<String, Handler>>>,
+    stats: Arc<RwLock<TransportStats>>,
+}
+
+impl InProcessTransport {
+    /// Create a transport with no registered handlers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a raw handler for an endpoint, replacing any existing one
+    pub async fn register<F, Fut>(&self, endpoint: impl Into<String>, handler: F)
+    where
+        F: Fn(ContentType, Bytes) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Bytes>> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |content_type, body| Box::pin(handler(content_type, body)));
+        self.handlers.write().await.insert(endpoint.into(), handler);
+    }
+
+    /// Register a handler operating on decoded JSON or MessagePack values
+    ///
+    /// The response is encoded with the same content type as the request.
+    pub async fn register_json<F, Fut>(&self, endpoint: impl Into<String>, handler: F)
+    where
+        F: Fn(serde_json::Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<serde_json::Value>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        self.register(endpoint, move |content_type, body| {
+            let handler = handler.clone();
+            async move {
+                let request: serde_json::Value = codec::decode_serde(content_type, &body)?;
+                let response = handler(request).await?;
+                codec::encode_serde(content_type, &response)
+            }
+        })
+        .await;
+    }
+
+    /// Remove the handler for an endpoint
+    pub async fn unregister(&self, endpoint: &str) -> bool {
+        self.handlers.write().await.remove(endpoint).is_some()
+    }
+
+    /// List registered endpoints
+    pub async fn endpoints(&self) -> Vec<String> {
+        self.handlers.read().await.keys().cloned().collect()
+    }
+}
+
+impl std::fmt::Debug for InProcessTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InProcessTransport").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Transport for InProcessTransport {
+    async fn send_request(&self, endpoint: &str, content_type: ContentType, body: Bytes) -> Result<Bytes> {
+        let start_time = Instant::now();
+        let request_size = body.len() as u64;
+
+        let handler = self.handlers.read().await.get(endpoint).cloned();
+        let result = match handler {
+            Some(handler) => handler(content_type, body).await,
+            None => Err(EtherlinkError::Network(format!("No in-process handler registered for {}", endpoint))),
+        };
+
+        let mut stats = self.stats.write().await;
+        match result {
+            Ok(response) => {
+                stats.total_requests += 1;
+                stats.bytes_sent += request_size;
+                stats.bytes_received += response.len() as u64;
+
+                let latency = start_time.elapsed().as_millis() as f64;
+                stats.average_latency_ms = (stats.average_latency_ms * (stats.total_requests - 1) as f64 + latency) / stats.total_requests as f64;
+
+                Ok(response)
+            }
+            Err(e) => {
+                stats.failed_requests += 1;
+                Err(e)
+            }
+        }
+    }
+
+    async fn health_check(&self, endpoint: &str) -> Result<()> {
+        let handlers = self.handlers.read().await;
+        if handlers.keys().any(|registered| registered.starts_with(endpoint)) {
+            Ok(())
+        } else {
+            Err(EtherlinkError::Network(format!("No in-process handler registered for {}", endpoint)))
+        }
+    }
+
+    async fn get_stats(&self) -> Result<TransportStats> {
+        let stats = self.stats.read().await;
+        Ok(stats.clone())
+    }
+}
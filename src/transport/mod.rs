@@ -1,21 +1,72 @@
 //! Transport layer implementations for GhostChain communication
+//!
+//! Only the codec and proxy configuration are available on `wasm32`; browsers
+//! go through the REST clients and the `fetch`-backed reqwest client instead.
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod accounting;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod channel;
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub mod chaos;
+pub mod codec;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dial;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod gquic;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod http;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod inprocess;
+pub mod proxy;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod uds;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use accounting::{AccountingTransport, BandwidthAccounting, RateLimit, ServiceQuota, ServiceUsage};
+#[cfg(not(target_arch = "wasm32"))]
+pub use channel::{ChannelConfig, ChannelManager};
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub use chaos::{ChaosStats, ChaosTransport, Fault, FaultProfile};
+pub use codec::ContentType;
+#[cfg(not(target_arch = "wasm32"))]
+pub use dial::{AddressFamilyPreference, DialConfig, Dialer, HostOverrides};
+#[cfg(not(target_arch = "wasm32"))]
 pub use gquic::GQuicTransport;
+#[cfg(not(target_arch = "wasm32"))]
 pub use http::HttpTransport;
+#[cfg(not(target_arch = "wasm32"))]
+pub use inprocess::InProcessTransport;
+pub use proxy::ProxyConfig;
+#[cfg(not(target_arch = "wasm32"))]
+pub use uds::{LocalEndpoint, UdsTransport};
 
+#[cfg(not(target_arch = "wasm32"))]
 use crate::{Result, EtherlinkError};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::validation::{ConfigErrors, Validator};
+#[cfg(not(target_arch = "wasm32"))]
 use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use bytes::Bytes;
 use serde::{Serialize, de::DeserializeOwned};
 use std::net::SocketAddr;
 
 /// Transport trait for different communication protocols
+#[cfg(not(target_arch = "wasm32"))]
 #[async_trait]
 pub trait Transport: Send + Sync {
+    /// Send an encoded request body and return the raw response body
+    ///
+    /// The response is expected in the same encoding as the request.
+    async fn send_request(&self, endpoint: &str, content_type: ContentType, body: Bytes) -> Result<Bytes>;
+
     /// Send a JSON request and return JSON response
-    async fn send_json_request(&self, endpoint: &str, request: serde_json::Value) -> Result<serde_json::Value>;
+    async fn send_json_request(&self, endpoint: &str, request: serde_json::Value) -> Result<serde_json::Value> {
+        let body = codec::encode_json(&request)?;
+        let response = self.send_request(endpoint, ContentType::Json, body).await?;
+        codec::decode_json(&response)
+    }
 
     /// Health check the transport connection
     async fn health_check(&self, endpoint: &str) -> Result<()>;
@@ -25,7 +76,7 @@ pub trait Transport: Send + Sync {
 }
 
 /// Transport statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TransportStats {
     pub active_connections: u32,
     pub total_requests: u64,
@@ -36,6 +87,7 @@ pub struct TransportStats {
 }
 
 /// Configuration for transport layer
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Debug, Clone)]
 pub struct TransportConfig {
     pub use_gquic: bool,
@@ -43,8 +95,11 @@ pub struct TransportConfig {
     pub timeout_ms: u64,
     pub max_connections: u32,
     pub keepalive_interval_ms: u64,
+    pub proxy: Option<ProxyConfig>,
+    pub dial: DialConfig,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl Default for TransportConfig {
     fn default() -> Self {
         Self {
@@ -53,11 +108,30 @@ impl Default for TransportConfig {
             timeout_ms: 30000,
             max_connections: 100,
             keepalive_interval_ms: 30000,
+            proxy: None,
+            dial: DialConfig::default(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TransportConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(!self.use_gquic || self.enable_tls, "use_gquic", "QUIC always runs over TLS; set enable_tls = true");
+        v.timeout("timeout_ms", self.timeout_ms);
+        v.check(self.max_connections > 0, "max_connections", "must be greater than zero");
+        v.check(self.keepalive_interval_ms > 0, "keepalive_interval_ms", "must be greater than zero");
+        if let Some(proxy) = &self.proxy {
+            v.nested("proxy", proxy.validate());
         }
+        v.finish()
     }
 }
 
 /// Create the appropriate transport based on configuration
+#[cfg(not(target_arch = "wasm32"))]
 pub fn create_transport(config: &TransportConfig) -> Result<Box<dyn Transport>> {
     if config.use_gquic {
         #[cfg(feature = "gquic")]
@@ -73,4 +147,18 @@ pub fn create_transport(config: &TransportConfig) -> Result<Box<dyn Transport>>
         let transport = HttpTransport::new(config.clone())?;
         Ok(Box::new(transport))
     }
-}
\ No newline at end of file
+}
+
+/// Create a transport suited to a specific endpoint
+///
+/// `unix://` and `npipe://` endpoints always use [`UdsTransport`]; anything else
+/// falls back to [`create_transport`].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn create_transport_for(endpoint: &str, config: &TransportConfig) -> Result<Box<dyn Transport>> {
+    if LocalEndpoint::is_local(endpoint) {
+        LocalEndpoint::parse(endpoint)?;
+        return Ok(Box::new(UdsTransport::new(config.clone())?));
+    }
+
+    create_transport(config)
+}
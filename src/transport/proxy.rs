@@ -0,0 +1,233 @@
+//! Outbound proxy support (HTTP CONNECT and SOCKS5)
+//!
+//! The same [`ProxyConfig`] is honored by the reqwest-based REST clients,
+//! [`HttpTransport`](crate::transport::HttpTransport) and the gRPC channels
+//! built by [`ChannelManager`](crate::transport::ChannelManager).
+
+use crate::{Result, EtherlinkError, SecretString};
+use crate::validation::{ConfigErrors, Validator};
+#[cfg(not(target_arch = "wasm32"))]
+use base64::Engine;
+use serde::{Serialize, Deserialize};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::net::TcpStream;
+
+/// Largest proxy CONNECT response header we are willing to buffer
+const MAX_CONNECT_RESPONSE: usize = 8192;
+
+/// Proxy used for outbound connections
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// Proxy URL: `http://host:port`, `socks5://host:port` or `socks5h://host:port`
+    pub url: String,
+    pub username: Option<String>,
+    /// Redacted in `Debug` output
+    pub password: Option<SecretString>,
+    /// Hosts reached directly: exact names, `.suffix` domains or `*` for everything
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+/// Proxy protocol derived from the URL scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    HttpConnect,
+    Socks5,
+}
+
+impl ProxyConfig {
+    /// Create a proxy configuration for a URL
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+            no_proxy: Vec::new(),
+        }
+    }
+
+    /// Set proxy credentials
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(SecretString::new(password.into()));
+        self
+    }
+
+    /// Password sent with the username, empty when unset
+    fn password_or_empty(&self) -> &str {
+        self.password.as_ref().map(|password| password.expose_secret().as_str()).unwrap_or("")
+    }
+
+    /// Add a host that should bypass the proxy
+    pub fn with_no_proxy(mut self, host: impl Into<String>) -> Self {
+        self.no_proxy.push(host.into());
+        self
+    }
+
+    /// Proxy protocol for this URL
+    pub fn kind(&self) -> Result<ProxyKind> {
+        let scheme = self.url.split("://").next().unwrap_or("").to_ascii_lowercase();
+        match scheme.as_str() {
+            "http" => Ok(ProxyKind::HttpConnect),
+            "socks5" | "socks5h" => Ok(ProxyKind::Socks5),
+            _ => Err(EtherlinkError::Configuration(format!("Unsupported proxy scheme: {}", self.url))),
+        }
+    }
+
+    /// Proxy `host:port`
+    pub fn address(&self) -> Result<String> {
+        let rest = self.url
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| EtherlinkError::Configuration(format!("Invalid proxy URL: {}", self.url)))?;
+        let authority = rest.split('/').next().unwrap_or("");
+        // Credentials belong in `username`/`password`; strip any embedded in the URL
+        let authority = authority.rsplit_once('@').map(|(_, host)| host).unwrap_or(authority);
+
+        if authority.is_empty() {
+            return Err(EtherlinkError::Configuration(format!("Invalid proxy URL: {}", self.url)));
+        }
+
+        if authority.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok()) {
+            Ok(authority.to_string())
+        } else {
+            let port = match self.kind()? {
+                ProxyKind::HttpConnect => 8080,
+                ProxyKind::Socks5 => 1080,
+            };
+            Ok(format!("{}:{}", authority, port))
+        }
+    }
+
+    /// Check the proxy URL and credentials
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        if self.kind().is_err() {
+            v.push("url", format!("`{}` must use http://, socks5:// or socks5h://", self.url));
+        } else if self.address().is_err() {
+            v.push("url", format!("`{}` has no host", self.url));
+        }
+        v.check(self.password.is_none() || self.username.is_some(), "password", "set without a username");
+        for (i, entry) in self.no_proxy.iter().enumerate() {
+            v.check(!entry.trim().is_empty(), &format!("no_proxy[{}]", i), "must not be empty");
+        }
+        v.finish()
+    }
+
+    /// Check whether a host should be reached without the proxy
+    pub fn bypasses(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim().to_ascii_lowercase();
+            if entry == "*" {
+                return true;
+            }
+            let domain = entry.trim_start_matches('.');
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+    }
+
+    /// Build the equivalent reqwest proxy
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_reqwest(&self) -> Result<reqwest::Proxy> {
+        self.kind()?;
+        let mut proxy = reqwest::Proxy::all(&self.url)
+            .map_err(|e| EtherlinkError::Configuration(format!("Invalid proxy URL {}: {}", self.url, e)))?;
+
+        if let Some(username) = &self.username {
+            proxy = proxy.basic_auth(username, self.password_or_empty());
+        }
+
+        if !self.no_proxy.is_empty() {
+            proxy = proxy.no_proxy(reqwest::NoProxy::from_string(&self.no_proxy.join(",")));
+        }
+
+        Ok(proxy)
+    }
+
+    /// Apply this proxy to a reqwest client builder
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn apply(&self, builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        Ok(builder.proxy(self.to_reqwest()?))
+    }
+
+    /// Open a tunnel through the proxy to `host:port`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn connect(&self, host: &str, port: u16) -> Result<TcpStream> {
+        let proxy_address = self.address()?;
+
+        match self.kind()? {
+            ProxyKind::HttpConnect => {
+                let mut stream = TcpStream::connect(&proxy_address)
+                    .await
+                    .map_err(|e| EtherlinkError::Network(format!("Failed to reach proxy {}: {}", proxy_address, e)))?;
+                self.http_connect(&mut stream, host, port).await?;
+                Ok(stream)
+            }
+            ProxyKind::Socks5 => {
+                let target = (host, port);
+                let stream = match &self.username {
+                    Some(username) => {
+                        let password = self.password_or_empty();
+                        tokio_socks::tcp::Socks5Stream::connect_with_password(proxy_address.as_str(), target, username, password).await
+                    }
+                    None => tokio_socks::tcp::Socks5Stream::connect(proxy_address.as_str(), target).await,
+                };
+
+                stream
+                    .map(|stream| stream.into_inner())
+                    .map_err(|e| EtherlinkError::Network(format!("SOCKS5 proxy {} failed: {}", proxy_address, e)))
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn http_connect(&self, stream: &mut TcpStream, host: &str, port: u16) -> Result<()> {
+        let target = if host.contains(':') && !host.starts_with('[') {
+            format!("[{}]:{}", host, port)
+        } else {
+            format!("{}:{}", host, port)
+        };
+
+        let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+        if let Some(username) = &self.username {
+            let credentials = format!("{}:{}", username, self.password_or_empty());
+            let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", encoded));
+        }
+        request.push_str("\r\n");
+
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        // Read byte-wise so nothing past the header end is consumed from the tunnel
+        let mut response = Vec::with_capacity(256);
+        let mut byte = [0u8; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_CONNECT_RESPONSE {
+                return Err(EtherlinkError::Network("Proxy CONNECT response too large".to_string()));
+            }
+            let n = stream
+                .read(&mut byte)
+                .await
+                .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+            if n == 0 {
+                return Err(EtherlinkError::Network("Proxy closed connection during CONNECT".to_string()));
+            }
+            response.push(byte[0]);
+        }
+
+        let status_line = String::from_utf8_lossy(&response);
+        let status_line = status_line.lines().next().unwrap_or("");
+        let status = status_line.split_whitespace().nth(1).unwrap_or("");
+        if status.starts_with('2') {
+            Ok(())
+        } else {
+            Err(EtherlinkError::Network(format!("Proxy CONNECT to {} failed: {}", target, status_line)))
+        }
+    }
+}
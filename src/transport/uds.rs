@@ -0,0 +1,242 @@
+//! Unix domain socket (and Windows named pipe) transport for co-located services
+//!
+//! Endpoints take the form `unix:///run/ghostd.sock` or, on Windows,
+//! `npipe://./pipe/ghostd`. An HTTP request path can be appended after a colon,
+//! e.g. `unix:///run/ghostd.sock:/api/v1/rpc`; it defaults to `/`.
+
+use crate::{Result, EtherlinkError};
+use crate::transport::{ContentType, Transport, TransportConfig, TransportStats};
+use async_trait::async_trait;
+use bytes::Bytes;
+use hyper::{Body, Request};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::RwLock;
+
+const UNIX_SCHEME: &str = "unix://";
+const NPIPE_SCHEME: &str = "npipe://";
+
+/// Host-local socket address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalEndpoint {
+    /// Unix domain socket path
+    Unix(PathBuf),
+    /// Windows named pipe, e.g. `\\.\pipe\ghostd`
+    NamedPipe(String),
+}
+
+impl LocalEndpoint {
+    /// Check whether an endpoint string refers to a host-local socket
+    pub fn is_local(endpoint: &str) -> bool {
+        endpoint.starts_with(UNIX_SCHEME) || endpoint.starts_with(NPIPE_SCHEME)
+    }
+
+    /// Parse a local endpoint into the socket address and HTTP request path
+    pub fn parse(endpoint: &str) -> Result<(Self, String)> {
+        let (rest, unix) = if let Some(rest) = endpoint.strip_prefix(UNIX_SCHEME) {
+            (rest, true)
+        } else if let Some(rest) = endpoint.strip_prefix(NPIPE_SCHEME) {
+            (rest, false)
+        } else {
+            return Err(EtherlinkError::Configuration(format!("Not a local socket endpoint: {}", endpoint)));
+        };
+
+        let (address, path) = match rest.split_once(':') {
+            Some((address, path)) if path.starts_with('/') => (address, path.to_string()),
+            Some(_) => {
+                return Err(EtherlinkError::Configuration(format!("Invalid request path in endpoint: {}", endpoint)));
+            }
+            None => (rest, "/".to_string()),
+        };
+
+        if address.is_empty() {
+            return Err(EtherlinkError::Configuration(format!("Missing socket address in endpoint: {}", endpoint)));
+        }
+
+        let local = if unix {
+            LocalEndpoint::Unix(PathBuf::from(address))
+        } else {
+            // npipe://./pipe/ghostd -> \\.\pipe\ghostd
+            LocalEndpoint::NamedPipe(format!(r"\\{}", address.replace('/', r"\")))
+        };
+
+        Ok((local, path))
+    }
+
+    /// Open a new stream to the socket
+    pub async fn connect(&self) -> Result<LocalStream> {
+        match self {
+            #[cfg(unix)]
+            LocalEndpoint::Unix(path) => tokio::net::UnixStream::connect(path)
+                .await
+                .map(|stream| Box::new(stream) as LocalStream)
+                .map_err(|e| EtherlinkError::Network(format!("Failed to connect to {}: {}", path.display(), e))),
+            #[cfg(windows)]
+            LocalEndpoint::NamedPipe(name) => tokio::net::windows::named_pipe::ClientOptions::new()
+                .open(name)
+                .map(|pipe| Box::new(pipe) as LocalStream)
+                .map_err(|e| EtherlinkError::Network(format!("Failed to open pipe {}: {}", name, e))),
+            #[allow(unreachable_patterns)]
+            other => Err(EtherlinkError::Configuration(format!("{:?} is not supported on this platform", other))),
+        }
+    }
+}
+
+/// Byte stream to a local socket
+pub trait LocalIo: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> LocalIo for T {}
+
+/// Boxed stream returned by [`LocalEndpoint::connect`]
+pub type LocalStream = Box<dyn LocalIo>;
+
+/// HTTP/1.1 transport over Unix domain sockets or Windows named pipes
+///
+/// Skips TCP and TLS entirely for ghostd/walletd running on the same host.
+#[derive(Debug, Clone)]
+pub struct UdsTransport {
+    config: TransportConfig,
+    stats: Arc<RwLock<TransportStats>>,
+}
+
+impl UdsTransport {
+    /// Create a new local socket transport
+    pub fn new(config: TransportConfig) -> Result<Self> {
+        let stats = TransportStats {
+            active_connections: 0,
+            total_requests: 0,
+            failed_requests: 0,
+            average_latency_ms: 0.0,
+            bytes_sent: 0,
+            bytes_received: 0,
+        };
+
+        Ok(Self {
+            config,
+            stats: Arc::new(RwLock::new(stats)),
+        })
+    }
+
+    async fn round_trip(&self, endpoint: &str, request: Request<Body>) -> Result<(hyper::StatusCode, hyper::HeaderMap, Bytes)> {
+        let (local, _) = LocalEndpoint::parse(endpoint)?;
+        let stream = local.connect().await?;
+
+        let (mut sender, connection) = hyper::client::conn::handshake(stream)
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        // The connection future drives the socket until the response has been read
+        tokio::spawn(async move {
+            let _ = connection.await;
+        });
+
+        let response = sender
+            .send_request(request)
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        let (parts, body) = response.into_parts();
+        let body = hyper::body::to_bytes(body)
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        Ok((parts.status, parts.headers, body))
+    }
+
+    fn build_request(endpoint: &str, method: hyper::Method, path_override: Option<&str>, content_type: Option<ContentType>, body: Bytes) -> Result<Request<Body>> {
+        let (_, path) = LocalEndpoint::parse(endpoint)?;
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(path_override.unwrap_or(&path))
+            .header(hyper::header::HOST, "localhost");
+
+        if let Some(content_type) = content_type {
+            builder = builder
+                .header(hyper::header::CONTENT_TYPE, content_type.mime())
+                .header(hyper::header::ACCEPT, content_type.mime());
+        }
+
+        builder
+            .body(Body::from(body))
+            .map_err(|e| EtherlinkError::Configuration(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl Transport for UdsTransport {
+    async fn send_request(&self, endpoint: &str, content_type: ContentType, body: Bytes) -> Result<Bytes> {
+        let start_time = Instant::now();
+        let request_size = body.len() as u64;
+        let request = Self::build_request(endpoint, hyper::Method::POST, None, Some(content_type), body)?;
+
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let result = match tokio::time::timeout(timeout, self.round_trip(endpoint, request)).await {
+            Ok(result) => result,
+            Err(_) => Err(EtherlinkError::Timeout(format!("Request to {} timed out", endpoint))),
+        };
+
+        let (status, headers, response) = match result {
+            Ok(response) => response,
+            Err(e) => {
+                self.stats.write().await.failed_requests += 1;
+                return Err(e);
+            }
+        };
+
+        if !status.is_success() {
+            self.stats.write().await.failed_requests += 1;
+            return Err(EtherlinkError::Network(format!(
+                "HTTP request failed with status: {}",
+                status
+            )));
+        }
+
+        // Local daemons answer in the encoding we asked for; anything else (e.g. an
+        // HTML error page from a proxy on the socket) can't be decoded by the caller
+        if let Some(returned) = headers.get(hyper::header::CONTENT_TYPE) {
+            let returned = returned.to_str().unwrap_or_default();
+            if ContentType::from_mime(returned) != Some(content_type) {
+                self.stats.write().await.failed_requests += 1;
+                return Err(EtherlinkError::Codec(format!(
+                    "Expected {} response, got {}",
+                    content_type, returned
+                )));
+            }
+        }
+
+        let mut stats = self.stats.write().await;
+        stats.total_requests += 1;
+        stats.bytes_sent += request_size;
+        stats.bytes_received += response.len() as u64;
+
+        let latency = start_time.elapsed().as_millis() as f64;
+        stats.average_latency_ms = (stats.average_latency_ms * (stats.total_requests - 1) as f64 + latency) / stats.total_requests as f64;
+
+        Ok(response)
+    }
+
+    async fn health_check(&self, endpoint: &str) -> Result<()> {
+        let request = Self::build_request(endpoint, hyper::Method::GET, Some("/health"), None, Bytes::new())?;
+
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let (status, _, _) = tokio::time::timeout(timeout, self.round_trip(endpoint, request))
+            .await
+            .map_err(|_| EtherlinkError::Timeout(format!("Health check of {} timed out", endpoint)))??;
+
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(EtherlinkError::Network(format!(
+                "Health check failed with status: {}",
+                status
+            )))
+        }
+    }
+
+    async fn get_stats(&self) -> Result<TransportStats> {
+        let stats = self.stats.read().await;
+        Ok(stats.clone())
+    }
+}
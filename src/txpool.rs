@@ -0,0 +1,412 @@
+//! Local fee market for GhostPlane transactions awaiting a batch
+//!
+//! Pending transactions are sorted into priority lanes, either by gas price
+//! thresholds or by an explicit priority given at submission. Batches are filled
+//! from the highest lane down, by gas price within a lane, while keeping each
+//! sender's transactions in nonce order and capping how much of a batch one sender
+//! can take. When the pool is full, a new transaction evicts the lowest-ranked one
+//! if it outbids it and is rejected otherwise.
+//!
+//! A [`BatchPolicy`] further bounds each batch by cumulative gas and encoded size,
+//! and decides when a batch is due even though it is not yet full.
+
+use crate::clock::{self, SharedClock};
+use crate::ghostplane::L2Transaction;
+use crate::validation::{ConfigErrors, Validator};
+use crate::{Address, EtherlinkError, Result, TxHash};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::Duration;
+
+/// Pool limits and lane layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TxPoolConfig {
+    /// Maximum pending transactions before underpriced ones are evicted
+    pub capacity: usize,
+    /// Ascending gas prices at which a transaction moves up a lane; `n` thresholds give `n + 1` lanes
+    pub lane_gas_prices: Vec<u64>,
+    /// Transactions below this gas price are refused outright
+    pub min_gas_price: u64,
+    /// Maximum pending transactions per sender
+    pub max_pending_per_sender: usize,
+    /// Maximum transactions per sender in a single batch
+    pub max_per_sender_per_batch: usize,
+}
+
+impl Default for TxPoolConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            lane_gas_prices: vec![1_000_000_000, 10_000_000_000],
+            min_gas_price: 0,
+            max_pending_per_sender: 64,
+            max_per_sender_per_batch: 100,
+        }
+    }
+}
+
+impl TxPoolConfig {
+    /// Number of priority lanes
+    pub fn lanes(&self) -> usize {
+        self.lane_gas_prices.len() + 1
+    }
+
+    /// Lane a gas price falls into, 0 being the lowest
+    pub fn lane_for_gas_price(&self, gas_price: u64) -> usize {
+        self.lane_gas_prices.iter().take_while(|threshold| gas_price >= **threshold).count()
+    }
+
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.capacity > 0, "capacity", "must be greater than zero");
+        v.check(self.lane_gas_prices.windows(2).all(|pair| pair[0] < pair[1]), "lane_gas_prices", "must be strictly ascending");
+        v.check(self.max_pending_per_sender > 0, "max_pending_per_sender", "must be greater than zero");
+        v.check(self.max_per_sender_per_batch > 0, "max_per_sender_per_batch", "must be greater than zero");
+        v.finish()
+    }
+}
+
+/// Limits on a single batch beyond its transaction count
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchPolicy {
+    /// Cumulative gas limit of the batched transactions
+    pub max_gas: Option<u64>,
+    /// Encoded size of the batched transactions, to fit L1 calldata limits
+    pub max_bytes: Option<usize>,
+    /// Seal a batch once its oldest transaction has waited this long, full or not
+    pub max_age_ms: Option<u64>,
+}
+
+impl BatchPolicy {
+    /// Cap the cumulative gas limit of a batch
+    pub fn max_gas(mut self, max_gas: u64) -> Self {
+        self.max_gas = Some(max_gas);
+        self
+    }
+
+    /// Cap the encoded size of a batch
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Seal a batch after its oldest transaction has waited this long
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age_ms = Some(max_age.as_millis() as u64);
+        self
+    }
+
+    /// Check a transaction fits in a batch on its own, so it cannot sit in the pool forever
+    pub fn check(&self, tx: &L2Transaction) -> Result<()> {
+        if let Some(max_gas) = self.max_gas
+            && tx.gas_limit > max_gas
+        {
+            return Err(EtherlinkError::TxPool(format!("gas limit {} exceeds the batch maximum of {}", tx.gas_limit, max_gas)));
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            let size = encoded_size(tx);
+            if size > max_bytes {
+                return Err(EtherlinkError::TxPool(format!("encoded size of {} bytes exceeds the batch maximum of {}", size, max_bytes)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        v.check(self.max_gas != Some(0), "max_gas", "must be greater than zero");
+        v.check(self.max_bytes != Some(0), "max_bytes", "must be greater than zero");
+        v.check(self.max_age_ms != Some(0), "max_age_ms", "must be greater than zero");
+        v.finish()
+    }
+}
+
+/// Size of a transaction as encoded for the bridge
+fn encoded_size(tx: &L2Transaction) -> usize {
+    serde_json::to_vec(tx).map_or(0, |bytes| bytes.len())
+}
+
+/// Queue depth and wait time figures for the pool
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TxPoolMetrics {
+    /// Pending transactions per lane, lowest lane first
+    pub lane_depths: Vec<usize>,
+    pub pending: usize,
+    pub batched: u64,
+    pub evicted: u64,
+    pub rejected: u64,
+    /// Mean time batched transactions spent in the pool
+    pub avg_wait_ms: u64,
+    pub max_wait_ms: u64,
+}
+
+/// Batch and eviction order of a pending transaction: lane, gas price, then oldest first
+type Rank = (usize, u64, Reverse<u64>);
+
+#[derive(Debug, Clone)]
+struct PoolEntry {
+    tx: L2Transaction,
+    lane: usize,
+    seq: u64,
+    size: usize,
+    /// Unix milliseconds, by the pool's clock
+    added_at: u64,
+}
+
+impl PoolEntry {
+    /// Higher ranks are batched first and evicted last
+    fn rank(&self) -> Rank {
+        (self.lane, self.tx.gas_price, Reverse(self.seq))
+    }
+}
+
+/// Priority pool of pending GhostPlane transactions
+#[derive(Debug, Clone)]
+pub struct TxPool {
+    config: TxPoolConfig,
+    entries: HashMap<TxHash, PoolEntry>,
+    /// Hashes per sender, kept sorted by nonce
+    by_sender: HashMap<Address, Vec<TxHash>>,
+    next_seq: u64,
+    batched: u64,
+    evicted: u64,
+    rejected: u64,
+    total_wait: Duration,
+    max_wait: Duration,
+    clock: SharedClock,
+}
+
+impl TxPool {
+    /// Create an empty pool
+    pub fn new(config: TxPoolConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+            by_sender: HashMap::new(),
+            next_seq: 0,
+            batched: 0,
+            evicted: 0,
+            rejected: 0,
+            total_wait: Duration::ZERO,
+            max_wait: Duration::ZERO,
+            clock: clock::system(),
+        }
+    }
+
+    /// Measure transaction age by `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Pool configuration
+    pub fn config(&self) -> &TxPoolConfig {
+        &self.config
+    }
+
+    /// Lane a transaction would be placed in
+    ///
+    /// An explicit priority selects the lane directly and is capped at the top lane.
+    pub fn lane_for(&self, tx: &L2Transaction, priority: Option<usize>) -> usize {
+        priority
+            .unwrap_or_else(|| self.config.lane_for_gas_price(tx.gas_price))
+            .min(self.config.lanes() - 1)
+    }
+
+    /// Check whether a transaction would be admitted, without adding it
+    pub fn check(&self, tx: &L2Transaction, priority: Option<usize>) -> Result<()> {
+        self.admission(tx, self.lane_for(tx, priority)).map(|_| ())
+    }
+
+    /// Add a transaction, returning the hash of any transaction evicted to make room
+    pub fn insert(&mut self, tx_hash: TxHash, tx: L2Transaction, priority: Option<usize>) -> Result<Option<TxHash>> {
+        let lane = self.lane_for(&tx, priority);
+        let evict = match self.admission(&tx, lane) {
+            Ok(evict) => evict,
+            Err(e) => {
+                self.rejected += 1;
+                return Err(e);
+            }
+        };
+
+        if let Some(evicted) = &evict {
+            self.remove(evicted);
+            self.evicted += 1;
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let sender = self.by_sender.entry(tx.from.clone()).or_default();
+        let at = sender.partition_point(|hash| self.entries[hash].tx.nonce <= tx.nonce);
+        sender.insert(at, tx_hash.clone());
+        let size = encoded_size(&tx);
+        self.entries.insert(tx_hash, PoolEntry { tx, lane, seq, size, added_at: self.clock.now_millis() });
+        Ok(evict)
+    }
+
+    /// Decide whether a transaction fits, and what it would evict
+    fn admission(&self, tx: &L2Transaction, lane: usize) -> Result<Option<TxHash>> {
+        if tx.gas_price < self.config.min_gas_price {
+            return Err(EtherlinkError::TxPool(format!(
+                "gas price {} is below the pool minimum of {}",
+                tx.gas_price, self.config.min_gas_price
+            )));
+        }
+
+        let pending = self.by_sender.get(&tx.from).map_or(0, Vec::len);
+        if pending >= self.config.max_pending_per_sender {
+            return Err(EtherlinkError::TxPool(format!(
+                "{} already has {} pending transactions",
+                tx.from, pending
+            )));
+        }
+
+        if self.entries.len() < self.config.capacity {
+            return Ok(None);
+        }
+
+        // Only a sender's last transaction can go without leaving a nonce gap
+        let candidate = (lane, tx.gas_price, Reverse(self.next_seq));
+        let lowest = self
+            .by_sender
+            .values()
+            .filter_map(|hashes| hashes.last())
+            .min_by_key(|hash| self.entries[*hash].rank());
+        match lowest {
+            Some(lowest) if self.entries[lowest].rank() < candidate => Ok(Some(lowest.clone())),
+            _ => Err(EtherlinkError::TxPool(format!(
+                "pool is full and gas price {} does not outbid any pending transaction",
+                tx.gas_price
+            ))),
+        }
+    }
+
+    /// Remove a transaction, e.g. once it is known to be included elsewhere
+    pub fn remove(&mut self, tx_hash: &TxHash) -> Option<L2Transaction> {
+        let entry = self.entries.remove(tx_hash)?;
+        if let Some(hashes) = self.by_sender.get_mut(&entry.tx.from) {
+            hashes.retain(|hash| hash != tx_hash);
+            if hashes.is_empty() {
+                self.by_sender.remove(&entry.tx.from);
+            }
+        }
+        Some(entry.tx)
+    }
+
+    /// Whether a batch should be sealed now: it would be full, or the oldest
+    /// transaction has waited for the policy's `max_age_ms`
+    pub fn batch_due(&self, max: usize, policy: &BatchPolicy) -> bool {
+        if self.entries.is_empty() {
+            return false;
+        }
+        if self.entries.len() >= max {
+            return true;
+        }
+        if let Some(max_gas) = policy.max_gas
+            && self.entries.values().map(|entry| entry.tx.gas_limit).sum::<u64>() >= max_gas
+        {
+            return true;
+        }
+        if let Some(max_bytes) = policy.max_bytes
+            && self.entries.values().map(|entry| entry.size).sum::<usize>() >= max_bytes
+        {
+            return true;
+        }
+        let now = self.clock.now_millis();
+        policy.max_age_ms.is_some_and(|max_age_ms| {
+            self.entries
+                .values()
+                .map(|entry| now.saturating_sub(entry.added_at))
+                .max()
+                .is_some_and(|oldest| oldest >= max_age_ms)
+        })
+    }
+
+    /// Take up to `max` transactions for the next batch, highest priority first
+    ///
+    /// Each sender contributes its transactions in nonce order, at most
+    /// `max_per_sender_per_batch` of them. A sender whose next transaction would
+    /// push the batch over the policy's gas or size limit contributes nothing more,
+    /// while smaller transactions from other senders may still fill the space.
+    pub fn take_batch(&mut self, max: usize, policy: &BatchPolicy) -> Vec<(TxHash, L2Transaction)> {
+        // Heap of each sender's next transaction, by rank
+        let senders: Vec<&Vec<TxHash>> = self.by_sender.values().collect();
+        let mut heads: BinaryHeap<(Rank, usize, usize)> = senders
+            .iter()
+            .enumerate()
+            .map(|(sender, hashes)| (self.entries[&hashes[0]].rank(), sender, 0))
+            .collect();
+
+        let mut selected = Vec::new();
+        let (mut gas, mut bytes) = (0u64, 0usize);
+        while selected.len() < max {
+            let Some((_, sender, index)) = heads.pop() else { break };
+            let hashes = senders[sender];
+            let entry = &self.entries[&hashes[index]];
+            if policy.max_gas.is_some_and(|max_gas| gas.checked_add(entry.tx.gas_limit).is_none_or(|total| total > max_gas))
+                || policy.max_bytes.is_some_and(|max_bytes| bytes + entry.size > max_bytes)
+            {
+                continue;
+            }
+            gas = gas.saturating_add(entry.tx.gas_limit);
+            bytes += entry.size;
+            selected.push(hashes[index].clone());
+
+            let next = index + 1;
+            if next < hashes.len() && next < self.config.max_per_sender_per_batch {
+                heads.push((self.entries[&hashes[next]].rank(), sender, next));
+            }
+        }
+
+        let now = self.clock.now_millis();
+        selected
+            .into_iter()
+            .filter_map(|tx_hash| {
+                let waited = Duration::from_millis(now.saturating_sub(self.entries.get(&tx_hash)?.added_at));
+                self.total_wait += waited;
+                self.max_wait = self.max_wait.max(waited);
+                self.batched += 1;
+                self.remove(&tx_hash).map(|tx| (tx_hash, tx))
+            })
+            .collect()
+    }
+
+    /// Number of pending transactions
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the pool is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Current queue depths and wait times
+    pub fn metrics(&self) -> TxPoolMetrics {
+        let mut lane_depths = vec![0; self.config.lanes()];
+        for entry in self.entries.values() {
+            lane_depths[entry.lane] += 1;
+        }
+
+        TxPoolMetrics {
+            lane_depths,
+            pending: self.entries.len(),
+            batched: self.batched,
+            evicted: self.evicted,
+            rejected: self.rejected,
+            avg_wait_ms: self.total_wait.as_millis().checked_div(self.batched as u128).unwrap_or(0) as u64,
+            max_wait_ms: self.max_wait.as_millis() as u64,
+        }
+    }
+}
+
+impl Default for TxPool {
+    fn default() -> Self {
+        Self::new(TxPoolConfig::default())
+    }
+}
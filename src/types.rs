@@ -1,48 +1,13 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-/// Address type for blockchain addresses
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct Address(pub String);
+use crate::validation::{scheme, ConfigErrors, Validator};
 
-impl Address {
-    pub fn new(addr: String) -> Self {
-        Self(addr)
-    }
-
-    pub fn as_str(&self) -> &str {
-        &self.0
-    }
-}
-
-impl std::fmt::Display for Address {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
-/// Transaction hash type
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct TxHash(pub String);
-
-impl TxHash {
-    pub fn new(hash: String) -> Self {
-        Self(hash)
-    }
-
-    pub fn as_str(&self) -> &str {
-        &self.0
-    }
-}
-
-/// Block height type
-pub type BlockHeight = u64;
-
-/// Gas limit and gas used types
-pub type Gas = u64;
+pub use crate::primitives::{Address, Amount, BlockHeight, Gas, TokenAmount, TokenType, TxHash};
 
 /// Configuration for Etherlink client
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EtherlinkConfig {
     pub ghostd_endpoint: String,
     pub cns_endpoint: Option<String>,
@@ -51,6 +16,28 @@ pub struct EtherlinkConfig {
     pub enable_tls: bool,
     pub timeout_ms: u64,
     pub retry_attempts: u32,
+    /// Proxy for outbound REST and gRPC connections
+    #[serde(default)]
+    pub proxy: Option<crate::transport::ProxyConfig>,
+    /// Finality awaited per operation class by the `wait_for_*` helpers
+    pub confirmations: crate::confirmation::ConfirmationPolicies,
+    /// Private relay for MEV-protected submission; unset submits publicly only
+    pub private_relay: Option<crate::clients::ghostd::PrivateRelayConfig>,
+    /// Simulate transactions with GHOSTD before sending them, rejecting predicted reverts
+    #[serde(default)]
+    pub simulate_before_send: bool,
+    /// Nonce refreshes and fee bumps after recoverable submission rejections
+    #[serde(default)]
+    pub resubmit: crate::resubmit::ResubmitPolicy,
+    /// gRPC endpoint for WALLETD, GID, GSIG and GLEDGER; defaults to `ghostd_endpoint`
+    #[serde(default)]
+    pub grpc_endpoint: Option<String>,
+    /// REST or gRPC for each service with a gRPC API
+    #[serde(default)]
+    pub service_transports: crate::clients::ServiceTransports,
+    /// Pinning service for IPFS content referenced by domains
+    #[serde(default)]
+    pub pinning: Option<crate::clients::PinningConfig>,
 }
 
 impl Default for EtherlinkConfig {
@@ -63,7 +50,58 @@ impl Default for EtherlinkConfig {
             enable_tls: true,
             timeout_ms: 30000,
             retry_attempts: 3,
+            proxy: None,
+            confirmations: crate::confirmation::ConfirmationPolicies::default(),
+            private_relay: None,
+            simulate_before_send: false,
+            resubmit: crate::resubmit::ResubmitPolicy::default(),
+            grpc_endpoint: None,
+            service_transports: crate::clients::ServiceTransports::default(),
+            pinning: None,
+        }
+    }
+}
+
+impl EtherlinkConfig {
+    /// Check every setting, reporting all problems with their field paths
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut v = Validator::new();
+        // (field, endpoint, scheme required, also serves the REST clients)
+        let endpoints = [
+            ("ghostd_endpoint", Some(&self.ghostd_endpoint), true, true),
+            ("cns_endpoint", self.cns_endpoint.as_ref(), true, true),
+            // GhostPlane is also addressed as a bare `host:port`
+            ("ghostplane_endpoint", self.ghostplane_endpoint.as_ref(), false, false),
+            ("grpc_endpoint", self.grpc_endpoint.as_ref(), true, false),
+        ];
+        for (field, endpoint, require_scheme, rest) in endpoints {
+            let Some(endpoint) = endpoint else { continue };
+            v.endpoint(field, endpoint, &["http", "https", "unix", "npipe"], require_scheme, self.enable_tls);
+            let local = matches!(scheme(endpoint).as_deref(), Some("unix" | "npipe"));
+            if self.use_quic && local {
+                v.push(field, "QUIC cannot be used with a local socket endpoint; set use_quic = false");
+            }
+            if rest && local {
+                v.push(field, "REST clients cannot reach a local socket endpoint; put it in grpc_endpoint instead");
+            }
         }
+
+        v.check(!self.use_quic || self.enable_tls, "use_quic", "QUIC always runs over TLS; set enable_tls = true");
+        v.timeout("timeout_ms", self.timeout_ms);
+        if let Some(proxy) = &self.proxy {
+            v.nested("proxy", proxy.validate());
+        }
+        v.nested("confirmations", self.confirmations.validate());
+        if let Some(relay) = &self.private_relay {
+            v.endpoint("private_relay.endpoint", &relay.endpoint, &["http", "https"], true, self.enable_tls);
+            v.nested("private_relay", relay.validate());
+        }
+        v.nested("resubmit", self.resubmit.validate());
+        if let Some(pinning) = &self.pinning {
+            v.endpoint("pinning.endpoint", &pinning.endpoint, &["http", "https"], true, self.enable_tls);
+            v.nested("pinning", pinning.validate());
+        }
+        v.finish()
     }
 }
 
@@ -76,15 +114,6 @@ pub enum Network {
     Custom(String),
 }
 
-/// Token types supported by GhostChain
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub enum TokenType {
-    GCC,    // Gas & transaction fees
-    SPIRIT, // Governance & voting
-    MANA,   // Utility & rewards
-    GHOST,  // Brand & collectibles
-}
-
 /// Transaction result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionResult {
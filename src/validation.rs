@@ -0,0 +1,149 @@
+//! Configuration validation
+//!
+//! Every config type has a `validate()` that checks all of its fields up front and
+//! reports every problem at once, each tagged with the path of the offending field
+//! (e.g. `proxy.url` or `supported_tlds[2]`), so a bad setting is caught at startup
+//! rather than as a connection error on the first request.
+
+use serde::Serialize;
+use std::fmt;
+
+/// Longest request timeout accepted, in milliseconds
+pub const MAX_TIMEOUT_MS: u64 = 600_000;
+
+/// A single problem found in a configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigIssue {
+    /// Path of the offending field, relative to the validated config
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// All problems found while validating a configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigErrors {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl ConfigErrors {
+    /// Issues reported for a field path
+    pub fn for_field<'a>(&'a self, field: &'a str) -> impl Iterator<Item = &'a ConfigIssue> + 'a {
+        self.issues.iter().filter(move |issue| issue.field == field)
+    }
+
+    /// Whether any issue was reported for a field path
+    pub fn has_field(&self, field: &str) -> bool {
+        self.for_field(field).next().is_some()
+    }
+}
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} problem(s)", self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "; {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Collects issues while a config is being checked
+#[derive(Debug, Default)]
+pub(crate) struct Validator {
+    issues: Vec<ConfigIssue>,
+}
+
+impl Validator {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn push(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(ConfigIssue { field: field.into(), message: message.into() });
+    }
+
+    /// Report `message` for `field` unless `ok` holds
+    pub(crate) fn check(&mut self, ok: bool, field: &str, message: &str) {
+        if !ok {
+            self.push(field, message);
+        }
+    }
+
+    /// Merge the result of validating a nested config found at `prefix`
+    pub(crate) fn nested(&mut self, prefix: &str, result: std::result::Result<(), ConfigErrors>) {
+        if let Err(errors) = result {
+            for issue in errors.issues {
+                self.push(format!("{}.{}", prefix, issue.field), issue.message);
+            }
+        }
+    }
+
+    /// Check a timeout is non-zero and below [`MAX_TIMEOUT_MS`]
+    pub(crate) fn timeout(&mut self, field: &str, timeout_ms: u64) {
+        if timeout_ms == 0 {
+            self.push(field, "must be greater than zero");
+        } else if timeout_ms > MAX_TIMEOUT_MS {
+            self.push(field, format!("{} ms exceeds the {} ms maximum", timeout_ms, MAX_TIMEOUT_MS));
+        }
+    }
+
+    /// Check an endpoint's scheme is one of `schemes` and agrees with the TLS flag
+    ///
+    /// With `require_scheme` unset, a bare `host:port` is also accepted.
+    pub(crate) fn endpoint(&mut self, field: &str, endpoint: &str, schemes: &[&str], require_scheme: bool, enable_tls: bool) {
+        if endpoint.trim().is_empty() {
+            self.push(field, "must not be empty");
+            return;
+        }
+
+        let Some(scheme) = scheme(endpoint) else {
+            if require_scheme {
+                self.push(field, format!("`{}` has no scheme; expected one of {}", endpoint, scheme_list(schemes)));
+            }
+            return;
+        };
+
+        if !schemes.contains(&scheme.as_str()) {
+            self.push(field, format!("unsupported scheme `{}`; expected one of {}", scheme, scheme_list(schemes)));
+        } else if scheme == "https" && !enable_tls {
+            self.push(field, "https endpoint requires enable_tls = true");
+        }
+    }
+
+    pub(crate) fn finish(self) -> std::result::Result<(), ConfigErrors> {
+        if self.issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors { issues: self.issues })
+        }
+    }
+}
+
+/// Lower-cased URL scheme of an endpoint, if it has one
+pub(crate) fn scheme(endpoint: &str) -> Option<String> {
+    endpoint.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase())
+}
+
+fn scheme_list(schemes: &[&str]) -> String {
+    schemes.iter().map(|scheme| format!("{}://", scheme)).collect::<Vec<_>>().join(", ")
+}
+
+/// Check that the GhostPlane L2 and rEVM configs target the same chain
+#[cfg(not(target_arch = "wasm32"))]
+pub fn validate_chain_ids(ghostplane: &crate::ghostplane::GhostPlaneConfig, revm: &crate::revm::REVMConfig) -> std::result::Result<(), ConfigErrors> {
+    let mut v = Validator::new();
+    v.nested("ghostplane", ghostplane.validate());
+    v.nested("revm", revm.validate());
+    if ghostplane.chain_id != revm.chain_id {
+        v.push("revm.chain_id", format!("{} does not match ghostplane.chain_id {}", revm.chain_id, ghostplane.chain_id));
+    }
+    v.finish()
+}
@@ -0,0 +1,217 @@
+//! API version discovery and client/service compatibility checks
+//!
+//! Each service reports its API version at `GET /api/version`. Newer client
+//! methods check the negotiated version before calling and fail with
+//! [`EtherlinkError::Unsupported`] instead of a confusing 404 from an older daemon.
+
+use crate::{EtherlinkError, Result};
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use tracing::{debug, warn};
+
+/// Semantic API version reported by a service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl ApiVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = EtherlinkError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let core = s.trim().trim_start_matches('v');
+        // Ignore pre-release and build metadata
+        let core = core.split(['-', '+']).next().unwrap_or(core);
+
+        let mut parts = core.split('.').map(|p| p.parse::<u32>());
+        let invalid = || EtherlinkError::Api(format!("Invalid API version: {}", s));
+
+        let major = parts.next().ok_or_else(invalid)?.map_err(|_| invalid())?;
+        let minor = parts.next().transpose().map_err(|_| invalid())?.unwrap_or(0);
+        let patch = parts.next().transpose().map_err(|_| invalid())?.unwrap_or(0);
+
+        Ok(Self { major, minor, patch })
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Client features that require a minimum service API version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// `GET /blockchain/block/{height}/header` on ghostd
+    BlockHeaders,
+    /// Protobuf and MessagePack request bodies
+    BinaryBodies,
+    /// `?block=<height>` on state reads
+    HistoricalState,
+    /// `/admin` node operator endpoints on ghostd
+    AdminOperations,
+    /// `/snapshots` state snapshot downloads on ghostd
+    StateSnapshots,
+    /// `GET /blockchain/fee-history` on ghostd
+    FeeHistory,
+    /// `GET /blockchain/stats` on ghostd
+    ChainStats,
+    /// `POST /transactions/simulate` on ghostd
+    TransactionSimulation,
+    /// `GET /time` on ghostd
+    ServerTime,
+}
+
+impl Feature {
+    /// Human-readable feature name for error messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::BlockHeaders => "block headers",
+            Feature::BinaryBodies => "binary request bodies",
+            Feature::HistoricalState => "historical state queries",
+            Feature::AdminOperations => "node admin operations",
+            Feature::StateSnapshots => "state snapshots",
+            Feature::FeeHistory => "fee history",
+            Feature::ChainStats => "chain statistics",
+            Feature::TransactionSimulation => "transaction simulation",
+            Feature::ServerTime => "server time",
+        }
+    }
+}
+
+/// Minimum service versions for each feature
+const COMPATIBILITY_MATRIX: &[(&str, Feature, ApiVersion)] = &[
+    ("ghostd", Feature::BlockHeaders, ApiVersion::new(1, 1, 0)),
+    ("ghostd", Feature::ChainStats, ApiVersion::new(1, 1, 0)),
+    ("ghostd", Feature::BinaryBodies, ApiVersion::new(1, 2, 0)),
+    ("walletd", Feature::BinaryBodies, ApiVersion::new(1, 2, 0)),
+    ("ghostd", Feature::HistoricalState, ApiVersion::new(1, 3, 0)),
+    ("ghostd", Feature::FeeHistory, ApiVersion::new(1, 3, 0)),
+    ("ghostd", Feature::TransactionSimulation, ApiVersion::new(1, 3, 0)),
+    ("ghostd", Feature::AdminOperations, ApiVersion::new(1, 4, 0)),
+    ("ghostd", Feature::ServerTime, ApiVersion::new(1, 4, 0)),
+    ("ghostd", Feature::StateSnapshots, ApiVersion::new(1, 5, 0)),
+];
+
+/// Minimum version of `service` required for `feature`, if the feature applies to it
+pub fn required_version(service: &str, feature: Feature) -> Option<ApiVersion> {
+    COMPATIBILITY_MATRIX
+        .iter()
+        .find(|(s, f, _)| *s == service && *f == feature)
+        .map(|(_, _, version)| *version)
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionResponse {
+    version: String,
+}
+
+/// Negotiated API versions per service, shared between clients
+///
+/// Services whose version is unknown (not yet discovered, or discovery failed) are
+/// assumed to support everything, so discovery is never a hard dependency.
+#[derive(Debug, Clone, Default)]
+pub struct VersionRegistry {
+    versions: Arc<RwLock<HashMap<String, ApiVersion>>>,
+}
+
+impl VersionRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a service's version
+    pub fn set(&self, service: &str, version: ApiVersion) {
+        self.versions.write().unwrap().insert(service.to_string(), version);
+    }
+
+    /// Get a service's version, if known
+    pub fn get(&self, service: &str) -> Option<ApiVersion> {
+        self.versions.read().unwrap().get(service).copied()
+    }
+
+    /// All known versions
+    pub fn all(&self) -> HashMap<String, ApiVersion> {
+        self.versions.read().unwrap().clone()
+    }
+
+    /// Whether `service` supports `feature`
+    pub fn supports(&self, service: &str, feature: Feature) -> bool {
+        match (required_version(service, feature), self.get(service)) {
+            (Some(required), Some(actual)) => actual >= required,
+            _ => true,
+        }
+    }
+
+    /// Fail with [`EtherlinkError::Unsupported`] if `service` lacks `feature`
+    pub fn require(&self, service: &str, feature: Feature) -> Result<()> {
+        if self.supports(service, feature) {
+            return Ok(());
+        }
+
+        let required = required_version(service, feature).unwrap_or(ApiVersion::new(0, 0, 0));
+        let actual = self.get(service).unwrap_or(ApiVersion::new(0, 0, 0));
+        Err(EtherlinkError::Unsupported(format!(
+            "{} {} does not support {} (requires >= {})",
+            service, actual, feature.name(), required
+        )))
+    }
+
+    /// Query `GET /api/version` on a service and record the result
+    ///
+    /// `base_url` is the service's versioned API root, e.g. `http://host/api/v1`.
+    pub async fn discover(&self, http_client: &HttpClient, service: &str, base_url: &str) -> Result<ApiVersion> {
+        let root = base_url.trim_end_matches('/');
+        let root = root.strip_suffix("/v1").unwrap_or(root);
+        let url = format!("{}/version", root);
+
+        let body: serde_json::Value = http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .error_for_status()
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+
+        // Accept both a bare body and the usual `ApiResponse` envelope
+        let payload = body.get("data").cloned().unwrap_or(body);
+        let response: VersionResponse = serde_json::from_value(payload)?;
+        let version: ApiVersion = response.version.parse()?;
+
+        debug!("{} reports API version {}", service, version);
+        self.set(service, version);
+        Ok(version)
+    }
+
+    /// Discover versions of several services, logging (not failing on) unreachable ones
+    pub async fn discover_all(&self, http_client: &HttpClient, services: &[(&str, &str)]) -> HashMap<String, ApiVersion> {
+        let lookups = services
+            .iter()
+            .map(|(service, base_url)| async move { (*service, self.discover(http_client, service, base_url).await) });
+
+        for (service, result) in futures::future::join_all(lookups).await {
+            if let Err(e) = result {
+                warn!("Version discovery for {} failed: {}", service, e);
+            }
+        }
+
+        self.all()
+    }
+}
@@ -0,0 +1,625 @@
+//! Account watch-list with notification rules
+//!
+//! A [`Watcher`] holds watched addresses and `.ghost` domains, each with a [`Rule`]
+//! such as `amount >= 1000 && token == GCC && direction == in`. Every [`Activity`]
+//! fed to it (from [`Watcher::poll`] over new GHOSTD blocks, or from the caller's own
+//! subscriptions via [`Watcher::process`]) is matched against the list. Each match
+//! becomes a [`WatchEvent`] queued for every [`Watcher::subscribe`] receiver and
+//! handed to every [`NotificationSink`]: [`StdoutSink`] for the CLI, [`WebhookSink`] for
+//! HTTP callbacks, or an application's own.
+//!
+//! Rule grammar: conditions `field op value` joined by `&&` and `||` (`&&` binds
+//! tighter), or `*` to match everything. Fields are `amount` (`==`, `!=`, `<`, `<=`,
+//! `>`, `>=`), `token` (`GCC`, `SPIRIT`, `MANA`, `GHOST`), `counterparty` (an address)
+//! and `direction` (`in` or `out`); the last three support `==` and `!=`.
+//!
+//! With [`Watcher::with_pinning`], refreshing watched domains also checks the pin of
+//! their `ipfs://` content, and raises a [`WatchWarning`] when pinning it failed.
+
+use crate::clients::build_http_client;
+use crate::clients::context::WithContext;
+use crate::clients::cns::CnsClient;
+use crate::clients::ghostd::{Block, GhostdClient};
+use crate::clients::gledger::TokenTransaction;
+use crate::clients::pinning::{PinningClient, ipfs_cid};
+use crate::events::{EtherlinkEvent, EventBus};
+use crate::pagination::PageConfig;
+use crate::queue::{Fanout, QueueConfig, QueueReceiver};
+use crate::{Address, BlockHeight, EtherlinkConfig, EtherlinkError, Result, TokenType};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{debug, warn};
+
+/// A value transfer observed on chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Activity {
+    pub tx_hash: Option<String>,
+    pub block_height: BlockHeight,
+    pub from: Address,
+    pub to: Address,
+    pub token: TokenType,
+    pub amount: u64,
+}
+
+impl Activity {
+    /// Native transfers of `block`, denominated in GCC
+    pub fn from_block(block: &Block) -> Vec<Self> {
+        block
+            .transactions
+            .iter()
+            .map(|tx| Self {
+                tx_hash: None,
+                block_height: block.height,
+                from: tx.from.clone(),
+                to: tx.to.clone(),
+                token: TokenType::GCC,
+                amount: tx.amount,
+            })
+            .collect()
+    }
+}
+
+impl From<&TokenTransaction> for Activity {
+    fn from(tx: &TokenTransaction) -> Self {
+        Self {
+            tx_hash: Some(tx.tx_hash.clone()),
+            block_height: tx.block_height,
+            from: tx.from.clone(),
+            to: tx.to.clone(),
+            token: tx.token_type.clone(),
+            amount: tx.amount,
+        }
+    }
+}
+
+/// Which side of a transfer the watched account is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    In,
+    Out,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn holds<T: PartialOrd>(&self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+}
+
+/// One `field op value` test
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum Condition {
+    Amount { op: Comparison, value: u64 },
+    Token { equal: bool, token: TokenType },
+    Counterparty { equal: bool, address: Address },
+    Direction { equal: bool, direction: Direction },
+}
+
+impl Condition {
+    fn matches(&self, activity: &Activity, direction: Direction, counterparty: &Address) -> bool {
+        match self {
+            Condition::Amount { op, value } => op.holds(activity.amount, *value),
+            Condition::Token { equal, token } => (activity.token == *token) == *equal,
+            Condition::Counterparty { equal, address } => (counterparty == address) == *equal,
+            Condition::Direction { equal, direction: expected } => (direction == *expected) == *equal,
+        }
+    }
+}
+
+/// When a watched account's activity should notify: any of `any_of`, each requiring all its conditions
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Rule {
+    pub any_of: Vec<Vec<Condition>>,
+}
+
+impl Rule {
+    /// Match every transfer
+    pub fn any() -> Self {
+        Self { any_of: vec![Vec::new()] }
+    }
+
+    /// Match transfers satisfying all of `conditions`
+    pub fn all(conditions: Vec<Condition>) -> Self {
+        Self { any_of: vec![conditions] }
+    }
+
+    /// Whether `activity`, seen from the watched side `direction`, matches
+    pub fn matches(&self, activity: &Activity, direction: Direction) -> bool {
+        let counterparty = match direction {
+            Direction::In => &activity.from,
+            Direction::Out => &activity.to,
+        };
+        self.any_of.iter().any(|all| all.iter().all(|condition| condition.matches(activity, direction, counterparty)))
+    }
+}
+
+impl FromStr for Rule {
+    type Err = EtherlinkError;
+
+    fn from_str(expression: &str) -> Result<Self> {
+        let expression = expression.trim();
+        if expression == "*" || expression.is_empty() {
+            return Ok(Self::any());
+        }
+        let any_of = expression
+            .split("||")
+            .map(|all| all.split("&&").map(parse_condition).collect::<Result<Vec<_>>>())
+            .collect::<Result<_>>()?;
+        Ok(Self { any_of })
+    }
+}
+
+fn parse_condition(condition: &str) -> Result<Condition> {
+    let invalid = |reason: &str| EtherlinkError::Configuration(format!("Invalid rule condition '{}': {}", condition.trim(), reason));
+    let parts: Vec<&str> = condition.split_whitespace().collect();
+    let [field, op, value] = parts[..] else {
+        return Err(invalid("expected `field op value`"));
+    };
+    let op = match op {
+        "==" => Comparison::Eq,
+        "!=" => Comparison::Ne,
+        "<" => Comparison::Lt,
+        "<=" => Comparison::Le,
+        ">" => Comparison::Gt,
+        ">=" => Comparison::Ge,
+        _ => return Err(invalid("unknown operator")),
+    };
+    let equal = match op {
+        Comparison::Eq => Some(true),
+        Comparison::Ne => Some(false),
+        _ => None,
+    };
+
+    match field {
+        "amount" => Ok(Condition::Amount { op, value: value.parse().map_err(|_| invalid("amount must be an integer"))? }),
+        "token" => {
+            let token = TokenType::from_symbol(value).ok_or_else(|| invalid("unknown token"))?;
+            Ok(Condition::Token { equal: equal.ok_or_else(|| invalid("token supports == and != only"))?, token })
+        }
+        "counterparty" => Ok(Condition::Counterparty {
+            equal: equal.ok_or_else(|| invalid("counterparty supports == and != only"))?,
+            address: Address::new(value.to_string()),
+        }),
+        "direction" => {
+            let direction = match value {
+                "in" => Direction::In,
+                "out" => Direction::Out,
+                _ => return Err(invalid("direction must be in or out")),
+            };
+            Ok(Condition::Direction { equal: equal.ok_or_else(|| invalid("direction supports == and != only"))?, direction })
+        }
+        _ => Err(invalid("unknown field")),
+    }
+}
+
+/// What a watch-list entry follows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchTarget {
+    Address(Address),
+    /// A CNS domain, followed to whichever address it currently resolves to
+    Domain(String),
+}
+
+impl fmt::Display for WatchTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchTarget::Address(address) => write!(f, "{}", address),
+            WatchTarget::Domain(domain) => f.write_str(domain),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchEntry {
+    pub id: String,
+    pub target: WatchTarget,
+    pub rule: Rule,
+}
+
+/// A watched account's activity that matched its rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchEvent {
+    pub watch_id: String,
+    pub target: WatchTarget,
+    /// The watched account's address at the time of the activity
+    pub address: Address,
+    pub direction: Direction,
+    pub activity: Activity,
+}
+
+impl fmt::Display for WatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (verb, counterparty) = match self.direction {
+            Direction::In => ("received", &self.activity.from),
+            Direction::Out => ("sent", &self.activity.to),
+        };
+        write!(
+            f,
+            "[block {}] {} {} {} {} {} {}",
+            self.activity.block_height,
+            self.target,
+            verb,
+            self.activity.amount,
+            self.activity.token,
+            if self.direction == Direction::In { "from" } else { "to" },
+            counterparty
+        )
+    }
+}
+
+/// Problem with a watched domain that is not a transfer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatchWarning {
+    /// The pinning service failed to pin the IPFS content the domain points at
+    ContentUnpinned { domain: String, cid: String },
+}
+
+impl fmt::Display for WatchWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchWarning::ContentUnpinned { domain, cid } => write!(f, "warning: {} points at unpinned content {}", domain, cid),
+        }
+    }
+}
+
+/// Destination for watch events
+#[async_trait::async_trait]
+pub trait NotificationSink: Send + Sync {
+    async fn notify(&self, event: &WatchEvent) -> Result<()>;
+
+    /// Deliver a warning; ignored unless the sink overrides it
+    async fn warn(&self, warning: &WatchWarning) -> Result<()> {
+        let _ = warning;
+        Ok(())
+    }
+}
+
+/// Prints one line per event, for `etherlink watch`
+#[derive(Debug, Clone, Default)]
+pub struct StdoutSink;
+
+#[async_trait::async_trait]
+impl NotificationSink for StdoutSink {
+    async fn notify(&self, event: &WatchEvent) -> Result<()> {
+        println!("{}", event);
+        Ok(())
+    }
+
+    async fn warn(&self, warning: &WatchWarning) -> Result<()> {
+        println!("{}", warning);
+        Ok(())
+    }
+}
+
+/// POSTs each event as JSON to a URL
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    url: String,
+    http_client: reqwest::Client,
+}
+
+impl WebhookSink {
+    /// POST to `url` with the proxy, TLS and timeout settings of `config`
+    pub fn new(url: impl Into<String>, config: &EtherlinkConfig) -> Result<Self> {
+        Ok(Self { url: url.into(), http_client: build_http_client(config)? })
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSink for WebhookSink {
+    async fn notify(&self, event: &WatchEvent) -> Result<()> {
+        let response = self.http_client
+            .post(&self.url)
+            .json(event)
+            .send_logged()
+            .await
+            .map_err(|e| EtherlinkError::Network(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(EtherlinkError::Network(format!("Webhook {} answered {}", self.url, response.status())));
+        }
+        Ok(())
+    }
+}
+
+/// Watch-list matching on-chain activity against per-entry rules
+pub struct Watcher {
+    entries: RwLock<Vec<WatchEntry>>,
+    /// Current address of each watched domain
+    resolved: RwLock<HashMap<String, Address>>,
+    sinks: Vec<Arc<dyn NotificationSink>>,
+    events: Fanout<WatchEvent>,
+    /// Queue given to each [`Watcher::subscribe`] receiver
+    queue: QueueConfig,
+    bus: Option<EventBus>,
+    /// Last block handled by [`Watcher::poll`]
+    last_height: Mutex<Option<BlockHeight>>,
+    pinning: Option<PinningClient>,
+    /// CID each watched domain pointed at when last checked, and whether its pin had failed
+    pins: RwLock<HashMap<String, (String, bool)>>,
+}
+
+impl fmt::Debug for Watcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watcher")
+            .field("entries", &self.entries.read().unwrap().len())
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
+
+impl Default for Watcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Watcher {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(Vec::new()),
+            resolved: RwLock::new(HashMap::new()),
+            sinks: Vec::new(),
+            events: Fanout::new(),
+            queue: QueueConfig::default(),
+            bus: None,
+            last_height: Mutex::new(None),
+            pinning: None,
+            pins: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Deliver events to `sink` as well
+    pub fn with_sink(mut self, sink: Arc<dyn NotificationSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Also publish every event as [`EtherlinkEvent::BalanceChanged`], warnings as
+    /// [`EtherlinkEvent::ContentUnpinned`] and each processed block as [`EtherlinkEvent::NewBlock`]
+    pub fn with_events(mut self, bus: EventBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Buffer each subscriber's events per `queue` instead of the default queue
+    pub fn with_queue(mut self, queue: QueueConfig) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// Check the pin state of watched domains' IPFS content on every refresh
+    pub fn with_pinning(mut self, pinning: PinningClient) -> Self {
+        self.pinning = Some(pinning);
+        self
+    }
+
+    /// Start [`Watcher::poll`] after `height` instead of at the chain head
+    pub fn starting_after(self, height: BlockHeight) -> Self {
+        *self.last_height.lock().unwrap() = Some(height);
+        self
+    }
+
+    /// Watch an address, returning the entry's id
+    pub fn watch_address(&self, address: Address, rule: Rule) -> String {
+        self.add(WatchTarget::Address(address), rule)
+    }
+
+    /// Watch a domain, returning the entry's id
+    ///
+    /// Its address is looked up by [`Watcher::refresh_domains`], which [`Watcher::poll`] calls.
+    pub fn watch_domain(&self, domain: impl Into<String>, rule: Rule) -> String {
+        self.add(WatchTarget::Domain(domain.into()), rule)
+    }
+
+    fn add(&self, target: WatchTarget, rule: Rule) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.entries.write().unwrap().push(WatchEntry { id: id.clone(), target, rule });
+        id
+    }
+
+    /// Remove an entry, returning whether it existed
+    pub fn unwatch(&self, id: &str) -> bool {
+        let mut entries = self.entries.write().unwrap();
+        let before = entries.len();
+        entries.retain(|entry| entry.id != id);
+        entries.len() != before
+    }
+
+    pub fn entries(&self) -> Vec<WatchEntry> {
+        self.entries.read().unwrap().clone()
+    }
+
+    /// Receive every event as it is produced
+    ///
+    /// Events are buffered in a queue configured by [`Watcher::with_queue`]; a slow
+    /// receiver is handled by its overflow policy, and one with
+    /// [`crate::queue::OverflowPolicy::Block`] holds up processing until it has room.
+    pub fn subscribe(&self) -> QueueReceiver<WatchEvent> {
+        self.events.subscribe(self.queue.clone())
+    }
+
+    /// Re-resolve every watched domain, so transfers follow a domain to its new owner
+    ///
+    /// A domain that fails to resolve keeps its previous address. With a pinning
+    /// client, each domain's IPFS content is checked too.
+    pub async fn refresh_domains(&self, cns: &CnsClient) {
+        let domains: Vec<String> = self
+            .entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|entry| match &entry.target {
+                WatchTarget::Domain(domain) => Some(domain.clone()),
+                WatchTarget::Address(_) => None,
+            })
+            .collect();
+        for domain in domains {
+            match cns.resolve_domain(&domain).await {
+                Ok(resolution) => {
+                    let cid = resolution.records.content_hash.as_deref().and_then(ipfs_cid);
+                    self.resolved.write().unwrap().insert(domain.clone(), resolution.owner);
+                    if let Some(cid) = cid {
+                        self.check_pin(domain, cid).await;
+                    }
+                }
+                Err(e) => warn!("Failed to resolve watched domain {}: {}", domain, e),
+            }
+        }
+    }
+
+    /// Warn when the pinning service fails to pin `domain`'s content
+    ///
+    /// Requests still queued or pinning are left to finish, and a failure is only
+    /// reported once while it lasts.
+    async fn check_pin(&self, domain: String, cid: String) {
+        let Some(pinning) = &self.pinning else {
+            return;
+        };
+        let failed = match pinning.pin_state(&cid).await {
+            Ok(state) => state == Some(crate::clients::pinning::PinState::Failed),
+            Err(e) => {
+                warn!("Failed to check pin of {} for {}: {}", cid, domain, e);
+                return;
+            }
+        };
+        let previous = self.pins.write().unwrap().insert(domain.clone(), (cid.clone(), failed));
+        let already_reported = previous.is_some_and(|(previous_cid, was_failed)| previous_cid == cid && was_failed);
+        if !failed || already_reported {
+            return;
+        }
+
+        warn!("Pinning service failed to pin {}, the content of watched domain {}", cid, domain);
+        if let Some(bus) = &self.bus {
+            bus.publish(EtherlinkEvent::ContentUnpinned { domain: domain.clone(), cid: cid.clone() });
+        }
+        let warning = WatchWarning::ContentUnpinned { domain, cid };
+        for sink in &self.sinks {
+            if let Err(e) = sink.warn(&warning).await {
+                warn!("Failed to deliver watch warning {}: {}", warning, e);
+            }
+        }
+    }
+
+    fn address_of(&self, target: &WatchTarget) -> Option<Address> {
+        match target {
+            WatchTarget::Address(address) => Some(address.clone()),
+            WatchTarget::Domain(domain) => self.resolved.read().unwrap().get(domain).cloned(),
+        }
+    }
+
+    /// Match one transfer against the watch-list and notify for every hit
+    ///
+    /// Sink failures are logged and do not stop other sinks or later events.
+    pub async fn process(&self, activity: &Activity) -> Vec<WatchEvent> {
+        let mut events = Vec::new();
+        for entry in self.entries.read().unwrap().iter() {
+            let Some(address) = self.address_of(&entry.target) else {
+                continue;
+            };
+            for (direction, side) in [(Direction::Out, &activity.from), (Direction::In, &activity.to)] {
+                if *side == address && entry.rule.matches(activity, direction) {
+                    events.push(WatchEvent {
+                        watch_id: entry.id.clone(),
+                        target: entry.target.clone(),
+                        address: address.clone(),
+                        direction,
+                        activity: activity.clone(),
+                    });
+                }
+            }
+        }
+
+        for event in &events {
+            self.events.publish(event.clone()).await;
+            if let Some(bus) = &self.bus {
+                bus.publish(EtherlinkEvent::BalanceChanged {
+                    address: event.address.clone(),
+                    token: event.activity.token.clone(),
+                    amount: event.activity.amount,
+                    direction: event.direction,
+                    block_height: event.activity.block_height,
+                });
+            }
+            for sink in &self.sinks {
+                if let Err(e) = sink.notify(event).await {
+                    warn!("Failed to deliver watch event for {}: {}", event.target, e);
+                }
+            }
+        }
+        events
+    }
+
+    /// Match every transfer in `block`
+    pub async fn process_block(&self, block: &Block) -> Vec<WatchEvent> {
+        let mut events = Vec::new();
+        for activity in Activity::from_block(block) {
+            events.extend(self.process(&activity).await);
+        }
+        events
+    }
+
+    /// Process blocks added since the last poll, returning the events they produced
+    ///
+    /// The first poll only records the chain head, unless [`Watcher::starting_after`]
+    /// set a starting point. Watched domains are re-resolved first when `cns` is given.
+    pub async fn poll(&self, ghostd: &GhostdClient, cns: Option<&CnsClient>) -> Result<Vec<WatchEvent>> {
+        if let Some(cns) = cns {
+            self.refresh_domains(cns).await;
+        }
+
+        let head = ghostd.get_blockchain_height().await?;
+        let Some(last) = *self.last_height.lock().unwrap() else {
+            *self.last_height.lock().unwrap() = Some(head);
+            return Ok(Vec::new());
+        };
+        if head <= last {
+            return Ok(Vec::new());
+        }
+
+        let mut events = Vec::new();
+        let mut blocks = ghostd.stream_blocks(last + 1..=head, PageConfig::default());
+        while let Some(block) = blocks.next().await {
+            let block = block?;
+            events.extend(self.process_block(&block).await);
+            *self.last_height.lock().unwrap() = Some(block.height);
+            if let Some(bus) = &self.bus {
+                bus.publish(EtherlinkEvent::NewBlock { height: block.height, hash: block.hash.clone() });
+            }
+        }
+        debug!("Watcher processed blocks {}..={}, {} events", last + 1, head, events.len());
+        Ok(events)
+    }
+}
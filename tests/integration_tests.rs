@@ -2,7 +2,7 @@
 
 use etherlink::{
     EtherlinkClient, EtherlinkConfig, EtherlinkClientBuilder,
-    ServiceClients, GhostdClient, GledgerClient, CnsClient,
+    ServiceClients, ServiceClient, GhostdClient, GledgerClient, CnsClient,
     Transport, TransportConfig, HttpTransport,
     AuthCredentials, AuthSecret, Permission, TokenType,
     Address, TxHash
@@ -52,6 +52,7 @@ async fn test_transport_config() {
         timeout_ms: 5000,
         max_connections: 50,
         keepalive_interval_ms: 30000,
+        ..Default::default()
     };
 
     assert_eq!(config.use_gquic, true);
@@ -86,7 +87,7 @@ async fn test_tx_hash_creation() {
 async fn test_auth_credentials() {
     let credentials = AuthCredentials {
         identity: "did:ghost:1234567890abcdef".to_string(),
-        secret: AuthSecret::PrivateKey("secret_key".to_string()),
+        secret: AuthSecret::PrivateKey("secret_key".into()),
         permissions: vec![
             Permission::ReadBlockchain,
             Permission::TransferTokens(TokenType::GCC),
@@ -110,6 +111,165 @@ async fn test_token_types() {
     assert_eq!(tokens.len(), 4);
 }
 
+#[test]
+fn test_config_validation_reports_all_problems() {
+    use etherlink::cns::CNSConfig;
+    use etherlink::ghostplane::GhostPlaneConfig;
+    use etherlink::revm::REVMConfig;
+    use etherlink::validation::validate_chain_ids;
+
+    assert!(EtherlinkConfig::default().validate().is_ok());
+    assert!(CNSConfig::default().validate().is_ok());
+    assert!(validate_chain_ids(&GhostPlaneConfig::default(), &REVMConfig::default()).is_ok());
+
+    let mut config = EtherlinkConfig::default();
+    config.ghostd_endpoint = "https://ghostd.example:8545".to_string();
+    config.cns_endpoint = Some("ftp://cns.example".to_string());
+    config.enable_tls = false;
+    config.timeout_ms = 0;
+    config.proxy = Some(etherlink::ProxyConfig::new("gopher://proxy:70"));
+
+    let errors = config.validate().unwrap_err();
+    assert_eq!(errors.issues.len(), 4);
+    for field in ["ghostd_endpoint", "cns_endpoint", "timeout_ms", "proxy.url"] {
+        assert!(errors.has_field(field), "missing issue for {}", field);
+    }
+    assert!(matches!(etherlink::Etherlink::new(config), Err(etherlink::EtherlinkError::InvalidConfig(_))));
+
+    // Local sockets are only reachable over gRPC
+    let mut config = EtherlinkConfig::default();
+    config.ghostd_endpoint = "unix:///run/ghostd.sock".to_string();
+    config.grpc_endpoint = Some("unix:///run/ghostd-grpc.sock".to_string());
+    let errors = config.validate().unwrap_err();
+    assert_eq!(errors.issues.len(), 1);
+    assert!(errors.has_field("ghostd_endpoint"));
+
+    let mut cns = CNSConfig::default();
+    cns.supported_tlds = vec!["ghost".to_string(), ".gcc".to_string(), "ghost".to_string()];
+    let errors = cns.validate().unwrap_err();
+    assert!(errors.has_field("supported_tlds[1]"));
+    assert!(errors.has_field("supported_tlds[2]"));
+
+    let mut cns = CNSConfig::default();
+    cns.proxy = Some(etherlink::ProxyConfig::new("gopher://proxy:70"));
+    assert!(cns.validate().unwrap_err().has_field("proxy.url"));
+
+    let mut revm = REVMConfig::default();
+    revm.chain_id = 42;
+    let errors = validate_chain_ids(&GhostPlaneConfig::default(), &revm).unwrap_err();
+    assert!(errors.has_field("revm.chain_id"));
+}
+
+#[tokio::test]
+async fn test_supervisor_restarts_panicked_task() {
+    use etherlink::{RestartPolicy, TaskSupervisor};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    let supervisor = TaskSupervisor::new();
+    let runs = Arc::new(AtomicU32::new(0));
+
+    let counter = runs.clone();
+    supervisor
+        .spawn("flaky", RestartPolicy::OnPanic { max_restarts: 2, backoff: Duration::from_millis(1) }, move |token| {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("first run fails");
+                }
+                token.cancelled().await;
+            }
+        })
+        .await
+        .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while runs.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("task was not restarted");
+    assert_eq!(supervisor.tasks().await[0].restarts, 1);
+
+    // Tasks spawned while shutdown races them are either refused or stopped with the rest
+    let racing = supervisor.clone();
+    let spawner = tokio::spawn(async move {
+        let mut spawned = 0;
+        while !racing.is_shutting_down() {
+            if racing.spawn("late", RestartPolicy::Never, |token| async move { token.cancelled().await }).await.is_ok() {
+                spawned += 1;
+            }
+            tokio::task::yield_now().await;
+        }
+        spawned
+    });
+    tokio::task::yield_now().await;
+
+    assert!(supervisor.shutdown(Duration::from_secs(1)).await.is_ok());
+    assert!(supervisor.is_shutting_down());
+    assert!(supervisor.tasks().await.is_empty());
+    spawner.await.unwrap();
+    assert!(supervisor.spawn("after", RestartPolicy::Never, |_| async {}).await.is_err());
+}
+
+#[tokio::test]
+async fn test_bounded_queues_apply_overflow_policies_and_report_lag() {
+    use etherlink::queue::{self, OverflowPolicy, QueueConfig};
+    use etherlink::{EtherlinkEvent, EventBus};
+    use std::time::Duration;
+
+    // Dropping the oldest keeps the latest items; dropping the newest keeps the first
+    let (sender, mut receiver) = queue::bounded(QueueConfig::new(2, OverflowPolicy::DropOldest));
+    for i in 1..=3 {
+        sender.try_send(i).unwrap();
+    }
+    assert_eq!(receiver.lag(), 2);
+    assert_eq!((receiver.recv().await, receiver.recv().await), (Some(2), Some(3)));
+    let metrics = receiver.metrics();
+    assert_eq!((metrics.enqueued, metrics.delivered, metrics.dropped, metrics.high_water, metrics.depth), (3, 2, 1, 2, 0));
+
+    let (sender, mut receiver) = queue::bounded(QueueConfig::new(2, OverflowPolicy::DropNewest));
+    for i in 1..=3 {
+        sender.send(i).await.unwrap();
+    }
+    drop(sender);
+    assert_eq!((receiver.recv().await, receiver.recv().await, receiver.recv().await), (Some(1), Some(2), None));
+
+    // Blocking holds the producer until the consumer makes room
+    let (sender, mut receiver) = queue::bounded(QueueConfig::new(1, OverflowPolicy::Block));
+    sender.send(1).await.unwrap();
+    assert!(tokio::time::timeout(Duration::from_millis(50), sender.send(2)).await.is_err());
+    let producer = tokio::spawn(async move { sender.send(3).await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(receiver.recv().await, Some(1));
+    assert_eq!(receiver.recv().await, Some(3));
+    producer.await.unwrap().unwrap();
+    assert_eq!(receiver.metrics().blocked, 2);
+    assert_eq!(receiver.recv().await, None);
+
+    // A dropped consumer hands the item back instead of blocking forever
+    let (sender, receiver) = queue::bounded(QueueConfig::new(1, OverflowPolicy::Block));
+    sender.send(1).await.unwrap();
+    drop(receiver);
+    assert_eq!(sender.send(2).await.unwrap_err().0, 2);
+    assert!(QueueConfig::new(0, OverflowPolicy::Block).validate().is_err());
+
+    // A slow bounded subscriber loses events in its own queue only
+    let events = EventBus::new();
+    let mut slow = events.subscribe_bounded(QueueConfig::new(1, OverflowPolicy::DropOldest));
+    let mut fast = events.subscribe();
+    assert_eq!(events.subscriber_count(), 2);
+    for cid in ["bafyone", "bafytwo"] {
+        events.publish(EtherlinkEvent::ContentUnpinned { domain: "site.ghost".to_string(), cid: cid.to_string() });
+    }
+    assert!(matches!(fast.recv().await.unwrap(), EtherlinkEvent::ContentUnpinned { cid, .. } if cid == "bafyone"));
+    assert!(matches!(slow.recv().await.unwrap(), EtherlinkEvent::ContentUnpinned { cid, .. } if cid == "bafytwo"));
+    assert_eq!(slow.metrics().dropped, 1);
+    drop(slow);
+    assert_eq!(events.subscriber_count(), 1);
+}
+
 #[cfg(test)]
 mod mock_server_tests {
     use super::*;
@@ -148,6 +308,33 @@ mod mock_server_tests {
         assert_eq!(health_data["service"], "ghostd");
     }
 
+    #[tokio::test]
+    async fn test_runtime_accounts_rest_calls() {
+        use etherlink::runtime::DaemonConfig;
+        use etherlink::transport::ServiceQuota;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "status": "healthy" })))
+            .mount(&mock_server)
+            .await;
+
+        let mut settings = DaemonConfig::default();
+        settings.etherlink.ghostd_endpoint = mock_server.uri();
+        settings.quotas.insert("ghostd".to_string(), ServiceQuota { max_requests: Some(1), max_bytes: None });
+        let runtime = etherlink::Etherlink::from_daemon_config(settings).unwrap();
+        let mut quota_events = runtime.accounting().subscribe();
+
+        // Calls made by the runtime's own clients land in its ledger under the service name
+        runtime.services().ghostd.health_check().await.unwrap();
+        runtime.services().ghostd.health_check().await.unwrap();
+        let usage = runtime.accounting().usage("ghostd");
+        assert_eq!(usage.requests, 2);
+        assert!(usage.bytes_received > 0);
+        assert_eq!(quota_events.try_recv().unwrap().service, "ghostd");
+    }
+
     #[tokio::test]
     async fn test_gledger_token_balances() {
         let mock_server = MockServer::start().await;
@@ -183,44 +370,6590 @@ mod mock_server_tests {
         assert_eq!(balances.mana, 2000);
         assert_eq!(balances.ghost, 10);
     }
-}
 
-#[cfg(test)]
-mod crypto_tests {
-    use super::*;
-    use etherlink::auth::crypto::{CryptoProvider, CryptoAlgorithm};
+    #[tokio::test]
+    async fn test_account_overview_merges_services_and_tolerates_failures() {
+        let mock_server = MockServer::start().await;
+        let owner = "ghost1234567890abcdef1234567890abcdef12345678";
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/tokens/balances/{}", owner)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "address": owner, "gcc": 1000, "spirit": 500, "mana": 2000, "ghost": 10 }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/accounts/{}/nonce", owner)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": { "nonce": 7 } })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/domains/owner/{}", owner)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "domains": ["alice.ghost", "alice.gcc"], "total_count": 2 }
+            })))
+            .mount(&mock_server)
+            .await;
+        // GID is down
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/identities/address/{}", owner)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": false, "error": "identity store unavailable" })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        config.cns_endpoint = None;
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+
+        let address = Address::new(owner.to_string());
+        let overview = clients.get_account_overview(&address).await.unwrap();
+        assert_eq!(overview.balances.as_ref().map(|b| b.gcc), Some(1000));
+        assert_eq!(overview.nonce, Some(7));
+        assert_eq!(overview.domains, Some(vec!["alice.ghost".to_string(), "alice.gcc".to_string()]));
+        assert!(overview.identities.is_none());
+        assert!(!overview.is_complete());
+        assert!(overview.errors["identities"].contains("identity store unavailable"));
+        assert_eq!(overview.errors.len(), 1);
+
+        // Only when every service fails does the overview fail
+        let mut offline = EtherlinkConfig::default();
+        offline.ghostd_endpoint = "http://127.0.0.1:1".to_string();
+        offline.cns_endpoint = None;
+        let offline = ServiceClients::new(&offline, Arc::new(HttpClient::new()));
+        assert!(offline.get_account_overview(&address).await.is_err());
+    }
 
     #[tokio::test]
-    async fn test_ed25519_keypair_generation() {
-        let provider = CryptoProvider::new();
-        let result = provider.generate_keypair(&CryptoAlgorithm::Ed25519);
+    async fn test_portfolio_values_holdings_and_computes_fifo_and_lifo_pnl() {
+        use etherlink::portfolio::{LotMethod, PriceTable};
 
-        assert!(result.is_ok());
-        let keypair = result.unwrap();
-        assert_eq!(keypair.algorithm, CryptoAlgorithm::Ed25519);
-        assert!(!keypair.private_key.is_empty());
-        assert!(!keypair.public_key.is_empty());
+        let mock_server = MockServer::start().await;
+        let me = "ghost1234567890abcdef1234567890abcdef12345678";
+        let other = "ghost9999999999999999999999999999999999999999";
+        let tx = |hash: &str, from: &str, to: &str, amount: u64, timestamp: u64| serde_json::json!({
+            "tx_hash": hash, "from": from, "to": to, "token_type": "GCC", "amount": amount,
+            "timestamp": timestamp, "block_height": timestamp / 10, "memo": null
+        });
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/tokens/history/{}", me)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": [
+                    tx("0xsell", me, other, 15, 300),
+                    tx("0xbuy1", other, me, 10, 100),
+                    tx("0xbuy2", other, me, 10, 200),
+                    tx("0xlater", other, me, 100, 400),
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let prices = PriceTable::new()
+            .with_price(TokenType::GCC, 100, 5)
+            .with_price(TokenType::GCC, 200, 8)
+            .with_price(TokenType::GCC, 300, 10)
+            .with_price(TokenType::GCC, 320, 12);
+        let portfolio = clients.portfolio(Arc::new(prices));
+        let address = Address::new(me.to_string());
+
+        // Transfers after the valuation time are ignored
+        let valuation = portfolio.value(&address, 350).await.unwrap();
+        assert_eq!(valuation.holdings.len(), 1);
+        assert_eq!((valuation.holdings[0].amount, valuation.holdings[0].price, valuation.total_value), (5, 12, 60));
+
+        // FIFO sells all of the first lot and half of the second
+        let fifo = portfolio.pnl(&address, 350).await.unwrap();
+        let realized: Vec<(u64, i128)> = fifo.disposals.iter().map(|d| (d.amount, d.realized_pnl)).collect();
+        assert_eq!(realized, vec![(10, 50), (5, 10)]);
+        assert_eq!((fifo.realized_pnl, fifo.unrealized_pnl), (60, 20));
+
+        let lifo = portfolio.clone().with_method(LotMethod::Lifo).pnl(&address, 350).await.unwrap();
+        assert_eq!((lifo.realized_pnl, lifo.unrealized_pnl), (45, 35));
+        assert_eq!(lifo.open_lots[0].tx_hash, "0xbuy1");
+
+        let mut csv = Vec::new();
+        fifo.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "kind,token,amount,acquired_at,disposed_at,cost_basis,value,pnl,tx_hash");
+        assert_eq!(lines[1], "realized,GCC,10,100,300,50,100,50,0xsell");
+        assert_eq!(lines[3], "unrealized,GCC,5,200,,40,60,20,0xbuy2");
+
+        // Without a price the valuation fails rather than guessing
+        let unpriced = clients.portfolio(Arc::new(PriceTable::new()));
+        assert!(matches!(unpriced.value(&address, 350).await, Err(etherlink::EtherlinkError::Portfolio(_))));
+
+        // Valuing needs no prices from before the valuation time
+        let current = clients.portfolio(Arc::new(PriceTable::new().with_price(TokenType::GCC, 350, 12)));
+        assert_eq!(current.value(&address, 350).await.unwrap().total_value, 60);
+        assert!(current.pnl(&address, 350).await.is_err());
+
+        // A fee leaves the balance and counts toward the disposal's cost basis
+        let payer = "ghost1111111111111111111111111111111111111111";
+        let with_fee = |hash: &str, from: &str, to: &str, amount: u64, fee: u64, timestamp: u64| {
+            let mut tx = tx(hash, from, to, amount, timestamp);
+            tx["fee"] = serde_json::json!(fee);
+            tx
+        };
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/tokens/history/{}", payer)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": [
+                    with_fee("0xbuy", other, payer, 10, 3, 100),
+                    with_fee("0xpay", payer, other, 4, 1, 300),
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+        let payer = Address::new(payer.to_string());
+        let report = portfolio.pnl(&payer, 350).await.unwrap();
+        assert_eq!(report.valuation.holdings[0].amount, 5);
+        let disposal = &report.disposals[0];
+        assert_eq!((disposal.amount, disposal.fee, disposal.cost_basis, disposal.proceeds, disposal.realized_pnl), (4, 1, 25, 40, 15));
+        assert_eq!((report.valuation.total_value, report.unrealized_pnl), (60, 35));
     }
 
     #[tokio::test]
-    async fn test_ed25519_sign_verify() {
-        let provider = CryptoProvider::new();
-        let keypair = provider.generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+    async fn test_grpc_transport_falls_back_to_rest() {
+        use etherlink::{ServiceTransport, ServiceTransports};
 
-        let message = b"Hello, GhostChain!";
-        let signature = provider.sign_message(message, &keypair.private_key, &CryptoAlgorithm::Ed25519);
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/tokens/balances/ghost1fallback"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "address": "ghost1fallback", "gcc": 7, "spirit": 0, "mana": 0, "ghost": 0 }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
 
-        assert!(signature.is_ok());
-        let sig = signature.unwrap();
+        let transports: ServiceTransports = serde_json::from_value(serde_json::json!({ "gledger": "grpc" })).unwrap();
+        assert_eq!(transports.gledger, ServiceTransport::Grpc);
+        assert_eq!(transports.walletd, ServiceTransport::Http);
 
-        let verification = provider.verify_signature(message, &sig, &keypair.public_key, &CryptoAlgorithm::Ed25519);
-        assert!(verification.is_ok());
-        assert_eq!(verification.unwrap(), true);
+        // Nothing listens on the gRPC endpoint, so the call is made over REST
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        config.grpc_endpoint = Some("http://127.0.0.1:1".to_string());
+        config.service_transports = transports;
+        assert!(config.validate().is_ok());
 
-        // Test with wrong message
-        let wrong_message = b"Wrong message";
-        let wrong_verification = provider.verify_signature(wrong_message, &sig, &keypair.public_key, &CryptoAlgorithm::Ed25519);
-        assert!(wrong_verification.is_ok());
-        assert_eq!(wrong_verification.unwrap(), false);
+        let services = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let balances = services.gledger.get_all_balances(&Address::new("ghost1fallback".to_string())).await.unwrap();
+        assert_eq!(balances.gcc, 7);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_transport_does_not_resubmit_unavailable_writes() {
+        use etherlink::clients::CallContext;
+        use etherlink::clients::gledger::TokenTransfer;
+        use etherlink::proto::gledger::v1::{self as gledger_pb, ledger_service_server::{LedgerService, LedgerServiceServer}};
+        use tonic::{Request, Response, Status};
+
+        /// Ledger whose every call fails as if the connection dropped mid-request
+        struct DroppingLedger;
+
+        #[tonic::async_trait]
+        impl LedgerService for DroppingLedger {
+            async fn transfer_tokens(&self, _: Request<gledger_pb::TransferTokensRequest>) -> Result<Response<gledger_pb::TransactionReceipt>, Status> {
+                Err(Status::unavailable("connection reset"))
+            }
+            async fn mint_tokens(&self, _: Request<gledger_pb::MintTokensRequest>) -> Result<Response<gledger_pb::TransactionReceipt>, Status> {
+                Err(Status::unavailable("connection reset"))
+            }
+            async fn burn_tokens(&self, _: Request<gledger_pb::BurnTokensRequest>) -> Result<Response<gledger_pb::TransactionReceipt>, Status> {
+                Err(Status::unavailable("connection reset"))
+            }
+            async fn get_balance(&self, _: Request<gledger_pb::GetBalanceRequest>) -> Result<Response<gledger_pb::GetBalanceResponse>, Status> {
+                Err(Status::unavailable("connection reset"))
+            }
+            async fn get_all_balances(&self, _: Request<gledger_pb::GetAllBalancesRequest>) -> Result<Response<gledger_pb::TokenBalances>, Status> {
+                Err(Status::unavailable("connection reset"))
+            }
+            async fn get_transaction_history(
+                &self,
+                _: Request<gledger_pb::GetTransactionHistoryRequest>,
+            ) -> Result<Response<gledger_pb::GetTransactionHistoryResponse>, Status> {
+                Err(Status::unavailable("connection reset"))
+            }
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let grpc_endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(LedgerServiceServer::new(DroppingLedger))
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/tokens/balances/ghost1fallback"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "address": "ghost1fallback", "gcc": 7, "spirit": 0, "mana": 0, "ghost": 0 }
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/tokens/transfer"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xsecond" }
+            })))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        config.grpc_endpoint = Some(grpc_endpoint);
+        config.service_transports = serde_json::from_value(serde_json::json!({ "gledger": "grpc" })).unwrap();
+        let services = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let address = Address::new("ghost1fallback".to_string());
+
+        // A read that may have been lost is simply repeated over REST
+        assert_eq!(services.gledger.get_all_balances(&address).await.unwrap().gcc, 7);
+
+        // A write that may have been applied is not, so it cannot be submitted twice
+        let transfer = TokenTransfer {
+            from: address.clone(),
+            to: Address::new("ghost1other".to_string()),
+            token_type: TokenType::GCC,
+            amount: 1,
+            memo: None,
+        };
+        assert!(services.gledger.transfer_tokens(transfer).await.is_err());
+
+        // A client sent to another endpoint has no gRPC endpoint to go with it
+        let elsewhere = services.gledger.with_context(CallContext::new().endpoint(mock_server.uri()));
+        assert_eq!(elsewhere.get_all_balances(&address).await.unwrap().gcc, 7);
+    }
+
+    #[tokio::test]
+    async fn test_storage_resumes_upload_and_verifies_download() {
+        use etherlink::clients::storage::{BlobManifest, StorageClient, chunk_hash};
+        use etherlink::EtherlinkError;
+        use etherlink::cns::RecordSet;
+        use etherlink::hash::{HashAlgorithm, HashDomain, Hasher};
+
+        let mock_server = MockServer::start().await;
+        let data = b"0123456789".to_vec();
+        let manifest = BlobManifest::for_bytes(&data, 4);
+        assert_eq!(manifest.chunks.len(), 3);
+        assert!(manifest.is_consistent());
+        let ok = |data: serde_json::Value| ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": data }));
+
+        Mock::given(method("POST"))
+            .and(path("/api/v1/storage/uploads"))
+            .respond_with(ok(serde_json::json!({ "upload_id": "up1", "chunk_size": 4 })))
+            .mount(&mock_server)
+            .await;
+        // The first chunk arrived before the upload was interrupted
+        Mock::given(method("GET"))
+            .and(path("/api/v1/storage/uploads/up1"))
+            .respond_with(ok(serde_json::json!({
+                "upload_id": "up1",
+                "chunk_size": 4,
+                "received": [{ "index": 0, "hash": chunk_hash(b"0123") }]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/storage/uploads/up1/chunks/0"))
+            .respond_with(ok(serde_json::json!({})))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+        for (index, chunk) in [(1, &b"4567"[..]), (2, &b"89"[..])] {
+            Mock::given(method("PUT"))
+                .and(path(format!("/api/v1/storage/uploads/up1/chunks/{}", index)))
+                .respond_with(ok(serde_json::json!({ "index": index, "hash": chunk_hash(chunk) })))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/api/v1/storage/uploads/up1/complete"))
+            .respond_with(ok(serde_json::to_value(&manifest).unwrap()))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let storage = StorageClient::new(&config, Arc::new(HttpClient::new()));
+
+        let session = storage.begin_upload(4).await.unwrap();
+        let uploaded = storage.resume_upload(&session, &data[..]).await.unwrap();
+        assert_eq!(uploaded, manifest);
+        let records = RecordSet::builder().content_hash(uploaded.content_uri()).build().unwrap();
+        assert_eq!(records.content_hash(), Some(format!("gstore://{}", manifest.blob_id).as_str()));
+
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/storage/blobs/{}", manifest.blob_id)))
+            .respond_with(ok(serde_json::to_value(&manifest).unwrap()))
+            .mount(&mock_server)
+            .await;
+        for (hash, chunk) in manifest.chunks.iter().zip([&b"0123"[..], b"4567", b"89"]) {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/v1/storage/chunks/{}", hash)))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(chunk))
+                .mount(&mock_server)
+                .await;
+        }
+        assert_eq!(storage.download(&manifest.blob_id).await.unwrap(), data);
+
+        // A chunk that does not match its hash is rejected
+        let forged = chunk_hash(b"forged");
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/storage/chunks/{}", forged)))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(&b"tampered"[..]))
+            .mount(&mock_server)
+            .await;
+        assert!(matches!(storage.download_chunk(&forged).await, Err(EtherlinkError::Integrity(_))));
+
+        // Manifests that disagree with the chunks they list are caught while downloading
+        let lying = |size: u64, chunk_size: u32| {
+            let mut hasher = Hasher::with_domain(HashAlgorithm::Blake3, HashDomain::Blob);
+            hasher.update(size.to_be_bytes()).update(chunk_size.to_be_bytes());
+            for chunk in &manifest.chunks {
+                hasher.update(hex::decode(chunk).unwrap());
+            }
+            BlobManifest { blob_id: hex::encode(hasher.finalize()), size, chunk_size, chunks: manifest.chunks.clone() }
+        };
+        for (forged, written) in [(lying(12, 4), 10), (lying(10, 2), 0)] {
+            assert!(forged.is_consistent());
+            Mock::given(method("GET"))
+                .and(path(format!("/api/v1/storage/blobs/{}", forged.blob_id)))
+                .respond_with(ok(serde_json::to_value(&forged).unwrap()))
+                .mount(&mock_server)
+                .await;
+            let mut out = Vec::new();
+            assert!(matches!(storage.download_to(&forged.blob_id, &mut out).await, Err(EtherlinkError::Integrity(_))));
+            // Nothing from the chunk that breaks the manifest reaches the writer
+            assert_eq!(out.len(), written);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_paginated_history_stream_with_checkpoint_and_deadline() {
+        use etherlink::{EtherlinkError, PageConfig, PageStream};
+        use futures::StreamExt;
+        use std::time::Duration;
+        use wiremock::matchers::query_param;
+
+        let mock_server = MockServer::start().await;
+        let tx = |n: u64| serde_json::json!({
+            "tx_hash": format!("0x{:02x}", n),
+            "from": "ghost1sender",
+            "to": "ghost1receiver",
+            "token_type": "GCC",
+            "amount": n,
+            "timestamp": 0,
+            "block_height": n,
+            "memo": null
+        });
+        for (offset, items) in [("0", vec![tx(0), tx(1)]), ("1", vec![tx(1), tx(2)]), ("2", vec![tx(2)]), ("3", vec![])] {
+            Mock::given(method("GET"))
+                .and(path("/api/v1/tokens/history/ghost1sender"))
+                .and(query_param("offset", offset))
+                .and(query_param("limit", "2"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": items })))
+                .mount(&mock_server)
+                .await;
+        }
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let gledger = GledgerClient::new(&config, Arc::new(HttpClient::new()));
+        let address = Address::new("ghost1sender".to_string());
+
+        let all: Vec<_> = gledger.stream_transaction_history(&address, PageConfig::default().page_size(2)).collect().await;
+        let amounts: Vec<u64> = all.into_iter().map(|tx| tx.unwrap().amount).collect();
+        assert_eq!(amounts, vec![0, 1, 2]);
+
+        // Stop after one item, then resume from the checkpoint
+        let mut stream = gledger.stream_transaction_history(&address, PageConfig::default().page_size(2));
+        assert_eq!(stream.next().await.unwrap().unwrap().amount, 0);
+        let checkpoint = stream.checkpoint();
+        assert_eq!(checkpoint.position, 1);
+        drop(stream);
+
+        let resumed = gledger.stream_transaction_history(&address, PageConfig::default().page_size(2).resume_from(checkpoint));
+        let amounts: Vec<u64> = resumed.map(|tx| tx.unwrap().amount).collect().await;
+        assert_eq!(amounts, vec![1, 2]);
+
+        // A listing that outlives its deadline ends with a timeout
+        let slow = PageStream::new(0, PageConfig::default().page_size(1).deadline(Duration::from_millis(50)), |position, _| async move {
+            if position > 0 {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+            Ok(vec![position])
+        });
+        let results: Vec<_> = slow.collect().await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(*results[0].as_ref().unwrap(), 0);
+        assert!(matches!(results[1], Err(EtherlinkError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_per_call_context_overrides_endpoint_and_auth() {
+        use etherlink::clients::CallContext;
+        use std::time::Duration;
+        use wiremock::matchers::header;
+
+        let default_server = MockServer::start().await;
+        let tenant_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "height": 1 }
+            })))
+            .mount(&default_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .and(header("authorization", "Bearer tenant-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "height": 2 }
+            })))
+            .expect(1)
+            .mount(&tenant_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = default_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+
+        let ctx = CallContext::new()
+            .endpoint(tenant_server.uri())
+            .auth_token("tenant-token")
+            .timeout(Duration::from_secs(5));
+        assert_eq!(clients.ghostd.with_context(ctx.clone()).get_blockchain_height().await.unwrap(), 2);
+        assert_eq!(clients.with_context(ctx).gledger.base_url(), format!("{}/api/v1", tenant_server.uri()));
+
+        // The client's own defaults are untouched
+        assert_eq!(clients.ghostd.get_blockchain_height().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cassette_records_and_replays_offline() {
+        use etherlink::cassette::{Cassette, CassetteMode, Interaction};
+        use etherlink::ghostplane::GhostPlaneClient;
+
+        let cassette_path = std::env::temp_dir().join(format!("etherlink-cassette-{}.json", uuid::Uuid::new_v4()));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "height": 77 }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Record a session against the live service and bridge
+        let cassette = Arc::new(Cassette::record(&cassette_path));
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let server = cassette.serve(&mut config).await.unwrap();
+        assert!(config.ghostd_endpoint.ends_with("/ghostd"));
+        let clients = ServiceClients::from_config(&config).unwrap();
+        assert_eq!(clients.ghostd.get_blockchain_height().await.unwrap(), 77);
+
+        let ghostplane = GhostPlaneClient::with_defaults();
+        ghostplane.set_cassette(cassette.clone()).await;
+        ghostplane.initialize().await.unwrap();
+        let recorded_state = ghostplane.query_state("accounts").await.unwrap();
+
+        server.stop().await;
+        cassette.save().unwrap();
+        assert_eq!(cassette.interactions().len(), 2);
+        assert!(matches!(&cassette.interactions()[0], Interaction::Http { service, status: 200, .. } if service == "ghostd"));
+        drop(mock_server);
+
+        // Replay it with the service gone
+        let cassette = Arc::new(Cassette::replay(&cassette_path).unwrap());
+        assert_eq!(cassette.mode(), CassetteMode::Replay);
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = "http://127.0.0.1:9".to_string();
+        let _server = cassette.serve(&mut config).await.unwrap();
+        let clients = ServiceClients::from_config(&config).unwrap();
+        assert_eq!(clients.ghostd.get_blockchain_height().await.unwrap(), 77);
+
+        let ghostplane = GhostPlaneClient::with_defaults();
+        ghostplane.set_cassette(cassette.clone()).await;
+        ghostplane.initialize().await.unwrap();
+        assert_eq!(ghostplane.query_state("accounts").await.unwrap(), recorded_state);
+        assert_eq!(cassette.unplayed(), 0);
+
+        // Anything not on the cassette fails instead of reaching the network
+        assert!(clients.ghostd.get_blockchain_height().await.is_err());
+        assert!(ghostplane.query_state("accounts").await.is_err());
+        let _ = std::fs::remove_file(&cassette_path);
+    }
+
+    #[tokio::test]
+    async fn test_verify_l2_inclusion_against_l1_commitment() {
+        use etherlink::ghostplane::{BatchInfo, GhostPlaneClient, GhostPlaneClientBuilder, InclusionStatus};
+        use etherlink::merkle::merkle_root;
+
+        let transactions: Vec<TxHash> = (0..5).map(|i| TxHash::new(format!("0xl2tx{}", i))).collect();
+        let batch = BatchInfo {
+            batch_id: "batch-7".to_string(),
+            merkle_root: merkle_root(&transactions),
+            transactions: transactions.clone(),
+            zk_proof: None,
+            l1_commitment_hash: None,
+            finalized_at: 0,
+        };
+        let forged = BatchInfo { batch_id: "batch-8".to_string(), ..batch.clone() };
+        let tampered = BatchInfo { batch_id: "batch-9".to_string(), merkle_root: merkle_root(&transactions[..4]), ..batch.clone() };
+
+        // L1 block 100 commits batch-7 honestly and batch-9 with another root; batch-8 is
+        // only committed by someone other than the sequencer
+        let sent_by = |from: &str, data: Vec<u8>| serde_json::json!({
+            "from": from, "to": "ghost1rollup", "amount": 0, "gas_limit": 50000,
+            "gas_price": 1, "nonce": 3, "data": data, "signature": null
+        });
+        let commitment_tx = |data: Vec<u8>| sent_by("ghost1sequencer", data);
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/block/100"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "height": 100, "hash": "0xl1", "previous_hash": "0xl0", "timestamp": 0,
+                    "transactions": [
+                        commitment_tx(b"not a commitment".to_vec()),
+                        commitment_tx(batch.commitment().to_bytes()),
+                        commitment_tx(BatchInfo { merkle_root: "0xdeadbeef".to_string(), ..tampered.clone() }.commitment().to_bytes()),
+                        sent_by("ghost1mallory", forged.commitment().to_bytes()),
+                    ],
+                    "merkle_root": "0x00", "gas_used": 0, "gas_limit": 0
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+
+        // Without a sequencer to trust, nothing can be verified
+        let unconfigured = GhostPlaneClient::with_defaults();
+        assert!(matches!(
+            unconfigured.verify_l2_inclusion(&ghostd, &transactions[0], &batch, 100).await,
+            Err(etherlink::EtherlinkError::Configuration(_))
+        ));
+
+        let ghostplane = GhostPlaneClientBuilder::new().sequencer_address(Address::new("ghost1sequencer".to_string())).build();
+        for tx_hash in &transactions {
+            let report = ghostplane.verify_l2_inclusion(&ghostd, tx_hash, &batch, 100).await.unwrap();
+            assert!(report.is_verified(), "{:?}", report);
+            assert_eq!(report.committed_root.as_deref(), Some(batch.merkle_root.as_str()));
+        }
+
+        let outsider = TxHash::new("0xnotinbatch".to_string());
+        let report = ghostplane.verify_l2_inclusion(&ghostd, &outsider, &batch, 100).await.unwrap();
+        assert_eq!(report.status, InclusionStatus::NotInBatch);
+        // A commitment from anyone but the sequencer is ignored
+        let report = ghostplane.verify_l2_inclusion(&ghostd, &transactions[0], &forged, 100).await.unwrap();
+        assert_eq!(report.status, InclusionStatus::CommitmentMissing);
+        let report = ghostplane.verify_l2_inclusion(&ghostd, &transactions[0], &tampered, 100).await.unwrap();
+        assert_eq!(report.status, InclusionStatus::RootMismatch);
+        let relabelled = BatchInfo { batch_id: "batch-9".to_string(), ..batch.clone() };
+        let report = ghostplane.verify_l2_inclusion(&ghostd, &transactions[0], &relabelled, 100).await.unwrap();
+        assert_eq!(report.status, InclusionStatus::CommitmentMismatch);
+
+        // A proof does not verify for another transaction
+        let proof = batch.inclusion_proof(&transactions[2]).unwrap();
+        assert!(proof.verify(&transactions[2], &batch.merkle_root));
+        assert!(!proof.verify(&transactions[3], &batch.merkle_root));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_confirmation_policies_per_operation_class() {
+        use etherlink::confirmation::{ConfirmationPolicies, ConfirmationPolicy, OperationClass};
+        use etherlink::ghostplane::{GhostPlaneClientBuilder, L2Transaction};
+
+        let mut policies = ConfirmationPolicies::default();
+        policies.payments = ConfirmationPolicy::inclusion();
+        policies.domains = ConfirmationPolicy::inclusion().l1_confirmations(2);
+        policies.admin = ConfirmationPolicy::inclusion().zk_verified(true);
+        policies.poll_interval_ms = 10;
+        policies.timeout_ms = 500;
+
+        // Included at block 10; the chain reaches 12 on the third height poll
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/transactions/0xpaid/receipt"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xpaid", "status": "success", "block_height": 10, "gas_used": 21000, "error": null }
+            })))
+            .mount(&mock_server)
+            .await;
+        for height in [11, 12] {
+            Mock::given(method("GET"))
+                .and(path("/api/v1/blockchain/height"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "height": height }
+                })))
+                .up_to_n_times(if height == 11 { 2 } else { 1 })
+                .expect(if height == 11 { 2 } else { 1 })
+                .mount(&mock_server)
+                .await;
+        }
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        config.confirmations = policies.clone();
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+        let tx_hash = TxHash::new("0xpaid".to_string());
+
+        // Payments accept inclusion without looking at the height; domain ops wait two blocks
+        let receipt = ghostd.wait_for_confirmation(&tx_hash, OperationClass::Payment).await.unwrap();
+        assert_eq!(receipt.block_height, Some(10));
+        let receipt = ghostd.wait_for_confirmation(&tx_hash, OperationClass::Domain).await.unwrap();
+        assert!(receipt.is_success());
+
+        // On L2, admin operations need a ZK-verified batch
+        let ghostplane = GhostPlaneClientBuilder::new().confirmations(policies.clone()).build();
+        ghostplane.initialize().await.unwrap();
+        let tx = L2Transaction {
+            from: Address::new("ghost1admin".to_string()),
+            to: Address::new("ghost1registry".to_string()),
+            value: 0,
+            data: vec![],
+            gas_limit: 21000,
+            gas_price: 10,
+            nonce: 0,
+            signature: vec![1],
+        };
+        let l2_hash = ghostplane.submit_transaction(tx).await.unwrap();
+        assert!(ghostplane.wait_for_l2_confirmation(&l2_hash, OperationClass::Payment).await.unwrap().is_none());
+        assert!(matches!(
+            ghostplane.wait_for_l2_confirmation(&l2_hash, OperationClass::Admin).await,
+            Err(etherlink::EtherlinkError::Timeout(_))
+        ));
+
+        let batch = ghostplane.create_batch().await.unwrap();
+        let proof = ghostplane.generate_batch_proof(&batch).await.unwrap();
+        ghostplane.finalize_batch(batch.clone(), proof).await.unwrap();
+        let finalized = ghostplane.wait_for_l2_confirmation(&l2_hash, OperationClass::Admin).await.unwrap().unwrap();
+        assert_eq!(finalized.batch_id, batch.batch_id);
+
+        // A ZK requirement without finalization is rejected
+        config.confirmations.admin = ConfirmationPolicy { zk_verified: true, l2_finalized: false, l1_confirmations: 0 };
+        let errors = config.validate().unwrap_err();
+        assert!(errors.has_field("confirmations.admin.zk_verified"));
+    }
+
+    #[tokio::test]
+    async fn test_historical_reads_at_block() {
+        use etherlink::revm::{REVMClient, REVMConfig};
+        use etherlink::version::ApiVersion;
+        use wiremock::matchers::query_param;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/accounts/ghost1auditee/balance"))
+            .and(query_param("block", "42"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "balance": 100, "address": "ghost1auditee" }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/accounts/ghost1auditee/balance"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "balance": 500, "address": "ghost1auditee" }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/tokens/balances/ghost1auditee"))
+            .and(query_param("block", "42"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "address": "ghost1auditee", "gcc": 1, "spirit": 2, "mana": 3, "ghost": 4 }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/domains/owner/ghost1auditee"))
+            .and(query_param("block", "42"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "domains": ["old.ghost"], "total_count": 1 }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let auditee = Address::new("ghost1auditee".to_string());
+
+        let past = clients.at_block(42);
+        assert_eq!(past.ghostd.get_balance(&auditee).await.unwrap(), 100);
+        assert_eq!(clients.ghostd.get_balance(&auditee).await.unwrap(), 500);
+        assert_eq!(past.gledger.get_all_balances(&auditee).await.unwrap().ghost, 4);
+        assert_eq!(past.cns.get_domains_by_owner(&auditee).await.unwrap(), vec!["old.ghost".to_string()]);
+
+        // A GHOSTD without historical state refuses instead of answering with current state
+        clients.versions.set("ghostd", ApiVersion::new(1, 2, 0));
+        assert!(matches!(clients.at_block(42).ghostd.get_balance(&auditee).await, Err(etherlink::EtherlinkError::Unsupported(_))));
+        assert_eq!(clients.ghostd.get_balance(&auditee).await.unwrap(), 500);
+
+        // The local EVM serves past state from sealed block snapshots
+        let mut evm = REVMClient::new(REVMConfig { history_blocks: 2, ..REVMConfig::default() });
+        evm.set_balance(auditee.clone(), 10);
+        assert_eq!(evm.seal_block(), 0);
+        evm.set_balance(auditee.clone(), 20);
+        assert_eq!(evm.seal_block(), 1);
+        evm.set_balance(auditee.clone(), 30);
+        assert_eq!(evm.at_block(0).unwrap().get_balance(&auditee), 10);
+        assert_eq!(evm.at_block(1).unwrap().get_balance(&auditee), 20);
+        assert_eq!(evm.at_block(2).unwrap().get_balance(&auditee), 30);
+        assert!(evm.at_block(3).is_err());
+        evm.seal_block();
+        assert!(evm.at_block(0).is_err(), "block 0 is pruned once three blocks are sealed");
+    }
+
+    #[tokio::test]
+    async fn test_export_block_range_with_resume() {
+        use etherlink::export::{ExportConfig, ExportManifest, Exporter, SCHEMA_VERSION};
+        use wiremock::matchers::{path_regex, query_param};
+
+        let mock_server = MockServer::start().await;
+        let transfer = |from: &str, to: &str, amount: u64| serde_json::json!({
+            "from": from, "to": to, "amount": amount, "gas_limit": 21000, "gas_price": 1, "nonce": 0, "data": null, "signature": null
+        });
+        for (height, transactions, fetches) in [
+            (1u64, vec![transfer("ghost1alice", "ghost1bob", 5)], 1),
+            (2, vec![], 1),
+            (3, vec![transfer("ghost1bob", "ghost1carol", 2)], 2),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/v1/blockchain/block/{}", height)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "height": height, "hash": format!("0xblock{}", height), "previous_hash": "0x00", "timestamp": 1000 + height,
+                        "transactions": transactions, "merkle_root": "0x00", "gas_used": 0, "gas_limit": 1000000
+                    }
+                })))
+                .expect(fetches)
+                .mount(&mock_server)
+                .await;
+        }
+        for (address, block, balance) in [("ghost1alice", "2", 95), ("ghost1bob", "2", 5), ("ghost1bob", "3", 3), ("ghost1carol", "3", 2)] {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/v1/accounts/{}/balance", address)))
+                .and(query_param("block", block))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "balance": balance, "address": address }
+                })))
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/api/v1/domains/owner/ghost1bob"))
+            .and(query_param("block", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "domains": ["bob.ghost"], "total_count": 1 }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/domains/bob.ghost"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "domain": "bob.ghost", "owner": "ghost1bob", "expires_at": 2000, "created_at": 900, "is_expired": false,
+                    "tld": "ghost", "registration_fee": 10, "renewal_fee": 5
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex("^/api/v1/domains/owner/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "domains": [], "total_count": 0 }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let dir = std::env::temp_dir().join(format!("etherlink-export-{}", uuid::Uuid::new_v4()));
+        let export_config = ExportConfig::new(&dir, 1..=3).blocks_per_part(2);
+        let exporter = Exporter::new(clients.ghostd.clone(), export_config.clone()).with_cns(clients.cns.clone());
+
+        let manifest = exporter.run().await.unwrap();
+        assert!(manifest.is_complete());
+        assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+        assert_eq!(manifest.files.len(), 6);
+
+        let transactions = std::fs::read_to_string(dir.join("transactions-1-2.csv")).unwrap();
+        assert_eq!(
+            transactions,
+            "block_height,block_hash,timestamp,tx_index,from_address,to_address,amount,gas_limit,gas_price,nonce\n\
+             1,0xblock1,1001,0,ghost1alice,ghost1bob,5,21000,1,0\n"
+        );
+        let balances = std::fs::read_to_string(dir.join("balances-1-2.csv")).unwrap();
+        assert_eq!(balances, "block_height,address,balance\n2,ghost1alice,95\n2,ghost1bob,5\n");
+        let domains = std::fs::read_to_string(dir.join("domains-1-2.csv")).unwrap();
+        assert_eq!(domains, "block_height,domain,owner,tld,created_at,expires_at\n2,bob.ghost,ghost1bob,ghost,900,2000\n");
+        assert_eq!(std::fs::read_to_string(dir.join("balances-3-3.csv")).unwrap(), "block_height,address,balance\n3,ghost1bob,3\n3,ghost1carol,2\n");
+
+        // Simulate a crash during the last part: resuming redoes only that part
+        let mut interrupted = manifest.clone();
+        interrupted.next_block = 3;
+        interrupted.files.truncate(3);
+        std::fs::write(dir.join("manifest.json"), serde_json::to_vec(&interrupted).unwrap()).unwrap();
+        let resumed = Exporter::new(clients.ghostd.clone(), export_config.clone().resume(true))
+            .with_cns(clients.cns.clone())
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(resumed, manifest);
+        assert_eq!(ExportManifest::load(&dir).unwrap(), Some(manifest));
+
+        // A different range cannot be resumed into the same directory
+        let mismatched = Exporter::new(clients.ghostd.clone(), ExportConfig::new(&dir, 1..=5).resume(true)).run().await;
+        assert!(matches!(mismatched, Err(etherlink::EtherlinkError::Configuration(_))));
+        let mut reversed = ExportConfig::new(&dir, 1..=1);
+        reversed.from_block = 5;
+        assert!(reversed.validate().is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "sqlite-index")]
+    #[tokio::test]
+    async fn test_chain_index_sync_and_queries() {
+        use etherlink::index::{BalancePoint, ChainIndex, IndexConfig, schema_version};
+        use wiremock::matchers::query_param;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "height": 3 }
+            })))
+            .mount(&mock_server)
+            .await;
+        let transfer = |from: &str, to: &str, amount: u64| serde_json::json!({
+            "from": from, "to": to, "amount": amount, "gas_limit": 21000, "gas_price": 1, "nonce": 0, "data": null, "signature": null
+        });
+        for (height, transactions) in [
+            (1u64, vec![transfer("ghost1alice", "ghost1bob", 5)]),
+            (2, vec![]),
+            (3, vec![transfer("ghost1bob", "ghost1carol", 2)]),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/v1/blockchain/block/{}", height)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "height": height, "hash": format!("0xblock{}", height), "previous_hash": "0x00", "timestamp": 1000 + height,
+                        "transactions": transactions, "merkle_root": "0x00", "gas_used": 0, "gas_limit": 1000000
+                    }
+                })))
+                // The head block is also read back by every later sync to check for reorgs
+                .expect(if height == 3 { 4 } else { 1 })
+                .mount(&mock_server)
+                .await;
+        }
+        for (address, block, balance) in [("ghost1alice", "1", 95), ("ghost1bob", "1", 5), ("ghost1bob", "3", 3), ("ghost1carol", "3", 2)] {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/v1/accounts/{}/balance", address)))
+                .and(query_param("block", block))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "balance": balance, "address": address }
+                })))
+                .mount(&mock_server)
+                .await;
+        }
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let path = std::env::temp_dir().join(format!("etherlink-index-{}.sqlite", uuid::Uuid::new_v4()));
+        let index_config = IndexConfig { path: path.clone(), start_block: Some(1), ..IndexConfig::default() };
+        let index = ChainIndex::open(index_config.clone()).unwrap();
+        assert_eq!(index.schema_version().unwrap(), schema_version());
+
+        assert_eq!(index.sync(&clients.ghostd).await.unwrap(), 3);
+        assert_eq!(index.sync(&clients.ghostd).await.unwrap(), 0);
+        assert_eq!(index.latest_height().unwrap(), Some(3));
+
+        let bob = Address::new("ghost1bob".to_string());
+        let history = index.transactions_by_address(&bob, 10).unwrap();
+        assert_eq!(history.iter().map(|tx| tx.block_height).collect::<Vec<_>>(), vec![3, 1]);
+        assert_eq!(history[1].from, Address::new("ghost1alice".to_string()));
+        assert_eq!(
+            index.balance_history(&bob).unwrap(),
+            vec![BalancePoint { block_height: 1, balance: 5 }, BalancePoint { block_height: 3, balance: 3 }]
+        );
+        assert_eq!(index.balance_at(&bob, 2).unwrap(), Some(5));
+        assert_eq!(index.balance_at(&bob, 0).unwrap(), None);
+
+        // A different block at an indexed height replaces it and everything derived from it
+        let replacement: etherlink::clients::ghostd::Block = serde_json::from_value(serde_json::json!({
+            "height": 3, "hash": "0xfork3", "previous_hash": "0xblock2", "timestamp": 1003,
+            "transactions": [], "merkle_root": "0x00", "gas_used": 0, "gas_limit": 1000000
+        }))
+        .unwrap();
+        index.index_block(&replacement).unwrap();
+        assert_eq!(index.block_hash(3).unwrap().as_deref(), Some("0xfork3"));
+        assert_eq!(index.transactions_by_address(&bob, 10).unwrap().len(), 1);
+        assert_eq!(index.balance_at(&bob, 3).unwrap(), Some(5));
+
+        // Pruning keeps the latest balance from before the cutoff
+        assert_eq!(index.prune_below(3).unwrap(), 2);
+        assert!(index.transactions_by_address(&bob, 10).unwrap().is_empty());
+        assert_eq!(index.balance_at(&bob, 3).unwrap(), Some(5));
+
+        // A sync notices the node's block 3 differs from the indexed one and re-indexes it
+        assert_eq!(index.sync(&clients.ghostd).await.unwrap(), 1);
+        assert_eq!(index.block_hash(3).unwrap().as_deref(), Some("0xblock3"));
+        assert_eq!(index.transactions_by_address(&bob, 10).unwrap().len(), 1);
+        assert_eq!(index.balance_at(&bob, 3).unwrap(), Some(3));
+
+        // Reopening an up-to-date database leaves it as it was
+        drop(index);
+        let reopened = ChainIndex::open(index_config).unwrap();
+        assert_eq!(reopened.schema_version().unwrap(), schema_version());
+        assert_eq!(reopened.latest_height().unwrap(), Some(3));
+        drop(reopened);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watcher_rules_and_sinks() {
+        use etherlink::queue::{OverflowPolicy, QueueConfig};
+        use etherlink::watcher::{Activity, Direction, Rule, Watcher, WebhookSink};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "height": 2 }
+            })))
+            .mount(&mock_server)
+            .await;
+        let transfer = |from: &str, to: &str, amount: u64| serde_json::json!({
+            "from": from, "to": to, "amount": amount, "gas_limit": 21000, "gas_price": 1, "nonce": 0, "data": null, "signature": null
+        });
+        for (height, transactions) in [
+            (1u64, vec![transfer("ghost1whale", "ghost1alice", 5000), transfer("ghost1alice", "ghost1bob", 10)]),
+            (2, vec![transfer("ghost1alice", "ghost1shop", 700)]),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/v1/blockchain/block/{}", height)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "height": height, "hash": format!("0xblock{}", height), "previous_hash": "0x00", "timestamp": 1000 + height,
+                        "transactions": transactions, "merkle_root": "0x00", "gas_used": 0, "gas_limit": 1000000
+                    }
+                })))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/api/v1/domains/resolve/shop.ghost"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "domain": "shop.ghost", "owner": "ghost1shop",
+                    "records": { "addresses": {}, "content_hash": null, "text_records": {}, "avatar": null, "website": null, "email": null, "description": null },
+                    "expires_at": 0, "created_at": 0, "last_updated": 0, "resolver": "cns"
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let large_incoming: Rule = "amount >= 1000 && direction == in && token == GCC".parse().unwrap();
+        assert!("amount ~ 5".parse::<Rule>().is_err());
+        assert!("token > GCC".parse::<Rule>().is_err());
+
+        let watcher = Watcher::new().with_sink(Arc::new(WebhookSink::new(format!("{}/hook", mock_server.uri()), &EtherlinkConfig::default()).unwrap())).starting_after(0);
+        let alice = watcher.watch_address(Address::new("ghost1alice".to_string()), large_incoming);
+        let shop = watcher.watch_domain("shop.ghost", "counterparty == ghost1alice || amount > 1000000".parse().unwrap());
+        let muted = watcher.watch_address(Address::new("ghost1bob".to_string()), Rule::any());
+        assert!(watcher.unwatch(&muted));
+        let mut events = watcher.subscribe();
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let matched = watcher.poll(&clients.ghostd, Some(&clients.cns)).await.unwrap();
+
+        // Alice's small outgoing transfer and Bob's (unwatched) receipt do not notify
+        assert_eq!(matched.len(), 2);
+        assert_eq!((matched[0].watch_id.as_str(), matched[0].direction, matched[0].activity.amount), (alice.as_str(), Direction::In, 5000));
+        assert_eq!((matched[1].watch_id.as_str(), matched[1].direction), (shop.as_str(), Direction::In));
+        assert_eq!(matched[1].address, Address::new("ghost1shop".to_string()));
+        assert_eq!(events.recv().await.unwrap(), matched[0]);
+        assert_eq!(events.recv().await.unwrap(), matched[1]);
+
+        // Nothing new on the next poll
+        assert!(watcher.poll(&clients.ghostd, None).await.unwrap().is_empty());
+
+        // Subscribers get their own bounded queue and its overflow policy
+        let small = Watcher::new().with_queue(QueueConfig::new(1, OverflowPolicy::DropOldest));
+        small.watch_address(Address::new("ghost1carol".to_string()), Rule::any());
+        let mut lagging = small.subscribe();
+        for amount in [1, 2] {
+            let activity = Activity {
+                tx_hash: None,
+                block_height: 3,
+                from: Address::new("ghost1dave".to_string()),
+                to: Address::new("ghost1carol".to_string()),
+                token: TokenType::GCC,
+                amount,
+            };
+            assert_eq!(small.process(&activity).await.len(), 1);
+        }
+        assert_eq!(lagging.metrics().dropped, 1);
+        assert_eq!(lagging.recv().await.unwrap().activity.amount, 2);
+    }
+
+    #[tokio::test]
+    async fn test_watcher_warns_when_domain_content_is_unpinned() {
+        use etherlink::clients::pinning::{PinState, PinningClient, PinningConfig, ipfs_cid};
+        use etherlink::watcher::{NotificationSink, Rule, WatchEvent, WatchWarning, Watcher};
+        use wiremock::matchers::{header, query_param};
+
+        #[derive(Default)]
+        struct Warnings(std::sync::Mutex<Vec<WatchWarning>>);
+
+        #[async_trait::async_trait]
+        impl NotificationSink for Warnings {
+            async fn notify(&self, _event: &WatchEvent) -> etherlink::Result<()> {
+                Ok(())
+            }
+
+            async fn warn(&self, warning: &WatchWarning) -> etherlink::Result<()> {
+                self.0.lock().unwrap().push(warning.clone());
+                Ok(())
+            }
+        }
+
+        assert_eq!(ipfs_cid("ipfs://bafysite/index.html").as_deref(), Some("bafysite"));
+        assert_eq!(ipfs_cid("ar://tx"), None);
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/domains/resolve/site.ghost"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "domain": "site.ghost", "owner": "ghost1site",
+                    "records": { "addresses": {}, "content_hash": "ipfs://bafysite", "text_records": {}, "avatar": null, "website": null, "email": null, "description": null },
+                    "expires_at": 0, "created_at": 0, "last_updated": 0, "resolver": "cns"
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        let pinned = serde_json::json!({
+            "count": 1,
+            "results": [{ "requestid": "r1", "status": "pinned", "created": "2026-01-01T00:00:00Z", "pin": { "cid": "bafysite" } }]
+        });
+        let failed = serde_json::json!({
+            "count": 1,
+            "results": [{ "requestid": "r2", "status": "failed", "created": "2026-01-02T00:00:00Z", "pin": { "cid": "bafysite" } }]
+        });
+        // Pinned on the first check, gone on the second and failed afterwards
+        Mock::given(method("GET"))
+            .and(path("/pins"))
+            .and(query_param("cid", "bafysite"))
+            .and(query_param("status", "queued,pinning,pinned,failed"))
+            .and(header("authorization", "Bearer pin-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(pinned))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/pins"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "count": 0, "results": [] })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/pins"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(failed))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        config.pinning = Some(PinningConfig::new(mock_server.uri()).with_access_token("pin-token"));
+        assert!(config.validate().is_ok());
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let pinning: PinningClient = clients.pinning.clone().unwrap();
+
+        let warnings = Arc::new(Warnings::default());
+        let events = etherlink::EventBus::new();
+        let mut bus = events.subscribe();
+        let watcher = Watcher::new().with_sink(warnings.clone()).with_events(events).with_pinning(pinning.clone());
+        watcher.watch_domain("site.ghost", Rule::any());
+
+        // Neither a pinned CID nor one without a pin request is a failure
+        watcher.refresh_domains(&clients.cns).await;
+        watcher.refresh_domains(&clients.cns).await;
+        assert!(warnings.0.lock().unwrap().is_empty());
+
+        // Failed now: one warning, not repeated while it stays failed
+        watcher.refresh_domains(&clients.cns).await;
+        watcher.refresh_domains(&clients.cns).await;
+        let expected = WatchWarning::ContentUnpinned { domain: "site.ghost".to_string(), cid: "bafysite".to_string() };
+        assert_eq!(*warnings.0.lock().unwrap(), vec![expected]);
+        assert_eq!(
+            bus.recv().await.unwrap(),
+            etherlink::EtherlinkEvent::ContentUnpinned { domain: "site.ghost".to_string(), cid: "bafysite".to_string() }
+        );
+        assert_eq!(pinning.pin_state("bafysite").await.unwrap(), Some(PinState::Failed));
+
+        // The pinning service is a third party and never sees the operation's correlation ID
+        let id = etherlink::correlation::CorrelationId::new();
+        etherlink::correlation::scope(id, pinning.pin_state("bafysite")).await.unwrap();
+        let requests = mock_server.received_requests().await.unwrap();
+        assert!(requests.iter().filter(|r| r.url.path() == "/pins").all(|r| r.headers.get(etherlink::correlation::CORRELATION_HEADER).is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_escrow_two_of_three_release() {
+        use etherlink::clients::escrow::{EscrowApproval, EscrowParty, EscrowRole, EscrowState, EscrowTerms};
+        use etherlink::clients::gledger::EscrowOutcome;
+        use etherlink::{CryptoAlgorithm, CryptoProvider, EtherlinkError, MockClock, TokenType};
+        use std::time::Duration;
+        use wiremock::matchers::body_partial_json;
+
+        let provider = CryptoProvider::new();
+        let [buyer, seller, arbiter] = [(); 3].map(|_| provider.generate_keypair(&CryptoAlgorithm::Ed25519).unwrap());
+        let party = |key: &etherlink::KeyPair| EscrowParty { address: key.address().unwrap(), public_key: key.public_key.clone() };
+
+        let mock_server = MockServer::start().await;
+        let ok = |data: serde_json::Value| ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": data }));
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow"))
+            .and(body_partial_json(serde_json::json!({ "amount": 250, "release_policy": { "threshold": 2 } })))
+            .respond_with(ok(serde_json::json!({ "escrow_id": "esc1", "tx_hash": "0xfund", "token_type": "GCC", "amount": 250 })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/signatures/threshold"))
+            .and(body_partial_json(serde_json::json!({ "threshold": 2 })))
+            .respond_with(ok(serde_json::json!({
+                "signature": "aggregate", "threshold": 2, "participants_count": 3, "algorithm": "Ed25519", "signature_id": "sig1"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow/esc1/refund"))
+            .respond_with(ok(serde_json::json!({ "tx_hash": "0xrefund", "status": "confirmed" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow/esc1/settle"))
+            .and(body_partial_json(serde_json::json!({ "outcome": "release", "to": seller.address().unwrap(), "signature": "aggregate" })))
+            .respond_with(ok(serde_json::json!({ "tx_hash": "0xsettle", "status": "confirmed" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clock = MockClock::new(1_000);
+        let escrow = ServiceClients::new(&config, Arc::new(HttpClient::new())).escrow().with_clock(clock.clone());
+
+        let terms = EscrowTerms {
+            buyer: party(&buyer),
+            seller: party(&seller),
+            arbiter: party(&arbiter),
+            algorithm: CryptoAlgorithm::Ed25519,
+            token_type: TokenType::GCC,
+            amount: 250,
+            reference: "order-17".to_string(),
+            expires_at: None,
+        };
+        assert!(escrow.create(EscrowTerms { seller: party(&buyer), ..terms.clone() }).is_err());
+        // Neither the key's spelling nor the address's case makes the buyer someone else
+        let disguised = EscrowParty {
+            address: Address::new(buyer.address().unwrap().as_str().to_uppercase()),
+            public_key: format!("0x{}", buyer.public_key.to_uppercase()),
+        };
+        assert!(escrow.create(EscrowTerms { seller: disguised, ..terms.clone() }).is_err());
+        let mut agreement = escrow.create(terms.clone()).unwrap();
+        assert_eq!(agreement.state, EscrowState::Created);
+        // Approvals commit to the GLEDGER escrow, so there is nothing to approve before funding
+        assert!(EscrowApproval::sign(&agreement, EscrowRole::Seller, EscrowOutcome::Release, &seller).is_err());
+
+        escrow.fund(&mut agreement).await.unwrap();
+        assert_eq!((agreement.state, agreement.escrow_id.as_deref()), (EscrowState::Funded, Some("esc1")));
+
+        let approve = |role, key| EscrowApproval::sign(&agreement, role, EscrowOutcome::Release, key).unwrap();
+        let by_seller = approve(EscrowRole::Seller, &seller);
+        let by_arbiter = approve(EscrowRole::Arbiter, &arbiter);
+
+        // One approval is not enough, and a signature by the wrong key is rejected locally
+        assert!(matches!(escrow.release(&mut agreement, &[by_seller.clone()]).await, Err(EtherlinkError::Configuration(_))));
+        let forged = EscrowApproval { role: EscrowRole::Buyer, ..by_arbiter.clone() };
+        assert!(matches!(escrow.release(&mut agreement, &[by_seller.clone(), forged]).await, Err(EtherlinkError::Crypto(_))));
+
+        // Without an expiry the buyer cannot take the funds back alone
+        assert!(matches!(escrow.refund_expired(&mut agreement).await, Err(EtherlinkError::Configuration(_))));
+
+        let tx = escrow.release(&mut agreement, &[by_seller, by_arbiter]).await.unwrap();
+        assert_eq!(tx.as_str(), "0xsettle");
+        assert_eq!(agreement.state, EscrowState::Released);
+        assert!(escrow.refund_expired(&mut agreement).await.is_err());
+
+        // With one, only once it has passed
+        let mut expiring = agreement.clone();
+        expiring.terms.expires_at = Some(1_060);
+        expiring.state = EscrowState::Funded;
+        clock.advance(Duration::from_secs(60));
+        assert!(matches!(escrow.refund_expired(&mut expiring).await, Err(EtherlinkError::Configuration(_))));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(escrow.refund_expired(&mut expiring).await.unwrap().as_str(), "0xrefund");
+        assert_eq!(expiring.state, EscrowState::Refunded);
+    }
+
+    #[tokio::test]
+    async fn test_atomic_swap_claims_or_refunds() {
+        use etherlink::auth::{EncryptedFileBackend, SecretBackend};
+        use etherlink::clients::swap::{SwapConfig, SwapStatus, hash_lock, secret_name};
+        use etherlink::{EtherlinkEvent, EventBus, MockClock, TokenType};
+        use std::time::Duration;
+        use wiremock::matchers::path_regex;
+
+        let mock_server = MockServer::start().await;
+        let ok = |data: serde_json::Value| ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": data }));
+        let (alice, bob) = (Address::new("ghost1alice".to_string()), Address::new("ghost1bob".to_string()));
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow/htlc"))
+            .respond_with(ok(serde_json::json!({ "escrow_id": "mine", "tx_hash": "0xlock", "token_type": "GCC", "amount": 100 })))
+            .mount(&mock_server)
+            .await;
+        // Bob has locked 40 MANA for Alice under her hash, expiring well before Alice's lock
+        Mock::given(method("GET"))
+            .and(path_regex("^/api/v1/escrow/htlc/[0-9a-f]{64}$"))
+            .respond_with(move |request: &wiremock::Request| {
+                let hash_lock = request.url.path().rsplit('/').next().unwrap().to_string();
+                ok(serde_json::json!([{
+                    "escrow_id": "theirs", "from": "ghost1bob", "to": "ghost1alice", "token_type": "MANA", "amount": 40,
+                    "hash_lock": hash_lock, "expires_at": 1_001_000, "state": "locked", "preimage": null
+                }]))
+            })
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex("^/api/v1/escrow/htlc/[0-9a-f]{64}$"))
+            .respond_with(ok(serde_json::json!([])))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow/theirs/claim"))
+            .respond_with(ok(serde_json::json!({ "tx_hash": "0xclaim", "status": "confirmed" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow/mine/refund"))
+            .respond_with(ok(serde_json::json!({ "tx_hash": "0xrefund", "status": "confirmed" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let clock = MockClock::new(1_000_000);
+        let events = EventBus::new();
+        let mut statuses = events.subscribe();
+        let dir = std::env::temp_dir().join(format!("etherlink-swap-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let secrets = Arc::new(EncryptedFileBackend::new(&dir, [3u8; 32]));
+        let swaps = clients
+            .atomic_swap(alice.clone())
+            .with_config(SwapConfig { timeout: Duration::from_secs(3600), poll_interval: Duration::from_millis(10) })
+            .with_clock(clock.clone())
+            .with_events(events)
+            .with_secret_store(secrets.clone());
+
+        let receipt = swaps.swap(TokenType::GCC, 100, TokenType::MANA, 40, &bob).await.unwrap();
+        assert_eq!((receipt.status, receipt.claim_tx.as_ref().map(|tx| tx.as_str())), (SwapStatus::Completed, Some("0xclaim")));
+        for expected in [SwapStatus::Locked, SwapStatus::CounterpartyLocked, SwapStatus::Completed] {
+            assert_eq!(statuses.recv().await.unwrap(), EtherlinkEvent::SwapStatusChanged { swap_id: receipt.swap_id.clone(), status: expected, correlation_id: None });
+        }
+
+        // The claim reveals the secret behind the hash lock Alice locked under
+        let requests = mock_server.received_requests().await.unwrap();
+        let lock: serde_json::Value = requests.iter().find(|r| r.url.path() == "/api/v1/escrow/htlc").unwrap().body_json().unwrap();
+        let claim: serde_json::Value = requests.iter().find(|r| r.url.path() == "/api/v1/escrow/theirs/claim").unwrap().body_json().unwrap();
+        let secret = hex::decode(claim["preimage"].as_str().unwrap()).unwrap();
+        assert_eq!(lock["hash_lock"], hash_lock(&secret));
+        assert_eq!(lock["hash_lock"], receipt.swap_id);
+        // ...which Alice kept before locking, and gets back in the receipt
+        assert_eq!(receipt.secret.as_deref(), claim["preimage"].as_str());
+        assert_eq!(secrets.get(&secret_name(&receipt.swap_id)).unwrap(), Some(secret));
+
+        // Bob's answer must be locked under the hash Alice asked about
+        let not_a_hash = clients.atomic_swap(bob.clone()).accept("ignored", TokenType::MANA, 40, TokenType::GCC, 100, &alice).await;
+        assert!(matches!(not_a_hash, Err(etherlink::EtherlinkError::Configuration(_))));
+
+        // Bob never locks: once Alice's lock expires it is refunded
+        let advance = {
+            let clock = clock.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                clock.advance(Duration::from_secs(7200));
+            })
+        };
+        let receipt = swaps.swap(TokenType::GCC, 100, TokenType::MANA, 40, &bob).await.unwrap();
+        advance.await.unwrap();
+        assert_eq!((receipt.status, receipt.refund_tx.as_ref().map(|tx| tx.as_str())), (SwapStatus::Refunded, Some("0xrefund")));
+        assert!(receipt.secret.is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_order_relay_submits_cancels_and_streams_fills() {
+        use etherlink::clients::CallContext;
+        use etherlink::clients::orderbook::{LimitOrder, OrderCancellation, OrderSide, OrderState};
+        use etherlink::{CryptoAlgorithm, CryptoProvider, TokenType};
+        use futures::StreamExt;
+        use std::time::Duration;
+        use wiremock::matchers::{body_partial_json, query_param};
+
+        let provider = CryptoProvider::new();
+        let maker = provider.generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let stranger = provider.generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let order = LimitOrder {
+            maker: maker.address().unwrap(),
+            taker: None,
+            side: OrderSide::Sell,
+            base: TokenType::GCC,
+            quote: TokenType::MANA,
+            amount: 100,
+            price: 3,
+            expiry: u64::MAX,
+            nonce: 7,
+        };
+
+        // Only the maker's key can sign, and any change to the order breaks the signature
+        assert!(order.clone().sign(&stranger).is_err());
+        let signed = order.clone().sign(&maker).unwrap();
+        signed.verify().unwrap();
+        let mut tampered = signed.clone();
+        tampered.order.price = 1;
+        assert!(tampered.verify().is_err());
+        let hash = signed.order_hash();
+
+        let mock_server = MockServer::start().await;
+        let ok = |data: serde_json::Value| ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": data }));
+        Mock::given(method("POST"))
+            .and(path("/orders"))
+            .and(body_partial_json(serde_json::json!({ "order": { "nonce": 7, "price": 3 }, "signature": signed.signature })))
+            .respond_with(ok(serde_json::json!({ "order_hash": hash, "state": "open", "filled": 0 })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/orders/{}/cancel", hash)))
+            .respond_with(ok(serde_json::json!({ "order_hash": hash, "state": "cancelled", "filled": 60 })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let fill = |sequence: u64| serde_json::json!({
+            "sequence": sequence, "order_hash": hash, "maker": maker.address().unwrap(), "taker": "ghost1taker",
+            "base_amount": 30, "quote_amount": 90, "tx_hash": null, "filled_at": 1_700_000_000
+        });
+        Mock::given(method("GET"))
+            .and(path("/fills"))
+            .and(query_param("after", "0"))
+            .respond_with(ok(serde_json::json!([fill(1)])))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fills"))
+            .and(query_param("after", "1"))
+            .respond_with(ok(serde_json::json!([fill(1), fill(2)])))
+            .mount(&mock_server)
+            .await;
+
+        let clients = ServiceClients::new(&EtherlinkConfig::default(), Arc::new(HttpClient::new()));
+        let relay = clients.order_relay(&mock_server.uri());
+
+        let status = relay.submit(&signed).await.unwrap();
+        assert_eq!((status.order_hash.as_str(), status.state), (hash.as_str(), OrderState::Open));
+
+        // Fills already seen are skipped as the subscription follows the relay
+        let fills: Vec<_> = relay
+            .subscribe_fills(maker.address().unwrap(), 0, Duration::from_millis(10))
+            .into_stream()
+            .take(2)
+            .map(|fill| fill.unwrap().sequence)
+            .collect()
+            .await;
+        assert_eq!(fills, vec![1, 2]);
+
+        // A client derived for another endpoint addresses the relay the same way
+        assert!(OrderCancellation::sign(&signed, &stranger).is_err());
+        let relay_again = relay.with_context(CallContext::new().endpoint(format!("{}/", mock_server.uri())));
+        let status = relay_again.cancel(&OrderCancellation::sign(&signed, &maker).unwrap()).await.unwrap();
+        assert_eq!((status.state, status.filled), (OrderState::Cancelled, 60));
+
+        // Expired orders never reach the relay
+        let expired = LimitOrder { expiry: 1, nonce: 8, ..order }.sign(&maker).unwrap();
+        assert!(relay.submit(&expired).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_private_relay_submission_with_public_fallback() {
+        use etherlink::clients::ghostd::{PrivateRelayConfig, RelayState, SubmissionRoute, TransactionBuilder};
+        use etherlink::{CryptoAlgorithm, CryptoProvider};
+        use etherlink::clients::CallContext;
+        use wiremock::matchers::header;
+
+        let signer = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let payment = || TransactionBuilder::new(signer.address().unwrap(), Address::new("ghost1merchant".to_string())).amount(500).private_relay(true);
+        let relay_config = |server: &MockServer| PrivateRelayConfig { fallback_after_ms: 200, poll_interval_ms: 20, ..PrivateRelayConfig::new(server.uri()) };
+
+        // The relay includes the transaction: the public mempool never sees it, and the relay
+        // only ever gets its own token
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/relay/transactions"))
+            .and(header("authorization", "Bearer relay-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xprivate", "status": "pending" }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/relay/transactions/0xprivate"))
+            .and(header("authorization", "Bearer relay-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "state": "included", "block_height": 7 }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        config.private_relay = Some(relay_config(&mock_server).with_access_token("relay-token"));
+        assert!(config.validate().is_ok());
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()))
+            .with_context(CallContext::new().auth_token("guardian-token"));
+        let submission = payment().submit(&ghostd, &signer).await.unwrap();
+        assert_eq!(submission.route, SubmissionRoute::PrivateRelay);
+        assert_eq!(submission.relay_state, Some(RelayState::Included));
+        assert_eq!(submission.block_height, Some(7));
+        assert_eq!(submission.tx_hash, TxHash::new("0xprivate".to_string()));
+
+        // The relay drops it: the same signed transaction goes to the public mempool
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/relay/transactions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xdropped", "status": "pending" }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/relay/transactions/0xdropped"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "state": "dropped", "block_height": null }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xpublic", "status": "pending" }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        config.ghostd_endpoint = mock_server.uri();
+        config.private_relay = Some(relay_config(&mock_server));
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+        let submission = payment().submit(&ghostd, &signer).await.unwrap();
+        assert_eq!(submission.route, SubmissionRoute::Public);
+        assert_eq!(submission.relay_state, Some(RelayState::Dropped));
+        assert_eq!(submission.tx_hash, TxHash::new("0xpublic".to_string()));
+
+        // Asking for privacy without a relay is an error, not a silent public submission
+        config.private_relay = None;
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+        assert!(matches!(payment().submit(&ghostd, &signer).await, Err(etherlink::EtherlinkError::Configuration(_))));
+
+        config.private_relay = Some(PrivateRelayConfig { poll_interval_ms: 0, ..PrivateRelayConfig::new("ftp://relay") });
+        let errors = config.validate().unwrap_err();
+        assert!(errors.issues.iter().any(|issue| issue.field == "private_relay.endpoint"));
+        assert!(errors.issues.iter().any(|issue| issue.field == "private_relay.poll_interval_ms"));
+    }
+
+    #[tokio::test]
+    async fn test_simulation_rejects_predicted_reverts_before_sending() {
+        use etherlink::clients::ghostd::TransactionBuilder;
+        use etherlink::revm::REVMClient;
+        use etherlink::{CryptoAlgorithm, CryptoProvider, EtherlinkError, RevmFork};
+
+        // ABI-encoded `Error("insufficient allowance")`
+        let reason = b"insufficient allowance";
+        let mut revert = hex::decode("08c379a0").unwrap();
+        revert.extend([0u8; 31].iter().chain(&[0x20]));
+        revert.extend([0u8; 31].iter().chain(&[reason.len() as u8]));
+        revert.extend(reason.iter().chain(&[0u8; 10]));
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions/simulate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "success": false, "gas_used": 23_000, "output": hex::encode(&revert) }
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xsent", "status": "pending" }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let signer = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let payment = || TransactionBuilder::new(signer.address().unwrap(), Address::new("ghost1merchant".to_string())).amount(500);
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        config.simulate_before_send = true;
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+
+        // GHOSTD predicts a revert: the decoded reason comes back and nothing is sent
+        match payment().submit(&ghostd, &signer).await {
+            Err(EtherlinkError::SimulatedRevert { reason, gas_used }) => {
+                assert_eq!((reason.as_deref(), gas_used), (Some("insufficient allowance"), 23_000));
+            }
+            other => panic!("expected a simulated revert, got {:?}", other),
+        }
+        assert!(ghostd.submit_transaction(payment().build()).await.is_err());
+
+        // A local rEVM fork replaces GHOSTD's simulation and leaves its own state untouched
+        let revm = Arc::new(tokio::sync::RwLock::new(REVMClient::with_defaults()));
+        let ghostd = ghostd.with_simulator(Arc::new(RevmFork::new(revm.clone())));
+        match ghostd.submit_transaction(payment().build()).await {
+            Err(EtherlinkError::SimulatedRevert { reason, .. }) => assert_eq!(reason.as_deref(), Some("Insufficient balance")),
+            other => panic!("expected a simulated revert, got {:?}", other),
+        }
+        revm.write().await.set_balance(signer.address().unwrap(), 1_000_000);
+        assert_eq!(ghostd.submit_transaction(payment().build()).await.unwrap(), TxHash::new("0xsent".to_string()));
+        assert_eq!(revm.read().await.get_account_nonce(&signer.address().unwrap()), 0);
+    }
+
+    #[tokio::test]
+    async fn test_resubmission_refreshes_nonce_and_bumps_fee() {
+        use etherlink::clients::ghostd::TransactionBuilder;
+        use etherlink::resubmit::{RejectionReason, ResubmitPolicy};
+        use etherlink::{CryptoAlgorithm, CryptoProvider, EtherlinkError};
+        use wiremock::matchers::body_partial_json;
+
+        let signer = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let mock_server = MockServer::start().await;
+        let rejected = |error: &str| ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": false, "error": error }));
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions"))
+            .and(body_partial_json(serde_json::json!({ "nonce": 0 })))
+            .respond_with(rejected("nonce too low: next nonce is 5"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/accounts/{}/nonce", signer.address().unwrap())))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": { "nonce": 5 } })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions"))
+            .and(body_partial_json(serde_json::json!({ "nonce": 5, "gas_price": 100 })))
+            .respond_with(rejected("replacement transaction underpriced"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions"))
+            .and(body_partial_json(serde_json::json!({ "nonce": 5, "gas_price": 110 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xaccepted", "status": "pending" }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let payment = || TransactionBuilder::new(signer.address().unwrap(), Address::new("ghost1merchant".to_string())).amount(500).gas_price(100);
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        assert!(payment().submit(&GhostdClient::new(&config, Arc::new(HttpClient::new())), &signer).await.is_err());
+        config.resubmit = ResubmitPolicy { max_attempts: 3, ..ResubmitPolicy::default() };
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+
+        let submission = payment().submit(&ghostd, &signer).await.unwrap();
+        assert_eq!(submission.tx_hash, TxHash::new("0xaccepted".to_string()));
+        let audit: Vec<_> = submission.attempts.iter().map(|a| (a.nonce, a.gas_price, a.rejection, a.is_accepted())).collect();
+        assert_eq!(audit, vec![
+            (0, 100, Some(RejectionReason::NonceTooLow), false),
+            (5, 100, Some(RejectionReason::Underpriced), false),
+            (5, 110, None, true),
+        ]);
+
+        // A stale nonce already taken by an earlier attempt returns that attempt instead of paying twice
+        let landed = TransactionBuilder::new(signer.address().unwrap(), Address::new("ghost1merchant".to_string())).amount(700).gas_price(100);
+        let landed_hash = landed.clone().build().hash();
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/transactions/{}/receipt", landed_hash.as_str())))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": landed_hash.as_str(), "status": "success", "block_height": 4, "gas_used": 21000, "error": null }
+            })))
+            .mount(&mock_server)
+            .await;
+        let submission = landed.submit(&ghostd, &signer).await.unwrap();
+        assert_eq!((submission.tx_hash, submission.block_height), (landed_hash, Some(4)));
+        assert_eq!(submission.attempts.len(), 1);
+
+        // Fee bumps stop at the configured ceiling, and resubmission can be turned off
+        config.resubmit = ResubmitPolicy { max_attempts: 3, max_gas_price: Some(105), ..ResubmitPolicy::default() };
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+        assert!(matches!(payment().nonce(5).submit(&ghostd, &signer).await, Err(EtherlinkError::Api(msg)) if msg.contains("underpriced")));
+        config.resubmit = ResubmitPolicy::disabled();
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+        assert!(payment().submit(&ghostd, &signer).await.is_err());
+        assert_eq!(ResubmitPolicy::default().max_attempts, 1);
+
+        assert_eq!(
+            RejectionReason::classify(&EtherlinkError::TxPool("gas price 1 is below the pool minimum of 5".to_string())),
+            Some(RejectionReason::Underpriced)
+        );
+        assert_eq!(RejectionReason::classify(&EtherlinkError::Network("connection reset".to_string())), None);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_lifecycle_is_tracked_and_persisted() {
+        use etherlink::clients::ghostd::TransactionBuilder;
+        use etherlink::confirmation::{ConfirmationPolicy, OperationClass};
+        use etherlink::lifecycle::{OperationTracker, Transition, TxState};
+        use etherlink::resubmit::ResubmitPolicy;
+        use etherlink::{CryptoAlgorithm, CryptoProvider, EtherlinkError, EtherlinkEvent, EventBus};
+        use wiremock::matchers::body_partial_json;
+
+        let signer = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions"))
+            .and(body_partial_json(serde_json::json!({ "nonce": 9 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": false, "error": "insufficient funds" })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xtracked", "status": "pending" }
+            })))
+            .mount(&mock_server)
+            .await;
+        let receipt = |status: &str, block_height: Option<u64>| {
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xtracked", "status": status, "block_height": block_height, "gas_used": 21000, "error": null }
+            }))
+        };
+        Mock::given(method("GET"))
+            .and(path("/api/v1/transactions/0xtracked/receipt"))
+            .respond_with(receipt("pending", None))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/transactions/0xtracked/receipt"))
+            .respond_with(receipt("success", Some(10)))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": { "height": 11 } })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        config.resubmit = ResubmitPolicy::disabled();
+        config.confirmations.payments = ConfirmationPolicy::inclusion().l1_confirmations(1);
+        config.confirmations.poll_interval_ms = 10;
+        config.confirmations.timeout_ms = 500;
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+
+        let dir = std::env::temp_dir().join(format!("etherlink-operations-{}", uuid::Uuid::new_v4()));
+        let events = EventBus::new();
+        let mut subscriber = events.subscribe();
+        let tracker = OperationTracker::open(&dir).unwrap().with_events(events);
+        let payment = || TransactionBuilder::new(signer.address().unwrap(), Address::new("ghost1merchant".to_string())).amount(500).tracked(tracker.clone());
+
+        let submission = payment().submit(&ghostd, &signer).await.unwrap();
+        let id = submission.operation_id.clone().unwrap();
+        assert_eq!(tracker.get(&id).unwrap().state, TxState::Broadcast);
+        assert_eq!(tracker.find_by_hash(&submission.tx_hash).unwrap().id, id);
+
+        let finalized = ghostd.follow_operation(&tracker, &id, OperationClass::Payment).await.unwrap();
+        assert_eq!((finalized.state, finalized.block_height), (TxState::Finalized, Some(10)));
+        let states: Vec<TxState> = finalized.history.iter().map(|change| change.to).collect();
+        assert_eq!(states, vec![TxState::Signed, TxState::Broadcast, TxState::Pending, TxState::Confirmed, TxState::Finalized]);
+        for expected in &states {
+            match subscriber.recv().await.unwrap() {
+                EtherlinkEvent::TransactionStateChanged { id: event_id, to, .. } => assert_eq!((event_id.as_str(), to), (id.as_str(), *expected)),
+                other => panic!("unexpected event {:?}", other),
+            }
+        }
+
+        // Finalized is terminal, and the history survives reopening the tracker
+        assert!(matches!(tracker.transition(&id, Transition::to(TxState::Pending)).await, Err(EtherlinkError::Lifecycle(_))));
+        let reopened = OperationTracker::open(&dir).unwrap();
+        assert_eq!(reopened.get(&id), Some(finalized));
+        assert!(reopened.active().is_empty());
+
+        // A rejected submission is recorded as failed with the node's error
+        assert!(payment().nonce(9).submit(&ghostd, &signer).await.is_err());
+        let failed = tracker.operations().into_iter().find(|operation| operation.nonce == 9).unwrap();
+        assert_eq!(failed.state, TxState::Failed);
+        assert!(failed.error.unwrap().contains("insufficient funds"));
+        assert!(tracker.active().is_empty());
+
+        // A lost answer leaves the operation unknown until following it finds out what the node did
+        for nonce in [12, 14] {
+            Mock::given(method("POST"))
+                .and(path("/api/v1/transactions"))
+                .and(body_partial_json(serde_json::json!({ "nonce": nonce })))
+                .respond_with(ResponseTemplate::new(502))
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/accounts/{}/nonce", signer.address().unwrap())))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": { "nonce": 13 } })))
+            .mount(&mock_server)
+            .await;
+        // Nonce 12 was taken by another transaction; nonce 14 is still free, so GHOSTD never got it
+        for (nonce, settled) in [(12, TxState::Replaced), (14, TxState::Failed)] {
+            assert!(matches!(payment().nonce(nonce).submit(&ghostd, &signer).await, Err(EtherlinkError::Network(_))));
+            let lost = tracker.active().into_iter().find(|operation| operation.nonce == nonce).unwrap();
+            assert_eq!(lost.state, TxState::Unknown);
+            assert!(lost.tx_hash.is_some());
+            let followed = ghostd.follow_operation(&tracker, &lost.id, OperationClass::Payment).await.unwrap();
+            assert_eq!(followed.state, settled);
+        }
+        assert!(tracker.active().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_correlation_id_propagates_to_requests_and_events() {
+        use etherlink::clients::CallContext;
+        use etherlink::clients::ghostd::TransactionBuilder;
+        use etherlink::correlation::{self, CorrelationId, CORRELATION_HEADER};
+        use etherlink::lifecycle::OperationTracker;
+        use etherlink::{CryptoAlgorithm, CryptoProvider, EtherlinkEvent, EventBus};
+        use wiremock::matchers::header;
+
+        let id = CorrelationId::new();
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .and(header(CORRELATION_HEADER, id.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": { "height": 7 } })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/transactions"))
+            .and(header(CORRELATION_HEADER, id.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0xcorrelated", "status": "pending" }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+
+        // Outside an operation no ID is sent; inside one, or set on the context, it is
+        assert!(ghostd.get_blockchain_height().await.is_err());
+        assert_eq!(correlation::scope(id.clone(), ghostd.get_blockchain_height()).await.unwrap(), 7);
+        assert_eq!(ghostd.with_context(CallContext::new().correlation_id(id.clone())).get_blockchain_height().await.unwrap(), 7);
+        assert_eq!(correlation::current(), None);
+
+        // Events caused by the operation carry its ID
+        let signer = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let events = EventBus::new();
+        let mut subscriber = events.subscribe();
+        let tracker = OperationTracker::new().with_events(events);
+        let payment = TransactionBuilder::new(signer.address().unwrap(), Address::new("ghost1merchant".to_string())).amount(500).tracked(tracker);
+        correlation::scope(id.clone(), payment.submit(&ghostd, &signer)).await.unwrap();
+        match subscriber.recv().await.unwrap() {
+            EtherlinkEvent::TransactionStateChanged { correlation_id, .. } => assert_eq!(correlation_id, Some(id)),
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        // IDs from elsewhere keep only what a header and a C string can carry
+        assert_eq!(CorrelationId::from_string("req-1\0\r\nx-admin: yes").as_str(), "req-1x-admin:yes");
+        assert_eq!(CorrelationId::from_string("a".repeat(300)).as_str().len(), 128);
+        assert!(!CorrelationId::from_string("\0 \n").as_str().is_empty());
+        let decoded: CorrelationId = serde_json::from_str("\"bad\\u0000id\"").unwrap();
+        assert_eq!(decoded.as_str(), "badid");
+    }
+
+    #[tokio::test]
+    async fn test_gid_policy_cache_evaluates_locally() {
+        use etherlink::clients::gid::{GidClient, PolicyRequest};
+        use std::time::Duration;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/guardian/policies/bundle"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "version": "7",
+                    "rules": [
+                        { "id": "read-all", "effect": "allow", "actions": ["read"] },
+                        { "id": "small-transfers", "effect": "allow", "actions": ["tx.*"],
+                          "conditions": [{ "attribute": "amount", "op": "le", "value": 1000 }] },
+                        { "id": "no-admin", "effect": "deny", "resources": ["admin/*"] }
+                    ]
+                }
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/guardian/evaluate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "allowed": false, "reason": "remote", "conditions": [], "expires_at": null }
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let gid = GidClient::new(&config, Arc::new(HttpClient::new())).with_policy_cache(Duration::from_secs(60));
+        let request = |action: &str, resource: &str, context: serde_json::Value| PolicyRequest {
+            identity: "did:ghost:bot".to_string(),
+            action: action.to_string(),
+            resource: resource.to_string(),
+            context: serde_json::from_value(context).unwrap(),
+        };
+
+        // Decided from the bundle, fetched once
+        let read = gid.evaluate_policy(request("read", "balances", serde_json::json!({}))).await.unwrap();
+        assert!(read.allowed);
+        assert!(read.reason.contains("read-all"));
+        let transfer = gid.evaluate_policy(request("tx.transfer", "wallet", serde_json::json!({ "amount": 10 }))).await.unwrap();
+        assert!(transfer.allowed);
+        let large = gid.evaluate_policy(request("tx.transfer", "wallet", serde_json::json!({ "amount": 5000 }))).await;
+        assert_eq!(large.unwrap().reason, "remote");
+        let admin = gid.evaluate_policy(request("read", "admin/keys", serde_json::json!({}))).await.unwrap();
+        assert!(!admin.allowed);
+        assert!(admin.reason.contains("no-admin"));
+
+        // A missing attribute defers to the server
+        let unknown = gid.evaluate_policy(request("tx.transfer", "wallet", serde_json::json!({}))).await.unwrap();
+        assert_eq!(unknown.reason, "remote");
+
+        // Invalidation forces a refetch
+        gid.invalidate_policy_cache();
+        assert!(gid.evaluate_policy(request("read", "balances", serde_json::json!({}))).await.unwrap().allowed);
+    }
+
+    #[tokio::test]
+    async fn test_permission_preflight_fails_fast() {
+        use etherlink::auth::AuthToken;
+        use etherlink::clients::CallContext;
+        use etherlink::clients::gledger::TokenTransfer;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": { "height": 42 } })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(403))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let token = AuthToken {
+            token_id: "reader".to_string(),
+            identity: "did:ghost:reader".to_string(),
+            permissions: vec![Permission::ReadBlockchain, Permission::TransferTokens(TokenType::MANA)],
+            issued_at: 0,
+            expires_at: u64::MAX,
+            signature: String::new(),
+            algorithm: "ed25519".to_string(),
+        };
+        let ctx = CallContext::new().token(&token);
+
+        assert_eq!(clients.ghostd.with_context(ctx.clone()).get_blockchain_height().await.unwrap(), 42);
+
+        let transfer = TokenTransfer {
+            from: Address::new("ghost1reader".to_string()),
+            to: Address::new("ghost1other".to_string()),
+            token_type: TokenType::GCC,
+            amount: 1,
+            memo: None,
+        };
+        match clients.gledger.with_context(ctx.clone()).transfer_tokens(transfer).await {
+            Err(etherlink::EtherlinkError::PermissionDenied { required }) => {
+                assert_eq!(required, vec![Permission::TransferTokens(TokenType::GCC)]);
+            }
+            other => panic!("expected PermissionDenied, got {:?}", other),
+        }
+        assert!(matches!(
+            clients.cns.with_context(ctx).resolve_domain("alice.ghost").await,
+            Err(etherlink::EtherlinkError::PermissionDenied { .. })
+        ));
+
+        // Admin covers everything
+        let admin = CallContext::new().permissions(vec![Permission::Admin]);
+        assert_eq!(clients.ghostd.with_context(admin).get_blockchain_height().await.unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn test_ghostd_admin_operations_require_admin() {
+        use etherlink::clients::CallContext;
+        use etherlink::clients::ghostd::{NodeKey, ProductionMode, SnapshotStatus};
+        use wiremock::matchers::body_json;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/admin/sequencer/start"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "data": { "mode": "sequencing", "active": true, "since": 1700000000 }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/admin/log-level"))
+            .and(body_json(serde_json::json!({ "filter": "info,consensus=debug" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "data": { "previous": "info", "current": "info,consensus=debug" }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/admin/snapshots"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "snapshot_id": "snap-1", "height": 100, "state_root": "0xabc", "status": "pending", "size_bytes": null }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/admin/keys/rotate"))
+            .and(body_json(serde_json::json!({ "key": "rpc_tls" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "key": "rpc_tls", "previous_public_key": "old", "new_public_key": "new", "effective_at_height": 101 }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let operator = clients.ghostd.with_context(CallContext::new().permissions(vec![Permission::Admin]));
+
+        let status = operator.start_sequencing().await.unwrap();
+        assert_eq!(status.mode, Some(ProductionMode::Sequencing));
+        assert!(status.active);
+        assert_eq!(operator.set_log_level("info,consensus=debug").await.unwrap().previous, "info");
+        let snapshot = operator.trigger_snapshot().await.unwrap();
+        assert_eq!((snapshot.height, snapshot.status), (100, SnapshotStatus::Pending));
+        assert_eq!(operator.rotate_key(NodeKey::RpcTls).await.unwrap().effective_at_height, 101);
+
+        // Invalid arguments and non-admin tokens never reach the node
+        assert!(operator.set_log_level(" ").await.is_err());
+        assert!(operator.prune_state(0).await.is_err());
+        let reader = clients.ghostd.with_context(CallContext::new().permissions(vec![Permission::ReadBlockchain]));
+        assert!(matches!(
+            reader.start_sequencing().await,
+            Err(etherlink::EtherlinkError::PermissionDenied { required }) if required == vec![Permission::Admin]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_validator_client_staking_and_slashing() {
+        use etherlink::clients::CallContext;
+        use etherlink::clients::validator::{SlashReason, ValidatorRegistration, ValidatorStatus};
+        use wiremock::matchers::{body_json, query_param};
+
+        let mock_server = MockServer::start().await;
+        let validator = serde_json::json!({
+            "address": "ghost1val", "moniker": "val-1", "consensus_public_key": "0xab", "status": "jailed",
+            "bonded_stake": 5000, "commission_bps": 500, "jailed_until": 900
+        });
+        Mock::given(method("POST"))
+            .and(path("/api/v1/validators"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": validator })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/validators/ghost1val/bond"))
+            .and(body_json(serde_json::json!({ "amount": 250 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "tx_hash": "0x1", "bonded_stake": 5250, "unbonding_stake": 0, "release_height": null }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/validators/ghost1val/missed-blocks"))
+            .and(query_param("window", "100"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "window_blocks": 100, "signed": 95, "missed": 5, "missed_heights": [801, 802] }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/validators/ghost1val/slashing"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": [{ "height": 850, "reason": "downtime", "amount": 50, "jailed_until": 900 }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+        let operator = clients.validator.with_context(
+            CallContext::new().permissions(vec![Permission::ManageValidator, Permission::ReadValidators]),
+        );
+        let address = Address::new("ghost1val".to_string());
+
+        let mut registration = ValidatorRegistration {
+            address: address.clone(),
+            consensus_public_key: "0xab".to_string(),
+            moniker: "val-1".to_string(),
+            commission_bps: 500,
+            self_stake: 5000,
+        };
+        let info = operator.register_validator(registration.clone()).await.unwrap();
+        assert_eq!((info.status, info.jailed_until), (ValidatorStatus::Jailed, Some(900)));
+        assert_eq!(operator.bond_stake(&address, 250).await.unwrap().bonded_stake, 5250);
+        assert!(operator.unbond_stake(&address, 0).await.is_err());
+
+        let missed = operator.get_missed_blocks(&address, 100).await.unwrap();
+        assert!((missed.uptime() - 0.95).abs() < f64::EPSILON);
+        let events = operator.get_slashing_events(&address).await.unwrap();
+        assert_eq!(events[0].reason, SlashReason::Downtime);
+
+        registration.commission_bps = 20_000;
+        registration.moniker = String::new();
+        match operator.register_validator(registration).await {
+            Err(etherlink::EtherlinkError::InvalidConfig(errors)) => {
+                assert!(errors.has_field("commission_bps") && errors.has_field("moniker"));
+            }
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+
+        // Reading validator state does not allow managing one
+        let reader = clients.validator.with_context(CallContext::new().permissions(vec![Permission::ReadValidators]));
+        assert!(matches!(
+            reader.bond_stake(&address, 1).await,
+            Err(etherlink::EtherlinkError::PermissionDenied { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_download_verifies_and_resumes_chunks() {
+        use etherlink::hash::{to_hex, HashAlgorithm, Hasher};
+        use wiremock::matchers::header;
+
+        let data = b"ghostchain-state".to_vec();
+        let sha = |bytes: &[u8]| to_hex(&Hasher::digest(HashAlgorithm::Sha256, bytes));
+        let chunks: Vec<&[u8]> = data.chunks(6).collect();
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/snapshots/500"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "height": 500, "state_root": "0xroot", "size_bytes": data.len(), "chunk_size": 6,
+                    "chunks": chunks.iter().map(|chunk| sha(chunk)).collect::<Vec<_>>(), "sha256": sha(&data)
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        for (index, chunk) in chunks.iter().enumerate() {
+            let start = index * 6;
+            let body = if index == 1 { b"XXXXXX".to_vec() } else { chunk.to_vec() };
+            Mock::given(method("GET"))
+                .and(path("/api/v1/snapshots/500/data"))
+                .and(header("range", format!("bytes={}-{}", start, start + chunk.len() - 1).as_str()))
+                .respond_with(ResponseTemplate::new(206).set_body_bytes(body))
+                .mount(&mock_server)
+                .await;
+        }
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+
+        // The corrupted second chunk is retried, then fails without being written
+        let mut partial = std::io::Cursor::new(Vec::new());
+        let result = clients.ghostd.download_snapshot(500, &mut partial, 0, |_| {}).await;
+        assert!(matches!(result, Err(etherlink::EtherlinkError::Crypto(_))));
+        assert_eq!(partial.get_ref(), &data[..6]);
+
+        // Once the server is fixed, the download resumes after the first chunk
+        mock_server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/snapshots/500"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "height": 500, "state_root": "0xroot", "size_bytes": data.len(), "chunk_size": 6,
+                    "chunks": chunks.iter().map(|chunk| sha(chunk)).collect::<Vec<_>>(), "sha256": sha(&data)
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/snapshots/500/data"))
+            .and(header("range", "bytes=0-5"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes(chunks[0].to_vec()))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+        for (index, chunk) in chunks.iter().enumerate().skip(1) {
+            let start = index * 6;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/snapshots/500/data"))
+                .and(header("range", format!("bytes={}-{}", start, start + chunk.len() - 1).as_str()))
+                .respond_with(ResponseTemplate::new(206).set_body_bytes(chunk.to_vec()))
+                .mount(&mock_server)
+                .await;
+        }
+
+        let mut progress = Vec::new();
+        let manifest = clients.ghostd
+            .download_snapshot(500, &mut partial, 6, |p| progress.push(p.downloaded_bytes))
+            .await
+            .unwrap();
+        assert_eq!(partial.get_ref(), &data);
+        assert_eq!(progress, vec![12, 16]);
+        assert_eq!(manifest.resume_offset(10), 6);
+        assert_eq!(manifest.resume_offset(16), 12);
+        assert!(clients.ghostd.download_snapshot(500, &mut std::io::Cursor::new(Vec::new()), 5, |_| {}).await.is_err());
+
+        // A corrupted prefix is fetched again rather than trusted
+        let mut tampered = data[..12].to_vec();
+        tampered[8] ^= 0xff;
+        let mut tampered = std::io::Cursor::new(tampered);
+        let mut progress = Vec::new();
+        clients.ghostd
+            .download_snapshot(500, &mut tampered, 12, |p| progress.push(p.downloaded_bytes))
+            .await
+            .unwrap();
+        assert_eq!(tampered.get_ref(), &data);
+        assert_eq!(progress, vec![12, 16]);
+    }
+
+    #[tokio::test]
+    async fn test_fee_history_and_chain_stats() {
+        use wiremock::matchers::query_param;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/fee-history"))
+            .and(query_param("block_count", "3"))
+            .and(query_param("percentiles", "10,50,90"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "oldest_block": 98,
+                    "base_fees": [100, 110, 105, 120],
+                    "gas_used_ratio": [0.5, 0.75, 1.0],
+                    "rewards": [[1, 5, 9], [2, 7, 20], [1, 3, 10]]
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/stats"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "height": 100, "window_blocks": 50, "transactions_per_second": 12.5,
+                    "average_block_time_ms": 2000, "gas_utilization": 0.6, "total_transactions": 1250
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+
+        let history = clients.ghostd.get_fee_history(3, &[10.0, 50.0, 90.0]).await.unwrap();
+        assert_eq!(history.next_base_fee(), Some(120));
+        assert_eq!(history.priority_fee(50.0), Some(5));
+        assert_eq!(history.suggest_gas_price(90.0), Some(130));
+        assert_eq!(history.priority_fee(75.0), None);
+        assert!((history.average_gas_used_ratio() - 0.75).abs() < 1e-9);
+
+        let stats = clients.ghostd.get_chain_stats().await.unwrap();
+        assert_eq!(stats.average_block_time_ms, 2000);
+        assert_eq!(stats.total_transactions, 1250);
+
+        assert!(clients.ghostd.get_fee_history(0, &[50.0]).await.is_err());
+        assert!(clients.ghostd.get_fee_history(3, &[90.0, 10.0]).await.is_err());
+        assert!(clients.ghostd.get_fee_history(3, &[101.0]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_guardian_request_signing_rejects_replays() {
+        use etherlink::auth::{AuthToken, GuardianAuthProvider, ReplayGuard};
+        use etherlink::auth::crypto::{CryptoAlgorithm, CryptoProvider};
+        use etherlink::clients::gid::GidClient;
+
+        let server_time = chrono::Utc::now().timestamp() as u64 + 100;
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/guardian/signing-policy"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "max_skew_seconds": 300, "server_time": server_time }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let key = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let gid = Arc::new(GidClient::new(&config, Arc::new(HttpClient::new())));
+        let provider = GuardianAuthProvider::new(gid).with_signing_key(key.clone());
+        let token = AuthToken {
+            token_id: "t1".to_string(),
+            identity: "did:ghost:alice".to_string(),
+            permissions: vec![],
+            issued_at: 0,
+            expires_at: u64::MAX,
+            signature: String::new(),
+            algorithm: "Guardian".to_string(),
+        };
+
+        let policy = provider.sync_signing_policy().await.unwrap();
+        let guard = ReplayGuard::from_policy(&policy);
+        let body = br#"{"amount":5}"#;
+        let headers = provider.sign_request(&token, "post", "/api/v1/transactions", body).unwrap();
+        let timestamp: i64 = headers["X-Guardian-Timestamp"].parse().unwrap();
+        assert!((timestamp - server_time as i64).abs() <= 2);
+        assert_ne!(headers["X-Guardian-Nonce"], provider.sign_request(&token, "POST", "/", b"").unwrap()["X-Guardian-Nonce"]);
+
+        // Tampering with the request breaks the signature
+        assert!(guard.verify_request(&headers, "POST", "/api/v1/transactions", br#"{"amount":500}"#, &key.public_key, &CryptoAlgorithm::Ed25519).is_err());
+        assert!(guard.verify_request(&headers, "POST", "/api/v1/blocks", body, &key.public_key, &CryptoAlgorithm::Ed25519).is_err());
+
+        // Accepted once, then rejected as a replay
+        guard.verify_request(&headers, "POST", "/api/v1/transactions", body, &key.public_key, &CryptoAlgorithm::Ed25519).unwrap();
+        assert!(guard.verify_request(&headers, "POST", "/api/v1/transactions", body, &key.public_key, &CryptoAlgorithm::Ed25519).is_err());
+
+        // Outside the skew window even a fresh nonce is refused
+        assert!(guard.check_at(timestamp, "fresh", timestamp + 301).is_err());
+        guard.check_at(timestamp, "fresh", timestamp + 300).unwrap();
+
+        // The window follows the injected clock
+        let clock = etherlink::clock::MockClock::new(1_700_000_000);
+        let guard = ReplayGuard::new(std::time::Duration::from_secs(300)).with_clock(clock.clone());
+        guard.check(1_700_000_000, "first").unwrap();
+        clock.advance(std::time::Duration::from_secs(300));
+        guard.check(1_700_000_000, "second").unwrap();
+        clock.advance(std::time::Duration::from_secs(1));
+        assert!(guard.check(1_700_000_000, "third").is_err());
+        assert!(guard.check(1_700_000_301, "first").is_err());
+
+        let unsigned = GuardianAuthProvider::new(Arc::new(GidClient::new(&config, Arc::new(HttpClient::new()))));
+        assert!(unsigned.sign_request(&token, "GET", "/", b"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_did_messaging_round_trip() {
+        use etherlink::auth::crypto::{CryptoAlgorithm, CryptoProvider};
+        use etherlink::clients::gid::{GidClient, IdentityDocument, VerificationMethod};
+        use etherlink::messaging::{encode_multibase, KeyAgreementKey, Messenger, MessagingIdentity, ED25519_MULTICODEC};
+
+        let identity = |did: &str| {
+            let signing_key = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+            MessagingIdentity::new(did, signing_key, KeyAgreementKey::generate())
+        };
+        let document = |identity: &MessagingIdentity| {
+            let signing_public: [u8; 32] = hex::decode(&identity.signing_key.public_key).unwrap().try_into().unwrap();
+            let method = |fragment: &str, public_key_multibase: String| VerificationMethod {
+                id: format!("{}#{}", identity.did, fragment),
+                method_type: fragment.to_string(),
+                controller: identity.did.clone(),
+                public_key_multibase,
+            };
+            IdentityDocument {
+                context: vec!["https://www.w3.org/ns/did/v1".to_string()],
+                id: identity.did.clone(),
+                verification_method: vec![
+                    method("sign", encode_multibase(&ED25519_MULTICODEC, &signing_public)),
+                    method("agree", identity.agreement_key.public_key_multibase()),
+                ],
+                authentication: vec![format!("{}#sign", identity.did)],
+                assertion_method: vec![],
+                key_agreement: vec![format!("{}#agree", identity.did)],
+                capability_invocation: vec![],
+                capability_delegation: vec![],
+                service: vec![],
+                metadata: Default::default(),
+            }
+        };
+        let alice = identity("did:ghost:alice");
+        let bob = identity("did:ghost:bob");
+        let (alice_doc, bob_doc) = (document(&alice), document(&bob));
+
+        let mock_server = MockServer::start().await;
+        for doc in [&alice_doc, &bob_doc] {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/v1/identities/resolve/{}", doc.id)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": doc })))
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/api/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": {} })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let http = Arc::new(HttpClient::new());
+        let gid = GidClient::new(&config, http.clone());
+        let alice_messenger = Messenger::new(alice, gid.clone(), http.clone(), &mock_server.uri());
+        let bob_messenger = Messenger::new(bob, gid, http, &mock_server.uri());
+
+        alice_messenger.send("did:ghost:bob", b"hi bob").await.unwrap();
+
+        let envelope = alice_messenger.seal(&bob_doc, b"gm").unwrap();
+        assert!(!envelope.payload.ciphertext.contains("gm"));
+        let mut forged = alice_messenger.seal(&bob_doc, b"spoof").unwrap();
+        forged.id = "forged".to_string();
+        Mock::given(method("GET"))
+            .and(path("/api/v1/messages/did:ghost:bob"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": [forged.clone(), envelope.clone()] })))
+            .mount(&mock_server)
+            .await;
+        // A bad envelope is reported on its own and does not hide the good one
+        let inbox = bob_messenger.receive().await.unwrap();
+        assert_eq!(inbox.messages.len(), 1);
+        assert_eq!(inbox.messages[0].from, "did:ghost:alice");
+        assert_eq!(inbox.messages[0].payload, b"gm");
+        assert_eq!(inbox.rejected.len(), 1);
+        assert_eq!(inbox.rejected[0].id, forged.id);
+
+        // Tampered ciphertext, a forged sender and the wrong recipient are all refused
+        let mut tampered = envelope.clone();
+        tampered.payload.ciphertext = envelope.payload.ciphertext.chars().rev().collect();
+        assert!(bob_messenger.open(&tampered, &alice_doc).is_err());
+        assert!(bob_messenger.open(&envelope, &bob_doc).is_err());
+        assert!(alice_messenger.open(&envelope, &alice_doc).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gsig_sign_typed_data_checks_signer() {
+        use etherlink::clients::gsig::GsigClient;
+        use wiremock::matchers::body_partial_json;
+
+        let signature = "0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c";
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/signatures/sign-typed-data"))
+            .and(body_partial_json(serde_json::json!({ "typed_data": { "primaryType": "Mail" } })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "signature": signature,
+                    "public_key": "",
+                    "algorithm": "Secp256k1",
+                    "message_hash": "0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2",
+                    "signature_id": null
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let gsig = GsigClient::new(&config, Arc::new(HttpClient::new()));
+        let mail = eip712_mail();
+
+        let cow = Address::new("0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826".to_string());
+        assert_eq!(gsig.sign_typed_data(&mail, &cow).await.unwrap().signature, signature);
+
+        // A signature from any other key is refused
+        let bob = Address::new("0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB".to_string());
+        assert!(matches!(gsig.sign_typed_data(&mail, &bob).await, Err(etherlink::EtherlinkError::Crypto(_))));
+    }
+
+    #[tokio::test]
+    async fn test_guardian_auto_refresh_runs_ahead_of_expiry() {
+        use etherlink::auth::{AuthConfig, AuthCredentials, AuthRefreshEvent, AuthSecret, GuardianAuthManager};
+        use etherlink::clients::gid::GidClient;
+        use etherlink::runtime::TaskSupervisor;
+        use std::time::Duration;
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        let token = |id: &str, expires_at: u64| ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "success": true,
+            "data": {
+                "token_id": id, "identity": "did:ghost:alice", "permissions": [],
+                "issued_at": now - 3600, "expires_at": expires_at, "signature": "sig"
+            }
+        }));
+
+        // The first token is one second from the refresh threshold; the first refresh fails
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST")).and(path("/api/v1/guardian/tokens"))
+            .respond_with(token("t1", now + 301)).up_to_n_times(1).mount(&mock_server).await;
+        Mock::given(method("POST")).and(path("/api/v1/guardian/tokens"))
+            .respond_with(ResponseTemplate::new(503)).up_to_n_times(1).mount(&mock_server).await;
+        Mock::given(method("POST")).and(path("/api/v1/guardian/tokens"))
+            .respond_with(token("t2", now + 3600)).mount(&mock_server).await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let gid = Arc::new(GidClient::new(&config, Arc::new(HttpClient::new())));
+        let manager = Arc::new(GuardianAuthManager::new(gid, AuthConfig::default()));
+        let mut events = manager.subscribe_refreshes();
+        manager.authenticate(&AuthCredentials {
+            identity: "did:ghost:alice".to_string(),
+            secret: AuthSecret::Password("secret".into()),
+            permissions: vec![],
+        }).await.unwrap();
+
+        let supervisor = TaskSupervisor::new();
+        manager.start_auto_refresh(&supervisor).await.unwrap();
+
+        // The failure is reported and retried without waiting for a request to need the token
+        let failed = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+        assert!(matches!(failed, AuthRefreshEvent::Failed { expires_at, .. } if expires_at == now + 301));
+        let refreshed = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+        assert_eq!(refreshed, AuthRefreshEvent::Refreshed { expires_at: now + 3600 });
+        assert_eq!(manager.get_current_token().await.unwrap().token_id, "t2");
+
+        // Cancelled through the supervisor
+        assert!(tokio::time::timeout(Duration::from_secs(1), supervisor.stop("guardian-auth-refresh")).await.unwrap());
+
+        // A token issued for less than the threshold is refreshed half way through, not on arrival
+        let short = etherlink::auth::AuthToken {
+            token_id: "t3".to_string(),
+            identity: "did:ghost:alice".to_string(),
+            permissions: vec![],
+            issued_at: now,
+            expires_at: now + 60,
+            signature: "sig".to_string(),
+            algorithm: "Guardian".to_string(),
+        };
+        assert_eq!(short.refresh_at(AuthConfig::default().refresh_threshold_seconds), now + 30);
+        assert!(!short.needs_refresh_at(300, now));
+        assert!(short.needs_refresh_at(300, now + 30));
+    }
+
+    #[tokio::test]
+    async fn test_gledger_coalesces_concurrent_reads() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/tokens/balances/ghost1coalesce"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_delay(std::time::Duration::from_millis(100))
+                .set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "address": "ghost1coalesce", "gcc": 7, "spirit": 0, "mana": 0, "ghost": 0 }
+                })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let gledger_client = GledgerClient::new(&config, Arc::new(HttpClient::new()));
+
+        let address = Address::new("ghost1coalesce".to_string());
+        let (a, b) = tokio::join!(
+            gledger_client.get_all_balances(&address),
+            gledger_client.get_all_balances(&address)
+        );
+
+        assert_eq!(a.unwrap().gcc, 7);
+        assert_eq!(b.unwrap().gcc, 7);
+        let stats = gledger_client.coalesce_stats();
+        assert_eq!(stats.executed, 1);
+        assert_eq!(stats.coalesced, 1);
+    }
+
+    #[tokio::test]
+    async fn test_ghostd_read_cache_serves_and_invalidates_balances() {
+        use etherlink::{EtherlinkEvent, EventBus};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/accounts/ghost1cached/balance"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "balance": 42, "address": "ghost1cached" }
+                })))
+            .expect(4)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let ghostd_client = GhostdClient::new(&config, Arc::new(HttpClient::new()))
+            .with_read_cache(etherlink::cache::ReadCacheConfig::default());
+
+        let address = Address::new("ghost1cached".to_string());
+        assert_eq!(ghostd_client.get_balance(&address).await.unwrap(), 42);
+        assert_eq!(ghostd_client.get_balance(&address).await.unwrap(), 42);
+
+        ghostd_client.on_new_block(100);
+        assert_eq!(ghostd_client.get_balance(&address).await.unwrap(), 42);
+        assert_eq!(ghostd_client.get_blockchain_height().await.unwrap(), 100);
+
+        // Repeated or older heights don't invalidate anything
+        ghostd_client.on_new_block(100);
+        ghostd_client.on_new_block(99);
+        assert_eq!(ghostd_client.get_balance(&address).await.unwrap(), 42);
+
+        // Block events reach the cache through the follower
+        let bus = EventBus::new();
+        let _follower = ghostd_client.spawn_block_follower(&bus);
+        tokio::task::yield_now().await;
+        bus.publish(EtherlinkEvent::NewBlock { height: 101, hash: "0xb101".to_string() });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(ghostd_client.get_blockchain_height().await.unwrap(), 101);
+        assert_eq!(ghostd_client.get_balance(&address).await.unwrap(), 42);
+
+        bus.publish(EtherlinkEvent::ReorgDetected { height: 101, old_hash: "0xb101".to_string(), new_hash: "0xc101".to_string() });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(ghostd_client.get_balance(&address).await.unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn test_health_report_aggregates_services() {
+        use etherlink::runtime::{Etherlink, ServiceState};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/v1/health"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "status": "ok", "version": "1.2.0" })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let runtime = Etherlink::new(config).unwrap();
+
+        let report = runtime.health_report().await;
+        assert_eq!(report.services.len(), 7);
+
+        let ghostd = report.service("ghostd").unwrap();
+        assert_eq!(ghostd.state, ServiceState::Healthy);
+        assert_eq!(ghostd.version.as_deref(), Some("1.2.0"));
+
+        // GhostPlane's bridge is never initialized here
+        assert_eq!(report.service("ghostplane").unwrap().state, ServiceState::Unhealthy);
+        assert!(!report.is_ready());
+    }
+
+    #[tokio::test]
+    async fn test_optional_service_degrades_instead_of_failing() {
+        use etherlink::runtime::{DaemonConfig, DegradationConfig, DegradedCallPolicy, Etherlink, ServiceState};
+        use std::time::Duration;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "status": "ok" })))
+            .mount(&mock_server)
+            .await;
+
+        let mut settings = DaemonConfig::default();
+        settings.etherlink.ghostd_endpoint = mock_server.uri();
+        settings.degradation = DegradationConfig {
+            optional: ["ghostplane".to_string()].into(),
+            ..Default::default()
+        };
+        let runtime = Etherlink::from_daemon_config(settings.clone()).unwrap();
+
+        // The uninitialized bridge degrades the runtime rather than failing it
+        let report = runtime.check_availability().await;
+        assert_eq!(report.service("ghostplane").unwrap().state, ServiceState::Degraded);
+        assert_eq!(report.overall(), ServiceState::Degraded);
+        assert_eq!(runtime.availability().unavailable(), vec!["ghostplane".to_string()]);
+
+        let rejected = runtime.call_optional("ghostplane", async { Ok(()) }).await;
+        assert!(matches!(rejected, Err(etherlink::EtherlinkError::ServiceUnavailable(_))));
+        assert!(runtime.call_optional("ghostd", async { Ok(()) }).await.is_ok());
+
+        // Queued calls run once the service is back
+        settings.degradation.policy = DegradedCallPolicy::Queue { max_wait_ms: 5_000 };
+        runtime.reload(settings).await.unwrap();
+        let availability = runtime.availability().clone();
+        let recover = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            availability.mark_up("ghostplane");
+        };
+        let (queued, ()) = tokio::join!(runtime.call_optional("ghostplane", async { Ok(7) }), recover);
+        assert_eq!(queued.unwrap(), 7);
+
+        let mut invalid = DegradationConfig::default();
+        invalid.optional.insert("ghostd".to_string());
+        assert!(invalid.validate().unwrap_err().has_field("optional"));
+    }
+
+    #[tokio::test]
+    async fn test_service_made_optional_by_reload_is_probed() {
+        use etherlink::runtime::{DaemonConfig, Etherlink};
+        use std::time::Duration;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "status": "ok" })))
+            .mount(&mock_server)
+            .await;
+
+        // Started with no optional services, the probe task still runs
+        let mut settings = DaemonConfig::default();
+        settings.etherlink.ghostd_endpoint = mock_server.uri();
+        let runtime = Etherlink::from_daemon_config(settings.clone()).unwrap();
+        runtime.start().await.unwrap();
+        assert!(runtime.supervisor().tasks().await.iter().any(|task| task.name == "optional-service-probe"));
+
+        settings.degradation.optional.insert("gledger".to_string());
+        settings.degradation.probe_interval_ms = 20;
+        runtime.reload(settings).await.unwrap();
+        runtime.availability().mark_down("gledger", "test");
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !runtime.availability().is_available("gledger") {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        runtime.shutdown(Duration::from_secs(5)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_preflight_reports_chain_and_clock_problems_with_fixes() {
+        use etherlink::runtime::{CheckCategory, CheckStatus, Etherlink};
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "status": "ok" })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/performance/metrics"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "version": "1.0.0", "chain_id": 42, "peer_count": 3, "uptime_seconds": 10,
+                    "transactions_per_second": 0.0, "blocks_per_second": 0.0, "memory_usage_mb": 1,
+                    "cpu_usage_percent": 0.0, "network_in_bytes": 0, "network_out_bytes": 0
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/guardian/signing-policy"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "max_skew_seconds": 300, "server_time": now + 200 }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let runtime = Etherlink::new(config).unwrap();
+        let report = runtime.preflight().await;
+
+        assert_eq!(report.category(CheckCategory::Config).next().unwrap().status, CheckStatus::Pass);
+        let chain = report.category(CheckCategory::Chain).next().unwrap();
+        assert_eq!(chain.status, CheckStatus::Fail);
+        assert!(chain.fix.as_deref().unwrap().contains("Set chain_id to 42"));
+        assert_eq!(report.category(CheckCategory::Clock).next().unwrap().status, CheckStatus::Warn);
+        assert_eq!(report.category(CheckCategory::Auth).next().unwrap().status, CheckStatus::Skipped);
+        // Loopback endpoints may use plain HTTP
+        assert!(report.category(CheckCategory::Tls).all(|check| check.status == CheckStatus::Pass));
+        assert!(!report.passed());
+
+        let expired = etherlink::AuthToken {
+            token_id: "t".to_string(), identity: "did:ghost:alice".to_string(), permissions: vec![],
+            issued_at: 0, expires_at: now - 1, signature: String::new(), algorithm: "Guardian".to_string(),
+        };
+        let report = runtime.preflight_with_token(Some(&expired)).await;
+        assert_eq!(report.category(CheckCategory::Auth).next().unwrap().status, CheckStatus::Fail);
+    }
+
+    #[tokio::test]
+    async fn test_clock_skew_is_estimated_from_watched_services_and_compensated() {
+        use etherlink::timesync::SkewEstimator;
+        use etherlink::{Clock, MockClock};
+        use std::time::Duration;
+
+        // GHOSTD's clock runs an hour ahead of ours
+        let hour_ms: i64 = 3_600_000;
+        let server_now = chrono::Utc::now() + chrono::Duration::hours(1);
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("date", server_now.to_rfc2822().as_str())
+                    .set_body_json(serde_json::json!({ "success": true, "data": { "height": 7 } })),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/time"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("date", server_now.to_rfc2822().as_str())
+                    .set_body_json(serde_json::json!({ "success": true, "data": { "unix_ms": server_now.timestamp_millis() } })),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let ghostd = GhostdClient::new(&config, Arc::new(HttpClient::new()));
+        let skew = SkewEstimator::new(Duration::from_secs(5));
+
+        // Only watched services' Date headers are sampled
+        ghostd.get_blockchain_height().await.unwrap();
+        assert_eq!(skew.samples(), 0);
+        skew.watch(&mock_server.uri());
+        ghostd.get_blockchain_height().await.unwrap();
+        assert_eq!(skew.samples(), 1);
+        assert!((skew.offset_ms() - hour_ms).abs() < 5_000);
+        assert!(skew.is_skewed());
+
+        // An explicit time check agrees
+        let offset = ghostd.sync_clock(&skew).await.unwrap();
+        assert!((offset - hour_ms).abs() < 5_000);
+
+        // A corrected clock reads server time, so expiry checks are judged by it
+        let corrected = skew.clock(MockClock::new(1_700_000_000));
+        assert!(corrected.now().abs_diff(1_700_000_000 + 3600) <= 5);
+
+        skew.set_warn_threshold(Duration::from_secs(7200));
+        assert!(!skew.is_skewed());
+
+        // An unwatched service is no longer sampled
+        skew.unwatch(&mock_server.uri());
+        let samples = skew.samples();
+        ghostd.get_blockchain_height().await.unwrap();
+        assert_eq!(skew.samples(), samples);
+
+        // Against an injected local clock that already reads server time, there is no skew
+        let local = SkewEstimator::new(Duration::from_secs(5)).with_clock(MockClock::new(server_now.timestamp() as u64));
+        local.watch(&mock_server.uri());
+        assert!(ghostd.sync_clock(&local).await.unwrap().abs() < 5_000);
+        ghostd.get_blockchain_height().await.unwrap();
+        assert!(local.offset_ms().abs() < 5_000);
+        assert!(!local.is_skewed());
+    }
+
+    #[tokio::test]
+    async fn test_capture_diagnostics_records_requests_and_redacts_secrets() {
+        use etherlink::diagnostics::{redact_json, redact_url, REDACTED};
+        use etherlink::runtime::Etherlink;
+        use std::time::Duration;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "status": "ok" })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/height"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let runtime = Etherlink::new(config).unwrap();
+        let ghostd = runtime.services().ghostd.clone();
+
+        let during = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            ghostd.get_blockchain_height().await
+        };
+        let (bundle, height) = tokio::join!(runtime.capture_diagnostics(Duration::from_millis(100)), during);
+        assert!(height.is_err());
+        let bundle = bundle.unwrap();
+
+        // Captures only see the runtime's own services
+        assert!(bundle.requests.iter().all(|r| r.url.starts_with(&mock_server.uri())));
+        let ours: Vec<_> = bundle.requests.iter().collect();
+        let failed = ours.iter().find(|r| r.url.ends_with("/api/v1/blockchain/height")).unwrap();
+        assert_eq!(failed.status, Some(503));
+        assert!(failed.is_error());
+        assert!(ours.iter().any(|r| r.url.ends_with("/api/v1/health") && r.status == Some(200)));
+        assert!(bundle.errors().any(|r| r == *failed));
+        assert!(bundle.duration_ms >= 100);
+        assert!(bundle.log_filter.is_none());
+        let key = format!("GET {}/api/v1/blockchain/height", mock_server.uri().trim_start_matches("http://"));
+        assert_eq!(bundle.latency[&key].errors, 1);
+        let json: serde_json::Value = serde_json::from_str(&bundle.to_json()).unwrap();
+        assert!(json["health"]["services"].is_array());
+
+        let url = redact_url("https://user:pw@gid.example/api/v1/login?identity=alice&auth_token=abc");
+        assert!(!url.contains("abc") && !url.contains("pw"));
+        assert!(url.contains("identity=alice"));
+        let mut value = serde_json::json!({ "guardian": { "refresh_token": "abc", "endpoint": "x" }, "keys": [{ "private_key": "k" }] });
+        redact_json(&mut value);
+        assert_eq!(value["guardian"]["refresh_token"], REDACTED);
+        assert_eq!(value["guardian"]["endpoint"], "x");
+        assert_eq!(value["keys"][0]["private_key"], REDACTED);
+
+        // Keys are matched by name, and URLs in values lose their credentials
+        let mut value = serde_json::json!({
+            "token_lifetime_seconds": 3600,
+            "accessToken": "abc",
+            "proxy": "socks5://user:pw@proxy.example:1080",
+            "endpoint": "https://ghostd.example:8545",
+        });
+        redact_json(&mut value);
+        assert_eq!(value["token_lifetime_seconds"], 3600);
+        assert_eq!(value["accessToken"], REDACTED);
+        assert!(!value["proxy"].as_str().unwrap().contains("pw"));
+        assert_eq!(value["endpoint"], "https://ghostd.example:8545");
+    }
+
+    #[tokio::test]
+    async fn test_diagnostic_capture_only_records_watched_origins() {
+        use etherlink::diagnostics::DiagnosticCapture;
+
+        let watched = MockServer::start().await;
+        let other = MockServer::start().await;
+        for server in [&watched, &other] {
+            Mock::given(method("GET"))
+                .and(path("/api/v1/blockchain/height"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true, "data": 7 })))
+                .mount(server)
+                .await;
+        }
+
+        let capture = DiagnosticCapture::start(16);
+        capture.watch(&watched.uri());
+        for server in [&watched, &other] {
+            let mut config = EtherlinkConfig::default();
+            config.ghostd_endpoint = server.uri();
+            let _ = GhostdClient::new(&config, Arc::new(HttpClient::new())).get_blockchain_height().await;
+        }
+
+        let records = capture.finish();
+        assert_eq!(records.len(), 1);
+        assert!(records[0].url.starts_with(&watched.uri()));
+    }
+
+    #[tokio::test]
+    async fn test_search_classifies_and_dispatches_queries() {
+        use etherlink::runtime::{Etherlink, SearchQuery, SearchResult};
+
+        let evm = "0x52908400098527886e0f7030069857d2e4169ee7";
+        let tx = format!("0x{}", "ab".repeat(32));
+        assert_eq!(SearchQuery::classify(" #42 "), Some(SearchQuery::Block(42)));
+        assert_eq!(SearchQuery::classify(&tx), Some(SearchQuery::Transaction(TxHash::new(tx.clone()))));
+        assert_eq!(SearchQuery::classify(evm), Some(SearchQuery::Address(Address::new(evm.to_string()))));
+        assert_eq!(SearchQuery::classify("Alice.Ghost"), Some(SearchQuery::Domain("alice.ghost".to_string())));
+        assert_eq!(SearchQuery::classify("10.0.0.1"), None);
+        assert_eq!(SearchQuery::classify("hello"), None);
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/block/42"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "height": 42, "hash": "0xb", "previous_hash": "0xa", "timestamp": 1, "transactions": [],
+                    "merkle_root": "0xm", "gas_used": 0, "gas_limit": 100
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/accounts/{}/balance", evm)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "data": { "balance": 77, "address": evm }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/transactions/{}/receipt", tx)))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "success": false, "data": null, "error": "transaction not found"
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/blockchain/block/43"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": false, "data": null, "error": "database locked"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let runtime = Etherlink::new(config).unwrap();
+
+        assert!(matches!(runtime.search("#42").await.unwrap(), SearchResult::Block { block } if block.hash == "0xb"));
+        assert!(matches!(
+            runtime.search(evm).await.unwrap(),
+            SearchResult::Account { balance: 77, .. }
+        ));
+        assert!(matches!(runtime.search("alice.ghost").await.unwrap(), SearchResult::Domain { resolution } if resolution.domain == "alice.ghost"));
+        let missing = runtime.search(&tx).await.unwrap();
+        assert!(!missing.is_found());
+        assert!(matches!(missing, SearchResult::NotFound { query: SearchQuery::Transaction(_) }));
+        // A rejection other than 404 is a failure, not an empty result
+        assert!(matches!(runtime.search("#43").await, Err(etherlink::EtherlinkError::Api(_))));
+        assert!(matches!(runtime.search("???").await.unwrap(), SearchResult::Unrecognized { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_domain_marketplace_escrows_offers_and_announces_sales() {
+        use etherlink::clients::cns::{ListingFilter, OfferStatus};
+        use etherlink::runtime::Etherlink;
+        use etherlink::EtherlinkEvent;
+        use wiremock::matchers::{body_partial_json, query_param};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/marketplace/listings"))
+            .and(query_param("tld", "ghost"))
+            .and(query_param("max_price", "500"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": [{
+                    "listing_id": "l1", "domain": "alice.ghost", "seller": "ghost1seller", "price": 400,
+                    "token": "GCC", "created_at": 1, "expires_at": null
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow"))
+            .and(body_partial_json(serde_json::json!({ "amount": 350, "reference": "cns-offer:alice.ghost" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "data": { "escrow_id": "e1", "tx_hash": "0xe", "token_type": "GCC", "amount": 350 }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/marketplace/offers"))
+            .and(body_partial_json(serde_json::json!({ "escrow_id": "e1" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "offer_id": "o1", "domain": "alice.ghost", "buyer": "ghost1buyer", "amount": 350, "token": "GCC",
+                    "escrow_id": "e1", "status": "open", "expires_at": null
+                }
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/marketplace/offers/o1/accept"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "domain": "alice.ghost", "seller": "ghost1seller", "buyer": "ghost1buyer", "price": 350,
+                    "token": "GCC", "offer_id": "o1", "transfer_tx_hash": "0xt", "payment_tx_hash": "0xp"
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let runtime = Etherlink::new(config).unwrap();
+        let mut events = runtime.subscribe_events();
+        let marketplace = runtime.marketplace();
+
+        let listings = marketplace.browse(&ListingFilter::new().tld("ghost").price_range(100, 500)).await.unwrap();
+        assert_eq!(listings[0].domain, "alice.ghost");
+
+        let buyer = Address::new("ghost1buyer".to_string());
+        let offer = marketplace.make_offer("alice.ghost", buyer.clone(), 350, TokenType::GCC, None).await.unwrap();
+        assert_eq!((offer.escrow_id.as_str(), offer.status), ("e1", OfferStatus::Open));
+
+        let sale = marketplace.accept_offer("o1").await.unwrap();
+        assert_eq!(sale.payment_tx_hash, TxHash::new("0xp".to_string()));
+        match events.recv().await.unwrap() {
+            EtherlinkEvent::DomainSold { domain, buyer: sold_to, price, .. } => {
+                assert_eq!((domain.as_str(), sold_to, price), ("alice.ghost", buyer.clone(), 350));
+            }
+            other => panic!("expected DomainSold, got {:?}", other),
+        }
+
+        // A refused offer refunds its escrow
+        Mock::given(method("POST"))
+            .and(path("/api/v1/marketplace/offers"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": false, "data": null, "error": "offer below reserve"
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow/e1/refund"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "data": { "tx_hash": "0xr", "status": "confirmed" }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let refused = marketplace.make_offer("alice.ghost", buyer.clone(), 350, TokenType::GCC, None).await;
+        assert!(matches!(refused, Err(etherlink::EtherlinkError::Api(_))));
+        mock_server.verify().await;
+
+        // A lost answer is reconciled against CNS instead of refunding an escrow in use
+        mock_server.reset().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "data": { "escrow_id": "e2", "tx_hash": "0xe", "token_type": "GCC", "amount": 350 }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/marketplace/offers"))
+            .respond_with(ResponseTemplate::new(502).set_body_string("<html>bad gateway</html>"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/marketplace/domains/alice.ghost/offers"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": [{
+                    "offer_id": "o2", "domain": "alice.ghost", "buyer": "ghost1buyer", "amount": 350, "token": "GCC",
+                    "escrow_id": "e2", "status": "open", "expires_at": null
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/escrow/e2/refund"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+        let recovered = marketplace.make_offer("alice.ghost", buyer, 350, TokenType::GCC, None).await.unwrap();
+        assert_eq!(recovered.offer_id, "o2");
+    }
+
+    #[tokio::test]
+    async fn test_grpc_health_and_reflection_server() {
+        use etherlink::runtime::{Etherlink, ServerConfig};
+        use tonic_health::pb::{health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest};
+        use tonic_reflection::pb::v1::{
+            server_reflection_client::ServerReflectionClient, server_reflection_request::MessageRequest,
+            server_reflection_response::MessageResponse, ServerReflectionRequest,
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "status": "ok" })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        config.timeout_ms = 500;
+        let runtime = Etherlink::new(config).unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let probe_addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let server_config = ServerConfig { health_interval_ms: 50, probe_addr: Some(probe_addr), ..Default::default() };
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+
+        let server = runtime.serve_with_listener(listener, &server_config, async {
+            let _ = stopped.await;
+        });
+        let client = async {
+            let channel = tonic::transport::Channel::from_shared(url.clone()).unwrap().connect().await.unwrap();
+            let mut health = HealthClient::new(channel.clone());
+            let check = |service: &str| HealthCheckRequest { service: service.to_string() };
+
+            // Wait for the first report to be published
+            let ghostd = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+                loop {
+                    if let Ok(response) = health.check(check("ghostd")).await {
+                        return response.into_inner().status;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                }
+            })
+            .await
+            .unwrap();
+            assert_eq!(ghostd, ServingStatus::Serving as i32);
+
+            // GhostPlane is down, so the server as a whole is not ready
+            let overall = health.check(check("")).await.unwrap().into_inner().status;
+            assert_eq!(overall, ServingStatus::NotServing as i32);
+
+            // The HTTP probes agree: alive, but not ready, with the report as the body
+            let http = HttpClient::new();
+            let healthz = http.get(format!("http://{}/healthz", probe_addr)).send().await.unwrap();
+            assert_eq!(healthz.status(), 200);
+            let readyz = http.get(format!("http://{}/readyz", probe_addr)).send().await.unwrap();
+            assert_eq!(readyz.status(), 503);
+            let report: etherlink::runtime::HealthReport = readyz.json().await.unwrap();
+            assert!(report.service("ghostd").is_some());
+            assert_eq!(http.get(format!("http://{}/other", probe_addr)).send().await.unwrap().status(), 404);
+
+            let mut reflection = ServerReflectionClient::new(channel);
+            let request = ServerReflectionRequest {
+                host: String::new(),
+                message_request: Some(MessageRequest::ListServices(String::new())),
+            };
+            let mut responses = reflection
+                .server_reflection_info(tokio_stream::iter(vec![request]))
+                .await
+                .unwrap()
+                .into_inner();
+            let services = match responses.message().await.unwrap().unwrap().message_response {
+                Some(MessageResponse::ListServicesResponse(list)) => list.service.into_iter().map(|s| s.name).collect::<Vec<_>>(),
+                other => panic!("unexpected reflection response: {:?}", other),
+            };
+            assert!(services.contains(&"grpc.health.v1.Health".to_string()));
+            assert!(services.contains(&"grpc.reflection.v1.ServerReflection".to_string()));
+            // Client-side protos are not served here, so reflection must not advertise them
+            assert!(!services.iter().any(|s| s.contains("GhostChainService")));
+            assert!(services.iter().all(|s| s.starts_with("grpc.")), "unexpected services: {:?}", services);
+
+            stop.send(()).unwrap();
+        };
+
+        let (result, _) = tokio::join!(server, client);
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_version_negotiation_gates_newer_methods() {
+        use etherlink::version::{ApiVersion, Feature};
+        use etherlink::EtherlinkError;
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/version"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "success": true, "data": { "version": "1.0.3" } })))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let clients = ServiceClients::new(&config, Arc::new(HttpClient::new()));
+
+        let versions = clients.negotiate_versions().await;
+        assert_eq!(versions.get("ghostd"), Some(&ApiVersion::new(1, 0, 3)));
+        assert!(!clients.versions.supports("ghostd", Feature::BlockHeaders));
+
+        let err = clients.ghostd.get_block_header(1).await.unwrap_err();
+        assert!(matches!(err, EtherlinkError::Unsupported(_)));
+        assert!(err.to_string().contains("requires >= 1.1.0"));
+
+        // Every endpoint newer than the daemon is refused before any request is sent
+        assert!(matches!(
+            clients.ghostd.at_block(1).get_balance(&Address::new("ghost1old".to_string())).await,
+            Err(EtherlinkError::Unsupported(_))
+        ));
+        assert!(matches!(clients.ghostd.latest_snapshot().await, Err(EtherlinkError::Unsupported(_))));
+        assert!(matches!(clients.ghostd.get_chain_stats().await, Err(EtherlinkError::Unsupported(_))));
+        assert!(matches!(clients.ghostd.get_fee_history(4, &[50.0]).await, Err(EtherlinkError::Unsupported(_))));
+        assert!(matches!(clients.ghostd.get_server_time().await, Err(EtherlinkError::Unsupported(_))));
+    }
+
+    // Plain test: the C API blocks on its own runtime, so the mock server gets a separate one
+    #[test]
+    fn test_c_api_round_trip() {
+        use etherlink::capi::*;
+        use std::ffi::{CStr, CString};
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = runtime.block_on(async {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/accounts/ghost1capi/balance"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "address": "ghost1capi", "balance": 4200 }
+                })))
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/api/v1/transactions"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "tx_hash": "0xc0ffee", "status": "pending" }
+                })))
+                .mount(&mock_server)
+                .await;
+            mock_server
+        });
+
+        let mut config = EtherlinkConfig::default();
+        config.ghostd_endpoint = mock_server.uri();
+        let config_json = CString::new(serde_json::to_string(&config).unwrap()).unwrap();
+
+        unsafe {
+            let handle = etherlink_new(config_json.as_ptr());
+            assert!(!handle.is_null());
+
+            let address = CString::new("ghost1capi").unwrap();
+            let mut balance = 0u64;
+            assert_eq!(etherlink_get_balance(handle, address.as_ptr(), &mut balance), EtherlinkStatus::Ok);
+            assert_eq!(balance, 4200);
+            assert!(etherlink_last_error().is_null());
+
+            let tx = CString::new(serde_json::json!({
+                "from": "ghost1capi", "to": "ghost1dest", "amount": 1,
+                "gas_limit": 21000, "gas_price": 1, "nonce": 0,
+                "data": null, "signature": "abcd"
+            }).to_string()).unwrap();
+            let mut tx_hash = std::ptr::null_mut();
+            assert_eq!(etherlink_submit_tx(handle, tx.as_ptr(), &mut tx_hash), EtherlinkStatus::Ok);
+            assert_eq!(CStr::from_ptr(tx_hash).to_str().unwrap(), "0xc0ffee");
+            etherlink_string_free(tx_hash);
+
+            let bad_tx = CString::new("not json").unwrap();
+            assert_eq!(etherlink_submit_tx(handle, bad_tx.as_ptr(), &mut tx_hash), EtherlinkStatus::InvalidArgument);
+            assert!(!etherlink_last_error().is_null());
+            assert_eq!(etherlink_get_balance(handle, std::ptr::null(), &mut balance), EtherlinkStatus::InvalidArgument);
+
+            etherlink_free(handle);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_transport_binary_bodies() {
+        use etherlink::transport::{codec, ContentType};
+        use wiremock::matchers::header;
+
+        let mock_server = MockServer::start().await;
+        let payload = serde_json::json!({ "method": "ping", "id": 1 });
+
+        Mock::given(method("POST"))
+            .and(path("/rpc"))
+            .and(header("content-type", "application/msgpack"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_raw(codec::encode_msgpack(&payload).unwrap().to_vec(), "application/msgpack"))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/rpc"))
+            .and(header("content-type", "application/json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&payload))
+            .mount(&mock_server)
+            .await;
+
+        let mut config = TransportConfig::default();
+        config.use_gquic = false;
+        let transport = HttpTransport::new(config).unwrap();
+        let endpoint = format!("{}/rpc", mock_server.uri());
+
+        let body = codec::encode_msgpack(&payload).unwrap();
+        let response = transport.send_request(&endpoint, ContentType::MessagePack, body).await.unwrap();
+        let decoded: serde_json::Value = codec::decode_msgpack(&response).unwrap();
+        assert_eq!(decoded, payload);
+
+        let json = transport.send_json_request(&endpoint, payload.clone()).await.unwrap();
+        assert_eq!(json, payload);
+
+        let stats = transport.get_stats().await.unwrap();
+        assert_eq!(stats.total_requests, 2);
+        assert!(stats.bytes_sent > 0);
+    }
+}
+
+/// In-process CNS gRPC service answering `ResolveDomain` and `ReverseResolve` from
+/// fixed domains and streaming fixed changes to each `SubscribeDomainChanges` call
+mod mock_cns {
+    use etherlink::cns::{DomainChangeEvent, DomainResolution, RecordSet, ServiceType};
+    use etherlink::proto::cns::v1::{self as cns_pb, cns_service_server::{CnsService, CnsServiceServer}};
+    use etherlink::Address;
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tonic::{Code, Request, Response, Status};
+
+    #[derive(Debug, Clone, Default)]
+    pub struct MockCns {
+        domains: HashMap<String, cns_pb::CnsResolveResponse>,
+        failures: HashMap<String, Code>,
+        prices: HashMap<String, (u64, String)>,
+        delay: Duration,
+        changes: Vec<cns_pb::CnsDomainChangeEvent>,
+        subscriptions: Arc<Mutex<Vec<cns_pb::CnsDomainSubscription>>>,
+        reverse_lookups: Arc<AtomicUsize>,
+    }
+
+    /// `domain` owned by `owner`, pointing at localhost
+    pub fn resolution(domain: &str, owner: &str, expires_at: u64) -> DomainResolution {
+        DomainResolution {
+            domain: domain.to_string(),
+            owner: Address::new(owner.to_string()),
+            records: RecordSet::builder().a(Ipv4Addr::LOCALHOST).aaaa(Ipv6Addr::LOCALHOST).build().unwrap(),
+            metadata: HashMap::new(),
+            expires_at,
+            service_type: ServiceType::Blockchain,
+            blockchain_address: Some(Address::new(owner.to_string())),
+            ipfs_hash: None,
+            web5_did: None,
+        }
+    }
+
+    impl MockCns {
+        pub fn domain(mut self, resolution: DomainResolution) -> Self {
+            self.domains.insert(resolution.domain.clone(), resolution.into());
+            self
+        }
+
+        /// Answer lookups of `domain` with `resolution`, whatever domain it is for
+        pub fn answer(mut self, domain: &str, resolution: DomainResolution) -> Self {
+            self.domains.insert(domain.to_string(), resolution.into());
+            self
+        }
+
+        /// Fail lookups of `domain` with `code`
+        pub fn failure(mut self, domain: &str, code: Code) -> Self {
+            self.failures.insert(domain.to_string(), code);
+            self
+        }
+
+        /// Quote `cost` in `token` for registering `domain`
+        pub fn price(mut self, domain: &str, cost: u64, token: &str) -> Self {
+            self.prices.insert(domain.to_string(), (cost, token.to_string()));
+            self
+        }
+
+        /// Answer every lookup only after `delay`
+        pub fn delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+
+        /// Stream `change` to every subscriber, unfiltered, before ending the stream
+        pub fn change(mut self, change: DomainChangeEvent) -> Self {
+            self.changes.push(change.into());
+            self
+        }
+
+        /// Subscription requests received so far
+        pub fn subscriptions(&self) -> Arc<Mutex<Vec<cns_pb::CnsDomainSubscription>>> {
+            self.subscriptions.clone()
+        }
+
+        /// Number of `ReverseResolve` calls received so far
+        pub fn reverse_lookups(&self) -> Arc<AtomicUsize> {
+            self.reverse_lookups.clone()
+        }
+
+        /// Serve on a free local port, returning the endpoint
+        pub async fn start(self) -> String {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let endpoint = format!("http://{}", listener.local_addr().unwrap());
+            tokio::spawn(
+                tonic::transport::Server::builder()
+                    .add_service(CnsServiceServer::new(self))
+                    .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+            );
+            endpoint
+        }
+    }
+
+    #[tonic::async_trait]
+    impl CnsService for MockCns {
+        async fn resolve_domain(&self, request: Request<cns_pb::CnsResolveRequest>) -> Result<Response<cns_pb::CnsResolveResponse>, Status> {
+            tokio::time::sleep(self.delay).await;
+            let domain = request.into_inner().domain;
+            if let Some(code) = self.failures.get(&domain) {
+                return Err(Status::new(*code, format!("{} refused", domain)));
+            }
+            self.domains
+                .get(&domain)
+                .cloned()
+                .map(Response::new)
+                .ok_or_else(|| Status::not_found(format!("{} is not registered", domain)))
+        }
+
+        async fn register_domain(&self, _: Request<cns_pb::CnsRegisterRequest>) -> Result<Response<cns_pb::CnsRegisterResponse>, Status> {
+            Err(Status::unimplemented("register_domain"))
+        }
+
+        async fn update_domain_records(&self, _: Request<cns_pb::CnsUpdateRequest>) -> Result<Response<cns_pb::CnsUpdateResponse>, Status> {
+            Err(Status::unimplemented("update_domain_records"))
+        }
+
+        async fn transfer_domain(&self, _: Request<cns_pb::CnsTransferRequest>) -> Result<Response<cns_pb::CnsTransferResponse>, Status> {
+            Err(Status::unimplemented("transfer_domain"))
+        }
+
+        async fn renew_domain(&self, _: Request<cns_pb::CnsRenewRequest>) -> Result<Response<cns_pb::CnsRenewResponse>, Status> {
+            Err(Status::unimplemented("renew_domain"))
+        }
+
+        async fn check_availability(&self, request: Request<cns_pb::CnsAvailabilityRequest>) -> Result<Response<cns_pb::CnsAvailabilityResponse>, Status> {
+            let domain = request.into_inner().domain;
+            let available = !self.domains.contains_key(&domain);
+            let (estimated_cost, cost_token) = match self.prices.get(&domain) {
+                Some(price) => price.clone(),
+                None if available => return Err(Status::unimplemented(format!("no price for {}", domain))),
+                None => (0, "GCC".to_string()),
+            };
+            Ok(Response::new(cns_pb::CnsAvailabilityResponse {
+                domain,
+                available,
+                reason: if available { String::new() } else { "registered".to_string() },
+                expiry_timestamp: 0,
+                estimated_cost,
+                cost_token,
+            }))
+        }
+
+        type SubscribeDomainChangesStream = tokio_stream::Iter<std::vec::IntoIter<Result<cns_pb::CnsDomainChangeEvent, Status>>>;
+
+        async fn subscribe_domain_changes(&self, request: Request<cns_pb::CnsDomainSubscription>) -> Result<Response<Self::SubscribeDomainChangesStream>, Status> {
+            self.subscriptions.lock().unwrap().push(request.into_inner());
+            let changes: Vec<_> = self.changes.iter().cloned().map(Ok).collect();
+            Ok(Response::new(tokio_stream::iter(changes)))
+        }
+
+        async fn get_domain_history(&self, _: Request<cns_pb::CnsHistoryRequest>) -> Result<Response<cns_pb::CnsHistoryResponse>, Status> {
+            Err(Status::unimplemented("get_domain_history"))
+        }
+
+        async fn reverse_resolve(&self, request: Request<cns_pb::CnsReverseResolveRequest>) -> Result<Response<cns_pb::CnsReverseResolveResponse>, Status> {
+            self.reverse_lookups.fetch_add(1, Ordering::SeqCst);
+            let address = request.into_inner().address;
+            let domains: Vec<String> = self
+                .domains
+                .values()
+                .filter(|resolution| resolution.blockchain_address == address)
+                .map(|resolution| resolution.domain.clone())
+                .collect();
+            if domains.is_empty() {
+                return Err(Status::not_found(format!("nothing points to {}", address)));
+            }
+            Ok(Response::new(cns_pb::CnsReverseResolveResponse { address, domains }))
+        }
+
+        async fn health_check(&self, _: Request<()>) -> Result<Response<cns_pb::CnsHealthResponse>, Status> {
+            Err(Status::unimplemented("health_check"))
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_cns_endpoint_resolution() {
+    use etherlink::cns::CNSClientBuilder;
+    use etherlink::runtime::EndpointResolver;
+    use mock_cns::{resolution, MockCns};
+
+    let cns_endpoint = MockCns::default()
+        .domain(resolution("ghostd.ghost", "0x1234567890123456789012345678901234567890", 1_700_000_000))
+        .start()
+        .await;
+    let resolver = EndpointResolver::new(CNSClientBuilder::new().endpoint(cns_endpoint.clone()).build());
+
+    assert_eq!(resolver.cns_domain("http://ghostd.ghost:8545"), Some("ghostd.ghost".to_string()));
+    assert_eq!(resolver.cns_domain("http://localhost:8545"), None);
+    assert_eq!(resolver.resolve("http://localhost:8545").await.unwrap(), "http://localhost:8545");
+    assert!(resolver.overrides().get("localhost").is_none());
+
+    // The hostname stays in the URL for TLS; the address goes to the connectors
+    assert_eq!(resolver.resolve("https://ghostd.ghost:8545").await.unwrap(), "https://ghostd.ghost:8545");
+    assert_eq!(resolver.overrides().get("ghostd.ghost").unwrap(), vec!["127.0.0.1:8545".parse().unwrap()]);
+    resolver.invalidate("https://ghostd.ghost:8545").await;
+    assert!(resolver.overrides().get("ghostd.ghost").is_none());
+
+    // Lookups follow the dialer's address family preference, overridden or not
+    let ipv4_only = etherlink::transport::HostOverrides::new().with_dialer(etherlink::transport::Dialer::new(
+        etherlink::transport::DialConfig { family: etherlink::transport::AddressFamilyPreference::Ipv4Only, ..Default::default() },
+    ));
+    ipv4_only.set("dual.ghost", vec!["[::1]:1".parse().unwrap(), "127.0.0.1:1".parse().unwrap()]);
+    assert_eq!(ipv4_only.lookup("dual.ghost", 8545).await.unwrap(), vec!["127.0.0.1:8545".parse().unwrap()]);
+    assert!(ipv4_only.lookup("::1", 8545).await.is_err());
+
+    // A proxy cannot resolve CNS names: plain HTTP is dialed by address, TLS is refused
+    let proxied = EndpointResolver::new(resolver.cns().clone()).with_proxy(Some(etherlink::ProxyConfig::new("http://proxy:3128")));
+    assert_eq!(proxied.resolve_rest("http://ghostd.ghost:8545").await.unwrap(), "http://127.0.0.1:8545");
+    assert!(matches!(proxied.resolve_rest("https://ghostd.ghost:8545").await, Err(etherlink::EtherlinkError::Configuration(_))));
+    let bypassed = proxied.with_proxy(Some(etherlink::ProxyConfig::new("http://proxy:3128").with_no_proxy(".ghost")));
+    assert_eq!(bypassed.resolve_rest("https://ghostd.ghost:8545").await.unwrap(), "https://ghostd.ghost:8545");
+
+    // The runtime's REST client reaches a `.ghost` endpoint through the resolved address
+    let ghostd = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::method("GET"))
+        .and(wiremock::matchers::path("/api/v1/blockchain/height"))
+        .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "success": true,
+            "data": { "height": 42 }
+        })))
+        .mount(&ghostd)
+        .await;
+    let endpoint = format!("http://ghostd.ghost:{}", ghostd.address().port());
+    let mut config = EtherlinkConfig::default();
+    config.ghostd_endpoint = endpoint.clone();
+    config.cns_endpoint = Some(cns_endpoint);
+    let runtime = etherlink::Etherlink::new_resolved(config).await.unwrap();
+    assert_eq!(runtime.config().ghostd_endpoint, endpoint);
+    assert_eq!(runtime.services().ghostd.get_blockchain_height().await.unwrap(), 42);
+}
+
+#[tokio::test]
+async fn test_bulk_domain_registration_previews_and_reports_per_item() {
+    use etherlink::cns::{BulkItemStatus, CNSClientBuilder, DomainRegistration};
+    use mock_cns::{resolution, MockCns};
+    use std::collections::HashMap;
+
+    let owner = Address::new("0x1234567890123456789012345678901234567890".to_string());
+    let registration = |domain: &str, token: TokenType, amount: u64| DomainRegistration {
+        domain: domain.to_string(),
+        owner: owner.clone(),
+        initial_records: vec![],
+        metadata: HashMap::new(),
+        payment_token: token,
+        payment_amount: amount,
+    };
+    let batch = vec![
+        registration("brand.eth", TokenType::GCC, 100),
+        registration("brand-shop.eth", TokenType::GCC, 50),
+        registration("brand.crypto", TokenType::MANA, 7),
+        registration("alice.ghost", TokenType::GCC, 10),
+        registration("brand.eth", TokenType::GCC, 100),
+        registration("nodot", TokenType::GCC, 1),
+    ];
+    let cns_endpoint = MockCns::default()
+        .domain(resolution("alice.ghost", "0x1234567890123456789012345678901234567890", 1_700_000_000))
+        .price("brand.eth", 120, "GCC")
+        .price("brand-shop.eth", 40, "GCC")
+        .price("brand.crypto", 9, "MANA")
+        .start()
+        .await;
+    let cns = CNSClientBuilder::new().endpoint(cns_endpoint).build();
+
+    // CNS has alice.ghost taken and quotes its own prices for the rest
+    let preview = cns.register_domains(batch.clone(), true).await;
+    assert!(preview.dry_run);
+    assert_eq!(preview.items.len(), 6);
+    assert_eq!(preview.items[0].status, BulkItemStatus::Available);
+    assert_eq!(preview.items[0].cost, 120);
+    assert_eq!(preview.item("alice.ghost").unwrap().status, BulkItemStatus::Unavailable);
+    assert_eq!(preview.total_cost[&TokenType::GCC], 160);
+    assert_eq!(preview.total_cost[&TokenType::MANA], 9);
+    assert_eq!(preview.failures().count(), 2);
+
+    // A real run pays what each registration offers
+    let report = cns.register_domains(batch, false).await;
+    assert!(matches!(report.items[1].status, BulkItemStatus::Registered { .. }));
+    assert!(matches!(report.items[4].status, BulkItemStatus::Failed { .. }));
+    assert_eq!(report.total_cost[&TokenType::GCC], 150);
+    assert_eq!(report.total_cost[&TokenType::MANA], 7);
+}
+
+#[tokio::test]
+async fn test_idn_normalization_and_homograph_policy() {
+    use etherlink::cns::CNSClientBuilder;
+    use etherlink::idn::{self, HomographPolicy, HomographReason};
+    use mock_cns::{resolution, MockCns};
+
+    assert_eq!(idn::to_ascii("Alice.GHOST.").unwrap(), "alice.ghost");
+    assert_eq!(idn::to_ascii("bücher.ghost").unwrap(), "xn--bcher-kva.ghost");
+    assert_eq!(idn::to_unicode("xn--bcher-kva.ghost"), "bücher.ghost");
+    assert!(idn::detect_homographs("bücher.ghost").is_empty());
+    assert!(idn::detect_homographs("пример.ghost").is_empty());
+
+    // Cyrillic "а" in an otherwise Latin label, and an all-Cyrillic imitation of "paypal"
+    let spoof = idn::to_ascii("\u{0430}pple.ghost").unwrap();
+    assert!(spoof.starts_with("xn--"));
+    let findings = idn::detect_homographs(&spoof);
+    assert_eq!(findings.len(), 1);
+    assert!(matches!(findings[0].reason, HomographReason::MixedScript { .. }));
+    let findings = idn::detect_homographs("\u{0440}\u{0430}\u{0443}\u{0440}\u{0430}\u{04cf}.eth");
+    assert_eq!(findings[0].reason, HomographReason::Confusable { lookalike: "paypal".to_string() });
+
+    let cns_endpoint = MockCns::default()
+        .domain(resolution("alice.ghost", "0x1234567890123456789012345678901234567890", 1_700_000_000))
+        .start()
+        .await;
+    let strict = CNSClientBuilder::new().endpoint(cns_endpoint).homograph_policy(HomographPolicy::Reject).build();
+    let err = strict.resolve_domain(&spoof).await.unwrap_err();
+    assert!(err.to_string().contains("looks like another name"));
+    assert!(HomographPolicy::Warn.enforce(&spoof).is_ok());
+
+    // Case variants resolve, and are cached, under one normalized name
+    let resolution = strict.resolve_domain("ALICE.Ghost").await.unwrap();
+    assert_eq!(resolution.domain, "alice.ghost");
+}
+
+#[tokio::test]
+async fn test_cns_native_resolution_maps_grpc_status_and_deadline() {
+    use etherlink::cns::CNSClientBuilder;
+    use etherlink::EtherlinkError;
+    use mock_cns::{resolution, MockCns};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use tonic::Code;
+
+    let owner = "0x1234567890123456789012345678901234567890";
+    let cns_endpoint = MockCns::default()
+        .domain(resolution("alice.ghost", owner, 1_700_000_000))
+        .failure("lapsed.ghost", Code::FailedPrecondition)
+        .failure("private.ghost", Code::PermissionDenied)
+        .answer("carol.ghost", resolution("mallory.ghost", owner, 1_700_000_000))
+        .start()
+        .await;
+    let cns = CNSClientBuilder::new().endpoint(cns_endpoint).build();
+
+    let alice = cns.resolve_domain("alice.ghost").await.unwrap();
+    assert_eq!(alice.owner, Address::new(owner.to_string()));
+    assert_eq!(alice.records.a_records(), vec![Ipv4Addr::LOCALHOST]);
+    assert_eq!(alice.expires_at, 1_700_000_000);
+
+    // A missing name is free; other answers about the name are resolution errors
+    assert!(matches!(cns.resolve_domain("bob.ghost").await, Err(EtherlinkError::NotFound(msg)) if msg.contains("bob.ghost")));
+    assert!(cns.is_domain_available("bob.ghost").await.unwrap());
+    assert!(!cns.is_domain_available("alice.ghost").await.unwrap());
+    assert!(matches!(cns.resolve_domain("lapsed.ghost").await, Err(EtherlinkError::CnsResolution(_))));
+    assert!(matches!(cns.resolve_domain("private.ghost").await, Err(EtherlinkError::Authentication(_))));
+    assert!(matches!(cns.resolve_domain("carol.ghost").await, Err(EtherlinkError::CnsResolution(msg)) if msg.contains("mallory.ghost")));
+
+    // A slow or absent service is never taken to mean the name is free
+    let slow_endpoint = MockCns::default()
+        .domain(resolution("alice.ghost", owner, 1_700_000_000))
+        .delay(Duration::from_millis(500))
+        .start()
+        .await;
+    let impatient = CNSClientBuilder::new().endpoint(slow_endpoint).request_timeout_ms(50).build();
+    assert!(matches!(impatient.resolve_domain("alice.ghost").await, Err(EtherlinkError::Timeout(_))));
+    assert!(impatient.is_domain_available("alice.ghost").await.is_err());
+
+    let offline = CNSClientBuilder::new().endpoint("http://127.0.0.1:1").build();
+    assert!(matches!(offline.resolve_domain("alice.ghost").await, Err(EtherlinkError::ServiceUnavailable(_))));
+}
+
+#[tokio::test]
+async fn test_cns_domain_change_subscription_reconnects_and_invalidates_cache() {
+    use etherlink::cns::{ChangeEventType, CNSClientBuilder, DomainChangeEvent, DomainSubscription, SubscriptionBackoff};
+    use mock_cns::{resolution, MockCns};
+
+    let owner = "0x1234567890123456789012345678901234567890";
+    let change = |domain: &str, event_type: ChangeEventType| DomainChangeEvent {
+        domain: domain.to_string(),
+        event_type,
+        timestamp: 1_700_000_000,
+        old_value: Some(owner.to_string()),
+        new_value: Some("0x9999999999999999999999999999999999999999".to_string()),
+        tx_hash: Some("0xtransfer".to_string()),
+    };
+    let mock = MockCns::default()
+        .domain(resolution("alice.ghost", owner, u64::MAX))
+        .change(change("bob.ghost", ChangeEventType::Updated))
+        .change(change("alice.ghost", ChangeEventType::Transferred));
+    let subscriptions = mock.subscriptions();
+    let cns = CNSClientBuilder::new()
+        .endpoint(mock.start().await)
+        .subscription_backoff(SubscriptionBackoff { initial_ms: 10, max_ms: 50, max_attempts: Some(3) })
+        .build();
+
+    cns.resolve_domain("alice.ghost").await.unwrap();
+    assert_eq!(cns.cache_stats().await.entries, 1);
+
+    let subscription = DomainSubscription { domains: vec!["Alice.ghost".to_string()], record_types: vec!["a".to_string()], include_metadata: false };
+    let mut changes = cns.subscribe_domain_changes(subscription).await.unwrap();
+
+    // Changes to other domains are dropped, and a change evicts the cached resolution
+    let first = changes.recv().await.unwrap().unwrap();
+    assert_eq!((first.domain.as_str(), first.event_type.clone(), first.tx_hash.as_deref()), ("alice.ghost", ChangeEventType::Transferred, Some("0xtransfer")));
+    assert_eq!(cns.cache_stats().await.entries, 0);
+
+    // The service ends each stream; the subscription is reopened with the same filters
+    let second = changes.recv().await.unwrap().unwrap();
+    assert_eq!(second.domain, "alice.ghost");
+    let subscriptions = subscriptions.lock().unwrap().clone();
+    assert!(subscriptions.len() >= 2);
+    assert!(subscriptions.iter().all(|s| s.domains == vec!["alice.ghost".to_string()] && s.record_types == vec!["A".to_string()]));
+
+    // A subscription that cannot be opened at all fails up front
+    let offline = CNSClientBuilder::new().endpoint("http://127.0.0.1:1").build();
+    let empty = || DomainSubscription { domains: Vec::new(), record_types: Vec::new(), include_metadata: false };
+    assert!(offline.subscribe_domain_changes(empty()).await.is_err());
+
+    // Streams that keep ending without a change are backed off from and given up on
+    let silent = MockCns::default();
+    let reopened = silent.subscriptions();
+    let quiet = CNSClientBuilder::new()
+        .endpoint(silent.start().await)
+        .subscription_backoff(SubscriptionBackoff { initial_ms: 10, max_ms: 50, max_attempts: Some(3) })
+        .build();
+    let mut changes = quiet.subscribe_domain_changes(empty()).await.unwrap();
+    assert!(matches!(changes.recv().await.unwrap(), Err(etherlink::EtherlinkError::ServiceUnavailable(_))));
+    assert_eq!(reopened.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_cns_reverse_resolution_over_grpc_and_rest() {
+    use etherlink::cns::{CNSClientBuilder, DomainRegistration};
+    use std::collections::HashMap;
+    use mock_cns::{resolution, MockCns};
+    use std::sync::atomic::Ordering;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let alice = "0x1234567890123456789012345678901234567890";
+    let bob = "0x9999999999999999999999999999999999999999";
+    let mock = MockCns::default()
+        .domain(resolution("alice.ghost", alice, u64::MAX))
+        .domain(resolution("alice.gcc", alice, u64::MAX))
+        .domain(resolution("bob.ghost", bob, u64::MAX));
+    let lookups = mock.reverse_lookups();
+    let cns = CNSClientBuilder::new().endpoint(mock.start().await).build();
+
+    let address = Address::new(alice.to_string());
+    assert_eq!(cns.reverse_resolve(&address).await.unwrap(), vec!["alice.gcc".to_string(), "alice.ghost".to_string()]);
+    assert_eq!(cns.reverse_resolve(&address).await.unwrap().len(), 2);
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    assert_eq!(cns.reverse_cache_stats().await.hits, 1);
+
+    // Nothing points to an unknown address
+    let nobody = Address::new("0x0000000000000000000000000000000000000001".to_string());
+    assert!(cns.reverse_resolve(&nobody).await.unwrap().is_empty());
+
+    // A domain change drops every cached reverse lookup
+    cns.transfer_domain("alice.ghost", &address, &Address::new(bob.to_string())).await.unwrap();
+    assert_eq!(cns.reverse_cache_stats().await.entries, 0);
+    cns.reverse_resolve(&address).await.unwrap();
+    assert_eq!(lookups.load(Ordering::SeqCst), 3);
+
+    // So do renewals and registrations
+    cns.renew_domain("alice.gcc", &address, 1, 100).await.unwrap();
+    assert_eq!(cns.reverse_cache_stats().await.entries, 0);
+    cns.reverse_resolve(&address).await.unwrap();
+    cns.register_domain(DomainRegistration {
+        domain: "alice2.ghost".to_string(),
+        owner: address.clone(),
+        initial_records: Vec::new(),
+        metadata: HashMap::new(),
+        payment_token: TokenType::GCC,
+        payment_amount: 100,
+    }).await.unwrap();
+    assert_eq!(cns.reverse_cache_stats().await.entries, 0);
+
+    // The REST client asks the CNS gateway
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/api/v1/domains/reverse/{}", alice)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "success": true,
+            "data": { "address": alice, "domains": ["alice.ghost", "alice.gcc"] }
+        })))
+        .mount(&server)
+        .await;
+    let mut config = EtherlinkConfig::default();
+    config.cns_endpoint = Some(server.uri());
+    let rest = CnsClient::new(&config, Arc::new(HttpClient::new()));
+    assert_eq!(rest.reverse_resolve(&address).await.unwrap(), vec!["alice.ghost".to_string(), "alice.gcc".to_string()]);
+}
+
+#[tokio::test]
+async fn test_cns_resolves_unstoppable_domains_through_the_resolution_api() {
+    use etherlink::cns::{CNSClientBuilder, ServiceType};
+    use etherlink::correlation::{self, CorrelationId, CORRELATION_HEADER};
+    use etherlink::EtherlinkError;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let owner = "0x8aad44321a86b170879d7a244c1e8d360c99dda8";
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/resolve/domains/brad.crypto"))
+        .and(header("authorization", "Bearer ud-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "meta": { "domain": "brad.crypto", "owner": owner, "blockchain": "MATIC", "networkId": 137, "resolver": "0xa9a6", "tokenId": "53115498" },
+            "records": {
+                "crypto.ETH.address": owner,
+                "crypto.BTC.address": "bc1q359khn0phg58xgezyqsuuaha28zkwx047c0c3y",
+                "crypto.USDT.version.ERC20.address": owner,
+                "ipfs.html.value": "QmTiqc12wo2pBsGa9XsbpavkhrjFiyuSWsKyffvZqVGtut",
+                "ipfs.redirect_domain.value": ""
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/resolve/domains/unminted.nft"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "meta": { "domain": "unminted.nft", "owner": null }, "records": {} })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/resolve/domains/busy.x"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&mock_server)
+        .await;
+
+    let cns = CNSClientBuilder::new()
+        .unstoppable_endpoint(format!("{}/resolve", mock_server.uri()))
+        .unstoppable_api_key("ud-key")
+        .build();
+    let brad = cns.resolve_domain("brad.crypto").await.unwrap();
+    assert_eq!(brad.owner, Address::new(owner.to_string()));
+    assert_eq!(brad.service_type, ServiceType::Bridge);
+    assert_eq!(brad.blockchain_address, Some(Address::new(owner.to_string())));
+    assert_eq!(brad.records.address_for_chain("btc"), Some(Address::new("bc1q359khn0phg58xgezyqsuuaha28zkwx047c0c3y".to_string())));
+    assert_eq!(brad.records.address_for_chain("usdt.erc20"), Some(Address::new(owner.to_string())));
+    assert_eq!(brad.records.content_hash(), Some("ipfs://QmTiqc12wo2pBsGa9XsbpavkhrjFiyuSWsKyffvZqVGtut"));
+    assert_eq!(brad.ipfs_hash.as_deref(), Some("QmTiqc12wo2pBsGa9XsbpavkhrjFiyuSWsKyffvZqVGtut"));
+    assert_eq!(brad.metadata.get("ud.network_id").map(String::as_str), Some("137"));
+    assert!(!brad.metadata.contains_key("ipfs.redirect_domain.value"));
+
+    // An unminted name is not found; an outage is not mistaken for one
+    assert!(matches!(cns.resolve_domain("unminted.nft").await, Err(EtherlinkError::NotFound(msg)) if msg.contains("unminted.nft")));
+    assert!(matches!(cns.resolve_domain("busy.x").await, Err(EtherlinkError::ServiceUnavailable(_))));
+
+    // Without the key the API refuses the request
+    let keyless = CNSClientBuilder::new().unstoppable_endpoint(format!("{}/resolve", mock_server.uri())).build();
+    assert!(keyless.resolve_domain("brad.crypto").await.is_err());
+
+    // A third party never sees the operation's correlation ID
+    let traced = CNSClientBuilder::new()
+        .unstoppable_endpoint(format!("{}/resolve", mock_server.uri()))
+        .unstoppable_api_key("ud-key")
+        .build();
+    correlation::scope(CorrelationId::new(), traced.resolve_domain("brad.crypto")).await.unwrap();
+    let requests = mock_server.received_requests().await.unwrap();
+    assert!(requests.iter().all(|request| request.headers.get(CORRELATION_HEADER).is_none()));
+}
+
+#[test]
+fn test_record_set_typed_accessors_and_wire_round_trip() {
+    use etherlink::cns::RecordSet;
+    use std::collections::BTreeMap;
+    use std::net::Ipv4Addr;
+
+    let owner = Address::new("0x1234567890123456789012345678901234567890".to_string());
+    let records = RecordSet::builder()
+        .a(Ipv4Addr::new(10, 0, 0, 1))
+        .a(Ipv4Addr::new(10, 0, 0, 2))
+        .txt("v=spf1 -all")
+        .txt("site-verification=abc")
+        .mx(20, "backup.alice.ghost")
+        .mx(10, "mail.alice.ghost")
+        .address("eth", owner.clone())
+        .content_hash("ipfs://QmHash")
+        .build()
+        .unwrap();
+
+    assert_eq!(records.a_records(), vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)]);
+    assert_eq!(records.txt(), vec!["v=spf1 -all", "site-verification=abc"]);
+    assert_eq!(records.mx()[0], (10, "mail.alice.ghost"));
+    assert_eq!(records.address_for_chain("ETH"), Some(owner));
+    assert_eq!(records.content_hash(), Some("ipfs://QmHash"));
+    assert_eq!(records.port(), None);
+
+    let wire: BTreeMap<String, String> = records.clone().into();
+    assert_eq!(wire["A"], "10.0.0.1\n10.0.0.2");
+    assert_eq!(RecordSet::from(wire), records);
+    let json = serde_json::to_value(&records).unwrap();
+    assert_eq!(json["CONTENTHASH"], "ipfs://QmHash");
+    assert_eq!(serde_json::from_value::<RecordSet>(json).unwrap(), records);
+
+    let errors = RecordSet::builder()
+        .address("eth", Address::new("not-an-address".to_string()))
+        .content_hash("https://example.com")
+        .port(0)
+        .cname("a.ghost")
+        .cname("b.ghost")
+        .build()
+        .unwrap_err();
+    assert!(errors.has_field("records.ADDR:ETH[0]"));
+    assert!(errors.has_field("records.CONTENTHASH[0]"));
+    assert!(errors.has_field("records.PORT[0]"));
+    assert!(errors.has_field("records.CNAME"));
+
+    let wire: BTreeMap<String, String> = [("A".to_string(), "not-an-ip".to_string())].into_iter().collect();
+    let records = RecordSet::from(wire);
+    assert!(records.a_records().is_empty());
+    assert!(records.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_runtime_event_bus_collects_client_events() {
+    use etherlink::cns::{ChangeEventType, DomainRegistration};
+    use etherlink::EtherlinkEvent;
+    use std::collections::HashMap;
+
+    // alice.ghost is free, so it can be registered
+    let cns_endpoint = mock_cns::MockCns::default().start().await;
+    let runtime = etherlink::Etherlink::new(EtherlinkConfig { cns_endpoint: Some(cns_endpoint), ..Default::default() }).unwrap();
+    let mut events = runtime.subscribe_events();
+
+    let owner = Address::new("0x1234567890123456789012345678901234567890".to_string());
+    let tx_hash = runtime.cns().register_domain(DomainRegistration {
+        domain: "alice.ghost".to_string(),
+        owner: owner.clone(),
+        initial_records: vec![],
+        metadata: HashMap::new(),
+        payment_token: TokenType::GCC,
+        payment_amount: 1,
+    }).await.unwrap();
+    assert_eq!(events.recv().await.unwrap(), EtherlinkEvent::DomainChanged {
+        domain: "alice.ghost".to_string(),
+        change: ChangeEventType::Registered,
+        tx_hash,
+        correlation_id: None,
+    });
+
+    // Channel health is only reported when it crosses the failure budget
+    let endpoint = "http://127.0.0.1:1";
+    let channels = runtime.channels();
+    channels.get_channel(endpoint).await.unwrap();
+    for _ in 0..channels.config().max_failures {
+        channels.report_failure(endpoint).await;
+    }
+    channels.report_success(endpoint).await;
+    channels.report_success(endpoint).await;
+    let unhealthy = EtherlinkEvent::ConnectionStatusChanged { endpoint: endpoint.to_string(), healthy: false };
+    let healthy = EtherlinkEvent::ConnectionStatusChanged { endpoint: endpoint.to_string(), healthy: true };
+    assert_eq!(events.recv().await.unwrap(), unhealthy);
+    assert_eq!(events.recv().await.unwrap(), healthy);
+    assert!(events.try_recv().is_err());
+
+    // A channel dropped for failures and redialed reports itself healthy again
+    for _ in 0..channels.config().max_failures {
+        channels.report_failure(endpoint).await;
+    }
+    assert_eq!(events.recv().await.unwrap(), unhealthy);
+    channels.get_channel(endpoint).await.unwrap();
+    assert_eq!(events.recv().await.unwrap(), healthy);
+    channels.get_channel(endpoint).await.unwrap();
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_mock_clock_drives_cache_and_token_expiry() {
+    use etherlink::cns::CNSClientBuilder;
+    use etherlink::{Clock, MockClock};
+    use std::time::Duration;
+
+    let clock = MockClock::new(1_700_000_000);
+    let cns_endpoint = mock_cns::MockCns::default()
+        .domain(mock_cns::resolution("alice.ghost", "0x1234567890123456789012345678901234567890", 1_700_000_000 + 365 * 24 * 3600))
+        .start()
+        .await;
+    let cns = CNSClientBuilder::new().endpoint(cns_endpoint).cache_ttl_seconds(60).clock(clock.clone()).build();
+    let resolution = cns.resolve_domain("alice.ghost").await.unwrap();
+    assert_eq!(resolution.expires_at, 1_700_000_000 + 365 * 24 * 3600);
+
+    clock.advance(Duration::from_secs(59));
+    cns.cleanup_cache().await;
+    assert_eq!(cns.cache_stats().await.entries, 1);
+    clock.advance(Duration::from_secs(1));
+    cns.cleanup_cache().await;
+    assert_eq!(cns.cache_stats().await.entries, 0);
+
+    // Tokens expire by the injected clock too
+    let token = etherlink::AuthToken {
+        token_id: "t".to_string(), identity: "did:ghost:alice".to_string(), permissions: vec![],
+        issued_at: 0, expires_at: clock.now() + 10, signature: String::new(), algorithm: "Guardian".to_string(),
+    };
+    assert!(!token.is_expired_at(clock.now()));
+    clock.advance(Duration::from_secs(10));
+    assert!(token.is_expired_at(clock.now()));
+}
+
+#[tokio::test]
+async fn test_cns_cache_evicts_least_recently_used_and_expires_per_entry() {
+    use etherlink::cns::CNSClientBuilder;
+    use etherlink::MockClock;
+    use std::time::Duration;
+
+    let now = 1_700_000_000;
+    let owner = "0x1234567890123456789012345678901234567890";
+    let clock = MockClock::new(now);
+    let endpoint = mock_cns::MockCns::default()
+        .domain(mock_cns::resolution("alice.ghost", owner, u64::MAX))
+        .domain(mock_cns::resolution("bob.ghost", owner, u64::MAX))
+        // Registration lapses long before the cache TTL would
+        .domain(mock_cns::resolution("carol.ghost", owner, now + 10))
+        .start()
+        .await;
+    let cns = CNSClientBuilder::new()
+        .endpoint(endpoint)
+        .cache_ttl_seconds(60)
+        .max_cache_entries(2)
+        .cache_cleanup_interval_seconds(1)
+        .clock(clock.clone())
+        .build();
+
+    // Reading alice makes bob the least recently used, so carol evicts bob
+    cns.resolve_domain("alice.ghost").await.unwrap();
+    cns.resolve_domain("bob.ghost").await.unwrap();
+    cns.resolve_domain("alice.ghost").await.unwrap();
+    cns.resolve_domain("carol.ghost").await.unwrap();
+    cns.resolve_domain("alice.ghost").await.unwrap();
+    let stats = cns.cache_stats().await;
+    assert_eq!((stats.entries, stats.capacity, stats.hits, stats.misses, stats.evictions), (2, 2, 2, 3, 1));
+    assert!((stats.hit_rate() - 0.4).abs() < f64::EPSILON);
+
+    // carol's entry expires with its registration, alice's with the TTL
+    clock.advance(Duration::from_secs(10));
+    cns.resolve_domain("carol.ghost").await.unwrap();
+    let stats = cns.cache_stats().await;
+    assert_eq!((stats.entries, stats.misses, stats.expirations), (1, 4, 1));
+
+    clock.advance(Duration::from_secs(50));
+    let _cleanup = cns.spawn_cache_cleanup();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while cns.cache_stats().await.entries > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("background cleanup did not run");
+    assert_eq!(cns.cache_stats().await.expirations, 2);
+}
+
+#[tokio::test]
+async fn test_dialer_happy_eyeballs() {
+    use etherlink::transport::{AddressFamilyPreference, DialConfig, Dialer};
+    use std::net::SocketAddr;
+
+    let v4: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let v6: SocketAddr = "[::1]:9000".parse().unwrap();
+    let v4b: SocketAddr = "127.0.0.2:9000".parse().unwrap();
+
+    let dialer = Dialer::new(DialConfig { attempt_delay_ms: 20, ..Default::default() });
+    assert_eq!(dialer.order(vec![v4, v4b, v6]), vec![v6, v4, v4b]);
+
+    let ipv4_first = Dialer::new(DialConfig { family: AddressFamilyPreference::PreferIpv4, ..Default::default() });
+    assert_eq!(ipv4_first.order(vec![v6, v4]), vec![v4, v6]);
+
+    // The IPv6 attempt hangs, so the IPv4 attempt started after the delay wins
+    let (winner, _) = dialer
+        .race(&[v6, v4], |addr| async move {
+            if addr.is_ipv6() {
+                std::future::pending::<()>().await;
+            }
+            Ok(())
+        })
+        .await
+        .unwrap();
+    assert_eq!(winner, v4);
+
+    // A failed address is remembered and tried last next time
+    let result = dialer
+        .race(&[v4], |_| async { Err::<(), _>(etherlink::EtherlinkError::Network("refused".to_string())) })
+        .await;
+    assert!(result.is_err());
+    assert_eq!(dialer.order(vec![v4, v4b]), vec![v4b, v4]);
+}
+
+#[tokio::test]
+async fn test_per_service_accounting_and_quotas() {
+    use etherlink::transport::accounting::QuotaKind;
+    use etherlink::transport::{AccountingTransport, BandwidthAccounting, InProcessTransport, ServiceQuota};
+
+    let inner = InProcessTransport::new();
+    inner
+        .register_json("inproc://ghostd/rpc", |_| async { Ok(serde_json::json!({ "ok": true })) })
+        .await;
+
+    let accounting = BandwidthAccounting::new();
+    accounting.route("inproc://ghostd", "ghostd");
+    accounting.set_quota("ghostd", ServiceQuota { max_requests: Some(1), max_bytes: None });
+    let mut events = accounting.subscribe();
+
+    let transport = AccountingTransport::new(inner, accounting.clone());
+    for _ in 0..3 {
+        transport.send_json_request("inproc://ghostd/rpc", serde_json::json!({})).await.unwrap();
+    }
+    assert!(transport.send_json_request("inproc://walletd/rpc", serde_json::json!({})).await.is_err());
+
+    let ghostd = accounting.usage("ghostd");
+    assert_eq!(ghostd.requests, 3);
+    assert!(ghostd.bytes_received > 0);
+    assert_eq!(accounting.usage("walletd").failed_requests, 1);
+
+    // The quota event fires once per period
+    let event = events.try_recv().unwrap();
+    assert_eq!(event.kind, QuotaKind::Requests);
+    assert_eq!(event.used, 2);
+    assert!(events.try_recv().is_err());
+
+    accounting.reset();
+    assert_eq!(accounting.usage("ghostd").requests, 0);
+}
+
+#[tokio::test]
+async fn test_inprocess_transport_dispatch() {
+    use etherlink::InProcessTransport;
+
+    let transport = InProcessTransport::new();
+    transport
+        .register_json("inproc://ghostd/height", |request| async move {
+            assert_eq!(request["method"], "height");
+            Ok(serde_json::json!({ "height": 1234 }))
+        })
+        .await;
+
+    let response = transport
+        .send_json_request("inproc://ghostd/height", serde_json::json!({ "method": "height" }))
+        .await
+        .unwrap();
+    assert_eq!(response["height"], 1234);
+
+    assert!(transport.health_check("inproc://ghostd").await.is_ok());
+    assert!(transport.send_json_request("inproc://walletd", serde_json::json!({})).await.is_err());
+
+    let stats = transport.get_stats().await.unwrap();
+    assert_eq!(stats.total_requests, 1);
+    assert_eq!(stats.failed_requests, 1);
+}
+
+#[test]
+fn test_canonical_encoding_golden_vectors() {
+    use etherlink::canonical::to_bytes;
+    use etherlink::clients::ghostd::Transaction;
+    use std::collections::HashMap;
+
+    #[derive(serde::Serialize)]
+    struct Sample {
+        b: u16,
+        a: Option<String>,
+    }
+
+    let hex = |value: &[u8]| hex::encode(value);
+    // Fields sorted by encoded name, fixed-width integers, length-prefixed strings
+    assert_eq!(
+        hex(&to_bytes(&Sample { b: 0x0102, a: Some("x".to_string()) }).unwrap()),
+        concat!("00000002", "0000000161", "01", "0000000178", "0000000162", "0102")
+    );
+    // Map entries are sorted regardless of insertion order
+    let mut map = HashMap::new();
+    map.insert("z", 1u8);
+    map.insert("a", 2u8);
+    assert_eq!(hex(&to_bytes(&map).unwrap()), concat!("00000002", "0000000161", "02", "000000017a", "01"));
+    assert_eq!(hex(&to_bytes(&vec![1u8, 2]).unwrap()), "000000020102");
+    assert_eq!(hex(&to_bytes(&-2i32).unwrap()), "fffffffe");
+    assert_eq!(hex(&to_bytes(&TokenType::GCC).unwrap()), "00000003474343");
+    assert_eq!(
+        hex(&to_bytes(&Permission::TransferTokens(TokenType::GCC)).unwrap()),
+        concat!("00000001", "0000000e", "5472616e73666572546f6b656e73", "00000003474343")
+    );
+    assert!(to_bytes(&1.5f64).is_err());
+
+    let tx = Transaction {
+        from: Address::new("a".to_string()),
+        to: Address::new("b".to_string()),
+        amount: 1,
+        gas_limit: 2,
+        gas_price: 3,
+        nonce: 4,
+        data: None,
+        signature: Some("ff".to_string()),
+        delegation: None,
+        token_type: TokenType::GCC,
+    };
+    assert_eq!(
+        hex(&tx.signing_payload()),
+        concat!(
+            "00000008",
+            "00000002746f", "0000000162",
+            "0000000464617461", "00",
+            "0000000466726f6d", "0000000161",
+            "000000056e6f6e6365", "0000000000000004",
+            "00000006616d6f756e74", "0000000000000001",
+            "000000096761735f6c696d6974", "0000000000000002",
+            "000000096761735f7072696365", "0000000000000003",
+            "000000097369676e6174757265", "00",
+        )
+    );
+}
+
+#[test]
+fn test_hasher_domain_separation() {
+    use etherlink::hash::{HashAlgorithm, HashDomain, Hasher};
+
+    assert_eq!(
+        hex::encode(Hasher::digest(HashAlgorithm::Sha256, b"abc")),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(
+        hex::encode(Hasher::digest(HashAlgorithm::Keccak256, b"")),
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+    assert_eq!(
+        hex::encode(Hasher::digest(HashAlgorithm::Blake3, b"")),
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+    );
+
+    // Each domain yields a distinct digest, none equal to the untagged one
+    let plain = Hasher::digest(HashAlgorithm::Sha256, b"payload");
+    let tagged: Vec<_> = [HashDomain::Tx, HashDomain::Batch, HashDomain::Domain, HashDomain::Auth, HashDomain::Dev]
+        .iter()
+        .map(|domain| Hasher::domain_digest(HashAlgorithm::Sha256, *domain, b"payload"))
+        .collect();
+    for (i, digest) in tagged.iter().enumerate() {
+        assert_ne!(*digest, plain);
+        assert!(tagged[i + 1..].iter().all(|other| other != digest));
+    }
+
+    // Incremental updates match the one-shot digest
+    for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Keccak256, HashAlgorithm::Blake3] {
+        let mut hasher = Hasher::with_domain(algorithm, HashDomain::Tx);
+        hasher.update(b"pay").update(b"load");
+        assert_eq!(hasher.finalize(), Hasher::domain_digest(algorithm, HashDomain::Tx, b"payload"));
+    }
+}
+
+#[test]
+fn test_evm_address_derivation() {
+    use etherlink::address::{create2_address, create_address, evm_bytes, to_checksum};
+    use etherlink::auth::crypto::{address_from_public_key, CryptoAlgorithm, KeyPair};
+
+    // EIP-55 checksum casing
+    let bytes: [u8; 20] = hex::decode("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap().try_into().unwrap();
+    assert_eq!(to_checksum(&bytes), "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+    assert_eq!(evm_bytes(&Address::new("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".to_string())).unwrap(), bytes);
+    assert!(matches!(
+        evm_bytes(&Address::new("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string())),
+        Err(etherlink::EtherlinkError::InvalidAddress(etherlink::address::AddressError::Checksum))
+    ));
+
+    // Private key 1 is the generator point, both compressed and uncompressed
+    let uncompressed = "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+    let compressed = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    for public_key in [uncompressed, compressed] {
+        assert_eq!(
+            address_from_public_key(public_key, &CryptoAlgorithm::Secp256k1).unwrap().as_str(),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+    }
+    let ed25519 = KeyPair { private_key: String::new().into(), public_key: "ab".repeat(32), algorithm: CryptoAlgorithm::Ed25519 };
+    assert!(ed25519.address().unwrap().as_str().starts_with("ghost1"));
+    // A point off the curve is an error, never a silent ghost1 address
+    let off_curve = KeyPair { private_key: String::new().into(), public_key: format!("04{}", "00".repeat(64)), algorithm: CryptoAlgorithm::Secp256k1 };
+    assert!(off_curve.address().is_err());
+    assert!(address_from_public_key(&uncompressed[2..], &CryptoAlgorithm::Secp256k1).is_ok());
+
+    let deployer = Address::new("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0".to_string());
+    assert_eq!(create_address(&deployer, 0).unwrap().as_str().to_lowercase(), "0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d");
+    assert_eq!(create_address(&deployer, 1).unwrap().as_str().to_lowercase(), "0x343c43a37d37dff08ae8c4a11544c718abb4fcf8");
+
+    // EIP-1014 examples 0 and 1
+    let zero = Address::new(format!("0x{}", "00".repeat(20)));
+    assert_eq!(create2_address(&zero, &[0; 32], &[0]).unwrap().as_str(), "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38");
+    let deployer = Address::new("0xdeadbeef00000000000000000000000000000000".to_string());
+    assert_eq!(create2_address(&deployer, &[0; 32], &[0]).unwrap().as_str(), "0xB928f69Bb1D91Cd65274e3c79d8986362984fDA3");
+}
+
+#[test]
+fn test_bech32_native_addresses() {
+    use etherlink::address::{from_native_bytes, native_bytes, upgrade_native, AddressError};
+    use etherlink::bech32::{decode, encode, Variant};
+    use etherlink::EtherlinkError;
+
+    // BIP-173 / BIP-350 valid strings
+    for (value, variant) in [
+        ("A12UEL5L", Variant::Bech32),
+        ("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw", Variant::Bech32),
+        ("a1lqfn3a", Variant::Bech32m),
+        ("abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx", Variant::Bech32m),
+    ] {
+        let (hrp, data, decoded_variant) = decode(value).unwrap();
+        assert_eq!(decoded_variant, variant);
+        assert_eq!(encode(&hrp, &data, variant).unwrap(), value.to_lowercase());
+    }
+    assert!(matches!(decode("A1G7SGD8"), Err(AddressError::Checksum)));
+    assert!(matches!(decode("x1b4n0q5v"), Err(AddressError::Character('b'))));
+    assert!(matches!(decode("li1dgmt3"), Err(AddressError::Length { .. })));
+    assert!(matches!(decode("1qzzfhee"), Err(AddressError::Format(_))));
+    assert!(matches!(decode("A12uEL5L"), Err(AddressError::Format(_))));
+
+    let payload = [0x5a; 20];
+    let address = from_native_bytes(&payload);
+    assert!(address.as_str().starts_with("ghost1"));
+    assert_eq!(native_bytes(&address).unwrap(), payload);
+
+    // Addresses in the old hex form still parse and convert to the same payload
+    let legacy = Address::new(format!("ghost1{}", hex::encode(payload)));
+    assert_eq!(native_bytes(&legacy).unwrap(), payload);
+    assert_eq!(upgrade_native(&legacy).unwrap(), address);
+    assert_eq!(upgrade_native(&address).unwrap(), address);
+
+    let mut corrupted = address.as_str().to_string();
+    let last = corrupted.pop().unwrap();
+    corrupted.push(if last == 'q' { 'p' } else { 'q' });
+    assert!(matches!(native_bytes(&Address::new(corrupted)), Err(EtherlinkError::InvalidAddress(AddressError::Checksum))));
+
+    let other_hrp = Address::new(encode("spirit", &payload, Variant::Bech32m).unwrap());
+    assert!(matches!(
+        native_bytes(&other_hrp),
+        Err(EtherlinkError::InvalidAddress(AddressError::Hrp { found, .. })) if found == "spirit"
+    ));
+    let short = Address::new(encode("ghost", &payload[..19], Variant::Bech32m).unwrap());
+    assert!(matches!(
+        native_bytes(&short),
+        Err(EtherlinkError::InvalidAddress(AddressError::Length { expected: 20, found: 19 }))
+    ));
+}
+
+#[test]
+fn test_rlp_spec_vectors() {
+    use etherlink::rlp::{decode, Item};
+
+    let bytes = |value: &str| Item::from(value);
+    let list = |items: Vec<Item>| Item::List(items);
+    let lorem = "Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+    let cases = vec![
+        (bytes("dog"), "83646f67".to_string()),
+        (list(vec![bytes("cat"), bytes("dog")]), "c88363617483646f67".to_string()),
+        (bytes(""), "80".to_string()),
+        (list(vec![]), "c0".to_string()),
+        (Item::from(0u64), "80".to_string()),
+        (Item::Bytes(vec![0x00]), "00".to_string()),
+        (Item::Bytes(vec![0x0f]), "0f".to_string()),
+        (Item::Bytes(vec![0x04, 0x00]), "820400".to_string()),
+        (Item::from(1024u64), "820400".to_string()),
+        (Item::from(u64::MAX), "88ffffffffffffffff".to_string()),
+        (
+            list(vec![list(vec![]), list(vec![list(vec![])]), list(vec![list(vec![]), list(vec![list(vec![])])])]),
+            "c7c0c1c0c3c0c1c0".to_string(),
+        ),
+        (bytes(lorem), format!("b838{}", hex::encode(lorem))),
+        (list(vec![bytes(lorem)]), format!("f83ab838{}", hex::encode(lorem))),
+        (Item::Bytes(vec![0xaa; 1024]), format!("b90400{}", "aa".repeat(1024))),
+    ];
+    for (item, expected) in cases {
+        assert_eq!(hex::encode(item.encode()), expected);
+        assert_eq!(decode(&hex::decode(&expected).unwrap()).unwrap(), item);
+    }
+    assert_eq!(decode(&hex::decode("820400").unwrap()).unwrap().as_u64().unwrap(), 1024);
+
+    // Non-canonical or malformed encodings are rejected
+    for invalid in ["", "8100", "b800", "b90000", "b8390000", "83646f", "83646f6700", "c3646f", "820004"] {
+        let item = decode(&hex::decode(invalid).unwrap());
+        let integer = item.as_ref().ok().map(|item| item.as_u64());
+        assert!(item.is_err() || matches!(integer, Some(Err(_))), "{} should be rejected", invalid);
+    }
+}
+
+#[test]
+fn test_rlp_rejects_deeply_nested_lists() {
+    use etherlink::rlp::{decode, Item, MAX_DEPTH};
+
+    // `depth` lists around an empty list, built inside out: c0, c1c0, c2c1c0, ...
+    let nested = |depth: usize| {
+        let mut encoded = vec![0xc0];
+        for _ in 0..depth {
+            let len = encoded.len();
+            let mut header = if len <= 55 {
+                vec![0xc0 + len as u8]
+            } else {
+                let len_bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+                let mut header = vec![0xf7 + len_bytes.len() as u8];
+                header.extend(len_bytes);
+                header
+            };
+            header.extend(encoded);
+            encoded = header;
+        }
+        encoded
+    };
+
+    let mut item = decode(&nested(MAX_DEPTH - 1)).unwrap();
+    for _ in 0..MAX_DEPTH - 1 {
+        let Item::List(mut items) = item else { panic!("expected a list") };
+        item = items.remove(0);
+    }
+    assert_eq!(item, Item::List(vec![]));
+
+    // Too deep is a decode error, not a stack overflow
+    assert!(decode(&nested(MAX_DEPTH)).is_err());
+    assert!(decode(&nested(5_000)).is_err());
+    assert!(decode(&[0xc1; 100_000]).is_err());
+}
+
+#[test]
+fn test_rlp_evm_transaction_receipt_and_account() {
+    use etherlink::auth::crypto::{CryptoAlgorithm, KeyPair};
+    use etherlink::revm::{AccountInfo, EvmLog, EvmReceipt, EvmSignature, EvmTransaction};
+    use etherlink::rlp::{Decodable, Encodable};
+
+    // EIP-155 example transaction
+    let key = KeyPair {
+        private_key: "46".repeat(32).into(),
+        public_key: "024bc2a31265153f07e70e0bab08724e6b85e217f8cd628ceb62974247bb493382".to_string(),
+        algorithm: CryptoAlgorithm::Secp256k1,
+    };
+    let mut tx = EvmTransaction {
+        from: Address::new(String::new()),
+        to: Some(Address::new(format!("0x{}", "35".repeat(20)))),
+        value: 1_000_000_000_000_000_000,
+        data: Vec::new(),
+        gas_limit: 21_000,
+        gas_price: 20_000_000_000,
+        nonce: 9,
+        chain_id: 1,
+        signature: EvmSignature { v: 0, r: Vec::new(), s: Vec::new() },
+    };
+    assert_eq!(
+        hex::encode(tx.signing_payload().unwrap()),
+        "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+    );
+    assert_eq!(hex::encode(tx.signing_hash().unwrap()), "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53");
+    tx.sign(&key).unwrap();
+    let signed = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    assert_eq!(hex::encode(tx.rlp_bytes().unwrap()), signed);
+    assert_eq!(tx.from.as_str(), "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F");
+
+    let decoded = EvmTransaction::from_rlp_bytes(&hex::decode(signed).unwrap()).unwrap();
+    assert_eq!(decoded.from, tx.from);
+    assert_eq!(decoded.chain_id, 1);
+    assert_eq!(decoded.value, tx.value);
+    assert_eq!(hex::encode(decoded.rlp_bytes().unwrap()), signed);
+
+    // A chain id whose EIP-155 `v` overflows is an error, not a wrapped or panicking signature
+    let mut huge = EvmTransaction { chain_id: u64::MAX / 2, ..tx.clone() };
+    assert!(matches!(huge.sign(&key), Err(etherlink::EtherlinkError::Codec(_))));
+
+    let receipt = EvmReceipt {
+        success: true,
+        cumulative_gas_used: 42_000,
+        logs: vec![EvmLog {
+            address: Address::new(format!("0x{}", "11".repeat(20))),
+            topics: vec![format!("0x{}", "22".repeat(32))],
+            data: vec![1, 2, 3],
+        }],
+    };
+    let bloom = receipt.logs_bloom().unwrap();
+    assert_eq!(bloom.iter().map(|b| b.count_ones()).sum::<u32>(), 6);
+    let encoded = receipt.rlp_bytes().unwrap();
+    let decoded = EvmReceipt::from_rlp_bytes(&encoded).unwrap();
+    assert_eq!(decoded.logs[0].topics, receipt.logs[0].topics);
+    assert_eq!(decoded.rlp_bytes().unwrap(), encoded);
+
+    // Empty account: empty storage trie and code hash
+    let account = AccountInfo::default();
+    assert_eq!(
+        hex::encode(account.rlp_bytes().unwrap()),
+        "f8448080a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+    let account = AccountInfo { balance: 5, nonce: 1, code_hash: Some(format!("0x{}", "ab".repeat(32))), storage_root: None };
+    let decoded = AccountInfo::from_rlp_bytes(&account.rlp_bytes().unwrap()).unwrap();
+    assert_eq!((decoded.balance, decoded.nonce, decoded.code_hash, decoded.storage_root), (5, 1, account.code_hash, None));
+}
+
+#[test]
+fn test_prefixed_message_signing() {
+    use etherlink::auth::crypto::{recover_message_signer, CryptoAlgorithm, CryptoProvider, MessagePrefix};
+
+    let provider = CryptoProvider::new();
+    let private_key = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    assert_eq!(
+        hex::encode(MessagePrefix::Ethereum.digest(b"Some data")),
+        "1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655"
+    );
+    let signature = provider.sign_message_prefixed(b"Some data", private_key, &CryptoAlgorithm::Secp256k1, MessagePrefix::Ethereum).unwrap();
+    assert_eq!(
+        signature,
+        "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c"
+    );
+    assert_eq!(
+        recover_message_signer(b"Some data", &signature, MessagePrefix::Ethereum).unwrap().as_str(),
+        "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23"
+    );
+
+    // Each prefix only verifies its own signatures, for every algorithm
+    for algorithm in [CryptoAlgorithm::Secp256k1, CryptoAlgorithm::Ed25519] {
+        let key = provider.generate_keypair(&algorithm).unwrap();
+        let ghost = provider.sign_message_prefixed(b"gm", key.private_key.expose_secret(), &algorithm, MessagePrefix::Ghost).unwrap();
+        assert!(provider.verify_message_prefixed(b"gm", &ghost, &key.public_key, &algorithm, MessagePrefix::Ghost).unwrap());
+        assert!(!provider.verify_message_prefixed(b"gm", &ghost, &key.public_key, &algorithm, MessagePrefix::Ethereum).unwrap());
+        assert!(!provider.verify_message_prefixed(b"gn", &ghost, &key.public_key, &algorithm, MessagePrefix::Ghost).unwrap());
+    }
+}
+
+#[test]
+fn test_address_ownership_proofs() {
+    use etherlink::auth::crypto::{recover_message_signer, CryptoAlgorithm, CryptoProvider, MessagePrefix};
+    use etherlink::auth::ownership::{prove_address_ownership, prove_address_ownership_with_clock, verify_address_ownership, OwnershipProof};
+    use etherlink::clock::MockClock;
+
+    let provider = CryptoProvider::new();
+    for algorithm in [CryptoAlgorithm::Secp256k1, CryptoAlgorithm::Ed25519] {
+        let key = provider.generate_keypair(&algorithm).unwrap();
+        let address = key.address().unwrap();
+        let proof = prove_address_ownership(&address, "exchange.example", "whitelist-7f3a", &key).unwrap();
+        assert!(proof.message.starts_with(&format!(
+            "exchange.example wants you to confirm that you control the address:\n{}\n\nDomain: exchange.example\nChallenge: whitelist-7f3a\n",
+            address
+        )));
+
+        // Proofs survive the trip through JSON to the verifier
+        let received: OwnershipProof = serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        verify_address_ownership(&received, "exchange.example", "whitelist-7f3a").unwrap();
+        assert!(verify_address_ownership(&received, "exchange.example", "whitelist-0000").is_err());
+
+        // A proof made for one verifier is no good to another
+        assert!(verify_address_ownership(&received, "phisher.example", "whitelist-7f3a").is_err());
+
+        // Tampering with any signed field breaks the proof
+        let stolen = OwnershipProof { address: provider.generate_keypair(&algorithm).unwrap().address().unwrap(), ..proof.clone() };
+        assert!(verify_address_ownership(&stolen, "exchange.example", "whitelist-7f3a").is_err());
+        let backdated = OwnershipProof { issued_at: proof.issued_at - 3600, ..proof.clone() };
+        assert!(verify_address_ownership(&backdated, "exchange.example", "whitelist-7f3a").is_err());
+        let redirected = OwnershipProof { domain: "phisher.example".to_string(), ..proof.clone() };
+        assert!(verify_address_ownership(&redirected, "phisher.example", "whitelist-7f3a").is_err());
+
+        // A key cannot prove an address it does not control
+        let other = provider.generate_keypair(&algorithm).unwrap();
+        assert!(prove_address_ownership(&address, "exchange.example", "whitelist-7f3a", &other).is_err());
+    }
+
+    // The statement is dated by the injected clock
+    let key = provider.generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+    let clock = MockClock::new(1_700_000_000);
+    let proof = prove_address_ownership_with_clock(&key.address().unwrap(), "exchange.example", "kyc", &key, clock.as_ref()).unwrap();
+    assert_eq!(proof.issued_at, 1_700_000_000);
+    assert!(proof.message.contains("Issued At: 2023-11-14T22:13:20Z"));
+    verify_address_ownership(&proof, "exchange.example", "kyc").unwrap();
+
+    // secp256k1 proofs are plain `personal_sign` signatures any Ethereum tool can check
+    let key = provider.generate_keypair(&CryptoAlgorithm::Secp256k1).unwrap();
+    let proof = prove_address_ownership(&key.address().unwrap(), "exchange.example", "kyc", &key).unwrap();
+    let signer = recover_message_signer(proof.message.as_bytes(), &proof.signature, MessagePrefix::Ethereum).unwrap();
+    assert_eq!(signer.as_str().to_lowercase(), key.address().unwrap().as_str().to_lowercase());
+    assert!(prove_address_ownership(&key.address().unwrap(), "exchange.example", "line\nbreak", &key).is_err());
+    assert!(prove_address_ownership(&key.address().unwrap(), "exchange.example\nevil.example", "kyc", &key).is_err());
+}
+
+#[tokio::test]
+async fn test_revm_fee_schedule_accounting() {
+    use etherlink::revm::{EvmSignature, EvmTransaction, FeeSchedule, REVMClient, REVMConfig};
+
+    let coinbase = Address::new(format!("0x{}", "c0".repeat(20)));
+    let alice = Address::new(format!("0x{}", "a1".repeat(20)));
+    let bob = Address::new(format!("0x{}", "b0".repeat(20)));
+    let config = REVMConfig {
+        gas_price: 10,
+        fee_schedule: FeeSchedule { base_fee: 4, coinbase: Some(coinbase.clone()), ..FeeSchedule::default() },
+        ..REVMConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    let mut evm = REVMClient::new(config.clone());
+    let transfer = |from: &Address, nonce: u64, value: u64, gas_limit: u64| EvmTransaction {
+        from: from.clone(),
+        to: Some(bob.clone()),
+        value,
+        data: Vec::new(),
+        gas_limit,
+        gas_price: 10,
+        nonce,
+        chain_id: config.chain_id,
+        signature: EvmSignature { v: 0, r: Vec::new(), s: Vec::new() },
+    };
+
+    // A zero-balance sender is rejected without touching state
+    assert!(evm.execute_transaction(transfer(&alice, 0, 0, 21_000)).await.is_err());
+    assert_eq!((evm.get_balance(&alice), evm.get_account_nonce(&alice)), (0, 0));
+
+    // Covering exactly value plus the full gas limit is enough; unused gas is never charged
+    evm.set_balance(alice.clone(), 30_000 * 10 + 100);
+    assert!(evm.execute_transaction(transfer(&alice, 0, 0, 20_999)).await.is_err(), "below intrinsic gas");
+    let result = evm.execute_transaction(transfer(&alice, 0, 100, 30_000)).await.unwrap();
+    assert_eq!(result.gas_used, 21_000);
+    assert_eq!((result.fee.total_fee, result.fee.burned, result.fee.priority_fee), (210_000, 84_000, 126_000));
+    assert_eq!(evm.get_balance(&alice), 300_100 - 210_000 - 100);
+    assert_eq!(evm.get_balance(&bob), 100);
+    assert_eq!(evm.get_balance(&coinbase), 126_000);
+    assert_eq!(evm.get_account_nonce(&alice), 1);
+
+    // The remaining balance no longer covers the worst case
+    assert!(evm.execute_transaction(transfer(&alice, 1, 0, 21_000)).await.is_err());
+
+    // Cost overflow is an error rather than a wrapped balance check
+    evm.set_balance(alice.clone(), u64::MAX);
+    assert!(evm.execute_transaction(transfer(&alice, 1, u64::MAX, 21_000)).await.is_err());
+
+    // A credit that fails after the fee is worked out leaves every account as it was
+    evm.set_balance(alice.clone(), 1_000_000);
+    evm.set_balance(bob.clone(), u64::MAX - 50);
+    assert!(evm.execute_transaction(transfer(&alice, 1, 100, 21_000)).await.is_err());
+    assert_eq!((evm.get_balance(&alice), evm.get_account_nonce(&alice)), (1_000_000, 1));
+    assert_eq!((evm.get_balance(&bob), evm.get_balance(&coinbase)), (u64::MAX - 50, 126_000));
+    assert!(evm.check_invariants().is_ok());
+
+    let schedule = FeeSchedule::default();
+    let mut with_data = transfer(&alice, 0, 0, 100_000);
+    with_data.data = vec![0, 0, 1];
+    assert_eq!(schedule.intrinsic_gas(&with_data).unwrap(), 21_000 + 2 * 4 + 16);
+    with_data.to = None;
+    assert_eq!(schedule.intrinsic_gas(&with_data).unwrap(), 53_000 + 2 * 4 + 16);
+
+    // Refunds are capped at a fifth of the gas used; without a coinbase everything is burned
+    let settled = schedule.settle(10, 50_000, 40_000).unwrap();
+    assert_eq!((settled.gas_refunded, settled.gas_charged, settled.burned), (10_000, 40_000, 400_000));
+    assert!(FeeSchedule { base_fee: 11, ..schedule.clone() }.settle(10, 21_000, 0).is_err());
+    assert!(REVMConfig { gas_price: 3, ..config }.validate().unwrap_err().has_field("gas_price"));
+}
+
+#[tokio::test]
+async fn test_state_invariant_checks() {
+    use etherlink::invariants::{self, StateInvariantViolation};
+    use etherlink::revm::{EvmSignature, EvmTransaction, FeeSchedule, REVMClient, REVMConfig};
+    use etherlink::rvm::GasMeter;
+
+    let alice = Address::new(format!("0x{}", "a1".repeat(20)));
+    assert_eq!(invariants::apply_delta(5, -2, "nonce").unwrap(), 3);
+    assert_eq!(invariants::apply_delta(u64::MAX, 1, "nonce").unwrap_err(), StateInvariantViolation::Overflow { context: "nonce".to_string() });
+    assert_eq!(invariants::apply_delta(1, -2, "nonce").unwrap_err(), StateInvariantViolation::Underflow { context: "nonce".to_string() });
+    assert_eq!(
+        invariants::apply_balance_delta(&alice, 10, -11).unwrap_err(),
+        StateInvariantViolation::NegativeBalance { address: alice.clone(), balance: 10, delta: -11 }
+    );
+    assert!(invariants::mul(u64::MAX, 2, "fee").is_err());
+
+    // Gas accounting reports running out instead of wrapping
+    let mut meter = GasMeter::new(100);
+    meter.consume(60).unwrap();
+    assert!(meter.consume(u64::MAX).is_err());
+    assert_eq!(meter.used(), 60);
+
+    // Burned and coinbase fees keep the supply balanced across blocks
+    let coinbase = Address::new(format!("0x{}", "c0".repeat(20)));
+    let bob = Address::new(format!("0x{}", "b0".repeat(20)));
+    let config = REVMConfig {
+        gas_price: 10,
+        fee_schedule: FeeSchedule { base_fee: 4, coinbase: Some(coinbase), ..FeeSchedule::default() },
+        ..REVMConfig::default()
+    };
+    let mut evm = REVMClient::new(config.clone());
+    evm.set_balance(alice.clone(), 1_000_000);
+    for nonce in 0..2 {
+        let tx = EvmTransaction {
+            from: alice.clone(),
+            to: Some(bob.clone()),
+            value: 7,
+            data: Vec::new(),
+            gas_limit: 21_000,
+            gas_price: 10,
+            nonce,
+            chain_id: config.chain_id,
+            signature: EvmSignature { v: 0, r: Vec::new(), s: Vec::new() },
+        };
+        evm.execute_transaction(tx).await.unwrap();
+        evm.check_invariants().unwrap();
+        evm.seal_block();
+    }
+    evm.set_balance(alice.clone(), 0);
+    evm.check_invariants().unwrap();
+
+    let error = etherlink::EtherlinkError::from(StateInvariantViolation::SupplyNotConserved { block: 3, expected: 10, actual: 9 });
+    assert!(error.to_string().contains("supply not conserved at block 3"));
+}
+
+#[tokio::test]
+async fn test_ghostplane_client_handles_share_state() {
+    use etherlink::ghostplane::{GhostPlaneClient, L2Transaction};
+
+    let tx = |nonce: u64| L2Transaction {
+        from: Address::new("ghost1alice".to_string()),
+        to: Address::new("ghost1bob".to_string()),
+        value: 1,
+        data: Vec::new(),
+        gas_limit: 21_000,
+        gas_price: 1_000_000_000,
+        nonce,
+        signature: Vec::new(),
+    };
+
+    // Clones are handles onto one client: initializing through one readies all of them
+    let client = GhostPlaneClient::with_defaults();
+    let handle = client.clone();
+    handle.initialize().await.unwrap();
+
+    let tasks: Vec<_> = (0..4)
+        .map(|nonce| {
+            let client = client.clone();
+            tokio::spawn(async move { client.submit_transaction(tx(nonce)).await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().unwrap();
+    }
+    assert_eq!(handle.total_transaction_count().await, 4);
+
+    // Bridge liveness is shared too, and shutting down through any handle stops the bridge
+    handle.bridge_link().mark_down("unloaded");
+    assert!(client.submit_transaction(tx(4)).await.is_err());
+    handle.recover_bridge().await.unwrap();
+    client.shutdown().await.unwrap();
+    assert!(handle.submit_transaction(tx(4)).await.is_err());
+}
+
+/// The `Mail` example from the EIP-712 specification
+fn eip712_mail() -> etherlink::auth::TypedData {
+    serde_json::from_value(serde_json::json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_eip712_typed_data_signing() {
+    use etherlink::auth::crypto::{CryptoAlgorithm, KeyPair};
+    use etherlink::auth::eip712::{recover_typed_data_signer, sign_typed_data, verify_typed_data};
+
+    let mail = eip712_mail();
+    assert_eq!(mail.encode_type("Mail").unwrap(), "Mail(Person from,Person to,string contents)Person(string name,address wallet)");
+    assert_eq!(hex::encode(mail.domain_separator().unwrap()), "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f");
+    assert_eq!(
+        hex::encode(mail.hash_struct("Mail", &mail.message).unwrap()),
+        "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+    );
+    assert_eq!(hex::encode(mail.signing_hash().unwrap()), "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2");
+
+    // The domain type is derived from the present fields when `types` leaves it out
+    let mut implicit = mail.clone();
+    implicit.types.remove("EIP712Domain");
+    assert_eq!(implicit.domain_separator().unwrap(), mail.domain_separator().unwrap());
+
+    // keccak256("cow")
+    let cow = KeyPair {
+        private_key: "c85ef7d79691fe79573b1a7064c19c1a9819ebdbd1faaab1a8ec92344438aaf4".into(),
+        public_key: String::new(),
+        algorithm: CryptoAlgorithm::Secp256k1,
+    };
+    let signature = sign_typed_data(&mail, &cow).unwrap();
+    assert_eq!(
+        signature,
+        "0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c"
+    );
+    let cow_address = Address::new("0xcd2a3d9f938e13cd947ec05abc7fe734df8dd826".to_string());
+    assert_eq!(recover_typed_data_signer(&mail, &signature).unwrap().as_str(), "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826");
+    assert!(verify_typed_data(&mail, &signature, &cow_address).unwrap());
+
+    let mut altered = mail.clone();
+    altered.message["contents"] = serde_json::json!("Hello, Eve!");
+    assert!(!verify_typed_data(&altered, &signature, &cow_address).unwrap());
+
+    // Integer widths and signs are enforced
+    let permit = |value: serde_json::Value, kind: &str| {
+        let data: etherlink::auth::TypedData = serde_json::from_value(serde_json::json!({
+            "types": { "Permit": [{ "name": "value", "type": kind }] },
+            "primaryType": "Permit",
+            "domain": { "name": "Token" },
+            "message": { "value": value }
+        }))
+        .unwrap();
+        data.signing_hash()
+    };
+    assert!(permit(serde_json::json!("115792089237316195423570985008687907853269984665640564039457584007913129639935"), "uint256").is_ok());
+    assert!(permit(serde_json::json!("115792089237316195423570985008687907853269984665640564039457584007913129639936"), "uint256").is_err());
+    assert!(permit(serde_json::json!(256), "uint8").is_err());
+    assert!(permit(serde_json::json!(-128), "int8").is_ok());
+    assert!(permit(serde_json::json!(-129), "int8").is_err());
+    assert!(permit(serde_json::json!(-1), "uint256").is_err());
+    assert!(permit(serde_json::json!("0xff"), "uint8").is_ok());
+}
+
+#[tokio::test]
+async fn test_proto_conversions_round_trip() {
+    use etherlink::clients::ghostd::Transaction;
+    use etherlink::cns::{DomainResolution, ServiceType};
+    use etherlink::proto::{cns::v1 as cns_pb, ghostchain::v1 as ghostchain_pb};
+
+    let tx = Transaction {
+        from: Address::new("0xsender".to_string()),
+        to: Address::new("0xrecipient".to_string()),
+        amount: 1000,
+        gas_limit: 21000,
+        gas_price: 10,
+        nonce: 7,
+        data: None,
+        signature: Some("0xdeadbeef".to_string()),
+        delegation: None,
+        token_type: TokenType::SPIRIT,
+    };
+    let proto_tx = ghostchain_pb::Transaction::try_from(tx.clone()).unwrap();
+    assert_eq!(proto_tx.signature, vec![0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(proto_tx.tx_type, ghostchain_pb::TransactionType::Transfer as i32);
+    assert_eq!(proto_tx.token_type, ghostchain_pb::TokenType::Spirit as i32);
+
+    let back = Transaction::try_from(proto_tx.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&back).unwrap(), serde_json::to_value(&tx).unwrap());
+
+    let unspecified = ghostchain_pb::Transaction { token_type: ghostchain_pb::TokenType::Unspecified as i32, ..proto_tx };
+    assert!(Transaction::try_from(unspecified).is_err());
+
+    let bad_signature = Transaction { signature: Some("not hex".to_string()), ..tx };
+    assert!(ghostchain_pb::Transaction::try_from(bad_signature).is_err());
+
+    let resolution = DomainResolution {
+        domain: "alice.ghost".to_string(),
+        owner: Address::new("0xowner".to_string()),
+        records: [("A".to_string(), "10.0.0.1".to_string())].into_iter().collect(),
+        metadata: Default::default(),
+        expires_at: 1_700_000_000,
+        service_type: ServiceType::Wallet,
+        blockchain_address: None,
+        ipfs_hash: Some("QmHash".to_string()),
+        web5_did: None,
+    };
+    let response = cns_pb::CnsResolveResponse::from(resolution);
+    assert_eq!(response.service_type, cns_pb::ServiceType::Wallet as i32);
+
+    let back = DomainResolution::try_from(response.clone()).unwrap();
+    assert_eq!(back.records.get("A").map(String::as_str), Some("10.0.0.1"));
+    assert_eq!(back.ipfs_hash.as_deref(), Some("QmHash"));
+    assert_eq!(back.blockchain_address, None);
+
+    let unspecified = cns_pb::CnsResolveResponse { service_type: 0, ..response };
+    assert!(DomainResolution::try_from(unspecified).is_err());
+    assert_eq!(TokenType::try_from(ghostchain_pb::TokenType::from(TokenType::SPIRIT)).unwrap(), TokenType::SPIRIT);
+}
+
+#[tokio::test]
+async fn test_proxy_http_connect_tunnel() {
+    use etherlink::ProxyConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![0u8; 1024];
+        let n = stream.read(&mut request).await.unwrap();
+        let request = String::from_utf8_lossy(&request[..n]).to_string();
+        assert!(request.starts_with("CONNECT ghostd.internal:9090 HTTP/1.1"));
+        // "user:secret" in base64
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpzZWNyZXQ="));
+        stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\ntunnel").await.unwrap();
+    });
+
+    let proxy = ProxyConfig::new(format!("http://{}", proxy_addr))
+        .with_auth("user", "secret")
+        .with_no_proxy(".local");
+    assert!(!format!("{:?}", proxy).contains("secret"), "proxy password must be redacted");
+
+    assert!(proxy.bypasses("ghostd.local"));
+    assert!(!proxy.bypasses("ghostd.internal"));
+
+    let mut stream = proxy.connect("ghostd.internal", 9090).await.unwrap();
+    let mut tunneled = [0u8; 6];
+    stream.read_exact(&mut tunneled).await.unwrap();
+    assert_eq!(&tunneled, b"tunnel");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_uds_transport_round_trip() {
+    use etherlink::UdsTransport;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+
+    let socket_path = std::env::temp_dir().join(format!("etherlink-{}.sock", uuid::Uuid::new_v4()));
+    let listener = UnixListener::bind(&socket_path).unwrap();
+
+    tokio::spawn(async move {
+        for (content_type, body) in [("application/json", r#"{"pong":true}"#), ("text/html", "<h1>Bad Gateway</h1>")] {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 4096];
+            let n = stream.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..n]).to_string();
+            assert!(request.starts_with("POST /api/v1/rpc HTTP/1.1"));
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: {}\r\ncontent-length: {}\r\n\r\n{}",
+                content_type,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    let transport = UdsTransport::new(TransportConfig::default()).unwrap();
+    let endpoint = format!("unix://{}:/api/v1/rpc", socket_path.display());
+    let response = transport
+        .send_json_request(&endpoint, serde_json::json!({ "method": "ping" }))
+        .await
+        .unwrap();
+
+    assert_eq!(response, serde_json::json!({ "pong": true }));
+
+    // A response in an encoding other than the one requested is refused
+    let err = transport
+        .send_json_request(&endpoint, serde_json::json!({ "method": "ping" }))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, etherlink::EtherlinkError::Codec(_)));
+    assert!(err.to_string().contains("text/html"));
+    let _ = std::fs::remove_file(&socket_path);
+}
+
+#[tokio::test]
+async fn test_config_file_hot_reload() {
+    use etherlink::runtime::{ConfigWatcher, DaemonConfig, ReloadEvent};
+    use std::time::Duration;
+
+    let dir = std::env::temp_dir().join(format!("etherlink-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("etherlink.toml");
+    std::fs::write(&path, "chain_id = 1337\ncns_cache_ttl_seconds = 60\n\n[etherlink]\nghostd_endpoint = \"http://127.0.0.1:9\"\n").unwrap();
+
+    let runtime = Arc::new(etherlink::Etherlink::from_daemon_config(DaemonConfig::load(&path).unwrap()).unwrap());
+    assert_eq!(runtime.cns().cache_ttl_seconds(), 60);
+    let mut events = runtime.subscribe_reloads();
+
+    let watcher = ConfigWatcher::new(&path).unwrap();
+    let watching = runtime.clone();
+    let task = tokio::spawn(async move { watching.watch_config(watcher).await });
+
+    std::fs::write(
+        &path,
+        "chain_id = 42\ncns_cache_ttl_seconds = 5\n\n[etherlink]\nghostd_endpoint = \"http://127.0.0.1:10\"\n\n[quotas.ghostd]\nmax_requests = 100\n\n[rate_limits.ghostd]\nrequests_per_second = 10.0\n",
+    )
+    .unwrap();
+
+    let mut applied = Vec::new();
+    let mut rejected = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while applied.is_empty() || rejected.is_empty() {
+            match events.recv().await.unwrap() {
+                ReloadEvent::Applied { fields } => applied = fields,
+                ReloadEvent::Rejected { issue } => rejected.push(issue.field),
+                ReloadEvent::Failed { error } => panic!("reload failed: {}", error),
+            }
+        }
+    })
+    .await
+    .expect("no reload events");
+
+    assert!(applied.contains(&"etherlink.ghostd_endpoint".to_string()));
+    assert!(applied.contains(&"cns_cache_ttl_seconds".to_string()));
+    assert!(applied.contains(&"quotas.ghostd".to_string()));
+    assert!(applied.contains(&"rate_limits.ghostd".to_string()));
+    assert_eq!(rejected, vec!["chain_id".to_string()]);
+
+    // The reloaded limit holds back a second request until a token refills
+    let started = std::time::Instant::now();
+    runtime.accounting().acquire("ghostd").await;
+    runtime.accounting().acquire("ghostd").await;
+    assert!(started.elapsed() >= Duration::from_millis(80));
+
+    assert_eq!(runtime.config().ghostd_endpoint, "http://127.0.0.1:10");
+    assert!(runtime.services().ghostd.base_url().starts_with("http://127.0.0.1:10/"));
+    assert_eq!(runtime.settings().chain_id, 1337);
+    assert_eq!(runtime.cns().cache_ttl_seconds(), 5);
+
+    // An invalid file leaves the running settings untouched
+    let mut invalid = runtime.settings();
+    invalid.etherlink.timeout_ms = 0;
+    assert!(runtime.reload(invalid).await.is_err());
+    assert_eq!(runtime.config().timeout_ms, 30000);
+
+    let mut invalid = runtime.settings();
+    invalid.cns_cache_ttl_seconds = 30;
+    invalid.rate_limits.get_mut("ghostd").unwrap().requests_per_second = 0.0;
+    assert!(runtime.reload(invalid).await.is_err());
+    assert_eq!(runtime.cns().cache_ttl_seconds(), 5);
+
+    // Concurrent reloads are applied one at a time, never mixed
+    let (mut first, mut second) = (runtime.settings(), runtime.settings());
+    first.cns_cache_ttl_seconds = 11;
+    first.etherlink.timeout_ms = 11_000;
+    second.cns_cache_ttl_seconds = 22;
+    second.etherlink.timeout_ms = 22_000;
+    let (a, b) = tokio::join!(runtime.reload(first), runtime.reload(second));
+    assert!(a.is_ok() && b.is_ok());
+    let settings = runtime.settings();
+    assert_eq!(settings.etherlink.timeout_ms, settings.cns_cache_ttl_seconds * 1000);
+    assert_eq!(runtime.cns().cache_ttl_seconds(), settings.cns_cache_ttl_seconds);
+
+    // The CNS client and resolver are built at startup, so a new CNS endpoint waits for a restart
+    let mut moved = runtime.settings();
+    moved.etherlink.cns_endpoint = Some("http://127.0.0.1:11".to_string());
+    let report = runtime.reload(moved).await.unwrap();
+    assert!(report.rejected.iter().any(|issue| issue.field == "etherlink.cns_endpoint"));
+    assert!(!report.applied.contains(&"etherlink.cns_endpoint".to_string()));
+    assert_eq!(runtime.config().cns_endpoint, settings.etherlink.cns_endpoint);
+
+    task.abort();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_txpool_lanes_fairness_and_eviction() {
+    use etherlink::ghostplane::L2Transaction;
+    use etherlink::txpool::{BatchPolicy, TxPool, TxPoolConfig};
+
+    let tx = |from: &str, nonce: u64, gas_price: u64| L2Transaction {
+        from: Address::new(from.to_string()),
+        to: Address::new("ghost1recipient".to_string()),
+        value: 1,
+        data: Vec::new(),
+        gas_limit: 21_000,
+        gas_price,
+        nonce,
+        signature: Vec::new(),
+    };
+    let hash = |name: &str| TxHash::new(name.to_string());
+
+    let mut pool = TxPool::new(TxPoolConfig {
+        capacity: 4,
+        lane_gas_prices: vec![10, 100],
+        min_gas_price: 1,
+        max_pending_per_sender: 3,
+        max_per_sender_per_batch: 2,
+    });
+
+    // Lanes by gas price, or by explicit priority
+    assert!(pool.insert(hash("a0"), tx("ghost1alice", 0, 5), None).unwrap().is_none());
+    assert!(pool.insert(hash("a1"), tx("ghost1alice", 1, 500), None).unwrap().is_none());
+    assert!(pool.insert(hash("a2"), tx("ghost1alice", 2, 500), None).unwrap().is_none());
+    assert!(pool.insert(hash("b0"), tx("ghost1bob", 0, 50), Some(2)).unwrap().is_none());
+    assert_eq!(pool.metrics().lane_depths, vec![1, 0, 3]);
+
+    // Fairness and minimum price limits
+    assert!(pool.check(&tx("ghost1alice", 3, 1_000), None).is_err());
+    assert!(pool.insert(hash("c0"), tx("ghost1carol", 0, 0), None).is_err());
+
+    // A full pool evicts the lowest-ranked sender tail only when outbid
+    assert!(pool.insert(hash("c0"), tx("ghost1carol", 0, 2), None).is_err());
+    assert_eq!(pool.insert(hash("c0"), tx("ghost1carol", 0, 60), Some(2)).unwrap(), Some(hash("b0")));
+
+    // Batches keep nonce order and cap each sender
+    let batch: Vec<TxHash> = pool.take_batch(10, &BatchPolicy::default()).into_iter().map(|(tx_hash, _)| tx_hash).collect();
+    assert_eq!(batch, vec![hash("c0"), hash("a0"), hash("a1")]);
+    assert_eq!(pool.take_batch(10, &BatchPolicy::default()).len(), 1);
+
+    let metrics = pool.metrics();
+    assert_eq!(metrics.pending, 0);
+    assert_eq!(metrics.batched, 4);
+    assert_eq!(metrics.evicted, 1);
+    assert_eq!(metrics.rejected, 2);
+}
+
+#[test]
+fn test_batch_policy_gas_size_and_age_limits() {
+    use etherlink::ghostplane::{GhostPlaneConfig, L2Transaction};
+    use etherlink::txpool::{BatchPolicy, TxPool, TxPoolConfig};
+    use std::time::Duration;
+
+    let tx = |from: &str, nonce: u64, gas_price: u64, gas_limit: u64, data: usize| L2Transaction {
+        from: Address::new(from.to_string()),
+        to: Address::new("ghost1recipient".to_string()),
+        value: 1,
+        data: vec![7; data],
+        gas_limit,
+        gas_price,
+        nonce,
+        signature: Vec::new(),
+    };
+    let hash = |name: &str| TxHash::new(name.to_string());
+    let hashes = |batch: Vec<(TxHash, L2Transaction)>| batch.into_iter().map(|(tx_hash, _)| tx_hash).collect::<Vec<_>>();
+
+    // A sender whose next transaction overflows the gas budget is skipped, not the whole batch
+    let gas = BatchPolicy::default().max_gas(100_000);
+    let mut pool = TxPool::default();
+    pool.insert(hash("a0"), tx("ghost1alice", 0, 90, 60_000, 0), None).unwrap();
+    pool.insert(hash("a1"), tx("ghost1alice", 1, 90, 60_000, 0), None).unwrap();
+    pool.insert(hash("b0"), tx("ghost1bob", 0, 50, 40_000, 0), None).unwrap();
+    pool.insert(hash("c0"), tx("ghost1carol", 0, 10, 1, 0), None).unwrap();
+    assert!(pool.batch_due(10, &gas));
+    assert_eq!(hashes(pool.take_batch(10, &gas)), vec![hash("a0"), hash("b0")]);
+    assert_eq!(hashes(pool.take_batch(10, &gas)), vec![hash("a1"), hash("c0")]);
+    assert!(!pool.batch_due(10, &gas));
+
+    // Encoded size bounds the batch in the same way
+    let small = tx("ghost1alice", 0, 90, 21_000, 0);
+    let large = tx("ghost1bob", 0, 50, 21_000, 4_096);
+    let small_size = serde_json::to_vec(&small).unwrap().len();
+    let bytes = BatchPolicy::default().max_bytes(small_size * 2);
+    assert!(bytes.check(&small).is_ok());
+    assert!(bytes.check(&large).is_err());
+    assert!(BatchPolicy::default().max_gas(20_000).check(&small).is_err());
+
+    pool.insert(hash("s0"), small.clone(), None).unwrap();
+    pool.insert(hash("l0"), large, None).unwrap();
+    pool.insert(hash("s1"), tx("ghost1alice", 1, 90, 21_000, 0), None).unwrap();
+    assert_eq!(hashes(pool.take_batch(10, &bytes)), vec![hash("s0"), hash("s1")]);
+    assert!(pool.take_batch(10, &bytes).is_empty());
+    assert_eq!(hashes(pool.take_batch(10, &BatchPolicy::default())), vec![hash("l0")]);
+
+    // Count and age make a batch due before any other limit is reached
+    let aged = BatchPolicy::default().max_age(Duration::from_millis(20));
+    let clock = etherlink::MockClock::new(1_700_000_000);
+    let mut pool = TxPool::new(TxPoolConfig::default()).with_clock(clock.clone());
+    assert!(!pool.batch_due(1, &aged));
+    pool.insert(hash("o0"), small, None).unwrap();
+    assert!(pool.batch_due(1, &BatchPolicy::default()));
+    assert!(!pool.batch_due(10, &aged));
+    clock.advance(Duration::from_millis(19));
+    assert!(!pool.batch_due(10, &aged));
+    clock.advance(Duration::from_millis(1));
+    assert!(pool.batch_due(10, &aged));
+
+    let mut config = GhostPlaneConfig::default();
+    config.batch_policy = BatchPolicy { max_gas: Some(0), max_bytes: Some(0), max_age_ms: Some(0) };
+    let errors = config.validate().unwrap_err();
+    assert!(errors.has_field("batch_policy.max_gas"));
+    assert!(errors.has_field("batch_policy.max_bytes"));
+    assert!(errors.has_field("batch_policy.max_age_ms"));
+}
+
+#[tokio::test]
+async fn test_ffi_chunked_transfer_round_trip() {
+    use etherlink::ffi::{ChunkAssembler, ChunkedPayload, ChunkingConfig, TransferManifest, ZigBridge};
+
+    let payload: Vec<u8> = (0..100_000u32).flat_map(|i| (i % 251).to_le_bytes()).collect();
+    let config = ChunkingConfig { threshold_bytes: 1024, chunk_size: 4096, ..Default::default() };
+
+    for compress in [true, false] {
+        let config = ChunkingConfig { compress, ..config.clone() };
+        let transfer = ChunkedPayload::encode(&payload, &config).unwrap();
+        assert_eq!(transfer.manifest.total_len, payload.len() as u64);
+        assert_eq!(transfer.manifest.chunk_count as usize, transfer.chunks.len());
+        assert_eq!(compress, transfer.manifest.encoded_len < payload.len() as u64);
+
+        let mut assembler = ChunkAssembler::begin(transfer.manifest);
+        for chunk in &transfer.chunks {
+            assembler.append(chunk.index, &chunk.data, chunk.checksum).unwrap();
+        }
+        assert_eq!(assembler.commit().unwrap(), payload);
+    }
+
+    // Corrupted, reordered and missing chunks are all detected
+    let config = ChunkingConfig { compress: false, ..config };
+    let transfer = ChunkedPayload::encode(&payload, &config).unwrap();
+    let mut assembler = ChunkAssembler::begin(transfer.manifest);
+    let mut corrupted = transfer.chunks[0].data.clone();
+    corrupted[0] ^= 0xff;
+    assert!(assembler.append(0, &corrupted, transfer.chunks[0].checksum).is_err());
+    assert!(assembler.append(1, &transfer.chunks[1].data, transfer.chunks[1].checksum).is_err());
+    assembler.append(0, &transfer.chunks[0].data, transfer.chunks[0].checksum).unwrap();
+    assert!(assembler.commit().is_err());
+
+    // A manifest claiming an absurd size does not reserve it up front
+    let bogus = TransferManifest { encoded_len: u64::MAX, total_len: u64::MAX, ..transfer.manifest };
+    let mut assembler = ChunkAssembler::begin(bogus);
+    assembler.append(0, &transfer.chunks[0].data, transfer.chunks[0].checksum).unwrap();
+    assert!(assembler.commit().is_err());
+
+    let mut bridge = ZigBridge::with_chunking(config);
+    bridge.initialize().unwrap();
+    assert!(bridge.call_zig_function("ghostplane_submit_batch", &payload).await.is_ok());
+    assert!(ChunkingConfig { chunk_size: 0, compression_level: 30, ..Default::default() }.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_shared_memory_ring_backpressure_and_corruption() {
+    use etherlink::ffi::ZigBridge;
+    use etherlink::shm::{SharedMemoryConfig, SharedRing};
+    use std::io::{Seek, SeekFrom, Write};
+    use std::time::Duration;
+
+    let path = std::env::temp_dir().join(format!("etherlink-test-{}.ring", uuid::Uuid::new_v4()));
+    let producer = Arc::new(SharedRing::create(&path, 4096).unwrap());
+    let consumer = SharedRing::open(&path).unwrap();
+
+    // Fill the ring, then wait for the consumer
+    let record = vec![0xab; 1000];
+    let mut written = 0;
+    while producer.try_push(&record).unwrap() {
+        written += 1;
+    }
+    assert_eq!(written, 4);
+    assert!(producer.push(&record, Duration::from_millis(20)).await.is_err());
+
+    // Records wrap around the end of the data area, in order and intact
+    let pushing = producer.clone();
+    let pusher = tokio::spawn(async move {
+        for i in 0..200u32 {
+            let payload: Vec<u8> = (0..(i % 700)).map(|b| (b ^ i) as u8).collect();
+            pushing.push(&payload, Duration::from_secs(5)).await.unwrap();
+        }
+    });
+    for _ in 0..written {
+        assert_eq!(consumer.pop(Duration::from_secs(1)).await.unwrap().unwrap(), record);
+    }
+    for i in 0..200u32 {
+        let expected: Vec<u8> = (0..(i % 700)).map(|b| (b ^ i) as u8).collect();
+        assert_eq!(consumer.pop(Duration::from_secs(5)).await.unwrap().unwrap(), expected);
+    }
+    pusher.await.unwrap();
+    assert_eq!(consumer.try_pop().unwrap(), None);
+    assert!(producer.try_push(&vec![0; 5000]).is_err());
+
+    // A flipped payload byte is reported rather than returned
+    assert!(producer.try_push(b"intact payload").unwrap());
+    let bytes = std::fs::read(&path).unwrap();
+    let tail_pos = bytes.windows(14).position(|window| window == b"intact payload").unwrap() as u64;
+    let mut file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    file.seek(SeekFrom::Start(tail_pos)).unwrap();
+    file.write_all(b"X").unwrap();
+    file.sync_all().unwrap();
+    assert!(consumer.try_pop().unwrap_err().to_string().contains("corrupted"));
+
+    drop(consumer);
+    drop(producer);
+    assert!(!path.exists());
+
+    // A record that cannot fit behind the end padding still goes in, from offset 0,
+    // once the consumer has passed the wrap marker
+    let ring = SharedRing::create(std::env::temp_dir().join(format!("etherlink-test-{}.ring", uuid::Uuid::new_v4())), 4096).unwrap();
+    assert!(ring.try_push(&[1; 1000]).unwrap());
+    assert_eq!(ring.try_pop().unwrap().unwrap(), vec![1; 1000]);
+    let large = vec![2; ring.max_payload()];
+    assert!(!ring.try_push(&large).unwrap());
+    assert_eq!(ring.try_pop().unwrap(), None);
+    assert!(ring.try_push(&large).unwrap());
+    assert_eq!(ring.try_pop().unwrap().unwrap(), large);
+    drop(ring);
+
+    // The bridge submits through a negotiated ring
+    let mut bridge = ZigBridge::new();
+    bridge.initialize().unwrap();
+    bridge.attach_shared_memory(&SharedMemoryConfig { enabled: true, ..Default::default() }).unwrap();
+    bridge.submit_ghostplane_transaction(b"{\"nonce\":1}").await.unwrap();
+    let ring = bridge.shared_memory().unwrap();
+    assert_eq!(ring.try_pop().unwrap().unwrap(), b"{\"nonce\":1}");
+}
+
+#[tokio::test]
+async fn test_bridge_supervision_fails_fast_and_recovers() {
+    use etherlink::EtherlinkError;
+    use etherlink::ffi::BridgeStatus;
+    use etherlink::ghostplane::{GhostPlaneClient, L2Transaction};
+    use etherlink::runtime::{BridgeBackoff, supervise_bridge};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    let tx = |nonce: u64| L2Transaction {
+        from: Address::new("ghost1alice".to_string()),
+        to: Address::new("ghost1bob".to_string()),
+        value: 1,
+        data: Vec::new(),
+        gas_limit: 21_000,
+        gas_price: 1_000_000_000,
+        nonce,
+        signature: Vec::new(),
+    };
+
+    let client = GhostPlaneClient::with_defaults();
+    client.initialize().await.unwrap();
+    client.submit_transaction(tx(0)).await.unwrap();
+    let client = Arc::new(client);
+
+    // Calls fail fast with a retriable error while the bridge is down
+    let link = client.bridge_link();
+    link.mark_down("library unloaded");
+    let err = tokio::time::timeout(Duration::from_millis(100), client.submit_transaction(tx(1)))
+        .await
+        .expect("call hung while bridge down")
+        .unwrap_err();
+    assert!(matches!(err, EtherlinkError::BridgeDown(ref reason) if reason == "library unloaded"));
+    assert!(err.is_retriable());
+    assert!(!EtherlinkError::TxPool("full".to_string()).is_retriable());
+
+    // The supervisor retries with backoff until recovery succeeds, replaying pending transactions
+    let attempts = Arc::new(AtomicU32::new(0));
+    let replayed = Arc::new(AtomicU32::new(0));
+    let token = tokio_util::sync::CancellationToken::new();
+    let backoff = BridgeBackoff { initial: Duration::from_millis(5), max: Duration::from_millis(20), max_attempts: None };
+    assert_eq!(backoff.delay(0), Duration::from_millis(5));
+    assert_eq!(backoff.delay(10), Duration::from_millis(20));
+
+    let supervisor = tokio::spawn({
+        let (client, attempts, replayed, token) = (client.clone(), attempts.clone(), replayed.clone(), token.clone());
+        async move {
+            supervise_bridge(client.bridge_link(), backoff, token, || {
+                let (client, attempts, replayed) = (client.clone(), attempts.clone(), replayed.clone());
+                async move {
+                    if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                        return Err(EtherlinkError::Ffi("ghostplane_init failed".to_string()));
+                    }
+                    let count = client.recover_bridge().await?;
+                    replayed.store(count as u32, Ordering::SeqCst);
+                    Ok(count)
+                }
+            })
+            .await
+        }
+    });
+
+    tokio::time::timeout(Duration::from_secs(5), link.wait_up()).await.expect("bridge not recovered");
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    assert_eq!(replayed.load(Ordering::SeqCst), 1);
+    let state = link.state();
+    assert_eq!(state.status, BridgeStatus::Up);
+    assert_eq!(state.restarts, 1);
+    assert!(client.submit_transaction(tx(1)).await.is_ok());
+
+    token.cancel();
+    supervisor.await.unwrap();
+
+    // A failed call into the Zig side takes the link down, here a ring nobody drains
+    let mut bridge = etherlink::ffi::ZigBridge::new();
+    bridge.initialize().unwrap();
+    let ring = etherlink::shm::SharedMemoryConfig { enabled: true, capacity_bytes: 4096, send_timeout_ms: 10, ..Default::default() };
+    bridge.attach_shared_memory(&ring).unwrap();
+    let mut outcome = Ok(String::new());
+    while outcome.is_ok() {
+        outcome = bridge.submit_ghostplane_transaction(&[0; 1000]).await;
+    }
+    assert!(matches!(outcome, Err(EtherlinkError::Timeout(_))));
+    assert_eq!(bridge.link().status(), BridgeStatus::Down);
+    assert!(matches!(bridge.submit_ghostplane_transaction(b"{}").await, Err(EtherlinkError::BridgeDown(_))));
+}
+
+#[cfg(feature = "dns-gateway")]
+#[tokio::test]
+async fn test_dns_gateway_answers_cns_names_over_udp_and_tcp() {
+    use etherlink::runtime::{DnsGatewayConfig, Etherlink};
+    use std::net::Ipv6Addr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+    fn query(id: u16, name: &str, qtype: u16) -> Vec<u8> {
+        let mut query = id.to_be_bytes().to_vec();
+        query.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in name.split('.') {
+            query.push(label.len() as u8);
+            query.extend_from_slice(label.as_bytes());
+        }
+        query.push(0);
+        query.extend_from_slice(&qtype.to_be_bytes());
+        query.extend_from_slice(&1u16.to_be_bytes());
+        query
+    }
+
+    let runtime = Etherlink::with_defaults().unwrap();
+    let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let (udp_addr, tcp_addr) = (udp.local_addr().unwrap(), tcp.local_addr().unwrap());
+    let config = DnsGatewayConfig { max_ttl_seconds: 120, ..Default::default() };
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = runtime.serve_dns_with_sockets(udp, tcp, &config, async {
+        let _ = stopped.await;
+    });
+
+    let client = async {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.connect(udp_addr).await.unwrap();
+        let mut buf = [0u8; 512];
+
+        // The native resolver answers every .ghost name with loopback addresses
+        socket.send(&query(0x1234, "Alice.ghost", 1)).await.unwrap();
+        let len = socket.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..2], &[0x12, 0x34]);
+        assert_eq!(buf[3] & 0x0f, 0);
+        assert_eq!(u16::from_be_bytes([buf[6], buf[7]]), 1);
+        let answer = &buf[len - 16..len];
+        assert_eq!(&answer[..2], &[0xC0, 0x0C]);
+        assert_eq!(u32::from_be_bytes(answer[6..10].try_into().unwrap()), 120);
+        assert_eq!(&answer[12..], &[127, 0, 0, 1]);
+
+        // ENS is not bridged yet, so .eth names do not exist; the SOA carries the negative TTL
+        socket.send(&query(2, "vitalik.eth", 1)).await.unwrap();
+        socket.recv(&mut buf).await.unwrap();
+        assert_eq!(buf[3] & 0x0f, 3);
+        assert_eq!(u16::from_be_bytes([buf[8], buf[9]]), 1);
+
+        // Names outside CNS are left to the OS resolver
+        socket.send(&query(3, "example.com", 1)).await.unwrap();
+        socket.recv(&mut buf).await.unwrap();
+        assert_eq!(buf[3] & 0x0f, 5);
+
+        let mut stream = TcpStream::connect(tcp_addr).await.unwrap();
+        let aaaa = query(4, "alice.ghost", 28);
+        stream.write_u16(aaaa.len() as u16).await.unwrap();
+        stream.write_all(&aaaa).await.unwrap();
+        let mut response = vec![0; usize::from(stream.read_u16().await.unwrap())];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(&response[response.len() - 16..], &Ipv6Addr::LOCALHOST.octets());
+
+        let _ = stop.send(());
+    };
+    let (result, ()) = tokio::join!(server, client);
+    result.unwrap();
+}
+
+#[cfg(feature = "dns-gateway")]
+#[tokio::test]
+async fn test_dns_gateway_bounds_workers_and_reports_a_taken_port() {
+    use etherlink::runtime::{DnsGatewayConfig, Etherlink};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+    let runtime = Etherlink::with_defaults().unwrap();
+
+    // A port someone else holds, as an mDNS responder holds 5353, fails with a hint
+    let taken = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let config = DnsGatewayConfig { listen_addr: taken.local_addr().unwrap(), ..Default::default() };
+    match runtime.serve_dns(&config, async {}).await {
+        Err(etherlink::EtherlinkError::Network(message)) => assert!(message.contains("listen_addr"), "{}", message),
+        other => panic!("expected a bind error, got {:?}", other),
+    }
+    assert!(DnsGatewayConfig { max_concurrent_queries: 0, ..Default::default() }.validate().is_err());
+
+    // With one worker, a second TCP connection is closed while the first is open
+    let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let tcp_addr = tcp.local_addr().unwrap();
+    let config = DnsGatewayConfig { max_concurrent_queries: 1, ..Default::default() };
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = runtime.serve_dns_with_sockets(udp, tcp, &config, async {
+        let _ = stopped.await;
+    });
+    let client = async {
+        let query = [0, 1, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0, 5, b'a', b'l', b'i', b'c', b'e', 5, b'g', b'h', b'o', b's', b't', 0, 0, 1, 0, 1];
+        let mut first = TcpStream::connect(tcp_addr).await.unwrap();
+        first.write_u16(query.len() as u16).await.unwrap();
+        first.write_all(&query).await.unwrap();
+        let mut response = vec![0; usize::from(first.read_u16().await.unwrap())];
+        first.read_exact(&mut response).await.unwrap();
+
+        let mut second = TcpStream::connect(tcp_addr).await.unwrap();
+        let _ = second.write_u16(query.len() as u16).await;
+        let _ = second.write_all(&query).await;
+        assert!(second.read_u16().await.is_err());
+
+        drop(first);
+        let _ = stop.send(());
+    };
+    let (result, ()) = tokio::join!(server, client);
+    result.unwrap();
+}
+
+#[cfg(feature = "http-gateway")]
+#[tokio::test]
+async fn test_http_gateway_routes_by_host() {
+    use etherlink::cns::{DomainResolution, RecordSet, ServiceType};
+    use etherlink::runtime::gateway::GatewayTarget;
+    use etherlink::runtime::{Etherlink, HttpGateway, HttpGatewayConfig};
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    let mut resolution = DomainResolution {
+        domain: "site.ghost".to_string(),
+        owner: Address::new("0xowner".to_string()),
+        records: RecordSet::builder().a(Ipv4Addr::new(10, 0, 0, 7)).port(8443).content_hash("ipfs://QmSite/").build().unwrap(),
+        metadata: Default::default(),
+        expires_at: u64::MAX,
+        service_type: ServiceType::Storage,
+        blockchain_address: None,
+        ipfs_hash: None,
+        web5_did: None,
+    };
+    assert_eq!(GatewayTarget::for_resolution(&resolution), Some(GatewayTarget::Ipfs { path: "ipfs/QmSite".to_string() }));
+    resolution.records = RecordSet::builder().a(Ipv4Addr::new(10, 0, 0, 7)).port(8443).build().unwrap();
+    assert_eq!(
+        GatewayTarget::for_resolution(&resolution),
+        Some(GatewayTarget::Upstream { addr: SocketAddr::from(([10, 0, 0, 7], 8443)) })
+    );
+    resolution.records = RecordSet::new();
+    assert_eq!(GatewayTarget::for_resolution(&resolution), None);
+
+    let runtime = Etherlink::with_defaults().unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let gateway = HttpGateway::new(runtime.cns().clone(), HttpGatewayConfig::default()).unwrap();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = runtime.serve_http_gateway_with_listener(listener, gateway, async {
+        let _ = stopped.await;
+    });
+
+    let client = async {
+        let http = reqwest::Client::new();
+        let get = |host: &'static str| http.get(format!("http://{}/index.html", addr)).header("host", host).send();
+
+        let response = get("example.com").await.unwrap();
+        assert_eq!(response.status(), 404);
+        assert!(response.text().await.unwrap().contains("not a CNS name"));
+        // ENS is not bridged yet, so .eth names do not resolve
+        let response = get("vitalik.eth:8080").await.unwrap();
+        assert_eq!(response.status(), 404);
+        assert!(response.text().await.unwrap().contains("does not resolve"));
+
+        let _ = stop.send(());
+    };
+    let (result, ()) = tokio::join!(server, client);
+    result.unwrap();
+}
+
+#[cfg(feature = "http-gateway")]
+#[tokio::test]
+async fn test_http_gateway_proxies_only_to_allowed_upstreams() {
+    use etherlink::cns::{CNSClientBuilder, RecordSet};
+    use etherlink::runtime::{HttpGateway, HttpGatewayConfig};
+    use mock_cns::{resolution, MockCns};
+    use std::net::{IpAddr, Ipv4Addr};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let upstream = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/form"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("hello"))
+        .mount(&upstream)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/moved"))
+        .respond_with(ResponseTemplate::new(302).insert_header("location", "http://169.254.169.254/latest/meta-data/"))
+        .mount(&upstream)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/huge"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("x".repeat(4096)))
+        .mount(&upstream)
+        .await;
+
+    let site = |domain: &str, ip: Ipv4Addr, port: u16| {
+        let mut site = resolution(domain, "0x1234567890123456789012345678901234567890", u64::MAX);
+        site.records = RecordSet::builder().a(ip).port(port).build().unwrap();
+        site
+    };
+    let port = upstream.address().port();
+    let cns_endpoint = MockCns::default()
+        .domain(site("site.ghost", Ipv4Addr::LOCALHOST, port))
+        .domain(site("intranet.ghost", Ipv4Addr::new(10, 0, 0, 7), 80))
+        .domain(site("metadata.ghost", Ipv4Addr::new(169, 254, 169, 254), 80))
+        .start()
+        .await;
+    let cns = CNSClientBuilder::new().endpoint(cns_endpoint).build();
+
+    let serve = |allowed: Vec<IpAddr>| {
+        let config = HttpGatewayConfig { allowed_private_upstreams: allowed, max_body_bytes: 1024, ..Default::default() };
+        let gateway = HttpGateway::new(cns.clone(), config).unwrap();
+        async move {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+            let server = tokio::spawn(async move {
+                gateway
+                    .serve(listener, async {
+                        let _ = stopped.await;
+                    })
+                    .await
+            });
+            (addr, stop, server)
+        }
+    };
+    let http = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+
+    // Without an allowlist every non-public address is refused before connecting
+    let (addr, stop, server) = serve(Vec::new()).await;
+    for host in ["site.ghost", "intranet.ghost", "metadata.ghost"] {
+        let response = http.get(format!("http://{}/", addr)).header("host", host).send().await.unwrap();
+        assert_eq!(response.status(), 403, "{}", host);
+    }
+    let _ = stop.send(());
+    server.await.unwrap().unwrap();
+    assert!(upstream.received_requests().await.unwrap().is_empty());
+
+    let (addr, stop, server) = serve(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]).await;
+    let url = |path: &str| format!("http://{}{}", addr, path);
+
+    // The allowlisted upstream is proxied to, without the visitor's credentials
+    let response = http
+        .post(url("/form"))
+        .header("host", "site.ghost")
+        .header("cookie", "session=secret")
+        .header("authorization", "Bearer secret")
+        .header("connection", "x-private")
+        .header("x-private", "hop")
+        .header("x-site", "kept")
+        .body("name=alice")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "hello");
+    let received = upstream.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+    let headers = &received[0].headers;
+    assert!(headers.get("cookie").is_none());
+    assert!(headers.get("authorization").is_none());
+    assert!(headers.get("x-private").is_none());
+    assert_eq!(headers.get("x-site").unwrap(), "kept");
+    assert_eq!(headers.get("x-forwarded-host").unwrap(), "site.ghost");
+    assert_eq!(received[0].body, b"name=alice");
+
+    // The allowlist is per address, not a blanket opt-out
+    let response = http.get(url("/")).header("host", "intranet.ghost").send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    // Redirects go back to the browser instead of being followed
+    let response = http.get(url("/moved")).header("host", "site.ghost").send().await.unwrap();
+    assert_eq!(response.status(), 302);
+    assert_eq!(response.headers()["location"], "http://169.254.169.254/latest/meta-data/");
+
+    // Bodies over the cap are refused in either direction
+    let response = http.post(url("/form")).header("host", "site.ghost").body("x".repeat(2048)).send().await.unwrap();
+    assert_eq!(response.status(), 413);
+    let response = http.get(url("/huge")).header("host", "site.ghost").send().await.unwrap();
+    assert_eq!(response.status(), 502);
+    assert_eq!(upstream.received_requests().await.unwrap().len(), 3);
+
+    let _ = stop.send(());
+    server.await.unwrap().unwrap();
+}
+
+#[cfg(feature = "http-gateway")]
+#[tokio::test]
+async fn test_certificate_issuance_via_cns_challenge() {
+    use etherlink::cns::CNSClientBuilder;
+    use etherlink::runtime::{CertManager, CertificateConfig, GatewayTlsConfig, HttpGatewayConfig};
+    use etherlink::MockClock;
+    use std::time::Duration;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let now = 1_700_000_000;
+    let not_after = now + 90 * 24 * 3600;
+    let certificate_pem = rcgen::generate_simple_self_signed(vec!["site.ghost".to_string()]).unwrap().serialize_pem().unwrap();
+    let order = |status: &str, certificate: Option<&str>| {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "success": true,
+            "data": {
+                "order_id": "order-1",
+                "domain": "site.ghost",
+                "status": status,
+                "challenge_token": "tok3n",
+                "expires_at": now + 3600,
+                "certificate_pem": certificate,
+                "not_after": certificate.map(|_| not_after),
+                "error": null
+            },
+            "error": null
+        }))
+    };
+
+    let ca = MockServer::start().await;
+    Mock::given(method("POST")).and(path("/api/v1/orders")).and(body_string_contains("site.ghost"))
+        .respond_with(order("pending", None)).mount(&ca).await;
+    Mock::given(method("POST")).and(path("/api/v1/orders/order-1/challenge"))
+        .respond_with(order("processing", None)).mount(&ca).await;
+    Mock::given(method("GET")).and(path("/api/v1/orders/order-1"))
+        .respond_with(order("ready", None)).mount(&ca).await;
+    Mock::given(method("POST")).and(path("/api/v1/orders/order-1/finalize")).and(body_string_contains("BEGIN CERTIFICATE REQUEST"))
+        .respond_with(order("valid", Some(&certificate_pem))).mount(&ca).await;
+
+    let config = CertificateConfig {
+        ca_endpoint: ca.uri(),
+        store_dir: std::env::temp_dir().join(format!("etherlink-certs-{}", uuid::Uuid::new_v4())),
+        domains: vec!["site.ghost".to_string()],
+        owner: Address::new("0x1234567890123456789012345678901234567890".to_string()),
+        poll_interval_ms: 10,
+        order_timeout_ms: 5000,
+        ..Default::default()
+    };
+    let clock = MockClock::new(now);
+    let cns_endpoint = mock_cns::MockCns::default()
+        .domain(mock_cns::resolution("site.ghost", "0x1234567890123456789012345678901234567890", not_after))
+        .start()
+        .await;
+    let cns = || CNSClientBuilder::new().endpoint(cns_endpoint.clone()).build();
+    let manager = CertManager::new(cns(), config.clone()).unwrap().with_clock(clock.clone());
+
+    let outcomes = manager.renew_due().await;
+    assert_eq!(outcomes.len(), 1);
+    let issued = outcomes.into_iter().next().unwrap().1.unwrap();
+    assert_eq!((issued.order_id.as_str(), issued.issued_at, issued.not_after), ("order-1", now, not_after));
+    assert!(issued.private_key_pem.contains("PRIVATE KEY"));
+    assert_eq!(manager.resolver().domains(), vec!["site.ghost".to_string()]);
+    assert_eq!(manager.store().load("site.ghost").unwrap(), Some(issued.clone()));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(config.store_dir.join("site.ghost.json")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // Fresh certificates are left alone, and a restarted manager serves the stored one
+    assert!(manager.renew_due().await.is_empty());
+    let restarted = CertManager::new(cns(), config.clone()).unwrap().with_clock(clock.clone());
+    assert_eq!(restarted.resolver().domains(), vec!["site.ghost".to_string()]);
+    clock.advance(Duration::from_secs(61 * 24 * 3600));
+    let renewed = restarted.renew_due().await;
+    assert_eq!(renewed.len(), 1);
+    assert!(renewed[0].1.is_ok());
+
+    let mut gateway = HttpGatewayConfig { certificates: Some(config.clone()), ..Default::default() };
+    assert!(gateway.validate().is_ok());
+    gateway.tls = Some(GatewayTlsConfig { cert_path: "cert.pem".into(), key_path: "key.pem".into() });
+    gateway.certificates = Some(CertificateConfig { domains: Vec::new(), ..config.clone() });
+    let errors = gateway.validate().unwrap_err();
+    assert!(errors.has_field("certificates"));
+    assert!(errors.has_field("certificates.domains"));
+
+    let _ = std::fs::remove_dir_all(&config.store_dir);
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn test_chaos_transport_injects_faults() {
+    use etherlink::transport::{ChaosTransport, FaultProfile};
+    use etherlink::{EtherlinkError, InProcessTransport};
+    use std::time::{Duration, Instant};
+
+    let inner = || async {
+        let transport = InProcessTransport::new();
+        transport
+            .register_json("inproc://ghostd/height", |_| async { Ok(serde_json::json!({ "height": 7 })) })
+            .await;
+        transport
+    };
+    let request = || serde_json::json!({ "method": "height" });
+
+    // Every fault kind surfaces as the error the real failure would produce
+    let chaos = ChaosTransport::new(inner().await, FaultProfile::none().server_errors(1.0, 502));
+    let err = chaos.send_json_request("inproc://ghostd/height", request()).await.unwrap_err();
+    assert!(err.to_string().contains("502 Bad Gateway"));
+    chaos.set_profile(FaultProfile::none().resets(1.0));
+    assert!(matches!(chaos.send_json_request("inproc://ghostd/height", request()).await, Err(EtherlinkError::Network(_))));
+    chaos.set_profile(FaultProfile::none().drops(1.0, Duration::from_millis(20)));
+    let started = Instant::now();
+    assert!(matches!(chaos.send_json_request("inproc://ghostd/height", request()).await, Err(EtherlinkError::Timeout(_))));
+    assert!(started.elapsed() >= Duration::from_millis(20));
+    chaos.set_profile(FaultProfile::none().latency(Duration::from_millis(15), Duration::from_millis(5)));
+    let started = Instant::now();
+    assert_eq!(chaos.send_json_request("inproc://ghostd/height", request()).await.unwrap()["height"], 7);
+    assert!(started.elapsed() >= Duration::from_millis(15));
+
+    let stats = chaos.stats();
+    assert_eq!((stats.requests, stats.server_errors, stats.resets, stats.dropped, stats.passed), (4, 1, 1, 1, 1));
+
+    // The same seed replays the same faults, so a retry loop behaves identically
+    let flaky = FaultProfile::none().server_errors(0.3, 503).resets(0.3).seed(42);
+    let mut outcomes = Vec::new();
+    for _ in 0..2 {
+        let chaos = ChaosTransport::new(inner().await, flaky.clone());
+        let mut attempts = 0;
+        let height = loop {
+            attempts += 1;
+            match chaos.send_json_request("inproc://ghostd/height", request()).await {
+                Ok(response) => break response["height"].clone(),
+                Err(e) if attempts < 50 => assert!(e.is_retriable()),
+                Err(e) => panic!("still failing after retries: {}", e),
+            }
+        };
+        assert_eq!(height, 7);
+        outcomes.push(chaos.stats());
+    }
+    assert_eq!(outcomes[0], outcomes[1]);
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn test_dev_accounts_are_deterministic_named_and_funded() {
+    use etherlink::testing::{DevAccounts, MockGhostChain, DEV_ACCOUNT_BALANCE, DEV_ACCOUNT_NAMES};
+    use etherlink::{CryptoAlgorithm, KeyPair, Network};
+
+    for algorithm in [CryptoAlgorithm::Ed25519, CryptoAlgorithm::Secp256k1] {
+        let accounts = DevAccounts::new(&algorithm).unwrap();
+        let again = DevAccounts::new(&algorithm).unwrap();
+        assert_eq!(accounts.len(), DEV_ACCOUNT_NAMES.len());
+        for (account, same) in accounts.iter().zip(again.iter()) {
+            assert_eq!(account.address(), same.address());
+            assert_eq!(account.keypair.private_key.expose_secret(), same.keypair.private_key.expose_secret());
+            // The derived key pairs up with its public key
+            let imported = KeyPair::from_private_key(account.keypair.private_key.expose_secret(), &algorithm).unwrap();
+            assert_eq!(imported.public_key, account.keypair.public_key);
+        }
+
+        let alice = accounts.by_name("Alice").unwrap();
+        assert_eq!(alice.index, 0);
+        assert_eq!(accounts.get(1).unwrap().name, "bob");
+        assert_ne!(alice.address(), accounts.get(1).unwrap().address());
+        assert!(accounts.by_name("mallory").is_none() && accounts.get(DEV_ACCOUNT_NAMES.len()).is_none());
+
+        let other_seed = DevAccounts::from_seed("another project", &algorithm).unwrap();
+        assert_ne!(other_seed.get(0).unwrap().address(), alice.address());
+    }
+
+    assert!(DevAccounts::for_network(&Network::Local, &CryptoAlgorithm::Ed25519).is_ok());
+    assert!(DevAccounts::for_network(&Network::Mainnet, &CryptoAlgorithm::Ed25519).is_err());
+    assert!(DevAccounts::for_network(&Network::Testnet, &CryptoAlgorithm::Ed25519).is_err());
+    assert!(DevAccounts::for_network(&Network::Custom("staging".to_string()), &CryptoAlgorithm::Ed25519).is_err());
+
+    let chain = MockGhostChain::start(std::time::Duration::from_millis(50)).await.unwrap();
+    let accounts = DevAccounts::new(&CryptoAlgorithm::Ed25519).unwrap();
+    chain.fund_dev_accounts(&accounts);
+    for account in accounts.iter() {
+        assert_eq!(chain.balance(&account.address()), DEV_ACCOUNT_BALANCE);
+    }
+    chain.stop().await;
+}
+
+#[tokio::test]
+async fn test_ghostplane_typed_l2_state_queries() {
+    use etherlink::cassette::{Cassette, Interaction, Payload};
+    use etherlink::ghostplane::GhostPlaneClient;
+    use etherlink::proto::ghostplane::v1 as pb;
+    use prost::Message;
+
+    let contract = Address::new("ghost1contract".to_string());
+    let state_request = pb::GetL2StateRequest { address: contract.as_str().to_string(), storage_keys: vec![] };
+    let state_response = pb::L2StateResponse {
+        address: contract.as_str().to_string(),
+        balance: 5_000,
+        nonce: 7,
+        code: vec![0x60, 0x80, 0x60, 0x40],
+        storage: Default::default(),
+    };
+    let storage_request = pb::QueryL2StorageRequest {
+        contract_address: contract.as_str().to_string(),
+        storage_key: "0x01".to_string(),
+        block_number: 0,
+    };
+    let storage_response = pb::QueryL2StorageResponse { value: vec![0xff; 32], block_number: 12 };
+    let wrong_account = pb::L2StateResponse { address: "ghost1other".to_string(), ..state_response.clone() };
+
+    // The bridge answers from a cassette with protobuf-encoded responses
+    let ffi = |function: &str, request: Vec<u8>, response: Vec<u8>| Interaction::Ffi {
+        function: function.to_string(),
+        request: Payload::new(&request),
+        response: Ok(Payload::new(&response)),
+    };
+    let mut interactions: Vec<Interaction> = (0..3)
+        .map(|_| ffi("ghostplane_get_state", state_request.encode_to_vec(), state_response.encode_to_vec()))
+        .collect();
+    interactions.push(ffi("ghostplane_query_storage", storage_request.encode_to_vec(), storage_response.encode_to_vec()));
+    interactions.push(ffi("ghostplane_get_state", state_request.encode_to_vec(), wrong_account.encode_to_vec()));
+    interactions.push(ffi("ghostplane_get_state", state_request.encode_to_vec(), vec![0xff, 0xff]));
+    let cassette_path = std::env::temp_dir().join(format!("etherlink-l2-state-{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(&cassette_path, serde_json::json!({ "version": 1, "interactions": interactions }).to_string()).unwrap();
+
+    let ghostplane = GhostPlaneClient::with_defaults();
+    ghostplane.set_cassette(Arc::new(Cassette::replay(&cassette_path).unwrap())).await;
+    ghostplane.initialize().await.unwrap();
+
+    assert_eq!(ghostplane.get_l2_balance(&contract).await.unwrap(), 5_000);
+    assert_eq!(ghostplane.get_l2_nonce(&contract).await.unwrap(), 7);
+    assert_eq!(ghostplane.get_l2_code(&contract).await.unwrap(), vec![0x60, 0x80, 0x60, 0x40]);
+    assert_eq!(ghostplane.get_l2_storage(&contract, "0x01").await.unwrap(), vec![0xff; 32]);
+
+    // State for another account or an undecodable response is rejected
+    assert!(matches!(ghostplane.get_l2_balance(&contract).await, Err(etherlink::EtherlinkError::Ffi(_))));
+    assert!(matches!(ghostplane.get_l2_balance(&contract).await, Err(etherlink::EtherlinkError::Codec(_))));
+    let _ = std::fs::remove_file(&cassette_path);
+
+    // Without the GhostPlane library there is no state to read, rather than an empty account
+    let unlinked = GhostPlaneClient::with_defaults();
+    unlinked.initialize().await.unwrap();
+    if !cfg!(feature = "ghostplane-ffi") {
+        assert!(matches!(unlinked.get_l2_balance(&contract).await, Err(etherlink::EtherlinkError::Unsupported(_))));
+        assert!(matches!(unlinked.get_l2_storage(&contract, "0x01").await, Err(etherlink::EtherlinkError::Unsupported(_))));
+    }
+}
+
+#[cfg(test)]
+mod crypto_tests {
+    use super::*;
+    use etherlink::auth::crypto::{CryptoProvider, CryptoAlgorithm};
+
+    #[tokio::test]
+    async fn test_ed25519_keypair_generation() {
+        let provider = CryptoProvider::new();
+        let result = provider.generate_keypair(&CryptoAlgorithm::Ed25519);
+
+        assert!(result.is_ok());
+        let keypair = result.unwrap();
+        assert_eq!(keypair.algorithm, CryptoAlgorithm::Ed25519);
+        assert!(!keypair.private_key.expose_secret().is_empty());
+        assert!(!keypair.public_key.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ed25519_sign_verify() {
+        let provider = CryptoProvider::new();
+        let keypair = provider.generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+
+        let message = b"Hello, GhostChain!";
+        let signature = provider.sign_message(message, keypair.private_key.expose_secret(), &CryptoAlgorithm::Ed25519);
+
+        assert!(signature.is_ok());
+        let sig = signature.unwrap();
+
+        let verification = provider.verify_signature(message, &sig, &keypair.public_key, &CryptoAlgorithm::Ed25519);
+        assert!(verification.is_ok());
+        assert_eq!(verification.unwrap(), true);
+
+        // Test with wrong message
+        let wrong_message = b"Wrong message";
+        let wrong_verification = provider.verify_signature(wrong_message, &sig, &keypair.public_key, &CryptoAlgorithm::Ed25519);
+        assert!(wrong_verification.is_ok());
+        assert_eq!(wrong_verification.unwrap(), false);
+    }
+
+    #[tokio::test]
+    async fn test_keypair_as_core_signer() {
+        use etherlink::primitives::{Signer, TokenAmount};
+
+        let provider = CryptoProvider::new();
+        let keypair = provider.generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+
+        let message = b"core types";
+        let signature = keypair.sign(message).unwrap();
+        assert_eq!(signature.algorithm, CryptoAlgorithm::Ed25519);
+        assert_eq!(Signer::public_key(&keypair).unwrap(), hex::decode(&keypair.public_key).unwrap());
+
+        // Corrupt key material surfaces as an error instead of an empty key
+        let mut broken = keypair.clone();
+        broken.public_key = "not-hex".to_string();
+        assert!(matches!(Signer::public_key(&broken), Err(etherlink::primitives::SigningError::InvalidKey(_))));
+
+        let verified = provider
+            .verify_signature(message, &hex::encode(&signature.bytes), &keypair.public_key, &CryptoAlgorithm::Ed25519)
+            .unwrap();
+        assert!(verified);
+
+        let balance = TokenAmount::new(TokenType::GCC, 100);
+        assert_eq!(balance.checked_sub(&TokenAmount::new(TokenType::GCC, 40)).unwrap().amount, 60);
+        assert!(balance.checked_sub(&TokenAmount::new(TokenType::GCC, 101)).is_none());
+        assert!(balance.checked_add(&TokenAmount::new(TokenType::MANA, 1)).is_none());
+        assert_eq!(balance.to_string(), "100 GCC");
+    }
+
+    #[tokio::test]
+    async fn test_injected_signer_delegates_to_wallet() {
+        use etherlink::InjectedSigner;
+        use etherlink::primitives::SigningError;
+
+        let provider = CryptoProvider::new();
+        let keypair = provider.generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let wallet_key = keypair.private_key.clone();
+
+        let signer = InjectedSigner::new(CryptoAlgorithm::Ed25519, hex::decode(&keypair.public_key).unwrap(), move |message| {
+            let wallet_key = wallet_key.clone();
+            async move {
+                let signature = CryptoProvider::new()
+                    .sign_message(&message, wallet_key.expose_secret(), &CryptoAlgorithm::Ed25519)
+                    .map_err(|e| SigningError::Backend(e.to_string()))?;
+                Ok(hex::decode(signature).unwrap())
+            }
+        });
+
+        let signature = signer.sign(b"approve").await.unwrap();
+        let verified = provider
+            .verify_signature(b"approve", &hex::encode(&signature.bytes), &keypair.public_key, &CryptoAlgorithm::Ed25519)
+            .unwrap();
+        assert!(verified);
+
+        let rejecting = InjectedSigner::new(CryptoAlgorithm::Ed25519, Vec::new(), |_| async {
+            Err(SigningError::Backend("user rejected the request".to_string()))
+        });
+        assert!(rejecting.sign(b"approve").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_envelope_encryption() {
+        use etherlink::auth::crypto::{EncryptedEnvelope, SymmetricAlgorithm, ENVELOPE_VERSION};
+        use etherlink::messaging::KeyAgreementKey;
+
+        let provider = CryptoProvider::new();
+        let key = [7u8; 32];
+        for algorithm in [SymmetricAlgorithm::Aes256Gcm, SymmetricAlgorithm::ChaCha20Poly1305] {
+            let envelope = provider.encrypt(algorithm, &key, b"secret", b"context").unwrap();
+            assert_eq!(envelope.version, ENVELOPE_VERSION);
+            assert_eq!(provider.decrypt(&envelope, &key, b"context").unwrap(), b"secret");
+            assert!(provider.decrypt(&envelope, &[8u8; 32], b"context").is_err());
+            assert!(provider.decrypt(&envelope, &key, b"other").is_err());
+
+            let json = serde_json::to_string(&envelope).unwrap();
+            let decoded: EncryptedEnvelope = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, envelope);
+        }
+
+        // Wrapped for two recipients; a third cannot open it
+        let (alice, bob, eve) = (KeyAgreementKey::generate(), KeyAgreementKey::generate(), KeyAgreementKey::generate());
+        let sealed = provider
+            .seal(SymmetricAlgorithm::Aes256Gcm, &[alice.public_key(), bob.public_key()], b"shared", b"")
+            .unwrap();
+        assert_eq!(sealed.recipients.len(), 2);
+        assert_eq!(provider.open(&sealed, &alice.to_bytes(), b"").unwrap(), b"shared");
+        assert_eq!(provider.open(&sealed, &bob.to_bytes(), b"").unwrap(), b"shared");
+        assert!(provider.open(&sealed, &eve.to_bytes(), b"").is_err());
+        assert!(provider.seal(SymmetricAlgorithm::Aes256Gcm, &[], b"", b"").is_err());
+
+        let mut future = sealed.clone();
+        future.version = ENVELOPE_VERSION + 1;
+        assert!(provider.open(&future, &alice.to_bytes(), b"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_session_key_enforces_policy() {
+        use etherlink::{LocalSigner, SessionKey, SessionMethod, SessionPolicy};
+        use etherlink::clients::ghostd::Transaction;
+        use std::time::Duration;
+
+        let owner = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let session = SessionKey::derive(&owner, SessionPolicy::new(1_000, Duration::from_secs(3600))).unwrap();
+        session.delegation.verify().unwrap();
+        let signer = LocalSigner::from_session(session);
+        assert_eq!(signer.address().unwrap(), owner.address().unwrap());
+
+        let transfer = |amount| Transaction {
+            from: owner.address().unwrap(),
+            to: Address::new("ghost1bot".to_string()),
+            amount,
+            gas_limit: 10,
+            gas_price: 10,
+            nonce: 0,
+            data: None,
+            signature: None,
+            delegation: None,
+            token_type: TokenType::GCC,
+        };
+
+        // 600 + 100 gas fits the budget and carries the delegation proof
+        let mut tx = transfer(600);
+        signer.sign_transaction(&mut tx).unwrap();
+        assert_eq!(signer.spent(), 700);
+        assert_eq!(signer.remaining(), Some(300));
+        let delegation = tx.delegation.clone().unwrap();
+        assert_eq!(delegation.authorizes(&tx, 0, 0).unwrap(), 700);
+        assert!(matches!(delegation.authorizes(&tx, 400, 0), Err(etherlink::EtherlinkError::SessionPolicy(_))));
+
+        // The proof survives JSON, and tampering with the policy breaks it
+        let decoded: Transaction = serde_json::from_slice(&serde_json::to_vec(&tx).unwrap()).unwrap();
+        assert_eq!(decoded.delegation, tx.delegation);
+        let wire = etherlink::proto::ghostchain::v1::Transaction::try_from(tx.clone()).unwrap();
+        let decoded = Transaction::try_from(wire).unwrap();
+        assert_eq!(decoded.delegation, tx.delegation);
+        assert_eq!(decoded.delegation.unwrap().authorizes(&decoded, 0, 0).unwrap(), 700);
+        let mut forged = tx.clone();
+        forged.delegation.as_mut().unwrap().policy.max_spend = u64::MAX;
+        assert!(forged.delegation.as_ref().unwrap().verify().is_err());
+
+        // Over budget, disallowed method, and a foreign account are refused without spending
+        let mut over = transfer(250);
+        assert!(matches!(signer.sign_transaction(&mut over), Err(etherlink::EtherlinkError::SessionPolicy(_))));
+        assert!(over.signature.is_none());
+        let mut call = Transaction { data: Some(vec![1]), ..transfer(1) };
+        assert!(signer.sign_transaction(&mut call).is_err());
+        let mut foreign = Transaction { from: Address::new("ghost1other".to_string()), ..transfer(1) };
+        assert!(signer.sign_transaction(&mut foreign).is_err());
+        assert_eq!(signer.spent(), 700);
+
+        let mut policy = SessionPolicy::new(1_000, Duration::ZERO).allow(SessionMethod::ContractCall);
+        assert!(policy.is_expired());
+        policy.expires_at = u64::MAX;
+        let contract_signer = LocalSigner::from_session(SessionKey::derive(&owner, policy).unwrap());
+        contract_signer.sign_transaction(&mut call).unwrap();
+
+        let expired = LocalSigner::from_session(SessionKey::derive(&owner, SessionPolicy::new(1_000, Duration::ZERO)).unwrap());
+        assert!(expired.sign_transaction(&mut transfer(1)).is_err());
+        assert!(etherlink::primitives::Signer::sign(&expired, b"raw").is_err());
+        // A live session key cannot sign arbitrary bytes around its policy either
+        assert!(etherlink::primitives::Signer::sign(&signer, b"raw").is_err());
+        assert!(etherlink::primitives::Signer::sign(&LocalSigner::new(owner.clone()), b"raw").is_ok());
+    }
+
+    #[test]
+    fn test_keystore_and_session_store_round_trip() {
+        use etherlink::{EncryptedFileBackend, Keystore, SecretBackend, SessionKey, SessionPolicy, SessionStore};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dir = std::env::temp_dir().join(format!("etherlink-keystore-{}", uuid::Uuid::new_v4()));
+        let backend = Arc::new(EncryptedFileBackend::new(&dir, [7u8; 32]));
+        let keystore = Keystore::new(backend.clone());
+        let owner = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+
+        assert!(keystore.load("owner").unwrap().is_none());
+        keystore.save("owner", &owner).unwrap();
+        assert_eq!(keystore.load("owner").unwrap().unwrap().private_key, owner.private_key);
+        // Only ciphertext reaches the disk, and another key cannot read it
+        let raw = std::fs::read_to_string(dir.join("key_owner.json")).unwrap();
+        assert!(!raw.contains(owner.private_key.expose_secret().as_str()));
+        assert!(EncryptedFileBackend::new(&dir, [8u8; 32]).get("key:owner").is_err());
+        assert!(keystore.save("../escape", &owner).is_err());
+
+        let sessions = SessionStore::new(backend.clone());
+        let session = SessionKey::derive(&owner, SessionPolicy::new(1_000, Duration::from_secs(3600))).unwrap();
+        sessions.save("daily", &session).unwrap();
+        assert_eq!(sessions.load("daily").unwrap().unwrap().delegation, session.delegation);
+        // Expired sessions are discarded on load
+        sessions.save("stale", &SessionKey::derive(&owner, SessionPolicy::new(1_000, Duration::ZERO)).unwrap()).unwrap();
+        assert!(sessions.load("stale").unwrap().is_none());
+        assert!(backend.get("session:stale").unwrap().is_none());
+
+        assert!(keystore.delete("owner").unwrap());
+        assert!(!keystore.delete("owner").unwrap());
+        assert!(keystore.load("owner").unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_secrets_are_redacted_but_serialize_transparently() {
+        use etherlink::{AuthSecret, SecretString};
+
+        let key = CryptoProvider::new().generate_keypair(&CryptoAlgorithm::Ed25519).unwrap();
+        let debug = format!("{:?}", key);
+        assert!(!debug.contains(key.private_key.expose_secret().as_str()));
+        assert!(debug.contains(etherlink::diagnostics::REDACTED));
+        assert_eq!(key.private_key.to_string(), etherlink::diagnostics::REDACTED);
+
+        // Services still receive the value
+        let json = serde_json::to_value(&key).unwrap();
+        assert_eq!(json["private_key"], key.private_key.expose_secret().as_str());
+        let restored: etherlink::KeyPair = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.private_key, key.private_key);
+
+        let mnemonic = AuthSecret::Mnemonic(SecretString::from("abandon abandon about"));
+        assert!(!format!("{:?}", mnemonic).contains("abandon"));
+        assert_eq!(serde_json::to_value(&mnemonic).unwrap()["Mnemonic"], "abandon abandon about");
+    }
+
+    #[test]
+    fn test_constant_time_equality_and_sealing_key() {
+        use etherlink::auth::crypto::{MessagePrefix, SymmetricAlgorithm};
+        use etherlink::secret::constant_time_eq;
+        use etherlink::{SealingKey, SecretString};
+
+        assert!(constant_time_eq(b"signature", b"signature"));
+        assert!(!constant_time_eq(b"signature", b"signaturf"));
+        assert!(!constant_time_eq(b"sig", b"signature"));
+        assert_eq!(SecretString::from("hunter2"), SecretString::from("hunter2"));
+        assert_ne!(SecretString::from("hunter2"), SecretString::from("hunter3"));
+
+        let key = SealingKey::generate(SymmetricAlgorithm::ChaCha20Poly1305);
+        let first = key.encrypt(b"payload", b"aad").unwrap();
+        let second = key.encrypt(b"payload", b"aad").unwrap();
+        // Every envelope gets its own nonce
+        assert_ne!(first.nonce, second.nonce);
+        assert_eq!(key.decrypt(&first, b"aad").unwrap(), b"payload");
+        assert!(key.decrypt(&first, b"other").is_err());
+        assert_eq!(key.messages_sealed(), 2);
+        assert!(format!("{:?}", key).contains(etherlink::diagnostics::REDACTED));
+
+        // Prefixed signatures still verify through the constant-time address check
+        let provider = CryptoProvider::new();
+        let signer = provider.generate_keypair(&CryptoAlgorithm::Secp256k1).unwrap();
+        let other = provider.generate_keypair(&CryptoAlgorithm::Secp256k1).unwrap();
+        let signature = provider
+            .sign_message_prefixed(b"gm", signer.private_key.expose_secret(), &CryptoAlgorithm::Secp256k1, MessagePrefix::Ethereum)
+            .unwrap();
+        assert!(provider.verify_message_prefixed(b"gm", &signature, &signer.public_key, &CryptoAlgorithm::Secp256k1, MessagePrefix::Ethereum).unwrap());
+        assert!(!provider.verify_message_prefixed(b"gm", &signature, &other.public_key, &CryptoAlgorithm::Secp256k1, MessagePrefix::Ethereum).unwrap());
+    }
+
+    #[test]
+    fn test_crypto_backend_selection_and_parity() {
+        use etherlink::CryptoBackend;
+
+        assert_eq!("GCRYPT".parse::<CryptoBackend>().unwrap(), CryptoBackend::Gcrypt);
+        assert!("openssl".parse::<CryptoBackend>().is_err());
+        assert_eq!(CryptoProvider::new().backend(), CryptoBackend::preferred());
+        assert_eq!(CryptoProvider::with_backend(CryptoBackend::Gcrypt).is_ok(), cfg!(feature = "gcrypt"));
+        assert_eq!(CryptoProvider::with_backend(CryptoBackend::Software).is_ok(), !cfg!(feature = "strict-crypto"));
+
+        // Whatever both backends in this build are, each verifies what the other signs
+        let providers: Vec<CryptoProvider> = [CryptoBackend::Software, CryptoBackend::Gcrypt]
+            .into_iter()
+            .filter_map(|backend| CryptoProvider::with_backend(backend).ok())
+            .collect();
+        for algorithm in [CryptoAlgorithm::Ed25519, CryptoAlgorithm::Secp256k1] {
+            for signer in &providers {
+                let key = signer.generate_keypair(&algorithm).unwrap();
+                let signature = signer.sign_message(b"parity", key.private_key.expose_secret(), &algorithm).unwrap();
+                for verifier in &providers {
+                    assert!(verifier.verify_signature(b"parity", &signature, &key.public_key, &algorithm).unwrap());
+                    assert!(!verifier.verify_signature(b"parity?", &signature, &key.public_key, &algorithm).unwrap());
+                    // Public keys derive identically, and Ed25519 signatures are deterministic
+                    let resigned = verifier.sign_message(b"parity", key.private_key.expose_secret(), &algorithm).unwrap();
+                    assert!(signer.verify_signature(b"parity", &resigned, &key.public_key, &algorithm).unwrap());
+                    if algorithm == CryptoAlgorithm::Ed25519 {
+                        assert_eq!(resigned, signature);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cached_signer_matches_provider() {
+        use etherlink::auth::crypto::{recover_address, sign_recoverable};
+        use etherlink::{CachedSigner, Signer};
+
+        let provider = CryptoProvider::new();
+        for algorithm in [CryptoAlgorithm::Ed25519, CryptoAlgorithm::Secp256k1] {
+            let key = provider.generate_keypair(&algorithm).unwrap();
+            let cached = CachedSigner::new(&key).unwrap();
+            assert_eq!(cached.address().unwrap(), &key.address().unwrap());
+            assert_eq!(Signer::public_key(&cached), hex::decode(&key.public_key).unwrap());
+
+            for message in [&b"first"[..], b"second", b""] {
+                let signature = cached.sign_hex(message).unwrap();
+                assert!(provider.verify_signature(message, &signature, &key.public_key, &algorithm).unwrap());
+                // Ed25519 and RFC 6979 ECDSA are deterministic, so the bytes match exactly
+                assert_eq!(signature, provider.sign_message(message, key.private_key.expose_secret(), &algorithm).unwrap());
+            }
+            assert!(!format!("{:?}", cached).contains(key.private_key.expose_secret().as_str()));
+        }
+
+        let key = provider.generate_keypair(&CryptoAlgorithm::Secp256k1).unwrap();
+        let cached = CachedSigner::new(&key).unwrap();
+        let digest = [9u8; 32];
+        let signature = cached.sign_recoverable(&digest).unwrap();
+        assert_eq!(signature, sign_recoverable(&digest, key.private_key.expose_secret()).unwrap());
+        assert_eq!(&recover_address(&digest, &signature).unwrap(), cached.address());
+        let ed25519 = CachedSigner::new(&provider.generate_keypair(&CryptoAlgorithm::Ed25519).unwrap()).unwrap();
+        assert!(ed25519.sign_recoverable(&digest).is_err());
+    }
+}
+
+/// Known-answer tests shared with the Zig implementation, see `tests/vectors/README.md`
+#[cfg(test)]
+mod kat_tests {
+    use super::*;
+    use etherlink::auth::crypto::{CryptoAlgorithm, CryptoBackend, CryptoProvider, KeyPair};
+    use serde_json::Value;
+
+    fn suite(name: &str) -> Value {
+        let path = format!("{}/tests/vectors/{}", env!("CARGO_MANIFEST_DIR"), name);
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+    }
+
+    fn vectors<'a>(suite: &'a Value, section: &str) -> &'a Vec<Value> {
+        suite[section].as_array().unwrap()
+    }
+
+    fn text<'a>(vector: &'a Value, field: &str) -> &'a str {
+        vector[field].as_str().unwrap()
+    }
+
+    fn bytes(vector: &Value, field: &str) -> Vec<u8> {
+        hex::decode(text(vector, field)).unwrap()
+    }
+
+    /// A provider for every backend in this build, so each is held to the same vectors
+    fn providers() -> Vec<CryptoProvider> {
+        [CryptoBackend::Software, CryptoBackend::Gcrypt]
+            .into_iter()
+            .filter_map(|backend| CryptoProvider::with_backend(backend).ok())
+            .collect()
+    }
+
+    #[test]
+    fn test_kat_hash() {
+        use etherlink::hash::{HashAlgorithm, HashDomain, Hasher};
+
+        for vector in vectors(&suite("hash.json"), "vectors") {
+            let algorithm: HashAlgorithm = serde_json::from_value(vector["algorithm"].clone()).unwrap();
+            let domain: Option<HashDomain> = serde_json::from_value(vector["domain"].clone()).unwrap();
+            let input = bytes(vector, "input");
+            let digest = match domain {
+                Some(domain) => Hasher::domain_digest(algorithm, domain, &input),
+                None => Hasher::digest(algorithm, &input),
+            };
+            assert_eq!(hex::encode(digest), text(vector, "digest"), "{}", vector);
+        }
+    }
+
+    #[test]
+    fn test_kat_ed25519() {
+        let algorithm = CryptoAlgorithm::Ed25519;
+        for provider in providers() {
+            for vector in vectors(&suite("ed25519.json"), "vectors") {
+                let message = bytes(vector, "message");
+                let signature = provider.sign_message(&message, text(vector, "private_key"), &algorithm).unwrap();
+                assert_eq!(signature, text(vector, "signature"), "{:?}: {}", provider.backend(), vector);
+                assert!(provider.verify_signature(&message, &signature, text(vector, "public_key"), &algorithm).unwrap());
+            }
+        }
+
+        #[cfg(feature = "fallback-crypto")]
+        for vector in vectors(&suite("ed25519.json"), "vectors") {
+            let key = KeyPair {
+                private_key: text(vector, "private_key").into(),
+                public_key: String::new(),
+                algorithm: algorithm.clone(),
+            };
+            assert_eq!(etherlink::CachedSigner::new(&key).unwrap().public_key_hex(), text(vector, "public_key"));
+        }
+    }
+
+    #[test]
+    fn test_kat_secp256k1() {
+        use etherlink::auth::crypto::{MessagePrefix, recover_address, sign_recoverable};
+
+        let algorithm = CryptoAlgorithm::Secp256k1;
+        let suite = suite("secp256k1.json");
+        for provider in providers() {
+            for vector in vectors(&suite, "sign") {
+                let message = bytes(vector, "message");
+                let signature = provider.sign_message(&message, text(vector, "private_key"), &algorithm).unwrap();
+                assert_eq!(signature, text(vector, "signature"), "{:?}: {}", provider.backend(), vector);
+                assert!(provider.verify_signature(&message, &signature, text(vector, "public_key"), &algorithm).unwrap());
+            }
+        }
+
+        for vector in vectors(&suite, "recoverable") {
+            let digest: [u8; 32] = bytes(vector, "digest").try_into().unwrap();
+            let signature = sign_recoverable(&digest, text(vector, "private_key")).unwrap();
+            assert_eq!(hex::encode(signature), text(vector, "signature"), "{}", vector);
+            assert_eq!(recover_address(&digest, &signature).unwrap().as_str(), text(vector, "address"));
+        }
+
+        let provider = CryptoProvider::new();
+        for vector in vectors(&suite, "prefixed") {
+            let prefix = match text(vector, "prefix") {
+                "ethereum" => MessagePrefix::Ethereum,
+                "ghost" => MessagePrefix::Ghost,
+                other => panic!("unknown prefix {}", other),
+            };
+            let message = bytes(vector, "message");
+            let signature = provider.sign_message_prefixed(&message, text(vector, "private_key"), &algorithm, prefix).unwrap();
+            assert_eq!(signature, text(vector, "signature"), "{}", vector);
+            assert_eq!(
+                etherlink::auth::crypto::recover_message_signer(&message, &signature, prefix).unwrap().as_str(),
+                text(vector, "address")
+            );
+        }
+
+        #[cfg(feature = "fallback-crypto")]
+        for vector in vectors(&suite, "sign") {
+            let key = KeyPair {
+                private_key: text(vector, "private_key").into(),
+                public_key: String::new(),
+                algorithm: algorithm.clone(),
+            };
+            assert_eq!(etherlink::CachedSigner::new(&key).unwrap().public_key_hex(), text(vector, "public_key"));
+        }
+    }
+
+    #[test]
+    fn test_kat_addresses() {
+        use etherlink::address::{
+            create2_address, create_address, evm_bytes, from_native_bytes, from_uncompressed_public_key, native_address,
+            native_bytes,
+        };
+        use etherlink::auth::crypto::address_from_public_key;
+
+        let suite = suite("address.json");
+        for vector in vectors(&suite, "evm") {
+            let address = from_uncompressed_public_key(&bytes(vector, "public_key")).unwrap();
+            assert_eq!(address.as_str(), text(vector, "address"), "{}", vector);
+            let compressed = address_from_public_key(text(vector, "compressed_public_key"), &CryptoAlgorithm::Secp256k1).unwrap();
+            assert_eq!(compressed, address);
+            assert_eq!(hex::encode(evm_bytes(&address).unwrap()), text(vector, "address")[2..].to_lowercase());
+        }
+
+        for vector in vectors(&suite, "native") {
+            let address = match vector.get("public_key") {
+                Some(public_key) => native_address(public_key.as_str().unwrap()),
+                None => {
+                    let payload: [u8; 20] = bytes(vector, "payload").try_into().unwrap();
+                    assert_eq!(native_bytes(&Address::new(text(vector, "address").to_string())).unwrap(), payload);
+                    from_native_bytes(&payload)
+                }
+            };
+            assert_eq!(address.as_str(), text(vector, "address"), "{}", vector);
+        }
+
+        for vector in vectors(&suite, "create") {
+            let deployer = Address::new(text(vector, "deployer").to_string());
+            let address = create_address(&deployer, vector["nonce"].as_u64().unwrap()).unwrap();
+            assert_eq!(address.as_str(), text(vector, "address"), "{}", vector);
+        }
+
+        for vector in vectors(&suite, "create2") {
+            let deployer = Address::new(text(vector, "deployer").to_string());
+            let salt: [u8; 32] = bytes(vector, "salt").try_into().unwrap();
+            let address = create2_address(&deployer, &salt, &bytes(vector, "init_code")).unwrap();
+            assert_eq!(address.as_str(), text(vector, "address"), "{}", vector);
+        }
+    }
+
+    #[test]
+    fn test_kat_canonical_encoding() {
+        use etherlink::canonical::to_bytes;
+        use etherlink::clients::ghostd::Transaction;
+        use etherlink::hash::{HashAlgorithm, HashDomain, Hasher};
+
+        let suite = suite("canonical.json");
+        for vector in vectors(&suite, "values") {
+            assert_eq!(hex::encode(to_bytes(&vector["value"]).unwrap()), text(vector, "encoding"), "{}", vector);
+        }
+
+        for vector in vectors(&suite, "transactions") {
+            let tx: Transaction = serde_json::from_value(vector["transaction"].clone()).unwrap();
+            let payload = tx.signing_payload();
+            assert_eq!(hex::encode(&payload), text(vector, "signing_payload"), "{}", vector);
+            assert_eq!(
+                hex::encode(Hasher::domain_digest(HashAlgorithm::Sha256, HashDomain::Tx, &payload)),
+                text(vector, "tx_hash")
+            );
+        }
+    }
+
+    #[test]
+    fn test_kat_merkle() {
+        use etherlink::merkle::{MerkleProof, merkle_root};
+
+        for vector in vectors(&suite("merkle.json"), "vectors") {
+            let leaves: Vec<TxHash> = serde_json::from_value(vector["leaves"].clone()).unwrap();
+            let root = text(vector, "root");
+            assert_eq!(merkle_root(&leaves), root, "{}", vector);
+
+            for (index, expected) in vector.get("proofs").and_then(Value::as_array).into_iter().flatten().enumerate() {
+                let proof = MerkleProof::build(&leaves, index).unwrap();
+                assert_eq!(proof, serde_json::from_value::<MerkleProof>(expected.clone()).unwrap());
+                assert!(proof.verify(&leaves[index], root));
+            }
+        }
+    }
+}